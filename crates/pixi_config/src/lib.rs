@@ -4,6 +4,7 @@ use std::{
     process::{Command, Stdio},
     str::FromStr,
     sync::LazyLock,
+    time::Duration,
 };
 
 use clap::{ArgAction, Parser};
@@ -17,7 +18,7 @@ use rattler_conda_types::{
     version_spec::{EqualityOperator, LogicalOperator, RangeOperator},
 };
 use rattler_networking::s3_middleware;
-use rattler_repodata_gateway::{Gateway, GatewayBuilder, SourceConfig};
+use rattler_repodata_gateway::{Gateway, GatewayBuilder, SourceConfig, fetch::CacheAction};
 use reqwest::{NoProxy, Proxy};
 use serde::{Deserialize, Serialize, de::Error, de::IntoDeserializer};
 use url::Url;
@@ -138,6 +139,37 @@ pub struct ConfigCli {
     pub concurrent_downloads: Option<usize>,
 }
 
+/// The network options of the commands that update the lock-file or install
+/// the environments of a workspace.
+#[derive(Parser, Debug, Clone, Default)]
+pub struct ConfigCliNetwork {
+    /// Only use the cached repodata and packages, never access the network.
+    #[arg(long, help_heading = consts::CLAP_CONFIG_OPTIONS)]
+    pub offline: bool,
+}
+
+impl From<ConfigCliNetwork> for Config {
+    fn from(cli: ConfigCliNetwork) -> Self {
+        Self {
+            network: NetworkConfig {
+                offline: cli.offline.then_some(true),
+                ..NetworkConfig::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+impl ConfigCliNetwork {
+    pub fn merge_config(self, config: Config) -> Config {
+        let mut config = config;
+        if self.offline {
+            config.network.offline = Some(true);
+        }
+        config
+    }
+}
+
 #[derive(Parser, Debug, Clone, Default)]
 pub struct ConfigCliPrompt {
     /// Do not change the PS1 variable when starting a prompt.
@@ -205,8 +237,8 @@ impl RepodataConfig {
 
 #[derive(Parser, Debug, Default, Clone)]
 pub struct ConfigCliActivation {
-    /// Do not use the environment activation cache. (default: true except in
-    /// experimental mode)
+    /// Do not use the environment activation cache, which `pixi run` uses
+    /// unless `run.use-activation-cache` is false
     #[arg(long, help_heading = consts::CLAP_CONFIG_OPTIONS)]
     force_activate: bool,
 
@@ -390,6 +422,12 @@ fn default_max_concurrent_downloads() -> usize {
     50
 }
 
+/// The default maximum number of environments that are installed at once.
+/// Installing is mostly bound by disk IO, so a few at a time is enough.
+fn default_max_concurrent_installs() -> usize {
+    4
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct ConcurrencyConfig {
@@ -404,6 +442,12 @@ pub struct ConcurrencyConfig {
     // to 0 of partial struct was omitted.
     #[serde(default = "default_max_concurrent_downloads")]
     pub downloads: usize,
+
+    /// The maximum number of environments to install at once.
+    // Needing to set this default next to the default of the full struct to avoid serde defaulting
+    // to 0 of partial struct was omitted.
+    #[serde(default = "default_max_concurrent_installs")]
+    pub installs: usize,
 }
 
 impl Default for ConcurrencyConfig {
@@ -411,6 +455,7 @@ impl Default for ConcurrencyConfig {
         Self {
             solves: default_max_concurrent_solves(),
             downloads: default_max_concurrent_downloads(),
+            installs: default_max_concurrent_installs(),
         }
     }
 }
@@ -430,6 +475,11 @@ impl ConcurrencyConfig {
             } else {
                 self.downloads
             },
+            installs: if other.installs != ConcurrencyConfig::default().installs {
+                other.installs
+            } else {
+                self.installs
+            },
         }
     }
 
@@ -480,7 +530,7 @@ impl PyPIConfig {
 }
 
 /// The strategy for that will be used for pinning a version of a package.
-#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Copy, clap::ValueEnum)]
 #[serde(rename_all = "kebab-case")]
 pub enum PinningStrategy {
     /// Default semver strategy e.g. "1.2.3" becomes ">=1.2.3, <2" but "0.1.0"
@@ -683,6 +733,12 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub run_post_link_scripts: Option<RunPostLinkScripts>,
 
+    /// Verify the content of every installed file when an environment is
+    /// updated, and relink the packages with corrupted files
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_environments: Option<bool>,
+
     /// Https/Http proxy configuration for pixi
     #[serde(default)]
     #[serde(skip_serializing_if = "ProxyConfig::is_default")]
@@ -693,6 +749,21 @@ pub struct Config {
     #[serde(skip_serializing_if = "BuildConfig::is_default")]
     pub build: BuildConfig,
 
+    /// Network configuration for pixi
+    #[serde(default)]
+    #[serde(skip_serializing_if = "NetworkConfig::is_default")]
+    pub network: NetworkConfig,
+
+    /// Configuration of how the lock-file is written
+    #[serde(default)]
+    #[serde(skip_serializing_if = "LockFileConfig::is_default")]
+    pub lock_file: LockFileConfig,
+
+    /// Configuration of `pixi run`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "RunConfig::is_default")]
+    pub run: RunConfig,
+
     //////////////////////
     // Deprecated fields //
     //////////////////////
@@ -724,8 +795,12 @@ impl Default for Config {
             experimental: ExperimentalConfig::default(),
             concurrency: ConcurrencyConfig::default(),
             run_post_link_scripts: None,
+            verify_environments: None,
             proxy_config: ProxyConfig::default(),
             build: BuildConfig::default(),
+            network: NetworkConfig::default(),
+            lock_file: LockFileConfig::default(),
+            run: RunConfig::default(),
 
             // Deprecated fields
             change_ps1: None,
@@ -751,6 +826,7 @@ impl From<ConfigCli> for Config {
                 downloads: cli
                     .concurrent_downloads
                     .unwrap_or(ConcurrencyConfig::default().downloads),
+                ..ConcurrencyConfig::default()
             },
             ..Default::default()
         }
@@ -766,7 +842,16 @@ impl From<Config> for rattler_repodata_gateway::ChannelConfig {
 impl From<&Config> for rattler_repodata_gateway::ChannelConfig {
     fn from(config: &Config) -> Self {
         let repodata_config = &config.repodata_config;
-        let default = repodata_config.default.clone().into();
+        // When offline, the cached repodata is used even if it is expired
+        let cache_action = if config.network.offline() {
+            CacheAction::ForceCacheOnly
+        } else {
+            CacheAction::default()
+        };
+        let default = SourceConfig {
+            cache_action,
+            ..repodata_config.default.clone().into()
+        };
 
         let per_channel = repodata_config
             .per_channel
@@ -774,7 +859,10 @@ impl From<&Config> for rattler_repodata_gateway::ChannelConfig {
             .map(|(url, config)| {
                 (
                     url.clone(),
-                    config.merge(repodata_config.default.clone()).into(),
+                    SourceConfig {
+                        cache_action,
+                        ..config.merge(repodata_config.default.clone()).into()
+                    },
                 )
             })
             .collect();
@@ -862,6 +950,156 @@ impl ProxyConfig {
     }
 }
 
+/// The default number of times a request that failed with a transient error
+/// is retried.
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// The default delay before the first retry of a failed request.
+pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 1000;
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct NetworkConfig {
+    /// The maximum number of times a request that failed with a transient
+    /// error (connection reset, timeout or a 5xx response) is retried.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+
+    /// The delay in milliseconds before the first retry, every next retry
+    /// waits exponentially longer.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_backoff_ms: Option<u64>,
+
+    /// Only use the repodata and packages that are in the cache, the network
+    /// is never accessed.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offline: Option<bool>,
+}
+
+impl NetworkConfig {
+    pub fn is_default(&self) -> bool {
+        self.max_retries.is_none() && self.retry_backoff_ms.is_none() && self.offline.is_none()
+    }
+
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            max_retries: other.max_retries.or(self.max_retries),
+            retry_backoff_ms: other.retry_backoff_ms.or(self.retry_backoff_ms),
+            offline: other.offline.or(self.offline),
+        }
+    }
+
+    /// Retrieve whether pixi only uses its caches (defaults to false).
+    pub fn offline(&self) -> bool {
+        self.offline.unwrap_or(false)
+    }
+
+    /// Retrieve the maximum number of retries (defaults to 3).
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or(DEFAULT_MAX_RETRIES)
+    }
+
+    /// Retrieve the delay before the first retry (defaults to one second).
+    pub fn retry_backoff(&self) -> Duration {
+        Duration::from_millis(self.retry_backoff_ms.unwrap_or(DEFAULT_RETRY_BACKOFF_MS))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct LockFileConfig {
+    /// Write the lock-file in a canonical form: the environments, platforms
+    /// and packages are sorted, so that locking the same manifest always
+    /// results in the same file.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical: Option<bool>,
+}
+
+impl LockFileConfig {
+    pub fn is_default(&self) -> bool {
+        self.canonical.is_none()
+    }
+
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            canonical: other.canonical.or(self.canonical),
+        }
+    }
+
+    /// Retrieve whether the lock-file is written in canonical form (defaults
+    /// to false).
+    pub fn canonical(&self) -> bool {
+        self.canonical.unwrap_or(false)
+    }
+}
+
+/// What `pixi run` does when a task is defined in multiple environments and
+/// no environment was specified.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum AmbiguousTaskChoice {
+    /// Ask which environment to use, or fall back to `default` if the
+    /// terminal is not interactive.
+    #[default]
+    Prompt,
+    /// Use the default environment if it defines the task, otherwise the
+    /// first environment that defines it.
+    Default,
+    /// Fail and ask to specify the environment.
+    Error,
+}
+
+impl FromStr for AmbiguousTaskChoice {
+    type Err = serde::de::value::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct RunConfig {
+    /// What to do when a task is defined in multiple environments and no
+    /// environment was specified.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ambiguous: Option<AmbiguousTaskChoice>,
+
+    /// Whether `pixi run` reuses the activation of an environment that it
+    /// cached in an earlier run.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_activation_cache: Option<bool>,
+}
+
+impl RunConfig {
+    pub fn is_default(&self) -> bool {
+        self.ambiguous.is_none() && self.use_activation_cache.is_none()
+    }
+
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            ambiguous: other.ambiguous.or(self.ambiguous),
+            use_activation_cache: other.use_activation_cache.or(self.use_activation_cache),
+        }
+    }
+
+    /// Retrieve whether `pixi run` uses the activation cache (defaults to
+    /// true).
+    pub fn use_activation_cache(&self) -> bool {
+        self.use_activation_cache.unwrap_or(true)
+    }
+
+    /// Retrieve what to do with an ambiguous task (defaults to prompting).
+    pub fn ambiguous(&self) -> AmbiguousTaskChoice {
+        self.ambiguous.unwrap_or_default()
+    }
+}
+
 /// Container for the package format and compression level
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct PackageFormatAndCompression {
@@ -1269,6 +1507,16 @@ impl Config {
             "proxy-config.https",
             "proxy-config.http",
             "proxy-config.non-proxy-hosts",
+            "network",
+            "network.max-retries",
+            "network.retry-backoff-ms",
+            "network.offline",
+            "lock-file",
+            "lock-file.canonical",
+            "run",
+            "run.ambiguous",
+            "run.use-activation-cache",
+            "verify-environments",
         ]
     }
 
@@ -1309,9 +1557,13 @@ impl Config {
             // Make other take precedence over self to allow for setting the value through the CLI
             concurrency: self.concurrency.merge(other.concurrency),
             run_post_link_scripts: other.run_post_link_scripts.or(self.run_post_link_scripts),
+            verify_environments: other.verify_environments.or(self.verify_environments),
 
             proxy_config: self.proxy_config.merge(other.proxy_config),
             build: self.build.merge(other.build),
+            network: self.network.merge(other.network),
+            lock_file: self.lock_file.merge(other.lock_file),
+            run: self.run.merge(other.run),
 
             // Deprecated fields that we can ignore as we handle them inside `shell.` field
             change_ps1: None,
@@ -1388,6 +1640,27 @@ impl Config {
         self.concurrency.downloads
     }
 
+    /// Retrieve the maximum number of environments to install at once.
+    pub fn max_concurrent_installs(&self) -> usize {
+        self.concurrency.installs
+    }
+
+    /// Retrieve the network configuration, e.g. how failed requests are
+    /// retried.
+    pub fn network(&self) -> &NetworkConfig {
+        &self.network
+    }
+
+    /// Retrieve the configuration of how the lock-file is written.
+    pub fn lock_file(&self) -> &LockFileConfig {
+        &self.lock_file
+    }
+
+    /// Retrieve the configuration of `pixi run`.
+    pub fn run(&self) -> &RunConfig {
+        &self.run
+    }
+
     pub fn get_proxies(&self) -> reqwest::Result<Vec<Proxy>> {
         if (self.proxy_config.https.is_none() && self.proxy_config.http.is_none())
             || *USE_PROXY_FROM_ENV
@@ -1453,6 +1726,10 @@ impl Config {
             "tls-no-verify" => {
                 self.tls_no_verify = value.map(|v| v.parse()).transpose().into_diagnostic()?;
             }
+            "verify-environments" => {
+                self.verify_environments =
+                    value.map(|v| v.parse()).transpose().into_diagnostic()?;
+            }
             "mirrors" => {
                 self.mirrors = value
                     .map(|v| serde_json::de::from_str(&v))
@@ -1662,6 +1939,13 @@ impl Config {
                             return Err(miette!("'downloads' requires a number value"));
                         }
                     }
+                    "installs" => {
+                        if let Some(value) = value {
+                            self.concurrency.installs = value.parse().into_diagnostic()?;
+                        } else {
+                            return Err(miette!("'installs' requires a number value"));
+                        }
+                    }
                     _ => return Err(err),
                 }
             }
@@ -1704,6 +1988,81 @@ impl Config {
                 }
                 return Ok(());
             }
+            key if key.starts_with("lock-file") => {
+                if key == "lock-file" {
+                    if let Some(value) = value {
+                        self.lock_file = serde_json::de::from_str(&value).into_diagnostic()?;
+                    } else {
+                        self.lock_file = LockFileConfig::default();
+                    }
+                    return Ok(());
+                } else if !key.starts_with("lock-file.") {
+                    return Err(err);
+                }
+
+                let subkey = key.strip_prefix("lock-file.").unwrap();
+                match subkey {
+                    "canonical" => {
+                        self.lock_file.canonical =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    _ => return Err(err),
+                }
+            }
+            key if key.starts_with("run") => {
+                if key == "run" {
+                    if let Some(value) = value {
+                        self.run = serde_json::de::from_str(&value).into_diagnostic()?;
+                    } else {
+                        self.run = RunConfig::default();
+                    }
+                    return Ok(());
+                } else if !key.starts_with("run.") {
+                    return Err(err);
+                }
+
+                let subkey = key.strip_prefix("run.").unwrap();
+                match subkey {
+                    "ambiguous" => {
+                        self.run.ambiguous =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    "use-activation-cache" => {
+                        self.run.use_activation_cache =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    _ => return Err(err),
+                }
+            }
+            key if key.starts_with("network") => {
+                if key == "network" {
+                    if let Some(value) = value {
+                        self.network = serde_json::de::from_str(&value).into_diagnostic()?;
+                    } else {
+                        self.network = NetworkConfig::default();
+                    }
+                    return Ok(());
+                } else if !key.starts_with("network.") {
+                    return Err(err);
+                }
+
+                let subkey = key.strip_prefix("network.").unwrap();
+                match subkey {
+                    "max-retries" => {
+                        self.network.max_retries =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    "retry-backoff-ms" => {
+                        self.network.retry_backoff_ms =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    "offline" => {
+                        self.network.offline =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    _ => return Err(err),
+                }
+            }
             key if key.starts_with("proxy-config") => {
                 if key == "proxy-config" {
                     if let Some(value) = value {
@@ -1799,6 +2158,12 @@ impl Config {
     pub fn run_post_link_scripts(&self) -> RunPostLinkScripts {
         self.run_post_link_scripts.clone().unwrap_or_default()
     }
+
+    /// Retrieve the value for the verify_environments field or default to
+    /// false.
+    pub fn verify_environments(&self) -> bool {
+        self.verify_environments.unwrap_or(false)
+    }
 }
 
 /// Returns the path to the system-level pixi config file.
@@ -1919,6 +2284,22 @@ UNUSED = "unused"
         assert!(!config.experimental.use_environment_activation_cache());
     }
 
+    #[test]
+    fn test_config_from_cli_network() {
+        let config = ConfigCliNetwork { offline: true }.merge_config(Config::default());
+        assert!(config.network().offline());
+
+        // Without the flag the configured value is kept
+        let mut offline = Config::default();
+        offline.network.offline = Some(true);
+        let config = ConfigCliNetwork::default().merge_config(offline);
+        assert!(config.network().offline());
+        assert_eq!(
+            Config::from(ConfigCliNetwork::default()).network.offline,
+            None
+        );
+    }
+
     #[test]
     fn test_pypi_config_parse() {
         let toml = r#"
@@ -2057,8 +2438,21 @@ UNUSED = "unused"
                 )]),
             },
             run_post_link_scripts: Some(RunPostLinkScripts::Insecure),
+            verify_environments: Some(true),
             proxy_config: ProxyConfig::default(),
             build: BuildConfig::default(),
+            network: NetworkConfig {
+                max_retries: Some(5),
+                retry_backoff_ms: Some(100),
+                offline: Some(true),
+            },
+            lock_file: LockFileConfig {
+                canonical: Some(false),
+            },
+            run: RunConfig {
+                ambiguous: Some(AmbiguousTaskChoice::Error),
+                use_activation_cache: Some(false),
+            },
             // Deprecated keys
             change_ps1: None,
             force_activate: None,
@@ -2245,6 +2639,11 @@ UNUSED = "unused"
             .unwrap();
         assert_eq!(config.tls_no_verify, Some(true));
 
+        config
+            .set("verify-environments", Some("true".to_string()))
+            .unwrap();
+        assert!(config.verify_environments());
+
         config
             .set(
                 "authentication-override-file",
@@ -2346,6 +2745,62 @@ UNUSED = "unused"
 
         assert_eq!(config.max_concurrent_downloads(), 1);
 
+        config
+            .set("concurrency.installs", Some("2".to_string()))
+            .unwrap();
+        assert_eq!(config.max_concurrent_installs(), 2);
+
+        assert_eq!(config.network().max_retries(), DEFAULT_MAX_RETRIES);
+        config
+            .set("network.max-retries", Some("5".to_string()))
+            .unwrap();
+        assert_eq!(config.network().max_retries(), 5);
+        config
+            .set("network.retry-backoff-ms", Some("250".to_string()))
+            .unwrap();
+        assert_eq!(config.network().retry_backoff(), Duration::from_millis(250));
+        config.set("network.max-retries", None).unwrap();
+        assert_eq!(config.network().max_retries(), DEFAULT_MAX_RETRIES);
+        config
+            .set("network.retries", Some("5".to_string()))
+            .unwrap_err();
+        assert!(!config.network().offline());
+        config
+            .set("network.offline", Some("true".to_string()))
+            .unwrap();
+        assert!(config.network().offline());
+        let channel_config = rattler_repodata_gateway::ChannelConfig::from(&config);
+        assert!(matches!(
+            channel_config.default.cache_action,
+            CacheAction::ForceCacheOnly
+        ));
+        config.set("network.offline", None).unwrap();
+        assert!(!config.network().offline());
+
+        assert!(!config.lock_file().canonical());
+        config
+            .set("lock-file.canonical", Some("true".to_string()))
+            .unwrap();
+        assert!(config.lock_file().canonical());
+        config
+            .set("lock-file.sorted", Some("true".to_string()))
+            .unwrap_err();
+
+        assert_eq!(config.run().ambiguous(), AmbiguousTaskChoice::Prompt);
+        config
+            .set("run.ambiguous", Some("error".to_string()))
+            .unwrap();
+        assert_eq!(config.run().ambiguous(), AmbiguousTaskChoice::Error);
+        config
+            .set("run.ambiguous", Some("random".to_string()))
+            .unwrap_err();
+
+        assert!(config.run().use_activation_cache());
+        config
+            .set("run.use-activation-cache", Some("false".to_string()))
+            .unwrap();
+        assert!(!config.run().use_activation_cache());
+
         config.set("s3-options.my-bucket", Some(r#"{"endpoint-url": "http://localhost:9000", "force-path-style": true, "region": "auto"}"#.to_string())).unwrap();
         let s3_options = config.s3_options.get("my-bucket").unwrap();
         assert!(
@@ -2382,6 +2837,7 @@ UNUSED = "unused"
     fn test_version_constraints() {
         let versions = vec![
             vec!["1.2.3"],
+            vec!["0.4.2"],
             vec!["0.0.0"],
             vec!["1!1"],
             vec!["1!0.0.0"],
@@ -25,7 +25,7 @@ pub const PYPI_DEPENDENCIES: &str = "pypi-dependencies";
 pub const DEPENDENCIES: &str = "dependencies";
 pub const SYSTEM_REQUIREMENTS: &str = "system-requirements";
 pub const TASK_CACHE_DIR: &str = "task-cache-v0";
-pub const ACTIVATION_ENV_CACHE_DIR: &str = "activation-env-v0";
+pub const ACTIVATION_ENV_CACHE_DIR: &str = "activation-cache";
 pub const PIXI_UV_INSTALLER: &str = "uv-pixi";
 pub const CONDA_PACKAGE_CACHE_DIR: &str = rattler_cache::PACKAGE_CACHE_DIR;
 pub const CONDA_REPODATA_CACHE_DIR: &str = rattler_cache::REPODATA_CACHE_DIR;
@@ -93,6 +93,7 @@ pub const CONDA_INSTALLER: &str = "conda";
 pub const ONE_TIME_MESSAGES_DIR: &str = "one-time-messages";
 
 pub const ENVIRONMENT_FILE_NAME: &str = "pixi";
+pub const POST_INSTALL_FILE_NAME: &str = "pixi_post_install";
 
 // Note: no trailing slash!
 pub const RELEASES_URL: &str = "https://github.com/prefix-dev/pixi/releases";
@@ -295,6 +295,45 @@ impl GitDatabase {
         Ok(checkout)
     }
 
+    /// Checks out a revision at `destination` for an editable install. An
+    /// existing checkout is never replaced because it may contain changes of
+    /// the user, a warning is printed when it is not at the revision. The
+    /// checkout is made next to the destination and moved in place once it is
+    /// complete, so that an interrupted checkout is never mistaken for one of
+    /// the user.
+    pub(crate) fn checkout_editable(
+        &self,
+        rev: GitOid,
+        destination: &Path,
+    ) -> Result<(), GitError> {
+        if destination.exists() {
+            if !destination.join(".git").exists() {
+                return Err(GitError::NotACheckout(destination.to_path_buf()));
+            }
+            let head = GitRepository::open(destination)?.rev_parse("HEAD")?;
+            if head != rev {
+                tracing::warn!(
+                    "the checkout at '{}' is at commit {} instead of the locked commit {}, it is used as is",
+                    destination.display(),
+                    head.as_str(),
+                    rev.as_str()
+                );
+            }
+            return Ok(());
+        }
+
+        let (Some(parent), Some(name)) = (destination.parent(), destination.file_name()) else {
+            return Err(GitError::NotACheckout(destination.to_path_buf()));
+        };
+        let partial = parent.join(format!(".{}.partial", name.to_string_lossy()));
+        GitCheckout::clone_into(&partial, self, rev)?;
+        // The marker of a finished checkout is only needed for the checkouts in
+        // the cache.
+        fs_err::remove_file(partial.join(CHECKOUT_READY_LOCK))?;
+        fs_err::rename(&partial, destination)?;
+        Ok(())
+    }
+
     /// Get a short OID for a `revision`, usually 7 chars or more if ambiguous.
     pub(crate) fn to_short_id(&self, revision: GitOid) -> Result<String, GitError> {
         let output = Command::new(GIT.as_ref().map_err(|e| e.clone())?)
@@ -791,3 +830,61 @@ fn is_short_hash_of(rev: &str, oid: GitOid) -> bool {
         None => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs git in `dir` with a fixed identity.
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new(GIT.as_ref().unwrap())
+            .args(["-c", "user.name=pixi", "-c", "user.email=pixi@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    /// Commits a file to the repository and returns the new `HEAD`.
+    fn commit(repo: &GitRepository, file: &str) -> GitOid {
+        fs_err::write(repo.path.join(file), file).unwrap();
+        git(&repo.path, &["add", file]);
+        git(&repo.path, &["commit", "-m", file]);
+        repo.rev_parse("HEAD").unwrap()
+    }
+
+    #[test]
+    fn test_checkout_editable_keeps_existing_checkout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("source");
+        fs_err::create_dir_all(&source).unwrap();
+        let db = GitDatabase {
+            repo: GitRepository::init(&source).unwrap(),
+        };
+        let first = commit(&db.repo, "a.txt");
+
+        let destination = tmp.path().join("src").join("pkg");
+        db.checkout_editable(first, &destination).unwrap();
+        assert!(destination.join("a.txt").is_file());
+        assert!(!destination.join(CHECKOUT_READY_LOCK).exists());
+
+        // Work of the user is kept when the locked commit changes
+        fs_err::write(destination.join("a.txt"), "changed").unwrap();
+        let second = commit(&db.repo, "b.txt");
+        db.checkout_editable(second, &destination).unwrap();
+        assert_eq!(
+            fs_err::read_to_string(destination.join("a.txt")).unwrap(),
+            "changed"
+        );
+        assert!(!destination.join("b.txt").exists());
+
+        // A directory that isn't a checkout is not replaced
+        let other = tmp.path().join("src").join("other");
+        fs_err::create_dir_all(&other).unwrap();
+        assert!(matches!(
+            db.checkout_editable(first, &other),
+            Err(GitError::NotACheckout(_))
+        ));
+    }
+}
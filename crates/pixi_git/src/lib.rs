@@ -1,6 +1,8 @@
 /// Derived from `uv-git` implementation
 /// Source: https://github.com/astral-sh/uv/blob/4b8cc3e29e4c2a6417479135beaa9783b05195d3/crates/uv-git/src/lib.rs
 /// This module expose types and functions to interact with Git repositories.
+use std::path::PathBuf;
+
 use ::url::Url;
 use git::{GitBinaryError, GitReference};
 use sha::{GitSha, OidParseError};
@@ -205,4 +207,7 @@ pub enum GitError {
 
     #[error(transparent)]
     Join(#[from] tokio::task::JoinError),
+
+    #[error("'{}' exists but is not a git checkout, move it to check out the repository there", .0.display())]
+    NotACheckout(PathBuf),
 }
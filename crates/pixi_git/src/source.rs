@@ -31,6 +31,9 @@ pub struct GitSource {
     cache: PathBuf,
     /// The reporter to use for this source.
     reporter: Option<Arc<dyn Reporter>>,
+    /// The path to check out the repository to, defaults to a location in the
+    /// cache.
+    checkout_path: Option<PathBuf>,
 }
 
 impl GitSource {
@@ -45,6 +48,7 @@ impl GitSource {
             client: client.into(),
             cache: cache.into(),
             reporter: None,
+            checkout_path: None,
         }
     }
 
@@ -57,6 +61,17 @@ impl GitSource {
         }
     }
 
+    /// Check out the repository at the given path instead of in the cache, for
+    /// an editable install. An existing checkout at that path is always reused
+    /// and never reset, so that changes of the user are kept.
+    #[must_use]
+    pub fn with_checkout_path(self, checkout_path: impl Into<PathBuf>) -> Self {
+        Self {
+            checkout_path: Some(checkout_path.into()),
+            ..self
+        }
+    }
+
     /// Fetch the underlying Git repository at the given revision.
     #[instrument(skip(self), fields(repository = %self.git.repository, rev = self.git.precise.map(tracing::field::display)))]
     pub fn fetch(self) -> Result<Fetch, GitError> {
@@ -118,18 +133,23 @@ impl GitSource {
         // Check out `actual_rev` from the database to a scoped location on the
         // filesystem. This will use hard links and such to ideally make the
         // checkout operation here pretty fast.
-        let checkout_path = self
-            .cache
-            .join("checkouts")
-            .join(&ident)
-            .join(short_id.as_str());
+        let checkout_path = self.checkout_path.clone().unwrap_or_else(|| {
+            self.cache
+                .join("checkouts")
+                .join(&ident)
+                .join(short_id.as_str())
+        });
 
         tracing::debug!(
             "Copying git revision `{}` to path `{}`",
             actual_rev,
             checkout_path.display()
         );
-        db.copy_to(actual_rev.into(), &checkout_path)?;
+        if self.checkout_path.is_some() {
+            db.checkout_editable(actual_rev.into(), &checkout_path)?;
+        } else {
+            db.copy_to(actual_rev.into(), &checkout_path)?;
+        }
 
         // Report the checkout operation to the reporter.
         if let Some(task) = task {
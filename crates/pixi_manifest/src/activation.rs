@@ -3,11 +3,16 @@ use toml_span::{DeserError, Value, de_helpers::TableHelper};
 
 use pixi_toml::TomlIndexMap;
 
+use crate::EnvFile;
+
 #[derive(Default, Clone, Debug)]
 pub struct Activation {
     pub scripts: Option<Vec<String>>,
     /// Environment variables to set before running the scripts.
     pub env: Option<IndexMap<String, String>>,
+    /// `.env` files to load environment variables from, later files override
+    /// earlier ones.
+    pub env_files: Option<Vec<EnvFile>>,
 }
 
 impl<'de> toml_span::Deserialize<'de> for Activation {
@@ -15,10 +20,12 @@ impl<'de> toml_span::Deserialize<'de> for Activation {
         let mut th = TableHelper::new(value)?;
         let scripts = th.optional("scripts");
         let env = th.optional::<TomlIndexMap<_, _>>("env");
+        let env_files = th.optional("env-files");
         th.finalize(None)?;
         Ok(Activation {
             scripts,
             env: env.map(TomlIndexMap::into_inner),
+            env_files,
         })
     }
 }
@@ -32,6 +39,7 @@ mod test {
     fn deserialize_activation() {
         let input = r#"
             scripts = ["echo 'Hello, World!'"]
+            env-files = [".env"]
             [env]
             FOO = "bar"
             "#;
@@ -48,5 +56,12 @@ mod test {
                 "bar".to_string()
             )]))
         );
+        assert_eq!(
+            activation.env_files,
+            Some(vec![EnvFile {
+                path: ".env".into(),
+                required: false,
+            }])
+        );
     }
 }
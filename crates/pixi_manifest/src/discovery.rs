@@ -171,6 +171,9 @@ pub enum ExplicitManifestError {
 
     /// The pixi version could not match the minimum requirement.
     #[error("workspace requires pixi '{}', but I am {}", .requires_pixi, consts::PIXI_VERSION)]
+    #[diagnostic(help(
+        "the `requires-pixi` key of the manifest sets the versions of pixi that can be used with this workspace, e.g. because older versions can't read its lock-file. Upgrade pixi with `pixi self-update`, or with the package manager that installed it"
+    ))]
     SelfVersionMatchError { requires_pixi: VersionSpec },
 }
 
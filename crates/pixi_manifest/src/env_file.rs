@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+
+use toml_edit::{InlineTable, Value};
+use toml_span::{
+    DeserError,
+    de_helpers::{TableHelper, expected},
+    value::ValueInner,
+};
+
+/// A `.env` file to load environment variables from, e.g.
+/// `env-files = [".env", { path = ".env.local", required = true }]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnvFile {
+    /// The path of the file, relative to the root of the workspace.
+    pub path: PathBuf,
+
+    /// Whether a missing file is an error instead of a warning.
+    pub required: bool,
+}
+
+impl EnvFile {
+    /// Returns the TOML representation, a plain string unless the file is
+    /// required.
+    pub fn to_toml_value(&self) -> Value {
+        let path = self.path.to_string_lossy().to_string();
+        if self.required {
+            let mut table = InlineTable::new();
+            table.insert("path", path.into());
+            table.insert("required", true.into());
+            Value::InlineTable(table)
+        } else {
+            path.into()
+        }
+    }
+}
+
+impl<'de> toml_span::Deserialize<'de> for EnvFile {
+    fn deserialize(value: &mut toml_span::Value<'de>) -> Result<Self, DeserError> {
+        let span = value.span;
+        match value.take() {
+            ValueInner::String(path) => Ok(EnvFile {
+                path: PathBuf::from(path.as_ref()),
+                required: false,
+            }),
+            ValueInner::Table(table) => {
+                let mut th = TableHelper::from((table, span));
+                let path = th.required::<String>("path")?;
+                let required = th.optional("required").unwrap_or(false);
+                th.finalize(None)?;
+                Ok(EnvFile {
+                    path: PathBuf::from(path),
+                    required,
+                })
+            }
+            inner => Err(expected("a string or a table", inner, span).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use toml_span::{DeserError, Value, de_helpers::TableHelper};
+
+    use super::*;
+    use crate::toml::FromTomlStr;
+
+    struct TopLevel {
+        env_files: Vec<EnvFile>,
+    }
+
+    impl<'de> toml_span::Deserialize<'de> for TopLevel {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            let mut th = TableHelper::new(value)?;
+            let env_files = th.required("env-files")?;
+            th.finalize(None)?;
+            Ok(TopLevel { env_files })
+        }
+    }
+
+    #[test]
+    fn test_parse_env_files() {
+        let input = r#"
+            env-files = [".env", { path = ".env.local", required = true }]
+        "#;
+
+        let top_level = TopLevel::from_toml_str(input).unwrap();
+        assert_eq!(
+            top_level.env_files,
+            vec![
+                EnvFile {
+                    path: PathBuf::from(".env"),
+                    required: false,
+                },
+                EnvFile {
+                    path: PathBuf::from(".env.local"),
+                    required: true,
+                },
+            ]
+        );
+        assert_eq!(
+            top_level
+                .env_files
+                .iter()
+                .map(|file| file.to_toml_value().to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "\".env\"".to_string(),
+                "{ path = \".env.local\", required = true }".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_env_file() {
+        let input = r#"
+            env-files = [{ file = ".env" }]
+        "#;
+        assert!(TopLevel::from_toml_str(input).is_err());
+    }
+}
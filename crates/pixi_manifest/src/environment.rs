@@ -10,7 +10,7 @@ use regex::Regex;
 use serde::{self, Deserialize, Deserializer, Serialize};
 use thiserror::Error;
 
-use crate::{consts::DEFAULT_ENVIRONMENT_NAME, solve_group::SolveGroupIdx};
+use crate::{EnvFile, TaskName, consts::DEFAULT_ENVIRONMENT_NAME, solve_group::SolveGroupIdx};
 
 #[derive(Debug, Clone, Error, Diagnostic, PartialEq)]
 #[error(
@@ -57,6 +57,25 @@ impl EnvironmentName {
         matches!(self, EnvironmentName::Default)
     }
 
+    /// Derives an environment name from the name of a feature, extra or
+    /// dependency group. The name is lowercased and every run of `-`, `_` and
+    /// `.` is replaced by a single dash, like extras are normalized in PEP 685.
+    pub fn normalized(name: &str) -> Result<Self, ParseEnvironmentNameError> {
+        let mut normalized = String::with_capacity(name.len());
+        for c in name.chars() {
+            if matches!(c, '-' | '_' | '.') {
+                if !normalized.ends_with('-') {
+                    normalized.push('-');
+                }
+            } else {
+                normalized.extend(c.to_lowercase());
+            }
+        }
+        EnvironmentName::from_str(&normalized).map_err(|_| ParseEnvironmentNameError {
+            attempted_parse: name.to_string(),
+        })
+    }
+
     /// Tries to read the environment name from an argument, then it will try
     /// to read from an environment variable, otherwise it will fall back to
     /// default
@@ -150,6 +169,14 @@ pub struct Environment {
 
     /// Whether to include the default feature in that environment
     pub no_default_feature: bool,
+
+    /// The tasks to run after the environment was installed, next to the ones
+    /// defined by its features.
+    pub post_install: Vec<TaskName>,
+
+    /// The `.env` files to load into the environment of the tasks, after the
+    /// ones of its features.
+    pub env_files: Vec<EnvFile>,
 }
 
 #[cfg(test)]
@@ -196,6 +223,34 @@ mod tests {
         assert!(EnvironmentName::from_str("Py-39").is_err());
     }
 
+    #[test]
+    fn test_environment_name_normalized() {
+        assert_eq!(
+            EnvironmentName::normalized("test").unwrap().as_str(),
+            "test"
+        );
+        assert_eq!(
+            EnvironmentName::normalized("Dev_Tools").unwrap().as_str(),
+            "dev-tools"
+        );
+        assert_eq!(
+            EnvironmentName::normalized("py3.9__extra")
+                .unwrap()
+                .as_str(),
+            "py3-9-extra"
+        );
+        assert_eq!(
+            EnvironmentName::normalized("default").unwrap(),
+            EnvironmentName::Default
+        );
+        assert_eq!(
+            EnvironmentName::normalized("foo bar").unwrap_err(),
+            ParseEnvironmentNameError {
+                attempted_parse: "foo bar".to_string()
+            }
+        );
+    }
+
     #[test]
     fn test_environment_name_as_str() {
         assert_eq!(EnvironmentName::Default.as_str(), "default");
@@ -1,6 +1,6 @@
 use crate::{
-    SpecType, SystemRequirements, WorkspaceTarget, channel::PrioritizedChannel, consts,
-    pypi::pypi_options::PypiOptions, target::Targets, workspace::ChannelPriority,
+    EnvFile, SpecType, SystemRequirements, TaskName, WorkspaceTarget, channel::PrioritizedChannel,
+    consts, pypi::pypi_options::PypiOptions, target::Targets, workspace::ChannelPriority,
 };
 use indexmap::{IndexMap, IndexSet};
 use itertools::Either;
@@ -143,6 +143,10 @@ pub struct Feature {
     /// Pypi-related options
     pub pypi_options: Option<PypiOptions>,
 
+    /// The tasks to run after an environment that includes this feature was
+    /// installed.
+    pub post_install: Vec<TaskName>,
+
     /// Target specific configuration.
     pub targets: Targets<WorkspaceTarget>,
 }
@@ -157,6 +161,7 @@ impl Feature {
             channel_priority: None,
             system_requirements: SystemRequirements::default(),
             pypi_options: None,
+            post_install: Vec::new(),
             targets: <Targets<WorkspaceTarget> as Default>::default(),
         }
     }
@@ -338,6 +343,16 @@ impl Feature {
             .next()
     }
 
+    /// Returns the `.env` files of the most specific target that matches the
+    /// given `platform` and defines any.
+    pub fn activation_env_files(&self, platform: Option<Platform>) -> Option<&Vec<EnvFile>> {
+        self.targets
+            .resolve(platform)
+            .filter_map(|t| t.activation.as_ref())
+            .filter_map(|a| a.env_files.as_ref())
+            .next()
+    }
+
     /// Returns the activation environment for the most specific target that
     /// matches the given `platform`.
     ///
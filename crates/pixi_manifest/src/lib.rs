@@ -3,6 +3,7 @@ mod build_system;
 pub(crate) mod channel;
 mod dependencies;
 mod discovery;
+mod env_file;
 mod environment;
 mod environments;
 mod error;
@@ -35,6 +36,7 @@ pub use discovery::{
     DiscoveryStart, ExplicitManifestError, LoadManifestsError, Manifests, WorkspaceDiscoverer,
     WorkspaceDiscoveryError,
 };
+pub use env_file::EnvFile;
 pub use environment::{Environment, EnvironmentName};
 pub use error::TomlError;
 pub use feature::{Feature, FeatureName};
@@ -44,7 +46,7 @@ pub use has_manifest_ref::HasWorkspaceManifest;
 use itertools::Itertools;
 pub use manifests::{
     AssociateProvenance, ManifestKind, ManifestProvenance, ManifestSource, PackageManifest,
-    ProvenanceError, WithProvenance, WorkspaceManifest, WorkspaceManifestMut,
+    PackageManifestMut, ProvenanceError, WithProvenance, WorkspaceManifest, WorkspaceManifestMut,
 };
 use miette::Diagnostic;
 pub use package::Package;
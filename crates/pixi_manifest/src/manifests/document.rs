@@ -11,7 +11,7 @@ use pixi_pypi_spec::{PixiPypiSpec, PypiPackageName};
 use pixi_spec::PixiSpec;
 use rattler_conda_types::{PackageName, Platform};
 use thiserror::Error;
-use toml_edit::{Array, DocumentMut, Item, Table, Value, value};
+use toml_edit::{Array, DocumentMut, Item, Table, TableLike, Value, value};
 
 /// Discriminates between a 'pixi.toml' and a 'pyproject.toml' manifest.
 #[derive(Debug, Clone)]
@@ -161,6 +161,15 @@ impl ManifestDocument {
         }
     }
 
+    /// Returns the name of the `[package]` table in the manifest.
+    fn package_table_prefix(&self) -> &'static str {
+        match self {
+            ManifestDocument::PyProjectToml(_) => "tool.pixi.package",
+            ManifestDocument::PixiToml(_) => "package",
+            ManifestDocument::MojoProjectToml(_) => "package",
+        }
+    }
+
     fn manifest_mut(&mut self) -> &mut TomlDocument {
         match self {
             ManifestDocument::PyProjectToml(document) => document,
@@ -245,39 +254,14 @@ impl ManifestDocument {
     ) -> Result<(), TomlError> {
         // For 'pyproject.toml' manifest, try and remove the dependency from native
         // arrays
-        let remove_requirement =
-            |source: &mut ManifestDocument, table, array_name| -> Result<(), TomlError> {
-                let array = source
-                    .manifest_mut()
-                    .get_mut_toml_array(table, array_name)?;
-                if let Some(array) = array {
-                    array.retain(|x| {
-                        let req: pep508_rs::Requirement = x
-                            .as_str()
-                            .unwrap_or("")
-                            .parse()
-                            .expect("should be a valid pep508 dependency");
-                        let name = PypiPackageName::from_normalized(req.name);
-                        name != *dep
-                    });
-                    if array.is_empty() {
-                        source
-                            .manifest_mut()
-                            .get_or_insert_nested_table(table)?
-                            .remove(array_name);
-                    }
-                }
-                Ok(())
-            };
-
         match self {
             ManifestDocument::PyProjectToml(_) if feature_name.is_default() => {
-                remove_requirement(self, "project", "dependencies")?;
+                self.remove_requirement("project", "dependencies", dep, false)?;
             }
             ManifestDocument::PyProjectToml(_) => {
                 let name = feature_name.to_string();
-                remove_requirement(self, "project.optional-dependencies", &name)?;
-                remove_requirement(self, "dependency-groups", &name)?;
+                self.remove_requirement("project.optional-dependencies", &name, dep, false)?;
+                self.remove_requirement("dependency-groups", &name, dep, false)?;
             }
             _ => (),
         };
@@ -293,6 +277,94 @@ impl ManifestDocument {
         self.manifest_mut()
             .get_or_insert_nested_table(table_name.to_string().as_str())
             .map(|t| t.remove(dep.as_source()))?;
+        if platform.is_some() {
+            self.remove_empty_target_table(&table_name);
+        }
+        Ok(())
+    }
+
+    /// Removes a table of a `target.<platform>` when it became empty, together
+    /// with the platform and `target` tables if they are left empty.
+    fn remove_empty_target_table(&mut self, table_name: &TableName) {
+        self.manifest_mut()
+            .remove_empty_nested_table(&table_name.to_string(), 3);
+    }
+
+    /// Removes a pypi dependency from a group of the native
+    /// `project.optional-dependencies` table of a 'pyproject.toml' manifest.
+    /// The group is removed when it becomes empty, unless `keep_empty_group`
+    /// is set.
+    ///
+    /// Returns `false` if the dependency is not part of the group.
+    pub fn remove_pypi_optional_dependency(
+        &mut self,
+        dep: &PypiPackageName,
+        group: &str,
+        keep_empty_group: bool,
+    ) -> Result<bool, TomlError> {
+        self.remove_requirement(
+            "project.optional-dependencies",
+            group,
+            dep,
+            keep_empty_group,
+        )
+    }
+
+    /// Removes the requirements of a pypi dependency from a native array of
+    /// requirements, the formatting of the other requirements is kept. The
+    /// array is removed when it becomes empty, unless `keep_empty` is set.
+    ///
+    /// Returns `false` if the array does not contain the dependency.
+    fn remove_requirement(
+        &mut self,
+        table: &str,
+        array_name: &str,
+        dep: &PypiPackageName,
+        keep_empty: bool,
+    ) -> Result<bool, TomlError> {
+        let Some(array) = self.manifest_mut().get_mut_toml_array(table, array_name)? else {
+            return Ok(false);
+        };
+        let len = array.len();
+        array.retain(|x| {
+            let req: pep508_rs::Requirement = x
+                .as_str()
+                .unwrap_or("")
+                .parse()
+                .expect("should be a valid pep508 dependency");
+            let name = PypiPackageName::from_normalized(req.name);
+            name != *dep
+        });
+        let removed = array.len() < len;
+        if array.is_empty() && !keep_empty {
+            self.manifest_mut()
+                .get_or_insert_nested_table(table)?
+                .remove(array_name);
+        }
+        Ok(removed)
+    }
+
+    /// Removes a conda dependency from the `[package]` table of the TOML
+    /// manifest
+    ///
+    /// If will be a no-op if the dependency is not found
+    pub fn remove_package_dependency(
+        &mut self,
+        dep: &PackageName,
+        spec_type: SpecType,
+        platform: Option<Platform>,
+    ) -> Result<(), TomlError> {
+        let table_name = TableName::new()
+            .with_prefix(Some(self.package_table_prefix()))
+            .with_platform(platform.as_ref())
+            .with_table(Some(spec_type.name()));
+
+        self.manifest_mut()
+            .get_or_insert_nested_table(table_name.to_string().as_str())
+            .map(|t| t.remove(dep.as_source()))?;
+        if platform.is_some() {
+            self.remove_empty_target_table(&table_name);
+        }
         Ok(())
     }
 
@@ -316,6 +388,9 @@ impl ManifestDocument {
         self.manifest_mut()
             .get_or_insert_nested_table(table_name.to_string().as_str())
             .map(|t| t.remove(dep.as_source()))?;
+        if platform.is_some() {
+            self.remove_empty_target_table(&table_name);
+        }
         Ok(())
     }
 
@@ -336,6 +411,28 @@ impl ManifestDocument {
             .with_feature_name(Some(feature_name))
             .with_table(Some(spec_type.name()));
 
+        self.manifest_mut()
+            .get_or_insert_nested_table(dependency_table.to_string().as_str())
+            .map(|t| insert_keeping_decor(t, name.as_normalized(), spec.to_toml_value()))?;
+
+        Ok(())
+    }
+
+    /// Adds a conda dependency to the `[package]` table of the TOML manifest
+    ///
+    /// If a dependency with the same name already exists, it will be replaced.
+    pub fn add_package_dependency(
+        &mut self,
+        name: &PackageName,
+        spec: &PixiSpec,
+        spec_type: SpecType,
+        platform: Option<Platform>,
+    ) -> Result<(), TomlError> {
+        let dependency_table = TableName::new()
+            .with_prefix(Some(self.package_table_prefix()))
+            .with_platform(platform.as_ref())
+            .with_table(Some(spec_type.name()));
+
         self.manifest_mut()
             .get_or_insert_nested_table(dependency_table.to_string().as_str())
             .map(|t| t.insert(name.as_normalized(), Item::Value(spec.to_toml_value())))?;
@@ -391,12 +488,12 @@ impl ManifestDocument {
                 .with_feature_name(Some(feature_name))
                 .with_table(Some(consts::PYPI_DEPENDENCIES));
 
-            self.manifest_mut()
-                .get_or_insert_nested_table(dependency_table.to_string().as_str())?
-                .insert(
-                    requirement.name.as_ref(),
-                    Item::Value(pypi_requirement.into()),
-                );
+            insert_keeping_decor(
+                self.manifest_mut()
+                    .get_or_insert_nested_table(dependency_table.to_string().as_str())?,
+                requirement.name.as_ref(),
+                pypi_requirement.into(),
+            );
             return Ok(());
         }
 
@@ -428,6 +525,37 @@ impl ManifestDocument {
         Ok(())
     }
 
+    /// Returns the PEP 508 requirement of a PyPi dependency that is stored as
+    /// a string in one of the arrays of a 'pyproject.toml' manifest, including
+    /// its markers.
+    ///
+    /// Returns `None` for dependencies in a `pypi-dependencies` table, those
+    /// are not written as PEP 508 strings.
+    pub fn pep508_requirement(
+        &self,
+        package_name: &PypiPackageName,
+        feature_name: &FeatureName,
+        location: &PypiDependencyLocation,
+    ) -> Option<pep508_rs::Requirement> {
+        let name = feature_name.to_string();
+        let (table, array) = match location {
+            PypiDependencyLocation::PixiPypiDependencies => return None,
+            PypiDependencyLocation::Dependencies => ("project", "dependencies"),
+            PypiDependencyLocation::OptionalDependencies => {
+                ("project.optional-dependencies", name.as_str())
+            }
+            PypiDependencyLocation::DependencyGroups => ("dependency-groups", name.as_str()),
+        };
+        self.manifest()
+            .get_toml_array(table, array)
+            .ok()
+            .flatten()?
+            .iter()
+            .filter_map(|value| value.as_str())
+            .filter_map(|value| pep508_rs::Requirement::from_str(value).ok())
+            .find(|requirement| &requirement.name == package_name.as_normalized())
+    }
+
     /// Determines the location of a PyPi dependency within the manifest.
     ///
     /// This method checks various sections of the manifest to locate the
@@ -749,3 +877,12 @@ impl ManifestDocument {
         Ok(())
     }
 }
+
+/// Inserts a value into a table. If the key already exists the comments and
+/// whitespace around the old value are kept.
+fn insert_keeping_decor(table: &mut dyn TableLike, key: &str, mut value: Value) {
+    if let Some(existing) = table.get(key).and_then(Item::as_value) {
+        *value.decor_mut() = existing.decor().clone();
+    }
+    table.insert(key, Item::Value(value));
+}
@@ -17,7 +17,7 @@ mod source;
 mod workspace;
 
 pub use document::ManifestDocument;
-pub use package::PackageManifest;
+pub use package::{PackageManifest, PackageManifestMut};
 pub use provenance::{
     AssociateProvenance, ManifestKind, ManifestProvenance, ProvenanceError, WithProvenance,
 };
@@ -1,5 +1,11 @@
+use pixi_spec::PixiSpec;
+use rattler_conda_types::{PackageName, Platform};
+
 use crate::target::PackageTarget;
-use crate::{PackageBuild, Targets, package::Package};
+use crate::{
+    DependencyOverwriteBehavior, ManifestDocument, PackageBuild, SpecType, TargetSelector, Targets,
+    error::DependencyError, package::Package, to_options,
+};
 
 /// Holds the parsed content of the package part of a pixi manifest. This
 /// describes the part related to the package only.
@@ -14,3 +20,73 @@ pub struct PackageManifest {
     /// Defines the dependencies of the package
     pub targets: Targets<PackageTarget>,
 }
+
+/// A mutable view of a [`PackageManifest`] together with the TOML document it
+/// is defined in.
+pub struct PackageManifestMut<'a> {
+    pub package: &'a mut PackageManifest,
+    pub document: &'a mut ManifestDocument,
+}
+
+impl PackageManifestMut<'_> {
+    /// Add a conda dependency to the package.
+    ///
+    /// This function modifies both the package and the TOML document. Use
+    /// `ManifestProvenance::save` to persist the changes to disk.
+    pub fn add_dependency(
+        &mut self,
+        name: &PackageName,
+        spec: &PixiSpec,
+        spec_type: SpecType,
+        platforms: &[Platform],
+        overwrite_behavior: DependencyOverwriteBehavior,
+    ) -> miette::Result<bool> {
+        let mut any_added = false;
+        for platform in to_options(platforms) {
+            match self
+                .package
+                .targets
+                .for_opt_target_or_default_mut(platform.map(TargetSelector::Platform).as_ref())
+                .try_add_dependency(name, spec, spec_type, overwrite_behavior)
+            {
+                Ok(true) => {
+                    self.document
+                        .add_package_dependency(name, spec, spec_type, platform)?;
+                    any_added = true;
+                }
+                Ok(false) => {}
+                Err(e) => return Err(e.into()),
+            };
+        }
+        Ok(any_added)
+    }
+
+    /// Removes a conda dependency from the package.
+    ///
+    /// This function modifies both the package and the TOML document. Use
+    /// `ManifestProvenance::save` to persist the changes to disk.
+    pub fn remove_dependency(
+        &mut self,
+        dep: &PackageName,
+        spec_type: SpecType,
+        platforms: &[Platform],
+    ) -> miette::Result<()> {
+        for platform in to_options(platforms) {
+            let removed = self
+                .package
+                .targets
+                .for_opt_target_mut(platform.map(TargetSelector::Platform).as_ref())
+                .map(|target| target.remove_dependency(dep, spec_type));
+            match removed {
+                None | Some(Ok(_)) => {}
+                Some(Err(DependencyError::NoDependency(e))) => {
+                    tracing::warn!("Dependency `{}` doesn't exist", e);
+                }
+                Some(Err(e)) => return Err(e.into()),
+            }
+            self.document
+                .remove_package_dependency(dep, spec_type, platform)?;
+        }
+        Ok(())
+    }
+}
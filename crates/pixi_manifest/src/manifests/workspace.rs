@@ -306,6 +306,8 @@ impl WorkspaceManifestMut<'_> {
             features: features.unwrap_or_default(),
             solve_group: None,
             no_default_feature,
+            post_install: Vec::new(),
+            env_files: Vec::new(),
         });
 
         if let Some(solve_group) = solve_group {
@@ -508,7 +510,7 @@ impl WorkspaceManifestMut<'_> {
             match self
                 .workspace
                 .get_or_insert_target_mut(platform, Some(feature_name))
-                .try_add_pep508_dependency(requirement, editable, overwrite_behavior)
+                .try_add_pep508_dependency((requirement, pixi_req), editable, overwrite_behavior)
             {
                 Ok(true) => {
                     self.document.add_pypi_dependency(
@@ -565,6 +567,51 @@ impl WorkspaceManifestMut<'_> {
         Ok(())
     }
 
+    /// Removes a pypi dependency from a group of the
+    /// `project.optional-dependencies` table of a `pyproject.toml` manifest.
+    /// The group is removed when it becomes empty, unless `keep_empty_group`
+    /// is set.
+    ///
+    /// Returns `true` if the group was removed.
+    ///
+    /// This function modifies both the workspace and the TOML document. Use
+    /// `ManifestProvenance::save` to persist the changes to disk.
+    pub fn remove_pypi_optional_dependency(
+        &mut self,
+        dep: &PypiPackageName,
+        group: &str,
+        keep_empty_group: bool,
+    ) -> miette::Result<bool> {
+        // Remove the dependency from the TOML document
+        if !self
+            .document
+            .remove_pypi_optional_dependency(dep, group, keep_empty_group)?
+        {
+            miette::bail!(
+                "could not find '{}' in the optional dependency group '{}'",
+                dep.as_source(),
+                group
+            );
+        }
+
+        // Remove the dependency from the feature of the group
+        if let Some(feature) = self.workspace.features.get_mut(group) {
+            if let Err(DependencyError::NoDependency(e)) =
+                feature.targets.default_mut().remove_pypi_dependency(dep)
+            {
+                tracing::warn!("Dependency `{}` doesn't exist", e);
+            }
+        }
+
+        Ok(self
+            .document
+            .manifest()
+            .get_toml_array("project.optional-dependencies", group)
+            .ok()
+            .flatten()
+            .is_none())
+    }
+
     /// Adds the specified channels to the manifest.
     ///
     /// This function modifies both the workspace and the TOML document. Use
@@ -988,6 +1035,68 @@ start = "python -m flask run --port=5050"
         assert_snapshot!(manifest.document.to_string());
     }
 
+    #[test]
+    fn test_remove_pypi_optional_dependency() {
+        let contents = format!(
+            r#"{PYPROJECT_BOILERPLATE}
+[project.optional-dependencies]
+docs = [
+    # The documentation generator
+    "sphinx",
+    # The theme
+    "furo",
+]
+test = ["pytest"]
+"#
+        );
+        let mut manifest = parse_pyproject_toml(&contents);
+        let mut manifest = manifest.editable();
+
+        // Comments of the other requirements are kept
+        let sphinx = PypiPackageName::from_str("sphinx").unwrap();
+        assert!(
+            !manifest
+                .remove_pypi_optional_dependency(&sphinx, "docs", false)
+                .unwrap()
+        );
+        assert!(
+            manifest
+                .workspace
+                .feature(&FeatureName::from("docs"))
+                .unwrap()
+                .targets
+                .default()
+                .pypi_dependencies
+                .as_ref()
+                .unwrap()
+                .get(&sphinx)
+                .is_none()
+        );
+
+        // A dependency that is not part of the group is an error
+        assert!(
+            manifest
+                .remove_pypi_optional_dependency(&sphinx, "docs", false)
+                .is_err()
+        );
+
+        // Empty groups are removed, unless they should be kept
+        let pytest = PypiPackageName::from_str("pytest").unwrap();
+        assert!(
+            !manifest
+                .remove_pypi_optional_dependency(&pytest, "test", true)
+                .unwrap()
+        );
+        let furo = PypiPackageName::from_str("furo").unwrap();
+        assert!(
+            manifest
+                .remove_pypi_optional_dependency(&furo, "docs", false)
+                .unwrap()
+        );
+
+        assert_snapshot!(manifest.document.to_string());
+    }
+
     #[test]
     fn test_target_specific() {
         let contents = format!(
@@ -1601,6 +1710,40 @@ start = "python -m flask run --port=5050"
         );
     }
 
+    #[test]
+    fn test_remove_target_dependency_removes_empty_table() {
+        let mut manifest = parse_pixi_toml(
+            r#"
+            [workspace]
+            name = "foo"
+            channels = []
+            platforms = ["linux-64", "win-64"]
+
+            [target.win-64]
+            dependencies = { mkl = "*" }
+
+            [target.linux-64.dependencies]
+            mkl = "*"
+            "#,
+        );
+        let mut manifest = manifest.editable();
+        manifest
+            .remove_dependency(
+                &PackageName::new_unchecked("mkl"),
+                SpecType::Run,
+                &[Platform::Win64],
+                &FeatureName::DEFAULT,
+            )
+            .unwrap();
+
+        let document = manifest.document.to_string();
+        assert!(!document.contains("win-64]"), "{document}");
+        assert!(
+            document.contains("[target.linux-64.dependencies]\n            mkl = \"*\""),
+            "{document}"
+        );
+    }
+
     #[test]
     fn test_remove_dependencies() {
         // Using known files in the project so the test succeed including the file
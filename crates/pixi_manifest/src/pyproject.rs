@@ -19,7 +19,7 @@ use super::{
     error::{RequirementConversionError, TomlError},
 };
 use crate::{
-    FeatureName, ManifestKind, Warning,
+    EnvironmentName, FeatureName, ManifestKind, Warning,
     error::{DependencyError, GenericError},
     manifests::PackageManifest,
     toml::{
@@ -122,6 +122,22 @@ impl PyProjectManifest {
         self.pixi_manifest().is_some()
     }
 
+    /// Returns the spec of the `python` dependency derived from the
+    /// `requires-python` field of the `[project]` table, if it is defined.
+    pub fn requires_python_spec(&self) -> Option<PixiSpec> {
+        let requires_python = self.project.project.as_ref()?.requires_python.as_ref()?;
+        version_or_url_to_spec(&Some(requires_python.value.clone())).ok()
+    }
+
+    /// Returns the number of requirements in `[project.dependencies]`.
+    pub fn dependency_count(&self) -> usize {
+        self.project
+            .project
+            .as_ref()
+            .and_then(|project| project.dependencies.as_ref())
+            .map_or(0, Vec::len)
+    }
+
     /// Returns optional dependencies from the `[project.optional-dependencies]`
     /// table
     fn optional_dependencies(&self) -> Option<IndexMap<String, Vec<Requirement>>> {
@@ -163,7 +179,9 @@ impl PyProjectManifest {
                     }
                 }
                 // Environments can only contain number, strings and dashes
-                environments.insert(extra.replace('_', "-").clone(), features);
+                let environment = EnvironmentName::normalized(&extra)
+                    .map_or_else(|_| extra.replace('_', "-"), |name| name.to_string());
+                environments.insert(environment, features);
             }
         }
 
@@ -387,7 +405,7 @@ impl PyProjectManifest {
             for requirement in deps.iter() {
                 target
                     .try_add_pep508_dependency(
-                        &requirement.value,
+                        (&requirement.value, None),
                         None,
                         DependencyOverwriteBehavior::Error,
                     )
@@ -417,7 +435,7 @@ impl PyProjectManifest {
                 if project_name.as_ref() != Some(&requirement.name) {
                     target
                         .try_add_pep508_dependency(
-                            requirement,
+                            (requirement, None),
                             None,
                             DependencyOverwriteBehavior::Error,
                         )
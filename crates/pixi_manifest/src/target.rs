@@ -230,7 +230,7 @@ impl WorkspaceTarget {
     /// This will overwrite any existing dependency of the same name
     pub fn try_add_pep508_dependency(
         &mut self,
-        requirement: &pep508_rs::Requirement,
+        (requirement, pixi_req): (&pep508_rs::Requirement, Option<&PixiPypiSpec>),
         editable: Option<bool>,
         dependency_overwrite_behavior: DependencyOverwriteBehavior,
     ) -> Result<bool, DependencyError> {
@@ -251,7 +251,8 @@ impl WorkspaceTarget {
 
         // Convert to an internal representation
         let name = PypiPackageName::from_normalized(requirement.name.clone());
-        let mut requirement = PixiPypiSpec::try_from(requirement.clone()).map_err(Box::new)?;
+        let mut requirement =
+            PixiPypiSpec::try_from((requirement.clone(), pixi_req.cloned())).map_err(Box::new)?;
         if let Some(editable) = editable {
             requirement.set_editable(editable);
         }
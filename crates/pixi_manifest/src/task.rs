@@ -6,6 +6,7 @@ use std::{
     ops::Deref,
     path::{Path, PathBuf},
     str::FromStr,
+    time::Duration,
 };
 
 use crate::workspace::JINJA_ENV;
@@ -16,7 +17,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use toml_edit::{Array, Item, Table, Value};
 
-use crate::EnvironmentName;
+use crate::{EnvFile, EnvironmentName};
 
 /// Represents a task name
 #[derive(Debug, Clone, Serialize, Eq, PartialEq, Hash, Ord, PartialOrd)]
@@ -98,9 +99,13 @@ impl From<&str> for Dependency {
 
 impl std::fmt::Display for Dependency {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.task_name)?;
+        if let Some(environment) = &self.environment {
+            write!(f, ":{environment}")?;
+        }
         match &self.args {
-            Some(args) if !args.is_empty() => write!(f, "{} with args", self.task_name),
-            _ => write!(f, "{}", self.task_name),
+            Some(args) if !args.is_empty() => write!(f, " with args"),
+            _ => Ok(()),
         }
     }
 }
@@ -249,6 +254,75 @@ impl Task {
         }
     }
 
+    /// Returns the variables of the running machine that are kept when the
+    /// task runs in a clean environment.
+    pub fn clean_env_keep(&self) -> &[String] {
+        match self {
+            Task::Execute(execute) => execute.clean_env_keep.as_deref().unwrap_or_default(),
+            _ => &[],
+        }
+    }
+
+    /// Returns the maximum time that the task may run.
+    pub fn timeout(&self) -> Option<Duration> {
+        match self {
+            Task::Execute(execute) => execute.timeout,
+            _ => None,
+        }
+    }
+
+    /// Returns the shell that runs the command of the task.
+    pub fn shell(&self) -> TaskShell {
+        match self {
+            Task::Execute(execute) => execute.shell.unwrap_or_default(),
+            _ => TaskShell::default(),
+        }
+    }
+
+    /// True if the task runs attached to the terminal, without capturing its
+    /// output.
+    pub fn interactive(&self) -> bool {
+        match self {
+            Task::Execute(execute) => execute.interactive,
+            _ => false,
+        }
+    }
+
+    /// Returns how many more times the command runs when it fails or times
+    /// out.
+    pub fn retries(&self) -> u32 {
+        match self {
+            Task::Execute(execute) => execute.retries.unwrap_or_default(),
+            _ => 0,
+        }
+    }
+
+    /// Returns the time to wait before the command runs again after it
+    /// failed.
+    pub fn retry_delay(&self) -> Option<Duration> {
+        match self {
+            Task::Execute(execute) => execute.retry_delay,
+            _ => None,
+        }
+    }
+
+    /// True if a failure of this task only skips the tasks that depend on it,
+    /// instead of stopping the whole run.
+    pub fn continue_on_error(&self) -> bool {
+        match self {
+            Task::Execute(execute) => execute.continue_on_error,
+            _ => false,
+        }
+    }
+
+    /// Returns the `.env` files of the task.
+    pub fn env_files(&self) -> Option<&[EnvFile]> {
+        match self {
+            Task::Execute(exe) => exe.env_files.as_deref(),
+            _ => None,
+        }
+    }
+
     /// Returns the inputs of the task.
     pub fn inputs(&self) -> Option<&GlobPatterns> {
         match self {
@@ -330,16 +404,115 @@ pub struct Execute {
     /// A list of environment variables to set before running the command
     pub env: Option<IndexMap<String, String>>,
 
+    /// `.env` files to load environment variables from before running the
+    /// command, the variables in `env` take precedence
+    pub env_files: Option<Vec<EnvFile>>,
+
     /// A description of the task
     pub description: Option<String>,
 
     /// Isolate the task from the running machine
     pub clean_env: bool,
 
+    /// The variables of the running machine that are kept when the task runs
+    /// in a clean environment, may contain `*` and `?` wildcards
+    pub clean_env_keep: Option<Vec<String>>,
+
+    /// The maximum time the command may run, after which it is terminated
+    pub timeout: Option<Duration>,
+
+    /// Only skip the tasks that depend on this one when it fails, the other
+    /// tasks of the run continue
+    pub continue_on_error: bool,
+
+    /// The shell that runs the command, the built-in shell if not specified
+    pub shell: Option<TaskShell>,
+
+    /// Run the command attached to the terminal, without capturing its output
+    pub interactive: bool,
+
+    /// How many more times the command runs when it fails or times out, only
+    /// the exit code of the last attempt counts
+    pub retries: Option<u32>,
+
+    /// The time to wait before the command runs again after it failed or
+    /// timed out
+    pub retry_delay: Option<Duration>,
+
     /// The arguments to pass to the task
     pub args: Option<Vec<TaskArg>>,
 }
 
+/// The shell that runs the command of a task.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TaskShell {
+    /// The cross-platform shell that is built into pixi
+    #[default]
+    Deno,
+    /// `bash` from the environment of the task
+    Bash,
+    /// `nu` from the environment of the task
+    Nu,
+    /// `pwsh` from the environment of the task
+    Powershell,
+}
+
+impl TaskShell {
+    /// All the shells, in the order in which they are documented.
+    pub const ALL: [TaskShell; 4] = [
+        TaskShell::Deno,
+        TaskShell::Bash,
+        TaskShell::Nu,
+        TaskShell::Powershell,
+    ];
+
+    /// Returns the name of the shell as it is written in the manifest.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskShell::Deno => "deno",
+            TaskShell::Bash => "bash",
+            TaskShell::Nu => "nu",
+            TaskShell::Powershell => "powershell",
+        }
+    }
+
+    /// Returns the name of the executable of the shell, `None` for the
+    /// built-in shell.
+    pub fn executable_name(&self) -> Option<&'static str> {
+        match self {
+            TaskShell::Deno => None,
+            TaskShell::Bash => Some("bash"),
+            TaskShell::Nu => Some("nu"),
+            TaskShell::Powershell => Some("pwsh"),
+        }
+    }
+}
+
+impl Display for TaskShell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TaskShell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TaskShell::ALL
+            .into_iter()
+            .find(|shell| shell.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown shell '{s}', expected one of {}",
+                    TaskShell::ALL
+                        .iter()
+                        .map(|shell| format!("'{shell}'"))
+                        .join(", ")
+                )
+            })
+    }
+}
+
 impl From<Execute> for Task {
     fn from(value: Execute) -> Self {
         Task::Execute(Box::new(value))
@@ -716,21 +889,26 @@ impl From<Task> for Item {
                     table.insert(
                         "depends-on",
                         Value::Array(Array::from_iter(process.depends_on.iter().map(|dep| {
-                            match &dep.args {
-                                Some(args) if !args.is_empty() => {
-                                    let mut table = Table::new().into_inline_table();
-                                    table.insert("task", dep.task_name.to_string().into());
-                                    table.insert(
-                                        "args",
-                                        Value::Array(Array::from_iter(
-                                            args.iter()
-                                                .map(|arg| Value::from(arg.source().to_string())),
-                                        )),
-                                    );
-                                    Value::InlineTable(table)
-                                }
-                                _ => Value::from(dep.task_name.to_string()),
+                            let args = dep.args.as_ref().filter(|args| !args.is_empty());
+                            if args.is_none() && dep.environment.is_none() {
+                                return Value::from(dep.task_name.to_string());
+                            }
+
+                            let mut table = Table::new().into_inline_table();
+                            table.insert("task", dep.task_name.to_string().into());
+                            if let Some(args) = args {
+                                table.insert(
+                                    "args",
+                                    Value::Array(Array::from_iter(
+                                        args.iter()
+                                            .map(|arg| Value::from(arg.source().to_string())),
+                                    )),
+                                );
+                            }
+                            if let Some(environment) = &dep.environment {
+                                table.insert("environment", environment.to_string().into());
                             }
+                            Value::InlineTable(table)
                         }))),
                     );
                 }
@@ -740,6 +918,44 @@ impl From<Task> for Item {
                 if let Some(env) = &process.env {
                     table.insert("env", Value::InlineTable(env.into_iter().collect()));
                 }
+                if let Some(env_files) = &process.env_files {
+                    table.insert(
+                        "env-files",
+                        Value::Array(Array::from_iter(
+                            env_files.iter().map(EnvFile::to_toml_value),
+                        )),
+                    );
+                }
+                if let Some(keep) = &process.clean_env_keep {
+                    table.insert(
+                        "clean-env-keep",
+                        Value::Array(Array::from_iter(keep.iter().map(Value::from))),
+                    );
+                }
+                if let Some(timeout) = process.timeout {
+                    table.insert(
+                        "timeout",
+                        humantime::format_duration(timeout).to_string().into(),
+                    );
+                }
+                if process.continue_on_error {
+                    table.insert("continue-on-error", true.into());
+                }
+                if let Some(shell) = process.shell {
+                    table.insert("shell", shell.as_str().into());
+                }
+                if process.interactive {
+                    table.insert("interactive", true.into());
+                }
+                if let Some(retries) = process.retries {
+                    table.insert("retries", i64::from(retries).into());
+                }
+                if let Some(retry_delay) = process.retry_delay {
+                    table.insert(
+                        "retry-delay",
+                        humantime::format_duration(retry_delay).to_string().into(),
+                    );
+                }
                 if let Some(description) = &process.description {
                     table.insert("description", description.into());
                 }
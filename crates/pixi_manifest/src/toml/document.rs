@@ -86,6 +86,31 @@ impl TomlDocument {
         Ok(current_table)
     }
 
+    /// Removes the table `table_name` in dotted form (e.g. `table1.table2`)
+    /// from the document if it is empty. The same is done for its parents that
+    /// become empty, up to `depth` tables in total.
+    pub fn remove_empty_nested_table(&mut self, table_name: &str, depth: usize) {
+        let parts: Vec<&str> = table_name.split('.').collect();
+        for len in (parts.len().saturating_sub(depth).max(1)..=parts.len()).rev() {
+            let (key, parents) = parts[..len].split_last().expect("len is at least 1");
+            let mut parent = self.0.as_table_mut() as &mut dyn TableLike;
+            for part in parents {
+                let Some(table) = parent.get_mut(part).and_then(Item::as_table_like_mut) else {
+                    return;
+                };
+                parent = table;
+            }
+            let is_empty = parent
+                .get(key)
+                .and_then(Item::as_table_like)
+                .is_some_and(|table| table.is_empty());
+            if !is_empty {
+                return;
+            }
+            parent.remove(key);
+        }
+    }
+
     /// Inserts a value into a certain table
     /// If the most inner table doesn't exist, an inline table will be created.
     /// If it already exists, the formatting of the table will be preserved
@@ -1,5 +1,8 @@
+use pixi_toml::{TomlFromStr, TomlWith};
 use toml_span::{DeserError, Spanned, Value, de_helpers::expected};
 
+use crate::{EnvFile, TaskName};
+
 /// Helper struct to deserialize the environment from TOML.
 /// The environment description can only hold these values.
 #[derive(Debug)]
@@ -7,6 +10,8 @@ pub struct TomlEnvironment {
     pub features: Option<Spanned<Vec<Spanned<String>>>>,
     pub solve_group: Option<String>,
     pub no_default_feature: bool,
+    pub post_install: Vec<TaskName>,
+    pub env_files: Vec<EnvFile>,
 }
 
 #[derive(Debug)]
@@ -22,6 +27,11 @@ impl<'de> toml_span::Deserialize<'de> for TomlEnvironment {
         let features = th.optional_s("features");
         let solve_group = th.optional("solve-group");
         let no_default_feature = th.optional("no-default-feature");
+        let post_install = th
+            .optional::<TomlWith<_, Vec<TomlFromStr<_>>>>("post-install")
+            .map(TomlWith::into_inner)
+            .unwrap_or_default();
+        let env_files = th.optional("env-files").unwrap_or_default();
 
         th.finalize(None)?;
 
@@ -37,6 +47,8 @@ impl<'de> toml_span::Deserialize<'de> for TomlEnvironment {
             features,
             solve_group,
             no_default_feature: no_default_feature.unwrap_or_default(),
+            post_install,
+            env_files,
         })
     }
 }
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use indexmap::{IndexMap, IndexSet};
-use pixi_toml::{TomlHashMap, TomlIndexMap, TomlIndexSet, TomlWith};
+use pixi_toml::{TomlFromStr, TomlHashMap, TomlIndexMap, TomlIndexSet, TomlWith};
 use rattler_conda_types::Platform;
 use toml_span::{DeserError, Spanned, Value, de_helpers::TableHelper};
 
@@ -40,6 +40,9 @@ pub struct TomlFeature {
     /// Additional options for PyPi dependencies.
     pub pypi_options: Option<PypiOptions>,
 
+    /// The tasks to run after the environment was installed.
+    pub post_install: Vec<TaskName>,
+
     /// Any warnings we encountered while parsing the feature
     pub warnings: Vec<Warning>,
 }
@@ -116,6 +119,7 @@ impl TomlFeature {
             channel_priority: self.channel_priority,
             system_requirements: self.system_requirements,
             pypi_options: self.pypi_options,
+            post_install: self.post_install,
             targets: Targets::from_default_and_user_defined(default_target, targets),
         })
         .with_warnings(warnings))
@@ -159,6 +163,10 @@ impl<'de> toml_span::Deserialize<'de> for TomlFeature {
             .collect();
         let pypi_options = th.optional("pypi-options");
         let system_requirements = th.optional("system-requirements").unwrap_or_default();
+        let post_install = th
+            .optional::<TomlWith<_, Vec<TomlFromStr<_>>>>("post-install")
+            .map(TomlWith::into_inner)
+            .unwrap_or_default();
 
         th.finalize(None)?;
 
@@ -175,6 +183,7 @@ impl<'de> toml_span::Deserialize<'de> for TomlFeature {
             activation,
             tasks,
             pypi_options,
+            post_install,
             warnings,
         })
     }
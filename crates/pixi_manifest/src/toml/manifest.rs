@@ -22,7 +22,7 @@ use crate::{
 use indexmap::IndexMap;
 use miette::LabeledSpan;
 use pixi_pypi_spec::{PixiPypiSpec, PypiPackageName};
-use pixi_toml::{Same, TomlHashMap, TomlIndexMap, TomlWith};
+use pixi_toml::{Same, TomlFromStr, TomlHashMap, TomlIndexMap, TomlWith};
 use rattler_conda_types::{Platform, Version};
 use toml_span::{
     DeserError, Spanned, Value,
@@ -60,6 +60,9 @@ pub struct TomlManifest {
     /// pypi-options
     pub pypi_options: Option<PixiSpanned<PypiOptions>>,
 
+    /// The tasks to run after an environment was installed.
+    pub post_install: Option<Vec<TaskName>>,
+
     /// Any warnings we encountered while parsing the manifest
     pub warnings: Vec<Warning>,
 }
@@ -199,6 +202,8 @@ impl TomlManifest {
             // the default feature
             pypi_options: self.pypi_options.map(PixiSpanned::into_inner),
 
+            post_install: self.post_install.unwrap_or_default(),
+
             // Combine the default target with all user specified targets
             targets: Targets::from_default_and_user_defined(
                 default_workspace_target,
@@ -267,7 +272,14 @@ impl TomlManifest {
         let mut features_used_by_environments = HashSet::new();
         for (name, env) in toml_environments {
             // Decompose the TOML
-            let (included_features, features_span, solve_group, no_default_feature) = match env {
+            let (
+                included_features,
+                features_span,
+                solve_group,
+                no_default_feature,
+                post_install,
+                env_files,
+            ) = match env {
                 TomlEnvironmentList::Map(env) => {
                     let (features, features_span) = env.features.map_or_else(
                         || (Vec::new(), None),
@@ -278,11 +290,18 @@ impl TomlManifest {
                         features_span,
                         env.solve_group,
                         env.no_default_feature,
+                        env.post_install,
+                        env.env_files,
                     )
                 }
-                TomlEnvironmentList::Seq(features) => {
-                    (features.value, Some(features.span), None, false)
-                }
+                TomlEnvironmentList::Seq(features) => (
+                    features.value,
+                    Some(features.span),
+                    None,
+                    false,
+                    Vec::new(),
+                    Vec::new(),
+                ),
             };
 
             features_used_by_environments
@@ -371,6 +390,8 @@ impl TomlManifest {
                 features: included_features.into_iter().map(Spanned::take).collect(),
                 solve_group: solve_group.map(|sg| solve_groups.add(sg, environment_idx)),
                 no_default_feature,
+                post_install,
+                env_files,
             }));
         }
 
@@ -505,6 +526,9 @@ impl<'de> toml_span::Deserialize<'de> for TomlManifest {
             .map(TomlWith::into_inner);
         let pypi_options = th.optional("pypi-options");
         let system_requirements = th.optional("system-requirements");
+        let post_install = th
+            .optional::<TomlWith<_, Vec<TomlFromStr<_>>>>("post-install")
+            .map(TomlWith::into_inner);
 
         // Parse the tool section by ignoring it.
         if let Some(mut tool) = th.table.remove("tool") {
@@ -542,6 +566,7 @@ impl<'de> toml_span::Deserialize<'de> for TomlManifest {
             feature,
             environments,
             pypi_options,
+            post_install,
             warnings,
         })
     }
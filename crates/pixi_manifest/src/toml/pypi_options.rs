@@ -31,79 +31,65 @@ impl<'de> toml_span::Deserialize<'de> for Pep508PackageName {
     }
 }
 
-impl<'de> toml_span::Deserialize<'de> for NoBuild {
+/// The packages selected by `no-build`, `only-binary` or `no-binary`. Next to
+/// booleans and arrays of package names these also accept pip's `:all:` and
+/// `:none:` sentinels, either on their own or as an element of the array.
+enum PackageSelection {
+    All,
+    None,
+    Packages(IndexSet<pep508_rs::PackageName>),
+}
+
+const ALL_PACKAGES: &str = ":all:";
+const NO_PACKAGES: &str = ":none:";
+
+impl<'de> toml_span::Deserialize<'de> for PackageSelection {
     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
-        // It can be either `true` or `false` or an array of strings
-        if value.as_bool().is_some() {
-            if bool::deserialize(value)? {
-                return Ok(NoBuild::All);
-            } else {
-                return Ok(NoBuild::None);
-            }
-        }
-        // We assume it's an array of strings
-        if value.as_array().is_some() {
-            match value.take() {
-                ValueInner::Array(array) => {
-                    let mut packages = IndexSet::with_capacity(array.len());
-                    for mut value in array {
-                        packages.insert(Pep508PackageName::deserialize(&mut value)?.0);
+        match value.take() {
+            ValueInner::Boolean(true) => Ok(PackageSelection::All),
+            ValueInner::Boolean(false) => Ok(PackageSelection::None),
+            ValueInner::String(str) if str == ALL_PACKAGES => Ok(PackageSelection::All),
+            ValueInner::String(str) if str == NO_PACKAGES => Ok(PackageSelection::None),
+            ValueInner::Array(array) => {
+                let mut packages = IndexSet::with_capacity(array.len());
+                for mut value in array {
+                    match value.as_str() {
+                        Some(ALL_PACKAGES) => return Ok(PackageSelection::All),
+                        Some(NO_PACKAGES) => packages.clear(),
+                        _ => {
+                            packages.insert(Pep508PackageName::deserialize(&mut value)?.0);
+                        }
                     }
-                    Ok(NoBuild::Packages(packages))
                 }
-                _ => Err(expected(
-                    "an array of packages e.g. [\"foo\", \"bar\"]",
-                    value.take(),
-                    value.span,
-                )
-                .into()),
+                Ok(PackageSelection::Packages(packages))
             }
-        } else {
-            Err(expected(
+            other => Err(expected(
                 r#"either "all", "none" or an array of packages e.g. ["foo", "bar"] "#,
-                value.take(),
+                other,
                 value.span,
             )
-            .into())
+            .into()),
         }
     }
 }
 
+impl<'de> toml_span::Deserialize<'de> for NoBuild {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        Ok(match PackageSelection::deserialize(value)? {
+            PackageSelection::All => NoBuild::All,
+            PackageSelection::None => NoBuild::None,
+            PackageSelection::Packages(packages) => NoBuild::Packages(packages),
+        })
+    }
+}
+
 impl<'de> toml_span::Deserialize<'de> for NoBinary {
     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
-        // It can be either `true` or `false` or an array of strings
-        if value.as_bool().is_some() {
-            if bool::deserialize(value)? {
-                return Ok(NoBinary::All);
-            } else {
-                return Ok(NoBinary::None);
-            }
-        }
-        // We assume it's an array of strings
-        if value.as_array().is_some() {
-            match value.take() {
-                ValueInner::Array(array) => {
-                    let mut packages = IndexSet::with_capacity(array.len());
-                    for mut value in array {
-                        packages.insert(Pep508PackageName::deserialize(&mut value)?.0);
-                    }
-                    Ok(NoBinary::Packages(packages))
-                }
-                _ => Err(expected(
-                    "an array of packages e.g. [\"foo\", \"bar\"]",
-                    value.take(),
-                    value.span,
-                )
-                .into()),
-            }
-        } else {
-            Err(expected(
-                r#"either "all", "none" or an array of packages e.g. ["foo", "bar"] "#,
-                value.take(),
-                value.span,
-            )
-            .into())
-        }
+        Ok(match PackageSelection::deserialize(value)? {
+            PackageSelection::All => NoBinary::All,
+            PackageSelection::None => NoBinary::None,
+            PackageSelection::Packages(packages) => NoBinary::Packages(packages),
+        })
     }
 }
 
@@ -123,7 +109,13 @@ impl<'de> toml_span::Deserialize<'de> for PypiOptions {
             .optional::<TomlEnum<_>>("index-strategy")
             .map(TomlEnum::into_inner);
 
+        // `only-binary` is pip's spelling of `no-build`, both are merged.
         let no_build = th.optional::<NoBuild>("no-build");
+        let only_binary = th.optional::<NoBuild>("only-binary");
+        let no_build = match (no_build, only_binary) {
+            (Some(no_build), Some(only_binary)) => Some(no_build.union(&only_binary)),
+            (no_build, only_binary) => no_build.or(only_binary),
+        };
 
         let no_binary = th.optional::<NoBinary>("no-binary");
 
@@ -320,6 +312,47 @@ mod test {
         assert_debug_snapshot!(options);
     }
 
+    #[test]
+    fn test_all_packages_sentinel() {
+        let input = r#"
+        no-binary = ":all:"
+        no-build = ["package1", ":all:"]
+        "#;
+        let options = PypiOptions::from_toml_str(input).unwrap();
+        assert_eq!(options.no_binary, Some(NoBinary::All));
+        assert_eq!(options.no_build, Some(NoBuild::All));
+    }
+
+    #[test]
+    fn test_no_packages_sentinel() {
+        let input = r#"
+        no-binary = ["package1", ":none:", "package2"]
+        "#;
+        let options = PypiOptions::from_toml_str(input).unwrap();
+        assert_eq!(
+            options.no_binary,
+            Some(NoBinary::Packages(IndexSet::from_iter(["package2"
+                .parse()
+                .unwrap()])))
+        );
+    }
+
+    #[test]
+    fn test_only_binary_merges_with_no_build() {
+        let input = r#"
+        no-build = ["package1"]
+        only-binary = ["package2"]
+        "#;
+        let options = PypiOptions::from_toml_str(input).unwrap();
+        assert_eq!(
+            options.no_build,
+            Some(NoBuild::Packages(IndexSet::from_iter([
+                "package1".parse().unwrap(),
+                "package2".parse().unwrap()
+            ])))
+        );
+    }
+
     #[test]
     fn test_no_build_isolation_boolean() {
         let input = r#"
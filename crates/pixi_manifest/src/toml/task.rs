@@ -9,7 +9,10 @@ use toml_span::{
 
 use crate::{
     EnvironmentName, Task, TaskName, WithWarnings,
-    task::{Alias, ArgName, CmdArgs, Dependency, Execute, GlobPatterns, TaskArg, TemplateString},
+    task::{
+        Alias, ArgName, CmdArgs, Dependency, Execute, GlobPatterns, TaskArg, TaskShell,
+        TemplateString,
+    },
     warning::Deprecation,
 };
 
@@ -189,8 +192,22 @@ impl<'de> toml_span::Deserialize<'de> for TomlTask {
             let env = th
                 .optional::<TomlIndexMap<_, _>>("env")
                 .map(TomlIndexMap::into_inner);
+            let env_files = th.optional("env-files");
             let description = th.optional("description");
             let clean_env = th.optional("clean-env").unwrap_or(false);
+            let clean_env_keep = th.optional("clean-env-keep");
+            let timeout = th
+                .optional::<TomlFromStr<humantime::Duration>>("timeout")
+                .map(|timeout| timeout.into_inner().into());
+            let continue_on_error = th.optional("continue-on-error").unwrap_or(false);
+            let shell = th
+                .optional::<TomlFromStr<TaskShell>>("shell")
+                .map(TomlFromStr::into_inner);
+            let interactive = th.optional("interactive").unwrap_or(false);
+            let retries = th.optional::<u32>("retries");
+            let retry_delay = th
+                .optional::<TomlFromStr<humantime::Duration>>("retry-delay")
+                .map(|delay| delay.into_inner().into());
             let args = th.optional::<Vec<TaskArg>>("args");
 
             let mut have_default = false;
@@ -217,8 +234,16 @@ impl<'de> toml_span::Deserialize<'de> for TomlTask {
                 depends_on,
                 cwd,
                 env,
+                env_files,
                 description,
                 clean_env,
+                clean_env_keep,
+                timeout,
+                continue_on_error,
+                shell,
+                interactive,
+                retries,
+                retry_delay,
                 args,
             }))
         } else {
@@ -297,6 +322,123 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_timeout() {
+        let parsed = TomlTask::from_toml_str(
+            r#"
+            cmd = "test"
+            timeout = "1m 30s"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.value.timeout(),
+            Some(std::time::Duration::from_secs(90))
+        );
+
+        assert!(
+            TomlTask::from_toml_str(
+                r#"
+            cmd = "test"
+            timeout = "ten minutes"
+        "#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_shell() {
+        let parsed = TomlTask::from_toml_str(
+            r#"
+            cmd = "set -euo pipefail; test"
+            shell = "bash"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(parsed.value.shell(), TaskShell::Bash);
+
+        let parsed = TomlTask::from_toml_str(r#"cmd = "test""#).unwrap();
+        assert_eq!(parsed.value.shell(), TaskShell::Deno);
+
+        assert!(
+            TomlTask::from_toml_str(
+                r#"
+            cmd = "test"
+            shell = "zsh"
+        "#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_retries() {
+        let parsed = TomlTask::from_toml_str(
+            r#"
+            cmd = "curl -O https://example.com/data.csv"
+            retries = 2
+            retry-delay = "5s"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(parsed.value.retries(), 2);
+        assert_eq!(
+            parsed.value.retry_delay(),
+            Some(std::time::Duration::from_secs(5))
+        );
+
+        let parsed = TomlTask::from_toml_str(r#"cmd = "test""#).unwrap();
+        assert_eq!(parsed.value.retries(), 0);
+        assert_eq!(parsed.value.retry_delay(), None);
+
+        assert!(
+            TomlTask::from_toml_str(
+                r#"
+            cmd = "test"
+            retries = -1
+        "#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_interactive() {
+        let parsed = TomlTask::from_toml_str(
+            r#"
+            cmd = "htop"
+            interactive = true
+        "#,
+        )
+        .unwrap();
+        assert!(parsed.value.interactive());
+
+        let parsed = TomlTask::from_toml_str(r#"cmd = "test""#).unwrap();
+        assert!(!parsed.value.interactive());
+    }
+
+    #[test]
+    fn test_depends_on_environment_round_trip() {
+        let parsed = TomlTask::from_toml_str(
+            r#"
+            cmd = "deploy"
+            depends-on = [{ task = "build", environment = "prod" }, "lint"]
+        "#,
+        )
+        .unwrap();
+        let depends_on = parsed.value.depends_on().to_vec();
+        assert_eq!(depends_on[0].to_string(), "build:prod");
+        assert_eq!(depends_on[1].to_string(), "lint");
+
+        let table = toml_edit::Item::from(parsed.value).into_table().unwrap();
+        let serialized = toml_edit::DocumentMut::from(table).to_string();
+        assert!(serialized.contains(r#"environment = "prod""#));
+
+        let reparsed = TomlTask::from_toml_str(&serialized).unwrap();
+        assert_eq!(reparsed.value.depends_on(), depends_on.as_slice());
+    }
+
     #[test]
     fn test_depends_on_is_list() {
         insta::assert_snapshot!(expect_parse_failure(
@@ -25,6 +25,7 @@ pub use version_or_star::VersionOrStar;
 pub enum PixiPypiSpec {
     Git {
         url: GitSpec,
+        editable: Option<bool>,
         #[serde(default)]
         extras: Vec<ExtraName>,
     },
@@ -143,11 +144,11 @@ impl PixiPypiSpec {
     /// Define whether the requirement is editable.
     pub fn set_editable(&mut self, editable: bool) {
         match self {
-            PixiPypiSpec::Path { editable: e, .. } => {
+            PixiPypiSpec::Path { editable: e, .. } | PixiPypiSpec::Git { editable: e, .. } => {
                 *e = Some(editable);
             }
             _ if editable => {
-                tracing::warn!("Ignoring editable flag for non-path requirements.");
+                tracing::warn!("Ignoring editable flag for non-path and non-git requirements.");
             }
             _ => {}
         }
@@ -217,6 +218,7 @@ mod tests {
                     rev: Some(GitReference::DefaultBranch),
                     subdirectory: None,
                 },
+                editable: None,
                 extras: vec![]
             }
         );
@@ -233,6 +235,7 @@ mod tests {
                     )),
                     subdirectory: None,
                 },
+                editable: None,
                 extras: vec![]
             }
         );
@@ -287,6 +290,7 @@ mod tests {
                     subdirectory: None
                 },
 
+                editable: None,
                 extras: vec![]
             }
         );
@@ -304,6 +308,7 @@ mod tests {
                     rev: Some(GitReference::DefaultBranch),
                     subdirectory: Some("python/ribasim".to_string()),
                 },
+                editable: None,
                 extras: vec![],
             }
         );
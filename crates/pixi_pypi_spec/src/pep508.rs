@@ -30,6 +30,7 @@ impl TryFrom<pep508_rs::Requirement> for PixiPypiSpec {
 
                                 Self::Git {
                                     url: git_spec,
+                                    editable: None,
                                     extras: req.extras,
                                 }
                             }
@@ -75,6 +76,7 @@ impl TryFrom<pep508_rs::Requirement> for PixiPypiSpec {
                         };
                         Self::Git {
                             url: git_spec,
+                            editable: None,
                             extras: req.extras,
                         }
                     } else if url.scheme().eq_ignore_ascii_case("file") {
@@ -118,14 +120,22 @@ impl TryFrom<(pep508_rs::Requirement, Option<PixiPypiSpec>)> for PixiPypiSpec {
         let mut converted = req.try_into()?;
 
         if let Some(pixi_req) = pixi_req {
-            if let (
-                PixiPypiSpec::Version {
-                    index: conv_index, ..
-                },
-                PixiPypiSpec::Version { index, .. },
-            ) = (&mut converted, &pixi_req)
-            {
-                *conv_index = index.clone()
+            match (&mut converted, &pixi_req) {
+                (
+                    PixiPypiSpec::Version {
+                        index: conv_index, ..
+                    },
+                    PixiPypiSpec::Version { index, .. },
+                ) => *conv_index = index.clone(),
+                // Prefer the path as it was given (e.g. relative to the manifest) over the
+                // absolute path derived from the file url.
+                (
+                    PixiPypiSpec::Path {
+                        path: conv_path, ..
+                    },
+                    PixiPypiSpec::Path { path, .. },
+                ) => *conv_path = path.clone(),
+                _ => {}
             }
         }
 
@@ -137,6 +137,7 @@ impl RawPyPiRequirement {
                         rev,
                         subdirectory: self.subdirectory,
                     },
+                    editable: self.editable,
                     extras,
                 }
             }
@@ -297,6 +298,7 @@ impl From<PixiPypiSpec> for toml_edit::Value {
                         rev,
                         subdirectory,
                     },
+                editable,
                 extras,
             } => {
                 let mut table = toml_edit::Table::new().into_inline_table();
@@ -337,6 +339,12 @@ impl From<PixiPypiSpec> for toml_edit::Value {
                         )),
                     );
                 }
+                if editable == &Some(true) {
+                    table.insert(
+                        "editable",
+                        toml_edit::Value::Boolean(toml_edit::Formatted::new(true)),
+                    );
+                }
                 insert_extras(&mut table, extras);
                 toml_edit::Value::InlineTable(table.to_owned())
             }
@@ -579,6 +587,34 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_deserialize_pypi_from_git_editable() {
+        let requirement = from_toml_str::<TomlIndexMap<pep508_rs::PackageName, PixiPypiSpec>>(
+            r#"foo = { git = "https://github.com/prefix-dev/rattler", branch = "main", editable = true }"#,
+        )
+        .unwrap()
+        .into_inner();
+        let spec = requirement.first().unwrap().1;
+        assert_eq!(
+            spec,
+            &PixiPypiSpec::Git {
+                url: GitSpec {
+                    git: Url::parse("https://github.com/prefix-dev/rattler").unwrap(),
+                    rev: Some(GitReference::Branch("main".to_string())),
+                    subdirectory: None,
+                },
+                editable: Some(true),
+                extras: vec![],
+            }
+        );
+
+        // The editable flag survives a round trip through toml
+        assert_eq!(
+            toml_edit::Value::from(spec.clone()).to_string(),
+            r#"{ git = "https://github.com/prefix-dev/rattler", branch = "main", editable = true }"#
+        );
+    }
+
     #[test]
     fn test_deserialize_fail_on_unknown() {
         let input = r#"foo = { borked = "bork"}"#;
@@ -625,6 +661,7 @@ mod test {
                     rev: None,
                     subdirectory: None,
                 },
+                editable: None,
                 extras: vec![],
             }
         );
@@ -645,6 +682,7 @@ mod test {
                     rev: Some(GitReference::Branch("main".to_string())),
                     subdirectory: None,
                 },
+                editable: None,
                 extras: vec![],
             }
         );
@@ -665,6 +703,7 @@ mod test {
                     rev: Some(GitReference::Tag("v.1.2.3".to_string())),
                     subdirectory: None,
                 },
+                editable: None,
                 extras: vec![],
             }
         );
@@ -685,6 +724,7 @@ mod test {
                     rev: Some(GitReference::Tag("3.0.0".to_string())),
                     subdirectory: None,
                 },
+                editable: None,
                 extras: vec![],
             },
         );
@@ -705,6 +745,7 @@ mod test {
                     rev: Some(GitReference::Rev("123456".to_string())),
                     subdirectory: None,
                 },
+                editable: None,
                 extras: vec![],
             }
         );
@@ -8,6 +8,8 @@ use std::path::PathBuf;
 use std::{io::BufRead, path::Path, str::FromStr};
 use thiserror::Error;
 
+use crate::conda_lock_file::CondaLockFile;
+
 #[derive(Debug, Error)]
 #[error("Failed to parse '{path}' as a conda environment file")]
 struct YamlParseError {
@@ -50,6 +52,36 @@ impl YamlParseError {
     }
 }
 
+/// A file that can be imported into a workspace, either a conda environment
+/// file or a conda-lock file.
+#[derive(Debug, Clone)]
+pub enum EnvironmentFile {
+    Conda(CondaEnvFile),
+    CondaLock(CondaLockFile),
+}
+
+impl EnvironmentFile {
+    /// Reads the file and determines its format from its structure.
+    pub fn from_path(path: &Path) -> miette::Result<Self> {
+        let contents = fs_err::read_to_string(path).into_diagnostic()?;
+        match serde_yaml::from_str::<serde_yaml::Value>(&contents) {
+            Ok(serde_yaml::Value::Mapping(document)) if CondaLockFile::is_conda_lock(&document) => {
+                Ok(Self::CondaLock(CondaLockFile::from_path(path)?))
+            }
+            Ok(serde_yaml::Value::Mapping(document)) if document.contains_key("dependencies") => {
+                Ok(Self::Conda(CondaEnvFile::from_path(path)?))
+            }
+            // Report the syntax error of the file
+            Err(_) => Ok(Self::Conda(CondaEnvFile::from_path(path)?)),
+            Ok(_) => miette::bail!(
+                help = "expected a conda environment file with a list of 'dependencies' or a conda-lock file",
+                "unrecognized environment file '{}'",
+                path.display()
+            ),
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct CondaEnvFile {
     #[serde(default)]
@@ -59,8 +91,14 @@ pub struct CondaEnvFile {
     dependencies: Vec<CondaEnvDep>,
     #[serde(default)]
     variables: HashMap<String, String>,
+    /// Keys of the file that are not imported, e.g. `prefix`.
+    #[serde(skip)]
+    unsupported_keys: Vec<String>,
 }
 
+/// The keys of a conda environment file that are imported.
+const SUPPORTED_KEYS: [&str; 4] = ["name", "channels", "dependencies", "variables"];
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum CondaEnvDep {
@@ -91,6 +129,11 @@ impl CondaEnvFile {
         self.variables.clone()
     }
 
+    /// Returns the keys of the file that are not imported.
+    pub fn unsupported_keys(&self) -> impl Iterator<Item = &str> + '_ {
+        self.unsupported_keys.iter().map(String::as_str)
+    }
+
     pub fn from_path(path: &Path) -> miette::Result<Self> {
         let file = fs_err::File::open(path).into_diagnostic()?;
         let reader = std::io::BufReader::new(file);
@@ -109,7 +152,7 @@ impl CondaEnvFile {
             s.push_str(&line);
             s.push('\n');
         }
-        let env_file: CondaEnvFile = match serde_yaml::from_str(&s) {
+        let mut env_file: CondaEnvFile = match serde_yaml::from_str(&s) {
             Ok(env_file) => env_file,
             Err(e) => {
                 let src = NamedSource::new(path.display().to_string(), s.to_string());
@@ -117,6 +160,16 @@ impl CondaEnvFile {
                 return Err(miette::Report::new(error));
             }
         };
+        env_file.unsupported_keys = serde_yaml::from_str::<serde_yaml::Mapping>(&s)
+            .map(|mapping| {
+                mapping
+                    .keys()
+                    .filter_map(serde_yaml::Value::as_str)
+                    .filter(|key| !SUPPORTED_KEYS.contains(key))
+                    .map(ToString::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
         Ok(env_file)
     }
 
@@ -127,16 +180,31 @@ impl CondaEnvFile {
         Vec<MatchSpec>,
         Vec<pep508_rs::Requirement>,
         Vec<NamedChannelOrUrl>,
+    )> {
+        let (conda_deps, pip_deps, mut channels) = self.to_dependencies_and_channels()?;
+        if channels.is_empty() {
+            channels = config.default_channels();
+        }
+
+        Ok((conda_deps, pip_deps, channels))
+    }
+
+    /// Returns the conda and pypi dependencies of the file together with its
+    /// channels, including the channels of `channel::package` dependencies.
+    /// Unlike [`Self::to_manifest`] no default channels are added.
+    pub fn to_dependencies_and_channels(
+        &self,
+    ) -> miette::Result<(
+        Vec<MatchSpec>,
+        Vec<pep508_rs::Requirement>,
+        Vec<NamedChannelOrUrl>,
     )> {
         let mut channels = parse_channels(self.channels().clone());
         let (conda_deps, pip_deps, extra_channels) =
             parse_dependencies(self.dependencies().clone())?;
 
         channels.extend(extra_channels);
-        let mut channels: Vec<_> = channels.into_iter().unique().collect();
-        if channels.is_empty() {
-            channels = config.default_channels();
-        }
+        let channels = channels.into_iter().unique().collect();
 
         Ok((conda_deps, pip_deps, channels))
     }
@@ -347,6 +415,36 @@ mod tests {
         assert_eq!(vars, empty_map);
     }
 
+    #[test]
+    fn test_parse_conda_env_file_with_unsupported_keys() {
+        let example_conda_env_file = r#"
+        name: pixi_example_project
+        channels:
+          - conda-forge
+        dependencies:
+          - python=3.11
+        prefix: /opt/conda/envs/pixi_example_project
+        "#;
+
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        f.write_all(example_conda_env_file.as_bytes()).unwrap();
+        let (_file, path) = f.into_parts();
+
+        let conda_env_file_data = CondaEnvFile::from_path(&path).unwrap();
+        assert_eq!(
+            conda_env_file_data.unsupported_keys().collect::<Vec<_>>(),
+            vec!["prefix"]
+        );
+
+        let (conda_deps, _, channels) = conda_env_file_data.to_dependencies_and_channels().unwrap();
+        // `=3.11` is the conda shorthand for `3.11.*`
+        assert_eq!(conda_deps[0].to_string(), "python 3.11.*");
+        assert_eq!(
+            channels,
+            vec![NamedChannelOrUrl::from_str("conda-forge").unwrap()]
+        );
+    }
+
     #[test]
     fn test_parse_conda_env_file_with_variables() {
         let example_conda_env_file = r#"
@@ -0,0 +1,295 @@
+//! Reads and writes the lock-files of [conda-lock](https://github.com/conda/conda-lock)
+//! in their unified (version 1) format, e.g. `conda-lock.yml`.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+    str::FromStr,
+};
+
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use rattler_conda_types::{NamedChannelOrUrl, Platform};
+use serde::{Deserialize, Serialize};
+
+/// A conda-lock file.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CondaLockFile {
+    pub version: u32,
+    pub metadata: CondaLockMetadata,
+    pub package: Vec<CondaLockPackage>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct CondaLockMetadata {
+    /// A hash of the inputs of each platform, used by conda-lock to find out
+    /// if a platform has to be locked again.
+    #[serde(default)]
+    pub content_hash: BTreeMap<String, String>,
+    #[serde(default)]
+    pub channels: Vec<CondaLockChannel>,
+    #[serde(default)]
+    pub platforms: Vec<Platform>,
+    /// The files the lock-file was created from.
+    #[serde(default)]
+    pub sources: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CondaLockChannel {
+    pub url: String,
+    #[serde(default)]
+    pub used_env_vars: Vec<String>,
+}
+
+/// A locked package of a single platform.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CondaLockPackage {
+    pub name: String,
+    pub version: String,
+    pub manager: CondaLockManager,
+    pub platform: Platform,
+    /// The names of the dependencies of the package and their specs.
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    pub url: String,
+    #[serde(default)]
+    pub hash: CondaLockHash,
+    #[serde(default = "default_category")]
+    pub category: String,
+    #[serde(default)]
+    pub optional: bool,
+}
+
+fn default_category() -> String {
+    String::from("main")
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CondaLockManager {
+    Conda,
+    Pip,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct CondaLockHash {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl FromStr for CondaLockFile {
+    type Err = miette::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lock_file: Self = serde_yaml::from_str(s).into_diagnostic()?;
+        if lock_file.version != 1 {
+            miette::bail!(
+                "only version 1 of the conda-lock format is supported, found version {}",
+                lock_file.version
+            );
+        }
+        Ok(lock_file)
+    }
+}
+
+impl CondaLockFile {
+    pub fn from_path(path: &Path) -> miette::Result<Self> {
+        fs_err::read_to_string(path)
+            .into_diagnostic()?
+            .parse()
+            .wrap_err_with(|| format!("failed to parse '{}' as a conda-lock file", path.display()))
+    }
+
+    /// Writes the lock-file as yaml.
+    pub fn to_yaml_string(&self) -> miette::Result<String> {
+        serde_yaml::to_string(self).into_diagnostic()
+    }
+
+    /// Returns `true` if the yaml document has the structure of a conda-lock
+    /// file, a `version` and a list of packages.
+    pub fn is_conda_lock(document: &serde_yaml::Mapping) -> bool {
+        document.contains_key("version")
+            && document
+                .get("package")
+                .is_some_and(serde_yaml::Value::is_sequence)
+    }
+
+    /// Returns the platforms of the lock-file.
+    pub fn platforms(&self) -> Vec<Platform> {
+        if !self.metadata.platforms.is_empty() {
+            return self.metadata.platforms.clone();
+        }
+        self.package
+            .iter()
+            .map(|package| package.platform)
+            .unique()
+            .collect()
+    }
+
+    /// Returns the channels the packages were locked from.
+    pub fn channels(&self) -> miette::Result<Vec<NamedChannelOrUrl>> {
+        self.metadata
+            .channels
+            .iter()
+            .map(|channel| {
+                NamedChannelOrUrl::from_str(&channel.url)
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("can't parse '{}' as a channel", channel.url))
+            })
+            .collect()
+    }
+
+    /// Returns the packages of the platform that were installed by the given
+    /// manager.
+    pub fn packages(
+        &self,
+        platform: Platform,
+        manager: CondaLockManager,
+    ) -> impl Iterator<Item = &CondaLockPackage> + '_ {
+        self.package
+            .iter()
+            .filter(move |package| package.platform == platform && package.manager == manager)
+    }
+
+    /// Returns the packages of the platform that no other package of the same
+    /// manager depends on, these are the packages that were requested.
+    pub fn requested_packages(
+        &self,
+        platform: Platform,
+        manager: CondaLockManager,
+    ) -> Vec<&CondaLockPackage> {
+        let normalize = |name: &str| name.to_lowercase().replace('_', "-");
+        let dependencies: HashSet<String> = self
+            .packages(platform, manager)
+            .flat_map(|package| package.dependencies.keys())
+            .map(|name| normalize(name))
+            .collect();
+        self.packages(platform, manager)
+            .filter(|package| !dependencies.contains(&normalize(&package.name)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONDA_LOCK: &str = r#"
+version: 1
+metadata:
+  content_hash:
+    linux-64: abc
+  channels:
+  - url: conda-forge
+    used_env_vars: []
+  platforms:
+  - linux-64
+  - osx-arm64
+  sources:
+  - environment.yml
+package:
+- name: python
+  version: 3.12.1
+  manager: conda
+  platform: linux-64
+  dependencies:
+    libzlib: '>=1.2.13'
+  url: https://conda.anaconda.org/conda-forge/linux-64/python-3.12.1-hab00c5b_1_cpython.conda
+  hash:
+    md5: 0bab699354cbd66959550eb9b9866620
+    sha256: 7f7c9ad0a1ae1a6ad3b8e6ed8d46fcbc5d7e2bc3b0d3c4ab7c2e1d8e0a2b1c3d
+  category: main
+  optional: false
+- name: libzlib
+  version: 1.3.1
+  manager: conda
+  platform: linux-64
+  dependencies: {}
+  url: https://conda.anaconda.org/conda-forge/linux-64/libzlib-1.3.1-h4ab18f5_1.conda
+  hash:
+    md5: 57d7dc60e9325e3de37ff8dffd18e814
+  category: main
+  optional: false
+- name: requests
+  version: 2.31.0
+  manager: pip
+  platform: linux-64
+  dependencies:
+    certifi: '>=2017.4.17'
+  url: https://files.pythonhosted.org/packages/requests-2.31.0-py3-none-any.whl
+  hash:
+    sha256: 58cd2187c01e70e6e26505bca751777aa9f2ee0b7f4300988b709f44e013003f
+  category: main
+  optional: false
+- name: certifi
+  version: 2024.2.2
+  manager: pip
+  platform: linux-64
+  dependencies: {}
+  url: https://files.pythonhosted.org/packages/certifi-2024.2.2-py3-none-any.whl
+  hash:
+    sha256: dc383c07b76109f368f6106eee2b593b04a011ea4d55f652c6ca24a754d1cdd1
+  category: main
+  optional: false
+"#;
+
+    #[test]
+    fn test_parse_conda_lock() {
+        let document: serde_yaml::Mapping = serde_yaml::from_str(CONDA_LOCK).unwrap();
+        assert!(CondaLockFile::is_conda_lock(&document));
+
+        let lock_file = CONDA_LOCK.parse::<CondaLockFile>().unwrap();
+        assert_eq!(
+            lock_file.platforms(),
+            vec![Platform::Linux64, Platform::OsxArm64]
+        );
+        assert_eq!(
+            lock_file.channels().unwrap(),
+            vec![NamedChannelOrUrl::Name("conda-forge".to_string())]
+        );
+
+        let names = |manager| {
+            lock_file
+                .requested_packages(Platform::Linux64, manager)
+                .into_iter()
+                .map(|package| package.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(CondaLockManager::Conda), vec!["python"]);
+        assert_eq!(names(CondaLockManager::Pip), vec!["requests"]);
+        assert!(
+            lock_file
+                .requested_packages(Platform::OsxArm64, CondaLockManager::Conda)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_conda_lock_round_trip() {
+        let lock_file = CONDA_LOCK.parse::<CondaLockFile>().unwrap();
+        let written = lock_file.to_yaml_string().unwrap();
+        let read = written.parse::<CondaLockFile>().unwrap();
+
+        assert_eq!(read.platforms(), lock_file.platforms());
+        assert_eq!(read.metadata.sources, vec!["environment.yml"]);
+        assert_eq!(read.package.len(), lock_file.package.len());
+        let python = &read.package[0];
+        assert_eq!(python.category, "main");
+        assert_eq!(python.dependencies["libzlib"], ">=1.2.13");
+        assert_eq!(
+            python.hash.md5.as_deref(),
+            Some("0bab699354cbd66959550eb9b9866620")
+        );
+        assert_eq!(read.package[1].hash.sha256, None);
+    }
+
+    #[test]
+    fn test_environment_file_is_not_a_conda_lock() {
+        let document: serde_yaml::Mapping =
+            serde_yaml::from_str("name: test\ndependencies:\n  - python\n").unwrap();
+        assert!(!CondaLockFile::is_conda_lock(&document));
+    }
+}
@@ -0,0 +1,186 @@
+//! Reads the state of an existing conda environment that was not created by
+//! pixi, e.g. to create a manifest from it.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use miette::IntoDiagnostic;
+use pixi_consts::consts;
+use rattler_conda_types::{MatchSpec, PackageName, ParseStrictness};
+
+/// A python package that was installed into an environment by another
+/// installer than conda, e.g. pip.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PipPackage {
+    pub name: String,
+    pub version: String,
+}
+
+/// Returns the names of the packages that were explicitly requested in the
+/// environment according to its `conda-meta/history` file. Returns `None` if
+/// the environment has no history.
+pub fn requested_packages(prefix: &Path) -> miette::Result<Option<Vec<PackageName>>> {
+    let path = prefix.join(consts::CONDA_META_DIR).join("history");
+    let contents = match fs_err::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).into_diagnostic(),
+    };
+    let requested = parse_history(&contents);
+    Ok((!requested.is_empty()).then_some(requested))
+}
+
+/// Replays the `# update specs` and `# remove specs` lines of a history file.
+fn parse_history(contents: &str) -> Vec<PackageName> {
+    let mut requested: Vec<PackageName> = Vec::new();
+    for line in contents.lines() {
+        if let Some(specs) = line.strip_prefix("# update specs:") {
+            for name in spec_names(specs) {
+                if !requested.contains(&name) {
+                    requested.push(name);
+                }
+            }
+        } else if let Some(specs) = line.strip_prefix("# remove specs:") {
+            let removed = spec_names(specs);
+            requested.retain(|name| !removed.contains(name));
+        }
+    }
+    requested
+}
+
+/// Returns the package names of a python list of specs, e.g.
+/// `['numpy>=1,<2', 'conda-forge::python=3.11']`.
+fn spec_names(specs: &str) -> Vec<PackageName> {
+    let mut names = Vec::new();
+    let mut rest = specs;
+    while let Some(start) = rest.find(['\'', '"']) {
+        let quote = rest[start..].chars().next().expect("found a quote");
+        let Some(len) = rest[start + 1..].find(quote) else {
+            break;
+        };
+        let spec = &rest[start + 1..start + 1 + len];
+        match MatchSpec::from_str(spec, ParseStrictness::Lenient) {
+            Ok(MatchSpec {
+                name: Some(name), ..
+            }) => names.push(name),
+            _ => tracing::debug!("ignoring the spec '{spec}' of the history"),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    names
+}
+
+/// Returns the python packages in the site-packages of the environment that
+/// were not installed by conda.
+pub fn pip_installed_packages(prefix: &Path) -> miette::Result<Vec<PipPackage>> {
+    let mut packages = Vec::new();
+    for site_packages in site_packages_dirs(prefix) {
+        for entry in fs_err::read_dir(&site_packages).into_diagnostic()? {
+            let path = entry.into_diagnostic()?.path();
+            if path
+                .extension()
+                .is_none_or(|extension| extension != "dist-info")
+            {
+                continue;
+            }
+            // Every installer records itself, conda packages are already part of
+            // the conda-meta records
+            let Ok(installer) = fs_err::read_to_string(path.join("INSTALLER")) else {
+                continue;
+            };
+            if installer.trim() == "conda" {
+                continue;
+            }
+            let Ok(metadata) = fs_err::read_to_string(path.join("METADATA")) else {
+                continue;
+            };
+            let field = |field: &str| {
+                metadata
+                    .lines()
+                    .take_while(|line| !line.is_empty())
+                    .find_map(|line| line.strip_prefix(field))
+                    .map(|value| value.trim().to_string())
+            };
+            if let (Some(name), Some(version)) = (field("Name:"), field("Version:")) {
+                packages.push(PipPackage { name, version });
+            }
+        }
+    }
+    packages.sort();
+    Ok(packages)
+}
+
+/// Returns the site-packages directories of the python installations in the
+/// environment, `lib/python3.x/site-packages` on unix and `Lib/site-packages`
+/// on Windows.
+fn site_packages_dirs(prefix: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![prefix.join("Lib").join("site-packages")];
+    if let Ok(entries) = fs_err::read_dir(prefix.join("lib")) {
+        dirs.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("python"))
+                })
+                .map(|path| path.join("site-packages")),
+        );
+    }
+    dirs.into_iter().filter(|dir| dir.is_dir()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_history() {
+        let history = r#"==> 2024-01-01 00:00:00 <==
+# cmd: conda create -n test python=3.11 numpy
+# conda version: 24.1.0
++conda-forge/linux-64::python-3.11.7-hab00c5b_1_cpython
+# update specs: ['python=3.11', 'numpy']
+==> 2024-01-02 00:00:00 <==
+# update specs: ["bioconda::samtools", "pandas>=2,<3"]
+==> 2024-01-03 00:00:00 <==
+# remove specs: ['numpy']
+"#;
+        assert_eq!(
+            parse_history(history)
+                .iter()
+                .map(PackageName::as_normalized)
+                .collect::<Vec<_>>(),
+            vec!["python", "samtools", "pandas"]
+        );
+    }
+
+    #[test]
+    fn test_pip_installed_packages() {
+        let prefix = tempfile::tempdir().unwrap();
+        let site_packages = prefix.path().join("lib/python3.12/site-packages");
+        for (name, version, installer) in
+            [("requests", "2.31.0", "pip"), ("numpy", "2.0.0", "conda")]
+        {
+            let dist_info = site_packages.join(format!("{name}-{version}.dist-info"));
+            fs_err::create_dir_all(&dist_info).unwrap();
+            fs_err::write(dist_info.join("INSTALLER"), format!("{installer}\n")).unwrap();
+            fs_err::write(
+                dist_info.join("METADATA"),
+                format!("Metadata-Version: 2.1\nName: {name}\nVersion: {version}\n\nName: other"),
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            pip_installed_packages(prefix.path()).unwrap(),
+            vec![PipPackage {
+                name: "requests".to_string(),
+                version: "2.31.0".to_string()
+            }]
+        );
+    }
+}
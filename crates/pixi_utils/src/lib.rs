@@ -1,8 +1,11 @@
 pub mod cache;
 pub mod conda_environment_file;
+pub mod conda_lock_file;
+pub mod conda_prefix;
 pub mod indicatif;
 mod prefix_guard;
 pub mod reqwest;
+pub mod requirements_file;
 
 mod executable_utils;
 pub use executable_utils::{
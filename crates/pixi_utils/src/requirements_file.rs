@@ -0,0 +1,318 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use miette::{Context, IntoDiagnostic};
+use rattler_conda_types::Platform;
+
+/// An entry of a pip requirements file, e.g. `requirements.txt`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequirementsFileEntry {
+    /// A named requirement, e.g. `requests[socks]>=2 ; python_version >= '3.9'`.
+    Named {
+        requirement: pep508_rs::Requirement,
+        /// A marker that only selects an operating system. It is not part of
+        /// the requirement so that it can be turned into a target instead.
+        platform_marker: Option<PlatformMarker>,
+    },
+    /// A requirement on a local directory, e.g. `-e ./libs/mypkg[dev]`.
+    Path {
+        path: PathBuf,
+        extras: Vec<String>,
+        editable: bool,
+    },
+}
+
+/// A marker of a requirement that only selects an operating system, e.g.
+/// `sys_platform == 'win32'`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlatformMarker {
+    /// The marker as it is written in the file.
+    pub marker: String,
+    os: Os,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Os {
+    Linux,
+    Windows,
+    MacOs,
+}
+
+impl PlatformMarker {
+    fn parse(marker: &str) -> Option<Self> {
+        let (variable, value) = marker.split_once("==")?;
+        let value = value
+            .trim()
+            .strip_prefix(['"', '\''])?
+            .strip_suffix(['"', '\''])?;
+        let os = match (variable.trim(), value) {
+            ("sys_platform", "linux") | ("platform_system", "Linux") => Os::Linux,
+            ("sys_platform", "win32") | ("platform_system", "Windows") => Os::Windows,
+            ("sys_platform", "darwin") | ("platform_system", "Darwin") => Os::MacOs,
+            _ => return None,
+        };
+        Some(Self {
+            marker: marker.trim().to_string(),
+            os,
+        })
+    }
+
+    /// Returns `true` if the marker selects the platform.
+    pub fn matches(&self, platform: Platform) -> bool {
+        match self.os {
+            Os::Linux => platform.is_linux(),
+            Os::Windows => platform.is_windows(),
+            Os::MacOs => platform.is_osx(),
+        }
+    }
+}
+
+/// Parses a pip requirements file and the files it includes with `-r`.
+/// Relative paths are resolved against the directory of the file that
+/// contains them.
+///
+/// Options that can't be imported, e.g. `--index-url` or `-c`, are skipped
+/// with a warning.
+pub fn parse_requirements_file(path: &Path) -> miette::Result<Vec<RequirementsFileEntry>> {
+    let mut entries = Vec::new();
+    parse_into(path, &mut Vec::new(), &mut entries)?;
+    Ok(entries)
+}
+
+fn parse_into(
+    path: &Path,
+    included_from: &mut Vec<PathBuf>,
+    entries: &mut Vec<RequirementsFileEntry>,
+) -> miette::Result<()> {
+    let canonical = fs_err::canonicalize(path).into_diagnostic()?;
+    if included_from.contains(&canonical) {
+        miette::bail!("'{}' is included recursively", path.display());
+    }
+    let contents = fs_err::read_to_string(path).into_diagnostic()?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    included_from.push(canonical);
+    for (line_number, line) in logical_lines(&contents) {
+        let location = || format!("{}:{line_number}", path.display());
+        let Some((option, value)) = split_option(&line) else {
+            entries.push(parse_requirement(&line, dir, false).wrap_err_with(location)?);
+            continue;
+        };
+        match option {
+            "-r" | "--requirement" if !value.is_empty() => {
+                parse_into(&dir.join(value), included_from, entries).wrap_err_with(location)?
+            }
+            "-e" | "--editable" if !value.is_empty() => {
+                entries.push(parse_requirement(value, dir, true).wrap_err_with(location)?)
+            }
+            "-r" | "--requirement" | "-e" | "--editable" => {
+                miette::bail!("{}: '{option}' requires a value", location())
+            }
+            _ => tracing::warn!(
+                "ignoring the unsupported option '{option}' in {}",
+                location()
+            ),
+        }
+    }
+    included_from.pop();
+    Ok(())
+}
+
+/// Returns the non-empty lines of the file without comments, together with
+/// their line number. Lines ending with a `\` are joined with the next line.
+fn logical_lines(contents: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut continued: Option<(usize, String)> = None;
+    for (idx, line) in contents.lines().enumerate() {
+        let line = strip_comment(line).trim_end();
+        let (line_number, mut text) = continued.take().unwrap_or((idx + 1, String::new()));
+        match line.strip_suffix('\\') {
+            Some(line) => {
+                text.push_str(line);
+                continued = Some((line_number, text));
+            }
+            None => {
+                text.push_str(line);
+                lines.push((line_number, text));
+            }
+        }
+    }
+    lines.extend(continued);
+    lines
+        .into_iter()
+        .map(|(line_number, text)| (line_number, text.trim().to_string()))
+        .filter(|(_, text)| !text.is_empty())
+        .collect()
+}
+
+fn strip_comment(line: &str) -> &str {
+    if line.trim_start().starts_with('#') {
+        return "";
+    }
+    match line.find(" #").or_else(|| line.find("\t#")) {
+        Some(idx) => &line[..idx],
+        None => line,
+    }
+}
+
+/// Splits a line that starts with an option into the option and its value,
+/// e.g. `-r other.txt` or `--requirement=other.txt`.
+fn split_option(line: &str) -> Option<(&str, &str)> {
+    if !line.starts_with('-') {
+        return None;
+    }
+    match line.find(['=', ' ', '\t']) {
+        Some(idx) => Some((&line[..idx], line[idx + 1..].trim())),
+        None => Some((line, "")),
+    }
+}
+
+fn parse_requirement(
+    text: &str,
+    dir: &Path,
+    editable: bool,
+) -> miette::Result<RequirementsFileEntry> {
+    // The options of a single requirement, e.g. `--hash`, can't be imported
+    let text = match text.find(" --") {
+        Some(idx) => {
+            tracing::warn!(
+                "ignoring the options '{}' of '{}'",
+                text[idx..].trim(),
+                text[..idx].trim()
+            );
+            text[..idx].trim()
+        }
+        None => text,
+    };
+
+    if is_path(text) {
+        let (path, extras) = match text.split_once('[') {
+            Some((path, extras)) => (
+                path,
+                extras
+                    .trim_end_matches(']')
+                    .split(',')
+                    .map(|extra| extra.trim().to_string())
+                    .filter(|extra| !extra.is_empty())
+                    .collect(),
+            ),
+            None => (text, Vec::new()),
+        };
+        let path = path.strip_prefix("file://").unwrap_or(path);
+        return Ok(RequirementsFileEntry::Path {
+            path: dir.join(path),
+            extras,
+            editable,
+        });
+    }
+
+    if editable {
+        tracing::warn!(
+            "only local directories can be installed in editable mode, '{text}' is imported as a regular requirement"
+        );
+    }
+
+    // Urls name the package with an `#egg=<name>` fragment
+    let text = match text.split_once("#egg=") {
+        Some((url, name)) if !text.contains(" @ ") => {
+            let name = name.split('&').next().unwrap_or(name);
+            format!("{name} @ {url}")
+        }
+        _ => text.to_string(),
+    };
+
+    let (requirement, platform_marker) = match text.split_once(';') {
+        Some((requirement, marker)) => match PlatformMarker::parse(marker) {
+            Some(marker) => (requirement.trim(), Some(marker)),
+            None => (text.as_str(), None),
+        },
+        None => (text.as_str(), None),
+    };
+    let requirement = pep508_rs::Requirement::from_str(requirement)
+        .into_diagnostic()
+        .wrap_err(format!("can't parse '{text}' as a requirement"))?;
+    Ok(RequirementsFileEntry::Named {
+        requirement,
+        platform_marker,
+    })
+}
+
+/// Returns `true` if the requirement refers to a local path instead of a
+/// package name.
+fn is_path(text: &str) -> bool {
+    text.starts_with(['.', '/'])
+        || text.starts_with("file:")
+        || Path::new(text.split('[').next().unwrap_or(text)).is_absolute()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requirements_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs_err::write(
+            dir.path().join("requirements.txt"),
+            r#"
+# The main requirements
+requests[socks]>=2.31 ; python_version >= "3.9"
+pywin32 ; sys_platform == 'win32'
+numpy==2.1.0 \
+    --hash=sha256:abcdef
+--index-url https://pypi.org/simple
+-e ./libs/mypkg[dev, test]  # Our own package
+-r dev-requirements.txt
+"#,
+        )
+        .unwrap();
+        fs_err::write(
+            dir.path().join("dev-requirements.txt"),
+            "pytest\n-r requirements.txt\n",
+        )
+        .unwrap();
+
+        // Including a file that is already being parsed is an error
+        let err = parse_requirements_file(&dir.path().join("requirements.txt")).unwrap_err();
+        assert!(
+            format!("{err:?}").contains("included recursively"),
+            "{err:?}"
+        );
+
+        fs_err::write(dir.path().join("dev-requirements.txt"), "pytest\n").unwrap();
+        let entries = parse_requirements_file(&dir.path().join("requirements.txt")).unwrap();
+        let named = |requirement: &str, marker: Option<&str>| RequirementsFileEntry::Named {
+            requirement: pep508_rs::Requirement::from_str(requirement).unwrap(),
+            platform_marker: marker.and_then(PlatformMarker::parse),
+        };
+        assert_eq!(
+            entries,
+            vec![
+                named(r#"requests[socks]>=2.31 ; python_version >= "3.9""#, None),
+                named("pywin32", Some("sys_platform == 'win32'")),
+                named("numpy==2.1.0", None),
+                RequirementsFileEntry::Path {
+                    path: dir.path().join("./libs/mypkg"),
+                    extras: vec!["dev".to_string(), "test".to_string()],
+                    editable: true,
+                },
+                named("pytest", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_platform_marker() {
+        let marker = PlatformMarker::parse(r#" platform_system == "Darwin""#).unwrap();
+        assert!(marker.matches(Platform::OsxArm64));
+        assert!(!marker.matches(Platform::Linux64));
+
+        // Only markers that select nothing but an operating system are supported
+        assert!(
+            PlatformMarker::parse("sys_platform == 'linux' and python_version < '3.10'").is_none()
+        );
+        assert!(PlatformMarker::parse("sys_platform != 'linux'").is_none());
+    }
+}
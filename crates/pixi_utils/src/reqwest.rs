@@ -1,5 +1,14 @@
-use std::{any::Any, path::PathBuf, sync::Arc, sync::LazyLock, time::Duration};
+use std::{
+    any::Any,
+    path::PathBuf,
+    sync::Arc,
+    sync::LazyLock,
+    time::{Duration, SystemTime},
+};
 
+use bytes::Bytes;
+use futures::{StreamExt, stream::BoxStream};
+use http::Extensions;
 use miette::IntoDiagnostic;
 use pixi_consts::consts;
 use rattler_networking::{
@@ -10,18 +19,298 @@ use rattler_networking::{
     retry_policies::ExponentialBackoff,
 };
 
-use reqwest::Client;
-use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware};
-use reqwest_retry::RetryTransientMiddleware;
+use reqwest::{
+    Client, Method, Request, Response, StatusCode,
+    header::{
+        ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_RANGE, ETAG, HeaderValue, IF_RANGE, LAST_MODIFIED,
+        RANGE,
+    },
+};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware, Next};
+use reqwest_retry::{
+    Jitter, RetryDecision, RetryPolicy, Retryable, default_on_request_failure,
+    default_on_request_success,
+};
 use std::collections::HashMap;
 use tracing::debug;
 
-use pixi_config::Config;
+use pixi_config::{Config, NetworkConfig};
+
+/// The upper bound of the delay between two retries.
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(30);
 
 /// The default retry policy employed by pixi.
-/// TODO: At some point we might want to make this configurable.
 pub fn default_retry_policy() -> ExponentialBackoff {
-    ExponentialBackoff::builder().build_with_max_retries(3)
+    retry_policy(&NetworkConfig::default())
+}
+
+/// The retry policy described by the `[network]` configuration, the delay
+/// between retries grows exponentially and is jittered to avoid retrying in
+/// lockstep with other requests.
+pub fn retry_policy(network: &NetworkConfig) -> ExponentialBackoff {
+    let min_interval = network.retry_backoff();
+    ExponentialBackoff::builder()
+        .retry_bounds(min_interval, MAX_RETRY_INTERVAL.max(min_interval))
+        .jitter(Jitter::Bounded)
+        .build_with_max_retries(network.max_retries())
+}
+
+/// Retries requests that failed with a transient error, like a dropped
+/// connection, a timeout or a 5xx response. Permanent errors, e.g. a 404, are
+/// returned immediately.
+///
+/// A download that is cut off is resumed with a range request when the server
+/// supports them, the bytes that were already received are not fetched again.
+///
+/// Every retry is reported as a warning, which is printed above the progress
+/// bars.
+pub struct RetryMiddleware {
+    policy: ExponentialBackoff,
+    max_retries: u32,
+    client: Client,
+}
+
+impl RetryMiddleware {
+    /// The `client` is used to resume downloads, it must be the client that
+    /// the middleware stack ends with.
+    pub fn new(network: &NetworkConfig, client: Client) -> Self {
+        Self {
+            policy: retry_policy(network),
+            max_retries: network.max_retries(),
+            client,
+        }
+    }
+
+    /// Wraps the body of the response, so it is resumed from the received
+    /// bytes if the connection drops. Only complete responses of servers that
+    /// accept range requests and identify the content with a strong validator
+    /// are resumed, anything else is returned as is.
+    fn resumable(&self, request: &Request, response: Response) -> Response {
+        let headers = response.headers();
+        let accepts_ranges = headers
+            .get(ACCEPT_RANGES)
+            .is_some_and(|value| value.as_bytes() == b"bytes");
+        if request.method() != Method::GET
+            || response.status() != StatusCode::OK
+            || !accepts_ranges
+            || headers.contains_key(CONTENT_ENCODING)
+        {
+            return response;
+        }
+        let Some(total) = response.content_length() else {
+            return response;
+        };
+        // A weak etag can't be used to resume, the content could differ
+        let validator = headers
+            .get(ETAG)
+            .filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+            .or_else(|| headers.get(LAST_MODIFIED))
+            .cloned();
+        let (Some(validator), Some(request)) = (validator, request.try_clone()) else {
+            return response;
+        };
+
+        // Keep the parts of the response, they also contain its url
+        let (parts, body) = http::Response::<reqwest::Body>::from(response).into_parts();
+        let download = ResumableDownload {
+            client: self.client.clone(),
+            request,
+            validator,
+            policy: self.policy,
+            max_retries: self.max_retries,
+            received: 0,
+            total,
+            resumes: 0,
+            start_time: SystemTime::now(),
+            body: Response::from(http::Response::new(body))
+                .bytes_stream()
+                .boxed(),
+        };
+        let stream = futures::stream::unfold(Some(download), |download| async move {
+            let mut download = download?;
+            loop {
+                match download.body.next().await {
+                    Some(Ok(chunk)) => {
+                        download.received += chunk.len() as u64;
+                        return Some((Ok(chunk), Some(download)));
+                    }
+                    Some(Err(err)) => {
+                        if !download.resume().await {
+                            return Some((Err(err), None));
+                        }
+                    }
+                    None => return None,
+                }
+            }
+        });
+        Response::from(http::Response::from_parts(
+            parts,
+            reqwest::Body::wrap_stream(stream),
+        ))
+    }
+}
+
+/// The state of a download that is resumed with range requests.
+struct ResumableDownload {
+    client: Client,
+    request: Request,
+    /// The etag or last modified date, the server only returns the requested
+    /// range if the content still matches it.
+    validator: HeaderValue,
+    policy: ExponentialBackoff,
+    max_retries: u32,
+    received: u64,
+    total: u64,
+    resumes: u32,
+    start_time: SystemTime,
+    body: BoxStream<'static, reqwest::Result<Bytes>>,
+}
+
+impl ResumableDownload {
+    /// Requests the remaining bytes, returns `false` if the download can't be
+    /// resumed.
+    async fn resume(&mut self) -> bool {
+        if self.received >= self.total {
+            return false;
+        }
+        loop {
+            let RetryDecision::Retry { execute_after } =
+                self.policy.should_retry(self.start_time, self.resumes)
+            else {
+                return false;
+            };
+            self.resumes += 1;
+            tracing::warn!(
+                "retrying {}/{} for {} (resuming at byte {})",
+                self.resumes,
+                self.max_retries,
+                self.request.url(),
+                self.received
+            );
+            let delay = execute_after
+                .duration_since(SystemTime::now())
+                .unwrap_or_default();
+            tokio::time::sleep(delay).await;
+
+            let Some(mut request) = self.request.try_clone() else {
+                return false;
+            };
+            let range = HeaderValue::from_str(&format!("bytes={}-", self.received))
+                .expect("a range is a valid header value");
+            request.headers_mut().insert(RANGE, range);
+            request
+                .headers_mut()
+                .insert(IF_RANGE, self.validator.clone());
+
+            match self.client.execute(request).await {
+                Ok(response) if response.status() == StatusCode::PARTIAL_CONTENT => {
+                    let expected = format!("bytes {}-", self.received);
+                    let starts_at_received = response
+                        .headers()
+                        .get(CONTENT_RANGE)
+                        .and_then(|range| range.to_str().ok())
+                        .is_some_and(|range| range.starts_with(&expected));
+                    if !starts_at_received {
+                        debug!(
+                            "can't resume {}, the range doesn't match",
+                            self.request.url()
+                        );
+                        return false;
+                    }
+                    self.body = response.bytes_stream().boxed();
+                    return true;
+                }
+                Ok(response) if response.status().is_server_error() => continue,
+                Ok(response) => {
+                    debug!(
+                        "can't resume {}, the server responded with {}",
+                        self.request.url(),
+                        response.status()
+                    );
+                    return false;
+                }
+                Err(err) => {
+                    let err = reqwest_middleware::Error::from(err);
+                    if default_on_request_failure(&err) != Some(Retryable::Transient) {
+                        debug!("can't resume {}: {err}", self.request.url());
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for RetryMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let start_time = SystemTime::now();
+        let mut past_retries = 0;
+        loop {
+            // Requests with a streaming body can't be cloned and thus not retried.
+            let Some(attempt) = req.try_clone() else {
+                return next.run(req, extensions).await;
+            };
+            let result = next.clone().run(attempt, extensions).await;
+            let retryable = match &result {
+                Ok(response) => default_on_request_success(response),
+                Err(err) => default_on_request_failure(err),
+            };
+            if retryable != Some(Retryable::Transient) {
+                return result.map(|response| self.resumable(&req, response));
+            }
+
+            let RetryDecision::Retry { execute_after } =
+                self.policy.should_retry(start_time, past_retries)
+            else {
+                return result;
+            };
+            past_retries += 1;
+            let reason = match &result {
+                Ok(response) => response.status().to_string(),
+                Err(err) => err.to_string(),
+            };
+            tracing::warn!(
+                "retrying {}/{} for {} ({reason})",
+                past_retries,
+                self.max_retries,
+                req.url()
+            );
+            let delay = execute_after
+                .duration_since(SystemTime::now())
+                .unwrap_or_default();
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// The error of a request that is made while `network.offline` is set.
+#[derive(Debug, thiserror::Error)]
+#[error("can't download {0} while offline, only cached packages and repodata can be used")]
+pub struct OfflineError(pub url::Url);
+
+/// Refuses every request, so that nothing is downloaded while
+/// `network.offline` is set. Local channels don't use the client and keep
+/// working.
+pub struct OfflineMiddleware;
+
+#[async_trait::async_trait]
+impl Middleware for OfflineMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        _extensions: &mut Extensions,
+        _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        Err(reqwest_middleware::Error::middleware(OfflineError(
+            req.url().clone(),
+        )))
+    }
 }
 
 fn auth_store(config: &Config) -> Result<AuthenticationStorage, AuthenticationStorageError> {
@@ -149,6 +438,10 @@ pub fn build_reqwest_clients(
 
     let mut client_builder = ClientBuilder::new(client.clone());
 
+    if config.network().offline() {
+        client_builder = client_builder.with(OfflineMiddleware);
+    }
+
     if !config.mirror_map().is_empty() {
         client_builder = client_builder
             .with(mirror_middleware(&config))
@@ -173,9 +466,7 @@ pub fn build_reqwest_clients(
         auth_middleware(&config).expect("could not create auth middleware"),
     ));
 
-    client_builder = client_builder.with(RetryTransientMiddleware::new_with_policy(
-        default_retry_policy(),
-    ));
+    client_builder = client_builder.with(RetryMiddleware::new(config.network(), client.clone()));
 
     let authenticated_client = client_builder.build();
 
@@ -192,3 +483,98 @@ pub fn uv_middlewares(config: &Config) -> Vec<Arc<dyn Middleware>> {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Serves the responses in order, one per connection, and returns the
+    /// url of the server and the requests it received.
+    async fn serve(responses: Vec<Vec<u8>>) -> (url::Url, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/file", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0; 4096];
+                let read = socket.read(&mut buffer).await.unwrap();
+                received
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&buffer[..read]).to_lowercase());
+                socket.write_all(&response).await.unwrap();
+                socket.shutdown().await.ok();
+            }
+        });
+        (url, requests)
+    }
+
+    fn client() -> ClientWithMiddleware {
+        let network = NetworkConfig {
+            max_retries: Some(3),
+            retry_backoff_ms: Some(1),
+            offline: None,
+        };
+        let client = Client::new();
+        ClientBuilder::new(client.clone())
+            .with(RetryMiddleware::new(&network, client))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_transient_failure_is_retried() {
+        let (url, requests) = serve(vec![
+            b"HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                .to_vec(),
+            b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello".to_vec(),
+        ])
+        .await;
+
+        let response = client().get(url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "hello");
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_failure_is_not_retried() {
+        let (url, requests) = serve(vec![
+            b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n".to_vec(),
+        ])
+        .await;
+
+        let response = client().get(url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cut_off_download_is_resumed() {
+        let (url, requests) = serve(vec![
+            // The connection drops after half of the body
+            b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\naccept-ranges: bytes\r\netag: \"abc\"\r\nconnection: close\r\n\r\nhello"
+                .to_vec(),
+            b"HTTP/1.1 206 Partial Content\r\ncontent-length: 5\r\ncontent-range: bytes 5-9/10\r\netag: \"abc\"\r\nconnection: close\r\n\r\nworld"
+                .to_vec(),
+        ])
+        .await;
+
+        let response = client().get(url).send().await.unwrap();
+        assert_eq!(response.bytes().await.unwrap().as_ref(), b"helloworld");
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains("range: bytes=5-"), "{}", requests[1]);
+        assert!(requests[1].contains("if-range: \"abc\""), "{}", requests[1]);
+    }
+}
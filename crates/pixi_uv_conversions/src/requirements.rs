@@ -318,6 +318,7 @@ mod tests {
                 )),
                 subdirectory: None,
             },
+            editable: None,
             extras: vec![],
         };
         let uv_req = as_uv_req(&pypi_req, "test", Path::new("")).unwrap();
@@ -342,6 +343,7 @@ mod tests {
                 )),
                 subdirectory: None,
             },
+            editable: None,
             extras: vec![],
         };
         let uv_req = as_uv_req(&pypi_req, "test", Path::new("")).unwrap();
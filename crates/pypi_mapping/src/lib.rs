@@ -162,11 +162,25 @@ pub enum MappingError {
 impl MappingClient {
     /// Construct a new `MappingClientBuilder` with the provided `Client`.
     pub fn builder(client: ClientWithMiddleware) -> MappingClientBuilder {
+        Self::builder_with_cache_mode(client, CacheMode::Default)
+    }
+
+    /// Construct a `MappingClientBuilder` that only uses mappings from the
+    /// http cache. Mappings that are not cached fail instead of being
+    /// downloaded, which is what `network.offline` asks for.
+    pub fn offline_builder(client: ClientWithMiddleware) -> MappingClientBuilder {
+        Self::builder_with_cache_mode(client, CacheMode::OnlyIfCached)
+    }
+
+    fn builder_with_cache_mode(
+        client: ClientWithMiddleware,
+        mode: CacheMode,
+    ) -> MappingClientBuilder {
         // Construct a client with a retry policy and local caching
         let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
         let retry_strategy = RetryTransientMiddleware::new_with_policy(retry_policy);
         let cache_strategy = Cache(HttpCache {
-            mode: CacheMode::Default,
+            mode,
             manager: CACacheManager {
                 path: get_cache_dir()
                     .expect("missing cache directory")
@@ -295,6 +295,7 @@ PyPIRequirement = (
     | PyPIUrlRequirement
 )
 PyPIPackageName = NonEmptyStr
+AllOrNonePackages = Literal[":all:"] | Literal[":none:"]
 
 DependenciesField = Field(
     None,
@@ -328,6 +329,16 @@ class TaskArgs(StrictBaseModel):
     default: str | None = Field(None, description="The default value of the argument")
 
 
+class EnvFile(StrictBaseModel):
+    """A `.env` file to load environment variables from."""
+
+    path: PathNoBackslash = Field(description="The path of the file, relative to the workspace root")
+    required: bool | None = Field(
+        False,
+        description="Whether a missing file is an error instead of a warning",
+    )
+
+
 class DependsOn(StrictBaseModel):
     """The dependencies of a task."""
 
@@ -369,6 +380,11 @@ class TaskInlineTable(StrictBaseModel):
         description="A map of environment variables to values, used in the task, these will be overwritten by the shell.",
         examples=[{"key": "value"}, {"ARGUMENT": "value"}],
     )
+    env_files: list[EnvFile | PathNoBackslash] | None = Field(
+        None,
+        description="The `.env` files to load environment variables from before running the task, later files override earlier ones. The variables in `env` take precedence.",
+        examples=[[".env", {"path": ".env.local", "required": True}]],
+    )
     description: NonEmptyStr | None = Field(
         None,
         description="A short description of the task",
@@ -378,6 +394,38 @@ class TaskInlineTable(StrictBaseModel):
         None,
         description="Whether to run in a clean environment, removing all environment variables except those defined in `env` and by pixi itself.",
     )
+    clean_env_keep: list[NonEmptyStr] | None = Field(
+        None,
+        description="The environment variables of the current shell to keep in a clean environment, may contain `*` and `?` wildcards.",
+        examples=[["SSH_AUTH_SOCK", "HTTP_PROXY", "HTTPS_PROXY", "AWS_*"]],
+    )
+    timeout: NonEmptyStr | None = Field(
+        None,
+        description="The maximum time the task may run, e.g. `10m` or `1h 30m`. The task fails and its processes are killed when it runs longer.",
+        examples=["10m", "1h 30m"],
+    )
+    continue_on_error: bool | None = Field(
+        None,
+        description="Whether the other tasks of the run continue when this task fails, only the tasks that depend on it are skipped",
+    )
+    shell: Literal["deno", "bash", "nu", "powershell"] | None = Field(
+        None,
+        description="The shell that runs the command: the cross-platform shell built into pixi (`deno`, the default), or `bash`, `nu` or `powershell` from the environment of the task.",
+    )
+    interactive: bool | None = Field(
+        None,
+        description="Whether the task runs attached to the terminal, its output is shown directly instead of being captured. Useful for programs that need a terminal, like TUIs",
+    )
+    retries: UnsignedInt | None = Field(
+        None,
+        description="How many more times the command runs when it fails or times out, only the exit code of the last attempt counts. The dependencies of the task don't run again.",
+        examples=[2],
+    )
+    retry_delay: NonEmptyStr | None = Field(
+        None,
+        description="The time to wait before the command runs again after it failed, e.g. `5s` or `1m`.",
+        examples=["5s", "1m"],
+    )
     args: list[TaskArgs | NonEmptyStr] | None = Field(
         None,
         description="The arguments to pass to the task",
@@ -436,6 +484,16 @@ class Environment(StrictBaseModel):
         False,
         description="Whether to add the default feature to this environment",
     )
+    post_install: list[TaskName] | None = Field(
+        None,
+        description="The tasks to run after the environment was installed",
+        examples=[["pre-commit-install"]],
+    )
+    env_files: list[EnvFile | PathNoBackslash] | None = Field(
+        None,
+        description="The `.env` files to load into the environment of the tasks, after the ones of the features of the environment",
+        examples=[[".env", {"path": ".env.local", "required": True}]],
+    )
 
 
 ######################
@@ -454,6 +512,11 @@ class Activation(StrictBaseModel):
         description="A map of environment variables to values, used in the activation of the environment. These will be set in the shell. Thus these variables are shell specific. Using '$' might not expand to a value in different shells.",
         examples=[{"key": "value"}, {"ARGUMENT": "value"}],
     )
+    env_files: list[EnvFile | PathNoBackslash] | None = Field(
+        None,
+        description="The `.env` files to load into the environment of the tasks, later files override earlier ones. The variables in `env` take precedence.",
+        examples=[[".env", {"path": ".env.local", "required": True}]],
+    )
 
 
 ##################
@@ -523,6 +586,11 @@ class Feature(StrictBaseModel):
     pypi_options: PyPIOptions | None = Field(
         None, description="Options related to PyPI indexes for this feature"
     )
+    post_install: list[TaskName] | None = Field(
+        None,
+        description="The tasks to run after an environment containing this feature was installed",
+        examples=[["pre-commit-install"]],
+    )
 
 
 ###################
@@ -594,15 +662,20 @@ class PyPIOptions(StrictBaseModel):
         description="The strategy to use when resolving packages from multiple indexes",
         examples=["first-index", "unsafe-first-match", "unsafe-best-match"],
     )
-    no_build: bool | list[PyPIPackageName] | None = Field(
+    no_build: bool | AllOrNonePackages | list[PyPIPackageName] | None = Field(
         None,
         description="Packages that should NOT be built",
-        examples=["true", "false"],
+        examples=["true", "false", ":all:"],
     )
-    no_binary: bool | list[PyPIPackageName] | None = Field(
+    only_binary: bool | AllOrNonePackages | list[PyPIPackageName] | None = Field(
+        None,
+        description="Packages that should NOT be built, merged with `no-build`",
+        examples=["true", "false", ":all:"],
+    )
+    no_binary: bool | AllOrNonePackages | list[PyPIPackageName] | None = Field(
         None,
         description="Don't use pre-built wheels for these packages",
-        examples=["true", "false"],
+        examples=["true", "false", ":all:"],
     )
 
 
@@ -722,6 +795,11 @@ class BaseManifest(StrictBaseModel):
     system_requirements: SystemRequirements | None = Field(
         None, description="The system requirements of the project"
     )
+    post_install: list[TaskName] | None = Field(
+        None,
+        description="The tasks to run after an environment was installed",
+        examples=[["pre-commit-install"]],
+    )
     environments: dict[EnvironmentName, Environment | list[FeatureName]] | None = Field(
         None,
         description="The environments of the project, defined as a full object or a list of feature names.",
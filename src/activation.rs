@@ -4,6 +4,7 @@ use fs_err::tokio as tokio_fs;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use miette::IntoDiagnostic;
+use pixi_consts::consts;
 use pixi_manifest::EnvironmentName;
 use pixi_manifest::FeaturesExt;
 use rattler_conda_types::Platform;
@@ -17,11 +18,16 @@ use rattler_shell::{
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use xxhash_rust::xxh3::Xxh3;
 
 // Setting a base prefix for the pixi package
 const PROJECT_PREFIX: &str = "PIXI_PROJECT_";
 
+#[derive(Hash)]
 pub enum CurrentEnvVarBehavior {
     /// Clean the environment variables of the current shell.
     /// This will return the minimal set of environment variables that are required to run the command.
@@ -34,8 +40,9 @@ pub enum CurrentEnvVarBehavior {
 
 #[derive(Serialize, Deserialize)]
 struct ActivationCache {
-    /// The hash of the environment which produced the activation's environment variables.
-    hash: EnvironmentHash,
+    /// The hash of the environment and the shell which produced the activation's environment
+    /// variables, see [`activation_cache_hash`].
+    hash: String,
     /// The environment variables set by the activation.
     environment_variables: HashMap<String, String>,
 }
@@ -118,6 +125,17 @@ impl Environment<'_> {
 pub(crate) fn get_activator<'p>(
     environment: &'p Environment<'p>,
     shell: ShellEnum,
+) -> Result<Activator<ShellEnum>, ActivationError> {
+    get_activator_for_prefix(environment, &environment.dir(), shell)
+}
+
+/// Creates an activator for the environment like [`get_activator`], but for
+/// the environment installed in the given prefix instead of the prefix of the
+/// environment.
+pub(crate) fn get_activator_for_prefix<'p>(
+    environment: &'p Environment<'p>,
+    prefix: &Path,
+    shell: ShellEnum,
 ) -> Result<Activator<ShellEnum>, ActivationError> {
     let platform = Platform::current();
     let additional_activation_scripts = environment.activation_scripts(Some(platform));
@@ -154,8 +172,7 @@ pub(crate) fn get_activator<'p>(
         }
     }
 
-    let mut activator =
-        Activator::from_path(environment.dir().as_path(), shell, Platform::current())?;
+    let mut activator = Activator::from_path(prefix, shell, Platform::current())?;
 
     // Add the custom activation scripts from the environment
     activator
@@ -193,6 +210,7 @@ fn get_environment_variable_from_shell_environment(
 async fn try_get_valid_activation_cache(
     lock_file: &LockFile,
     environment: &Environment<'_>,
+    env_var_behavior: &CurrentEnvVarBehavior,
     cache_file: PathBuf,
 ) -> Option<HashMap<String, String>> {
     // Find cache file
@@ -226,7 +244,12 @@ async fn try_get_valid_activation_cache(
     );
 
     // Hash the current state
-    let hash = EnvironmentHash::from_environment(environment, &current_input_env_vars, lock_file);
+    let hash = activation_cache_hash(
+        environment,
+        env_var_behavior,
+        &current_input_env_vars,
+        lock_file,
+    );
 
     // Check if the hash matches
     if cache.hash == hash {
@@ -236,6 +259,62 @@ async fn try_get_valid_activation_cache(
     }
 }
 
+/// Returns the hash that a cached activation has to match to be used. Next to the
+/// [`EnvironmentHash`] of the locked packages and the activation of the manifest, it covers the
+/// prefix, how the activation treats the variables of the shell and the `PATH` and `CONDA_*`
+/// variables that it starts from.
+fn activation_cache_hash(
+    environment: &Environment<'_>,
+    env_var_behavior: &CurrentEnvVarBehavior,
+    input_env_vars: &HashMap<String, Option<String>>,
+    lock_file: &LockFile,
+) -> String {
+    let mut hasher = Xxh3::new();
+    EnvironmentHash::from_environment(environment, input_env_vars, lock_file).hash(&mut hasher);
+    environment.dir().hash(&mut hasher);
+    env_var_behavior.hash(&mut hasher);
+    std::env::vars()
+        .filter(|(key, _)| key.eq_ignore_ascii_case("PATH") || key.starts_with("CONDA_"))
+        .sorted()
+        .for_each(|variable| variable.hash(&mut hasher));
+    format!("{:x}", hasher.finish())
+}
+
+/// The package records in the `conda-meta` directory of a prefix with the time they were last
+/// written, to tell whether an install modified the prefix. `None` if the prefix has no
+/// `conda-meta` directory.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct PrefixSnapshot(Option<Vec<(OsString, Option<SystemTime>)>>);
+
+impl PrefixSnapshot {
+    pub(crate) fn new(prefix: &Path) -> Self {
+        let Ok(entries) = fs_err::read_dir(prefix.join(consts::CONDA_META_DIR)) else {
+            return Self(None);
+        };
+        let records = entries
+            .flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .map(|entry| {
+                let modified = entry.metadata().and_then(|metadata| metadata.modified());
+                (entry.file_name(), modified.ok())
+            })
+            .sorted()
+            .collect();
+        Self(Some(records))
+    }
+}
+
+/// Removes the cached activation of the environment, e.g. after the prefix was modified by an
+/// install.
+pub(crate) fn invalidate_activation_cache(environment: &Environment<'_>) {
+    let cache_file = environment.activation_cache_file_path();
+    match fs_err::remove_file(&cache_file) {
+        Ok(()) => tracing::debug!("Removed activation cache for {}", environment.name()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => tracing::warn!("Failed to remove the activation cache: {err}"),
+    }
+}
+
 /// Runs and caches the activation script.
 pub async fn run_activation(
     environment: &Environment<'_>,
@@ -252,7 +331,8 @@ pub async fn run_activation(
             .join(environment.activation_cache_name());
         if let Some(lock_file) = lock_file {
             if let Some(env_vars) =
-                try_get_valid_activation_cache(lock_file, environment, cache_file).await
+                try_get_valid_activation_cache(lock_file, environment, env_var_behavior, cache_file)
+                    .await
             {
                 tracing::debug!("Using activation cache for {:?}", environment.name());
                 return Ok(env_vars);
@@ -345,8 +425,9 @@ pub async fn run_activation(
             );
             let cache_file = environment.activation_cache_file_path();
             let cache = ActivationCache {
-                hash: EnvironmentHash::from_environment(
+                hash: activation_cache_hash(
                     environment,
+                    env_var_behavior,
                     &current_input_env_vars,
                     lock_file,
                 ),
@@ -400,6 +481,23 @@ pub(crate) fn get_static_environment_variables<'p>(
         .collect()
 }
 
+/// The variables that Windows needs to start processes, which are therefore
+/// kept in a clean environment.
+const WINDOWS_CLEAN_ENV_KEYS: &[&str] = &[
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "TEMP",
+    "TMP",
+    "USERNAME",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+];
+
 /// Get the environment variables that are set in the current shell
 /// and strip them down to the minimal set required to run a command.
 pub(crate) fn get_clean_environment_variables() -> HashMap<String, String> {
@@ -429,10 +527,16 @@ pub(crate) fn get_clean_environment_variables() -> HashMap<String, String> {
         vec![]
     };
 
+    let windows_keys = if cfg!(windows) {
+        WINDOWS_CLEAN_ENV_KEYS.to_vec()
+    } else {
+        vec![]
+    };
+
     let keys = unix_keys
         .into_iter()
         .chain(macos_keys)
-        // .chain(windows_keys)
+        .chain(windows_keys)
         .map(|s| s.to_string().to_uppercase())
         .collect_vec();
 
@@ -441,6 +545,44 @@ pub(crate) fn get_clean_environment_variables() -> HashMap<String, String> {
         .collect::<HashMap<String, String>>()
 }
 
+/// Get the environment variables that are set in the current shell and whose
+/// names match one of the `patterns`, which may contain `*` and `?`
+/// wildcards. On Windows the names are compared case-insensitively.
+pub(crate) fn get_kept_environment_variables(patterns: &[String]) -> HashMap<String, String> {
+    std::env::vars()
+        .filter(|(key, _)| {
+            patterns
+                .iter()
+                .any(|pattern| env_name_matches(pattern, key))
+        })
+        .collect()
+}
+
+/// Matches the name of an environment variable against a pattern with `*`
+/// and `?` wildcards.
+fn env_name_matches(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match (pattern.first(), name.first()) {
+            (None, None) => true,
+            (Some('*'), _) => {
+                matches(&pattern[1..], name) || (!name.is_empty() && matches(pattern, &name[1..]))
+            }
+            (Some('?'), Some(_)) => matches(&pattern[1..], &name[1..]),
+            (Some(p), Some(n)) => p == n && matches(&pattern[1..], &name[1..]),
+            _ => false,
+        }
+    }
+
+    let normalize = |s: &str| -> Vec<char> {
+        if cfg!(windows) {
+            s.to_uppercase().chars().collect()
+        } else {
+            s.chars().collect()
+        }
+    };
+    matches(&normalize(pattern), &normalize(name))
+}
+
 /// Determine the environment variables that need to be set in an interactive shell to make it
 /// function as if the environment has been activated. This method runs the activation scripts from
 /// the environment and stores the environment variables it added, finally it adds environment
@@ -466,12 +608,15 @@ pub(crate) async fn initialize_env_variables(
 
     // Get environment variables from the currently activated shell.
     let current_shell_env_vars = match env_var_behavior {
-        CurrentEnvVarBehavior::Clean if cfg!(windows) => {
-            return Err(miette::miette!(
-                "Currently it's not possible to run a `clean-env` option on Windows."
-            ));
+        CurrentEnvVarBehavior::Clean => {
+            if cfg!(windows) {
+                tracing::warn!(
+                    "`clean-env` can't fully isolate a task on Windows: the variables that Windows needs to start processes ({}) are kept, next to the ones listed in `clean-env-keep` or `--keep-env`",
+                    WINDOWS_CLEAN_ENV_KEYS.join(", ")
+                );
+            }
+            get_clean_environment_variables()
         }
-        CurrentEnvVarBehavior::Clean => get_clean_environment_variables(),
         CurrentEnvVarBehavior::Include => std::env::vars().collect(),
         CurrentEnvVarBehavior::Exclude => HashMap::new(),
     };
@@ -603,6 +748,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_env_name_matches() {
+        assert!(env_name_matches("SSH_AUTH_SOCK", "SSH_AUTH_SOCK"));
+        assert!(env_name_matches("AWS_*", "AWS_PROFILE"));
+        assert!(env_name_matches("AWS_*", "AWS_"));
+        assert!(env_name_matches("*_PROXY", "HTTPS_PROXY"));
+        assert!(env_name_matches("HTTP?_PROXY", "HTTPS_PROXY"));
+        assert!(!env_name_matches("HTTP?_PROXY", "HTTP_PROXY"));
+        assert!(!env_name_matches("AWS_*", "MY_AWS_PROFILE"));
+        assert!(!env_name_matches("SSH_AUTH_SOCK", "SSH_AUTH_SOCKET"));
+        assert_eq!(env_name_matches("aws_*", "AWS_PROFILE"), cfg!(windows));
+    }
+
     /// Test that the activation cache is created and used correctly based on the lockfile.
     ///
     /// This test will validate the cache usages by running the activation script and checking if the cache is created.
@@ -723,6 +881,10 @@ packages:
             true,
         );
         assert_eq!(env.await.unwrap().get("TEST").unwrap(), "ACTIVATION456");
+
+        // An install removes the cache
+        invalidate_activation_cache(&default_env);
+        assert!(!cache_file.exists());
     }
 
     #[tokio::test]
@@ -864,4 +1026,23 @@ packages:
     //     // Unset the environment variable
     //     std::env::remove_var("TEST_ENV_VAR");
     // }
+
+    #[test]
+    fn test_prefix_snapshot() {
+        let prefix = tempfile::tempdir().unwrap();
+        let missing = PrefixSnapshot::new(prefix.path());
+
+        // Creating the prefix modifies it, the history file is not a record
+        let conda_meta = prefix.path().join(consts::CONDA_META_DIR);
+        fs_err::create_dir_all(&conda_meta).unwrap();
+        fs_err::write(conda_meta.join("history"), "").unwrap();
+        let empty = PrefixSnapshot::new(prefix.path());
+        assert_ne!(missing, empty);
+        fs_err::write(conda_meta.join("history"), "changed").unwrap();
+        assert_eq!(PrefixSnapshot::new(prefix.path()), empty);
+
+        // Installing a package modifies the prefix
+        fs_err::write(conda_meta.join("foo-1.0-0.json"), "{}").unwrap();
+        assert_ne!(PrefixSnapshot::new(prefix.path()), empty);
+    }
 }
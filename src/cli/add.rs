@@ -1,17 +1,32 @@
 use clap::Parser;
-use indexmap::IndexMap;
-use miette::IntoDiagnostic;
-use pixi_config::ConfigCli;
-use pixi_manifest::{FeatureName, SpecType};
-use pixi_spec::{GitSpec, SourceSpec};
-use rattler_conda_types::{MatchSpec, PackageName};
-
-use super::{cli_config::LockFileUpdateConfig, has_specs::HasSpecs};
+use fancy_display::FancyDisplay;
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic, MietteDiagnostic};
+use pixi_config::{Config, ConfigCli, ConfigCliNetwork, PinningStrategy};
+use pixi_consts::consts;
+use pixi_manifest::{
+    DependencyOverwriteBehavior, EnvironmentName, FeatureName, FeaturesExt, HasFeaturesIter,
+    ManifestKind, PrioritizedChannel, PypiDependencyLocation, SpecType,
+};
+use pixi_pypi_spec::PixiPypiSpec;
+use pixi_spec::{GitSpec, PixiSpec, SourceSpec};
+use rattler_conda_types::{MatchSpec, NamedChannelOrUrl, PackageName, Platform};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    cli_config::LockFileUpdateConfig, has_specs::HasSpecs, solve_conflict::explain_solve_conflict,
+};
 use crate::{
-    WorkspaceLocator,
+    Workspace, WorkspaceLocator,
     cli::cli_config::{DependencyConfig, PrefixUpdateConfig, WorkspaceConfig},
+    diff::{JsonDependencyChanges, JsonManifestChange, LockFileDiff},
     environment::sanity_check_workspace,
-    workspace::DependencyType,
+    workspace::{DependencyType, MatchSpecs, WorkspaceMut},
 };
 
 /// Adds dependencies to the workspace
@@ -33,17 +48,40 @@ use crate::{
 ///
 /// - `pixi add python pytest`: This will add both `python` and `pytest` to the
 ///   workspace's dependencies.
+/// - `pixi add --pypi --from-file requirements.txt`: This will add all the
+///   requirements listed in `requirements.txt` in a single solve.
+/// - `pixi add pytest --feature test --feature lint`: This will add `pytest`
+///   to both the `test` and `lint` features, which should already exist.
 ///
-/// The `--platform` and `--build/--host` flags make the dependency target
-/// specific.
+/// The `--platform` flag makes the dependency target specific. The dependency
+/// is written to the `target.<platform>` table of the feature, the platforms
+/// must already be supported by the workspace.
 ///
 /// - `pixi add python --platform linux-64 --platform osx-arm64`: Will add the
 ///   latest version of python for linux-64 and osx-arm64 platforms.
-/// - `pixi add python --build`: Will add the latest version of python for as a
-///   build dependency.
+///
+/// The `--build` and `--host` flags add the dependency to the
+/// `[package.build-dependencies]` or `[package.host-dependencies]` table of
+/// the package defined in the workspace manifest. These dependencies are not
+/// part of any environment, so the lock file is not updated.
+///
+/// - `pixi add cmake --build`: Will add cmake as a build dependency of the
+///   package.
 ///
 /// Mixing `--platform` and `--build`/`--host` flags is supported
 ///
+/// The `--dry-run` flag resolves the dependencies and prints the specs and
+/// lock-file changes that would be made, without modifying the manifest, the
+/// lock file or the environment.
+///
+/// The `--git` option will add the package as a conda source dependency that
+/// is built from a git repository. The `--branch`, `--tag` or `--rev` and
+/// `--subdirectory` options select what to build, the resolved commit is
+/// recorded in the lock file.
+///
+/// - `pixi add mylib --git https://github.com/org/mylib --branch main`
+/// - `pixi add mylib --git https://github.com/org/mono --tag v1.0 --subdirectory mylib`
+///
 /// The `--pypi` option will add the package as a pypi dependency. This cannot
 /// be mixed with the conda dependencies
 ///
@@ -60,11 +98,25 @@ use crate::{
 ///   `dependency-groups.aws` array
 /// - `pixi add --pypi --editable 'boto3 @ file://absolute/path/to/boto3'` will add
 ///   the local editable `boto3` to the `pypi-dependencies` array
+/// - `pixi add --pypi --editable boto3 --git https://github.com/boto/boto3` will
+///   check out `boto3` in the environment and install it editable
+///
+/// Use `--optional <EXTRA>` to add the dependency to the
+/// `project.optional-dependencies` table instead. The group is created when it
+/// doesn't exist yet and an environment with the same name is added if no
+/// environment uses the extra:
+///
+/// - `pixi add --pypi pytest --optional test` will add `pytest` to the
+///   `project.optional-dependencies.test` array
 ///
 /// Note that if `--platform` or `--editable` are specified, the pypi dependency
 /// will be added to the `tool.pixi.pypi-dependencies` table instead as native
 /// arrays have no support for platform-specific or editable dependencies.
 ///
+/// Local path dependencies are stored relative to the workspace root using
+/// forward slashes, so the manifest and lock file work on any machine. Paths
+/// outside of the workspace are only accepted with `--allow-external-path`.
+///
 /// These dependencies will then be read by pixi as if they had been added to
 /// the pixi `pypi-dependencies` tables of the default or of a named feature.
 ///
@@ -73,8 +125,25 @@ use crate::{
 /// packages that are not following the semver versioning scheme but will use
 /// the minor version by default:
 /// Python, Rust, Julia, GCC, GXX, GFortran, NodeJS, Deno, R, R-Base, Perl
+///
+/// If the dependencies can't be solved, the version constraints in the manifest
+/// are shown next to the solver output, together with suggestions on how to
+/// relax them. Use `--no-suggestions` to only show the solver output.
+///
+/// Specs can select a channel with the `channel::package` shorthand, e.g.
+/// `pixi add bioconda::samtools`. If the channel is not part of the workspace
+/// or of the feature yet, pixi asks to add it to the end of the channels list,
+/// use `--yes` to add it without asking and `--prepend` to give it the highest
+/// priority. The channel is kept in the spec written to the manifest.
+///
+/// Use `--pin <STRATEGY>` to override the pinning strategy, or `--no-pin` to
+/// keep the specs exactly as they were passed.
 #[derive(Parser, Debug, Default)]
-#[clap(arg_required_else_help = true, verbatim_doc_comment)]
+#[clap(
+    arg_required_else_help = true,
+    verbatim_doc_comment,
+    mut_arg("specs", |arg| arg.required(false).required_unless_present("from_file"))
+)]
 pub struct Args {
     #[clap(flatten)]
     pub workspace_config: WorkspaceConfig,
@@ -90,33 +159,151 @@ pub struct Args {
 
     #[clap(flatten)]
     pub config: ConfigCli,
+    #[clap(flatten)]
+    pub network_config: ConfigCliNetwork,
 
     /// Whether the pypi requirement should be editable
     #[arg(long, requires = "pypi")]
     pub editable: bool,
+
+    /// Only show the changes that would be made, without actually updating the
+    /// manifest, lock file, or environment.
+    #[clap(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Read the dependencies from a file with one spec per line, in addition
+    /// to the specs passed on the command line. Empty lines and lines starting
+    /// with `#` are skipped.
+    #[clap(long, value_name = "PATH")]
+    pub from_file: Option<PathBuf>,
+
+    /// The strategy used to pin the version of dependencies that are added
+    /// without a version, overrides the `pinning-strategy` configuration.
+    #[clap(long = "pin", value_name = "STRATEGY")]
+    pub pinning_strategy: Option<PinningStrategy>,
+
+    /// Don't pin the version of the added dependencies, the specs are written
+    /// to the manifest exactly as they were passed. The lock file still
+    /// contains the solved versions.
+    #[clap(long, conflicts_with = "pinning_strategy")]
+    pub no_pin: bool,
+
+    /// Allow adding pypi path dependencies that are located outside of the
+    /// workspace directory.
+    #[arg(long, requires = "pypi")]
+    pub allow_external_path: bool,
+
+    /// Output the changes in JSON format.
+    #[clap(long)]
+    pub json: bool,
+
+    /// Add the pypi dependencies to this group of the
+    /// `project.optional-dependencies` table of a `pyproject.toml` manifest.
+    #[arg(
+        long,
+        value_name = "EXTRA",
+        requires = "pypi",
+        conflicts_with_all = ["features", "platforms", "editable"]
+    )]
+    pub optional: Option<String>,
+
+    /// Don't explain conflicts with the specs in the manifest when the
+    /// dependencies can't be solved, show the full solver output instead.
+    #[clap(long)]
+    pub no_suggestions: bool,
+
+    /// Add the channels of `channel::package` specs that are not part of the
+    /// workspace without asking for confirmation.
+    #[clap(short = 'y', long = "yes")]
+    pub assume_yes: bool,
+
+    /// Add the channels of `channel::package` specs to the beginning of the
+    /// channels list, making them the highest priority.
+    #[clap(long)]
+    pub prepend: bool,
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
-    let (dependency_config, prefix_update_config, lock_file_update_config, workspace_config) = (
+    let (mut dependency_config, prefix_update_config, lock_file_update_config, workspace_config) = (
         args.dependency_config,
         args.prefix_update_config,
         args.lock_file_update_config,
         args.workspace_config,
     );
 
+    // Extend the specs with the ones from the file, they are all added in a
+    // single solve.
+    if let Some(from_file) = &args.from_file {
+        let contents = fs_err::read_to_string(from_file).into_diagnostic()?;
+        dependency_config.specs.extend(
+            parse_specs_file(&contents)
+                .with_context(|| format!("failed to read specs from '{}'", from_file.display()))?,
+        );
+    }
+
     let workspace = WorkspaceLocator::for_cli()
         .with_search_start(workspace_config.workspace_locator_start())
         .locate()?
-        .with_cli_config(args.config.clone());
+        .with_cli_config(
+            args.network_config
+                .clone()
+                .merge_config(args.config.clone().into()),
+        )
+        .with_cli_config(Config {
+            pinning_strategy: if args.no_pin {
+                Some(PinningStrategy::NoPin)
+            } else {
+                args.pinning_strategy
+            },
+            ..Config::default()
+        });
 
     sanity_check_workspace(&workspace).await?;
 
+    // Build and host dependencies are added to the package of the workspace
+    if dependency_config.is_package_dependency() && workspace.package.is_none() {
+        return Err(miette::miette!(
+            help = "build and host dependencies can only be added to a workspace that defines a `[package]`",
+            "the workspace at '{}' does not contain a package",
+            workspace.workspace.provenance.path.display()
+        ));
+    }
+
+    // Optional dependencies are added to the feature of the same name
+    if let Some(extra) = &args.optional {
+        if workspace.workspace.provenance.kind != ManifestKind::Pyproject {
+            return Err(miette::miette!(
+                help =
+                    format!("use `--feature {extra}` to add the dependencies to a feature instead"),
+                "`--optional` can only be used with a `pyproject.toml` manifest"
+            ));
+        }
+        dependency_config.features = vec![FeatureName::from(extra.clone())];
+    }
+
+    // When adding to multiple features at once, all of them should already exist
+    let features = dependency_config.feature_names();
+    if features.len() > 1 {
+        ensure_features_exist(&workspace, &features)?;
+    }
+
+    // Target specific dependencies can only be added for supported platforms
+    ensure_platforms_supported(&workspace, &features, &dependency_config.platforms)?;
+
+    // Keep the original workspace around to report the changes to the manifest
+    let original_workspace = args.json.then(|| workspace.clone());
+
     let mut workspace = workspace.modify()?;
 
-    // Add the platform if it is not already present
-    workspace
-        .manifest()
-        .add_platforms(dependency_config.platforms.iter(), &FeatureName::DEFAULT)?;
+    // Make sure the optional dependencies are picked up by an environment
+    if let Some(extra) = &args.optional {
+        ensure_extra_environment(&mut workspace, extra)?;
+    }
+    let location = || {
+        args.optional
+            .is_some()
+            .then_some(PypiDependencyLocation::OptionalDependencies)
+    };
 
     let (match_specs, source_specs, pypi_deps) = match dependency_config.dependency_type() {
         DependencyType::CondaDependency(spec_type) => {
@@ -157,28 +344,105 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             {
                 Some(vcs_reqs) => vcs_reqs
                     .into_iter()
-                    .map(|(name, req)| (name, (req, None, None)))
+                    .map(|(name, req)| (name, (req, None, location())))
                     .collect(),
                 None => dependency_config
                     .pypi_deps(workspace.workspace())?
                     .into_iter()
-                    .map(|(name, req)| (name, (req, None, None)))
-                    .collect(),
+                    .map(|(name, req)| {
+                        let pixi_req = relative_path_spec(
+                            &req,
+                            workspace.workspace().root(),
+                            args.allow_external_path,
+                        )?;
+                        Ok((name, (req, pixi_req, location())))
+                    })
+                    .collect::<miette::Result<_>>()?,
             };
 
             (match_specs, source_specs, pypi_deps)
         }
     };
-    // TODO: add dry_run logic to add
-    let dry_run = false;
+    let dry_run = args.dry_run;
+    let added_packages = match_specs
+        .keys()
+        .chain(source_specs.keys())
+        .cloned()
+        .collect_vec();
+
+    // Make sure the channels of `channel::package` specs are part of the workspace
+    add_spec_channels(
+        &mut workspace,
+        &match_specs,
+        &features,
+        args.prepend,
+        args.assume_yes,
+    )?;
+
+    // Build and host dependencies are not part of any environment, so there is
+    // nothing to solve.
+    if let DependencyType::CondaDependency(spec_type @ (SpecType::Build | SpecType::Host)) =
+        dependency_config.dependency_type()
+    {
+        let channel_config = workspace.workspace().channel_config();
+        let specs = match_specs
+            .into_iter()
+            .map(|(name, (spec, _))| {
+                let (_, nameless_spec) = spec.into_nameless();
+                (
+                    name,
+                    PixiSpec::from_nameless_matchspec(nameless_spec, &channel_config),
+                )
+            })
+            .chain(
+                source_specs
+                    .into_iter()
+                    .map(|(name, (spec, _))| (name, PixiSpec::from(spec))),
+            )
+            .collect_vec();
+
+        let Some(mut package) = workspace.package() else {
+            return Err(miette::miette!(
+                "the package is not defined in the workspace manifest"
+            ));
+        };
+        for (name, spec) in &specs {
+            package.add_dependency(
+                name,
+                spec,
+                spec_type,
+                &dependency_config.platforms,
+                DependencyOverwriteBehavior::Overwrite,
+            )?;
+        }
+
+        let manifest_changes = original_workspace
+            .as_ref()
+            .map(|original| dependency_config.manifest_changes(original, workspace.workspace()))
+            .transpose()?;
+        let workspace = if dry_run {
+            workspace.revert().await.into_diagnostic()?
+        } else {
+            workspace.save().await.into_diagnostic()?
+        };
 
-    let update_deps = match Box::pin(workspace.update_dependencies(
+        if args.json {
+            print_json_changes(&workspace, manifest_changes.unwrap_or_default(), None);
+        } else if dry_run {
+            dependency_config.display_success("Would add", Default::default());
+        } else {
+            dependency_config.display_success("Added", Default::default());
+        }
+        return Ok(());
+    }
+
+    let (update_deps, manifest_changes, workspace) = match Box::pin(workspace.update_dependencies(
         match_specs,
         pypi_deps,
         source_specs,
         &prefix_update_config,
         &lock_file_update_config,
-        &dependency_config.feature,
+        &features,
         &dependency_config.platforms,
         args.editable,
         dry_run,
@@ -186,20 +450,546 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     .await
     {
         Ok(update_deps) => {
-            // Write the updated manifest
-            workspace.save().await.into_diagnostic()?;
-            update_deps
+            let manifest_changes = original_workspace.as_ref().map(|original| {
+                dependency_config.manifest_changes(original, workspace.workspace())
+            });
+            let workspace = if dry_run {
+                // Throw away the modifications, we only wanted to know what would change
+                workspace.revert().await.into_diagnostic()?
+            } else {
+                // Write the updated manifest
+                workspace.save().await.into_diagnostic()?
+            };
+            (update_deps, manifest_changes.transpose()?, workspace)
         }
         Err(e) => {
-            workspace.revert().await.into_diagnostic()?;
-            return Err(e);
+            let workspace = workspace.revert().await.into_diagnostic()?;
+            if args.no_suggestions {
+                return Err(e);
+            }
+            return Err(explain_solve_conflict(
+                e,
+                &workspace,
+                &added_packages,
+                &features,
+                &dependency_config.platforms,
+            ));
         }
     };
 
+    if args.json {
+        print_json_changes(
+            &workspace,
+            manifest_changes.unwrap_or_default(),
+            update_deps.map(|update_deps| update_deps.lock_file_diff),
+        );
+        return Ok(());
+    }
+
     if let Some(update_deps) = update_deps {
-        // Notify the user we succeeded
-        dependency_config.display_success("Added", update_deps.implicit_constraints);
+        if dry_run {
+            // Show the specs that would have been written and the resulting lock-file changes
+            dependency_config.display_success("Would add", update_deps.implicit_constraints);
+            if update_deps.lock_file_diff.is_empty() {
+                eprintln!(
+                    "{}Lock-file would not change",
+                    console::style(console::Emoji("✔ ", "")).green()
+                );
+            } else {
+                update_deps
+                    .lock_file_diff
+                    .print()
+                    .into_diagnostic()
+                    .context("failed to print lock-file diff")?;
+            }
+        } else {
+            // Notify the user we succeeded
+            dependency_config.display_success("Added", update_deps.implicit_constraints);
+        }
+    } else if dry_run {
+        dependency_config.display_success("Would add", Default::default());
     }
 
     Ok(())
 }
+
+/// Adds the channels of `channel::package` specs that are not used by the
+/// given features to the manifest, after asking the user for confirmation.
+///
+/// Features that don't define their own channels use the channels of the
+/// workspace, in that case the channel is added to the workspace.
+fn add_spec_channels(
+    workspace: &mut WorkspaceMut,
+    match_specs: &MatchSpecs,
+    features: &[FeatureName],
+    prepend: bool,
+    assume_yes: bool,
+) -> miette::Result<()> {
+    let channel_config = workspace.workspace().channel_config();
+    for feature_name in features {
+        let manifest = &workspace.workspace().workspace.value;
+        let (channel_feature, current_channels) = match manifest
+            .feature(feature_name)
+            .and_then(|feature| feature.channels.as_ref())
+        {
+            Some(channels) => (feature_name.clone(), channels),
+            None => (FeatureName::DEFAULT, &manifest.workspace.channels),
+        };
+        let current_urls = current_channels
+            .iter()
+            .filter_map(|channel| channel.channel.clone().into_base_url(&channel_config).ok())
+            .collect_vec();
+
+        let missing_channels = match_specs
+            .values()
+            .filter_map(|(spec, _)| spec.channel.as_deref())
+            .filter(|channel| !current_urls.contains(&channel.base_url))
+            .map(|channel| {
+                let url = channel.base_url.url();
+                match url
+                    .as_str()
+                    .strip_prefix(channel_config.channel_alias.as_str())
+                {
+                    Some(name) => NamedChannelOrUrl::Name(name.trim_end_matches('/').to_string()),
+                    None => NamedChannelOrUrl::Url(url.clone()),
+                }
+            })
+            .collect::<IndexSet<_>>();
+        if missing_channels.is_empty() {
+            continue;
+        }
+
+        for channel in &missing_channels {
+            let confirmed = assume_yes
+                || (console::user_attended_stderr()
+                    && dialoguer::Confirm::new()
+                        .with_prompt(format!(
+                            "The channel '{}' is not part of the workspace, do you want to add it?",
+                            console::style(channel).bold()
+                        ))
+                        .default(true)
+                        .show_default(true)
+                        .interact()
+                        .into_diagnostic()?);
+            if !confirmed {
+                return Err(miette::miette!(
+                    help = format!(
+                        "pass `--yes` to add it automatically or add it with `pixi workspace channel add {channel}`"
+                    ),
+                    "the channel '{channel}' is not part of the workspace"
+                ));
+            }
+        }
+
+        workspace.manifest().add_channels(
+            missing_channels
+                .iter()
+                .cloned()
+                .map(PrioritizedChannel::from),
+            &channel_feature,
+            prepend,
+        )?;
+        for channel in missing_channels {
+            eprintln!(
+                "{}Added channel {}",
+                console::style(console::Emoji("✔ ", "")).green(),
+                console::style(channel).bold()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Prints the changes made to the manifest and the lock-file as JSON to stdout.
+fn print_json_changes(
+    workspace: &Workspace,
+    manifest_changes: Vec<JsonManifestChange>,
+    lock_file_diff: Option<LockFileDiff>,
+) {
+    let json = JsonDependencyChanges::new(Some(workspace), manifest_changes, lock_file_diff);
+    let json = serde_json::to_string_pretty(&json).expect("failed to convert to json");
+    println!("{}", json);
+}
+
+/// Adds an environment for the feature of the given extra, unless the feature
+/// is already used by an environment.
+fn ensure_extra_environment(workspace: &mut WorkspaceMut, extra: &str) -> miette::Result<()> {
+    let feature_name = FeatureName::from(extra.to_string());
+    let workspace_ref = workspace.workspace();
+    if workspace_ref
+        .environments()
+        .iter()
+        .any(|e| e.features().any(|f| f.name == feature_name))
+    {
+        return Ok(());
+    }
+
+    let environment_name = EnvironmentName::normalized(extra).into_diagnostic()?;
+    if workspace_ref.environment(&environment_name).is_some() {
+        miette::bail!(
+            help = format!(
+                "add the feature to the environment with `features = [\"{extra}\"]` or rename the environment"
+            ),
+            "the environment '{}' already exists but does not include the feature of the extra '{extra}'",
+            environment_name.fancy_display()
+        );
+    }
+
+    let mut manifest = workspace.manifest();
+    manifest.workspace.get_or_insert_feature_mut(&feature_name);
+    manifest.add_environment(
+        environment_name.to_string(),
+        Some(vec![extra.to_string()]),
+        None,
+        false,
+    )
+}
+
+/// Ensures that all the given features are defined in the workspace.
+///
+/// Returns an error listing all the unknown features, with suggestions based on
+/// the names of the existing features.
+fn ensure_features_exist(workspace: &Workspace, features: &[FeatureName]) -> miette::Result<()> {
+    let existing_features = workspace
+        .workspace
+        .value
+        .features
+        .keys()
+        .map(|name| name.as_str())
+        .collect_vec();
+
+    let unknown_features = features
+        .iter()
+        .filter(|feature| !existing_features.contains(&feature.as_str()))
+        .collect_vec();
+    if unknown_features.is_empty() {
+        return Ok(());
+    }
+
+    let suggestions = unknown_features
+        .iter()
+        .filter_map(|feature| {
+            existing_features
+                .iter()
+                .map(|name| (name, strsim::jaro(feature.as_str(), name)))
+                .filter(|(_, distance)| *distance > 0.6)
+                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .map(|(name, _)| format!("'{name}' instead of '{feature}'"))
+        })
+        .collect_vec();
+
+    Err(MietteDiagnostic {
+        message: format!(
+            "could not find the feature(s) {}",
+            unknown_features
+                .iter()
+                .map(|feature| feature.fancy_display())
+                .format(", ")
+        ),
+        code: None,
+        severity: None,
+        help: if suggestions.is_empty() {
+            None
+        } else {
+            Some(format!("did you mean {}?", suggestions.iter().format(", ")))
+        },
+        url: None,
+        labels: None,
+    }
+    .into())
+}
+
+/// Ensures that the platforms passed with `--platform` are supported by the
+/// environments that include any of the given features.
+///
+/// If none of the features is used by an environment, the platforms of the
+/// workspace are used instead.
+fn ensure_platforms_supported(
+    workspace: &Workspace,
+    features: &[FeatureName],
+    platforms: &[Platform],
+) -> miette::Result<()> {
+    if platforms.is_empty() {
+        return Ok(());
+    }
+
+    let environments = workspace
+        .environments()
+        .into_iter()
+        .filter(|e| e.features().any(|f| features.contains(&f.name)))
+        .collect_vec();
+    let supported_platforms: HashSet<Platform> = if environments.is_empty() {
+        workspace
+            .workspace
+            .value
+            .workspace
+            .platforms
+            .iter()
+            .copied()
+            .collect()
+    } else {
+        environments.iter().flat_map(|e| e.platforms()).collect()
+    };
+
+    let unsupported_platforms = platforms
+        .iter()
+        .filter(|platform| !supported_platforms.contains(platform))
+        .unique()
+        .collect_vec();
+    if unsupported_platforms.is_empty() {
+        return Ok(());
+    }
+
+    Err(miette::miette!(
+        help = format!(
+            "add the platform(s) to the workspace first with `pixi workspace platform add {}`",
+            unsupported_platforms.iter().format(" ")
+        ),
+        "the platform(s) {} are not supported by the workspace, supported platforms are {}",
+        unsupported_platforms
+            .iter()
+            .map(|p| consts::PLATFORM_STYLE.apply_to(p))
+            .format(", "),
+        supported_platforms
+            .iter()
+            .sorted()
+            .map(|p| consts::PLATFORM_STYLE.apply_to(p))
+            .format(", ")
+    ))
+}
+
+/// Returns a path spec for a pypi path requirement with the path relative to
+/// the workspace root, so that the manifest doesn't depend on the location of
+/// the workspace on disk. Returns `None` for other requirements.
+pub(crate) fn relative_path_spec(
+    req: &pep508_rs::Requirement,
+    root: &Path,
+    allow_external_path: bool,
+) -> miette::Result<Option<PixiPypiSpec>> {
+    let Ok(PixiPypiSpec::Path {
+        path,
+        editable,
+        extras,
+    }) = PixiPypiSpec::try_from(req.clone())
+    else {
+        return Ok(None);
+    };
+
+    let path = dunce::canonicalize(&path).unwrap_or(path);
+    let Some(relative) = relative_path(&path, root) else {
+        // There is no relative path, e.g. when on a different drive on Windows
+        return Ok(None);
+    };
+    if relative.starts_with("..") && !allow_external_path {
+        return Err(miette::miette!(
+            help = "use `--allow-external-path` to add it anyway",
+            "the path '{}' of '{}' is outside of the workspace",
+            path.display(),
+            req.name
+        ));
+    }
+
+    Ok(Some(PixiPypiSpec::Path {
+        path: relative,
+        editable,
+        extras,
+    }))
+}
+
+/// Returns `path` relative to `root` using forward slashes, so that it can be
+/// used on any platform.
+pub(crate) fn relative_path(path: &Path, root: &Path) -> Option<PathBuf> {
+    let relative = pathdiff::diff_paths(path, root)?;
+    let relative = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .join("/");
+    if relative.is_empty() {
+        Some(PathBuf::from("."))
+    } else if Path::new(&relative).starts_with("..") {
+        Some(PathBuf::from(relative))
+    } else {
+        Some(PathBuf::from(format!("./{relative}")))
+    }
+}
+
+/// Parses the contents of a file passed with `--from-file` into a list of
+/// specs.
+///
+/// Every non-empty line that is not a comment is considered a spec. Like in
+/// pip requirement files, a `#` at the start of a line or after whitespace
+/// starts a comment. Options from pip requirement files (e.g. `-r other.txt`
+/// or `-c constraints.txt`) are not supported and result in an error.
+fn parse_specs_file(contents: &str) -> miette::Result<Vec<String>> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, strip_comment(line).trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(line_number, line)| {
+            if let Some(option) = line.split_whitespace().next().filter(|o| o.starts_with('-')) {
+                let help = match option {
+                    "-r" | "--requirement" | "-c" | "--constraint" => {
+                        "include the specs of the referenced file directly or add them with a separate `--from-file`"
+                    }
+                    _ => "only specs are supported, remove the option from the file",
+                };
+                return Err(miette::miette!(
+                    help = help,
+                    "line {line_number}: '{option}' options are not supported"
+                ));
+            }
+            Ok(line.to_string())
+        })
+        .collect()
+}
+
+/// Removes the comment from a line of a specs file. A `#` that directly
+/// follows another character, like in the url fragment `#egg=name`, doesn't
+/// start a comment.
+fn strip_comment(line: &str) -> &str {
+    line.char_indices()
+        .find(|&(idx, c)| c == '#' && (idx == 0 || line[..idx].ends_with(char::is_whitespace)))
+        .map_or(line, |(idx, _)| &line[..idx])
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use std::str::FromStr;
+
+    use pixi_pypi_spec::PixiPypiSpec;
+
+    use super::{Args, parse_specs_file, relative_path, relative_path_spec};
+
+    #[test]
+    fn test_git_source_args() {
+        let args = Args::try_parse_from([
+            "add",
+            "mylib",
+            "--git",
+            "https://github.com/org/mylib",
+            "--branch",
+            "main",
+            "--subdirectory",
+            "recipe",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.dependency_config.rev.unwrap().branch.as_deref(),
+            Some("main")
+        );
+        assert_eq!(args.dependency_config.subdir.as_deref(), Some("recipe"));
+
+        // Multiple references are ambiguous
+        assert!(
+            Args::try_parse_from([
+                "add",
+                "mylib",
+                "--git",
+                "https://github.com/org/mylib",
+                "--branch",
+                "main",
+                "--rev",
+                "abcdef",
+            ])
+            .is_err()
+        );
+
+        // A reference without a git url makes no sense
+        assert!(Args::try_parse_from(["add", "mylib", "--tag", "v1"]).is_err());
+    }
+
+    #[test]
+    fn test_revalidate_args() {
+        let args = Args::try_parse_from(["add", "python", "--revalidate"]).unwrap();
+        assert!(args.prefix_update_config.revalidate);
+
+        // Revalidating an environment that is not installed makes no sense
+        assert!(Args::try_parse_from(["add", "python", "--revalidate", "--no-install"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_specs_file() {
+        let contents = r#"
+# The main dependencies
+numpy>=2 # the new api
+python 3.12.*	# pinned by the cluster
+
+  pandas
+mylib @ https://example.com/mylib.zip#egg=mylib
+"#;
+        assert_eq!(
+            parse_specs_file(contents).unwrap(),
+            vec![
+                "numpy>=2",
+                "python 3.12.*",
+                "pandas",
+                "mylib @ https://example.com/mylib.zip#egg=mylib"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_specs_file_rejects_includes() {
+        for contents in [
+            "-r other.txt",
+            "numpy\n-c constraints.txt",
+            "--requirement=a.txt",
+        ] {
+            assert!(parse_specs_file(contents).is_err(), "{contents}");
+        }
+    }
+
+    #[test]
+    fn test_relative_path_spec() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(workspace.path()).unwrap();
+        let package = root.join("libs").join("mypkg");
+        fs_err::create_dir_all(&package).unwrap();
+        let requirement = |path: &std::path::Path| {
+            let url = url::Url::from_directory_path(path).unwrap();
+            pep508_rs::Requirement::from_str(&format!("mypkg @ {url}")).unwrap()
+        };
+        let relative = |spec: Option<PixiPypiSpec>| match spec {
+            Some(PixiPypiSpec::Path { path, .. }) => path.to_string_lossy().into_owned(),
+            other => panic!("expected a path spec, got {other:?}"),
+        };
+
+        let spec = relative_path_spec(&requirement(&package), &root, false).unwrap();
+        assert_eq!(relative(spec), "./libs/mypkg");
+
+        // Paths outside of the workspace are only allowed explicitly
+        let external = root.join("libs");
+        let spec = relative_path_spec(&requirement(&external), &package, true).unwrap();
+        assert_eq!(relative(spec), "..");
+        assert!(relative_path_spec(&requirement(&external), &package, false).is_err());
+
+        // Other requirements are left as they are
+        let registry = pep508_rs::Requirement::from_str("mypkg>=1").unwrap();
+        assert!(
+            relative_path_spec(&registry, &root, false)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let root = std::env::temp_dir().join("workspace");
+        let relative = |path: &std::path::Path| {
+            relative_path(path, &root).map(|p| p.to_string_lossy().into_owned())
+        };
+
+        assert_eq!(
+            relative(&root.join("libs").join("mypkg")).as_deref(),
+            Some("./libs/mypkg")
+        );
+        assert_eq!(relative(&root).as_deref(), Some("."));
+        assert_eq!(
+            relative(&root.parent().unwrap().join("other")).as_deref(),
+            Some("../other")
+        );
+    }
+}
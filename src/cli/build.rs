@@ -8,7 +8,7 @@ use pixi_build_types::{
     ChannelConfiguration, PlatformAndVirtualPackages, procedures::conda_build::CondaBuildParams,
 };
 use pixi_command_dispatcher::{InstantiateBackendSpec, SourceCheckout, build::WorkDirKey};
-use pixi_config::ConfigCli;
+use pixi_config::{ConfigCli, ConfigCliNetwork};
 use pixi_manifest::FeaturesExt;
 use pixi_progress::global_multi_progress;
 use pixi_record::{PinnedPathSpec, PinnedSourceSpec};
@@ -31,6 +31,8 @@ pub struct Args {
 
     #[clap(flatten)]
     pub config_cli: ConfigCli,
+    #[clap(flatten)]
+    pub network_config: ConfigCliNetwork,
 
     /// The target platform to build for (defaults to the current platform)
     #[clap(long, short, default_value_t = Platform::current())]
@@ -54,7 +56,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         .with_search_start(args.project_config.workspace_locator_start())
         .with_closest_package(true)
         .locate()?
-        .with_cli_config(args.config_cli);
+        .with_cli_config(args.network_config.merge_config(args.config_cli.into()));
 
     let Some(package_manifest) = &workspace.package else {
         miette::bail!(
@@ -1,8 +1,9 @@
 use crate::DependencyType;
 use crate::Workspace;
 use crate::cli::has_specs::HasSpecs;
+use crate::diff::JsonManifestChange;
 use crate::environment::LockFileUsage;
-use crate::lock_file::UpdateMode;
+use crate::lock_file::{ReinstallPackages, UpdateMode};
 use crate::workspace::DiscoveryStart;
 use clap::Parser;
 use indexmap::IndexMap;
@@ -13,12 +14,13 @@ use pep508_rs::Requirement;
 use pixi_config::Config;
 use pixi_consts::consts;
 use pixi_manifest::FeaturesExt;
-use pixi_manifest::{FeatureName, SpecType};
+use pixi_manifest::{FeatureName, SpecType, TargetSelector};
 use pixi_spec::GitReference;
 use rattler_conda_types::ChannelConfig;
-use rattler_conda_types::{Channel, NamedChannelOrUrl, Platform};
+use rattler_conda_types::{Channel, NamedChannelOrUrl, PackageName, Platform};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::str::FromStr;
 use url::Url;
 
 use pixi_git::GIT_URL_QUERY_REV_TYPE;
@@ -134,7 +136,7 @@ pub struct PrefixUpdateConfig {
     pub no_install: bool,
 
     /// Run the complete environment validation. This will reinstall a broken environment.
-    #[arg(long, help_heading = consts::CLAP_UPDATE_OPTIONS)]
+    #[arg(long, conflicts_with = "no_install", help_heading = consts::CLAP_UPDATE_OPTIONS)]
     pub revalidate: bool,
 }
 
@@ -149,6 +151,31 @@ impl PrefixUpdateConfig {
     }
 }
 
+/// Configuration for forcing packages to be reinstalled
+#[derive(Parser, Debug, Default, Clone)]
+pub struct ReinstallConfig {
+    /// Reinstall the conda or PyPI package even if the environment is up-to-date, can be given multiple times
+    #[arg(long = "reinstall", value_name = "PACKAGE", help_heading = consts::CLAP_UPDATE_OPTIONS)]
+    pub reinstall: Vec<String>,
+
+    /// Reinstall all packages even if the environment is up-to-date
+    #[arg(long, conflicts_with = "reinstall", help_heading = consts::CLAP_UPDATE_OPTIONS)]
+    pub reinstall_all: bool,
+}
+
+impl ReinstallConfig {
+    /// The packages that are unlinked and linked again from the cache
+    pub(crate) fn reinstall_packages(&self) -> ReinstallPackages {
+        if self.reinstall_all {
+            ReinstallPackages::All
+        } else if self.reinstall.is_empty() {
+            ReinstallPackages::None
+        } else {
+            ReinstallPackages::Some(self.reinstall.iter().cloned().collect())
+        }
+    }
+}
+
 #[derive(Parser, Debug, Default, Clone)]
 pub struct GitRev {
     /// The git branch
@@ -235,14 +262,14 @@ pub struct DependencyConfig {
     #[arg(required = true, value_name = "SPEC")]
     pub specs: Vec<String>,
 
-    /// The specified dependencies are host dependencies. Conflicts with `build`
-    /// and `pypi`
-    #[arg(long, conflicts_with_all = ["build", "pypi"], hide = true)]
+    /// The specified dependencies are host dependencies of the `[package]`.
+    /// Conflicts with `build`, `pypi` and `feature`
+    #[arg(long, conflicts_with_all = ["build", "pypi", "features"])]
     pub host: bool,
 
-    /// The specified dependencies are build dependencies. Conflicts with `host`
-    /// and `pypi`
-    #[arg(long, conflicts_with_all = ["host", "pypi"], hide = true)]
+    /// The specified dependencies are build dependencies of the `[package]`.
+    /// Conflicts with `host`, `pypi` and `feature`
+    #[arg(long, conflicts_with_all = ["host", "pypi", "features"])]
     pub build: bool,
 
     /// The specified dependencies are pypi dependencies. Conflicts with `host`
@@ -255,8 +282,9 @@ pub struct DependencyConfig {
     pub platforms: Vec<Platform>,
 
     /// The feature for which the dependency should be modified.
-    #[clap(long, short, default_value_t)]
-    pub feature: FeatureName,
+    /// Multiple features can be specified by using this option multiple times.
+    #[clap(long = "feature", short, value_name = "FEATURE", default_value = consts::DEFAULT_FEATURE_NAME)]
+    pub features: Vec<FeatureName>,
 
     /// The git url to use when adding a git dependency
     #[clap(long, short, help_heading = consts::CLAP_GIT_OPTIONS)]
@@ -267,7 +295,7 @@ pub struct DependencyConfig {
     pub rev: Option<GitRev>,
 
     /// The subdirectory of the git repository to use
-    #[clap(long, short, requires = "git", help_heading = consts::CLAP_GIT_OPTIONS)]
+    #[clap(long, short, visible_alias = "subdirectory", requires = "git", help_heading = consts::CLAP_GIT_OPTIONS)]
     pub subdir: Option<String>,
 }
 
@@ -284,6 +312,25 @@ impl DependencyConfig {
         }
     }
 
+    /// Returns `true` if the dependencies are build or host dependencies, these
+    /// belong to the `[package]` of the workspace.
+    pub(crate) fn is_package_dependency(&self) -> bool {
+        matches!(
+            self.dependency_type(),
+            DependencyType::CondaDependency(SpecType::Build | SpecType::Host)
+        )
+    }
+
+    /// Returns the features that should be modified, without duplicates. If no
+    /// feature was specified, this is the default feature.
+    pub(crate) fn feature_names(&self) -> Vec<FeatureName> {
+        if self.features.is_empty() {
+            vec![FeatureName::DEFAULT]
+        } else {
+            self.features.iter().unique().cloned().collect()
+        }
+    }
+
     pub(crate) fn display_success(
         &self,
         operation: &str,
@@ -322,14 +369,129 @@ impl DependencyConfig {
             )
         }
         // Print something if we've modified for features
-        if let Some(feature) = self.feature.non_default() {
-            {
-                eprintln!(
-                    "{operation} these only for feature: {}",
-                    consts::FEATURE_STYLE.apply_to(feature)
-                )
+        let features = self.feature_names();
+        if features.iter().any(|feature| !feature.is_default()) {
+            eprintln!(
+                "{operation} these only for feature{}: {}",
+                if features.len() > 1 { "s" } else { "" },
+                features
+                    .iter()
+                    .map(|feature| consts::FEATURE_STYLE.apply_to(feature))
+                    .format(", ")
+            )
+        }
+    }
+
+    /// Returns the normalized names of the conda or pypi dependencies in this
+    /// config.
+    pub(crate) fn normalized_names(&self, workspace: &Workspace) -> miette::Result<Vec<String>> {
+        Ok(match self.dependency_type() {
+            DependencyType::CondaDependency(_) => self
+                .specs()?
+                .into_keys()
+                .map(|name| name.as_normalized().to_string())
+                .collect_vec(),
+            DependencyType::PypiDependency => self
+                .pypi_deps(workspace)?
+                .into_keys()
+                .map(|name| name.as_normalized().to_string())
+                .collect_vec(),
+        })
+    }
+
+    /// Returns how the specs of the dependencies in this config differ
+    /// between the two versions of the workspace, for every feature and
+    /// platform they were modified for.
+    pub(crate) fn manifest_changes(
+        &self,
+        before: &Workspace,
+        after: &Workspace,
+    ) -> miette::Result<Vec<JsonManifestChange>> {
+        let names = self.normalized_names(before)?;
+        let platforms = if self.platforms.is_empty() {
+            vec![None]
+        } else {
+            self.platforms.iter().copied().map(Some).collect_vec()
+        };
+
+        let mut changes = Vec::new();
+        for feature in self.feature_names() {
+            for platform in &platforms {
+                for name in &names {
+                    changes.push(self.manifest_change(
+                        before,
+                        after,
+                        self.dependency_type(),
+                        &feature,
+                        *platform,
+                        name,
+                    ));
+                }
             }
         }
+        Ok(changes)
+    }
+
+    /// Fails if one of the dependencies is not defined for one of the selected
+    /// platforms, they are removed from the `target.<platform>` tables only.
+    pub(crate) fn ensure_in_platform_targets(&self, workspace: &Workspace) -> miette::Result<()> {
+        let dependency_type = self.dependency_type();
+        let names = self.normalized_names(workspace)?;
+        for feature in self.feature_names() {
+            for platform in &self.platforms {
+                for name in &names {
+                    if manifest_spec(workspace, &feature, Some(*platform), dependency_type, name)
+                        .is_some()
+                    {
+                        continue;
+                    }
+                    let feature_suffix = if feature.is_default() {
+                        String::new()
+                    } else {
+                        format!(" of feature '{feature}'")
+                    };
+                    let message =
+                        format!("'{name}' is not a dependency of '{platform}'{feature_suffix}");
+                    return Err(
+                        if manifest_spec(workspace, &feature, None, dependency_type, name).is_some()
+                        {
+                            miette::miette!(
+                                help = "it is a dependency of all platforms, remove it without `--platform`",
+                                "{message}"
+                            )
+                        } else {
+                            miette::miette!("{message}")
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Describes how the dependency of the given type with the given
+    /// normalized name differs between two versions of the workspace.
+    pub(crate) fn manifest_change(
+        &self,
+        before: &Workspace,
+        after: &Workspace,
+        dependency_type: DependencyType,
+        feature: &FeatureName,
+        platform: Option<Platform>,
+        name: &str,
+    ) -> JsonManifestChange {
+        JsonManifestChange {
+            name: name.to_string(),
+            feature: feature.to_string(),
+            platform,
+            table: if self.is_package_dependency() {
+                format!("package.{}", dependency_type.name())
+            } else {
+                dependency_type.name().to_string()
+            },
+            before: manifest_spec(before, feature, platform, dependency_type, name),
+            after: manifest_spec(after, feature, platform, dependency_type, name),
+        }
     }
 
     pub fn vcs_pep508_requirements(
@@ -372,6 +534,49 @@ impl HasSpecs for DependencyConfig {
     }
 }
 
+/// Returns the spec of a dependency as it is written in the manifest for the
+/// given feature and platform.
+fn manifest_spec(
+    workspace: &Workspace,
+    feature: &FeatureName,
+    platform: Option<Platform>,
+    dependency_type: DependencyType,
+    name: &str,
+) -> Option<String> {
+    let target_selector = platform.map(TargetSelector::Platform);
+    if let DependencyType::CondaDependency(spec_type @ (SpecType::Build | SpecType::Host)) =
+        dependency_type
+    {
+        return workspace
+            .package
+            .as_ref()?
+            .value
+            .targets
+            .for_opt_target(target_selector.as_ref())?
+            .dependencies(spec_type)?
+            .get(&PackageName::from_str(name).ok()?)
+            .map(ToString::to_string);
+    }
+
+    let target = workspace
+        .workspace
+        .value
+        .feature(feature)?
+        .targets
+        .for_opt_target(target_selector.as_ref())?;
+    match dependency_type {
+        DependencyType::CondaDependency(spec_type) => target
+            .dependencies(spec_type)?
+            .get(&PackageName::from_str(name).ok()?)
+            .map(ToString::to_string),
+        DependencyType::PypiDependency => target
+            .pypi_dependencies
+            .as_ref()?
+            .get(&PypiPackageName::from_str(name).ok()?)
+            .map(ToString::to_string),
+    }
+}
+
 /// Builds a PEP 508 compliant VCS requirement string.
 /// Main difference between a simple VCS requirement is that it encode
 /// in a separate query parameter the reference type.
@@ -407,11 +612,85 @@ fn build_vcs_requirement(
     vcs_req
 }
 
+/// Shorthands that can be used instead of the name of a platform.
+const PLATFORM_ALIASES: &[(&str, &[Platform])] = &[
+    ("linux", &[Platform::Linux64]),
+    ("macos", &[Platform::Osx64, Platform::OsxArm64]),
+    ("osx", &[Platform::Osx64, Platform::OsxArm64]),
+    ("windows", &[Platform::Win64]),
+    (
+        "all",
+        &[
+            Platform::Linux64,
+            Platform::Osx64,
+            Platform::OsxArm64,
+            Platform::Win64,
+        ],
+    ),
+];
+
+/// Parses the platforms passed on the command line. Every value can contain
+/// multiple comma-separated platforms or an alias like `linux` or `macos`.
+///
+/// Unknown platforms are rejected with the closest known platforms as a
+/// suggestion.
+pub fn parse_platforms(values: &[String]) -> miette::Result<Vec<Platform>> {
+    let mut platforms = IndexSet::new();
+    for value in values.iter().flat_map(|value| value.split(',')) {
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        if let Some((_, aliased)) = PLATFORM_ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(value))
+        {
+            platforms.extend(aliased.iter().copied());
+            continue;
+        }
+        match Platform::from_str(value) {
+            Ok(platform) => {
+                platforms.insert(platform);
+            }
+            Err(_) => {
+                let known = Platform::all()
+                    .map(|platform| platform.as_str())
+                    .chain(PLATFORM_ALIASES.iter().map(|(alias, _)| *alias));
+                let suggestions = known
+                    .map(|name| (name, strsim::jaro(value, name)))
+                    .filter(|(_, similarity)| *similarity > 0.7)
+                    .sorted_by(|(_, a), (_, b)| b.total_cmp(a))
+                    .take(3)
+                    .map(|(name, _)| format!("'{name}'"))
+                    .collect_vec();
+                miette::bail!(
+                    help = if suggestions.is_empty() {
+                        format!(
+                            "use one of {}",
+                            Platform::all().map(|platform| platform.as_str()).join(", ")
+                        )
+                    } else {
+                        format!("did you mean {}?", suggestions.join(", "))
+                    },
+                    "'{value}' is not a known platform"
+                );
+            }
+        }
+    }
+    Ok(platforms.into_iter().collect())
+}
+
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+
+    use rattler_conda_types::Platform;
     use url::Url;
 
-    use crate::cli::cli_config::{GitRev, build_vcs_requirement};
+    use crate::{
+        Workspace,
+        cli::cli_config::{DependencyConfig, GitRev, build_vcs_requirement, parse_platforms},
+    };
 
     #[test]
     fn test_build_vcs_requirement_with_all_fields() {
@@ -476,4 +755,109 @@ mod tests {
         );
         assert_eq!(result, "mypackage @ git+file:///home/user/GitHub/mypackage");
     }
+
+    #[test]
+    fn test_manifest_changes() {
+        let before = Workspace::from_str(
+            Path::new("pixi.toml"),
+            r#"
+            [workspace]
+            name = "test"
+            channels = []
+            platforms = ["linux-64"]
+
+            [dependencies]
+            python = "3.12.*"
+            "#,
+        )
+        .unwrap();
+        let after = Workspace::from_str(
+            Path::new("pixi.toml"),
+            r#"
+            [workspace]
+            name = "test"
+            channels = []
+            platforms = ["linux-64"]
+
+            [dependencies]
+            python = ">=3.12"
+            numpy = "*"
+            "#,
+        )
+        .unwrap();
+
+        let dependency_config = DependencyConfig {
+            specs: vec!["python".to_string(), "numpy".to_string()],
+            ..Default::default()
+        };
+        let changes = dependency_config
+            .manifest_changes(&before, &after)
+            .unwrap()
+            .into_iter()
+            .map(|change| (change.name, change.feature, change.before, change.after))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                (
+                    "python".to_string(),
+                    "default".to_string(),
+                    Some("3.12.*".to_string()),
+                    Some(">=3.12".to_string())
+                ),
+                (
+                    "numpy".to_string(),
+                    "default".to_string(),
+                    None,
+                    Some("*".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_platforms() {
+        let parse = |values: &[&str]| {
+            parse_platforms(&values.iter().map(ToString::to_string).collect::<Vec<_>>())
+        };
+
+        assert_eq!(
+            parse(&["linux-64,osx-arm64", "win-64"]).unwrap(),
+            vec![Platform::Linux64, Platform::OsxArm64, Platform::Win64]
+        );
+        assert_eq!(parse(&["linux"]).unwrap(), vec![Platform::Linux64]);
+        assert_eq!(
+            parse(&["macos"]).unwrap(),
+            vec![Platform::Osx64, Platform::OsxArm64]
+        );
+        assert_eq!(parse(&["OSX"]).unwrap(), parse(&["macos"]).unwrap());
+        assert_eq!(parse(&["windows"]).unwrap(), vec![Platform::Win64]);
+        assert_eq!(
+            parse(&["all"]).unwrap(),
+            vec![
+                Platform::Linux64,
+                Platform::Osx64,
+                Platform::OsxArm64,
+                Platform::Win64
+            ]
+        );
+
+        // Duplicates are removed but the order is kept
+        assert_eq!(
+            parse(&["win-64", "all"]).unwrap(),
+            vec![
+                Platform::Win64,
+                Platform::Linux64,
+                Platform::Osx64,
+                Platform::OsxArm64
+            ]
+        );
+
+        let err = parse(&["linux-46"]).unwrap_err();
+        assert!(
+            err.help()
+                .is_some_and(|help| help.to_string().contains("'linux-64'")),
+            "{err:?}"
+        );
+    }
 }
@@ -3,7 +3,7 @@ use std::{path::Path, str::FromStr, sync::LazyLock};
 use clap::{Parser, ValueHint};
 use itertools::Itertools;
 use miette::{Context, IntoDiagnostic};
-use pixi_config::{self, Config, ConfigCli};
+use pixi_config::{self, Config, ConfigCli, ConfigCliNetwork};
 use pixi_progress::{await_in_progress, global_multi_progress, wrap_in_progress};
 use pixi_utils::{AsyncPrefixGuard, EnvironmentHash, reqwest::build_reqwest_clients};
 use rattler::{
@@ -61,11 +61,16 @@ pub struct Args {
 
     #[clap(flatten)]
     pub config: ConfigCli,
+    #[clap(flatten)]
+    pub network_config: ConfigCliNetwork,
 }
 
 /// CLI entry point for `pixi exec`
 pub async fn execute(args: Args) -> miette::Result<()> {
-    let config = Config::with_cli_config(&args.config);
+    let config = args
+        .network_config
+        .clone()
+        .merge_config(Config::with_cli_config(&args.config));
     let cache_dir = pixi_config::get_cache_dir().context("failed to determine cache directory")?;
 
     let mut command_args = args.command.iter();
@@ -1,28 +1,50 @@
 use std::{
     cmp::PartialEq,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter},
     fs,
     io::{ErrorKind, Write},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     str::FromStr,
 };
 
 use clap::{Parser, ValueEnum};
+use fancy_display::FancyDisplay;
+use indexmap::IndexMap;
+use itertools::Itertools;
 use miette::{Context, IntoDiagnostic};
 use minijinja::{Environment, context};
 use pixi_config::{Config, get_default_author};
 use pixi_consts::consts;
 use pixi_manifest::{
-    DependencyOverwriteBehavior, FeatureName, SpecType, pyproject::PyProjectManifest,
+    ChannelPriority, DependencyOverwriteBehavior, EnvironmentName, FeatureName, ManifestDocument,
+    SpecType, pyproject::PyProjectManifest,
 };
 use pixi_spec::PixiSpec;
-use pixi_utils::conda_environment_file::CondaEnvFile;
-use rattler_conda_types::{NamedChannelOrUrl, Platform};
+use pixi_utils::{
+    conda_environment_file::{CondaEnvFile, EnvironmentFile},
+    conda_lock_file::{CondaLockFile, CondaLockManager},
+    conda_prefix,
+    requirements_file::{RequirementsFileEntry, parse_requirements_file},
+};
+use rattler_conda_types::{NamedChannelOrUrl, ParseStrictness, Platform, Version, VersionSpec};
 use tokio::fs::OpenOptions;
+use toml_edit::{Array, DocumentMut, Item, Table};
 use url::Url;
 use uv_normalize::PackageName;
 
-use crate::workspace::WorkspaceMut;
+use crate::{
+    Workspace,
+    cli::{
+        add::{relative_path, relative_path_spec},
+        cli_config::parse_platforms,
+    },
+    environment::LockFileUsage,
+    global::common::channel_url_to_prioritized_channel,
+    lock_file::{UpdateLockFileOptions, lock_file_from_conda_lock},
+    prefix::Prefix,
+    workspace::WorkspaceMut,
+};
 
 #[derive(Parser, Debug, Clone, PartialEq, ValueEnum)]
 pub enum ManifestFormat {
@@ -37,14 +59,70 @@ pub enum ManifestFormat {
 /// It prepares a manifest and some helpers for the user to start working.
 ///
 /// As pixi can both work with `pixi.toml` and `pyproject.toml` files, the user can choose which one to use with `--format`.
+/// An existing `pyproject.toml` is extended with the `[tool.pixi]` tables, its
+/// `requires-python` is used as the `python` dependency.
 ///
 /// You can import an existing conda environment file with the `--import` flag.
+/// Use it multiple times to import several files, a `:<feature>` suffix imports
+/// the file into that feature and creates an environment for it, e.g.
+/// `--import base.yml --import dev.yml:dev`.
+/// A conda-lock file is imported with the requested packages pinned to their
+/// locked versions, its packages are written to the lock-file so that the
+/// environment is installed without solving it.
+///
+/// A pip requirements file can be imported with `--import-requirements`. Its
+/// requirements are added to the pypi dependencies, requirements with a
+/// `sys_platform` or `platform_system` marker are added to the targets of the
+/// matching platforms. Other markers are only supported in a `pyproject.toml`,
+/// a `pixi.toml` skips the requirements that have them.
+///
+/// A workspace can be created from a template with `--template`. The template
+/// is a built-in template, a directory or a git repository that contains a
+/// `pixi.toml`. The `{{ name }}` and `{{ platforms }}` placeholders in its files
+/// are replaced, files that already exist are never overwritten.
+///
+/// An existing conda environment can be turned into a workspace with
+/// `--from-prefix`. The packages that were explicitly requested, according to
+/// the history of the environment, are added with their installed version
+/// together with the channels they were installed from. Packages installed by
+/// pip are added to the pypi dependencies.
+///
+/// Empty features can be scaffolded with `--feature`, every feature gets an
+/// environment with the same name that also includes the default feature,
+/// unless `--no-default-feature` is given.
+///
+/// A workspace with multiple packages is created with `--workspace-member`.
+/// Every member directory gets a `pixi.toml` with a `[package]` named after
+/// the directory, unless it already has one, and the members are added as path
+/// dependencies of the workspace. Running it in an existing workspace adds the
+/// members to it.
 #[derive(Parser, Debug)]
 pub struct Args {
     /// Where to place the workspace (defaults to current path)
     #[arg(default_value = ".")]
     pub path: PathBuf,
 
+    /// The name of the workspace (defaults to the name of the directory).
+    #[arg(long, value_parser = parse_workspace_name)]
+    pub name: Option<String>,
+
+    /// The description of the workspace.
+    #[arg(long, conflicts_with = "template")]
+    pub description: Option<String>,
+
+    /// The version of the workspace (defaults to 0.1.0).
+    #[arg(long, conflicts_with = "template")]
+    pub version: Option<Version>,
+
+    /// Create an empty feature and an environment with the same name, can be
+    /// given multiple times.
+    #[arg(long = "feature", value_name = "FEATURE", value_parser = parse_feature_name, conflicts_with = "template")]
+    pub features: Vec<FeatureName>,
+
+    /// Don't include the default feature in the environments of `--feature`.
+    #[arg(long, requires = "features")]
+    pub no_default_feature: bool,
+
     /// Channel to use in the workspace.
     #[arg(
         short,
@@ -54,13 +132,69 @@ pub struct Args {
     )]
     pub channels: Option<Vec<NamedChannelOrUrl>>,
 
-    /// Platforms that the workspace supports.
+    /// The channel priority of the workspace, `strict` or `disabled`.
+    #[arg(long, value_name = "PRIORITY")]
+    pub channel_priority: Option<ChannelPriority>,
+
+    /// Platforms that the workspace supports, separated by commas or given
+    /// multiple times. `linux`, `macos`, `windows` and `all` are accepted as
+    /// shorthands.
     #[arg(short, long = "platform", id = "PLATFORM")]
     pub platforms: Vec<String>,
 
-    /// Environment.yml file to bootstrap the workspace.
-    #[arg(short = 'i', long = "import", id = "ENVIRONMENT_FILE")]
-    pub env_file: Option<PathBuf>,
+    /// Environment.yml or conda-lock file to bootstrap the workspace, append
+    /// `:<feature>` to import it into a feature.
+    #[arg(
+        short = 'i',
+        long = "import",
+        id = "ENVIRONMENT_FILE",
+        value_name = "ENVIRONMENT_FILE[:FEATURE]"
+    )]
+    pub env_files: Vec<EnvFileImport>,
+
+    /// A pip requirements file to import into the pypi dependencies.
+    #[arg(
+        long = "import-requirements",
+        value_name = "REQUIREMENTS_FILE",
+        conflicts_with = "ENVIRONMENT_FILE"
+    )]
+    pub requirements_file: Option<PathBuf>,
+
+    /// Don't solve the lock-file after importing the requirements file.
+    #[arg(long, requires = "requirements_file")]
+    pub no_lockfile_update: bool,
+
+    /// Create the workspace from a template, either the name of a built-in
+    /// template (python, rust, cpp), a path to a directory or a git url.
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        conflicts_with_all = ["ENVIRONMENT_FILE", "channels", "channel_priority", "format", "pyproject_toml"]
+    )]
+    pub template: Option<TemplateSource>,
+
+    /// Create the workspace from the packages of an existing conda environment.
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        conflicts_with_all = ["ENVIRONMENT_FILE", "requirements_file", "channels", "template", "format", "pyproject_toml"]
+    )]
+    pub from_prefix: Option<PathBuf>,
+
+    /// Add a task for every console script of an existing `pyproject.toml`,
+    /// from `[project.scripts]` or `[tool.poetry.scripts]`.
+    #[arg(long, conflicts_with_all = ["ENVIRONMENT_FILE", "template", "from_prefix"])]
+    pub import_scripts: bool,
+
+    /// Create a package in the directory and add it to the dependencies of the
+    /// workspace, can be given multiple times. Members are added to an
+    /// existing `pixi.toml` workspace.
+    #[arg(
+        long = "workspace-member",
+        value_name = "PATH",
+        conflicts_with_all = ["ENVIRONMENT_FILE", "template", "from_prefix", "pyproject_toml"]
+    )]
+    pub workspace_members: Vec<PathBuf>,
 
     /// The manifest format to create.
     #[arg(long, conflicts_with_all = ["ENVIRONMENT_FILE", "pyproject_toml"], ignore_case = true)]
@@ -71,11 +205,96 @@ pub struct Args {
     #[arg(long, conflicts_with_all = ["ENVIRONMENT_FILE", "format"], alias = "pyproject", hide = true)]
     pub pyproject_toml: bool,
 
-    /// Source Control Management used for this workspace
+    /// Don't access the network, the lock-file is not solved and templates
+    /// can't be cloned. Offline mode is enabled in the `.pixi/config.toml` of
+    /// the workspace, run `pixi lock` to solve the lock-file later on.
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Source Control Management used for this workspace, detected from the
+    /// `origin` remote of the git repository if not given. Use `none` to not
+    /// create a .gitignore and .gitattributes
     #[arg(short = 's', long = "scm", ignore_case = true)]
     pub scm: Option<GitAttributes>,
+
+    /// Don't create or update the `.gitignore` and `.gitattributes` files.
+    #[arg(long, conflicts_with = "scm")]
+    pub no_scm_files: bool,
 }
 
+/// An environment file to import, parsed from `<path>[:<feature>]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvFileImport {
+    pub path: PathBuf,
+    /// The feature to import the file into, the default feature if `None`.
+    pub feature: Option<FeatureName>,
+}
+
+impl FromStr for EnvFileImport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once(':') {
+            // A single character before the colon is a Windows drive letter
+            Some((path, feature))
+                if path.len() > 1 && !feature.is_empty() && !feature.contains(['/', '\\']) =>
+            {
+                Ok(Self {
+                    path: PathBuf::from(path),
+                    feature: Some(FeatureName::from(feature.to_string())),
+                })
+            }
+            _ => Ok(Self {
+                path: PathBuf::from(s),
+                feature: None,
+            }),
+        }
+    }
+}
+
+/// A template to create the workspace from, parsed from the name of a built-in
+/// template, a git url or a path.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateSource {
+    BuiltIn(String),
+    Git(String),
+    Path(PathBuf),
+}
+
+impl FromStr for TemplateSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if BUILTIN_TEMPLATES.iter().any(|(name, _)| *name == s) {
+            Ok(Self::BuiltIn(s.to_string()))
+        } else if let Some(url) = s.strip_prefix("git+") {
+            Ok(Self::Git(url.to_string()))
+        } else if s.starts_with("git@") || s.contains("://") {
+            Ok(Self::Git(s.to_string()))
+        } else {
+            Ok(Self::Path(PathBuf::from(s)))
+        }
+    }
+}
+
+impl Display for TemplateSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateSource::BuiltIn(name) => write!(f, "{name}"),
+            TemplateSource::Git(url) => write!(f, "{url}"),
+            TemplateSource::Path(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// The built-in templates and their manifest. They are part of the binary so
+/// that they can be used offline.
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("python", include_str!("init_templates/python/pixi.toml")),
+    ("rust", include_str!("init_templates/rust/pixi.toml")),
+    ("cpp", include_str!("init_templates/cpp/pixi.toml")),
+];
+
 /// The pixi.toml template
 ///
 /// This uses a template just to simplify the flexibility of emitting it.
@@ -84,6 +303,12 @@ const WORKSPACE_TEMPLATE: &str = r#"[workspace]
 authors = ["{{ author[0] }} <{{ author[1] }}>"]
 {%- endif %}
 channels = {{ channels }}
+{%- if channel_priority %}
+channel-priority = "{{ channel_priority }}"
+{%- endif %}
+{%- if description %}
+description = {{ description }}
+{%- endif %}
 name = "{{ name }}"
 platforms = {{ platforms }}
 version = "{{ version }}"
@@ -124,6 +349,23 @@ force-path-style = {{ s3[key]["force-path-style"] }}
 env = { {{ env_vars }} }
 {%- endif %}
 
+{%- for feature in named_features %}
+
+[feature.{{ feature }}.dependencies]
+{%- endfor %}
+
+{%- if named_features %}
+
+[environments]
+{%- for feature in named_features %}
+{%- if no_default_feature %}
+{{ feature }} = { features = ["{{ feature }}"], no-default-feature = true }
+{%- else %}
+{{ feature }} = ["{{ feature }}"]
+{%- endif %}
+{%- endfor %}
+{%- endif %}
+
 "#;
 
 /// The pyproject.toml template
@@ -135,18 +377,41 @@ const PYROJECT_TEMPLATE_EXISTING: &str = r#"
 name = "{{ name }}"
 {%- endif %}
 channels = {{ channels }}
+{%- if channel_priority %}
+channel-priority = "{{ channel_priority }}"
+{%- endif %}
 platforms = {{ platforms }}
+{%- if python %}
+
+[tool.pixi.dependencies]
+python = {{ python }}
+{%- endif %}
 
 [tool.pixi.pypi-dependencies]
 {{ name }} = { path = ".", editable = true }
-{%- for env, features in environments|items %}
-{%- if loop.first %}
+
+{%- for feature in named_features %}
+
+[tool.pixi.feature.{{ feature }}.dependencies]
+{%- endfor %}
+
+{%- if environments or named_features %}
 
 [tool.pixi.environments]
+{%- if environments %}
 default = { solve-group = "default" }
 {%- endif %}
+{%- for env, features in environments|items %}
 {{env}} = { features = {{ features }}, solve-group = "default" }
 {%- endfor %}
+{%- for feature in named_features %}
+{%- if no_default_feature %}
+{{ feature }} = { features = ["{{ feature }}"], no-default-feature = true }
+{%- else %}
+{{ feature }} = ["{{ feature }}"]
+{%- endif %}
+{%- endfor %}
+{%- endif %}
 
 {%- if s3 %}
 {%- for key in s3 %}
@@ -179,6 +444,9 @@ const NEW_PYROJECT_TEMPLATE: &str = r#"[project]
 authors = [{name = "{{ author[0] }}", email = "{{ author[1] }}"}]
 {%- endif %}
 dependencies = []
+{%- if description %}
+description = {{ description }}
+{%- endif %}
 name = "{{ name }}"
 requires-python = ">= 3.11"
 version = "{{ version }}"
@@ -189,6 +457,9 @@ requires = ["hatchling"]
 
 [tool.pixi.workspace]
 channels = {{ channels }}
+{%- if channel_priority %}
+channel-priority = "{{ channel_priority }}"
+{%- endif %}
 platforms = {{ platforms }}
 
 
@@ -221,10 +492,40 @@ force-path-style = {{ s3[key]["force-path-style"] }}
 [tool.pixi.pypi-dependencies]
 {{ pypi_package_name }} = { path = ".", editable = true }
 
+{%- for feature in named_features %}
+
+[tool.pixi.feature.{{ feature }}.dependencies]
+{%- endfor %}
+
+{%- if named_features %}
+
+[tool.pixi.environments]
+{%- for feature in named_features %}
+{%- if no_default_feature %}
+{{ feature }} = { features = ["{{ feature }}"], no-default-feature = true }
+{%- else %}
+{{ feature }} = ["{{ feature }}"]
+{%- endif %}
+{%- endfor %}
+{%- endif %}
+
 [tool.pixi.tasks]
 
 "#;
 
+/// The manifest of a package created with `--workspace-member`.
+const MEMBER_TEMPLATE: &str = r#"[package]
+name = "{{ name }}"
+version = "0.1.0"
+
+[package.build]
+backend = { name = "pixi-build-rattler-build", version = "0.1.*" }
+channels = [
+  "https://prefix.dev/pixi-build-backends",
+  "https://prefix.dev/conda-forge",
+]
+"#;
+
 const GITIGNORE_TEMPLATE: &str = r#"
 # pixi environments
 .pixi
@@ -235,21 +536,60 @@ pub enum GitAttributes {
     Github,
     Gitlab,
     Codeberg,
+    None,
 }
 
 impl GitAttributes {
-    fn template(&self) -> &'static str {
+    fn template(&self) -> Option<&'static str> {
         match self {
-            GitAttributes::Github | GitAttributes::Codeberg => {
+            GitAttributes::Github | GitAttributes::Codeberg => Some(
                 r#"# SCM syntax highlighting & preventing 3-way merges
 pixi.lock merge=binary linguist-language=YAML linguist-generated=true
-"#
-            }
-            GitAttributes::Gitlab => {
+"#,
+            ),
+            GitAttributes::Gitlab => Some(
                 r#"# GitLab syntax highlighting & preventing 3-way merges
 pixi.lock merge=binary gitlab-language=yaml gitlab-generated=true
-"#
-            }
+"#,
+            ),
+            GitAttributes::None => None,
+        }
+    }
+
+    /// Detects the provider from the url of the `origin` remote of the git
+    /// repository that contains `dir`. Returns `None` if `dir` is not in a git
+    /// repository or the provider is unknown.
+    fn detect(dir: &Path) -> Option<Self> {
+        let output = std::process::Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["remote", "get-url", "origin"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Self::from_remote_url(String::from_utf8_lossy(&output.stdout).trim())
+    }
+
+    /// Returns the provider of a remote url, self-hosted GitLab instances are
+    /// recognized by a host that contains `gitlab`.
+    fn from_remote_url(url: &str) -> Option<Self> {
+        // Both `https://host/org/repo` and `git@host:org/repo` urls
+        let host = url
+            .split_once("://")
+            .map_or(url, |(_, rest)| rest)
+            .split(['/', ':'])
+            .next()?;
+        let host = host.rsplit('@').next()?.to_lowercase();
+        if host == "github.com" || host.ends_with(".github.com") {
+            Some(GitAttributes::Github)
+        } else if host.contains("gitlab") {
+            Some(GitAttributes::Gitlab)
+        } else if host == "codeberg.org" {
+            Some(GitAttributes::Codeberg)
+        } else {
+            None
         }
     }
 }
@@ -262,8 +602,6 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     let pixi_manifest_path = dir.join(consts::WORKSPACE_MANIFEST);
     let pyproject_manifest_path = dir.join(consts::PYPROJECT_MANIFEST);
     let mojoproject_manifest_path = dir.join(consts::MOJOPROJECT_MANIFEST);
-    let gitignore_path = dir.join(".gitignore");
-    let gitattributes_path = dir.join(".gitattributes");
     let config = Config::load_global();
 
     // Deprecation warning for the `pyproject` option
@@ -276,74 +614,205 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         );
     }
 
-    let default_name = get_name_from_dir(&dir).unwrap_or_else(|_| String::from("new_workspace"));
-    let version = "0.1.0";
+    if !args.workspace_members.is_empty() {
+        if matches!(
+            args.format,
+            Some(ManifestFormat::Pyproject | ManifestFormat::Mojoproject)
+        ) {
+            miette::bail!(
+                "workspace members can only be added to a '{}' manifest",
+                consts::WORKSPACE_MANIFEST
+            );
+        }
+
+        // An existing workspace only gets the additional members
+        if pixi_manifest_path.is_file() {
+            add_workspace_members(&pixi_manifest_path, &args.workspace_members)?;
+            if !args.no_scm_files {
+                write_scm_files(&dir, args.scm);
+            }
+            return Ok(());
+        }
+    }
+
+    let default_name = match &args.name {
+        Some(name) => name.clone(),
+        None => get_name_from_dir(&dir).unwrap_or_else(|_| String::from("new_workspace")),
+    };
+    let version = args
+        .version
+        .as_ref()
+        .map_or_else(|| String::from("0.1.0"), ToString::to_string);
+    // Render the description as a toml string, it can contain any character
+    let description = args
+        .description
+        .as_deref()
+        .map(|description| toml_edit::Value::from(description).to_string());
     let author = get_default_author();
+    let named_features = args.features.iter().unique().cloned().collect_vec();
     let platforms = if args.platforms.is_empty() {
         vec![Platform::current().to_string()]
     } else {
-        args.platforms.clone()
+        parse_platforms(&args.platforms)?
+            .iter()
+            .map(ToString::to_string)
+            .collect()
     };
 
-    // Create a 'pixi.toml' manifest and populate it by importing a conda
-    // environment file
-    if let Some(env_file_path) = args.env_file {
+    if let (true, Some(TemplateSource::Git(url))) = (args.offline, &args.template) {
+        miette::bail!(
+            help = "clone the repository and pass its path to '--template' instead",
+            "can't clone the template '{url}' while offline"
+        );
+    }
+
+    // Create a 'pixi.toml' manifest and populate it by importing conda
+    // environment files
+    let manifest_path = if let Some(template) = &args.template {
+        let skipped = apply_template(template, &dir, &default_name, &platforms)?;
+        for path in &skipped {
+            tracing::warn!(
+                "not copying '{}' from the template, the file already exists",
+                path.display()
+            );
+        }
+        // An existing manifest is never overwritten by the template
+        if skipped
+            .iter()
+            .any(|path| path == Path::new(consts::WORKSPACE_MANIFEST))
+        {
+            eprintln!(
+                "{}Applied template '{}', kept the existing {}",
+                console::style(console::Emoji("✔ ", "")).green(),
+                template,
+                pixi_manifest_path.display()
+            );
+        } else {
+            eprintln!(
+                "{}Created {} from template '{}'",
+                console::style(console::Emoji("✔ ", "")).green(),
+                pixi_manifest_path.display(),
+                template
+            );
+        }
+        pixi_manifest_path
+    } else if let Some(prefix) = &args.from_prefix {
         // Check if the 'pixi.toml' file doesn't already exist. We don't want to
         // overwrite it.
         if pixi_manifest_path.is_file() {
             miette::bail!("{} already exists", consts::WORKSPACE_MANIFEST);
         }
 
-        let env_file = CondaEnvFile::from_path(&env_file_path)?;
-        let name = env_file
-            .name()
-            .unwrap_or(default_name.clone().as_str())
-            .to_string();
+        let records = Prefix::new(prefix).find_installed_packages()?;
+        if records.is_empty() {
+            miette::bail!(
+                "'{}' is not a conda environment, it doesn't contain any packages",
+                prefix.display()
+            );
+        }
+        let records = match conda_prefix::requested_packages(prefix)? {
+            Some(requested) => records
+                .iter()
+                .filter(|record| requested.contains(&record.repodata_record.package_record.name))
+                .collect_vec(),
+            // Without a history, every package that no other package depends on
+            // was requested
+            None => {
+                let dependencies: HashSet<&str> = records
+                    .iter()
+                    .flat_map(|record| &record.repodata_record.package_record.depends)
+                    .filter_map(|spec| spec.split([' ', '=', '<', '>', '!', '~', '[']).next())
+                    .collect();
+                records
+                    .iter()
+                    .filter(|record| {
+                        !dependencies
+                            .contains(record.repodata_record.package_record.name.as_normalized())
+                    })
+                    .collect_vec()
+            }
+        };
+        let pip_packages = conda_prefix::pip_installed_packages(prefix)?;
+
+        // Order the channels by the number of packages that were installed from them
+        let mut channel_counts: Vec<(NamedChannelOrUrl, usize)> = Vec::new();
+        let mut unattributed = Vec::new();
+        for record in &records {
+            let Some(channel) = record
+                .repodata_record
+                .channel
+                .as_deref()
+                .filter(|channel| !channel.is_empty())
+            else {
+                unattributed.push(record.repodata_record.package_record.name.as_source());
+                continue;
+            };
+            let channel =
+                channel_url_to_prioritized_channel(channel, config.global_channel_config())?
+                    .channel;
+            match channel_counts.iter_mut().find(|(c, _)| c == &channel) {
+                Some((_, count)) => *count += 1,
+                None => channel_counts.push((channel, 1)),
+            }
+        }
+        channel_counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let mut channels = channel_counts
+            .into_iter()
+            .map(|(channel, _)| channel)
+            .collect_vec();
+        if channels.is_empty() {
+            channels = config.default_channels();
+        }
 
-        let env_vars = env_file.variables();
-        // TODO: Improve this:
-        //  - Use .condarc as channel config
-        let (conda_deps, pypi_deps, channels) = env_file.to_manifest(&config)?;
         let rendered_workspace_template = render_workspace(
             &env,
-            name,
-            version,
+            default_name,
+            &version,
+            description.as_deref(),
             author.as_ref(),
             channels,
+            args.channel_priority.as_ref(),
             &platforms,
             None,
             &vec![],
             config.s3_options,
-            Some(&env_vars),
+            None,
+            &named_features,
+            args.no_default_feature,
         );
         let mut workspace =
             WorkspaceMut::from_template(pixi_manifest_path, rendered_workspace_template)?;
-        let channel_config = workspace.workspace().channel_config();
-        for spec in conda_deps {
-            // Determine the name of the package to add
-            let (Some(name), spec) = spec.clone().into_nameless() else {
-                miette::bail!(
-                    "{} does not support wildcard dependencies",
-                    pixi_utils::executable_name()
-                );
-            };
-            let spec = PixiSpec::from_nameless_matchspec(spec, &channel_config);
+        for record in &records {
+            let package_record = &record.repodata_record.package_record;
+            let spec = VersionSpec::from_str(
+                &format!("=={}", package_record.version),
+                ParseStrictness::Lenient,
+            )
+            .into_diagnostic()?;
             workspace.manifest().add_dependency(
-                &name,
-                &spec,
+                &package_record.name,
+                &PixiSpec::Version(spec),
                 SpecType::Run,
-                // No platforms required as you can't define them in the yaml
                 &[],
-                &FeatureName::default(),
+                &FeatureName::DEFAULT,
                 DependencyOverwriteBehavior::Overwrite,
             )?;
         }
-        for requirement in pypi_deps {
+        for package in &pip_packages {
+            let requirement = match pep508_rs::Requirement::from_str(&format!(
+                "{}=={}",
+                package.name, package.version
+            )) {
+                Ok(requirement) => requirement,
+                Err(err) => {
+                    tracing::warn!("ignoring the pip package '{}': {err}", package.name);
+                    continue;
+                }
+            };
             workspace.manifest().add_pep508_dependency(
                 (&requirement, None),
-                // No platforms required as you can't define them in the yaml
                 &[],
-                &FeatureName::default(),
+                &FeatureName::DEFAULT,
                 None,
                 DependencyOverwriteBehavior::Overwrite,
                 None,
@@ -351,6 +820,289 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         }
         let workspace = workspace.save().await.into_diagnostic()?;
 
+        eprintln!(
+            "{}Imported {} conda package{} and {} pypi package{} from '{}'",
+            console::style(console::Emoji("✔ ", "")).green(),
+            records.len(),
+            if records.len() == 1 { "" } else { "s" },
+            pip_packages.len(),
+            if pip_packages.len() == 1 { "" } else { "s" },
+            prefix.display()
+        );
+        if !unattributed.is_empty() {
+            tracing::warn!(
+                "the channel of {} is unknown, they are resolved from the workspace channels",
+                unattributed
+                    .iter()
+                    .map(|name| format!("'{name}'"))
+                    .join(", ")
+            );
+        }
+        eprintln!(
+            "{}Created {}",
+            console::style(console::Emoji("✔ ", "")).green(),
+            workspace.workspace.provenance.path.display()
+        );
+        workspace.workspace.provenance.path
+    } else if let Some(conda_lock) = conda_lock_import(&args.env_files)? {
+        // Check if the 'pixi.toml' file doesn't already exist. We don't want to
+        // overwrite it.
+        if pixi_manifest_path.is_file() {
+            miette::bail!("{} already exists", consts::WORKSPACE_MANIFEST);
+        }
+
+        // The platforms of the lock-file are the platforms of the workspace
+        let lock_platforms = conda_lock.platforms();
+        if !args.platforms.is_empty() {
+            tracing::warn!("ignoring the platforms, the platforms of the conda-lock file are used");
+        }
+        let mut channels = conda_lock.channels()?;
+        if channels.is_empty() {
+            channels = config.default_channels();
+        }
+
+        let rendered_workspace_template = render_workspace(
+            &env,
+            default_name,
+            &version,
+            description.as_deref(),
+            author.as_ref(),
+            channels,
+            args.channel_priority.as_ref(),
+            &lock_platforms.iter().map(ToString::to_string).collect(),
+            None,
+            &vec![],
+            config.s3_options,
+            None,
+            &named_features,
+            args.no_default_feature,
+        );
+        let mut workspace =
+            WorkspaceMut::from_template(pixi_manifest_path, rendered_workspace_template)?;
+
+        // Pin the requested packages to their locked version, packages that are
+        // locked differently per platform are added to the targets
+        for manager in [CondaLockManager::Conda, CondaLockManager::Pip] {
+            let mut versions: IndexMap<&str, Vec<(Platform, &str)>> = IndexMap::new();
+            for &platform in &lock_platforms {
+                for package in conda_lock.requested_packages(platform, manager) {
+                    versions
+                        .entry(package.name.as_str())
+                        .or_default()
+                        .push((platform, package.version.as_str()));
+                }
+            }
+            for (name, versions) in versions {
+                let targets = if versions.len() == lock_platforms.len()
+                    && versions.iter().map(|(_, version)| version).all_equal()
+                {
+                    vec![(Vec::new(), versions[0].1)]
+                } else {
+                    versions
+                        .iter()
+                        .map(|&(platform, version)| (vec![platform], version))
+                        .collect()
+                };
+                for (platforms, version) in targets {
+                    if manager == CondaLockManager::Conda {
+                        let spec = VersionSpec::from_str(
+                            &format!("=={version}"),
+                            ParseStrictness::Lenient,
+                        )
+                        .into_diagnostic()?;
+                        workspace.manifest().add_dependency(
+                            &rattler_conda_types::PackageName::from_str(name).into_diagnostic()?,
+                            &PixiSpec::Version(spec),
+                            SpecType::Run,
+                            &platforms,
+                            &FeatureName::DEFAULT,
+                            DependencyOverwriteBehavior::Overwrite,
+                        )?;
+                    } else {
+                        let requirement =
+                            pep508_rs::Requirement::from_str(&format!("{name}=={version}"))
+                                .into_diagnostic()?;
+                        workspace.manifest().add_pep508_dependency(
+                            (&requirement, None),
+                            &platforms,
+                            &FeatureName::DEFAULT,
+                            None,
+                            DependencyOverwriteBehavior::Overwrite,
+                            None,
+                        )?;
+                    }
+                }
+            }
+        }
+        let workspace = workspace.save().await.into_diagnostic()?;
+
+        // Write the locked packages so that the environment is installed without
+        // solving it
+        let lock_file = lock_file_from_conda_lock(&workspace, &conda_lock)?;
+        let lock_file_path = workspace.lock_file_path();
+        workspace.write_lock_file(&lock_file)?;
+
+        eprintln!(
+            "{}Created {} and {}",
+            console::style(console::Emoji("✔ ", "")).green(),
+            workspace.workspace.provenance.path.display(),
+            lock_file_path.display()
+        );
+        workspace.workspace.provenance.path
+    } else if !args.env_files.is_empty() {
+        // Check if the 'pixi.toml' file doesn't already exist. We don't want to
+        // overwrite it.
+        if pixi_manifest_path.is_file() {
+            miette::bail!("{} already exists", consts::WORKSPACE_MANIFEST);
+        }
+
+        // Every file is imported into its own feature
+        let mut env_files: Vec<(FeatureName, &Path, CondaEnvFile)> = Vec::new();
+        for import in &args.env_files {
+            let feature = import.feature.clone().unwrap_or_default();
+            if let Some((_, other, _)) = env_files.iter().find(|(f, _, _)| f == &feature) {
+                miette::bail!(
+                    help = "append `:<feature>` to the path to import a file into another feature",
+                    "both '{}' and '{}' are imported into feature {}",
+                    other.display(),
+                    import.path.display(),
+                    feature.fancy_display()
+                );
+            }
+            let env_file = CondaEnvFile::from_path(&import.path)?;
+            for key in env_file.unsupported_keys() {
+                tracing::warn!(
+                    "ignoring the unsupported key '{key}' of '{}'",
+                    import.path.display()
+                );
+            }
+            if !feature.is_default() && !env_file.variables().is_empty() {
+                tracing::warn!(
+                    "ignoring the variables of '{}', only the variables of the file that is imported into the default feature are used",
+                    import.path.display()
+                );
+            }
+            env_files.push((feature, import.path.as_path(), env_file));
+        }
+
+        // The default feature determines the name and the variables
+        let default_env_file = env_files
+            .iter()
+            .find(|(feature, _, _)| feature.is_default())
+            .map(|(_, _, env_file)| env_file);
+        let name = args
+            .name
+            .as_deref()
+            .or_else(|| {
+                default_env_file
+                    .or(env_files.first().map(|(_, _, env_file)| env_file))
+                    .and_then(|env_file| env_file.name())
+            })
+            .unwrap_or(default_name.as_str())
+            .to_string();
+        let env_vars = default_env_file
+            .map(|env_file| env_file.variables())
+            .unwrap_or_default();
+
+        // TODO: Improve this:
+        //  - Use .condarc as channel config
+        let mut channels = Vec::new();
+        let mut dependencies = Vec::new();
+        for (feature, _, env_file) in &env_files {
+            let (conda_deps, pypi_deps, file_channels) = env_file.to_dependencies_and_channels()?;
+            channels.extend(file_channels);
+            dependencies.push((feature, conda_deps, pypi_deps));
+        }
+        let mut channels = channels.into_iter().unique().collect_vec();
+        if channels.is_empty() {
+            channels = config.default_channels();
+        }
+
+        let rendered_workspace_template = render_workspace(
+            &env,
+            name,
+            &version,
+            description.as_deref(),
+            author.as_ref(),
+            channels,
+            args.channel_priority.as_ref(),
+            &platforms,
+            None,
+            &vec![],
+            config.s3_options,
+            Some(&env_vars),
+            &named_features,
+            args.no_default_feature,
+        );
+        let mut workspace =
+            WorkspaceMut::from_template(pixi_manifest_path, rendered_workspace_template)?;
+        let channel_config = workspace.workspace().channel_config();
+        for (feature, conda_deps, pypi_deps) in dependencies {
+            for spec in conda_deps {
+                // Determine the name of the package to add
+                let (Some(name), spec) = spec.clone().into_nameless() else {
+                    miette::bail!(
+                        "{} does not support wildcard dependencies",
+                        pixi_utils::executable_name()
+                    );
+                };
+                let spec = PixiSpec::from_nameless_matchspec(spec, &channel_config);
+                workspace.manifest().add_dependency(
+                    &name,
+                    &spec,
+                    SpecType::Run,
+                    // No platforms required as you can't define them in the yaml
+                    &[],
+                    feature,
+                    DependencyOverwriteBehavior::Overwrite,
+                )?;
+            }
+            for requirement in pypi_deps {
+                workspace.manifest().add_pep508_dependency(
+                    (&requirement, None),
+                    // No platforms required as you can't define them in the yaml
+                    &[],
+                    feature,
+                    None,
+                    DependencyOverwriteBehavior::Overwrite,
+                    None,
+                )?;
+            }
+        }
+
+        // Register an environment for every feature
+        for (feature, path, _) in &env_files {
+            if feature.is_default() || named_features.contains(feature) {
+                continue;
+            }
+            if workspace
+                .workspace()
+                .workspace
+                .value
+                .feature(feature)
+                .is_none()
+            {
+                tracing::warn!(
+                    "not creating an environment for '{}', it does not contain any dependencies",
+                    path.display()
+                );
+                continue;
+            }
+            let environment_name = EnvironmentName::normalized(feature.as_str())
+                .into_diagnostic()
+                .wrap_err(format!(
+                    "cannot create an environment for '{}'",
+                    path.display()
+                ))?;
+            workspace.manifest().add_environment(
+                environment_name.to_string(),
+                Some(vec![feature.to_string()]),
+                None,
+                false,
+            )?;
+        }
+        let workspace = workspace.save().await.into_diagnostic()?;
+
         eprintln!(
             "{}Created {}",
             console::style(console::Emoji("✔ ", "")).green(),
@@ -358,9 +1110,10 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             // is.
             workspace.workspace.provenance.path.display()
         );
+        workspace.workspace.provenance.path
     } else {
         let channels = if let Some(channels) = args.channels {
-            channels
+            normalize_channels(channels, &dir)?
         } else {
             config.default_channels().to_vec()
         };
@@ -398,20 +1151,39 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         if pyproject && pyproject_manifest_path.is_file() {
             let pyproject = PyProjectManifest::from_path(&pyproject_manifest_path)?;
 
-            // Early exit if 'pyproject.toml' already contains a '[tool.pixi.workspace]' table
+            // Never duplicate the tables of an existing workspace
             if pyproject.has_pixi_table() {
-                eprintln!(
-                    "{}Nothing to do here: 'pyproject.toml' already contains a '[tool.pixi.workspace]' section.",
-                    console::style(console::Emoji("🤔 ", "")).blue(),
+                miette::bail!(
+                    help = "edit the '[tool.pixi]' tables of the manifest instead",
+                    "'{}' already contains a '[tool.pixi]' table",
+                    pyproject_manifest_path.display()
                 );
-                return Ok(());
             }
 
             let (name, pixi_name) = match pyproject.name() {
-                Some(name) => (name, false),
+                Some(name) => {
+                    if args.name.as_deref().is_some_and(|other| other != name) {
+                        miette::bail!(
+                            help = "change the name in '[project]' instead",
+                            "the name '{}' does not match the name '{name}' of '{}'",
+                            default_name,
+                            pyproject_manifest_path.display()
+                        );
+                    }
+                    (name, false)
+                }
                 None => (default_name.as_str(), true),
             };
+            if args.description.is_some() || args.version.is_some() {
+                tracing::warn!(
+                    "ignoring the description and version, the '[project]' table of '{}' is used instead",
+                    pyproject_manifest_path.display()
+                );
+            }
             let environments = pyproject.environments_from_extras().into_diagnostic()?;
+            let python = pyproject
+                .requires_python_spec()
+                .map(|spec| spec.to_toml_value().to_string().trim().to_string());
             let rv = env
                 .render_named_str(
                     consts::PYPROJECT_MANIFEST,
@@ -420,46 +1192,100 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                         name,
                         pixi_name,
                         channels,
+                        channel_priority => args.channel_priority.as_ref().map(ToString::to_string),
                         platforms,
+                        python,
                         environments,
+                        named_features => named_features
+                            .iter()
+                            .filter(|feature| !environments.contains_key(feature.as_str()))
+                            .collect_vec(),
+                        no_default_feature => args.no_default_feature,
                         s3 => relevant_s3_options(config.s3_options, channels),
                     },
                 )
                 .expect("should be able to render the template");
-            if let Err(e) = {
-                fs::OpenOptions::new()
-                    .append(true)
-                    .open(pyproject_manifest_path.clone())
-                    .and_then(|mut p| p.write_all(rv.as_bytes()))
-            } {
-                tracing::warn!(
-                    "Warning, couldn't update '{}' because of: {}",
-                    pyproject_manifest_path.to_string_lossy(),
-                    e
-                );
-            } else {
-                // Inform about the addition of the package itself as an editable dependency of
-                // the workspace
-                eprintln!(
-                    "{}Added package '{}' as an editable dependency.",
-                    console::style(console::Emoji("✔ ", "")).green(),
-                    name
-                );
-                // Inform about the addition of environments from optional dependencies
-                // or dependency groups (if any)
-                if !environments.is_empty() {
-                    let envs: Vec<&str> = environments.keys().map(AsRef::as_ref).collect();
-                    eprintln!(
-                        "{}Added environment{} '{}' from optional dependencies or dependency groups.",
-                        console::style(console::Emoji("✔ ", "")).green(),
-                        if envs.len() > 1 { "s" } else { "" },
-                        envs.join("', '")
-                    )
-                }
-            }
+            let pixi_tables = rv
+                .parse::<DocumentMut>()
+                .expect("the template should render valid toml");
 
-            // Create a 'pyproject.toml' manifest
+            // Insert the tables into the existing document, so that its content
+            // and formatting are preserved
+            let mut document = fs_err::read_to_string(&pyproject_manifest_path)
+                .into_diagnostic()?
+                .parse::<DocumentMut>()
+                .into_diagnostic()
+                .wrap_err_with(|| {
+                    format!("failed to parse '{}'", pyproject_manifest_path.display())
+                })?;
+            let mut added_tables = Vec::new();
+            merge_tables(
+                document.as_table_mut(),
+                pixi_tables.as_table(),
+                "",
+                &mut added_tables,
+            )?;
+            let imported_tasks = if args.import_scripts {
+                import_console_scripts(&mut document, name)
+            } else {
+                Vec::new()
+            };
+            fs_err::write(&pyproject_manifest_path, document.to_string()).into_diagnostic()?;
+
+            eprintln!(
+                "{}Added {} to '{}'",
+                console::style(console::Emoji("✔ ", "")).green(),
+                added_tables
+                    .iter()
+                    .map(|table| format!("[{table}]"))
+                    .join(", "),
+                pyproject_manifest_path.display()
+            );
+            // Inform about the addition of the package itself as an editable dependency of
+            // the workspace
+            eprintln!(
+                "{}Added package '{}' as an editable dependency.",
+                console::style(console::Emoji("✔ ", "")).green(),
+                name
+            );
+            if !imported_tasks.is_empty() {
+                eprintln!(
+                    "{}Added task{} '{}' for the console scripts.",
+                    console::style(console::Emoji("✔ ", "")).green(),
+                    if imported_tasks.len() > 1 { "s" } else { "" },
+                    imported_tasks.join("', '")
+                );
+            }
+            let dependency_count = pyproject.dependency_count();
+            if dependency_count > 0 {
+                eprintln!(
+                    "{}Using the {dependency_count} requirement{} of '[project.dependencies]' as pypi dependencies.",
+                    console::style(console::Emoji("✔ ", "")).green(),
+                    if dependency_count > 1 { "s" } else { "" },
+                );
+            }
+            // Inform about the addition of environments from optional dependencies
+            // or dependency groups (if any)
+            if !environments.is_empty() {
+                let envs: Vec<&str> = environments.keys().map(AsRef::as_ref).collect();
+                eprintln!(
+                    "{}Added environment{} '{}' from optional dependencies or dependency groups.",
+                    console::style(console::Emoji("✔ ", "")).green(),
+                    if envs.len() > 1 { "s" } else { "" },
+                    envs.join("', '")
+                )
+            }
+            pyproject_manifest_path
+
+            // Create a 'pyproject.toml' manifest
         } else if pyproject {
+            if args.import_scripts {
+                tracing::warn!(
+                    "ignoring '--import-scripts', there is no '{}' to import scripts from",
+                    consts::PYPROJECT_MANIFEST
+                );
+            }
+
             // Python package names cannot contain '-', so we replace them with '_'
             let pypi_package_name = PackageName::from_str(&default_name)
                 .map(|name| name.as_dist_info_name().to_string())
@@ -473,8 +1299,12 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                         name => default_name,
                         pypi_package_name,
                         version,
+                        description,
+                        named_features,
+                        no_default_feature => args.no_default_feature,
                         author,
                         channels,
+                        channel_priority => args.channel_priority.as_ref().map(ToString::to_string),
                         platforms,
                         index_url => index_url.as_ref(),
                         extra_index_urls => &extra_index_urls,
@@ -507,6 +1337,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                     });
                 }
             };
+            pyproject_manifest_path
 
         // Create a 'pixi.toml' manifest
         } else {
@@ -517,68 +1348,477 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             };
 
             // Check if the manifest file doesn't already exist. We don't want to
-            // overwrite it.
+            // overwrite it, re-running init only updates the SCM files.
             if path.is_file() {
-                miette::bail!("{} already exists", consts::WORKSPACE_MANIFEST);
+                if args.no_scm_files || args.requirements_file.is_some() {
+                    miette::bail!("{} already exists", consts::WORKSPACE_MANIFEST);
+                }
+                write_scm_files(&dir, args.scm);
+                eprintln!(
+                    "{}{} already exists, only the SCM files were updated",
+                    console::style(console::Emoji("✔ ", "")).green(),
+                    path.display()
+                );
+                return Ok(());
+            }
+            if args.import_scripts {
+                tracing::warn!(
+                    "ignoring '--import-scripts', scripts are only imported when extending an existing '{}'",
+                    consts::PYPROJECT_MANIFEST
+                );
             }
 
             let rv = render_workspace(
                 &env,
                 default_name,
-                version,
+                &version,
+                description.as_deref(),
                 author.as_ref(),
                 channels,
+                args.channel_priority.as_ref(),
                 &platforms,
                 index_url.as_ref(),
                 &extra_index_urls,
                 config.s3_options,
                 None,
+                &named_features,
+                args.no_default_feature,
             );
             save_manifest_file(&path, rv)?;
-        };
+            path
+        }
+    };
+
+    if !args.workspace_members.is_empty() {
+        if manifest_path != pixi_manifest_path {
+            miette::bail!(
+                "workspace members can only be added to a '{}' manifest",
+                consts::WORKSPACE_MANIFEST
+            );
+        }
+        add_workspace_members(&manifest_path, &args.workspace_members)?;
     }
 
-    // create a .gitignore if one is missing
-    if let Err(e) = create_or_append_file(&gitignore_path, GITIGNORE_TEMPLATE.trim_start()) {
-        tracing::warn!(
-            "Warning, couldn't update '{}' because of: {}",
-            gitignore_path.to_string_lossy(),
-            e
-        );
+    if !args.no_scm_files {
+        write_scm_files(&dir, args.scm);
+    }
+
+    if args.offline {
+        write_offline_config(&dir)?;
+    }
+
+    if let Some(requirements_file) = &args.requirements_file {
+        import_requirements_file(
+            &manifest_path,
+            requirements_file,
+            args.no_lockfile_update || args.offline,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Enables `network.offline` in the `.pixi/config.toml` of the workspace, the
+/// other settings of an existing config are kept.
+fn write_offline_config(dir: &Path) -> miette::Result<()> {
+    let path = dir.join(consts::PIXI_DIR).join(consts::CONFIG_FILE);
+    let mut config = if path.is_file() {
+        Config::from_path(&path).into_diagnostic()?
+    } else {
+        Config::default()
+    };
+    config.network.offline = Some(true);
+    config.save(&path)?;
+    eprintln!(
+        "{}Enabled offline mode in '{}', run `{}` once the network is available",
+        console::style(console::Emoji("ℹ️ ", "")).yellow(),
+        path.display(),
+        console::style(format!(
+            "{} config unset --local network.offline",
+            pixi_utils::executable_name()
+        ))
+        .bold()
+    );
+    Ok(())
+}
+
+/// Returns the conda-lock file that is imported, a conda-lock file can only be
+/// imported on its own into the default feature.
+fn conda_lock_import(imports: &[EnvFileImport]) -> miette::Result<Option<CondaLockFile>> {
+    let mut conda_lock = None;
+    for import in imports {
+        if let EnvironmentFile::CondaLock(lock_file) = EnvironmentFile::from_path(&import.path)? {
+            if imports.len() > 1 || import.feature.is_some() {
+                miette::bail!(
+                    "the conda-lock file '{}' can only be imported on its own into the default feature",
+                    import.path.display()
+                );
+            }
+            conda_lock = Some(lock_file);
+        }
+    }
+    Ok(conda_lock)
+}
+
+/// Adds the requirements of a pip requirements file to the pypi dependencies
+/// of the default feature and solves the lock-file.
+async fn import_requirements_file(
+    manifest_path: &Path,
+    requirements_file: &Path,
+    no_lockfile_update: bool,
+) -> miette::Result<()> {
+    let entries = parse_requirements_file(requirements_file)
+        .wrap_err_with(|| format!("failed to import '{}'", requirements_file.display()))?;
+
+    let mut workspace = Workspace::from_path(manifest_path)?.modify()?;
+    let platforms = workspace
+        .workspace()
+        .workspace
+        .value
+        .workspace
+        .platforms
+        .clone();
+    let is_pyproject = matches!(workspace.document(), ManifestDocument::PyProjectToml(_));
+    let mut skipped = 0;
+    for entry in &entries {
+        match entry {
+            RequirementsFileEntry::Named {
+                requirement,
+                platform_marker,
+            } => {
+                // A requirement for a single operating system is added to the
+                // targets of the platforms of that operating system
+                let target_platforms = platform_marker
+                    .iter()
+                    .flat_map(|marker| platforms.iter().filter(|p| marker.matches(**p)))
+                    .copied()
+                    .collect_vec();
+                let requirement = match platform_marker {
+                    Some(marker) if target_platforms.is_empty() => {
+                        pep508_rs::Requirement::from_str(&format!(
+                            "{requirement} ; {}",
+                            marker.marker
+                        ))
+                        .into_diagnostic()?
+                    }
+                    _ => requirement.clone(),
+                };
+
+                // Only the dependencies of a pyproject.toml support markers,
+                // dropping the marker would install the requirement everywhere
+                if !requirement.marker.is_true() && !is_pyproject {
+                    let marker = requirement.marker.try_to_string().unwrap_or_default();
+                    if platform_marker.is_some() && target_platforms.is_empty() {
+                        tracing::warn!(
+                            "skipping '{}', none of the platforms of the workspace match '{marker}'",
+                            requirement.name,
+                        );
+                    } else {
+                        tracing::warn!(
+                            "skipping '{}', the marker '{marker}' can't be written to {}, use `--format pyproject` to keep it",
+                            requirement.name,
+                            consts::WORKSPACE_MANIFEST
+                        );
+                    }
+                    skipped += 1;
+                    continue;
+                }
+
+                workspace.manifest().add_pep508_dependency(
+                    (&requirement, None),
+                    &target_platforms,
+                    &FeatureName::DEFAULT,
+                    None,
+                    DependencyOverwriteBehavior::Overwrite,
+                    None,
+                )?;
+            }
+            RequirementsFileEntry::Path {
+                path,
+                extras,
+                editable,
+            } => {
+                let path = dunce::canonicalize(path)
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("failed to import '{}'", path.display()))?;
+                let name = PyProjectManifest::from_path(&path.join(consts::PYPROJECT_MANIFEST))
+                    .ok()
+                    .and_then(|pyproject| pyproject.name().map(str::to_string))
+                    .or_else(|| {
+                        path.file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                    })
+                    .ok_or_else(|| {
+                        miette::miette!("can't determine the package name of '{}'", path.display())
+                    })?;
+                let url = Url::from_directory_path(&path)
+                    .map_err(|_| miette::miette!("'{}' is not a valid path", path.display()))?;
+                let extras = if extras.is_empty() {
+                    String::new()
+                } else {
+                    format!("[{}]", extras.join(","))
+                };
+                let requirement =
+                    pep508_rs::Requirement::from_str(&format!("{name}{extras} @ {url}"))
+                        .into_diagnostic()?;
+                let pixi_req =
+                    relative_path_spec(&requirement, workspace.workspace().root(), true)?;
+                workspace.manifest().add_pep508_dependency(
+                    (&requirement, pixi_req.as_ref()),
+                    &[],
+                    &FeatureName::DEFAULT,
+                    editable.then_some(true),
+                    DependencyOverwriteBehavior::Overwrite,
+                    None,
+                )?;
+            }
+        }
     }
+    let workspace = workspace.save().await.into_diagnostic()?;
 
-    let git_attributes = args.scm.unwrap_or(GitAttributes::Github);
+    let imported = entries.len() - skipped;
+    eprintln!(
+        "{}Imported {} requirement{} from '{}'{}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        imported,
+        if imported == 1 { "" } else { "s" },
+        requirements_file.display(),
+        if skipped > 0 {
+            format!(", skipped {skipped}")
+        } else {
+            String::new()
+        }
+    );
 
-    // create a .gitattributes if one is missing
-    if let Err(e) = create_or_append_file(&gitattributes_path, git_attributes.template()) {
-        tracing::warn!(
-            "Warning, couldn't update '{}' because of: {}",
-            gitattributes_path.to_string_lossy(),
-            e
+    if no_lockfile_update {
+        eprintln!(
+            "{}Run `{}` to solve the lock-file.",
+            console::style(console::Emoji("ℹ️ ", "")).yellow(),
+            console::style(format!("{} lock", pixi_utils::executable_name())).bold()
         );
+    } else {
+        workspace
+            .update_lock_file(UpdateLockFileOptions {
+                lock_file_usage: LockFileUsage::Update,
+                no_install: true,
+                max_concurrent_solves: workspace.config().max_concurrent_solves(),
+                no_prune: false,
+            })
+            .await?;
     }
 
     Ok(())
 }
 
+/// Copies the tables and values of `source` into `target` without touching the
+/// existing content of `target`. The dotted names of the tables that are added
+/// are pushed to `added`.
+fn merge_tables(
+    target: &mut Table,
+    source: &Table,
+    prefix: &str,
+    added: &mut Vec<String>,
+) -> miette::Result<()> {
+    for (key, item) in source.iter() {
+        let name = if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        let Item::Table(table) = item else {
+            target.insert(key, item.clone());
+            continue;
+        };
+        let is_new = !target.contains_key(key);
+        let Some(target_table) = target
+            .entry(key)
+            .or_insert_with(|| {
+                let mut table = Table::new();
+                table.set_implicit(true);
+                Item::Table(table)
+            })
+            .as_table_mut()
+        else {
+            miette::bail!("can't add the '[{name}]' table, '{name}' is not a table");
+        };
+        if is_new && !table.is_implicit() {
+            target_table.set_implicit(false);
+            added.push(name.clone());
+        }
+        merge_tables(target_table, table, &name, added)?;
+    }
+    Ok(())
+}
+
+/// Writes the files of the template to `dir` and returns the files that were
+/// skipped because they already exist.
+fn apply_template(
+    template: &TemplateSource,
+    dir: &Path,
+    name: &str,
+    platforms: &[String],
+) -> miette::Result<Vec<PathBuf>> {
+    let files = template_files(template)?;
+    if !files
+        .iter()
+        .any(|(path, _)| path == Path::new(consts::WORKSPACE_MANIFEST))
+    {
+        miette::bail!(
+            "the template '{template}' does not contain a {}",
+            consts::WORKSPACE_MANIFEST
+        );
+    }
+
+    let platforms = format!(
+        "[{}]",
+        platforms
+            .iter()
+            .map(|platform| format!("\"{platform}\""))
+            .join(", ")
+    );
+    let mut skipped = Vec::new();
+    for (relative_path, contents) in files {
+        let path = dir.join(&relative_path);
+        if path.exists() {
+            skipped.push(relative_path);
+            continue;
+        }
+        // Placeholders are only replaced in text files
+        let contents = match String::from_utf8(contents) {
+            Ok(text) => text
+                .replace("{{ name }}", name)
+                .replace("{{ platforms }}", &platforms)
+                .into_bytes(),
+            Err(err) => err.into_bytes(),
+        };
+        if let Some(parent) = path.parent() {
+            fs_err::create_dir_all(parent).into_diagnostic()?;
+        }
+        fs_err::write(&path, contents).into_diagnostic()?;
+    }
+    Ok(skipped)
+}
+
+/// Returns the files of the template, with their path relative to the root of
+/// the template.
+fn template_files(template: &TemplateSource) -> miette::Result<Vec<(PathBuf, Vec<u8>)>> {
+    match template {
+        TemplateSource::BuiltIn(name) => {
+            let (_, manifest) = BUILTIN_TEMPLATES
+                .iter()
+                .find(|(builtin, _)| builtin == name)
+                .expect("only built-in templates are parsed as built-in");
+            Ok(vec![(
+                PathBuf::from(consts::WORKSPACE_MANIFEST),
+                manifest.as_bytes().to_vec(),
+            )])
+        }
+        TemplateSource::Path(path) => {
+            if !path.is_dir() {
+                miette::bail!(
+                    help = format!(
+                        "the built-in templates are: {}",
+                        BUILTIN_TEMPLATES.iter().map(|(name, _)| name).join(", ")
+                    ),
+                    "'{}' is not a built-in template or a directory",
+                    path.display()
+                );
+            }
+            read_template_dir(path)
+        }
+        TemplateSource::Git(url) => {
+            let checkout = tempfile::tempdir().into_diagnostic()?;
+            let output = std::process::Command::new("git")
+                .args(["clone", "--depth", "1", "--quiet", url])
+                .arg(checkout.path())
+                .output()
+                .into_diagnostic()
+                .wrap_err("failed to run git")?;
+            if !output.status.success() {
+                miette::bail!(
+                    "failed to clone the template '{url}': {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            read_template_dir(checkout.path())
+        }
+    }
+}
+
+fn read_template_dir(root: &Path) -> miette::Result<Vec<(PathBuf, Vec<u8>)>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs_err::read_dir(&dir).into_diagnostic()? {
+            let path = entry.into_diagnostic()?.path();
+            if path.file_name().is_some_and(|name| name == ".git") {
+                continue;
+            }
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                let contents = fs_err::read(&path).into_diagnostic()?;
+                let relative_path = path
+                    .strip_prefix(root)
+                    .expect("the file is in the template")
+                    .to_path_buf();
+                files.push((relative_path, contents));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Normalizes the channels passed on the command line. Duplicates are removed
+/// while keeping the order, as the order determines the priority, and relative
+/// paths are made relative to the workspace instead of the current directory.
+fn normalize_channels(
+    channels: Vec<NamedChannelOrUrl>,
+    dir: &Path,
+) -> miette::Result<Vec<NamedChannelOrUrl>> {
+    let current_dir = std::env::current_dir().into_diagnostic()?;
+    Ok(channels
+        .into_iter()
+        .map(|channel| match channel {
+            NamedChannelOrUrl::Path(path) if path.is_relative() => {
+                let path = current_dir.join(path.as_str());
+                let path = relative_path(&path, dir).unwrap_or(path);
+                NamedChannelOrUrl::Path(path.to_string_lossy().as_ref().into())
+            }
+            channel => channel,
+        })
+        .unique()
+        .collect())
+}
+
 #[allow(clippy::too_many_arguments)]
 fn render_workspace(
     env: &Environment<'_>,
     name: String,
     version: &str,
+    description: Option<&str>,
     author: Option<&(String, String)>,
     channels: Vec<NamedChannelOrUrl>,
+    channel_priority: Option<&ChannelPriority>,
     platforms: &Vec<String>,
     index_url: Option<&Url>,
     extra_index_urls: &Vec<Url>,
     s3_options: HashMap<String, pixi_config::S3Options>,
     env_vars: Option<&HashMap<String, String>>,
+    named_features: &[FeatureName],
+    no_default_feature: bool,
 ) -> String {
     let ctx = context! {
+        named_features,
+        no_default_feature,
         name,
         version,
+        description,
         author,
         channels,
+        channel_priority => channel_priority.map(ToString::to_string),
         platforms,
         index_url,
         extra_index_urls,
@@ -632,6 +1872,244 @@ fn save_manifest_file(path: &Path, content: String) -> miette::Result<()> {
     Ok(())
 }
 
+/// Creates a package manifest in every member directory that doesn't have one
+/// yet and adds the members as path dependencies of the workspace. The
+/// `pixi-build` preview feature is enabled as it is required for source
+/// dependencies.
+fn add_workspace_members(manifest_path: &Path, members: &[PathBuf]) -> miette::Result<()> {
+    let root = manifest_path
+        .parent()
+        .expect("the manifest must have a parent directory");
+    let mut document = fs_err::read_to_string(manifest_path)
+        .into_diagnostic()?
+        .parse::<DocumentMut>()
+        .into_diagnostic()?;
+
+    let workspace_table = if document.contains_key("workspace") {
+        "workspace"
+    } else {
+        "project"
+    };
+    let Some(workspace) = document
+        .get_mut(workspace_table)
+        .and_then(Item::as_table_like_mut)
+    else {
+        miette::bail!(
+            "'{}' doesn't contain a '[workspace]' table",
+            manifest_path.display()
+        );
+    };
+    match workspace.get_mut("preview") {
+        Some(Item::Value(toml_edit::Value::Array(preview))) => {
+            if !preview
+                .iter()
+                .any(|value| value.as_str() == Some("pixi-build"))
+            {
+                preview.push("pixi-build");
+            }
+        }
+        // `preview = true` enables every preview feature
+        Some(_) => {}
+        None => {
+            workspace.insert(
+                "preview",
+                toml_edit::value(Array::from_iter(["pixi-build"])),
+            );
+        }
+    }
+
+    for member in members.iter().unique() {
+        let member_path = member_path(member)?;
+        let member_dir = root.join(&member_path);
+        let member_manifest_path = member_dir.join(consts::WORKSPACE_MANIFEST);
+
+        let name = if member_manifest_path.is_file() {
+            let name = fs_err::read_to_string(&member_manifest_path)
+                .into_diagnostic()?
+                .parse::<DocumentMut>()
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to parse '{}'", member_manifest_path.display()))?
+                .get("package")
+                .and_then(|package| package.get("name"))
+                .and_then(Item::as_str)
+                .map(ToString::to_string);
+            let Some(name) = name else {
+                miette::bail!(
+                    "'{}' already exists but doesn't contain a '[package]' with a name",
+                    member_manifest_path.display()
+                );
+            };
+            eprintln!(
+                "{}Using the existing package '{}' of {}",
+                console::style(console::Emoji("ℹ️ ", "")).yellow(),
+                console::style(&name).bold(),
+                member_manifest_path.display()
+            );
+            name
+        } else {
+            let name = member_dir
+                .file_name()
+                .map(|name| normalize_package_name(&name.to_string_lossy()))
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| {
+                    miette::miette!("can't derive a package name from '{}'", member.display())
+                })?;
+            fs_err::create_dir_all(&member_dir).into_diagnostic()?;
+            let rv = Environment::new()
+                .render_named_str(
+                    consts::WORKSPACE_MANIFEST,
+                    MEMBER_TEMPLATE,
+                    context! { name },
+                )
+                .expect("should be able to render the template");
+            save_manifest_file(&member_manifest_path, rv)?;
+            name
+        };
+
+        let dependencies = document
+            .entry("dependencies")
+            .or_insert_with(|| Item::Table(Table::new()))
+            .as_table_like_mut()
+            .ok_or_else(|| miette::miette!("'[dependencies]' is not a table"))?;
+        let mut spec = toml_edit::InlineTable::new();
+        spec.insert("path", member_path.into());
+        dependencies.insert(&name, toml_edit::value(spec));
+    }
+
+    fs_err::write(manifest_path, document.to_string()).into_diagnostic()?;
+
+    // Make sure the members are valid packages of the workspace
+    Workspace::from_path(manifest_path)?;
+    eprintln!(
+        "{}Added {} to the dependencies of {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        members
+            .iter()
+            .unique()
+            .map(|member| console::style(member.display()).bold())
+            .join(", "),
+        manifest_path.display()
+    );
+    Ok(())
+}
+
+/// Returns the path of a workspace member relative to the root of the
+/// workspace, with forward slashes so that the manifest works on every
+/// platform.
+fn member_path(member: &Path) -> miette::Result<String> {
+    let mut components = Vec::new();
+    for component in member.components() {
+        match component {
+            Component::Normal(component) => components.push(component.to_string_lossy()),
+            Component::CurDir => {}
+            _ => miette::bail!(
+                "the workspace member '{}' must be a relative path inside the workspace",
+                member.display()
+            ),
+        }
+    }
+    if components.is_empty() {
+        miette::bail!("the root of the workspace can't be a workspace member");
+    }
+    Ok(components.join("/"))
+}
+
+/// Adds a task to `[tool.pixi.tasks]` for every console script of the
+/// `[project.scripts]` and `[tool.poetry.scripts]` tables. The name of the
+/// project is stripped from the task names, e.g. the script `myapp-serve` of
+/// `myapp` becomes the task `serve`.
+///
+/// Returns the names of the added tasks, tasks that already exist are skipped.
+fn import_console_scripts(document: &mut DocumentMut, project_name: &str) -> Vec<String> {
+    let scripts = [
+        document
+            .get("project")
+            .and_then(|project| project.get("scripts")),
+        document
+            .get("tool")
+            .and_then(|tool| tool.get("poetry"))
+            .and_then(|poetry| poetry.get("scripts")),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(Item::as_table_like)
+    .flat_map(|scripts| scripts.iter().map(|(script, _)| script.to_string()))
+    .unique()
+    .collect_vec();
+
+    let Some(tasks) = document
+        .get_mut("tool")
+        .and_then(|tool| tool.get_mut("pixi"))
+        .and_then(|pixi| pixi.get_mut("tasks"))
+        .and_then(Item::as_table_mut)
+    else {
+        return Vec::new();
+    };
+
+    let prefix = format!("{}-", project_name.to_lowercase().replace('_', "-"));
+    let mut added = Vec::new();
+    for script in scripts {
+        let task = script
+            .strip_prefix(&prefix)
+            .filter(|task| !task.is_empty())
+            .unwrap_or(&script);
+        if tasks.contains_key(task) {
+            tracing::warn!(
+                "not adding a task for the script '{script}', the task '{task}' already exists"
+            );
+            continue;
+        }
+        tasks.insert(task, toml_edit::value(&script));
+        added.push(task.to_string());
+    }
+    added
+}
+
+/// Parses the name of a feature to create, which is also the name of its
+/// environment.
+fn parse_feature_name(name: &str) -> Result<FeatureName, String> {
+    if name == consts::DEFAULT_FEATURE_NAME {
+        return Err(format!("'{name}' is the name of the default feature"));
+    }
+    EnvironmentName::from_str(name).map_err(|err| err.to_string())?;
+    Ok(FeatureName::from(name))
+}
+
+/// Parses the name of the workspace, only lowercase package names are allowed.
+fn parse_workspace_name(name: &str) -> Result<String, String> {
+    match rattler_conda_types::PackageName::from_str(name) {
+        Ok(package_name) if package_name.as_normalized() == name => Ok(name.to_string()),
+        _ => {
+            let suggestion = normalize_package_name(name);
+            let mut message = format!(
+                "'{name}' is not a valid workspace name, use only lowercase letters, numbers, '-', '_' and '.'"
+            );
+            if !suggestion.is_empty() {
+                message.push_str(&format!(", e.g. '{suggestion}'"));
+            }
+            Err(message)
+        }
+    }
+}
+
+/// Turns a name into a valid package name by lowercasing it and replacing
+/// unsupported characters with a `-`.
+fn normalize_package_name(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
 fn get_name_from_dir(path: &Path) -> miette::Result<String> {
     Ok(path
         .file_name()
@@ -645,17 +2123,68 @@ fn get_name_from_dir(path: &Path) -> miette::Result<String> {
 
 // When the specific template is not in the file or the file does not exist.
 // Make the file and append the template to the file.
-fn create_or_append_file(path: &Path, template: &str) -> std::io::Result<()> {
-    let file = fs_err::read_to_string(path).unwrap_or_default();
+/// Adds the pixi lines to the `.gitignore` and to the `.gitattributes` of the
+/// SCM provider, which is detected from the git remote if it isn't given.
+/// Existing files are extended, re-running it doesn't change them.
+fn write_scm_files(dir: &Path, scm: Option<GitAttributes>) {
+    let git_attributes = scm
+        .or_else(|| GitAttributes::detect(dir))
+        .unwrap_or(GitAttributes::Github);
+    let Some(template) = git_attributes.template() else {
+        return;
+    };
 
-    if !file.contains(template) {
-        fs::OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(path)?
-            .write_all(template.as_bytes())?;
+    for (path, template) in [
+        (dir.join(".gitignore"), GITIGNORE_TEMPLATE.trim_start()),
+        (dir.join(".gitattributes"), template),
+    ] {
+        if let Err(e) = append_missing_lines(&path, template) {
+            tracing::warn!(
+                "Warning, couldn't update '{}' because of: {}",
+                path.to_string_lossy(),
+                e
+            );
+        }
     }
-    Ok(())
+}
+
+/// Appends the lines of the template that the file doesn't contain yet, the
+/// file is created if it doesn't exist. Lines are compared without
+/// surrounding whitespace and trailing slashes, so `.pixi/` matches `.pixi`.
+fn append_missing_lines(path: &Path, template: &str) -> std::io::Result<()> {
+    let contents = match fs_err::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+
+    let normalize = |line: &str| line.trim().trim_end_matches('/').to_string();
+    let existing: HashSet<String> = contents.lines().map(normalize).collect();
+    let missing = template
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !existing.contains(&normalize(line)))
+        .collect_vec();
+    // Only comments are missing, the file already has the pixi lines
+    if missing
+        .iter()
+        .all(|line| line.trim_start().starts_with('#'))
+    {
+        return Ok(());
+    }
+
+    let mut addition = String::new();
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        addition.push('\n');
+    }
+    for line in missing {
+        addition.push_str(line);
+        addition.push('\n');
+    }
+    fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)?
+        .write_all(addition.as_bytes())
 }
 
 #[cfg(test)]
@@ -667,10 +2196,10 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_create_or_append_file() {
+    fn test_append_missing_lines() {
         let dir = tempdir().unwrap();
         let file_path = dir.path().join("test_file.txt");
-        let template = "Test Template";
+        let template = "# Comment\nTest Template\n";
 
         fn read_file_content(path: &Path) -> String {
             let mut file = fs_err::File::open(path).unwrap();
@@ -680,25 +2209,59 @@ mod tests {
         }
 
         // Scenario 1: File does not exist.
-        create_or_append_file(&file_path, template).unwrap();
+        append_missing_lines(&file_path, template).unwrap();
         assert_eq!(read_file_content(&file_path), template);
 
         // Scenario 2: File exists but doesn't contain the template.
-        create_or_append_file(&file_path, "New Content").unwrap();
-        assert!(read_file_content(&file_path).contains(template));
-        assert!(read_file_content(&file_path).contains("New Content"));
+        append_missing_lines(&file_path, "New Content").unwrap();
+        assert_eq!(
+            read_file_content(&file_path),
+            "# Comment\nTest Template\nNew Content\n"
+        );
 
         // Scenario 3: File exists and already contains the template.
         let original_content = read_file_content(&file_path);
-        create_or_append_file(&file_path, template).unwrap();
+        append_missing_lines(&file_path, template).unwrap();
         assert_eq!(read_file_content(&file_path), original_content);
 
-        // Scenario 4: Path is a folder not a file, give an error.
-        assert!(create_or_append_file(dir.path(), template).is_err());
+        // Scenario 4: Only the missing lines are added to existing content.
+        fs_err::write(&file_path, "*.log\n.pixi/").unwrap();
+        append_missing_lines(&file_path, "# pixi environments\n.pixi\n*.pyc\n").unwrap();
+        assert_eq!(
+            read_file_content(&file_path),
+            "*.log\n.pixi/\n# pixi environments\n*.pyc\n"
+        );
+
+        // Scenario 5: Path is a folder not a file, give an error.
+        assert!(append_missing_lines(dir.path(), template).is_err());
 
         dir.close().unwrap();
     }
 
+    #[test]
+    fn test_env_file_import_from_str() {
+        let import = |s: &str| EnvFileImport::from_str(s).unwrap();
+        assert_eq!(
+            import("dev.yml:dev"),
+            EnvFileImport {
+                path: PathBuf::from("dev.yml"),
+                feature: Some(FeatureName::from("dev".to_string())),
+            }
+        );
+        assert_eq!(import("environment.yml").feature, None);
+        assert_eq!(
+            import(r"C:\envs\environment.yml"),
+            EnvFileImport {
+                path: PathBuf::from(r"C:\envs\environment.yml"),
+                feature: None,
+            }
+        );
+        assert_eq!(
+            import(r"C:\envs\gpu.yml:gpu").path,
+            PathBuf::from(r"C:\envs\gpu.yml")
+        );
+    }
+
     #[test]
     fn test_multiple_format_values() {
         let test_cases = vec![
@@ -729,6 +2292,7 @@ mod tests {
             ("codeberg", GitAttributes::Codeberg),
             ("CoDeBeRg", GitAttributes::Codeberg),
             ("CODEBERG", GitAttributes::Codeberg),
+            ("none", GitAttributes::None),
         ];
 
         for (input, expected) in test_cases {
@@ -737,6 +2301,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_scm_from_remote_url() {
+        let test_cases = vec![
+            (
+                "https://github.com/prefix-dev/pixi.git",
+                Some(GitAttributes::Github),
+            ),
+            (
+                "git@github.com:prefix-dev/pixi.git",
+                Some(GitAttributes::Github),
+            ),
+            ("https://gitlab.com/org/repo", Some(GitAttributes::Gitlab)),
+            (
+                "ssh://git@gitlab.example.org:2222/org/repo.git",
+                Some(GitAttributes::Gitlab),
+            ),
+            (
+                "https://codeberg.org/org/repo",
+                Some(GitAttributes::Codeberg),
+            ),
+            ("https://git.example.org/org/repo", None),
+        ];
+
+        for (url, expected) in test_cases {
+            assert_eq!(GitAttributes::from_remote_url(url), expected, "{url}");
+        }
+    }
+
     #[test]
     fn test_invalid_scm_values() {
         let invalid_values = vec!["invalid", "", "git", "bitbucket", "mercurial", "svn"];
@@ -750,4 +2342,178 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_import_console_scripts() {
+        let mut document = r#"
+[project]
+name = "my_app"
+
+[project.scripts]
+my-app-serve = "my_app.server:main"
+migrate = "my_app.db:migrate"
+
+[tool.poetry.scripts]
+serve = "my_app.other:main"
+migrate = "my_app.db:migrate"
+
+[tool.pixi.tasks]
+"#
+        .parse::<DocumentMut>()
+        .unwrap();
+
+        let added = import_console_scripts(&mut document, "my_app");
+        assert_eq!(added, vec!["serve", "migrate"]);
+        let tasks = document["tool"]["pixi"]["tasks"].as_table().unwrap();
+        assert_eq!(tasks["serve"].as_str(), Some("my-app-serve"));
+        assert_eq!(tasks["migrate"].as_str(), Some("migrate"));
+    }
+
+    #[test]
+    fn test_workspace_name() {
+        for name in ["my-project", "my_project", "project.2"] {
+            assert_eq!(parse_workspace_name(name).as_deref(), Ok(name));
+        }
+
+        let err = parse_workspace_name("My Project").unwrap_err();
+        assert!(err.contains("e.g. 'my-project'"), "{err}");
+        assert!(parse_workspace_name("").is_err());
+        assert!(Args::try_parse_from(["init", "--name", "src dir"]).is_err());
+    }
+
+    #[test]
+    fn test_member_path() {
+        assert_eq!(
+            member_path(Path::new("./packages/core")).unwrap(),
+            "packages/core"
+        );
+        assert!(member_path(Path::new("../core")).is_err());
+        assert!(member_path(Path::new(".")).is_err());
+        assert_eq!(normalize_package_name("My Package"), "my-package");
+    }
+
+    #[test]
+    fn test_feature_name() {
+        assert_eq!(parse_feature_name("test"), Ok(FeatureName::from("test")));
+        assert!(parse_feature_name("default").is_err());
+        assert!(parse_feature_name("Test Feature").is_err());
+        assert!(Args::try_parse_from(["init", "--no-default-feature"]).is_err());
+
+        let args =
+            Args::try_parse_from(["init", "--feature", "test", "--no-default-feature"]).unwrap();
+        assert_eq!(args.features, vec![FeatureName::from("test")]);
+        assert!(args.no_default_feature);
+    }
+
+    #[test]
+    fn test_template_source_from_str() {
+        assert_eq!(
+            "python".parse(),
+            Ok(TemplateSource::BuiltIn("python".to_string()))
+        );
+        assert_eq!(
+            "git+https://github.com/org/template".parse(),
+            Ok(TemplateSource::Git(
+                "https://github.com/org/template".to_string()
+            ))
+        );
+        assert_eq!(
+            "git@github.com:org/template.git".parse(),
+            Ok(TemplateSource::Git(
+                "git@github.com:org/template.git".to_string()
+            ))
+        );
+        assert_eq!(
+            "./python".parse(),
+            Ok(TemplateSource::Path(PathBuf::from("./python")))
+        );
+    }
+
+    #[test]
+    fn test_apply_template() {
+        let template = tempdir().unwrap();
+        fs_err::write(
+            template.path().join(consts::WORKSPACE_MANIFEST),
+            "[workspace]\nname = \"{{ name }}\"\nplatforms = {{ platforms }}\n",
+        )
+        .unwrap();
+        fs_err::create_dir_all(template.path().join("src")).unwrap();
+        fs_err::write(template.path().join("src/main.py"), "print('{{ name }}')\n").unwrap();
+        fs_err::write(template.path().join("README.md"), "template readme").unwrap();
+
+        let dir = tempdir().unwrap();
+        fs_err::write(dir.path().join("README.md"), "existing readme").unwrap();
+        let skipped = apply_template(
+            &TemplateSource::Path(template.path().to_path_buf()),
+            dir.path(),
+            "my-project",
+            &["linux-64".to_string(), "win-64".to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(skipped, vec![PathBuf::from("README.md")]);
+        assert_eq!(
+            fs_err::read_to_string(dir.path().join("README.md")).unwrap(),
+            "existing readme"
+        );
+        assert_eq!(
+            fs_err::read_to_string(dir.path().join(consts::WORKSPACE_MANIFEST)).unwrap(),
+            "[workspace]\nname = \"my-project\"\nplatforms = [\"linux-64\", \"win-64\"]\n"
+        );
+        assert_eq!(
+            fs_err::read_to_string(dir.path().join("src/main.py")).unwrap(),
+            "print('my-project')\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_tables() {
+        let mut document = r#"[project]
+name = "foo" # the name
+
+[tool.ruff]
+line-length = 100
+
+[build-system]
+requires = ["setuptools"]
+"#
+        .parse::<DocumentMut>()
+        .unwrap();
+        let pixi_tables = r#"
+[tool.pixi.workspace]
+platforms = ["linux-64"]
+
+[tool.pixi.tasks]
+"#
+        .parse::<DocumentMut>()
+        .unwrap();
+
+        let mut added = Vec::new();
+        merge_tables(
+            document.as_table_mut(),
+            pixi_tables.as_table(),
+            "",
+            &mut added,
+        )
+        .unwrap();
+
+        assert_eq!(added, vec!["tool.pixi.workspace", "tool.pixi.tasks"]);
+        assert_eq!(
+            document.to_string(),
+            r#"[project]
+name = "foo" # the name
+
+[tool.ruff]
+line-length = 100
+
+[tool.pixi.workspace]
+platforms = ["linux-64"]
+
+[tool.pixi.tasks]
+
+[build-system]
+requires = ["setuptools"]
+"#
+        );
+    }
 }
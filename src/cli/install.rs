@@ -1,13 +1,28 @@
-use clap::Parser;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, ValueEnum};
 use fancy_display::FancyDisplay;
 use itertools::Itertools;
-use pixi_config::ConfigCli;
+use miette::{Context, Diagnostic, IntoDiagnostic};
+use pixi_config::{Config, ConfigCli, ConfigCliNetwork};
+use rattler_conda_types::PackageName;
+use thiserror::Error;
 
 use crate::{
-    UpdateLockFileOptions, WorkspaceLocator,
-    cli::cli_config::WorkspaceConfig,
-    environment::get_update_lock_file_and_prefixes,
-    lock_file::{ReinstallPackages, UpdateMode},
+    UpdateLockFileOptions, Workspace, WorkspaceLocator,
+    build::GlobHashCache,
+    cli::cli_config::{ReinstallConfig, WorkspaceConfig},
+    diff::LockFileDiff,
+    environment::{
+        LockFileUsage, PostInstallTaskFailed, get_update_lock_file_and_prefixes,
+        print_pending_operations, run_post_install_tasks, sanity_check_workspace,
+    },
+    lock_file::{
+        FrozenLockFileError, InstallFilter, ReinstallPackages, UpdateContext, UpdateMode,
+        verify_frozen_environment,
+    },
+    reporters::json_lines::{self, EventContext},
+    workspace::Environment,
 };
 
 /// Install an environment, both updating the lockfile and installing the
@@ -16,19 +31,43 @@ use crate::{
 /// This command installs an environment, if the lockfile is not up-to-date it
 /// will be updated.
 ///
-/// `pixi install` only installs one environment at a time,
-/// if you have multiple environments you can select the right one with the
-/// `--environment` flag. If you don't provide an environment, the `default`
-/// environment will be installed.
+/// If you have multiple environments you can select the ones to install with
+/// the `--environment` flag, which can be given multiple times. If you don't
+/// provide an environment, the `default` environment will be installed.
 ///
 /// If you want to install all environments, you can use the `--all` flag.
+/// Environments are installed concurrently, `--concurrency` limits how many
+/// are installed at once. When one of them fails the others are still
+/// installed, and the failures are reported at the end.
 ///
 /// Running `pixi install` is not required before running other commands like
 /// `pixi run` or `pixi shell`. These commands will automatically install the
 /// environment if it is not already installed.
 ///
 /// You can use `pixi reinstall` to reinstall all environments, one environment
-/// or just some packages of an environment.
+/// or just some packages of an environment. The `--reinstall` and
+/// `--reinstall-all` flags do the same while installing.
+///
+/// With `--skip` locked conda packages are left out of the environment, for
+/// instance when the host already provides them. Other commands keep skipping
+/// these packages until `pixi install` is run with a different set.
+///
+/// The `post-install` tasks of an environment run after its packages changed,
+/// unless `--no-hooks` is given.
+///
+/// With `--verify` the size and digest of every installed file are checked
+/// against the records in `conda-meta`, and packages with missing or modified
+/// files are linked again.
+///
+/// With `--dry-run` nothing is downloaded or modified, instead the conda and
+/// PyPI packages that would be linked, changed, reinstalled or unlinked are
+/// printed per environment. It exits with code `5` if installing would change
+/// anything, and `0` if the environments are up-to-date.
+///
+/// With `--target-prefix` a single environment is installed into another
+/// directory, e.g. to build a container image. The directory is not managed by
+/// pixi, other commands keep using the environment in the workspace. Use
+/// `pixi shell-hook --target-prefix` to activate it.
 #[derive(Parser, Debug)]
 pub struct Args {
     #[clap(flatten)]
@@ -37,88 +76,459 @@ pub struct Args {
     #[clap(flatten)]
     pub lock_file_usage: super::LockFileUsageConfig,
 
-    /// The environment to install
+    /// The environment to install, can be given multiple times
     #[arg(long, short)]
-    pub environment: Option<Vec<String>>,
+    pub environment: Vec<String>,
 
     #[clap(flatten)]
     pub config: ConfigCli,
+    #[clap(flatten)]
+    pub network_config: ConfigCliNetwork,
+
+    #[clap(flatten)]
+    pub reinstall_config: ReinstallConfig,
 
     /// Install all environments
     #[arg(long, short, conflicts_with = "environment")]
     pub all: bool,
+
+    /// Don't install the locked conda package with this exact name, can be
+    /// given multiple times
+    #[arg(long, value_name = "PACKAGE")]
+    pub skip: Vec<PackageName>,
+
+    /// How progress is reported, `json-lines` replaces the progress bars with
+    /// one JSON event per line on stdout
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+
+    /// Don't run the `post-install` tasks of the environments
+    #[arg(long)]
+    pub no_hooks: bool,
+
+    /// The maximum number of environments to install at once, defaults to the
+    /// `concurrency.installs` configuration
+    #[arg(long, value_name = "N")]
+    pub concurrency: Option<usize>,
+
+    /// Install the environment into this directory instead of the workspace,
+    /// the directory is not managed by pixi
+    #[arg(long, value_name = "PATH", conflicts_with = "all")]
+    pub target_prefix: Option<PathBuf>,
+
+    /// Install into a `--target-prefix` that is not empty
+    #[arg(long, requires = "target_prefix")]
+    pub force: bool,
+
+    /// Check the content of every installed file, and link the packages with
+    /// missing or modified files again
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Print the packages that would be linked or unlinked without
+    /// downloading or modifying anything, exits with `5` if anything would
+    /// change
+    #[arg(long, conflicts_with_all = ["target_prefix", "verify"])]
+    pub dry_run: bool,
+}
+
+/// The exit code of `--dry-run` if installing would change the environments.
+pub const PENDING_CHANGES_EXIT_CODE: i32 = 5;
+
+/// Returned by `--dry-run` if installing would change the environments.
+#[derive(Debug, Error, Diagnostic)]
+#[error("installing would change the lock-file or the environments")]
+pub struct PendingChanges;
+
+/// The format in which `pixi install` reports its progress.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Progress bars on stderr
+    #[default]
+    Human,
+    /// One JSON object per event on stdout
+    JsonLines,
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
+    if args.output_format == OutputFormat::JsonLines {
+        json_lines::enable_json_lines();
+    }
+
+    let mut config = args.network_config.merge_config(Config::from(args.config));
+    if let Some(concurrency) = args.concurrency {
+        config.concurrency.installs = concurrency;
+    }
+
     let workspace = WorkspaceLocator::for_cli()
         .with_search_start(args.project_config.workspace_locator_start())
         .locate()?
-        .with_cli_config(args.config);
+        .with_cli_config(config);
 
     // Install either:
     //
     // 1. specific environments
     // 2. all environments
     // 3. default environment (if no environments are specified)
-    let envs = if let Some(envs) = args.environment {
-        envs
+    let mut environments = if !args.environment.is_empty() {
+        environments_from_names(&workspace, &args.environment)?
     } else if args.all {
-        workspace
-            .environments()
-            .iter()
-            .map(|env| env.name().to_string())
-            .collect()
+        workspace.environments()
     } else {
-        vec![workspace.default_environment().name().to_string()]
+        vec![workspace.default_environment()]
     };
 
-    // Get the environments by name
-    let environments = envs
-        .into_iter()
-        .map(|env| workspace.environment_from_name_or_env_var(Some(env)))
-        .collect::<Result<Vec<_>, _>>()?;
+    // A frozen lock-file can only install the environments it contains, the
+    // others are reported after installing the rest
+    let lock_file_usage: LockFileUsage = args.lock_file_usage.try_into()?;
+    let requested_count = environments.len();
+    let mut missing = Vec::new();
+    if lock_file_usage == LockFileUsage::Frozen {
+        let lock_file = workspace.load_lock_file().await?;
+        let glob_hash_cache = GlobHashCache::default();
+        let mut installable = Vec::new();
+        for env in environments {
+            match verify_frozen_environment(&env, &lock_file, glob_hash_cache.clone()).await {
+                Ok(()) => installable.push(env),
+                Err(err) if err.is_missing() => missing.push(err),
+                Err(err) => {
+                    tracing::warn!("{:?}", miette::Report::new(err));
+                    installable.push(env);
+                }
+            }
+        }
+        environments = installable;
+    }
+    if environments.is_empty() {
+        if missing.len() == 1 {
+            return Err(missing.remove(0).into());
+        }
+        return Err(frozen_install_error(missing, requested_count));
+    }
+
+    if args.dry_run {
+        let result = dry_run(
+            &workspace,
+            &environments,
+            lock_file_usage,
+            &args.reinstall_config.reinstall_packages(),
+            &InstallFilter::skip(args.skip),
+        )
+        .await;
+        // The pending changes are already printed, only the exit code is left
+        if let Err(err) = &result {
+            if err.downcast_ref::<PendingChanges>().is_some() {
+                std::process::exit(PENDING_CHANGES_EXIT_CODE);
+            }
+        }
+        return result;
+    }
+
+    if let Some(target_prefix) = &args.target_prefix {
+        let [env] = environments.as_slice() else {
+            miette::bail!("only one environment can be installed into a target prefix");
+        };
+        return install_into_target_prefix(
+            &workspace,
+            env,
+            target_prefix,
+            args.force,
+            lock_file_usage,
+            InstallFilter::skip(args.skip),
+        )
+        .await;
+    }
 
     // Update the prefixes by installing all packages
-    get_update_lock_file_and_prefixes(
+    let update_mode = if args.verify {
+        UpdateMode::Verify
+    } else {
+        UpdateMode::Revalidate
+    };
+    let (lock_file, _) = get_update_lock_file_and_prefixes(
         &environments,
-        UpdateMode::Revalidate,
+        update_mode,
         UpdateLockFileOptions {
-            lock_file_usage: args.lock_file_usage.try_into()?,
+            lock_file_usage,
             no_install: false,
             max_concurrent_solves: workspace.config().max_concurrent_solves(),
+            no_prune: false,
         },
-        ReinstallPackages::default(),
+        args.reinstall_config.reinstall_packages(),
+        InstallFilter::skip(args.skip),
     )
     .await?;
 
-    let installed_envs = environments
-        .into_iter()
-        .map(|env| env.name())
-        .collect::<Vec<_>>();
+    // Run the post-install tasks of the environments that changed
+    if !args.no_hooks {
+        for env in &environments {
+            exit_on_post_install_failure(run_post_install_tasks(env, &lock_file).await)?;
+        }
+    }
 
     // Message what's installed
-    let detached_envs_message =
-        if let Ok(Some(path)) = workspace.config().detached_environments().path() {
-            format!(" in '{}'", console::style(path.display()).bold())
-        } else {
-            "".to_string()
-        };
+    let lock_file = lock_file.into_lock_file();
+    let package_count = |env: &Environment| {
+        lock_file
+            .environment(env.name().as_str())
+            .and_then(|locked| locked.packages(env.best_platform()))
+            .map_or(0, |packages| packages.count())
+    };
 
-    if installed_envs.len() == 1 {
+    if let [env] = environments.as_slice() {
         eprintln!(
-            "{}The {} environment has been installed{}.",
+            "{}The {} environment has been installed in '{}' ({} packages).",
             console::style(console::Emoji("✔ ", "")).green(),
-            installed_envs[0].fancy_display(),
-            detached_envs_message
+            env.name().fancy_display(),
+            console::style(env.dir().display()).bold(),
+            package_count(env),
         );
     } else {
         eprintln!(
-            "{}The following environments have been installed: {}\t{}",
+            "{}The following environments have been installed:",
             console::style(console::Emoji("✔ ", "")).green(),
-            installed_envs.iter().map(|n| n.fancy_display()).join(", "),
-            detached_envs_message
         );
+        for env in &environments {
+            eprintln!(
+                "    {} in '{}' ({} packages)",
+                env.name().fancy_display(),
+                console::style(env.dir().display()).bold(),
+                package_count(env),
+            );
+        }
+    }
+
+    if let Some(sink) = json_lines::json_lines() {
+        sink.emit_summary(
+            environments
+                .iter()
+                .map(|env| EventContext {
+                    environment: env.name().to_string(),
+                    platform: env.best_platform(),
+                })
+                .collect(),
+        );
+    }
+
+    if !missing.is_empty() {
+        return Err(frozen_install_error(missing, requested_count));
     }
 
     Ok(())
 }
+
+/// Prints the changes that installing the environments would make, and
+/// returns [`PendingChanges`] if there are any. The lock-file is only updated
+/// in memory.
+async fn dry_run(
+    workspace: &Workspace,
+    environments: &[Environment<'_>],
+    lock_file_usage: LockFileUsage,
+    reinstall_packages: &ReinstallPackages,
+    filter: &InstallFilter,
+) -> miette::Result<()> {
+    let original_lock_file = workspace.load_lock_file().await?;
+    let lock_file = if lock_file_usage.allows_lock_file_updates() {
+        UpdateContext::builder(workspace)
+            .with_lock_file(original_lock_file.clone())
+            .with_no_install(true)
+            .finish()
+            .await?
+            .update()
+            .await?
+    } else {
+        workspace
+            .update_lock_file(UpdateLockFileOptions {
+                lock_file_usage,
+                no_install: true,
+                max_concurrent_solves: workspace.config().max_concurrent_solves(),
+                no_prune: false,
+            })
+            .await?
+    };
+
+    let lock_file_diff =
+        LockFileDiff::from_lock_files(&original_lock_file, lock_file.as_lock_file());
+    if !lock_file_diff.is_empty() {
+        eprintln!("The lock-file would be updated:");
+        lock_file_diff
+            .print()
+            .into_diagnostic()
+            .context("failed to print lock-file diff")?;
+        eprintln!();
+    }
+
+    let mut pending = Vec::with_capacity(environments.len());
+    for env in environments {
+        let operations = lock_file
+            .pending_operations(env, reinstall_packages, filter)
+            .await?;
+        pending.push((env.name().clone(), operations));
+    }
+    print_pending_operations(&pending)
+        .into_diagnostic()
+        .context("failed to print the pending operations")?;
+
+    if lock_file_diff.is_empty() && pending.iter().all(|(_, operations)| operations.is_empty()) {
+        eprintln!(
+            "{}Nothing to install, the environments are up-to-date",
+            console::style(console::Emoji("✔ ", "")).green(),
+        );
+        return Ok(());
+    }
+    Err(PendingChanges.into())
+}
+
+/// Installs the environment into the target prefix. The lock-file is updated
+/// like a normal install, but the prefix is not registered with the workspace
+/// and no `post-install` tasks are run.
+async fn install_into_target_prefix(
+    workspace: &Workspace,
+    environment: &Environment<'_>,
+    target_prefix: &Path,
+    force: bool,
+    lock_file_usage: LockFileUsage,
+    filter: InstallFilter,
+) -> miette::Result<()> {
+    // The path is written into the installed files, so it has to be absolute
+    let target_prefix = std::path::absolute(target_prefix).into_diagnostic()?;
+    ensure_target_prefix_is_empty(&target_prefix, force)?;
+
+    sanity_check_workspace(workspace).await?;
+    let lock_file = workspace
+        .update_lock_file(UpdateLockFileOptions {
+            lock_file_usage,
+            no_install: false,
+            max_concurrent_solves: workspace.config().max_concurrent_solves(),
+            no_prune: false,
+        })
+        .await?;
+    lock_file
+        .install_into_prefix(environment, &target_prefix, &filter)
+        .await?;
+
+    let package_count = lock_file
+        .as_lock_file()
+        .environment(environment.name().as_str())
+        .and_then(|locked| locked.packages(environment.best_platform()))
+        .map_or(0, |packages| packages.count());
+    eprintln!(
+        "{}The {} environment has been installed in '{}' ({} packages).",
+        console::style(console::Emoji("✔ ", "")).green(),
+        environment.name().fancy_display(),
+        console::style(target_prefix.display()).bold(),
+        package_count,
+    );
+    eprintln!(
+        "To activate it, run: {}",
+        console::style(format!(
+            "eval \"$(pixi shell-hook --environment {} --target-prefix {})\"",
+            environment.name(),
+            target_prefix.display()
+        ))
+        .bold()
+    );
+
+    Ok(())
+}
+
+/// Makes sure nothing is overwritten by installing into the target prefix,
+/// unless that is forced.
+fn ensure_target_prefix_is_empty(target_prefix: &Path, force: bool) -> miette::Result<()> {
+    if !target_prefix.exists() || force {
+        return Ok(());
+    }
+    if !target_prefix.is_dir() {
+        miette::bail!("'{}' is not a directory", target_prefix.display());
+    }
+    let is_empty = fs_err::read_dir(target_prefix)
+        .into_diagnostic()?
+        .next()
+        .is_none();
+    if !is_empty {
+        miette::bail!(
+            help = "use `--force` to install into it anyway",
+            "the target prefix '{}' is not empty",
+            target_prefix.display()
+        );
+    }
+    Ok(())
+}
+
+/// Reports the environments that couldn't be installed from the frozen
+/// lock-file.
+/// Exits with the exit code of a post-install task that failed, other errors
+/// are returned.
+pub(crate) fn exit_on_post_install_failure(result: miette::Result<()>) -> miette::Result<()> {
+    match result {
+        Err(err) => match err.downcast_ref::<PostInstallTaskFailed>() {
+            Some(failed) => {
+                let exit_code = failed.exit_code;
+                eprintln!("{err:?}");
+                std::process::exit(exit_code)
+            }
+            None => Err(err),
+        },
+        Ok(()) => Ok(()),
+    }
+}
+
+fn frozen_install_error(
+    missing: Vec<FrozenLockFileError>,
+    requested_count: usize,
+) -> miette::Report {
+    let failed_count = missing.len();
+    for err in missing {
+        eprintln!("{:?}", miette::Report::new(err));
+    }
+    miette::miette!(
+        help = "run `pixi lock` to update the lock-file, or install without `--frozen`",
+        "{failed_count} of {requested_count} environments could not be installed from the lock-file"
+    )
+}
+
+/// Returns the environments with the given names, in the order they are given
+/// without duplicates. Unknown names are reported together, with suggestions
+/// for names that are similar to an existing environment.
+pub(crate) fn environments_from_names<'p>(
+    workspace: &'p Workspace,
+    names: &[String],
+) -> miette::Result<Vec<Environment<'p>>> {
+    let mut environments = Vec::new();
+    let mut unknown = Vec::new();
+    for name in names.iter().unique() {
+        match workspace.environment(name.as_str()) {
+            Some(environment) => environments.push(environment),
+            None => unknown.push(name.as_str()),
+        }
+    }
+    if unknown.is_empty() {
+        return Ok(environments);
+    }
+
+    let existing = workspace
+        .environments()
+        .into_iter()
+        .map(|env| env.name().to_string())
+        .collect_vec();
+    let suggestions = unknown
+        .iter()
+        .filter_map(|name| {
+            existing
+                .iter()
+                .map(|existing| (existing, strsim::jaro(name, existing)))
+                .filter(|(_, similarity)| *similarity > 0.7)
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(existing, _)| format!("'{existing}' instead of '{name}'"))
+        })
+        .collect_vec();
+    miette::bail!(
+        help = if suggestions.is_empty() {
+            format!("use one of {}", existing.iter().format(", "))
+        } else {
+            format!("did you mean {}?", suggestions.join(", "))
+        },
+        "unknown environment(s) {}",
+        unknown.iter().map(|name| format!("'{name}'")).join(", ")
+    );
+}
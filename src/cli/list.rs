@@ -187,6 +187,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             lock_file_usage: args.lock_file_update_config.lock_file_usage()?,
             no_install: false,
             max_concurrent_solves: workspace.config().max_concurrent_solves(),
+            no_prune: false,
         })
         .await?
         .into_lock_file();
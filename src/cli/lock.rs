@@ -1,19 +1,59 @@
-use clap::Parser;
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use ahash::HashSet;
+
+use clap::{Parser, ValueEnum};
+use fancy_display::FancyDisplay;
+use itertools::Itertools;
 use miette::{Context, IntoDiagnostic};
+use pixi_config::{ConfigCli, ConfigCliNetwork};
+use pixi_consts::consts;
+use pixi_manifest::EnvironmentName;
+use pixi_utils::conda_lock_file::CondaLockFile;
+use rattler_conda_types::Platform;
+use rattler_lock::LockFile;
+use serde::Serialize;
+use tabwriter::TabWriter;
 
-use crate::lock_file::LockFileDerivedData;
+use crate::lock_file::{
+    LockFileDerivedData, OutdatedEnvironments, OutdatedReason, UnavailablePackage, UpdateContext,
+    canonicalize_lock_file, collect_licenses, conda_lock_from_lock_file, find_unavailable_packages,
+    import_conda_lock, print_pruned_targets, print_unavailable_packages, prune_lock_file,
+    restore_stale_targets, verify_locked_artifacts, write_lock_file_with_version,
+};
 use crate::{
-    WorkspaceLocator,
-    cli::cli_config::WorkspaceConfig,
+    Workspace, WorkspaceLocator,
+    build::GlobHashCache,
+    cli::{
+        cli_config::WorkspaceConfig,
+        tree::{Package, PackageSource, direct_dependencies, generate_dependency_map},
+    },
     diff::{LockFileDiff, LockFileJsonDiff},
     environment::LockFileUsage,
     lock_file::UpdateLockFileOptions,
+    workspace::Environment,
 };
 
+/// The exit code of `--check` if there is no lock-file.
+pub const MISSING_LOCK_FILE_EXIT_CODE: i32 = 6;
+
+/// The exit code of `--check` if the lock-file is out of date with the
+/// manifest.
+pub const OUTDATED_LOCK_FILE_EXIT_CODE: i32 = 3;
+
+/// The exit code of `--check --check-availability` if locked packages are no
+/// longer available in their channel.
+pub const UNAVAILABLE_PACKAGES_EXIT_CODE: i32 = 4;
+
 /// Solve environment and update the lock file without installing the
 /// environments.
 #[derive(Debug, Parser)]
 #[clap(arg_required_else_help = false)]
+#[clap(group(clap::ArgGroup::new("refreshable").multiple(true).args(["check_availability", "licenses"])))]
 pub struct Args {
     #[clap(flatten)]
     pub workspace_config: WorkspaceConfig,
@@ -23,60 +63,944 @@ pub struct Args {
     pub json: bool,
 
     /// Check if any changes have been made to the lock file.
-    /// If yes, exit with a non-zero code.
+    /// Exits with code 6 if there is no lock file and with code 3 if the lock
+    /// file is out of date with the manifest.
     #[clap(long)]
     pub check: bool,
+
+    /// Also check that the file of every locked conda package still appears
+    /// in the repodata of its channel. Exits with code 4 if any package is no
+    /// longer available.
+    #[clap(long, requires = "check")]
+    pub check_availability: bool,
+
+    /// Fetch the repodata again instead of using the cached repodata to check
+    /// the availability of the locked packages. With `--licenses`, fetch the
+    /// licenses of the pypi packages that are not installed from the index.
+    #[clap(long, requires = "refreshable")]
+    pub refresh: bool,
+
+    /// The environments to lock. If none is specified, all environments are
+    /// locked. The locked packages of the other environments are kept as they
+    /// are.
+    #[clap(long = "environment", short = 'e')]
+    pub environments: Option<Vec<EnvironmentName>>,
+
+    /// The platforms to lock. If none is specified, all platforms are locked.
+    /// The locked packages of the other platforms are kept as they are.
+    #[clap(long = "platform", short = 'p')]
+    pub platforms: Option<Vec<Platform>>,
+
+    /// The format to write the lock-file of an environment in. A
+    /// `conda-lock` file is written to the path given by `--output`.
+    #[clap(long, value_enum, default_value_t = LockFileFormat::Pixi)]
+    pub format: LockFileFormat,
+
+    /// The path to write the lock-file to when it is exported with
+    /// `--format conda-lock`, or to write the dependency graph to instead of
+    /// `stdout` with `--graph`.
+    #[clap(long, required_if_eq("format", "conda-lock"))]
+    pub output: Option<PathBuf>,
+
+    /// Seed the lock-file of an environment with the packages of a conda-lock
+    /// file, instead of solving it. Fails if the packages don't satisfy the
+    /// dependencies of the manifest.
+    #[clap(long = "import", value_name = "CONDA_LOCK_FILE", conflicts_with_all = ["check", "format", "output", "platforms"])]
+    pub import: Option<PathBuf>,
+
+    /// Rewrite the existing lock-file in canonical form, with sorted
+    /// environments, platforms and packages, without solving it again.
+    #[clap(long, conflicts_with_all = ["json", "check", "environments", "platforms", "format", "output", "import"])]
+    pub reformat: bool,
+
+    /// Check that the artifact of every locked package of the selected
+    /// environments and platforms can still be downloaded, without installing
+    /// anything. Artifacts in the package cache are compared with the locked
+    /// hash, and their extracted files with the `info/paths.json` of the
+    /// package. Exits with a non-zero code if any artifact fails the check.
+    #[clap(long, conflicts_with_all = ["json", "check", "format", "output", "import", "reformat"])]
+    pub verify_hashes: bool,
+
+    /// Download every artifact completely and compare it with the locked hash.
+    #[clap(long, requires = "verify_hashes")]
+    pub deep: bool,
+
+    /// Write the dependency graph of the locked packages of an environment
+    /// and platform, without updating the lock-file. Direct dependencies of
+    /// the manifest are highlighted.
+    #[clap(long, value_enum, conflicts_with_all = ["json", "check", "format", "import", "reformat", "verify_hashes"])]
+    pub graph: Option<GraphFormat>,
+
+    /// Only include the packages that can be reached from this package in the
+    /// dependency graph.
+    #[clap(long, requires = "graph")]
+    pub package: Option<String>,
+
+    /// List the locked packages of an environment grouped by their license,
+    /// without updating the lock-file. Packages without license information
+    /// are listed as unknown.
+    #[clap(long, conflicts_with_all = ["check", "format", "output", "import", "reformat", "verify_hashes", "graph"])]
+    pub licenses: bool,
+
+    /// Keep the environments and platforms that are no longer part of the
+    /// manifest in the lock-file, instead of removing them.
+    #[clap(long)]
+    pub no_prune: bool,
+
+    /// Solve every selected environment and platform again from scratch,
+    /// ignoring the locked packages, instead of only the ones that no longer
+    /// satisfy the manifest.
+    #[clap(long, conflicts_with = "check")]
+    pub force_all: bool,
+
+    #[clap(flatten)]
+    pub config: ConfigCli,
+    #[clap(flatten)]
+    pub network_config: ConfigCliNetwork,
+}
+
+/// The format of the lock-file that `pixi lock` writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LockFileFormat {
+    /// The `pixi.lock` of the workspace.
+    #[default]
+    Pixi,
+    /// A unified (version 1) conda-lock file of a single environment.
+    CondaLock,
+}
+
+/// The format of the dependency graph that `pixi lock --graph` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// A Graphviz digraph.
+    Dot,
+    /// A Mermaid flowchart.
+    Mermaid,
+}
+
+/// The state of the lock-file before it was updated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum LockFileStatus {
+    UpToDate,
+    Missing,
+    Outdated,
+}
+
+/// The report of `--check --json`.
+#[derive(Serialize)]
+struct CheckReport {
+    status: LockFileStatus,
+    outdated: Vec<OutdatedReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unavailable: Option<Vec<UnavailablePackage>>,
+    diff: LockFileJsonDiff,
+}
+
+impl Args {
+    /// Returns true if the environment and platform are selected with
+    /// `--environment` and `--platform`.
+    fn is_selected(&self, environment: &str, platform: Platform) -> bool {
+        self.environments
+            .as_ref()
+            .is_none_or(|environments| environments.iter().any(|env| env.as_str() == environment))
+            && self
+                .platforms
+                .as_ref()
+                .is_none_or(|platforms| platforms.contains(&platform))
+    }
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
     let workspace = WorkspaceLocator::for_cli()
         .with_search_start(args.workspace_config.workspace_locator_start())
-        .locate()?;
+        .locate()?
+        .with_cli_config(
+            args.network_config
+                .clone()
+                .merge_config(args.config.clone().into()),
+        );
+
+    if args.reformat {
+        return reformat(&workspace).await;
+    }
+
+    // If the user specified an environment name, check to see if it exists.
+    for name in args.environments.iter().flatten() {
+        if workspace.environment(name).is_none() {
+            miette::bail!(
+                "could not find an environment named {}",
+                name.fancy_display()
+            )
+        }
+    }
+    if let Some(platforms) = &args.platforms {
+        ensure_platforms_supported(&workspace, args.environments.as_deref(), platforms)?;
+    }
+    if let Some(path) = &args.import {
+        return import(&workspace, path, args.environments.as_deref()).await;
+    }
+    if args.verify_hashes {
+        return verify_hashes(&workspace, &args).await;
+    }
+    if let Some(format) = args.graph {
+        return graph(&workspace, &args, format).await;
+    }
+    if args.licenses {
+        return licenses(&workspace, &args).await;
+    }
+    let scoped = args.environments.is_some() || args.platforms.is_some() || args.force_all;
+
+    // Find out why the lock-file is out of date before it is updated.
+    let original_lock_file = workspace.load_lock_file().await?;
+    let (lock_file, pruned) = if scoped && !args.no_prune {
+        prune_lock_file(&workspace, &original_lock_file)
+    } else {
+        (original_lock_file.clone(), Vec::new())
+    };
+    let glob_hash_cache = GlobHashCache::default();
+    let outdated = if args.check || scoped {
+        let mut outdated = if args.force_all {
+            OutdatedEnvironments::all(&workspace)
+        } else {
+            OutdatedEnvironments::from_workspace_and_lock_file(
+                &workspace,
+                &lock_file,
+                glob_hash_cache.clone(),
+            )
+            .await
+        };
+        if let Some(environments) = &args.environments {
+            outdated.retain(|env| environments.contains(env.name()));
+            ensure_solve_groups_selected(&outdated, environments)?;
+        }
+        if let Some(platforms) = &args.platforms {
+            if !args.force_all {
+                ensure_locked_content_kept(&outdated)?;
+            }
+            outdated.retain_platforms(|platform| platforms.contains(&platform));
+        }
+        Some(outdated)
+    } else {
+        None
+    };
+
+    let (mut status, reasons) = match &outdated {
+        _ if !args.check => (LockFileStatus::UpToDate, Vec::new()),
+        _ if !workspace.lock_file_path().is_file() => (LockFileStatus::Missing, Vec::new()),
+        Some(outdated) if !outdated.is_empty() => {
+            (LockFileStatus::Outdated, outdated.reasons.clone())
+        }
+        _ => (LockFileStatus::UpToDate, Vec::new()),
+    };
 
     // Update the lock-file, and extract it from the derived data to drop additional resources
     // created for the solve.
-    let original_lock_file = workspace.load_lock_file().await?;
     let LockFileDerivedData {
         lock_file,
         was_outdated,
         ..
-    } = workspace
-        .update_lock_file(UpdateLockFileOptions {
-            lock_file_usage: LockFileUsage::Update,
-            no_install: false,
-            max_concurrent_solves: workspace.config().max_concurrent_solves(),
-        })
-        .await?;
+    } = match outdated {
+        Some(outdated) if scoped => {
+            let derived_data = lock_selected(
+                &workspace,
+                &original_lock_file,
+                lock_file,
+                !pruned.is_empty(),
+                outdated,
+                glob_hash_cache,
+                args.no_prune,
+            )
+            .await?;
+            print_pruned_targets(&pruned);
+            derived_data
+        }
+        _ => {
+            workspace
+                .update_lock_file(UpdateLockFileOptions {
+                    lock_file_usage: LockFileUsage::Update,
+                    no_install: false,
+                    max_concurrent_solves: workspace.config().max_concurrent_solves(),
+                    no_prune: args.no_prune,
+                })
+                .await?
+        }
+    };
 
     // Determine the diff between the old and new lock-file.
     let diff = LockFileDiff::from_lock_files(&original_lock_file, &lock_file);
+    if args.check && status == LockFileStatus::UpToDate && !diff.is_empty() {
+        status = LockFileStatus::Outdated;
+    }
+
+    // Check that the locked packages can still be found in their channels.
+    let unavailable = if args.check_availability {
+        Some(
+            find_unavailable_packages(
+                &workspace,
+                &lock_file,
+                |environment, platform| args.is_selected(environment, platform),
+                args.refresh,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
 
     // Format as json?
     if args.json {
-        let diff = LockFileDiff::from_lock_files(&original_lock_file, &lock_file);
         let json_diff = LockFileJsonDiff::new(Some(&workspace), diff);
-        let json = serde_json::to_string_pretty(&json_diff).expect("failed to convert to json");
+        let json = if args.check {
+            serde_json::to_string_pretty(&CheckReport {
+                status,
+                outdated: reasons,
+                unavailable: unavailable.clone(),
+                diff: json_diff,
+            })
+        } else {
+            serde_json::to_string_pretty(&json_diff)
+        }
+        .expect("failed to convert to json");
         println!("{}", json);
-    } else if was_outdated {
+    } else {
+        match status {
+            LockFileStatus::Missing => eprintln!(
+                "{}There is no lock-file at {}",
+                console::style(console::Emoji("✘ ", "")).red(),
+                workspace.lock_file_path().display()
+            ),
+            LockFileStatus::Outdated => {
+                eprintln!(
+                    "{}The lock-file is out of date with the manifest",
+                    console::style(console::Emoji("✘ ", "")).red()
+                );
+                print_outdated_reasons(&reasons)
+                    .into_diagnostic()
+                    .context("failed to print the outdated environments")?;
+            }
+            LockFileStatus::UpToDate => {}
+        }
+        match unavailable.as_deref() {
+            Some([]) => eprintln!(
+                "{}All locked packages are available in their channel",
+                console::style(console::Emoji("✔ ", "")).green()
+            ),
+            Some(unavailable) => {
+                eprintln!(
+                    "{}The following locked packages are no longer available in their channel",
+                    console::style(console::Emoji("✘ ", "")).red()
+                );
+                print_unavailable_packages(unavailable)
+                    .into_diagnostic()
+                    .context("failed to print the unavailable packages")?;
+            }
+            None => {}
+        }
+
+        if was_outdated {
+            eprintln!(
+                "{}Updated lock-file",
+                console::style(console::Emoji("✔ ", "")).green()
+            );
+            diff.print()
+                .into_diagnostic()
+                .context("failed to print lock-file diff")?;
+        } else {
+            eprintln!(
+                "{}Lock-file was already up-to-date",
+                console::style(console::Emoji("✔ ", "")).green()
+            );
+        }
+    }
+
+    if args.format == LockFileFormat::CondaLock {
+        let output = args
+            .output
+            .as_deref()
+            .expect("clap requires an output for the conda-lock format");
+        export(&workspace, &lock_file, output, args.environments.as_deref())?;
+    }
+
+    // Return with a non-zero exit code if `--check` has been passed and the lock
+    // file has been updated
+    match status {
+        LockFileStatus::Missing => std::process::exit(MISSING_LOCK_FILE_EXIT_CODE),
+        LockFileStatus::Outdated => std::process::exit(OUTDATED_LOCK_FILE_EXIT_CODE),
+        LockFileStatus::UpToDate => {}
+    }
+    if unavailable.is_some_and(|unavailable| !unavailable.is_empty()) {
+        std::process::exit(UNAVAILABLE_PACKAGES_EXIT_CODE);
+    }
+
+    Ok(())
+}
+
+/// Rewrites the lock-file in canonical form, the locked packages are kept as
+/// they are.
+async fn reformat(workspace: &Workspace) -> miette::Result<()> {
+    let lock_file_path = workspace.lock_file_path();
+    if !lock_file_path.is_file() {
+        miette::bail!(
+            help = "run `pixi lock` to create the lock-file",
+            "there is no lock-file at {}",
+            lock_file_path.display()
+        );
+    }
+
+    let original = fs_err::read(&lock_file_path).into_diagnostic()?;
+    let lock_file = workspace.load_lock_file().await?;
+    write_lock_file_with_version(&canonicalize_lock_file(&lock_file), &lock_file_path)
+        .into_diagnostic()
+        .wrap_err("failed to write the lock-file")?;
+
+    if fs_err::read(&lock_file_path).into_diagnostic()? == original {
         eprintln!(
-            "{}Updated lock-file",
+            "{}Lock-file was already in canonical form",
             console::style(console::Emoji("✔ ", "")).green()
         );
-        diff.print()
-            .into_diagnostic()
-            .context("failed to print lock-file diff")?;
     } else {
         eprintln!(
-            "{}Lock-file was already up-to-date",
-            console::style(console::Emoji("✔ ", "")).green()
+            "{}Reformatted {}",
+            console::style(console::Emoji("✔ ", "")).green(),
+            lock_file_path.display()
         );
     }
+    Ok(())
+}
 
-    // Return with a non-zero exit code if `--check` has been passed and the lock
-    // file has been updated
-    if args.check && !diff.is_empty() {
-        std::process::exit(1);
+/// Checks that the locked artifacts of the selected environments and platforms
+/// can still be downloaded and match their locked hash.
+async fn verify_hashes(workspace: &Workspace, args: &Args) -> miette::Result<()> {
+    let lock_file = workspace.load_lock_file().await?;
+    let report = verify_locked_artifacts(
+        workspace,
+        &lock_file,
+        |environment, platform| args.is_selected(environment, platform),
+        args.deep,
+    )
+    .await?;
+
+    if report.failures.is_empty() {
+        eprintln!(
+            "{}Verified {} locked artifacts",
+            console::style(console::Emoji("✔ ", "")).green(),
+            report.checked
+        );
+        return Ok(());
+    }
+
+    eprintln!(
+        "{}{} of {} locked artifacts failed the verification",
+        console::style(console::Emoji("✘ ", "")).red(),
+        report.failures.len(),
+        report.checked
+    );
+    report
+        .print_failures()
+        .into_diagnostic()
+        .context("failed to print the failed artifacts")?;
+    Err(miette::miette!(
+        help = "run `pixi update` to lock packages that can be downloaded",
+        "the lock-file contains artifacts that can't be verified"
+    ))
+}
+
+/// Returns the single environment that is exported or imported, the default
+/// environment if none is selected.
+fn single_environment<'p>(
+    workspace: &'p Workspace,
+    environments: Option<&[EnvironmentName]>,
+    what: &str,
+) -> miette::Result<Environment<'p>> {
+    match environments.unwrap_or_default() {
+        [] => Ok(workspace.default_environment()),
+        [name] => workspace.environment(name).ok_or_else(|| {
+            miette::miette!(
+                "could not find an environment named {}",
+                name.fancy_display()
+            )
+        }),
+        _ => Err(miette::miette!(
+            help = "select a single environment with `--environment`",
+            "{what} contains the packages of a single environment"
+        )),
+    }
+}
+
+/// Writes the locked packages of an environment to a conda-lock file.
+fn export(
+    workspace: &Workspace,
+    lock_file: &LockFile,
+    output: &Path,
+    environments: Option<&[EnvironmentName]>,
+) -> miette::Result<()> {
+    let environment = single_environment(workspace, environments, "a conda-lock file")?;
+    let sources = workspace
+        .workspace
+        .provenance
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .into_iter()
+        .collect();
+    let conda_lock = conda_lock_from_lock_file(lock_file, environment.name().as_str(), sources)?;
+    fs_err::write(output, conda_lock.to_yaml_string()?)
+        .into_diagnostic()
+        .wrap_err("failed to write the conda-lock file")?;
+
+    eprintln!(
+        "{}Exported the lock-file of {} to {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        environment.name().fancy_display(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Replaces the locked packages of an environment with the packages of a
+/// conda-lock file. The lock-file is only written if it satisfies the manifest.
+async fn import(
+    workspace: &Workspace,
+    path: &Path,
+    environments: Option<&[EnvironmentName]>,
+) -> miette::Result<()> {
+    let environment = single_environment(workspace, environments, "a conda-lock file")?;
+    let conda_lock = CondaLockFile::from_path(path)?;
+    let original_lock_file = workspace.load_lock_file().await?;
+    let lock_file = import_conda_lock(workspace, &environment, &conda_lock, &original_lock_file)?;
+
+    let mut outdated = OutdatedEnvironments::from_workspace_and_lock_file(
+        workspace,
+        &lock_file,
+        GlobHashCache::default(),
+    )
+    .await;
+    outdated.retain(|env| env.name() == environment.name());
+    if !outdated.is_empty() {
+        return Err(miette::miette!(
+            help = "update the conda-lock file or run `pixi lock` to solve the environment",
+            "the packages of '{}' don't satisfy the manifest:\n{}",
+            path.display(),
+            outdated
+                .reasons
+                .iter()
+                .map(|reason| match reason.platform {
+                    Some(platform) => format!(
+                        "  {}:{}\t{}",
+                        reason.environment.as_str(),
+                        platform,
+                        reason.reason
+                    ),
+                    None => format!("  {}\t{}", reason.environment.as_str(), reason.reason),
+                })
+                .format("\n")
+        ));
+    }
+
+    workspace.write_lock_file(&lock_file)?;
+    eprintln!(
+        "{}Imported the packages of {} from {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        environment.name().fancy_display(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Writes the dependency graph of the locked packages of an environment and
+/// platform, the lock-file is used as it is.
+async fn graph(workspace: &Workspace, args: &Args, format: GraphFormat) -> miette::Result<()> {
+    let environment = single_environment(
+        workspace,
+        args.environments.as_deref(),
+        "a dependency graph",
+    )?;
+    let platform = match args.platforms.as_deref().unwrap_or_default() {
+        [] => environment.best_platform(),
+        [platform] => *platform,
+        _ => miette::bail!(
+            help = "select a single platform with `--platform`",
+            "a dependency graph contains the packages of a single platform"
+        ),
+    };
+
+    let lock_file = workspace.load_lock_file().await?;
+    let packages = lock_file
+        .environment(environment.name().as_str())
+        .and_then(|env| env.packages(platform).map(Vec::from_iter))
+        .ok_or_else(|| {
+            miette::miette!(
+                help = "run `pixi lock` to lock it",
+                "{} is not locked for {}",
+                environment.name().fancy_display(),
+                consts::PLATFORM_STYLE.apply_to(platform)
+            )
+        })?;
+    let dep_map = generate_dependency_map(&packages);
+    let direct_deps = direct_dependencies(&environment, &platform, &dep_map);
+
+    let names = match &args.package {
+        Some(package) => reachable_packages(&dep_map, package).ok_or_else(|| {
+            miette::miette!(
+                "could not find a package named '{}' in the lock-file of {}",
+                package,
+                environment.name().fancy_display()
+            )
+        })?,
+        None => dep_map.keys().cloned().collect(),
+    };
+    let graph = match format {
+        GraphFormat::Dot => dot_graph(environment.name(), &dep_map, &names, &direct_deps),
+        GraphFormat::Mermaid => mermaid_graph(&dep_map, &names, &direct_deps),
+    };
+
+    match &args.output {
+        Some(output) => fs_err::write(output, graph)
+            .into_diagnostic()
+            .wrap_err("failed to write the dependency graph")?,
+        None => print!("{graph}"),
+    }
+    Ok(())
+}
+
+/// Lists the locked packages of an environment grouped by their license, the
+/// lock-file is used as it is.
+async fn licenses(workspace: &Workspace, args: &Args) -> miette::Result<()> {
+    let environment =
+        single_environment(workspace, args.environments.as_deref(), "a license report")?;
+    let platforms = match &args.platforms {
+        Some(platforms) => platforms.clone(),
+        None => environment
+            .platforms()
+            .into_iter()
+            .sorted_by_key(|platform| platform.as_str())
+            .collect(),
+    };
+
+    let lock_file = workspace.load_lock_file().await?;
+    if lock_file.environment(environment.name().as_str()).is_none() {
+        miette::bail!(
+            help = "run `pixi lock` to lock it",
+            "{} is not locked",
+            environment.name().fancy_display()
+        );
     }
+    let report = collect_licenses(
+        workspace,
+        &lock_file,
+        &environment,
+        &platforms,
+        args.refresh,
+    )
+    .await?;
 
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("failed to convert to json")
+        );
+    } else {
+        report
+            .print()
+            .into_diagnostic()
+            .context("failed to print the licenses")?;
+    }
     Ok(())
 }
+
+/// Returns the names of the packages that can be reached from `root`,
+/// including `root` itself. Dependencies that are not locked, e.g. virtual
+/// packages, are skipped.
+fn reachable_packages(dep_map: &HashMap<String, Package>, root: &str) -> Option<BTreeSet<String>> {
+    // Pypi packages are keyed by their dist-info name, e.g. `my_pkg`
+    let root = [
+        root.to_string(),
+        root.to_lowercase(),
+        root.to_lowercase().replace(['-', '.'], "_"),
+    ]
+    .into_iter()
+    .find(|name| dep_map.contains_key(name))?;
+
+    // The depends of conda packages can contain cycles, every package is
+    // only visited once.
+    let mut reachable = BTreeSet::new();
+    let mut stack = vec![root];
+    while let Some(name) = stack.pop() {
+        let Some(package) = dep_map.get(&name) else {
+            continue;
+        };
+        if !reachable.insert(name) {
+            continue;
+        }
+        stack.extend(package.dependencies.iter().cloned());
+    }
+    Some(reachable)
+}
+
+/// The edges between the selected packages, sorted by name.
+fn graph_edges<'a>(
+    dep_map: &'a HashMap<String, Package>,
+    names: &'a BTreeSet<String>,
+) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+    names.iter().flat_map(move |name| {
+        dep_map[name]
+            .dependencies
+            .iter()
+            .filter(|dependency| names.contains(*dependency))
+            .sorted()
+            .map(move |dependency| (name.as_str(), dependency.as_str()))
+    })
+}
+
+fn dot_graph(
+    environment: &EnvironmentName,
+    dep_map: &HashMap<String, Package>,
+    names: &BTreeSet<String>,
+    direct_deps: &HashSet<String>,
+) -> String {
+    let mut graph = format!("digraph \"{}\" {{\n", dot_escape(environment.as_str()));
+    for name in names {
+        let package = &dep_map[name];
+        let mut attributes = vec![format!(
+            "label=\"{}\"",
+            dot_escape(&format!("{} {}", package.name, package.version))
+        )];
+        if package.source == PackageSource::Pypi {
+            attributes.push("shape=box".to_string());
+        }
+        if direct_deps.contains(name) {
+            attributes.push("style=bold, color=green".to_string());
+        }
+        graph.push_str(&format!(
+            "  \"{}\" [{}];\n",
+            dot_escape(name),
+            attributes.join(", ")
+        ));
+    }
+    for (from, to) in graph_edges(dep_map, names) {
+        graph.push_str(&format!(
+            "  \"{}\" -> \"{}\";\n",
+            dot_escape(from),
+            dot_escape(to)
+        ));
+    }
+    graph.push_str("}\n");
+    graph
+}
+
+fn mermaid_graph(
+    dep_map: &HashMap<String, Package>,
+    names: &BTreeSet<String>,
+    direct_deps: &HashSet<String>,
+) -> String {
+    // Package names can contain characters that are not allowed in the ids of
+    // mermaid nodes
+    let ids: HashMap<&str, String> = names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.as_str(), format!("n{index}")))
+        .collect();
+
+    let mut graph = String::from("flowchart LR\n");
+    for name in names {
+        let package = &dep_map[name];
+        let label = mermaid_escape(&format!("{} {}", package.name, package.version));
+        let node = match package.source {
+            PackageSource::Conda => format!("(\"{label}\")"),
+            PackageSource::Pypi => format!("[\"{label}\"]"),
+        };
+        graph.push_str(&format!("  {}{node}\n", ids[name.as_str()]));
+    }
+    for (from, to) in graph_edges(dep_map, names) {
+        graph.push_str(&format!("  {} --> {}\n", ids[from], ids[to]));
+    }
+    let direct = names
+        .iter()
+        .filter(|name| direct_deps.contains(*name))
+        .map(|name| &ids[name.as_str()])
+        .collect_vec();
+    if !direct.is_empty() {
+        graph.push_str("  classDef direct stroke:#2e7d32,stroke-width:3px\n");
+        graph.push_str(&format!("  class {} direct\n", direct.iter().join(",")));
+    }
+    graph
+}
+
+/// Escapes a quoted id or label of a DOT graph.
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escapes a quoted label of a mermaid node, quotes can only be written as an
+/// entity.
+fn mermaid_escape(value: &str) -> String {
+    value.replace('"', "#quot;")
+}
+
+/// Solves only the selected outdated environments and platforms, the locked
+/// packages of all other environments and platforms are kept as they are.
+/// The environments and platforms that are no longer part of the manifest have
+/// already been pruned from `lock_file`, unless `no_prune` is set.
+async fn lock_selected<'p>(
+    workspace: &'p Workspace,
+    original_lock_file: &LockFile,
+    lock_file: LockFile,
+    was_pruned: bool,
+    outdated: OutdatedEnvironments<'p>,
+    glob_hash_cache: GlobHashCache,
+    no_prune: bool,
+) -> miette::Result<LockFileDerivedData<'p>> {
+    if outdated.is_empty() && !was_pruned {
+        return workspace
+            .update_lock_file(UpdateLockFileOptions {
+                lock_file_usage: LockFileUsage::Frozen,
+                no_install: false,
+                max_concurrent_solves: workspace.config().max_concurrent_solves(),
+                no_prune: false,
+            })
+            .await;
+    }
+
+    let mut derived_data = UpdateContext::builder(workspace)
+        .with_outdated_environments(outdated)
+        .with_lock_file(lock_file)
+        .with_glob_hash_cache(glob_hash_cache)
+        .finish()
+        .await?
+        .update()
+        .await?;
+    if no_prune {
+        derived_data.lock_file =
+            restore_stale_targets(workspace, original_lock_file, &derived_data.lock_file);
+    }
+    derived_data.write_to_disk()?;
+    Ok(derived_data)
+}
+
+/// Ensures that the platforms passed with `--platform` are platforms of the
+/// selected environments, or of any environment if none is selected.
+fn ensure_platforms_supported(
+    workspace: &Workspace,
+    environments: Option<&[EnvironmentName]>,
+    platforms: &[Platform],
+) -> miette::Result<()> {
+    let supported_platforms: HashSet<Platform> = workspace
+        .environments()
+        .into_iter()
+        .filter(|env| environments.is_none_or(|names| names.contains(env.name())))
+        .flat_map(|env| env.platforms())
+        .collect();
+
+    let unsupported_platforms = platforms
+        .iter()
+        .filter(|platform| !supported_platforms.contains(platform))
+        .unique()
+        .collect_vec();
+    if unsupported_platforms.is_empty() {
+        return Ok(());
+    }
+
+    Err(miette::miette!(
+        help = format!(
+            "add the platform(s) to the workspace first with `pixi workspace platform add {}`",
+            unsupported_platforms.iter().format(" ")
+        ),
+        "the platform(s) {} are not supported by the workspace, supported platforms are {}",
+        unsupported_platforms
+            .iter()
+            .map(|p| consts::PLATFORM_STYLE.apply_to(p))
+            .format(", "),
+        supported_platforms
+            .iter()
+            .sorted()
+            .map(|p| consts::PLATFORM_STYLE.apply_to(p))
+            .format(", ")
+    ))
+}
+
+/// Refuses to lock a part of a solve group, because all the environments in a
+/// solve group are solved together.
+fn ensure_solve_groups_selected(
+    outdated: &OutdatedEnvironments<'_>,
+    environments: &[EnvironmentName],
+) -> miette::Result<()> {
+    for env in outdated
+        .conda
+        .keys()
+        .chain(outdated.pypi.keys())
+        .unique()
+        .sorted_by_key(|env| env.name().clone())
+    {
+        let Some(solve_group) = env.solve_group() else {
+            continue;
+        };
+        let missing = solve_group
+            .environments()
+            .filter(|env| !environments.contains(env.name()))
+            .collect_vec();
+        if !missing.is_empty() {
+            return Err(miette::miette!(
+                help = format!(
+                    "also select {} with `--environment`",
+                    missing
+                        .iter()
+                        .map(|env| env.name().fancy_display())
+                        .format(", ")
+                ),
+                "{} can't be locked on its own, because it is solved together with the other environments in the solve group '{}'",
+                env.name().fancy_display(),
+                solve_group.name()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Refuses to lock only some platforms of an environment whose locked packages
+/// can't be used anymore for any platform.
+fn ensure_locked_content_kept(outdated: &OutdatedEnvironments<'_>) -> miette::Result<()> {
+    for env in outdated
+        .conda
+        .keys()
+        .chain(outdated.pypi.keys())
+        .unique()
+        .sorted_by_key(|env| env.name().clone())
+    {
+        if outdated
+            .disregard_locked_content
+            .should_disregard_conda(env)
+            || outdated.disregard_locked_content.should_disregard_pypi(env)
+        {
+            return Err(miette::miette!(
+                help = "run `pixi lock` without `--platform` to lock all platforms",
+                "the lock-file of {} has to be solved again for all platforms, e.g. because its channels or indexes changed",
+                env.name().fancy_display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Prints the environments and platforms that are not satisfied by the
+/// lock-file, together with the reason.
+fn print_outdated_reasons(reasons: &[OutdatedReason]) -> std::io::Result<()> {
+    let mut writer = TabWriter::new(std::io::stderr());
+    for reason in reasons.iter().sorted_by_key(|reason| {
+        (
+            reason.environment.as_str(),
+            reason.platform.map(|p| p.as_str()),
+        )
+    }) {
+        let target = match reason.platform {
+            Some(platform) => format!(
+                "{}:{}",
+                reason.environment.fancy_display(),
+                consts::PLATFORM_STYLE.apply_to(platform)
+            ),
+            None => reason.environment.fancy_display().to_string(),
+        };
+        writeln!(writer, "  {}\t{}", target, reason.reason)?;
+    }
+    writer.flush()
+}
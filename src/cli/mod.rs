@@ -34,7 +34,9 @@ pub mod search;
 pub mod self_update;
 pub mod shell;
 pub mod shell_hook;
+mod solve_conflict;
 pub mod task;
+pub mod task_shell;
 pub mod tree;
 pub mod update;
 pub mod upgrade;
@@ -165,6 +167,8 @@ pub enum Command {
     Shell(shell::Args),
     ShellHook(shell_hook::Args),
     Task(task::Args),
+    #[clap(name = "__task-shell", hide = true)]
+    TaskShell(task_shell::Args),
     #[clap(visible_alias = "t")]
     Tree(tree::Args),
     Update(update::Args),
@@ -378,6 +382,7 @@ pub async fn execute_command(
         Command::Shell(cmd) => shell::execute(cmd).await,
         Command::ShellHook(cmd) => shell_hook::execute(cmd).await,
         Command::Task(cmd) => task::execute(cmd).await,
+        Command::TaskShell(cmd) => task_shell::execute(cmd).await,
         Command::Info(cmd) => info::execute(cmd).await,
         Command::Upload(cmd) => upload::execute(cmd).await,
         Command::Search(cmd) => search::execute(cmd).await,
@@ -1,11 +1,11 @@
 use crate::cli::cli_config::WorkspaceConfig;
 use crate::environment::get_update_lock_file_and_prefix;
-use crate::lock_file::{ReinstallPackages, UpdateMode};
+use crate::lock_file::{InstallFilter, ReinstallPackages, UpdateMode};
 use crate::{UpdateLockFileOptions, WorkspaceLocator};
 use clap::Parser;
 use fancy_display::FancyDisplay;
 use itertools::Itertools;
-use pixi_config::ConfigCli;
+use pixi_config::{ConfigCli, ConfigCliNetwork};
 
 /// Re-install an environment, both updating the lockfile and re-installing the environment.
 ///
@@ -37,6 +37,8 @@ pub struct Args {
 
     #[clap(flatten)]
     pub config: ConfigCli,
+    #[clap(flatten)]
+    pub network_config: ConfigCliNetwork,
 
     /// Install all environments.
     #[arg(long, short, conflicts_with = "environment")]
@@ -47,7 +49,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     let workspace = WorkspaceLocator::for_cli()
         .with_search_start(args.project_config.workspace_locator_start())
         .locate()?
-        .with_cli_config(args.config);
+        .with_cli_config(args.network_config.merge_config(args.config.into()));
 
     // Install either:
     //
@@ -84,8 +86,10 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                 lock_file_usage: args.lock_file_usage.clone().try_into()?,
                 no_install: false,
                 max_concurrent_solves: workspace.config().max_concurrent_solves(),
+                no_prune: false,
             },
             reinstall_packages.clone(),
+            InstallFilter::default(),
         )
         .await?;
 
@@ -1,14 +1,28 @@
+use std::{collections::HashSet, path::Path, str::FromStr};
+
 use super::{cli_config::LockFileUpdateConfig, has_specs::HasSpecs};
 use crate::{
-    DependencyType, UpdateLockFileOptions, WorkspaceLocator,
+    DependencyType, UpdateLockFileOptions, Workspace, WorkspaceLocator,
     cli::cli_config::{DependencyConfig, PrefixUpdateConfig, WorkspaceConfig},
-    environment::get_update_lock_file_and_prefix,
-    lock_file::{ReinstallPackages, UpdateMode},
+    diff::{JsonDependencyChanges, JsonManifestChange, LockFileDiff},
+    environment::sanity_check_workspace,
+    lock_file::{InstallFilter, ReinstallPackages, UpdateContext, UpdateMode},
+    workspace::{Environment, WorkspaceMut},
 };
 use clap::Parser;
+use indexmap::IndexSet;
+use itertools::Itertools;
 use miette::{Context, IntoDiagnostic};
-use pixi_config::ConfigCli;
-use pixi_manifest::FeaturesExt;
+use pep508_rs::Requirement;
+use pixi_config::{ConfigCli, ConfigCliNetwork};
+use pixi_manifest::{
+    EnvironmentName, FeatureName, FeaturesExt, ManifestKind, SpecType, TargetSelector,
+    task::CmdArgs,
+};
+use pixi_pypi_spec::PypiPackageName;
+use rattler_conda_types::{PackageName, Platform};
+use rattler_lock::LockedPackage;
+use regex::Regex;
 
 /// Removes dependencies from the workspace.
 ///
@@ -20,6 +34,32 @@ use pixi_manifest::FeaturesExt;
 ///
 /// - pixi `pypi-dependencies` tables of the default feature or, if a feature is
 ///   specified, a named feature
+///
+/// With `--platform` the dependency is only removed from the
+/// `target.<platform>` table, it is an error if it is not defined for that
+/// platform.
+///
+/// Use `--optional <EXTRA>` to only remove the dependency from that group of
+/// the `project.optional-dependencies` table. The group is removed when it
+/// becomes empty, use `--keep-empty-group` to keep it.
+///
+/// Use `--all-features` to remove the dependencies from every feature that
+/// contains them, including the `target.<platform>` tables, at once.
+///
+/// Names containing `*` or `?` are matched against the dependencies of the
+/// selected features, e.g. `pixi remove 'jupyterlab-*'`. Removing more than one
+/// matched package asks for confirmation, use `--yes` to skip it.
+///
+/// A warning is printed when tasks run an executable named after a removed
+/// package, use `--check-tasks` to fail instead.
+///
+/// With `--json` the removed manifest entries and the changes to the lock-file
+/// are printed to stdout, in the same format as `pixi add --json`. The command
+/// fails if none of the dependencies could be found in the manifest.
+///
+/// The `--dry-run` flag solves the environments without the removed
+/// dependencies and prints the lock-file changes that would be made, without
+/// modifying the manifest, the lock file or the environment.
 #[derive(Debug, Default, Parser)]
 #[clap(arg_required_else_help = true)]
 pub struct Args {
@@ -37,21 +77,109 @@ pub struct Args {
 
     #[clap(flatten)]
     pub config: ConfigCli,
+    #[clap(flatten)]
+    pub network_config: ConfigCliNetwork,
+
+    /// Output the changes in JSON format.
+    #[clap(long)]
+    pub json: bool,
+
+    /// Only show the changes that would be made, without actually updating the
+    /// manifest, lock file, or environment.
+    #[clap(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Remove the dependencies from all features that contain them, including
+    /// their platform specific tables.
+    #[clap(long, conflicts_with_all = ["features", "platforms", "host", "build"])]
+    pub all_features: bool,
+
+    /// Remove the pypi dependencies from this group of the
+    /// `project.optional-dependencies` table of a `pyproject.toml` manifest.
+    #[arg(
+        long,
+        value_name = "EXTRA",
+        requires = "pypi",
+        conflicts_with_all = ["features", "platforms", "all_features"]
+    )]
+    pub optional: Option<String>,
+
+    /// Keep the group of `--optional` in the manifest when it becomes empty.
+    #[arg(long, requires = "optional")]
+    pub keep_empty_group: bool,
+
+    /// Remove all the packages matched by a glob pattern without asking for
+    /// confirmation.
+    #[clap(short = 'y', long = "yes")]
+    pub assume_yes: bool,
+
+    /// Fail instead of warning when tasks run an executable of a removed
+    /// package.
+    #[clap(long)]
+    pub check_tasks: bool,
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
-    let (dependency_config, prefix_update_config, lock_file_update_config, workspace_config) = (
+    let (mut dependency_config, prefix_update_config, lock_file_update_config, workspace_config) = (
         args.dependency_config,
         args.prefix_update_config,
         args.lock_file_update_config,
         args.workspace_config,
     );
 
-    let mut workspace = WorkspaceLocator::for_cli()
+    let workspace = WorkspaceLocator::for_cli()
         .with_search_start(workspace_config.workspace_locator_start())
         .locate()?
-        .with_cli_config(args.config.clone())
-        .modify()?;
+        .with_cli_config(
+            args.network_config
+                .clone()
+                .merge_config(args.config.clone().into()),
+        );
+
+    // Build and host dependencies are removed from the package of the workspace
+    if dependency_config.is_package_dependency() && workspace.package.is_none() {
+        return Err(miette::miette!(
+            help = "build and host dependencies can only be removed from a workspace that defines a `[package]`",
+            "the workspace at '{}' does not contain a package",
+            workspace.workspace.provenance.path.display()
+        ));
+    }
+
+    // Optional dependencies are removed from the feature of the same name
+    if let Some(extra) = &args.optional {
+        if workspace.workspace.provenance.kind != ManifestKind::Pyproject {
+            return Err(miette::miette!(
+                help = format!(
+                    "use `--feature {extra}` to remove the dependencies from a feature instead"
+                ),
+                "`--optional` can only be used with a `pyproject.toml` manifest"
+            ));
+        }
+        dependency_config.features = vec![FeatureName::from(extra.clone())];
+    }
+
+    // Replace the glob patterns by the names of the dependencies they match
+    dependency_config.specs = expand_glob_specs(
+        &workspace,
+        &dependency_config,
+        args.all_features,
+        args.assume_yes || args.dry_run,
+    )?;
+
+    // With `--platform` only the `target.<platform>` tables are modified
+    if !dependency_config.platforms.is_empty() {
+        dependency_config.ensure_in_platform_targets(&workspace)?;
+    }
+
+    // Keep the original workspace and lock-file around to report the changes
+    let original = if args.json || args.dry_run {
+        let lock_file = workspace.load_lock_file().await?;
+        Some((workspace.clone(), lock_file))
+    } else {
+        None
+    };
+
+    let mut workspace = workspace.modify()?;
     let dependency_type = dependency_config.dependency_type();
 
     // Prevent removing Python if PyPI dependencies exist
@@ -77,59 +205,763 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             }
         }
     }
-    match dependency_type {
-        DependencyType::PypiDependency => {
-            for name in dependency_config.pypi_deps(workspace.workspace())?.keys() {
-                workspace
-                    .manifest()
-                    .remove_pypi_dependency(
-                        name,
-                        &dependency_config.platforms,
-                        &dependency_config.feature,
-                    )
-                    .wrap_err(format!(
-                        "failed to remove PyPI dependency: '{}'",
-                        name.as_source()
-                    ))?;
+
+    // With `--all-features` the dependencies are removed from every table that
+    // contains them instead of from the selected features.
+    let locations = if args.all_features {
+        let locations = find_in_all_features(workspace.workspace(), &dependency_config)?;
+        remove_locations(&mut workspace, &locations)?;
+        Some(locations)
+    } else {
+        None
+    };
+    if let Some(extra) = &args.optional {
+        remove_optional_dependencies(
+            &mut workspace,
+            &dependency_config,
+            extra,
+            args.keep_empty_group,
+        )?;
+    }
+    let features = if locations.is_some() || args.optional.is_some() {
+        Vec::new()
+    } else {
+        dependency_config.feature_names()
+    };
+    for feature in features {
+        match dependency_type {
+            DependencyType::PypiDependency => {
+                for name in dependency_config.pypi_deps(workspace.workspace())?.keys() {
+                    workspace
+                        .manifest()
+                        .remove_pypi_dependency(name, &dependency_config.platforms, &feature)
+                        .wrap_err(format!(
+                            "failed to remove PyPI dependency: '{}'",
+                            name.as_source()
+                        ))?;
+                }
             }
+            DependencyType::CondaDependency(spec_type @ (SpecType::Build | SpecType::Host)) => {
+                let Some(mut package) = workspace.package() else {
+                    return Err(miette::miette!(
+                        "the package is not defined in the workspace manifest"
+                    ));
+                };
+                for name in dependency_config.specs()?.keys() {
+                    package
+                        .remove_dependency(name, spec_type, &dependency_config.platforms)
+                        .wrap_err(format!(
+                            "failed to remove dependency: '{}'",
+                            name.as_source()
+                        ))?;
+                }
+            }
+            DependencyType::CondaDependency(spec_type) => {
+                for name in dependency_config.specs()?.keys() {
+                    workspace
+                        .manifest()
+                        .remove_dependency(name, spec_type, &dependency_config.platforms, &feature)
+                        .wrap_err(format!(
+                            "failed to remove dependency: '{}'",
+                            name.as_source()
+                        ))?;
+                }
+            }
+        };
+    }
+
+    // Tasks that run an executable of a removed package would fail later on
+    if !dependency_config.is_package_dependency() {
+        let removed = dependency_config.normalized_names(workspace.workspace())?;
+        let tasks = tasks_using_removed_packages(workspace.workspace(), &removed);
+        if !tasks.is_empty() {
+            let message = format!(
+                "the following tasks run an executable of a removed package: {}",
+                tasks.join(", ")
+            );
+            if args.check_tasks {
+                return Err(miette::miette!(
+                    help = "update or remove the tasks, or keep the package",
+                    "{message}"
+                ));
+            }
+            tracing::warn!("{message}");
         }
-        DependencyType::CondaDependency(spec_type) => {
-            for name in dependency_config.specs()?.keys() {
-                workspace
-                    .manifest()
-                    .remove_dependency(
-                        name,
-                        spec_type,
-                        &dependency_config.platforms,
-                        &dependency_config.feature,
+    }
+
+    let manifest_changes = original
+        .as_ref()
+        .map(|(original, _)| match &locations {
+            Some(locations) => Ok(locations
+                .iter()
+                .map(|location| {
+                    dependency_config.manifest_change(
+                        original,
+                        workspace.workspace(),
+                        location.dependency_type,
+                        &location.feature,
+                        location.platform,
+                        &location.name,
                     )
-                    .wrap_err(format!(
-                        "failed to remove dependency: '{}'",
-                        name.as_source()
-                    ))?;
+                })
+                .collect()),
+            None => dependency_config.manifest_changes(original, workspace.workspace()),
+        })
+        .transpose()?
+        .map(|changes| {
+            // Only the entries that were actually in the manifest are reported
+            changes
+                .into_iter()
+                .filter(|change| change.before.is_some() && change.after.is_none())
+                .collect_vec()
+        });
+
+    // Consumers of the JSON output rely on something being removed
+    if args.json && manifest_changes.as_ref().is_some_and(Vec::is_empty) {
+        return Err(miette::miette!(
+            help = "use `pixi list` to see the dependencies of the workspace",
+            "nothing was removed, {} could not be found in the manifest",
+            dependency_config
+                .specs
+                .iter()
+                .map(|spec| format!("'{spec}'"))
+                .join(", ")
+        ));
+    }
+
+    if args.dry_run {
+        // Solve the modified manifest in memory, nothing is written to disk
+        let lock_file_diff = match &original {
+            Some((_, original_lock_file)) if !dependency_config.is_package_dependency() => {
+                let lock_file = UpdateContext::builder(workspace.workspace())
+                    .with_lock_file(original_lock_file.clone())
+                    .with_no_install(true)
+                    .finish()
+                    .await?
+                    .update()
+                    .await?;
+                Some(LockFileDiff::from_lock_files(
+                    original_lock_file,
+                    &lock_file.into_lock_file(),
+                ))
             }
+            _ => None,
+        };
+        let workspace = workspace.revert().await.into_diagnostic()?;
+
+        if args.json {
+            print_json_changes(
+                &workspace,
+                manifest_changes.unwrap_or_default(),
+                lock_file_diff,
+            );
+            return Ok(());
         }
-    };
+
+        display_removed(&dependency_config, locations.as_deref(), "Would remove");
+        match lock_file_diff {
+            Some(lock_file_diff) if lock_file_diff.is_empty() => eprintln!(
+                "{}Lock-file would not change",
+                console::style(console::Emoji("✔ ", "")).green()
+            ),
+            Some(lock_file_diff) => lock_file_diff
+                .print()
+                .into_diagnostic()
+                .context("failed to print lock-file diff")?,
+            None => {}
+        }
+        return Ok(());
+    }
 
     let workspace = workspace.save().await.into_diagnostic()?;
 
     // TODO: update all environments touched by this feature defined.
     // updating prefix after removing from toml
-    if !lock_file_update_config.no_lockfile_update {
-        get_update_lock_file_and_prefix(
-            &workspace.default_environment(),
-            UpdateMode::Revalidate,
-            UpdateLockFileOptions {
+    let mut lock_file_diff = None;
+    // Build and host dependencies are not part of any environment
+    if !lock_file_update_config.no_lockfile_update && !dependency_config.is_package_dependency() {
+        // The lock-file on disk still describes the environments before the removal
+        let previous_lock_file = workspace.load_lock_file().await?;
+        sanity_check_workspace(&workspace).await?;
+        let lock_file = workspace
+            .update_lock_file(UpdateLockFileOptions {
                 lock_file_usage: lock_file_update_config.lock_file_usage()?,
                 no_install: prefix_update_config.no_install,
                 max_concurrent_solves: workspace.config().max_concurrent_solves(),
-            },
-            ReinstallPackages::default(),
-        )
-        .await?;
+                no_prune: false,
+            })
+            .await?;
+        let diff = LockFileDiff::from_lock_files(&previous_lock_file, lock_file.as_lock_file());
+
+        // Only the conda packages that were removed from or changed in the
+        // default environment are touched, instead of updating the whole
+        // prefix. The PyPI packages are synced with the lock-file as usual,
+        // which only touches the packages that changed. With `--revalidate`
+        // every package of the prefix is checked, also if nothing changed.
+        let environment = workspace.default_environment();
+        if !prefix_update_config.no_install
+            && environment
+                .platforms()
+                .contains(&environment.best_platform())
+        {
+            if prefix_update_config.revalidate {
+                lock_file
+                    .prefix(
+                        &environment,
+                        UpdateMode::Revalidate,
+                        &ReinstallPackages::None,
+                        &InstallFilter::default(),
+                    )
+                    .await?;
+            } else if let Some(packages) = affected_packages(&diff, &environment) {
+                lock_file
+                    .prefix(
+                        &environment,
+                        // The manifest changed, so the prefix is always revalidated
+                        UpdateMode::Revalidate,
+                        &ReinstallPackages::None,
+                        &InstallFilter::only(packages),
+                    )
+                    .await?;
+            }
+        }
+        lock_file_diff = Some(diff);
+    }
+
+    if args.json {
+        print_json_changes(
+            &workspace,
+            manifest_changes.unwrap_or_default(),
+            lock_file_diff,
+        );
+        return Ok(());
+    }
+
+    display_removed(&dependency_config, locations.as_deref(), "Removed");
+
+    Ok(())
+}
+
+/// Returns the names of the conda packages of the environment, on the
+/// platform it is installed for, that were removed or changed in the
+/// lock-file. Returns `None` if the installed packages are not affected at
+/// all.
+fn affected_packages(
+    diff: &LockFileDiff,
+    environment: &Environment,
+) -> Option<HashSet<PackageName>> {
+    let packages = diff
+        .environment
+        .get(environment.name().as_str())?
+        .get(&environment.best_platform())?;
+    if packages.removed.is_empty() && packages.changed.is_empty() {
+        return None;
+    }
+    Some(
+        packages
+            .removed
+            .iter()
+            .chain(packages.changed.iter().map(|(previous, _)| previous))
+            .filter_map(|package| match package {
+                LockedPackage::Conda(package) => Some(package.record().name.clone()),
+                LockedPackage::Pypi(..) => None,
+            })
+            .collect(),
+    )
+}
+
+/// Returns the tasks, of all features and platforms, whose command starts with
+/// an executable named after one of the removed packages. Packages that are
+/// still a dependency of the workspace in another table are ignored.
+fn tasks_using_removed_packages(workspace: &Workspace, removed: &[String]) -> Vec<String> {
+    let features = &workspace.workspace.value.features;
+    let mut present = HashSet::new();
+    for target in features
+        .values()
+        .flat_map(|feature| feature.targets.targets())
+    {
+        present.extend(
+            SpecType::all()
+                .filter_map(|spec_type| target.dependencies(spec_type))
+                .flat_map(|dependencies| dependencies.keys())
+                .map(|name| name.as_normalized().to_string()),
+        );
+        present.extend(
+            target
+                .pypi_dependencies
+                .iter()
+                .flat_map(|dependencies| dependencies.keys())
+                .map(|name| name.as_normalized().to_string()),
+        );
+    }
+    let executables: HashSet<&str> = removed
+        .iter()
+        .filter(|name| !present.contains(*name))
+        .map(String::as_str)
+        .collect();
+    if executables.is_empty() {
+        return Vec::new();
+    }
+
+    let mut tasks = Vec::new();
+    for feature in features.values() {
+        for (target, selector) in feature.targets.iter() {
+            for (name, task) in target
+                .tasks
+                .iter()
+                .sorted_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()))
+            {
+                let Some(executable) = task.as_command().as_ref().and_then(command_executable)
+                else {
+                    continue;
+                };
+                if !executables.contains(executable.as_str()) {
+                    continue;
+                }
+                let mut description = format!("'{name}'");
+                if !feature.name.is_default() {
+                    description.push_str(&format!(" of feature '{}'", feature.name));
+                }
+                if let Some(selector) = selector {
+                    description.push_str(&format!(" for '{selector}'"));
+                }
+                tasks.push(description);
+            }
+        }
     }
+    tasks
+}
+
+/// Returns the lowercase name of the executable that a task command starts
+/// with, skipping leading environment variable assignments.
+fn command_executable(command: &CmdArgs) -> Option<String> {
+    let executable = match command {
+        CmdArgs::Single(command) => command
+            .source()
+            .split_whitespace()
+            .find(|token| !token.contains('='))?
+            .to_string(),
+        CmdArgs::Multiple(args) => args.first()?.source().to_string(),
+    };
+    let file_name = Path::new(&executable).file_name()?.to_str()?;
+    Some(file_name.to_lowercase())
+}
+
+/// Returns `true` if the spec is a glob pattern over dependency names instead
+/// of a name or a spec.
+fn is_glob(spec: &str) -> bool {
+    spec.contains(['*', '?'])
+        && spec
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.*?".contains(c))
+}
 
-    dependency_config.display_success("Removed", Default::default());
+/// Converts a glob pattern into a regex that matches the whole name, ignoring
+/// case like the package names themselves do.
+fn glob_regex(pattern: &str) -> miette::Result<Regex> {
+    let regex = pattern
+        .chars()
+        .map(|c| match c {
+            '*' => ".*".to_string(),
+            '?' => ".".to_string(),
+            c => regex::escape(&c.to_string()),
+        })
+        .collect::<String>();
+    Regex::new(&format!("(?i)^{regex}$")).into_diagnostic()
+}
+
+/// Returns the names of the dependencies of the given type in the tables that
+/// are selected by the features and platforms of the dependency config, or in
+/// all tables with `--all-features`.
+fn dependency_names(
+    workspace: &Workspace,
+    dependency_config: &DependencyConfig,
+    all_features: bool,
+) -> IndexSet<String> {
+    let features = dependency_config.feature_names();
+    let is_selected = |selector: Option<&TargetSelector>| match selector {
+        None => all_features || dependency_config.platforms.is_empty(),
+        Some(TargetSelector::Platform(platform)) => {
+            all_features || dependency_config.platforms.contains(platform)
+        }
+        Some(_) => false,
+    };
+
+    let mut names = IndexSet::new();
+    match dependency_config.dependency_type() {
+        DependencyType::CondaDependency(spec_type @ (SpecType::Build | SpecType::Host)) => {
+            let Some(package) = &workspace.package else {
+                return names;
+            };
+            for (target, selector) in package.value.targets.iter() {
+                if is_selected(selector) {
+                    names.extend(
+                        target
+                            .dependencies(spec_type)
+                            .into_iter()
+                            .flat_map(|dependencies| dependencies.keys())
+                            .map(|name| name.as_source().to_string()),
+                    );
+                }
+            }
+        }
+        dependency_type => {
+            for feature in workspace.workspace.value.features.values() {
+                if !all_features && !features.contains(&feature.name) {
+                    continue;
+                }
+                for (target, selector) in feature.targets.iter() {
+                    if !is_selected(selector) {
+                        continue;
+                    }
+                    match dependency_type {
+                        DependencyType::CondaDependency(spec_type) => names.extend(
+                            target
+                                .dependencies(spec_type)
+                                .into_iter()
+                                .flat_map(|dependencies| dependencies.keys())
+                                .map(|name| name.as_source().to_string()),
+                        ),
+                        DependencyType::PypiDependency => names.extend(
+                            target
+                                .pypi_dependencies
+                                .iter()
+                                .flat_map(|dependencies| dependencies.keys())
+                                .map(|name| name.as_source().to_string()),
+                        ),
+                    }
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Replaces the glob patterns among the specs by the names of the dependencies
+/// they match. Fails if a pattern matches nothing, or if more than one package
+/// matches and the removal is not confirmed.
+fn expand_glob_specs(
+    workspace: &Workspace,
+    dependency_config: &DependencyConfig,
+    all_features: bool,
+    assume_yes: bool,
+) -> miette::Result<Vec<String>> {
+    if !dependency_config.specs.iter().any(|spec| is_glob(spec)) {
+        return Ok(dependency_config.specs.clone());
+    }
+
+    let names = dependency_names(workspace, dependency_config, all_features);
+    let mut specs = IndexSet::new();
+    let mut matched = IndexSet::new();
+    for spec in &dependency_config.specs {
+        if !is_glob(spec) {
+            specs.insert(spec.clone());
+            continue;
+        }
+
+        let regex = glob_regex(spec)?;
+        let matches = names
+            .iter()
+            .filter(|name| regex.is_match(name))
+            .collect_vec();
+        if matches.is_empty() {
+            let dependency_type = dependency_config.dependency_type();
+            return Err(miette::miette!(
+                help = if all_features || dependency_config.is_package_dependency() {
+                    format!("searched the {}", dependency_type.name())
+                } else {
+                    format!(
+                        "searched the {} of the feature(s): {}",
+                        dependency_type.name(),
+                        dependency_config
+                            .feature_names()
+                            .iter()
+                            .map(|feature| feature.as_str())
+                            .join(", ")
+                    )
+                },
+                "no dependencies match the pattern '{spec}'"
+            ));
+        }
+        eprintln!(
+            "{}'{spec}' matches {}",
+            console::style(console::Emoji("🔍 ", "")).cyan(),
+            matches
+                .iter()
+                .map(|name| console::style(name).bold())
+                .join(", ")
+        );
+        specs.extend(matches.iter().map(|name| name.to_string()));
+        matched.extend(matches);
+    }
+
+    if matched.len() > 1 {
+        let confirmed = assume_yes
+            || (console::user_attended_stderr()
+                && dialoguer::Confirm::new()
+                    .with_prompt(format!(
+                        "Do you want to remove these {} packages?",
+                        matched.len()
+                    ))
+                    .default(true)
+                    .show_default(true)
+                    .interact()
+                    .into_diagnostic()?);
+        if !confirmed {
+            return Err(miette::miette!(
+                help = "pass `--yes` to remove them without confirmation",
+                "the pattern(s) matched {} packages, not removing them",
+                matched.len()
+            ));
+        }
+    }
+
+    Ok(specs.into_iter().collect())
+}
+
+/// A dependency table of the manifest that contains a dependency that is
+/// removed.
+struct DependencyLocation {
+    /// The normalized name of the dependency.
+    name: String,
+    dependency_type: DependencyType,
+    feature: FeatureName,
+    platform: Option<Platform>,
+}
 
+impl DependencyLocation {
+    /// The name of the table in dotted form, e.g.
+    /// `feature.test.target.linux-64.pypi-dependencies`.
+    fn table_name(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.feature.is_default() {
+            parts.extend(["feature", self.feature.as_str()]);
+        }
+        if let Some(platform) = &self.platform {
+            parts.extend(["target", platform.as_str()]);
+        }
+        parts.push(self.dependency_type.name());
+        parts.join(".")
+    }
+}
+
+/// Finds the dependencies in the default and platform specific tables of all
+/// features. Unless `--pypi` is given, both the conda and the pypi
+/// dependencies are searched. Fails if none of the dependencies can be found.
+fn find_in_all_features(
+    workspace: &Workspace,
+    dependency_config: &DependencyConfig,
+) -> miette::Result<Vec<DependencyLocation>> {
+    let mut searched = vec![(
+        dependency_config.dependency_type(),
+        dependency_config.normalized_names(workspace)?,
+    )];
+    if !dependency_config.pypi {
+        // The specs were parsed as conda specs, only look for the ones that are
+        // valid PyPI requirements as well.
+        let pypi_names = dependency_config
+            .packages()
+            .into_iter()
+            .filter_map(|package| Requirement::parse(package, workspace.root()).ok())
+            .map(|requirement| {
+                PypiPackageName::from_normalized(requirement.name)
+                    .as_normalized()
+                    .to_string()
+            })
+            .collect_vec();
+        searched.push((DependencyType::PypiDependency, pypi_names));
+    }
+
+    let features = &workspace.workspace.value.features;
+    let mut locations = Vec::new();
+    for feature in features.values() {
+        for (target, selector) in feature.targets.iter() {
+            let platform = match selector {
+                None => None,
+                Some(TargetSelector::Platform(platform)) => Some(*platform),
+                Some(_) => continue,
+            };
+            for (dependency_type, names) in &searched {
+                let present: HashSet<String> = match dependency_type {
+                    DependencyType::CondaDependency(spec_type) => target
+                        .dependencies(*spec_type)
+                        .into_iter()
+                        .flat_map(|dependencies| dependencies.keys())
+                        .map(|name| name.as_normalized().to_string())
+                        .collect(),
+                    DependencyType::PypiDependency => target
+                        .pypi_dependencies
+                        .iter()
+                        .flat_map(|dependencies| dependencies.keys())
+                        .map(|name| name.as_normalized().to_string())
+                        .collect(),
+                };
+                locations.extend(
+                    names
+                        .iter()
+                        .filter(|name| present.contains(*name))
+                        .map(|name| DependencyLocation {
+                            name: name.clone(),
+                            dependency_type: *dependency_type,
+                            feature: feature.name.clone(),
+                            platform,
+                        }),
+                );
+            }
+        }
+    }
+
+    if locations.is_empty() {
+        return Err(miette::miette!(
+            help = format!(
+                "searched the {} of the features: {}",
+                searched
+                    .iter()
+                    .map(|(dependency_type, _)| dependency_type.name())
+                    .join(" and "),
+                features.keys().map(|feature| feature.as_str()).join(", ")
+            ),
+            "could not find {} in any feature",
+            dependency_config
+                .packages()
+                .iter()
+                .map(|name| format!("'{name}'"))
+                .join(", ")
+        ));
+    }
+    Ok(locations)
+}
+
+/// Removes the dependencies from the tables they were found in.
+fn remove_locations(
+    workspace: &mut WorkspaceMut,
+    locations: &[DependencyLocation],
+) -> miette::Result<()> {
+    for location in locations {
+        let platforms = location.platform.into_iter().collect_vec();
+        let removed = match location.dependency_type {
+            DependencyType::PypiDependency => {
+                let name = PypiPackageName::from_str(&location.name).into_diagnostic()?;
+                workspace
+                    .manifest()
+                    .remove_pypi_dependency(&name, &platforms, &location.feature)
+            }
+            DependencyType::CondaDependency(spec_type) => {
+                let name = PackageName::from_str(&location.name).into_diagnostic()?;
+                workspace.manifest().remove_dependency(
+                    &name,
+                    spec_type,
+                    &platforms,
+                    &location.feature,
+                )
+            }
+        };
+        removed.wrap_err(format!("failed to remove dependency: '{}'", location.name))?;
+    }
     Ok(())
 }
+
+/// Removes the pypi dependencies from a group of the
+/// `project.optional-dependencies` table.
+///
+/// When the group becomes empty it is removed together with the environment
+/// that `pixi add --optional` creates for it. The group is kept if other
+/// environments use the extra, they would refer to a missing feature otherwise.
+fn remove_optional_dependencies(
+    workspace: &mut WorkspaceMut,
+    dependency_config: &DependencyConfig,
+    extra: &str,
+    keep_empty_group: bool,
+) -> miette::Result<()> {
+    let feature_name = FeatureName::from(extra.to_string());
+    let created_environment = created_extra_environment(workspace.workspace(), extra);
+    let used_by = workspace
+        .workspace()
+        .environments()
+        .iter()
+        .filter(|environment| Some(environment.name()) != created_environment.as_ref())
+        .filter(|environment| environment.features().any(|f| f.name == feature_name))
+        .map(|environment| environment.name().to_string())
+        .collect_vec();
+
+    let names = dependency_config
+        .pypi_deps(workspace.workspace())?
+        .into_keys()
+        .collect_vec();
+    let mut manifest = workspace.manifest();
+    let mut group_removed = false;
+    for name in &names {
+        group_removed = manifest
+            .remove_pypi_optional_dependency(name, extra, keep_empty_group || !used_by.is_empty())
+            .wrap_err(format!(
+                "failed to remove PyPI dependency: '{}'",
+                name.as_source()
+            ))?;
+    }
+
+    if group_removed {
+        if let Some(environment) = created_environment {
+            manifest.remove_environment(environment.as_str())?;
+        }
+    } else if !keep_empty_group
+        && !used_by.is_empty()
+        && manifest
+            .document
+            .manifest()
+            .get_toml_array("project.optional-dependencies", extra)
+            .ok()
+            .flatten()
+            .is_some_and(|group| group.is_empty())
+    {
+        tracing::warn!(
+            "kept the empty optional dependency group '{extra}' because it is used by the environment(s): {}",
+            used_by.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Returns the name of the environment that `pixi add --optional` created for
+/// the extra, if it exists and wasn't changed by the user since.
+fn created_extra_environment(workspace: &Workspace, extra: &str) -> Option<EnvironmentName> {
+    let name = EnvironmentName::normalized(extra).ok()?;
+    let environment = workspace.workspace.value.environments.find(&name)?;
+    let created = environment.features == [extra]
+        && environment.solve_group.is_none()
+        && !environment.no_default_feature
+        && environment.post_install.is_empty()
+        && environment.env_files.is_empty();
+    created.then_some(name)
+}
+
+/// Tells the user which dependencies were removed, when they were removed
+/// from all features this lists every table that was touched.
+fn display_removed(
+    dependency_config: &DependencyConfig,
+    locations: Option<&[DependencyLocation]>,
+    operation: &str,
+) {
+    let Some(locations) = locations else {
+        dependency_config.display_success(operation, Default::default());
+        return;
+    };
+    for location in locations {
+        eprintln!(
+            "{}{operation} {} from [{}]",
+            console::style(console::Emoji("✔ ", "")).green(),
+            console::style(&location.name).bold(),
+            location.table_name()
+        );
+    }
+}
+
+/// Prints the changes made to the manifest and the lock-file as JSON to stdout.
+fn print_json_changes(
+    workspace: &Workspace,
+    manifest_changes: Vec<JsonManifestChange>,
+    lock_file_diff: Option<LockFileDiff>,
+) {
+    let json = JsonDependencyChanges::new(Some(workspace), manifest_changes, lock_file_diff);
+    let json = serde_json::to_string_pretty(&json).expect("failed to convert to json");
+    println!("{}", json);
+}
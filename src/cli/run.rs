@@ -1,34 +1,55 @@
 use std::{
+    cell::RefCell,
     collections::{HashMap, HashSet, hash_map::Entry},
     convert::identity,
     ffi::OsString,
+    io::{IsTerminal, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    pin::{Pin, pin},
     string::String,
     sync::{
         Arc,
         atomic::{AtomicBool, Ordering},
     },
+    time::{Duration, Instant},
 };
 
+use chrono::Utc;
 use clap::Parser;
+use deno_task_shell::{KillSignal, SignalKind};
 use dialoguer::theme::ColorfulTheme;
 use fancy_display::FancyDisplay;
+use futures::{StreamExt, stream::FuturesUnordered};
 use itertools::Itertools;
 use miette::{Diagnostic, IntoDiagnostic};
-use pixi_config::{ConfigCli, ConfigCliActivation};
-use pixi_manifest::{FeaturesExt, TaskName};
+use pixi_config::{AmbiguousTaskChoice, ConfigCli, ConfigCliActivation, ConfigCliNetwork};
+use pixi_consts::consts;
+use pixi_manifest::{FeaturesExt, TaskName, task::TaskShell};
 use rattler_conda_types::Platform;
+use serde::Serialize;
 use thiserror::Error;
+use tokio::sync::Mutex;
 use tracing::Level;
 
 use super::cli_config::LockFileUpdateConfig;
 use crate::{
     Workspace, WorkspaceLocator,
-    cli::cli_config::{PrefixUpdateConfig, WorkspaceConfig},
-    environment::sanity_check_workspace,
-    lock_file::{ReinstallPackages, UpdateLockFileOptions},
+    cli::{
+        cli_config::{PrefixUpdateConfig, ReinstallConfig, WorkspaceConfig},
+        install::exit_on_post_install_failure,
+    },
+    environment::{PostInstallTaskFailed, run_post_install_tasks, sanity_check_workspace},
+    lock_file::{
+        InstallFilter, LockFileDerivedData, ReinstallPackages, UpdateLockFileOptions, UpdateMode,
+    },
     task::{
-        AmbiguousTask, CanSkip, ExecutableTask, FailedToParseShellScript, InvalidWorkingDirectory,
-        SearchEnvironments, TaskAndEnvironment, TaskGraph, get_task_env,
+        self, AmbiguousTask, CanSkip, DEFAULT_GRACE_PERIOD, ExecutableTask,
+        FailedToParseShellScript, FileWatcher, InvalidWorkingDirectory, MissingShellError,
+        REPORT_OUTPUT_LIMIT, RunOutput, SearchEnvironments, TaskAndEnvironment, TaskGraph, TaskId,
+        TaskOutput, TaskProcesses, TaskScheduler, WatchChange, WatchSnapshot, WatchedPaths,
+        get_task_env, print_environment_choice, resolve_working_directory, signal_exit_code,
+        wait_for_interrupt, write_json_report,
     },
     workspace::{Environment, errors::UnsupportedPlatformError},
 };
@@ -41,6 +62,9 @@ use crate::{
 ///
 /// `pixi run` will also update the lockfile and install the environment if it
 /// is required.
+///
+/// Without a task, `pixi run` lists the available tasks by environment and
+/// feature.
 #[derive(Parser, Debug, Default)]
 #[clap(trailing_var_arg = true, disable_help_flag = true)]
 pub struct Args {
@@ -54,18 +78,26 @@ pub struct Args {
     #[clap(flatten)]
     pub prefix_update_config: PrefixUpdateConfig,
 
+    #[clap(flatten)]
+    pub reinstall_config: ReinstallConfig,
+
     #[clap(flatten)]
     pub lock_file_update_config: LockFileUpdateConfig,
 
     #[clap(flatten)]
     pub config: ConfigCli,
+    #[clap(flatten)]
+    pub network_config: ConfigCliNetwork,
 
     #[clap(flatten)]
     pub activation_config: ConfigCliActivation,
 
-    /// The environment to run the task in.
+    /// The environment to run the task in, can be given multiple times to run
+    /// the task in every environment, one after the other unless `--jobs` is
+    /// given. `all` runs the task in every environment that defines it, unless
+    /// the workspace has an environment named `all`
     #[arg(long, short)]
-    pub environment: Option<String>,
+    pub environment: Vec<String>,
 
     /// Use a clean environment to run the task
     ///
@@ -74,15 +106,114 @@ pub struct Args {
     #[arg(long)]
     pub clean_env: bool,
 
+    /// A variable of your current shell to keep in a clean environment, can be
+    /// given multiple times and may contain `*` and `?` wildcards, e.g.
+    /// `--keep-env 'AWS_*'`. Adds to the `clean-env-keep` of the task
+    #[arg(long = "keep-env", value_name = "NAME")]
+    pub keep_env: Vec<String>,
+
+    /// The working directory to run the tasks in, relative to the root of the
+    /// workspace. Overrides the `cwd` of the tasks
+    #[arg(long, value_name = "PATH")]
+    pub cwd: Option<PathBuf>,
+
+    /// The maximum time that every task may run, e.g. `10m` or `1h 30m`.
+    /// Overrides the `timeout` of the tasks
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// The number of times that a task that failed is run again before the
+    /// run fails. Overrides the `retries` of the tasks
+    #[arg(long, value_name = "N")]
+    pub retries: Option<u32>,
+
+    /// The shell that runs a command that isn't a task: `deno` (the built-in
+    /// shell), `bash`, `nu` or `powershell`. The shell has to be installed in
+    /// the environment. Tasks use their own `shell`
+    #[arg(long, value_name = "SHELL")]
+    pub shell: Option<TaskShell>,
+
+    /// Run the tasks attached to the terminal without capturing their output,
+    /// for programs that need a terminal like TUIs. Same as the `interactive`
+    /// field of a task. Interactive tasks can't run at the same time as other
+    /// tasks
+    #[arg(long)]
+    pub interactive: bool,
+
     /// Don't run the dependencies of the task ('depends-on' field in the task
-    /// definition)
+    /// definition), only the task itself. If the task is an alias, the tasks
+    /// it refers to are run without their dependencies. The dependencies are
+    /// not resolved, so they don't have to be available
     #[arg(long)]
     pub skip_deps: bool,
 
-    /// Run the task in dry-run mode (only print the command that would run)
+    /// Run the task in dry-run mode (only print the tasks that would run, in
+    /// order, with their environment, working directory, command and
+    /// environment variables, without updating the lock-file or installing
+    /// the environment)
     #[clap(short = 'n', long)]
     pub dry_run: bool,
 
+    /// Don't run the `post-install` tasks when the environment is installed
+    #[arg(long)]
+    pub no_hooks: bool,
+
+    /// The number of tasks that don't depend on each other to run at the same
+    /// time. Defaults to the number of logical CPUs, `--jobs 1` runs the tasks
+    /// one after the other
+    #[arg(long)]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Run the tasks even if their inputs and outputs didn't change since they
+    /// last ran
+    #[arg(long)]
+    pub force: bool,
+
+    /// Keep running and re-run the tasks when their `inputs` change. A run that
+    /// is still in progress when a file changes is cancelled
+    #[arg(long, conflicts_with = "dry_run")]
+    pub watch: bool,
+
+    /// The files to watch instead of the `inputs` of the tasks, can be given
+    /// multiple times and may contain globs
+    #[arg(long = "watch-path", value_name = "PATH", requires = "watch")]
+    pub watch_path: Vec<String>,
+
+    /// The time in milliseconds that the watched files have to be unchanged
+    /// before the tasks are re-run
+    #[arg(long, value_name = "MS", default_value_t = 200, requires = "watch")]
+    pub debounce: u64,
+
+    /// The time in seconds that the tasks get to stop after pixi is
+    /// interrupted or a task timed out, after which the processes that are
+    /// still running are killed
+    #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_GRACE_PERIOD.as_secs())]
+    pub grace_period: u64,
+
+    /// Keep running the tasks that don't depend on a task that failed, instead
+    /// of stopping at the first failure. A summary of the tasks is printed at
+    /// the end and pixi exits with the exit code of the first failure
+    #[arg(long)]
+    pub continue_on_error: bool,
+
+    /// Write a report of the tasks that ran to a file, with their timing,
+    /// exit code and the start of their output, e.g. `--report json
+    /// report.json`. The output is only included when it doesn't go to a
+    /// terminal. The report is also written when a task fails. The only
+    /// format is `json`
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["FORMAT", "FILE"],
+        conflicts_with_all = ["watch", "dry_run"]
+    )]
+    pub report: Vec<String>,
+
+    /// Print the available tasks as JSON, when no task is given. Meant for
+    /// editors and shell completion scripts
+    #[arg(long, conflicts_with = "task")]
+    pub json: bool,
+
     #[clap(long, action = clap::ArgAction::HelpLong)]
     pub help: Option<bool>,
 
@@ -90,13 +221,156 @@ pub struct Args {
     pub h: Option<bool>,
 }
 
+impl Args {
+    /// Returns the number of tasks that may run at the same time, the number
+    /// of logical CPUs unless `--jobs` is given.
+    pub fn jobs(&self) -> usize {
+        self.jobs
+            .or_else(|| std::thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get)
+    }
+}
+
 /// CLI entry point for `pixi run`
-/// When running the sigints are ignored and child can react to them. As it
-/// pleases.
+///
+/// SIGINT and SIGTERM are forwarded to the processes of the running tasks,
+/// which get the grace period to stop before they are killed.
 pub async fn execute(args: Args) -> miette::Result<()> {
-    let cli_config = args
-        .activation_config
-        .merge_config(args.config.clone().into());
+    // In watch mode CTRL-C stops watching instead of exiting the process
+    if args.watch {
+        return watch(&args).await;
+    }
+
+    let report_path = report_path(&args)?;
+    let report = RefCell::new(Vec::new());
+    let report = report_path.as_ref().map(|_| &report);
+    let ctrlc_should_exit_process = Arc::new(AtomicBool::new(true));
+
+    let kill_signal = KillSignal::default();
+    let processes = TaskProcesses::new();
+    let mut run = pin!(run_tasks(
+        &args,
+        &ctrlc_should_exit_process,
+        kill_signal.clone(),
+        None,
+        report
+    ));
+    let signal = tokio::select! {
+        result = &mut run => {
+            write_report(report_path.as_deref(), report)?;
+            return exit_on_task_failure(result);
+        }
+        signal = wait_for_interrupt() => signal.into_diagnostic()?,
+    };
+    reset_cursor();
+    if ctrlc_should_exit_process.load(Ordering::Relaxed) {
+        match signal {
+            SignalKind::SIGINT => exit_process_on_sigint(),
+            _ => std::process::exit(signal_exit_code(signal)),
+        }
+    }
+
+    // Forward the signal to the processes of the tasks and give them the
+    // grace period to stop. The processes that are still running after it, or
+    // when pixi is interrupted again, are killed.
+    kill_signal.send(signal);
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(args.grace_period);
+    let result = tokio::select! {
+        result = &mut run => result,
+        _ = tokio::time::sleep_until(deadline) => {
+            kill_signal.send(SignalKind::SIGKILL);
+            run.await
+        }
+        _ = wait_for_interrupt() => {
+            kill_signal.send(SignalKind::SIGKILL);
+            run.await
+        }
+    };
+
+    let exit_code = signal_exit_code(signal);
+    if let Err(err) = result {
+        match err.downcast_ref::<TaskExecutionError>() {
+            Some(TaskExecutionError::Interrupted(_)) => eprintln!(
+                "{}{}",
+                console::Emoji("❌ ", ""),
+                console::style(format!("{err}, exiting with code {exit_code}")).red()
+            ),
+            _ => eprintln!("{err:?}"),
+        }
+    }
+    if let Err(err) = write_report(report_path.as_deref(), report) {
+        eprintln!("{err:?}");
+    }
+
+    // Processes that a task started in the background may outlive the task
+    if !processes.wait_for_exit(deadline).await {
+        processes.kill();
+    }
+
+    // 128 + the signal, also for CTRL-C on Windows
+    std::process::exit(exit_code);
+}
+
+/// Returns the file to write the report of the run to, if any.
+fn report_path(args: &Args) -> miette::Result<Option<PathBuf>> {
+    match args.report.as_slice() {
+        [] => Ok(None),
+        [format, path] if format == "json" => Ok(Some(PathBuf::from(path))),
+        [format, _] => {
+            miette::bail!("unsupported report format '{format}', the only format is 'json'")
+        }
+        _ => unreachable!("clap requires two values for `--report`"),
+    }
+}
+
+/// Writes the report of the tasks that ran, if a report was requested.
+fn write_report(
+    path: Option<&Path>,
+    report: Option<&RefCell<Vec<TaskOutput>>>,
+) -> miette::Result<()> {
+    if let (Some(path), Some(report)) = (path, report) {
+        write_json_report(path, &report.borrow())?;
+    }
+    Ok(())
+}
+
+/// Exits with the exit code of a task that failed, other errors are returned.
+fn exit_on_task_failure(result: miette::Result<()>) -> miette::Result<()> {
+    match result {
+        // A command that wasn't found exits with the exit code of the shell,
+        // also when a similar task is suggested.
+        Err(err) if err.downcast_ref::<UnknownTaskError>().is_some() => {
+            eprintln!("{err:?}");
+            std::process::exit(127)
+        }
+        Err(err) if err.downcast_ref::<PostInstallTaskFailed>().is_some() => {
+            exit_on_post_install_failure(Err(err))
+        }
+        Err(err) => match err.downcast_ref::<TaskExecutionError>() {
+            Some(TaskExecutionError::NonZeroExitCode(code)) => std::process::exit(*code),
+            _ => Err(err),
+        },
+        Ok(()) => Ok(()),
+    }
+}
+
+/// Locates the workspace, builds the task graph and runs its tasks. The
+/// `kill_signal` kills the running tasks when it is sent.
+///
+/// In watch mode the files to watch for the next run, together with a
+/// snapshot of them, are stored in `watch_state` just before the tasks start.
+async fn run_tasks(
+    args: &Args,
+    ctrlc_should_exit_process: &Arc<AtomicBool>,
+    kill_signal: KillSignal,
+    watch_state: Option<&RefCell<Option<WatchState>>>,
+    report: Option<&RefCell<Vec<TaskOutput>>>,
+) -> miette::Result<()> {
+    let cli_config = args.activation_config.merge_config(
+        args.network_config
+            .clone()
+            .merge_config(args.config.clone().into()),
+    );
 
     // Load the workspace
     let workspace = WorkspaceLocator::for_cli()
@@ -104,26 +378,86 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         .locate()?
         .with_cli_config(cli_config);
 
-    // Extract the passed in environment name.
-    let environment = workspace.environment_from_name_or_env_var(args.environment.clone())?;
-
-    // Find the environment to run the task in, if any were specified.
-    let explicit_environment = if args.environment.is_none() && environment.is_default() {
-        None
-    } else {
-        Some(environment.clone())
-    };
+    // The environments to run the tasks in, `None` if no environment was
+    // specified and the tasks run in the environments that define them.
+    let explicit_environments = explicit_environments(&workspace, args)?;
 
     // Print all available tasks if no task is provided
     if args.task.is_empty() {
-        command_not_found(&workspace, explicit_environment);
+        let environments = explicit_environments
+            .iter()
+            .flatten()
+            .cloned()
+            .collect_vec();
+        let available = if environments.is_empty() {
+            available_tasks(&workspace.environments())
+        } else {
+            available_tasks(&environments)
+        };
+        if args.json {
+            let json = serde_json::to_string_pretty(&available).into_diagnostic()?;
+            println!("{json}");
+        } else {
+            print_available_tasks(&available);
+            print_platform_help(&workspace);
+        }
         return Ok(());
     }
 
     // Sanity check of prefix location
     sanity_check_workspace(&workspace).await?;
 
-    let best_platform = environment.best_platform();
+    let reinstall_packages = args.reinstall_config.reinstall_packages();
+
+    // Construct a task graph from the input arguments for every environment
+    let ambiguous_choice = workspace.config().run().ambiguous();
+    let mut runs = Vec::with_capacity(explicit_environments.len());
+    for explicit_environment in explicit_environments {
+        let best_platform = explicit_environment
+            .as_ref()
+            .map_or_else(|| workspace.default_environment(), Clone::clone)
+            .best_platform();
+        let search_environment = SearchEnvironments::from_opt_env(
+            &workspace,
+            explicit_environment.clone(),
+            Some(best_platform),
+        )
+        .with_disambiguate_fn(|problem| disambiguate_task(problem, ambiguous_choice));
+
+        let task_graph = TaskGraph::from_cmd_args(
+            &workspace,
+            &search_environment,
+            args.task.clone(),
+            args.skip_deps,
+        )?;
+
+        tracing::info!("Task graph: {}", task_graph);
+        runs.push(EnvironmentRun::new(explicit_environment, task_graph));
+    }
+
+    // The working directory that overrides the `cwd` of the tasks has to exist
+    // before any task runs.
+    let cwd_override = args
+        .cwd
+        .as_deref()
+        .map(|cwd| resolve_working_directory(workspace.root(), cwd))
+        .transpose()?;
+
+    // On dry-run mode, we only print the tasks. The task graph doesn't depend on
+    // the lock-file, so it is not updated and no prefix is installed.
+    if args.dry_run {
+        return print_dry_run(
+            runs.iter().map(|run| &run.task_graph),
+            cwd_override,
+            args.timeout,
+            args.shell,
+            args.retries,
+        );
+    }
+
+    // Interactive tasks need the terminal to themselves, which is checked
+    // before the lock-file is updated and any task runs.
+    check_interactive_tasks(&runs, args, report.is_some())?;
 
     // Ensure that the lock-file is up-to-date.
     let lock_file = workspace
@@ -134,219 +468,1154 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         })
         .await?;
 
-    let ctrlc_should_exit_process = Arc::new(AtomicBool::new(true));
-    let ctrlc_should_exit_process_clone = Arc::clone(&ctrlc_should_exit_process);
+    // The snapshot is taken after the lock-file is updated, so that updating
+    // it doesn't trigger another run.
+    if let Some(watch_state) = watch_state {
+        let paths = WatchedPaths::from_task_graphs(
+            runs.iter().map(|run| &run.task_graph),
+            workspace.root(),
+            &args.watch_path,
+            workspace_watch_globs(&workspace),
+        );
+        let snapshot = paths.snapshot().await.into_diagnostic()?;
+        watch_state.replace(Some(WatchState { paths, snapshot }));
+    }
+
+    let jobs = args.jobs();
+    let context = TaskRunContext {
+        lock_file: &lock_file,
+        update_mode: args.prefix_update_config.update_mode(),
+        reinstall_packages: &reinstall_packages,
+        clean_env: args.clean_env,
+        keep_env: &args.keep_env,
+        no_hooks: args.no_hooks,
+        force: args.force,
+        task_envs: Mutex::new(HashMap::new()),
+        ctrlc_should_exit_process: Arc::clone(ctrlc_should_exit_process),
+        kill_signal,
+        report,
+    };
+
+    // Start the tasks whose dependencies have finished, at most `jobs` at the
+    // same time. Tasks that may run at the same time as another task have
+    // every line of their output prefixed with their name, so that the output
+    // of the tasks can be told apart, and they don't read from the terminal.
+    // The environments run one after the other, unless `--jobs` is given.
+    let multiple_environments = runs.len() > 1;
+    let parallel_environments = args.jobs.is_some();
+    let mut running = FuturesUnordered::new();
+    let mut running_tasks: HashSet<(usize, TaskId)> = HashSet::new();
+    let mut task_idx = 0;
+    loop {
+        while running.len() < jobs {
+            // Without parallel environments only the first environment that
+            // has tasks left is considered.
+            let current_run = runs.iter().enumerate().position(|(idx, run)| {
+                run.scheduler.has_pending()
+                    || running_tasks.iter().any(|(run_idx, _)| *run_idx == idx)
+            });
+            let Some((run_idx, task_id)) = runs
+                .iter_mut()
+                .enumerate()
+                .filter(|(idx, _)| parallel_environments || Some(*idx) == current_run)
+                .find_map(|(idx, run)| Some((idx, run.scheduler.next_ready()?)))
+            else {
+                break;
+            };
+            let executable_task =
+                ExecutableTask::from_task_graph(&runs[run_idx].task_graph, task_id)
+                    .with_cwd_override(cwd_override.clone())
+                    .with_timeout_override(args.timeout)
+                    .with_shell_override(args.shell)
+                    .with_interactive_override(args.interactive)
+                    .with_retries_override(args.retries)
+                    .with_grace_period(Duration::from_secs(args.grace_period));
+
+            // If the task is not executable (e.g. an alias), we skip it. This ensures we
+            // don't instantiate a prefix for an alias.
+            if !executable_task.task().is_executable() {
+                runs[run_idx].scheduler.finish(task_id);
+                continue;
+            }
+
+            // The dependencies of the task are skipped with `--skip-deps`.
+            if executable_task.skipped {
+                if tracing::enabled!(Level::INFO) {
+                    print_skipped_task(&executable_task);
+                }
+                if let Some(report) = report {
+                    report
+                        .borrow_mut()
+                        .push(TaskOutput::for_task(&executable_task));
+                }
+                runs[run_idx].scheduler.finish(task_id);
+                continue;
+            }
 
-    ctrlc::set_handler(move || {
-        reset_cursor();
-        if ctrlc_should_exit_process_clone.load(Ordering::Relaxed) {
-            exit_process_on_sigint();
+            // Showing which command is being run if the level and type allows it.
+            if tracing::enabled!(Level::WARN) && !executable_task.task().is_custom() {
+                if task_idx > 0 {
+                    // Add a newline between task outputs
+                    eprintln!();
+                }
+                print_task_header(&workspace, &executable_task);
+            }
+            task_idx += 1;
+
+            let capture_output = jobs > 1
+                && may_overlap(
+                    &runs,
+                    &running_tasks,
+                    parallel_environments,
+                    run_idx,
+                    task_id,
+                );
+            running_tasks.insert((run_idx, task_id));
+            let continue_on_error =
+                args.continue_on_error || executable_task.task().continue_on_error();
+            let context = &context;
+            running.push(async move {
+                let result = run_task(context, &executable_task, capture_output).await;
+                (
+                    run_idx,
+                    task_id,
+                    task_label(&executable_task),
+                    continue_on_error,
+                    result,
+                )
+            });
         }
-    })
-    .into_diagnostic()?;
 
-    // Construct a task graph from the input arguments
-    let search_environment = SearchEnvironments::from_opt_env(
-        &workspace,
-        explicit_environment.clone(),
-        Some(best_platform),
-    )
-    .with_disambiguate_fn(disambiguate_task_interactive);
+        let Some((run_idx, task_id, label, continue_on_error, result)) = running.next().await
+        else {
+            break;
+        };
+        running_tasks.remove(&(run_idx, task_id));
+        let run = &mut runs[run_idx];
+        run.scheduler.finish(task_id);
 
-    let task_graph =
-        TaskGraph::from_cmd_args(&workspace, &search_environment, args.task, args.skip_deps)?;
+        // A failure cancels the tasks of the environment that have not been
+        // started yet, the running tasks and the other environments are
+        // allowed to finish. With `continue-on-error` only the tasks that
+        // depend on the failed task are skipped.
+        match result {
+            Ok(()) => run.succeeded.push(label),
+            Err(err) => {
+                if continue_on_error {
+                    let skipped = run.scheduler.skip_dependents(task_id);
+                    run.skipped.extend(
+                        skipped
+                            .into_iter()
+                            .filter(|task_id| run.task_graph[*task_id].task.is_executable())
+                            .map(|task_id| {
+                                task_label(&ExecutableTask::from_task_graph(
+                                    &run.task_graph,
+                                    task_id,
+                                ))
+                            }),
+                    );
+                    run.continued = true;
+                } else {
+                    run.scheduler.cancel();
+                }
+                run.failures.push((label, err));
+            }
+        }
 
-    tracing::info!("Task graph: {}", task_graph);
+        if running.is_empty() {
+            // Handle CTRL-C ourselves again
+            ctrlc_should_exit_process.store(true, Ordering::Relaxed);
+        }
+    }
 
-    // Print dry-run message if dry-run mode is enabled
-    if args.dry_run {
-        eprintln!(
-            "{}{}",
-            console::Emoji("🌵 ", ""),
-            console::style("Dry-run mode enabled - no tasks will be executed.")
-                .yellow()
-                .bold(),
-        );
-        eprintln!();
+    if runs.iter().any(|run| run.continued) {
+        print_task_summary(&runs);
+    }
+    if multiple_environments {
+        print_environment_summary(&runs);
     }
 
-    // Traverse the task graph in topological order and execute each individual
-    // task.
-    let mut task_idx = 0;
-    let mut task_envs = HashMap::new();
-    for task_id in task_graph.topological_order() {
-        let executable_task = ExecutableTask::from_task_graph(&task_graph, task_id);
+    // The first task that failed determines the result of the run.
+    let Some((explicit_environment, err)) = runs.into_iter().find_map(|run| {
+        let (_, err) = run.failures.into_iter().next()?;
+        Some((run.explicit_environment, err))
+    }) else {
+        return Ok(());
+    };
+    match err.downcast_ref::<TaskExecutionError>() {
+        Some(TaskExecutionError::NonZeroExitCode(127)) => Err(command_not_found(
+            &workspace,
+            explicit_environment,
+            args,
+            err,
+        )),
+        _ => Err(err),
+    }
+}
 
-        // If the task is not executable (e.g. an alias), we skip it. This ensures we
-        // don't instantiate a prefix for an alias.
-        if !executable_task.task().is_executable() {
-            continue;
+/// Returns true if the task may run at the same time as another task: a task
+/// of another environment that is running or that has not been started yet,
+/// if the environments run in parallel, or such a task of the same environment
+/// that neither depends on the task nor is a dependency of it.
+fn may_overlap(
+    runs: &[EnvironmentRun<'_>],
+    running_tasks: &HashSet<(usize, TaskId)>,
+    parallel_environments: bool,
+    run_idx: usize,
+    task_id: TaskId,
+) -> bool {
+    runs.iter().enumerate().any(|(idx, run)| {
+        if !parallel_environments && idx != run_idx {
+            return false;
+        }
+        let running = running_tasks
+            .iter()
+            .filter(move |(other_idx, _)| *other_idx == idx)
+            .map(|(_, other)| *other);
+        let pending = run
+            .scheduler
+            .pending()
+            .filter(|other| run.executable_tasks.contains(other));
+        let mut others = running.chain(pending);
+        if idx == run_idx {
+            run.scheduler.may_overlap(task_id, others)
+        } else {
+            others.next().is_some()
         }
+    })
+}
 
-        // Showing which command is being run if the level and type allows it.
-        if tracing::enabled!(Level::WARN) && !executable_task.task().is_custom() {
-            if task_idx > 0 {
-                // Add a newline between task outputs
-                eprintln!();
+/// Returns an error if an interactive task can't be attached to the terminal,
+/// because it may run at the same time as other tasks or because its output
+/// is captured for the report.
+fn check_interactive_tasks(
+    runs: &[EnvironmentRun<'_>],
+    args: &Args,
+    report: bool,
+) -> Result<(), InteractiveTaskError> {
+    let captured = report && !std::io::stdout().is_terminal();
+    let parallel_environments = args.jobs.is_some() && runs.len() > 1;
+    for (run_idx, run) in runs.iter().enumerate() {
+        for &task_id in &run.executable_tasks {
+            let executable_task = ExecutableTask::from_task_graph(&run.task_graph, task_id)
+                .with_interactive_override(args.interactive);
+            if !executable_task.interactive() {
+                continue;
+            }
+            let name = task_label(&executable_task);
+            if captured {
+                return Err(InteractiveTaskError::Captured(name));
+            }
+            let overlaps_other_environment = parallel_environments
+                && runs
+                    .iter()
+                    .enumerate()
+                    .any(|(idx, other)| idx != run_idx && !other.executable_tasks.is_empty());
+            if args.jobs() > 1
+                && (overlaps_other_environment
+                    || run
+                        .scheduler
+                        .may_overlap(task_id, run.executable_tasks.iter().copied()))
+            {
+                return Err(InteractiveTaskError::Parallel(name));
             }
+        }
+    }
+    Ok(())
+}
+
+/// An interactive task that can't be attached to the terminal.
+#[derive(Debug, Error, Diagnostic)]
+enum InteractiveTaskError {
+    #[error("the interactive task '{0}' can't run at the same time as other tasks")]
+    #[diagnostic(help("run the tasks one after the other with `--jobs 1`"))]
+    Parallel(String),
+
+    #[error("the output of the interactive task '{0}' can't be captured for the report")]
+    #[diagnostic(help("run the task in a terminal or without `--report`"))]
+    Captured(String),
+}
+
+/// The tasks of a run in a single environment.
+struct EnvironmentRun<'p> {
+    /// The environment that was specified, or `None` if the tasks run in the
+    /// environments that define them.
+    explicit_environment: Option<Environment<'p>>,
+    task_graph: TaskGraph<'p>,
+    scheduler: TaskScheduler,
+    /// The tasks of the graph that run a command.
+    executable_tasks: Vec<TaskId>,
+    /// The tasks that finished successfully.
+    succeeded: Vec<String>,
+    /// The tasks that failed, in the order in which they failed.
+    failures: Vec<(String, miette::Report)>,
+    /// The tasks that were skipped because a task they depend on failed.
+    skipped: Vec<String>,
+    /// Whether the run continued after a task failed.
+    continued: bool,
+}
+
+impl<'p> EnvironmentRun<'p> {
+    fn new(explicit_environment: Option<Environment<'p>>, task_graph: TaskGraph<'p>) -> Self {
+        let executable_tasks = task_graph
+            .topological_order()
+            .into_iter()
+            .filter(|task_id| {
+                let node = &task_graph[*task_id];
+                node.task.is_executable() && !node.skipped
+            })
+            .collect_vec();
+        Self {
+            explicit_environment,
+            scheduler: TaskScheduler::new(&task_graph),
+            task_graph,
+            executable_tasks,
+            succeeded: Vec::new(),
+            failures: Vec::new(),
+            skipped: Vec::new(),
+            continued: false,
+        }
+    }
+}
 
-            let display_command = executable_task.display_command().to_string();
+/// Returns the environments to run the tasks in, in the order in which they
+/// were given. `None` stands for the environments that define the tasks, if
+/// no environment was specified.
+///
+/// `--environment all` expands to the environments that define the task,
+/// unless the workspace has an environment named `all`.
+fn explicit_environments<'p>(
+    workspace: &'p Workspace,
+    args: &Args,
+) -> miette::Result<Vec<Option<Environment<'p>>>> {
+    if args.environment.iter().any(|name| name == "all") && workspace.environment("all").is_none() {
+        if args.environment.len() > 1 {
+            miette::bail!("`--environment all` can't be combined with other environments");
+        }
+        let Some(task_name) = task_name(&args.task) else {
+            return Ok(vec![None]);
+        };
+        let environments = workspace.environments_with_task(&task_name);
+        if environments.is_empty() {
+            miette::bail!(
+                "none of the environments define the task '{}'",
+                task_name.fancy_display()
+            );
+        }
+        return Ok(environments.into_iter().map(Some).collect());
+    }
+
+    if let [] | [_] = args.environment.as_slice() {
+        let environment =
+            workspace.environment_from_name_or_env_var(args.environment.first().cloned())?;
+        return if args.environment.is_empty() && environment.is_default() {
+            Ok(vec![None])
+        } else {
+            Ok(vec![Some(environment)])
+        };
+    }
 
+    args.environment
+        .iter()
+        .unique()
+        .map(|name| {
+            workspace
+                .environment_from_name_or_env_var(Some(name.clone()))
+                .map(Some)
+        })
+        .collect()
+}
+
+/// Returns the name of the task that is run, the first word of the command.
+fn task_name(task: &[String]) -> Option<TaskName> {
+    let name = match task {
+        [command] => shlex::split(command)?.into_iter().next()?,
+        _ => task.first()?.clone(),
+    };
+    Some(TaskName::from(name))
+}
+
+/// Prints the tasks that succeeded, failed and were skipped, after a run that
+/// continued when a task failed.
+fn print_task_summary(runs: &[EnvironmentRun<'_>]) {
+    eprintln!();
+    eprintln!("{}", console::style("Summary:").bold());
+    for run in runs {
+        let indent = match &run.explicit_environment {
+            Some(environment) if runs.len() > 1 => {
+                eprintln!("  {}", environment.name().fancy_display());
+                "    "
+            }
+            _ => "  ",
+        };
+        if !run.succeeded.is_empty() {
             eprintln!(
-                "{}{}{}{}{}{}{}",
-                console::Emoji("✨ ", ""),
-                console::style("Pixi task (").bold(),
-                console::style(executable_task.name().unwrap_or("unnamed"))
-                    .green()
-                    .bold(),
-                // Only print environment if multiple environments are available
-                if workspace.environments().len() > 1 {
-                    format!(
-                        " in {}",
-                        executable_task.run_environment.name().fancy_display()
-                    )
-                } else {
-                    "".to_string()
-                },
-                console::style("): ").bold(),
-                display_command,
-                if let Some(description) = executable_task.task().description() {
-                    console::style(format!(": ({})", description)).yellow()
-                } else {
-                    console::style("".to_string()).yellow()
+                "{indent}{}{} {}",
+                console::Emoji("✅ ", ""),
+                console::style("succeeded:").green(),
+                run.succeeded.join(", ")
+            );
+        }
+        if !run.failures.is_empty() {
+            let failures = run.failures.iter().map(|(label, err)| {
+                match err.downcast_ref::<TaskExecutionError>() {
+                    Some(TaskExecutionError::NonZeroExitCode(code)) => {
+                        format!("{label} (exit code {code})")
+                    }
+                    _ => label.clone(),
                 }
+            });
+            eprintln!(
+                "{indent}{}{} {}",
+                console::Emoji("❌ ", ""),
+                console::style("failed:").red(),
+                failures.format(", ")
+            );
+        }
+        if !run.skipped.is_empty() {
+            eprintln!(
+                "{indent}{}{} {}",
+                console::Emoji("⏭️  ", ""),
+                console::style("skipped:").yellow(),
+                run.skipped.join(", ")
             );
         }
+    }
+}
 
-        // on dry-run mode, we just print the command and skip the execution
-        if args.dry_run {
-            task_idx += 1;
+/// Prints whether the tasks passed or failed in every environment.
+fn print_environment_summary(runs: &[EnvironmentRun<'_>]) {
+    eprintln!();
+    for run in runs {
+        let Some(environment) = &run.explicit_environment else {
             continue;
+        };
+        let name = environment.name().fancy_display();
+        match run.failures.first() {
+            None => eprintln!(
+                "{}{name}: {}",
+                console::Emoji("✅ ", ""),
+                console::style("passed").green()
+            ),
+            Some((_, err)) => {
+                let reason = match err.downcast_ref::<TaskExecutionError>() {
+                    Some(TaskExecutionError::NonZeroExitCode(code)) => {
+                        format!("failed with exit code {code}")
+                    }
+                    _ => "failed".to_string(),
+                };
+                eprintln!(
+                    "{}{name}: {}",
+                    console::Emoji("❌ ", ""),
+                    console::style(reason).red()
+                );
+            }
         }
+    }
+}
 
-        // check task cache
-        let task_cache = match executable_task
-            .can_skip(lock_file.as_lock_file())
-            .await
-            .into_diagnostic()?
-        {
-            CanSkip::No(cache) => cache,
-            CanSkip::Yes => {
-                let args_text = if !executable_task.args().is_empty() {
-                    format!(
-                        " with args {}",
-                        console::style(executable_task.args()).bold()
-                    )
-                } else {
-                    String::new()
-                };
+/// The files that are watched after a run, and their hashes when the run
+/// started.
+struct WatchState {
+    paths: WatchedPaths,
+    snapshot: WatchSnapshot,
+}
+
+/// The globs of the manifest and the lock-file, relative to the root of the
+/// workspace. When they change the task graph and the environment are
+/// resolved again.
+fn workspace_watch_globs(workspace: &Workspace) -> Vec<String> {
+    let manifest_path = &workspace.workspace.provenance.path;
+    let manifest = manifest_path
+        .strip_prefix(workspace.root())
+        .unwrap_or(manifest_path);
+    vec![
+        manifest.to_string_lossy().into_owned(),
+        consts::PROJECT_LOCK_FILE.to_string(),
+    ]
+}
 
+/// Runs the tasks and runs them again whenever the watched files change,
+/// until CTRL-C is pressed.
+async fn watch(args: &Args) -> miette::Result<()> {
+    let debounce = Duration::from_millis(args.debounce);
+
+    // The files that are watched if a run fails before it knows its tasks.
+    let workspace = WorkspaceLocator::for_cli()
+        .with_search_start(args.workspace_config.workspace_locator_start())
+        .locate()?;
+    let mut fallback = WatchedPaths {
+        root: workspace.root().to_path_buf(),
+        globs: args.watch_path.clone(),
+        workspace_globs: workspace_watch_globs(&workspace),
+        ignored_globs: Vec::new(),
+    };
+
+    // A single watcher is used for all runs, so that the watches of the
+    // system aren't set up again for every run.
+    let mut watcher = FileWatcher::new(workspace.root()).into_diagnostic()?;
+
+    // Tasks don't change whether CTRL-C exits, it is handled below.
+    let ctrlc_should_exit_process = Arc::new(AtomicBool::new(false));
+    let watch_state = RefCell::new(None);
+    loop {
+        let kill_signal = KillSignal::default();
+        watch_state.replace(None);
+        let mut run = pin!(run_tasks(
+            args,
+            &ctrlc_should_exit_process,
+            kill_signal.clone(),
+            Some(&watch_state),
+            None,
+        ));
+
+        // Files that change while the tasks run cancel the run.
+        let change = tokio::select! {
+            result = &mut run => {
+                report_watch_run(result);
+                None
+            }
+            change = wait_for_change_during_run(&watch_state, &mut watcher, debounce) => {
+                stop_run(run, &kill_signal, args.grace_period).await;
+                Some(change?)
+            }
+            _ = tokio::signal::ctrl_c() => {
+                stop_run(run, &kill_signal, args.grace_period).await;
+                reset_cursor();
+                return Ok(());
+            }
+        };
+
+        let change = match change {
+            Some(change) => change,
+            None => {
+                let WatchState { paths, snapshot } = match watch_state.take() {
+                    Some(state) => state,
+                    None => {
+                        let snapshot = fallback.snapshot().await.into_diagnostic()?;
+                        WatchState {
+                            paths: fallback.clone(),
+                            snapshot,
+                        }
+                    }
+                };
+                if paths.globs.is_empty() {
+                    tracing::warn!(
+                        "none of the tasks define `inputs`, only the manifest and the lock-file are watched. Use `--watch-path` to watch other files"
+                    );
+                }
                 eprintln!(
-                    "Task '{}'{args_text} can be skipped (cache hit) 🚀",
-                    console::style(executable_task.name().unwrap_or("")).bold(),
+                    "{}{}",
+                    console::Emoji("👀 ", ""),
+                    console::style("Watching for changes, press CTRL-C to stop").dim()
                 );
-                task_idx += 1;
-                continue;
+                let change = tokio::select! {
+                    change = paths.wait_for_change(&mut watcher, &snapshot, debounce) => change.into_diagnostic()?,
+                    _ = tokio::signal::ctrl_c() => {
+                        reset_cursor();
+                        return Ok(());
+                    }
+                };
+                fallback = paths;
+                change
             }
         };
 
-        // If we don't have a command environment yet, we need to compute it. We lazily
-        // compute the task environment because we only need the environment if
-        // a task is actually executed.
-        let task_env: &_ = match task_envs.entry(executable_task.run_environment.clone()) {
+        eprintln!();
+        eprintln!(
+            "{}{}",
+            console::Emoji("🔄 ", ""),
+            console::style(match change {
+                WatchChange::Inputs => "Files changed, running the tasks again",
+                WatchChange::Workspace => {
+                    "The workspace changed, resolving and running the tasks again"
+                }
+            })
+            .bold()
+        );
+    }
+}
+
+/// Stops a run in watch mode. The tasks get the grace period to stop after
+/// SIGTERM, after which the processes that are still running are killed.
+async fn stop_run<F: Future>(mut run: Pin<&mut F>, kill_signal: &KillSignal, grace_period: u64) {
+    kill_signal.send(SignalKind::SIGTERM);
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(grace_period);
+    tokio::select! {
+        _ = &mut run => {}
+        _ = tokio::time::sleep_until(deadline) => {
+            kill_signal.send(SignalKind::SIGKILL);
+            run.await;
+        }
+    }
+}
+
+/// Waits until the files of the current run change. The files are only known
+/// once the run has resolved its tasks.
+async fn wait_for_change_during_run(
+    watch_state: &RefCell<Option<WatchState>>,
+    watcher: &mut FileWatcher,
+    debounce: Duration,
+) -> miette::Result<WatchChange> {
+    const RESOLVE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+    let (paths, snapshot) = loop {
+        if let Some(state) = watch_state.borrow().as_ref() {
+            break (state.paths.clone(), state.snapshot.clone());
+        }
+        tokio::time::sleep(RESOLVE_POLL_INTERVAL).await;
+    };
+    paths
+        .wait_for_change(watcher, &snapshot, debounce)
+        .await
+        .into_diagnostic()
+}
+
+/// Reports the result of a run in watch mode, a failing run doesn't stop
+/// watching.
+fn report_watch_run(result: miette::Result<()>) {
+    match result {
+        Ok(()) => {}
+        Err(err) => match err.downcast_ref::<TaskExecutionError>() {
+            Some(TaskExecutionError::NonZeroExitCode(code)) => {
+                eprintln!(
+                    "{}{}",
+                    console::Emoji("❌ ", ""),
+                    console::style(format!("The tasks failed with exit code {code}")).red()
+                );
+            }
+            _ => eprintln!("{err:?}"),
+        },
+    }
+}
+
+/// The environment a task runs in, whether it is clean and the variables that
+/// are kept in a clean environment.
+type TaskEnvKey<'p> = (Environment<'p>, bool, Vec<String>);
+
+/// The state that is shared between the tasks of a run.
+struct TaskRunContext<'a, 'p> {
+    lock_file: &'a LockFileDerivedData<'p>,
+    update_mode: UpdateMode,
+    reinstall_packages: &'a ReinstallPackages,
+    clean_env: bool,
+    keep_env: &'a [String],
+    no_hooks: bool,
+    force: bool,
+
+    /// The environment variables of every environment a task ran in, for
+    /// every combination of `clean-env` and the variables to keep. They are
+    /// computed lazily, because an environment is only installed if a task
+    /// actually runs in it.
+    task_envs: Mutex<HashMap<TaskEnvKey<'p>, HashMap<String, String>>>,
+
+    /// Whether CTRL-C exits pixi, which is not the case while tasks run so
+    /// that they can react to it.
+    ctrlc_should_exit_process: Arc<AtomicBool>,
+
+    /// Stops the running tasks when pixi is interrupted or a run is cancelled
+    /// in watch mode.
+    kill_signal: KillSignal,
+
+    /// The output of the tasks that ran, if a report is written.
+    report: Option<&'a RefCell<Vec<TaskOutput>>>,
+}
+
+/// Prints the name and command of the task that is started.
+fn print_task_header(workspace: &Workspace, executable_task: &ExecutableTask<'_>) {
+    let display_command = executable_task.display_command().to_string();
+
+    eprintln!(
+        "{}{}{}{}{}{}{}",
+        console::Emoji("✨ ", ""),
+        console::style("Pixi task (").bold(),
+        console::style(executable_task.name().unwrap_or("unnamed"))
+            .green()
+            .bold(),
+        // Only print environment if multiple environments are available
+        if workspace.environments().len() > 1 {
+            format!(
+                " in {}",
+                executable_task.run_environment.name().fancy_display()
+            )
+        } else {
+            "".to_string()
+        },
+        console::style("): ").bold(),
+        display_command,
+        if let Some(description) = executable_task.task().description() {
+            console::style(format!(": ({})", description)).yellow()
+        } else {
+            console::style("".to_string()).yellow()
+        }
+    );
+}
+
+/// Prints the name of a task that is skipped because it is a dependency of the
+/// requested task with `--skip-deps`.
+fn print_skipped_task(executable_task: &ExecutableTask<'_>) {
+    eprintln!(
+        "{}Skipping task '{}' ({})",
+        console::Emoji("⏭️  ", ""),
+        console::style(executable_task.name().unwrap_or("unnamed")).bold(),
+        console::style("--skip-deps").dim()
+    );
+}
+
+/// Runs a single task, unless it can be skipped because of its cache. If
+/// `capture_output` is set, the task doesn't read from the terminal and every
+/// line of its output is prefixed by the name of the task while it runs.
+async fn run_task(
+    context: &TaskRunContext<'_, '_>,
+    executable_task: &ExecutableTask<'_>,
+    capture_output: bool,
+) -> miette::Result<()> {
+    let lock_file = context.lock_file;
+
+    // check task cache, unless it is bypassed with `--force`
+    let can_skip = if context.force {
+        CanSkip::No(None)
+    } else {
+        executable_task
+            .can_skip(lock_file.as_lock_file())
+            .await
+            .into_diagnostic()?
+    };
+    let task_cache = match can_skip {
+        CanSkip::No(cache) => cache,
+        CanSkip::Yes => {
+            let args_text = if !executable_task.args().is_empty() {
+                format!(
+                    " with args {}",
+                    console::style(executable_task.args()).bold()
+                )
+            } else {
+                String::new()
+            };
+
+            eprintln!(
+                "Task '{}'{args_text} can be skipped (cache hit) 🚀",
+                console::style(executable_task.name().unwrap_or("")).bold(),
+            );
+            if let Some(report) = context.report {
+                report.borrow_mut().push(TaskOutput {
+                    cache_hit: true,
+                    ..TaskOutput::for_task(executable_task)
+                });
+            }
+            return Ok(());
+        }
+    };
+
+    // If we don't have a command environment yet, we need to compute it. We lazily
+    // compute the task environment because we only need the environment if
+    // a task is actually executed. The lock is held while the environment is
+    // installed, so that concurrent tasks don't install the same environment.
+    let keep_env = executable_task.clean_env_keep(context.clean_env, context.keep_env);
+    let clean_env = keep_env.is_some();
+    let keep_env = keep_env.unwrap_or_default();
+    let mut task_envs = context.task_envs.lock().await;
+    let installed = task_envs
+        .keys()
+        .any(|(environment, ..)| environment == &executable_task.run_environment);
+    let task_env: &_ =
+        match task_envs.entry((executable_task.run_environment.clone(), clean_env, keep_env)) {
             Entry::Occupied(env) => env.into_mut(),
             Entry::Vacant(entry) => {
-                // Ensure there is a valid prefix
-                lock_file
-                    .prefix(
-                        &executable_task.run_environment,
-                        args.prefix_update_config.update_mode(),
-                        &ReinstallPackages::default(),
-                    )
-                    .await?;
-
-                // Clear the current progress reports.
-                lock_file
-                    .build_context
-                    .command_dispatcher()
-                    .clear_reporter()
-                    .await;
+                // The environment is only installed for the first task that runs in
+                // it.
+                if !installed {
+                    // Ensure there is a valid prefix
+                    lock_file
+                        .prefix(
+                            &executable_task.run_environment,
+                            context.update_mode,
+                            context.reinstall_packages,
+                            &InstallFilter::default(),
+                        )
+                        .await?;
+
+                    if !context.no_hooks {
+                        run_post_install_tasks(&executable_task.run_environment, lock_file).await?;
+                    }
 
+                    // Clear the current progress reports.
+                    lock_file
+                        .build_context
+                        .command_dispatcher()
+                        .clear_reporter()
+                        .await;
+                }
+
+                let workspace = executable_task.project();
                 let command_env = get_task_env(
                     &executable_task.run_environment,
-                    args.clean_env || executable_task.task().clean_env(),
+                    clean_env,
+                    &entry.key().2,
                     Some(lock_file.as_lock_file()),
                     workspace.config().force_activate(),
-                    workspace.config().experimental_activation_cache_usage(),
+                    workspace.config().run().use_activation_cache(),
                 )
                 .await?;
                 entry.insert(command_env)
             }
         };
+    let mut task_env = task_env.clone();
+    drop(task_envs);
+    executable_task.extend_with_env_files(&mut task_env)?;
+    let task_env = task_env
+        .into_iter()
+        .map(|(k, v)| (OsString::from(k), OsString::from(v)))
+        .collect();
 
-        ctrlc_should_exit_process.store(false, Ordering::Relaxed);
+    context
+        .ctrlc_should_exit_process
+        .store(false, Ordering::Relaxed);
 
-        let task_env = task_env
-            .iter()
-            .map(|(k, v)| (OsString::from(k), OsString::from(v)))
-            .collect();
-
-        // Execute the task itself within the command environment. If one of the tasks
-        // failed with a non-zero exit code, we exit this parent process with
-        // the same code.
-        match execute_task(&executable_task, &task_env).await {
-            Ok(_) => {
-                task_idx += 1;
-            }
-            Err(TaskExecutionError::NonZeroExitCode(code)) => {
-                if code == 127 {
-                    command_not_found(&workspace, explicit_environment.clone());
+    // Execute the task itself within the command environment. A task that
+    // failed runs again as long as it has retries left, its dependencies are
+    // not run again.
+    let retries = executable_task.retries();
+    let mut attempts = 1;
+    let (result, task_output) = loop {
+        let (result, task_output) =
+            run_attempt(context, executable_task, &task_env, capture_output).await;
+        let retryable = matches!(
+            result,
+            Err(TaskExecutionError::NonZeroExitCode(_) | TaskExecutionError::TimedOut(..))
+        );
+        if !retryable || attempts > retries {
+            break (result, task_output);
+        }
+
+        print_retry(executable_task, attempts + 1, retries + 1);
+        if let Some(delay) = executable_task.retry_delay() {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = context.kill_signal.wait_any() => {
+                    break (
+                        Err(TaskExecutionError::Interrupted(task_label(executable_task))),
+                        task_output,
+                    );
                 }
-                std::process::exit(code);
             }
-            Err(err) => return Err(err.into()),
         }
+        attempts += 1;
+    };
+    if let (Some(report), Some(task_output)) = (context.report, task_output) {
+        report.borrow_mut().push(TaskOutput {
+            attempts,
+            ..task_output
+        });
+    }
+    result?;
 
-        // Handle CTRL-C ourselves again
-        ctrlc_should_exit_process.store(true, Ordering::Relaxed);
+    // Update the task cache with the new hash
+    executable_task
+        .save_cache(lock_file.as_lock_file(), task_cache)
+        .await
+        .into_diagnostic()?;
 
-        // Update the task cache with the new hash
+    Ok(())
+}
+
+/// Runs the command of a task once. If `capture_output` is set, the task
+/// doesn't read from the terminal and every line of its output is prefixed by
+/// the name of the task while it runs. The output is also returned if the task
+/// is included in the report.
+async fn run_attempt(
+    context: &TaskRunContext<'_, '_>,
+    executable_task: &ExecutableTask<'_>,
+    task_env: &HashMap<OsString, OsString>,
+    capture_output: bool,
+) -> (Result<(), TaskExecutionError>, Option<TaskOutput>) {
+    // The output is captured if it is printed per task or included in the
+    // report.
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let output = if capture_output {
+        let prefix = console::style(format!("[{}]", executable_task.name().unwrap_or("unnamed")))
+            .green()
+            .bold()
+            .to_string();
+        executable_task
+            .execute_with_prefix(
+                task_env,
+                &prefix,
+                context.kill_signal.clone(),
+                REPORT_OUTPUT_LIMIT,
+            )
+            .await
+    } else if context.report.is_some() && !std::io::stdout().is_terminal() {
+        executable_task
+            .execute_with_tee(task_env, context.kill_signal.clone(), REPORT_OUTPUT_LIMIT)
+            .await
+    } else if context.report.is_some() {
+        // A task in a terminal stays attached to it, its output isn't part of
+        // the report.
         executable_task
-            .save_cache(lock_file.as_lock_file(), task_cache)
+            .execute(task_env, context.kill_signal.clone())
             .await
-            .into_diagnostic()?;
+            .map(|exit_code| RunOutput {
+                exit_code,
+                ..RunOutput::default()
+            })
+    } else {
+        return (
+            execute_task(executable_task, task_env, context.kill_signal.clone()).await,
+            None,
+        );
+    };
+
+    let task_output = context.report.map(|_| {
+        let mut task_output = TaskOutput {
+            started_at: Some(started_at),
+            duration: started.elapsed(),
+            ..TaskOutput::for_task(executable_task)
+        };
+        match &output {
+            Ok(output) => {
+                task_output.exit_code = output.exit_code;
+                task_output.stdout = output.stdout.clone();
+                task_output.stderr = output.stderr.clone();
+            }
+            Err(err) => task_output.error = Some(err.to_string()),
+        }
+        task_output
+    });
+
+    let output = match output {
+        Ok(output) => output,
+        Err(err) => {
+            return (
+                Err(TaskExecutionError::from_task(executable_task, err)),
+                task_output,
+            );
+        }
+    };
+    if output.exit_code != 0 {
+        return (
+            Err(TaskExecutionError::NonZeroExitCode(output.exit_code)),
+            task_output,
+        );
     }
+    (Ok(()), task_output)
+}
 
-    Ok(())
+/// Prints that a task failed and runs again, with the number of the attempt
+/// out of the number of attempts the task has.
+fn print_retry(executable_task: &ExecutableTask<'_>, attempt: u32, max_attempts: u32) {
+    let delay = executable_task
+        .retry_delay()
+        .map(|delay| format!(" in {}", humantime::format_duration(delay)))
+        .unwrap_or_default();
+    eprintln!(
+        "{}Task '{}' failed, retrying{delay} (attempt {attempt}/{max_attempts})",
+        console::Emoji("🔁 ", ""),
+        console::style(executable_task.name().unwrap_or("unnamed")).bold(),
+    );
 }
 
-/// Called when a command was not found.
-fn command_not_found<'p>(workspace: &'p Workspace, explicit_environment: Option<Environment<'p>>) {
-    let available_tasks: HashSet<TaskName> =
-        if let Some(explicit_environment) = explicit_environment {
-            explicit_environment.get_filtered_tasks()
-        } else {
-            workspace
-                .environments()
-                .into_iter()
-                .flat_map(|env| env.get_filtered_tasks())
-                .collect()
+/// Prints every task of the task graphs in the order in which it would run,
+/// with the environment and working directory it would run in, the command
+/// after argument substitution and the environment variables the task sets.
+fn print_dry_run<'a, 'p: 'a>(
+    task_graphs: impl IntoIterator<Item = &'a TaskGraph<'p>>,
+    cwd_override: Option<PathBuf>,
+    timeout_override: Option<Duration>,
+    shell_override: Option<TaskShell>,
+    retries_override: Option<u32>,
+) -> miette::Result<()> {
+    eprintln!(
+        "{}{}",
+        console::Emoji("🌵 ", ""),
+        console::style("Dry-run mode enabled - no tasks will be executed.")
+            .yellow()
+            .bold(),
+    );
+
+    for (task_graph, task_id) in task_graphs.into_iter().flat_map(|task_graph| {
+        task_graph
+            .topological_order()
+            .into_iter()
+            .map(move |task_id| (task_graph, task_id))
+    }) {
+        let executable_task = ExecutableTask::from_task_graph(task_graph, task_id)
+            .with_cwd_override(cwd_override.clone())
+            .with_timeout_override(timeout_override)
+            .with_shell_override(shell_override)
+            .with_retries_override(retries_override);
+        let Some(command) = executable_task.full_command()? else {
+            // Aliases don't run a command of their own
+            continue;
         };
+        if executable_task.skipped {
+            eprintln!();
+            print_skipped_task(&executable_task);
+            continue;
+        }
 
-    if !available_tasks.is_empty() {
+        eprintln!();
         eprintln!(
-            "\nAvailable tasks:\n{}",
-            available_tasks
+            "{}{}{}{}{}",
+            console::Emoji("✨ ", ""),
+            console::style("Pixi task (").bold(),
+            console::style(executable_task.name().unwrap_or("unnamed"))
+                .green()
+                .bold(),
+            console::style("): ").bold(),
+            consts::TASK_STYLE.apply_to(command).bold(),
+        );
+        eprintln!(
+            "   environment:       {}",
+            executable_task.run_environment.name().fancy_display()
+        );
+        eprintln!(
+            "   working directory: {}",
+            executable_task.working_directory()?.display()
+        );
+        if let Some(timeout) = executable_task.timeout() {
+            eprintln!(
+                "   timeout:           {}",
+                humantime::format_duration(timeout)
+            );
+        }
+        let retries = executable_task.retries();
+        if retries > 0 {
+            eprintln!("   retries:           {retries}");
+        }
+        let shell = executable_task.shell();
+        if shell != TaskShell::Deno {
+            eprintln!("   shell:             {shell}");
+        }
+        for (key, value) in executable_task.task().env().into_iter().flatten() {
+            eprintln!("   env:               {key}={value}");
+        }
+    }
+
+    Ok(())
+}
+
+/// The tasks of an environment that are listed by `pixi run`, by feature.
+#[derive(Debug, Serialize)]
+struct EnvironmentTasks {
+    environment: String,
+    features: Vec<FeatureTasks>,
+}
+
+/// The tasks that a feature adds to an environment.
+#[derive(Debug, Serialize)]
+struct FeatureTasks {
+    feature: String,
+    tasks: Vec<ListedTask>,
+}
+
+/// A task that is listed by `pixi run`.
+#[derive(Debug, Serialize)]
+struct ListedTask {
+    name: String,
+    description: Option<String>,
+    depends_on: Vec<String>,
+}
+
+/// Returns the tasks of the environments by feature. Tasks whose name starts
+/// with `_` are helpers of other tasks and aren't listed.
+fn available_tasks(environments: &[Environment<'_>]) -> Vec<EnvironmentTasks> {
+    environments
+        .iter()
+        .filter_map(|environment| {
+            let features = environment
+                .feature_tasks()
                 .into_iter()
-                .sorted()
-                .format_with("\n", |name, f| {
-                    f(&format_args!("\t{}", name.fancy_display().bold()))
+                .sorted_by(|(a, _), (b, _)| {
+                    (!a.is_default(), a.as_str()).cmp(&(!b.is_default(), b.as_str()))
+                })
+                .filter_map(|(feature, tasks)| {
+                    let tasks = tasks
+                        .into_iter()
+                        .filter(|(name, _)| !name.as_str().starts_with('_'))
+                        .sorted_by_key(|(name, _)| *name)
+                        .map(|(name, task)| ListedTask {
+                            name: name.to_string(),
+                            description: task.description().map(str::to_string),
+                            depends_on: task
+                                .depends_on()
+                                .iter()
+                                .map(|dependency| dependency.task_name.to_string())
+                                .collect(),
+                        })
+                        .collect_vec();
+                    (!tasks.is_empty()).then(|| FeatureTasks {
+                        feature: feature.to_string(),
+                        tasks,
+                    })
                 })
+                .collect_vec();
+            (!features.is_empty()).then(|| EnvironmentTasks {
+                environment: environment.name().to_string(),
+                features,
+            })
+        })
+        .collect()
+}
+
+/// Prints the available tasks grouped by environment and feature, with their
+/// description and the tasks they depend on.
+fn print_available_tasks(available: &[EnvironmentTasks]) {
+    if available.is_empty() {
+        eprintln!("\nNo tasks found");
+        return;
+    }
+
+    let mut writer = tabwriter::TabWriter::new(Vec::new());
+    for environment in available {
+        let _ = writeln!(
+            writer,
+            "\n{}",
+            console::style(format!(
+                "Tasks in the '{}' environment:",
+                environment.environment
+            ))
+            .bold()
         );
+        for feature in &environment.features {
+            let _ = writeln!(writer, "  from the '{}' feature", feature.feature);
+            for task in &feature.tasks {
+                let depends_on = if task.depends_on.is_empty() {
+                    String::new()
+                } else {
+                    format!("depends on {}", task.depends_on.join(", "))
+                };
+                let _ = writeln!(
+                    writer,
+                    "    {}\t{}\t{}",
+                    consts::TASK_STYLE.apply_to(&task.name).bold(),
+                    console::style(task.description.as_deref().unwrap_or_default()).italic(),
+                    console::style(depends_on).dim(),
+                );
+            }
+        }
+    }
+    if let Ok(listing) = writer.into_inner() {
+        eprint!("{}", String::from_utf8_lossy(&listing));
     }
+}
 
-    // Help user when there is no task available because the platform is not
-    // supported
+/// Returns the name of the listed task that is the closest to the name, if
+/// one is close enough.
+fn suggest_task<'a>(name: &str, available: &'a [EnvironmentTasks]) -> Option<&'a str> {
+    available
+        .iter()
+        .flat_map(|environment| &environment.features)
+        .flat_map(|feature| &feature.tasks)
+        .map(|task| (task.name.as_str(), strsim::jaro(name, &task.name)))
+        .filter(|(_, similarity)| *similarity > 0.8)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(name, _)| name)
+}
+
+/// Helps the user when there is no task available because the platform is
+/// not supported.
+fn print_platform_help(workspace: &Workspace) {
     if workspace
         .environments()
         .iter()
@@ -360,6 +1629,48 @@ fn command_not_found<'p>(workspace: &'p Workspace, explicit_environment: Option<
     }
 }
 
+/// The command that `pixi run` was given is neither a task nor an executable
+/// in the environment, but it is close to the name of a task.
+#[derive(Debug, Error, Diagnostic)]
+#[error("'{name}' is not a task or a command in the environment")]
+#[diagnostic(help("did you mean the task '{suggestion}'?"))]
+struct UnknownTaskError {
+    name: String,
+    suggestion: String,
+}
+
+/// Called when a command was not found. Lists the available tasks and returns
+/// the error to report, which suggests a task with a similar name when the
+/// command isn't a task itself.
+fn command_not_found<'p>(
+    workspace: &'p Workspace,
+    explicit_environment: Option<Environment<'p>>,
+    args: &Args,
+    err: miette::Report,
+) -> miette::Report {
+    let available = match explicit_environment {
+        Some(environment) => available_tasks(&[environment]),
+        None => available_tasks(&workspace.environments()),
+    };
+    print_available_tasks(&available);
+    print_platform_help(workspace);
+
+    let Some(name) = task_name(&args.task) else {
+        return err;
+    };
+    if !workspace.environments_with_task(&name).is_empty() {
+        return err;
+    }
+    match suggest_task(name.as_str(), &available) {
+        Some(suggestion) => UnknownTaskError {
+            name: name.to_string(),
+            suggestion: suggestion.to_string(),
+        }
+        .into(),
+        None => err,
+    }
+}
+
 #[derive(Debug, Error, Diagnostic)]
 enum TaskExecutionError {
     #[error("the script exited with a non-zero exit code {0}")]
@@ -374,6 +1685,45 @@ enum TaskExecutionError {
 
     #[error(transparent)]
     UnsupportedPlatformError(#[from] UnsupportedPlatformError),
+
+    #[error("the task '{0}' was interrupted")]
+    Interrupted(String),
+
+    #[error("the task '{0}' timed out after {1:.1?}")]
+    TimedOut(String, Duration),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MissingShell(#[from] MissingShellError),
+
+    #[error("failed to start the shell '{1}' for the task '{0}'")]
+    SpawnShell(String, TaskShell, #[source] std::io::Error),
+}
+
+/// Returns the name of the task, or its command if it is a custom task.
+fn task_label(task: &ExecutableTask<'_>) -> String {
+    match task.name() {
+        Some(name) => name.to_string(),
+        None => task.display_command().to_string(),
+    }
+}
+
+impl TaskExecutionError {
+    /// Converts the error of executing the task, naming the task in the
+    /// errors that stopped it.
+    fn from_task(task: &ExecutableTask<'_>, err: task::TaskExecutionError) -> Self {
+        let name = || task_label(task);
+        match err {
+            task::TaskExecutionError::InvalidWorkingDirectory(err) => err.into(),
+            task::TaskExecutionError::FailedToParseShellScript(err) => err.into(),
+            task::TaskExecutionError::Interrupted(_) => Self::Interrupted(name()),
+            task::TaskExecutionError::TimedOut(elapsed) => Self::TimedOut(name(), elapsed),
+            task::TaskExecutionError::MissingShell(err) => err.into(),
+            task::TaskExecutionError::SpawnShell { shell, source } => {
+                Self::SpawnShell(name(), shell, source)
+            }
+        }
+    }
 }
 
 /// Called to execute a single command.
@@ -382,20 +1732,12 @@ enum TaskExecutionError {
 async fn execute_task(
     task: &ExecutableTask<'_>,
     command_env: &HashMap<OsString, OsString>,
+    kill_signal: KillSignal,
 ) -> Result<(), TaskExecutionError> {
-    let Some(script) = task.as_deno_script()? else {
-        return Ok(());
-    };
-    let cwd = task.working_directory()?;
-
-    let status_code = deno_task_shell::execute(
-        script,
-        command_env.clone(),
-        cwd,
-        Default::default(),
-        Default::default(),
-    )
-    .await;
+    let status_code = task
+        .execute(command_env, kill_signal)
+        .await
+        .map_err(|err| TaskExecutionError::from_task(task, err))?;
 
     if status_code != 0 {
         return Err(TaskExecutionError::NonZeroExitCode(status_code));
@@ -405,13 +1747,74 @@ async fn execute_task(
 }
 
 /// Called to disambiguate between environments to run a task in.
+///
+/// Depending on `choice` the user is asked which environment to use, an
+/// environment is chosen, or the task stays ambiguous, which is an error.
+/// The user is only asked in an interactive terminal, otherwise the task stays
+/// ambiguous.
+fn disambiguate_task<'p>(
+    problem: &AmbiguousTask<'p>,
+    choice: AmbiguousTaskChoice,
+) -> Option<TaskAndEnvironment<'p>> {
+    match choice {
+        AmbiguousTaskChoice::Error => None,
+        AmbiguousTaskChoice::Prompt => {
+            if std::io::stdin().is_terminal() && std::io::stderr().is_terminal() {
+                disambiguate_task_interactive(problem)
+            } else {
+                None
+            }
+        }
+        AmbiguousTaskChoice::Default => Some(disambiguate_task_default(problem)),
+    }
+}
+
+/// Chooses the default environment if it provides the task, otherwise the
+/// first environment that provides it, and prints why it was chosen.
+fn disambiguate_task_default<'p>(problem: &AmbiguousTask<'p>) -> TaskAndEnvironment<'p> {
+    let (chosen, reason) = match problem
+        .environments
+        .iter()
+        .find(|(env, _)| env.is_default())
+    {
+        Some(chosen) => (chosen, "it is the default environment"),
+        None => (
+            &problem.environments[0],
+            "it is the first environment that defines it",
+        ),
+    };
+    print_environment_choice(
+        &problem.task_name,
+        problem.environments.iter().map(|(env, _)| env.name()),
+        chosen.0.name(),
+        reason,
+    );
+    chosen.clone()
+}
+
+/// Asks the user which environment to run the task in.
 fn disambiguate_task_interactive<'p>(
     problem: &AmbiguousTask<'p>,
 ) -> Option<TaskAndEnvironment<'p>> {
+    // Every environment is shown with the feature that defines its task
     let environment_names = problem
         .environments
         .iter()
-        .map(|(env, _)| env.name())
+        .map(|(env, task)| {
+            let feature = env
+                .feature_tasks()
+                .into_iter()
+                .find(|(_, tasks)| tasks.values().any(|other| std::ptr::eq(*other, *task)))
+                .map(|(feature, _)| feature);
+            match feature {
+                Some(feature) => format!(
+                    "{} {}",
+                    env.name().fancy_display(),
+                    console::style(format!("(from feature '{}')", feature.fancy_display())).dim()
+                ),
+                None => env.name().fancy_display().to_string(),
+            }
+        })
         .collect_vec();
     let theme = ColorfulTheme {
         active_item_style: console::Style::new().for_stderr().magenta(),
@@ -9,6 +9,7 @@ use rattler_shell::{
 };
 
 use crate::lock_file::UpdateMode;
+use crate::task::environment_env_file_variables;
 use crate::workspace::get_activated_environment_variables;
 use crate::{
     UpdateLockFileOptions, WorkspaceLocator, activation::CurrentEnvVarBehavior,
@@ -16,9 +17,9 @@ use crate::{
 };
 use crate::{
     cli::cli_config::{PrefixUpdateConfig, WorkspaceConfig},
-    lock_file::ReinstallPackages,
+    lock_file::{InstallFilter, ReinstallPackages},
 };
-use pixi_config::{ConfigCli, ConfigCliActivation, ConfigCliPrompt};
+use pixi_config::{ConfigCli, ConfigCliActivation, ConfigCliNetwork, ConfigCliPrompt};
 #[cfg(target_family = "unix")]
 use pixi_pty::unix::PtySession;
 
@@ -41,6 +42,8 @@ pub struct Args {
 
     #[clap(flatten)]
     config: ConfigCli,
+    #[clap(flatten)]
+    network_config: ConfigCliNetwork,
 
     /// The environment to activate in the shell
     #[arg(long, short)]
@@ -268,6 +271,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         .activation_config
         .merge_config(args.prompt_config.into())
         .merge_config(args.config.clone().into());
+    let config = args.network_config.clone().merge_config(config);
 
     let workspace = WorkspaceLocator::for_cli()
         .with_search_start(args.workspace_config.workspace_locator_start())
@@ -286,14 +290,16 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             no_install: args.prefix_update_config.no_install
                 && args.lock_file_update_config.no_lockfile_update,
             max_concurrent_solves: workspace.config().max_concurrent_solves(),
+            no_prune: false,
         },
         ReinstallPackages::default(),
+        InstallFilter::default(),
     )
     .await?;
     let lock_file = lock_file_data.into_lock_file();
 
     // Get the environment variables we need to set activate the environment in the shell.
-    let env = get_activated_environment_variables(
+    let mut env = get_activated_environment_variables(
         workspace.env_vars(),
         &environment,
         CurrentEnvVarBehavior::Exclude,
@@ -301,7 +307,12 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         workspace.config().force_activate(),
         workspace.config().experimental_activation_cache_usage(),
     )
-    .await?;
+    .await?
+    .clone();
+
+    // Add the variables of the `.env` files, like the tasks get them.
+    let env_file_vars = environment_env_file_variables(&environment, &env)?;
+    env.extend(env_file_vars);
 
     tracing::debug!("Pixi environment activation:\n{:?}", env);
 
@@ -327,9 +338,9 @@ pub async fn execute(args: Args) -> miette::Result<()> {
 
     #[cfg(target_family = "windows")]
     let res = match interactive_shell {
-        ShellEnum::NuShell(nushell) => start_nu_shell(nushell, env, prompt_hook).await,
-        ShellEnum::PowerShell(pwsh) => start_powershell(pwsh, env, prompt_hook),
-        ShellEnum::CmdExe(cmdexe) => start_cmdexe(cmdexe, env, prompt_hook),
+        ShellEnum::NuShell(nushell) => start_nu_shell(nushell, &env, prompt_hook).await,
+        ShellEnum::PowerShell(pwsh) => start_powershell(pwsh, &env, prompt_hook),
+        ShellEnum::CmdExe(cmdexe) => start_cmdexe(cmdexe, &env, prompt_hook),
         _ => {
             miette::bail!("Unsupported shell: {:?}", interactive_shell);
         }
@@ -339,13 +350,13 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     let res = {
         let source_shell_completions = workspace.config().shell.source_completion_scripts();
         match interactive_shell {
-            ShellEnum::NuShell(nushell) => start_nu_shell(nushell, env, prompt_hook).await,
-            ShellEnum::PowerShell(pwsh) => start_powershell(pwsh, env, prompt_hook),
+            ShellEnum::NuShell(nushell) => start_nu_shell(nushell, &env, prompt_hook).await,
+            ShellEnum::PowerShell(pwsh) => start_powershell(pwsh, &env, prompt_hook),
             ShellEnum::Bash(bash) => {
                 start_unix_shell(
                     bash,
                     vec!["-i"],
-                    env,
+                    &env,
                     prompt_hook,
                     &prefix,
                     source_shell_completions,
@@ -356,7 +367,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                 start_unix_shell(
                     zsh,
                     vec!["-i"],
-                    env,
+                    &env,
                     prompt_hook,
                     &prefix,
                     source_shell_completions,
@@ -367,7 +378,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                 start_unix_shell(
                     fish,
                     vec![],
-                    env,
+                    &env,
                     prompt_hook,
                     &prefix,
                     source_shell_completions,
@@ -378,7 +389,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                 start_unix_shell(
                     xonsh,
                     vec![],
-                    env,
+                    &env,
                     prompt_hook,
                     &prefix,
                     source_shell_completions,
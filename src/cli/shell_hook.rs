@@ -1,8 +1,13 @@
-use std::{collections::HashMap, default::Default};
+use std::{
+    collections::HashMap,
+    default::Default,
+    path::{Path, PathBuf},
+};
 
 use clap::Parser;
 use miette::IntoDiagnostic;
-use pixi_config::{ConfigCli, ConfigCliActivation, ConfigCliPrompt};
+use pixi_config::{ConfigCli, ConfigCliActivation, ConfigCliNetwork, ConfigCliPrompt};
+use pixi_consts::consts;
 use rattler_lock::LockFile;
 use rattler_shell::{
     activation::{ActivationVariables, PathModificationBehavior},
@@ -13,11 +18,12 @@ use serde_json;
 
 use crate::{
     UpdateLockFileOptions, Workspace, WorkspaceLocator,
-    activation::{CurrentEnvVarBehavior, get_activator},
+    activation::{CurrentEnvVarBehavior, get_activator_for_prefix},
     cli::cli_config::{PrefixUpdateConfig, WorkspaceConfig},
     environment::get_update_lock_file_and_prefix,
-    lock_file::ReinstallPackages,
+    lock_file::{InstallFilter, ReinstallPackages},
     prompt,
+    task::environment_env_file_variables,
     workspace::{Environment, HasWorkspaceRef, get_activated_environment_variables},
 };
 
@@ -27,6 +33,9 @@ use super::cli_config::LockFileUpdateConfig;
 ///
 /// You can source the script to activate the environment without needing pixi
 /// itself.
+///
+/// With `--target-prefix` the script activates an environment that was
+/// installed with `pixi install --target-prefix` instead.
 #[derive(Parser, Debug)]
 pub struct Args {
     /// Sets the shell, options: [`bash`,  `zsh`,  `xonsh`,  `cmd`,
@@ -45,6 +54,8 @@ pub struct Args {
 
     #[clap(flatten)]
     config: ConfigCli,
+    #[clap(flatten)]
+    network_config: ConfigCliNetwork,
 
     #[clap(flatten)]
     activation_config: ConfigCliActivation,
@@ -57,6 +68,11 @@ pub struct Args {
     #[clap(long, default_value = "false", conflicts_with = "shell")]
     json: bool,
 
+    /// Activate the environment installed in this directory by `pixi install
+    /// --target-prefix`, instead of the environment of the workspace
+    #[arg(long, value_name = "PATH", conflicts_with = "json")]
+    target_prefix: Option<PathBuf>,
+
     #[clap(flatten)]
     prompt_config: ConfigCliPrompt,
 }
@@ -70,6 +86,7 @@ struct ShellEnv<'a> {
 async fn generate_activation_script(
     shell: Option<ShellEnum>,
     environment: &Environment<'_>,
+    prefix: &Path,
     project: &Workspace,
 ) -> miette::Result<String> {
     // Get shell from the arguments or from the current process or use default if
@@ -79,7 +96,8 @@ async fn generate_activation_script(
             .unwrap_or_else(|| ShellEnum::from_env().unwrap_or_default())
     });
 
-    let activator = get_activator(environment, shell.clone()).into_diagnostic()?;
+    let activator =
+        get_activator_for_prefix(environment, prefix, shell.clone()).into_diagnostic()?;
 
     let path = std::env::var("PATH")
         .ok()
@@ -90,6 +108,16 @@ async fn generate_activation_script(
     let conda_prefix = std::env::var("CONDA_PREFIX").ok().map(|p| p.into());
     let current_env = std::env::vars().collect::<HashMap<_, _>>();
 
+    // The variables of the `.env` files can refer to the variables of the
+    // activation, the scripts only run in the shell.
+    let mut expansion_env = current_env.clone();
+    expansion_env.extend(activator.env_vars.clone());
+    expansion_env.insert(
+        "CONDA_PREFIX".to_string(),
+        prefix.to_string_lossy().into_owned(),
+    );
+    let env_file_vars = environment_env_file_variables(environment, &expansion_env)?;
+
     let mut result = activator
         .activation(ActivationVariables {
             conda_prefix,
@@ -99,11 +127,15 @@ async fn generate_activation_script(
         })
         .into_diagnostic()?;
 
+    for (key, value) in &env_file_vars {
+        result.script.set_env_var(key, value).into_diagnostic()?;
+    }
+
     if project.config().shell.source_completion_scripts() {
         if let Some(completions_dir) = shell.completion_script_location() {
             result
                 .script
-                .source_completions(&environment.dir().join(completions_dir))
+                .source_completions(&prefix.join(completions_dir))
                 .into_diagnostic()?;
         }
     }
@@ -128,7 +160,7 @@ async fn generate_environment_json(
     force_activate: bool,
     experimental_cache: bool,
 ) -> miette::Result<String> {
-    let environment_variables = get_activated_environment_variables(
+    let mut environment_variables = get_activated_environment_variables(
         environment.workspace().env_vars(),
         environment,
         CurrentEnvVarBehavior::Exclude,
@@ -136,10 +168,15 @@ async fn generate_environment_json(
         force_activate,
         experimental_cache,
     )
-    .await?;
+    .await?
+    .clone();
+
+    // Add the variables of the `.env` files, like the tasks get them.
+    let env_file_vars = environment_env_file_variables(environment, &environment_variables)?;
+    environment_variables.extend(env_file_vars);
 
     let shell_env = ShellEnv {
-        environment_variables,
+        environment_variables: &environment_variables,
     };
 
     serde_json::to_string(&shell_env).into_diagnostic()
@@ -147,9 +184,13 @@ async fn generate_environment_json(
 
 /// Prints the activation script to the stdout.
 pub async fn execute(args: Args) -> miette::Result<()> {
-    let config = args
-        .activation_config
-        .merge_config(args.prompt_config.merge_config(args.config.clone().into()));
+    let config = args.activation_config.merge_config(
+        args.prompt_config.merge_config(
+            args.network_config
+                .clone()
+                .merge_config(args.config.clone().into()),
+        ),
+    );
 
     let workspace = WorkspaceLocator::for_cli()
         .with_search_start(args.project_config.workspace_locator_start())
@@ -158,6 +199,24 @@ pub async fn execute(args: Args) -> miette::Result<()> {
 
     let environment = workspace.environment_from_name_or_env_var(args.environment)?;
 
+    // An environment in a target prefix is already installed, and it is not
+    // managed by the workspace.
+    if let Some(target_prefix) = args.target_prefix {
+        let target_prefix = std::path::absolute(target_prefix).into_diagnostic()?;
+        if !target_prefix.join(consts::CONDA_META_DIR).is_dir() {
+            miette::bail!(
+                help = "install it with `pixi install --target-prefix {}`",
+                "'{}' doesn't contain an installed environment",
+                target_prefix.display()
+            );
+        }
+        let script =
+            generate_activation_script(args.shell, &environment, &target_prefix, &workspace)
+                .await?;
+        println!("{}", script);
+        return Ok(());
+    }
+
     let (lock_file_data, _prefix) = get_update_lock_file_and_prefix(
         &environment,
         args.prefix_update_config.update_mode(),
@@ -166,8 +225,10 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             no_install: args.prefix_update_config.no_install
                 && args.lock_file_update_config.no_lockfile_update,
             max_concurrent_solves: workspace.config().max_concurrent_solves(),
+            no_prune: false,
         },
         ReinstallPackages::default(),
+        InstallFilter::default(),
     )
     .await?;
 
@@ -183,7 +244,10 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         }
         // Skipping the activated environment caching for the script.
         // As it can still run scripts.
-        false => generate_activation_script(args.shell, &environment, &workspace).await?,
+        false => {
+            generate_activation_script(args.shell, &environment, &environment.dir(), &workspace)
+                .await?
+        }
     };
 
     // Print the output - either a JSON object or a shell script
@@ -210,31 +274,45 @@ mod tests {
         let path_var_name = default_shell.path_var(&Platform::current());
         let project = WorkspaceLocator::default().locate().unwrap();
         let environment = project.default_environment();
+        let prefix = environment.dir();
 
-        let script =
-            generate_activation_script(Some(ShellEnum::Bash(Bash)), &environment, &project)
-                .await
-                .unwrap();
-        assert!(script.contains(&format!("export {path_var_name}=")));
-        assert!(script.contains("export CONDA_PREFIX="));
-
-        let script = generate_activation_script(Some(ShellEnum::Zsh(Zsh)), &environment, &project)
-            .await
-            .unwrap();
+        let script = generate_activation_script(
+            Some(ShellEnum::Bash(Bash)),
+            &environment,
+            &prefix,
+            &project,
+        )
+        .await
+        .unwrap();
         assert!(script.contains(&format!("export {path_var_name}=")));
         assert!(script.contains("export CONDA_PREFIX="));
 
         let script =
-            generate_activation_script(Some(ShellEnum::Fish(Fish)), &environment, &project)
+            generate_activation_script(Some(ShellEnum::Zsh(Zsh)), &environment, &prefix, &project)
                 .await
                 .unwrap();
+        assert!(script.contains(&format!("export {path_var_name}=")));
+        assert!(script.contains("export CONDA_PREFIX="));
+
+        let script = generate_activation_script(
+            Some(ShellEnum::Fish(Fish)),
+            &environment,
+            &prefix,
+            &project,
+        )
+        .await
+        .unwrap();
         assert!(script.contains(&format!("set -gx {path_var_name} ")));
         assert!(script.contains("set -gx CONDA_PREFIX "));
 
-        let script =
-            generate_activation_script(Some(ShellEnum::Xonsh(Xonsh)), &environment, &project)
-                .await
-                .unwrap();
+        let script = generate_activation_script(
+            Some(ShellEnum::Xonsh(Xonsh)),
+            &environment,
+            &prefix,
+            &project,
+        )
+        .await
+        .unwrap();
         assert!(script.contains(&format!("${path_var_name} = ")));
         assert!(script.contains("$CONDA_PREFIX = "));
 
@@ -242,6 +320,7 @@ mod tests {
         let script = generate_activation_script(
             Some(ShellEnum::PowerShell(PowerShell::default())),
             &environment,
+            &prefix,
             &project,
         )
         .await
@@ -249,14 +328,37 @@ mod tests {
         assert!(script.contains(&format!("${{Env:{path_var_name}}}")));
         assert!(script.contains("${Env:CONDA_PREFIX}"));
 
-        let script =
-            generate_activation_script(Some(ShellEnum::NuShell(NuShell)), &environment, &project)
-                .await
-                .unwrap();
+        let script = generate_activation_script(
+            Some(ShellEnum::NuShell(NuShell)),
+            &environment,
+            &prefix,
+            &project,
+        )
+        .await
+        .unwrap();
         assert!(script.contains(&format!("$env.{path_var_name} = ")));
         assert!(script.contains("$env.CONDA_PREFIX = "));
     }
 
+    #[cfg(not(target_family = "windows"))]
+    #[tokio::test]
+    async fn test_shell_hook_target_prefix() {
+        let project = WorkspaceLocator::default().locate().unwrap();
+        let environment = project.default_environment();
+        let target_prefix = tempfile::tempdir().unwrap();
+
+        let script = generate_activation_script(
+            Some(ShellEnum::Bash(Bash)),
+            &environment,
+            target_prefix.path(),
+            &project,
+        )
+        .await
+        .unwrap();
+        assert!(script.contains("export CONDA_PREFIX="));
+        assert!(script.contains(&target_prefix.path().display().to_string()));
+    }
+
     #[cfg(target_family = "windows")]
     #[tokio::test]
     async fn test_shell_hook_windows() {
@@ -264,10 +366,12 @@ mod tests {
         let path_var_name = default_shell.path_var(&Platform::current());
         let project = WorkspaceLocator::default().locate().unwrap();
         let environment = project.default_environment();
+        let prefix = environment.dir();
 
         let script = generate_activation_script(
             Some(ShellEnum::PowerShell(PowerShell::default())),
             &environment,
+            &prefix,
             &project,
         )
         .await
@@ -275,17 +379,25 @@ mod tests {
         assert!(script.contains(&format!("${{Env:{path_var_name}}}")));
         assert!(script.contains("${Env:CONDA_PREFIX}"));
 
-        let script =
-            generate_activation_script(Some(ShellEnum::CmdExe(CmdExe)), &environment, &project)
-                .await
-                .unwrap();
+        let script = generate_activation_script(
+            Some(ShellEnum::CmdExe(CmdExe)),
+            &environment,
+            &prefix,
+            &project,
+        )
+        .await
+        .unwrap();
         assert!(script.contains(&format!("@SET \"{path_var_name}=")));
         assert!(script.contains("@SET \"CONDA_PREFIX="));
 
-        let script =
-            generate_activation_script(Some(ShellEnum::NuShell(NuShell)), &environment, &project)
-                .await
-                .unwrap();
+        let script = generate_activation_script(
+            Some(ShellEnum::NuShell(NuShell)),
+            &environment,
+            &prefix,
+            &project,
+        )
+        .await
+        .unwrap();
         assert!(script.contains(&format!("$env.{path_var_name} = ")));
         assert!(script.contains("$env.CONDA_PREFIX = "));
     }
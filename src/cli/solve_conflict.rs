@@ -0,0 +1,275 @@
+use std::sync::Arc;
+
+use itertools::Itertools;
+use miette::{Diagnostic, LabeledSpan, NamedSource, Report};
+use pixi_consts::consts;
+use pixi_manifest::{FeatureName, ManifestKind, SpecType, TargetSelector};
+use pixi_spec::PixiSpec;
+use rattler_conda_types::{
+    PackageName, Platform, VersionSpec,
+    version_spec::{EqualityOperator, LogicalOperator, RangeOperator, StrictRangeOperator},
+};
+use rattler_solve::SolveError;
+use thiserror::Error;
+
+use crate::Workspace;
+
+/// A spec in the manifest that is involved in a conflict.
+struct ConflictingSpec {
+    name: PackageName,
+    spec: PixiSpec,
+    /// The keys of the table in the manifest that contains the spec.
+    table: Vec<String>,
+}
+
+impl ConflictingSpec {
+    /// The name of the table in dotted form, e.g. `feature.test.dependencies`.
+    fn table_name(&self) -> String {
+        self.table.join(".")
+    }
+}
+
+/// The error of a failed solve after adding packages, it points at the version
+/// constraints in the manifest that take part in the solve. The output of the
+/// solver is kept as the source of the error.
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "cannot add {added}, the environment can't be solved with the version constraints in the manifest"
+)]
+struct SolveConflictError {
+    /// The added packages, formatted for the message.
+    added: String,
+
+    #[help]
+    help: String,
+
+    #[source_code]
+    source_code: Option<NamedSource<Arc<str>>>,
+
+    #[label(collection)]
+    labels: Vec<LabeledSpan>,
+
+    #[source]
+    solve_error: SolveError,
+}
+
+/// Turns the error of a failed solve after adding the `added` packages into a
+/// diagnostic that points at the version constraints in the manifest that
+/// take part in the solve and suggests how to relax them.
+///
+/// The original error is returned if the conda environment is solvable or if
+/// the manifest doesn't constrain the version of any other package.
+pub(crate) fn explain_solve_conflict(
+    error: Report,
+    workspace: &Workspace,
+    added: &[PackageName],
+    features: &[FeatureName],
+    platforms: &[Platform],
+) -> Report {
+    let Some(SolveError::Unsolvable(messages)) = error
+        .chain()
+        .find_map(|err| err.downcast_ref::<SolveError>())
+    else {
+        return error;
+    };
+
+    let conflicts = manifest_specs(workspace, features, platforms)
+        .into_iter()
+        .filter(|spec| {
+            !added.contains(&spec.name)
+                && spec
+                    .spec
+                    .as_version_spec()
+                    .is_some_and(|version| version != &VersionSpec::Any)
+        })
+        .collect_vec();
+    if conflicts.is_empty() {
+        return error;
+    }
+
+    let mut suggestions = conflicts
+        .iter()
+        .filter_map(|conflict| {
+            let name = conflict.name.as_source();
+            let version = conflict.spec.as_version_spec()?;
+            Some(match relax_version_spec(version) {
+                Some(relaxed) => format!(
+                    "relax `{name} {version}` to `{relaxed}` in `[{}]`",
+                    conflict.table_name()
+                ),
+                None => format!(
+                    "relax or remove `{name} {version}` in `[{}]`",
+                    conflict.table_name()
+                ),
+            })
+        })
+        .collect_vec();
+    suggestions.extend(
+        platforms
+            .iter()
+            .map(|platform| format!("drop `--platform {platform}`")),
+    );
+    suggestions.push("use `--no-suggestions` to only show the solver output".to_string());
+
+    let provenance = &workspace.workspace.provenance;
+    let source = provenance.read().ok().map(|source| source.into_inner());
+    let labels = source
+        .iter()
+        .flat_map(|source| {
+            conflicts.iter().filter_map(|conflict| {
+                let span = spec_span(source, conflict)?;
+                Some(LabeledSpan::new_with_span(
+                    Some("constrains the solve".to_string()),
+                    span,
+                ))
+            })
+        })
+        .collect_vec();
+    Report::new(SolveConflictError {
+        added: added
+            .iter()
+            .map(|name| format!("`{}`", name.as_source()))
+            .join(", "),
+        help: suggestions.join("\n"),
+        source_code: source.map(|source| {
+            NamedSource::new(provenance.path.to_string_lossy(), Arc::<str>::from(source))
+        }),
+        labels,
+        solve_error: SolveError::Unsolvable(messages.clone()),
+    })
+}
+
+/// Returns the conda specs of the manifest that end up in the environments of
+/// the given features, for the given platforms.
+fn manifest_specs(
+    workspace: &Workspace,
+    features: &[FeatureName],
+    platforms: &[Platform],
+) -> Vec<ConflictingSpec> {
+    let manifest = &workspace.workspace.value;
+    let prefix = (workspace.workspace.provenance.kind == ManifestKind::Pyproject)
+        .then_some(consts::PYPROJECT_PIXI_PREFIX);
+
+    let mut specs = Vec::new();
+    let default_feature = FeatureName::DEFAULT;
+    let features = std::iter::once(&default_feature)
+        .chain(features)
+        .unique()
+        .collect_vec();
+    let platforms = std::iter::once(None)
+        .chain(platforms.iter().copied().map(Some))
+        .collect_vec();
+    for feature_name in features {
+        let Some(feature) = manifest.feature(feature_name) else {
+            continue;
+        };
+        for platform in &platforms {
+            let Some(target) = feature
+                .targets
+                .for_opt_target(platform.map(TargetSelector::Platform).as_ref())
+            else {
+                continue;
+            };
+            for spec_type in SpecType::all() {
+                let Some(dependencies) = target.dependencies(spec_type) else {
+                    continue;
+                };
+                let mut table = prefix
+                    .iter()
+                    .flat_map(|prefix| prefix.split('.'))
+                    .map(ToString::to_string)
+                    .collect_vec();
+                if !feature_name.is_default() {
+                    table.extend(["feature".to_string(), feature_name.to_string()]);
+                }
+                if let Some(platform) = platform {
+                    table.extend(["target".to_string(), platform.to_string()]);
+                }
+                table.push(spec_type.name().to_string());
+
+                specs.extend(dependencies.iter().map(|(name, spec)| ConflictingSpec {
+                    name: name.clone(),
+                    spec: spec.clone(),
+                    table: table.clone(),
+                }));
+            }
+        }
+    }
+    specs
+}
+
+/// Relaxes a version spec by dropping its upper bounds, or by turning an exact
+/// or a prefix match into a lower bound.
+fn relax_version_spec(spec: &VersionSpec) -> Option<VersionSpec> {
+    match spec {
+        VersionSpec::Exact(EqualityOperator::Equals, version)
+        | VersionSpec::StrictRange(StrictRangeOperator::StartsWith, version) => Some(
+            VersionSpec::Range(RangeOperator::GreaterEquals, version.clone()),
+        ),
+        VersionSpec::Range(RangeOperator::Less | RangeOperator::LessEquals, _) => {
+            Some(VersionSpec::Any)
+        }
+        VersionSpec::Group(LogicalOperator::And, specs) => {
+            let lower_bounds = specs
+                .iter()
+                .filter(|spec| {
+                    matches!(
+                        spec,
+                        VersionSpec::Range(
+                            RangeOperator::Greater | RangeOperator::GreaterEquals,
+                            _
+                        )
+                    )
+                })
+                .cloned()
+                .collect_vec();
+            match lower_bounds.len() {
+                0 => Some(VersionSpec::Any),
+                n if n == specs.len() => None,
+                1 => lower_bounds.into_iter().next(),
+                _ => Some(VersionSpec::Group(LogicalOperator::And, lower_bounds)),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Finds the span of the spec in the source of the manifest.
+fn spec_span(source: &str, conflict: &ConflictingSpec) -> Option<std::ops::Range<usize>> {
+    let root = toml_span::parse(source).ok()?;
+    let mut keys = conflict
+        .table
+        .iter()
+        .map(String::as_str)
+        .chain([conflict.name.as_source()])
+        .peekable();
+    let mut value = &root;
+    while let Some(key) = keys.next() {
+        let (key, next) = value.as_table()?.iter().find(|(k, _)| k.name == key)?;
+        if keys.peek().is_none() {
+            return Some(key.span.start..next.span.end);
+        }
+        value = next;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use rattler_conda_types::ParseStrictness::Lenient;
+
+    use super::*;
+
+    #[test]
+    fn test_relax_version_spec() {
+        let relax = |spec: &str| {
+            relax_version_spec(&VersionSpec::from_str(spec, Lenient).unwrap())
+                .map(|spec| spec.to_string())
+        };
+        assert_eq!(relax(">=2.2,<2.3").as_deref(), Some(">=2.2"));
+        assert_eq!(relax("2.2.*").as_deref(), Some(">=2.2"));
+        assert_eq!(relax("==2.2.1").as_deref(), Some(">=2.2.1"));
+        assert_eq!(relax("<3").as_deref(), Some("*"));
+        assert_eq!(relax(">=2").as_deref(), None);
+    }
+}
@@ -71,8 +71,9 @@ pub struct AddArgs {
     #[clap(required = true, num_args = 1.., id = "COMMAND")]
     pub commands: Vec<String>,
 
-    /// Depends on these other commands.
-    #[clap(long)]
+    /// Depends on these other commands. Use `task:environment` to run a
+    /// dependency in another environment than the task.
+    #[clap(long, value_parser = parse_dependency)]
     #[clap(num_args = 1..)]
     pub depends_on: Option<Vec<Dependency>>,
 
@@ -117,14 +118,22 @@ fn parse_key_val(s: &str) -> Result<(String, String), Box<dyn Error + Send + Syn
     Ok((key, value))
 }
 
+/// Parse a dependency on a task. The environment of `task:environment` is
+/// only known once the workspace is loaded, see
+/// [`resolve_dependency_environments`].
+fn parse_dependency(s: &str) -> Result<Dependency, Box<dyn Error + Send + Sync + 'static>> {
+    Ok(Dependency::from(s))
+}
+
 #[derive(Parser, Debug, Clone)]
 #[clap(arg_required_else_help = true)]
 pub struct AliasArgs {
     /// Alias name
     pub alias: TaskName,
 
-    /// Depends on these tasks to execute
-    #[clap(required = true, num_args = 1..)]
+    /// Depends on these tasks to execute. Use `task:environment` to run a
+    /// task in a specific environment.
+    #[clap(required = true, num_args = 1.., value_parser = parse_dependency)]
     pub depends_on: Vec<Dependency>,
 
     /// The platform for which the alias should be added
@@ -216,8 +225,16 @@ impl From<AddArgs> for Task {
                 outputs: None,
                 cwd,
                 env,
+                env_files: None,
                 description,
                 clean_env,
+                clean_env_keep: None,
+                timeout: None,
+                continue_on_error: false,
+                shell: None,
+                interactive: false,
+                retries: None,
+                retry_delay: None,
                 args,
             }))
         }
@@ -393,6 +410,10 @@ async fn list_tasks(workspace: Workspace, args: ListArgs) -> miette::Result<()>
 
 async fn alias_task(mut workspace: WorkspaceMut, args: AliasArgs) -> miette::Result<()> {
     let name = &args.alias;
+    let args = AliasArgs {
+        depends_on: resolve_dependency_environments(workspace.workspace(), args.depends_on)?,
+        ..args
+    };
     let task: Task = args.clone().into();
     workspace.manifest().add_task(
         name.clone(),
@@ -473,8 +494,66 @@ async fn remove_tasks(mut workspace: WorkspaceMut, args: RemoveArgs) -> miette::
     Ok(())
 }
 
+/// Resolves the `task:environment` syntax of the dependencies of a task. The
+/// part after the last `:` is only the environment of the dependency if the
+/// workspace has that environment and `task:environment` isn't a task itself,
+/// task names may contain a `:` as well.
+fn resolve_dependency_environments(
+    workspace: &Workspace,
+    depends_on: Vec<Dependency>,
+) -> miette::Result<Vec<Dependency>> {
+    depends_on
+        .into_iter()
+        .map(|dependency| {
+            let Some((task, environment)) = dependency.task_name.as_str().rsplit_once(':') else {
+                return Ok(dependency);
+            };
+            if !workspace
+                .environments_with_task(&dependency.task_name)
+                .is_empty()
+            {
+                return Ok(dependency);
+            }
+            match EnvironmentName::from_str(environment)
+                .ok()
+                .filter(|name| workspace.environment(name).is_some())
+            {
+                Some(environment) => Ok(Dependency::new(task, dependency.args, Some(environment))),
+                // An existing task with a suffix that isn't an environment is
+                // most likely a typo in the environment.
+                None if !workspace
+                    .environments_with_task(&TaskName::from(task))
+                    .is_empty() =>
+                {
+                    Err(miette::miette!(
+                        help = format!(
+                            "the environments of the workspace are: {}",
+                            workspace
+                                .environments()
+                                .iter()
+                                .map(|env| env.name().to_string())
+                                .join(", ")
+                        ),
+                        "the dependency '{}' refers to the unknown environment '{}'",
+                        task,
+                        environment,
+                    ))
+                }
+                None => Ok(dependency),
+            }
+        })
+        .collect()
+}
+
 async fn add_task(mut workspace: WorkspaceMut, args: AddArgs) -> miette::Result<()> {
     let name = &args.name;
+    let args = AddArgs {
+        depends_on: args
+            .depends_on
+            .map(|depends_on| resolve_dependency_environments(workspace.workspace(), depends_on))
+            .transpose()?,
+        ..args
+    };
     let task: Task = args.clone().into();
     let feature = args
         .feature
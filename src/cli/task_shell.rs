@@ -0,0 +1,39 @@
+use clap::Parser;
+use deno_task_shell::{KillSignal, ShellPipeReader, ShellPipeWriter, ShellState};
+use miette::IntoDiagnostic;
+
+use crate::task::parse_deno_script;
+
+/// Run a script with the shell that is built into pixi.
+///
+/// `pixi run` starts the tasks that use the built-in shell with this command,
+/// so that every task runs in a process of its own which can be stopped
+/// together with the processes that it started. The script runs with the
+/// environment variables and in the working directory of this process.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The script to run.
+    #[arg(short = 'c', allow_hyphen_values = true)]
+    pub script: String,
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let script = parse_deno_script(&args.script)?;
+    let env_vars = std::env::vars_os().collect();
+    let cwd = std::env::current_dir().into_diagnostic()?;
+
+    // The signals that stop the task reach the processes of the script with
+    // the process group of this process, the script doesn't forward them.
+    let state = ShellState::new(env_vars, cwd, Default::default(), KillSignal::default());
+    let exit_code = deno_task_shell::execute_with_pipes(
+        script,
+        state,
+        ShellPipeReader::stdin(),
+        ShellPipeWriter::stdout(),
+        ShellPipeWriter::stderr(),
+    )
+    .await;
+
+    // Return the exit code of the script
+    std::process::exit(exit_code);
+}
@@ -87,6 +87,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             lock_file_usage: args.lock_file_update_config.lock_file_usage()?,
             no_install: args.lock_file_update_config.no_lockfile_update,
             max_concurrent_solves: workspace.config().max_concurrent_solves(),
+            no_prune: false,
         })
         .await
         .wrap_err("Failed to update lock file")?
@@ -421,7 +422,7 @@ fn print_package(
 }
 
 /// Extract the direct Conda and PyPI dependencies from the environment
-fn direct_dependencies(
+pub(crate) fn direct_dependencies(
     environment: &Environment<'_>,
     platform: &Platform,
     dep_map: &HashMap<String, Package>,
@@ -456,18 +457,18 @@ fn direct_dependencies(
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
-enum PackageSource {
+pub(crate) enum PackageSource {
     Conda,
     Pypi,
 }
 
 #[derive(Debug, Clone)]
-struct Package {
-    name: String,
-    version: String,
-    dependencies: Vec<String>,
+pub(crate) struct Package {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    pub(crate) dependencies: Vec<String>,
     needed_by: Vec<String>,
-    source: PackageSource,
+    pub(crate) source: PackageSource,
 }
 
 /// Simplified package information extracted from the lock file
@@ -533,7 +534,7 @@ fn extract_package_info(package: rattler_lock::LockedPackageRef<'_>) -> Option<P
 }
 
 /// Generate a map of dependencies from a list of locked packages
-fn generate_dependency_map(
+pub(crate) fn generate_dependency_map(
     locked_deps: &[rattler_lock::LockedPackageRef<'_>],
 ) -> HashMap<String, Package> {
     let mut package_dependencies_map = HashMap::new();
@@ -1,23 +1,40 @@
-use std::{cmp::Ordering, collections::HashSet};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::{Display, Formatter},
+    io::IsTerminal,
+    str::FromStr,
+};
 
 use crate::{
     Workspace,
-    lock_file::{UpdateContext, filter_lock_file},
+    build::GlobHashCache,
+    lock_file::{
+        LockFileDerivedData, OutdatedEnvironments, SolveConstraints, UpdateContext,
+        filter_lock_file, refresh_lock_file_metadata,
+    },
 };
 use crate::{
     WorkspaceLocator,
     cli::cli_config::WorkspaceConfig,
     diff::{LockFileDiff, LockFileJsonDiff},
+    install_pypi::utils::{is_direct_url, strip_direct_scheme},
 };
 use clap::Parser;
+use dialoguer::theme::ColorfulTheme;
 use fancy_display::FancyDisplay;
 use itertools::Itertools;
 use miette::{Context, IntoDiagnostic, MietteDiagnostic};
-use pixi_config::ConfigCli;
+use pixi_config::{ConfigCli, ConfigCliNetwork};
 use pixi_consts::consts;
-use pixi_manifest::EnvironmentName;
-use rattler_conda_types::Platform;
-use rattler_lock::{LockFile, LockedPackageRef};
+use pixi_manifest::{EnvironmentName, FeaturesExt};
+use pixi_record::LockedGitUrl;
+use pixi_spec::GitReference;
+use rattler_conda_types::{
+    MatchSpec, NamelessMatchSpec, ParseStrictness, Platform, Version, VersionBumpType, VersionSpec,
+    version_spec::{LogicalOperator, RangeOperator},
+};
+use rattler_lock::{LockFile, LockedPackage, LockedPackageRef, UrlOrPath};
 
 /// The `update` command checks if there are newer versions of the dependencies and updates the `pixi.lock` file and environments accordingly.
 ///
@@ -26,6 +43,8 @@ use rattler_lock::{LockFile, LockedPackageRef};
 pub struct Args {
     #[clap(flatten)]
     pub config: ConfigCli,
+    #[clap(flatten)]
+    pub network_config: ConfigCliNetwork,
 
     #[clap(flatten)]
     pub project_config: WorkspaceConfig,
@@ -45,6 +64,24 @@ pub struct Args {
     /// Output the changes in JSON format.
     #[clap(long)]
     pub json: bool,
+
+    /// Select which of the changes to apply from a checklist before the
+    /// lock-file is written. The packages that are not selected are kept at
+    /// their locked version.
+    #[clap(long, short = 'i', conflicts_with_all = ["json", "dry_run"])]
+    pub interactive: bool,
+
+    /// Keep the locked version and build of every package and only refresh
+    /// their urls, hashes and metadata from the repodata of the configured
+    /// channels, without solving. Fails if a locked package can no longer be
+    /// found in the channels.
+    #[clap(long, conflicts_with_all = ["interactive", "packages", "conservative", "major", "minor", "patch", "exclude"])]
+    pub refresh_metadata: bool,
+
+    /// Only show the changes to the direct dependencies of the environments,
+    /// the changes to the other packages are still applied.
+    #[clap(long)]
+    pub direct_only: bool,
 }
 
 #[derive(Parser, Debug, Default)]
@@ -54,14 +91,131 @@ pub struct UpdateSpecsArgs {
     pub packages: Option<Vec<String>>,
 
     /// The environments to update. If none is specified, all environments are
-    /// updated.
+    /// updated. Environments that share a solve group with the selected
+    /// environments are also updated, all other environments are kept as
+    /// they are locked.
     #[clap(long = "environment", short = 'e')]
     pub environments: Option<Vec<EnvironmentName>>,
 
     /// The platforms to update. If none is specified, all platforms are
-    /// updated.
+    /// updated. The locked packages of the other platforms are kept as they
+    /// are, the update is refused if they would have to change as well.
     #[clap(long = "platform", short = 'p')]
     pub platforms: Option<Vec<Platform>>,
+
+    /// Prefer the currently locked versions of all packages that are not
+    /// updated. If no packages are provided, only the direct dependencies are
+    /// updated and other packages only change when that is required.
+    #[clap(long)]
+    pub conservative: bool,
+
+    /// Allow updating the packages to a newer major version.
+    #[clap(long, requires = "packages", conflicts_with_all = ["minor", "patch"])]
+    pub major: bool,
+
+    /// Only update the packages to a newer minor or patch version, the major
+    /// version of the locked packages is kept.
+    #[clap(long, requires = "packages", conflicts_with = "patch")]
+    pub minor: bool,
+
+    /// Only update the packages to a newer patch version, the major and minor
+    /// version of the locked packages are kept.
+    #[clap(long, requires = "packages")]
+    pub patch: bool,
+
+    /// The packages to keep at their currently locked version, can be
+    /// provided more than once. The update fails if one of these packages
+    /// has to change.
+    #[clap(long = "exclude", value_name = "PACKAGE", conflicts_with = "packages")]
+    pub exclude: Option<Vec<String>>,
+}
+
+/// Restricts how far the updated packages can move away from their locked
+/// version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UpdateGranularity {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl UpdateGranularity {
+    /// The number of leading version segments that are kept.
+    fn fixed_segments(self) -> usize {
+        match self {
+            UpdateGranularity::Major => 0,
+            UpdateGranularity::Minor => 1,
+            UpdateGranularity::Patch => 2,
+        }
+    }
+
+    /// Returns the spec that restricts a conda package to newer versions
+    /// within the granularity. The upper bound keeps the epoch and the fixed
+    /// segments of the locked version, missing segments count as `0`.
+    fn conda_spec(self, locked: &Version) -> miette::Result<NamelessMatchSpec> {
+        let lower_bound = VersionSpec::Range(RangeOperator::GreaterEquals, locked.clone());
+        let version = match self.fixed_segments() {
+            0 => lower_bound,
+            segments => {
+                let upper_bound = locked
+                    .extend_to_length(segments)
+                    .into_diagnostic()?
+                    .with_segments(..segments)
+                    .ok_or_else(|| miette::miette!("cannot restrict the version '{locked}'"))?
+                    .bump(VersionBumpType::Last)
+                    .into_diagnostic()?;
+                VersionSpec::Group(
+                    LogicalOperator::And,
+                    vec![
+                        lower_bound,
+                        VersionSpec::Range(RangeOperator::Less, upper_bound),
+                    ],
+                )
+            }
+        };
+        Ok(NamelessMatchSpec {
+            version: Some(version),
+            ..NamelessMatchSpec::default()
+        })
+    }
+
+    /// Returns the specifiers that restrict a pypi package to newer versions
+    /// within the granularity. The prefix keeps the epoch and the fixed
+    /// release segments of the locked version, missing segments count as `0`.
+    fn pypi_specifiers(
+        self,
+        locked: &pep440_rs::Version,
+    ) -> miette::Result<pep440_rs::VersionSpecifiers> {
+        let lower_bound = pep440_rs::VersionSpecifier::greater_than_equal_version(locked.clone());
+        let specifiers = match self.fixed_segments() {
+            0 => vec![lower_bound],
+            segments => {
+                let release = (0..segments)
+                    .map(|idx| locked.release().get(idx).copied().unwrap_or_default())
+                    .collect::<Vec<_>>();
+                let prefix = pep440_rs::Version::new(release).with_epoch(locked.epoch());
+                vec![
+                    lower_bound,
+                    pep440_rs::VersionSpecifier::from_version(
+                        pep440_rs::Operator::EqualStar,
+                        prefix,
+                    )
+                    .into_diagnostic()?,
+                ]
+            }
+        };
+        Ok(pep440_rs::VersionSpecifiers::from_iter(specifiers))
+    }
+}
+
+impl Display for UpdateGranularity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateGranularity::Major => write!(f, "major"),
+            UpdateGranularity::Minor => write!(f, "minor"),
+            UpdateGranularity::Patch => write!(f, "patch"),
+        }
+    }
 }
 
 /// A distilled version of `UpdateSpecsArgs`.
@@ -71,6 +225,11 @@ struct UpdateSpecs {
     packages: Option<HashSet<String>>,
     environments: Option<HashSet<EnvironmentName>>,
     platforms: Option<HashSet<Platform>>,
+    conservative: bool,
+    granularity: Option<UpdateGranularity>,
+    excluded: HashSet<String>,
+    /// The packages of an environment that were not selected interactively.
+    deselected: HashSet<(EnvironmentName, String)>,
 }
 
 impl From<UpdateSpecsArgs> for UpdateSpecs {
@@ -79,6 +238,18 @@ impl From<UpdateSpecsArgs> for UpdateSpecs {
             packages: args.packages.map(|args| args.into_iter().collect()),
             environments: args.environments.map(|args| args.into_iter().collect()),
             platforms: args.platforms.map(|args| args.into_iter().collect()),
+            conservative: args.conservative,
+            granularity: if args.major {
+                Some(UpdateGranularity::Major)
+            } else if args.minor {
+                Some(UpdateGranularity::Minor)
+            } else if args.patch {
+                Some(UpdateGranularity::Patch)
+            } else {
+                None
+            },
+            excluded: args.exclude.into_iter().flatten().collect(),
+            deselected: HashSet::new(),
         }
     }
 }
@@ -92,18 +263,8 @@ impl UpdateSpecs {
         platform: &Platform,
         package: LockedPackageRef<'_>,
     ) -> bool {
-        // Check if the platform is in the list of platforms to update.
-        if let Some(platforms) = &self.platforms {
-            if !platforms.contains(platform) {
-                return false;
-            }
-        }
-
-        // Check if the environmtent is in the list of environments to update.
-        if let Some(environments) = &self.environments {
-            if !environments.contains(environment_name) {
-                return false;
-            }
+        if !self.is_selected(environment_name, platform) {
+            return false;
         }
 
         // Check if the package is in the list of packages to update.
@@ -113,6 +274,11 @@ impl UpdateSpecs {
             }
         }
 
+        // Excluded packages are kept at their locked version.
+        if self.is_pinned(environment_name, package.name()) {
+            return false;
+        }
+
         tracing::debug!(
             "relaxing package: {}, env={}, platform={}",
             package.name(),
@@ -122,16 +288,57 @@ impl UpdateSpecs {
 
         true
     }
+
+    /// Returns true if the package is kept at its locked version.
+    fn is_pinned(&self, environment_name: &EnvironmentName, package_name: &str) -> bool {
+        self.excluded.contains(package_name)
+            || self
+                .deselected
+                .contains(&(environment_name.clone(), package_name.to_string()))
+    }
+
+    /// Returns true if the environment and platform are selected to be
+    /// updated.
+    fn is_selected(&self, environment_name: &EnvironmentName, platform: &Platform) -> bool {
+        // Check if the platform is in the list of platforms to update.
+        if let Some(platforms) = &self.platforms {
+            if !platforms.contains(platform) {
+                return false;
+            }
+        }
+
+        // Check if the environmtent is in the list of environments to update.
+        if let Some(environments) = &self.environments {
+            if !environments.contains(environment_name) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Returns true if only the direct dependencies of the environments should
+    /// be relaxed.
+    fn only_direct_dependencies(&self) -> bool {
+        self.conservative && self.packages.is_none()
+    }
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
-    let config = args.config;
+    let config = args
+        .network_config
+        .clone()
+        .merge_config(args.config.clone().into());
     let workspace = WorkspaceLocator::for_cli()
         .with_search_start(args.project_config.workspace_locator_start())
         .locate()?
         .with_cli_config(config);
 
-    let specs = UpdateSpecs::from(args.specs);
+    if args.interactive && !std::io::stdout().is_terminal() {
+        miette::bail!("`--interactive` can only be used in a terminal");
+    }
+
+    let mut specs = UpdateSpecs::from(args.specs);
 
     // If the user specified an environment name, check to see if it exists.
     if let Some(env) = &specs.environments {
@@ -144,29 +351,96 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             }
         }
     }
+    include_solve_group_environments(&workspace, &mut specs);
+
+    // If the user specified a platform, check to see if any of the environments
+    // supports it.
+    if let Some(platforms) = &specs.platforms {
+        for platform in platforms.iter().sorted_by_key(|p| p.as_str()) {
+            if !workspace
+                .environments()
+                .iter()
+                .filter(|env| specs.is_selected(env.name(), platform))
+                .any(|env| env.platforms().contains(platform))
+            {
+                miette::bail!(
+                    "none of the environments to update supports the platform {}",
+                    consts::PLATFORM_STYLE.apply_to(platform)
+                )
+            }
+        }
+    }
 
     // Load the current lock-file, if any. If none is found, a dummy lock-file is
     // returned.
     let loaded_lock_file = &workspace.load_lock_file().await?;
 
-    // If the user specified a package name, check to see if it is even locked.
-    if let Some(packages) = &specs.packages {
-        for package in packages {
-            ensure_package_exists(loaded_lock_file, package, &specs)?
+    // Only look up the locked packages again, nothing is solved.
+    if args.refresh_metadata {
+        let lock_file =
+            refresh_lock_file_metadata(&workspace, loaded_lock_file, |env, platform| {
+                specs.is_selected(env, &platform)
+            })
+            .await?;
+        if !args.dry_run {
+            workspace.write_lock_file(&lock_file)?;
         }
+        let diff = lock_file_diff(loaded_lock_file, &lock_file, &specs);
+        return print_lock_file_diff(&workspace, diff, &args);
+    }
+
+    // If the user specified a package name, check to see if it is even locked.
+    for package in specs.packages.iter().flatten().chain(&specs.excluded) {
+        ensure_package_exists(loaded_lock_file, package, &specs)?
+    }
+
+    // Packages from a git repository only move when they follow a branch.
+    check_git_packages(loaded_lock_file, &mut specs)?;
+    if specs
+        .packages
+        .as_ref()
+        .is_some_and(|packages| packages.is_empty())
+    {
+        let diff = LockFileDiff::from_lock_files(loaded_lock_file, loaded_lock_file);
+        return print_lock_file_diff(&workspace, diff, &args);
     }
 
     // Unlock dependencies in the lock-file that we want to update.
     let relaxed_lock_file = unlock_packages(&workspace, loaded_lock_file, &specs);
 
+    // Pin the excluded packages and keep the updated packages close to their
+    // locked version if requested.
+    let constraints = solve_constraints(loaded_lock_file, &specs)?;
+
     // Update the packages in the lock-file.
-    let updated_lock_file = UpdateContext::builder(&workspace)
-        .with_lock_file(relaxed_lock_file.clone())
-        .with_no_install(args.no_install)
-        .finish()
-        .await?
-        .update()
-        .await?;
+    let mut updated_lock_file = solve_lock_file(
+        &workspace,
+        &specs,
+        relaxed_lock_file,
+        constraints,
+        args.no_install,
+    )
+    .await?;
+
+    // Let the user pick the changes to apply and solve again with the
+    // packages that were not picked pinned to their locked version.
+    if args.interactive {
+        let diff = lock_file_diff(loaded_lock_file, updated_lock_file.as_lock_file(), &specs);
+        specs.deselected =
+            select_changes(loaded_lock_file, updated_lock_file.as_lock_file(), &diff)?;
+        if !specs.deselected.is_empty() {
+            let relaxed_lock_file = unlock_packages(&workspace, loaded_lock_file, &specs);
+            let constraints = solve_constraints(loaded_lock_file, &specs)?;
+            updated_lock_file = solve_lock_file(
+                &workspace,
+                &specs,
+                relaxed_lock_file,
+                constraints,
+                args.no_install,
+            )
+            .await?;
+        }
+    }
 
     // If we're doing a dry-run, we don't want to write the lock-file.
     if !args.dry_run {
@@ -175,13 +449,27 @@ pub async fn execute(args: Args) -> miette::Result<()> {
 
     let lock_file = updated_lock_file.into_lock_file();
 
-    // Determine the diff between the old and new lock-file.
-    let diff = LockFileDiff::from_lock_files(loaded_lock_file, &lock_file);
+    report_packages_without_update(loaded_lock_file, &lock_file, &specs)?;
+
+    // Determine the diff between the old and new lock-file, only for the
+    // selected environments.
+    let diff = lock_file_diff(loaded_lock_file, &lock_file, &specs);
+    print_lock_file_diff(&workspace, diff, &args)
+}
 
-    // Format as json?
+/// Prints the changes to the lock-file, either as a table or as json. The
+/// changes to the direct dependencies of an environment are listed before the
+/// other changes.
+fn print_lock_file_diff(
+    workspace: &Workspace,
+    diff: LockFileDiff,
+    args: &Args,
+) -> miette::Result<()> {
     if args.json {
-        let diff = LockFileDiff::from_lock_files(loaded_lock_file, &lock_file);
-        let json_diff = LockFileJsonDiff::new(Some(&workspace), diff);
+        let mut json_diff = LockFileJsonDiff::new(Some(workspace), diff);
+        if args.direct_only {
+            json_diff = json_diff.direct_only();
+        }
         let json = serde_json::to_string_pretty(&json_diff).expect("failed to convert to json");
         println!("{}", json);
     } else if diff.is_empty() {
@@ -190,7 +478,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             console::style(console::Emoji("✔ ", "")).green()
         );
     } else {
-        diff.print()
+        diff.print_by_dependency_kind(workspace, args.direct_only)
             .into_diagnostic()
             .context("failed to print lock-file diff")?;
     }
@@ -198,6 +486,379 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     Ok(())
 }
 
+/// Solves the outdated environments of the relaxed lock-file. If only some
+/// environments are selected, the locked content of all other environments is
+/// kept as is.
+async fn solve_lock_file<'p>(
+    workspace: &'p Workspace,
+    specs: &UpdateSpecs,
+    relaxed_lock_file: LockFile,
+    constraints: SolveConstraints,
+    no_install: bool,
+) -> miette::Result<LockFileDerivedData<'p>> {
+    let glob_hash_cache = GlobHashCache::default();
+    let mut update_context = UpdateContext::builder(workspace)
+        .with_no_install(no_install)
+        .with_solve_constraints(constraints)
+        .with_glob_hash_cache(glob_hash_cache.clone());
+
+    if specs.environments.is_some() || specs.platforms.is_some() {
+        let mut outdated = OutdatedEnvironments::from_workspace_and_lock_file(
+            workspace,
+            &relaxed_lock_file,
+            glob_hash_cache,
+        )
+        .await;
+        if let Some(environments) = &specs.environments {
+            outdated.retain(|env| environments.contains(env.name()));
+        }
+        if let Some(platforms) = &specs.platforms {
+            ensure_only_selected_platforms_outdated(&outdated, platforms)?;
+        }
+        update_context = update_context.with_outdated_environments(outdated);
+    }
+
+    let updated_lock_file = update_context
+        .with_lock_file(relaxed_lock_file)
+        .finish()
+        .await?
+        .update()
+        .await;
+    // The packages that are kept at their locked version are the likely cause
+    // of a failing solve, the solver only reports them as a locked constraint
+    let pinned = specs
+        .excluded
+        .iter()
+        .chain(specs.deselected.iter().map(|(_, name)| name))
+        .sorted()
+        .dedup()
+        .collect_vec();
+    if pinned.is_empty() {
+        return updated_lock_file;
+    }
+    updated_lock_file.with_context(|| {
+        format!(
+            "failed to update while keeping '{}' at the locked version, the requirements below need a different version",
+            pinned.iter().format("', '")
+        )
+    })
+}
+
+/// Ensures that only the selected platforms have to be solved again. The
+/// locked packages of the other platforms can only be kept if they still
+/// satisfy the manifest.
+fn ensure_only_selected_platforms_outdated(
+    outdated: &OutdatedEnvironments<'_>,
+    platforms: &HashSet<Platform>,
+) -> miette::Result<()> {
+    let environments = outdated
+        .conda
+        .keys()
+        .chain(outdated.pypi.keys())
+        .unique()
+        .sorted_by_key(|env| env.name().clone());
+    for env in environments {
+        let reason = match env.solve_group() {
+            Some(solve_group) => format!(
+                "the environments in the solve group '{}' have to be locked consistently",
+                solve_group.name()
+            ),
+            None => String::from("its locked packages don't satisfy the manifest anymore"),
+        };
+
+        if outdated
+            .disregard_locked_content
+            .should_disregard_conda(env)
+            || outdated.disregard_locked_content.should_disregard_pypi(env)
+        {
+            return Err(miette::miette!(
+                help = "run `pixi update` without `--platform` to update all platforms",
+                "the lock-file of {} has to be solved again for all platforms, e.g. because its channels or indexes changed",
+                env.name().fancy_display()
+            ));
+        }
+
+        let other_platforms = outdated
+            .conda
+            .get(env)
+            .into_iter()
+            .chain(outdated.pypi.get(env))
+            .flatten()
+            .filter(|platform| !platforms.contains(platform))
+            .unique()
+            .sorted_by_key(|platform| platform.as_str())
+            .collect_vec();
+        if !other_platforms.is_empty() {
+            return Err(miette::miette!(
+                help = "also select these platforms with `--platform`, or run `pixi update` without `--platform`",
+                "the lock-file of {} also has to be updated for {}, because {reason}",
+                env.name().fancy_display(),
+                other_platforms
+                    .iter()
+                    .map(|platform| consts::PLATFORM_STYLE.apply_to(platform))
+                    .format(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Determines the diff between the old and new lock-file, only for the
+/// selected environments and platforms.
+fn lock_file_diff(previous: &LockFile, current: &LockFile, specs: &UpdateSpecs) -> LockFileDiff {
+    let mut diff = LockFileDiff::from_lock_files(previous, current);
+    if let Some(environments) = &specs.environments {
+        diff.environment
+            .retain(|name, _| environments.iter().any(|env| env.as_str() == name.as_str()));
+    }
+    if let Some(platforms) = &specs.platforms {
+        for environment in diff.environment.values_mut() {
+            environment.retain(|platform, _| platforms.contains(platform));
+        }
+        diff.environment
+            .retain(|_, environment| !environment.is_empty());
+    }
+    diff
+}
+
+/// A change to a package of an environment that can be deselected
+/// interactively, merged over all platforms.
+#[derive(Debug, Default)]
+struct SelectableChange {
+    environment: String,
+    name: String,
+    versions: BTreeSet<String>,
+    /// The packages that are kept at their locked version if the change is
+    /// not selected.
+    pins: BTreeSet<String>,
+}
+
+/// Returns the changed, added and removed packages of every environment. A
+/// changed package is deselected by keeping it at its locked version. Added
+/// and removed packages are not in both lock-files, so they are deselected by
+/// keeping the locked packages that depend on them.
+fn selectable_changes(
+    previous: &LockFile,
+    updated: &LockFile,
+    diff: &LockFileDiff,
+) -> Vec<SelectableChange> {
+    fn version(package: &LockedPackage) -> String {
+        match package {
+            LockedPackage::Conda(data) => {
+                format!("{} {}", data.record().version, data.record().build)
+            }
+            LockedPackage::Pypi(data, _) => data.version.to_string(),
+        }
+    }
+
+    let mut changes: BTreeMap<(String, String), SelectableChange> = BTreeMap::new();
+    let mut entry = |environment_name: &str, name: &str| {
+        changes
+            .entry((environment_name.to_string(), name.to_string()))
+            .or_insert_with(|| SelectableChange {
+                environment: environment_name.to_string(),
+                name: name.to_string(),
+                ..SelectableChange::default()
+            })
+    };
+    for (environment_name, platforms) in &diff.environment {
+        for (platform, packages) in platforms {
+            for (previous, current) in &packages.changed {
+                let change = entry(environment_name, previous.name());
+                change
+                    .versions
+                    .insert(format!("{} -> {}", version(previous), version(current)));
+                change.pins.insert(previous.name().to_string());
+            }
+            for added in &packages.added {
+                let pins =
+                    locked_dependents(previous, updated, environment_name, *platform, added.name());
+                let change = entry(environment_name, added.name());
+                change.versions.insert(format!("added {}", version(added)));
+                change.pins.extend(pins);
+            }
+            for removed in &packages.removed {
+                let pins = dependents(previous, environment_name, *platform, removed.name());
+                let change = entry(environment_name, removed.name());
+                change
+                    .versions
+                    .insert(format!("removed {}", version(removed)));
+                change.pins.extend(pins);
+            }
+        }
+    }
+    changes.into_values().collect()
+}
+
+/// Returns the names of the packages of the environment that depend on the
+/// package.
+fn dependents(
+    lock_file: &LockFile,
+    environment_name: &str,
+    platform: Platform,
+    name: &str,
+) -> Vec<String> {
+    let Some(packages) = lock_file
+        .environment(environment_name)
+        .and_then(|environment| environment.packages(platform))
+    else {
+        return Vec::new();
+    };
+    packages
+        .filter(|package| match package {
+            LockedPackageRef::Conda(data) => data.record().depends.iter().any(|spec| {
+                MatchSpec::from_str(spec, ParseStrictness::Lenient)
+                    .ok()
+                    .and_then(|spec| spec.name)
+                    .is_some_and(|dependency| dependency.as_normalized() == name)
+            }),
+            LockedPackageRef::Pypi(data, _) => data
+                .requires_dist
+                .iter()
+                .any(|requirement| requirement.name.as_ref() == name),
+        })
+        .map(|package| package.name().to_string())
+        .collect()
+}
+
+/// Returns the packages of the previous lock-file that pull an added package
+/// into the updated lock-file, following the dependents that were added as
+/// well.
+fn locked_dependents(
+    previous: &LockFile,
+    updated: &LockFile,
+    environment_name: &str,
+    platform: Platform,
+    name: &str,
+) -> BTreeSet<String> {
+    let was_locked = |name: &str| {
+        previous
+            .environment(environment_name)
+            .and_then(|environment| environment.packages(platform))
+            .is_some_and(|mut packages| packages.any(|package| package.name() == name))
+    };
+
+    let mut pins = BTreeSet::new();
+    let mut visited = HashSet::from([name.to_string()]);
+    let mut queue = vec![name.to_string()];
+    while let Some(name) = queue.pop() {
+        for dependent in dependents(updated, environment_name, platform, &name) {
+            if !visited.insert(dependent.clone()) {
+                continue;
+            }
+            if was_locked(&dependent) {
+                pins.insert(dependent);
+            } else {
+                queue.push(dependent);
+            }
+        }
+    }
+    pins
+}
+
+/// Returns the environment and name of the packages that are kept at their
+/// locked version because their change was not selected.
+fn deselected_pins(
+    changes: &[SelectableChange],
+    selected: &[usize],
+) -> miette::Result<HashSet<(EnvironmentName, String)>> {
+    changes
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !selected.contains(idx))
+        .flat_map(|(_, change)| {
+            change
+                .pins
+                .iter()
+                .map(move |name| (change.environment.as_str(), name))
+        })
+        .map(|(environment_name, name)| {
+            Ok((
+                EnvironmentName::from_str(environment_name).into_diagnostic()?,
+                name.clone(),
+            ))
+        })
+        .collect()
+}
+
+/// Presents the changed, added and removed packages of every environment as
+/// a checklist and returns the environment and name of the packages that are
+/// kept at their locked version.
+fn select_changes(
+    previous: &LockFile,
+    updated: &LockFile,
+    diff: &LockFileDiff,
+) -> miette::Result<HashSet<(EnvironmentName, String)>> {
+    let changes = selectable_changes(previous, updated, diff);
+    if changes.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let items = changes
+        .iter()
+        .map(|change| {
+            format!(
+                "{}: {} {}",
+                consts::ENVIRONMENT_STYLE.apply_to(&change.environment),
+                change.name,
+                change.versions.iter().format(", ")
+            )
+        })
+        .collect_vec();
+    let theme = ColorfulTheme {
+        active_item_style: console::Style::new().for_stderr().magenta(),
+        ..ColorfulTheme::default()
+    };
+    let Some(selected) = dialoguer::MultiSelect::with_theme(&theme)
+        .with_prompt("Select the packages to update")
+        .items(&items)
+        .defaults(&vec![true; items.len()])
+        .report(false)
+        .interact_opt()
+        .into_diagnostic()?
+    else {
+        miette::bail!("the update was cancelled, the lock-file was not changed");
+    };
+
+    deselected_pins(&changes, &selected)
+}
+
+/// Adds the environments that share a solve group with the selected
+/// environments, because they have to stay consistent with each other.
+fn include_solve_group_environments(workspace: &Workspace, specs: &mut UpdateSpecs) {
+    let Some(environments) = &mut specs.environments else {
+        return;
+    };
+
+    let selected = environments.iter().cloned().sorted().collect_vec();
+    for name in selected {
+        let Some(solve_group) = workspace
+            .environment(&name)
+            .and_then(|env| env.solve_group())
+        else {
+            continue;
+        };
+        let added = solve_group
+            .environments()
+            .filter(|env| environments.insert(env.name().clone()))
+            .collect_vec();
+        if !added.is_empty() {
+            eprintln!(
+                "{}Also updating {} because {} {} in the solve group '{}' with {}",
+                console::style(console::Emoji("ℹ️ ", "")).yellow(),
+                added
+                    .iter()
+                    .map(|env| env.name().fancy_display())
+                    .format(", "),
+                if added.len() == 1 { "it" } else { "they" },
+                if added.len() == 1 { "is" } else { "are" },
+                solve_group.name(),
+                name.fancy_display()
+            );
+        }
+    }
+}
+
 /// Ensures the existence of the specified package
 ///
 /// # Returns
@@ -274,7 +935,403 @@ fn ensure_package_exists(
 
 /// Constructs a new lock-file where some of the constraints have been removed.
 fn unlock_packages(project: &Workspace, lock_file: &LockFile, specs: &UpdateSpecs) -> LockFile {
+    let mut direct_dependencies = HashMap::new();
     filter_lock_file(project, lock_file, |env, platform, package| {
-        !specs.should_relax(env.name(), &platform, package)
+        if !specs.should_relax(env.name(), &platform, package) {
+            return true;
+        }
+
+        // The locked packages are used as preferences by the solver, so keeping
+        // the indirect dependencies makes them only change when required.
+        if specs.only_direct_dependencies() {
+            let direct_dependencies = direct_dependencies
+                .entry((env.name().clone(), platform))
+                .or_insert_with(|| {
+                    env.combined_dependencies(Some(platform))
+                        .names()
+                        .map(|name| name.as_normalized().to_string())
+                        .chain(
+                            env.pypi_dependencies(Some(platform))
+                                .names()
+                                .map(|name| name.as_normalized().to_string()),
+                        )
+                        .collect::<HashSet<_>>()
+                });
+            return !direct_dependencies.contains(package.name());
+        }
+
+        false
     })
 }
+
+/// Pins the excluded packages to their locked version and constrains the
+/// packages that are updated to newer versions within the requested
+/// granularity of their locked version.
+fn solve_constraints(
+    lock_file: &LockFile,
+    specs: &UpdateSpecs,
+) -> miette::Result<SolveConstraints> {
+    let mut constraints = SolveConstraints::default();
+    if specs.granularity.is_none() && specs.excluded.is_empty() && specs.deselected.is_empty() {
+        return Ok(constraints);
+    }
+
+    for (environment_name, environment) in lock_file.environments() {
+        let environment_name = EnvironmentName::from_str(environment_name).into_diagnostic()?;
+        for (platform, packages) in environment.packages_by_platform() {
+            if !specs.is_selected(&environment_name, &platform) {
+                continue;
+            }
+            for package in packages {
+                if specs.is_pinned(&environment_name, package.name()) {
+                    pin_locked_package(&mut constraints, &environment_name, platform, package)?;
+                    continue;
+                }
+
+                let Some(granularity) = specs.granularity else {
+                    continue;
+                };
+                if !specs.should_relax(&environment_name, &platform, package) {
+                    continue;
+                }
+                match package {
+                    LockedPackageRef::Conda(data) => constraints.add_conda(
+                        environment_name.clone(),
+                        platform,
+                        data.record().name.clone(),
+                        granularity.conda_spec(&data.record().version)?,
+                    ),
+                    LockedPackageRef::Pypi(data, _) => constraints.add_pypi(
+                        environment_name.clone(),
+                        platform,
+                        data.name.clone(),
+                        granularity.pypi_specifiers(&data.version)?,
+                    ),
+                }
+            }
+        }
+    }
+
+    Ok(constraints)
+}
+
+/// Constrains a package to the version and build that is locked.
+fn pin_locked_package(
+    constraints: &mut SolveConstraints,
+    environment_name: &EnvironmentName,
+    platform: Platform,
+    package: LockedPackageRef<'_>,
+) -> miette::Result<()> {
+    match package {
+        LockedPackageRef::Conda(data) => {
+            let record = data.record();
+            let spec = NamelessMatchSpec::from_str(
+                &format!("=={} {}", record.version, record.build),
+                ParseStrictness::Strict,
+            )
+            .into_diagnostic()?;
+            constraints.add_conda(
+                environment_name.clone(),
+                platform,
+                record.name.clone(),
+                spec,
+            );
+        }
+        LockedPackageRef::Pypi(data, _) => constraints.add_pypi(
+            environment_name.clone(),
+            platform,
+            data.name.clone(),
+            pep440_rs::VersionSpecifiers::from(pep440_rs::VersionSpecifier::equals_version(
+                data.version.clone(),
+            )),
+        ),
+    }
+    Ok(())
+}
+
+/// Tells the user which of the requested packages have no newer version
+/// within the requested granularity, instead of silently keeping them.
+fn report_packages_without_update(
+    previous: &LockFile,
+    current: &LockFile,
+    specs: &UpdateSpecs,
+) -> miette::Result<()> {
+    let (Some(granularity), Some(packages)) = (specs.granularity, &specs.packages) else {
+        return Ok(());
+    };
+
+    fn locked_version(package: LockedPackageRef<'_>) -> String {
+        match package {
+            LockedPackageRef::Conda(data) => data.record().version.to_string(),
+            LockedPackageRef::Pypi(data, _) => data.version.to_string(),
+        }
+    }
+
+    for package_name in packages.iter().sorted() {
+        let mut locked_versions = BTreeSet::new();
+        let mut updated = false;
+        for (environment_name, environment) in previous.environments() {
+            let name = EnvironmentName::from_str(environment_name).into_diagnostic()?;
+            for (platform, locked_packages) in environment.packages_by_platform() {
+                for package in locked_packages.filter(|package| package.name() == package_name) {
+                    if !specs.should_relax(&name, &platform, package) {
+                        continue;
+                    }
+                    let version = locked_version(package);
+                    let current_version = current
+                        .environment(environment_name)
+                        .and_then(|environment| environment.packages(platform))
+                        .and_then(|mut packages| {
+                            packages.find(|package| package.name() == package_name)
+                        })
+                        .map(locked_version);
+                    updated |= current_version.as_ref() != Some(&version);
+                    locked_versions.insert(version);
+                }
+            }
+        }
+
+        if !updated && !locked_versions.is_empty() {
+            eprintln!(
+                "{}No newer {} release of '{}' is available than {}",
+                console::style(console::Emoji("ℹ️ ", "")).yellow(),
+                granularity,
+                package_name,
+                locked_versions.iter().format(", ")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the requested pypi packages that are locked from a git repository.
+/// A package that follows a branch is re-locked by the solve to the commit the
+/// branch currently points to. A package that is pinned to a tag or a revision
+/// can't move, so it is removed from the packages to update.
+fn check_git_packages(lock_file: &LockFile, specs: &mut UpdateSpecs) -> miette::Result<()> {
+    let Some(packages) = &specs.packages else {
+        return Ok(());
+    };
+
+    // The requested names are matched with the normalized names of the lock-file
+    let requested: HashMap<pep508_rs::PackageName, &String> = packages
+        .iter()
+        .filter_map(|package| {
+            pep508_rs::PackageName::from_str(package)
+                .ok()
+                .map(|name| (name, package))
+        })
+        .collect();
+
+    let mut locked_git_urls: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (environment_name, environment) in lock_file.environments() {
+        let environment_name = EnvironmentName::from_str(environment_name).into_diagnostic()?;
+        for (platform, locked_packages) in environment.packages_by_platform() {
+            if !specs.is_selected(&environment_name, &platform) {
+                continue;
+            }
+            for package in locked_packages {
+                let LockedPackageRef::Pypi(data, _) = package else {
+                    continue;
+                };
+                let Some(requested_name) = requested.get(&data.name) else {
+                    continue;
+                };
+                let UrlOrPath::Url(url) = &data.location else {
+                    continue;
+                };
+                if !is_direct_url(url.scheme()) {
+                    continue;
+                }
+                let url = strip_direct_scheme(url);
+                if LockedGitUrl::is_locked_git_url(&url) {
+                    locked_git_urls
+                        .entry(requested_name.to_string())
+                        .or_default()
+                        .insert(url.to_string());
+                }
+            }
+        }
+    }
+
+    let mut pinned_packages = Vec::new();
+    for (package_name, urls) in locked_git_urls {
+        let mut follows_branch = false;
+        let mut pins = BTreeSet::new();
+        for url in urls {
+            let spec = LockedGitUrl::parse(&url)?.to_pinned_git_spec()?;
+            match &spec.source.reference {
+                GitReference::Tag(tag) => {
+                    pins.insert(format!("tag '{tag}'"));
+                }
+                GitReference::Rev(rev) => {
+                    pins.insert(format!("revision '{rev}'"));
+                }
+                GitReference::Branch(_) | GitReference::DefaultBranch => follows_branch = true,
+            }
+        }
+
+        if !follows_branch {
+            eprintln!(
+                "{}'{}' is pinned to {} and is not updated",
+                console::style(console::Emoji("ℹ️ ", "")).yellow(),
+                package_name,
+                pins.iter().format(", ")
+            );
+            pinned_packages.push(package_name);
+        }
+    }
+
+    if let Some(packages) = &mut specs.packages {
+        for package_name in pinned_packages {
+            packages.remove(&package_name);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conda_spec() {
+        let spec = |granularity: UpdateGranularity, locked: &str| {
+            let locked = Version::from_str(locked).unwrap();
+            granularity.conda_spec(&locked).unwrap().to_string()
+        };
+        assert_eq!(spec(UpdateGranularity::Major, "1.2.3"), ">=1.2.3");
+        assert_eq!(spec(UpdateGranularity::Minor, "1.2.3"), ">=1.2.3,<2");
+        assert_eq!(spec(UpdateGranularity::Patch, "1.2.3"), ">=1.2.3,<1.3");
+        assert_eq!(spec(UpdateGranularity::Patch, "1"), ">=1,<1.1");
+        assert_eq!(
+            spec(UpdateGranularity::Patch, "1!2.3.4"),
+            ">=1!2.3.4,<1!2.4"
+        );
+        assert_eq!(
+            spec(UpdateGranularity::Patch, "2.3.4+cuda"),
+            ">=2.3.4+cuda,<2.4"
+        );
+
+        // The spec matches the newer patch releases, but not the minor release
+        let matches = |locked: &str, candidate: &str| {
+            let spec = UpdateGranularity::Patch
+                .conda_spec(&Version::from_str(locked).unwrap())
+                .unwrap();
+            spec.version
+                .unwrap()
+                .matches(&Version::from_str(candidate).unwrap())
+        };
+        assert!(matches("1", "1.0.5"));
+        assert!(!matches("1", "1.1"));
+        assert!(matches("1!2.3.4", "1!2.3.5"));
+        assert!(!matches("1!2.3.4", "2.3.5"));
+        assert!(matches("2.3.4+cuda", "2.3.5+cuda"));
+        assert!(!matches("2.3.4+cuda", "2.4.0+cuda"));
+    }
+
+    /// Builds a lock-file for linux-64 with the given conda packages,
+    /// specified as `(file name, dependencies)`.
+    fn conda_lock_file(packages: &[(&str, &[&str])]) -> LockFile {
+        let url = |file_name: &str| {
+            format!("https://conda.anaconda.org/conda-forge/linux-64/{file_name}")
+        };
+        let mut lock = String::from(
+            "version: 6\nenvironments:\n  default:\n    channels:\n    - url: https://conda.anaconda.org/conda-forge/\n    packages:\n      linux-64:\n",
+        );
+        for (file_name, _) in packages {
+            lock.push_str(&format!("      - conda: {}\n", url(file_name)));
+        }
+        lock.push_str("packages:\n");
+        for (file_name, depends) in packages {
+            lock.push_str(&format!("- conda: {}\n", url(file_name)));
+            if !depends.is_empty() {
+                lock.push_str("  depends:\n");
+                for depend in *depends {
+                    lock.push_str(&format!("  - {depend}\n"));
+                }
+            }
+        }
+        LockFile::from_str(&lock).unwrap()
+    }
+
+    #[test]
+    fn test_select_changes() {
+        // `app` moves from `old-dep` to `new-dep`, `lib` is updated on its own
+        let previous = conda_lock_file(&[
+            ("app-1-h0_0.conda", &["old-dep >=1", "lib"]),
+            ("old-dep-1-h0_0.conda", &[]),
+            ("lib-1-h0_0.conda", &[]),
+        ]);
+        let updated = conda_lock_file(&[
+            ("app-2-h0_0.conda", &["new-dep", "lib"]),
+            ("new-dep-1-h0_0.conda", &["new-dep-base"]),
+            ("new-dep-base-1-h0_0.conda", &[]),
+            ("lib-2-h0_0.conda", &[]),
+        ]);
+        let diff = LockFileDiff::from_lock_files(&previous, &updated);
+        let changes = selectable_changes(&previous, &updated, &diff);
+
+        let offered = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.name.as_str(),
+                    change.versions.iter().join(", "),
+                    change.pins.iter().join(", "),
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            offered,
+            vec![
+                ("app", "1 h0_0 -> 2 h0_0".to_string(), "app".to_string()),
+                ("lib", "1 h0_0 -> 2 h0_0".to_string(), "lib".to_string()),
+                ("new-dep", "added 1 h0_0".to_string(), "app".to_string()),
+                (
+                    "new-dep-base",
+                    "added 1 h0_0".to_string(),
+                    "app".to_string()
+                ),
+                ("old-dep", "removed 1 h0_0".to_string(), "app".to_string()),
+            ]
+        );
+
+        let pinned = |selected: &[usize]| {
+            deselected_pins(&changes, selected)
+                .unwrap()
+                .into_iter()
+                .map(|(environment, name)| format!("{}:{name}", environment.as_str()))
+                .sorted()
+                .collect_vec()
+        };
+        // Everything is selected, nothing is pinned
+        assert!(pinned(&[0, 1, 2, 3, 4]).is_empty());
+        // Not adding `new-dep` keeps `app` at its locked version
+        assert_eq!(pinned(&[0, 1, 3, 4]), vec!["default:app"]);
+        // Not removing `old-dep` keeps `app` as well, `lib` is still updated
+        assert_eq!(pinned(&[0, 1, 2, 3]), vec!["default:app"]);
+        // Not updating `lib` only pins `lib`
+        assert_eq!(pinned(&[0, 2, 3, 4]), vec!["default:lib"]);
+    }
+
+    #[test]
+    fn test_pypi_specifiers() {
+        let specifiers = |granularity: UpdateGranularity, locked: &str| {
+            let locked = pep440_rs::Version::from_str(locked).unwrap();
+            granularity.pypi_specifiers(&locked).unwrap().to_string()
+        };
+        assert_eq!(specifiers(UpdateGranularity::Major, "1.2.3"), ">=1.2.3");
+        assert_eq!(
+            specifiers(UpdateGranularity::Minor, "1.2.3"),
+            ">=1.2.3, ==1.*"
+        );
+        assert_eq!(specifiers(UpdateGranularity::Patch, "1"), ">=1, ==1.0.*");
+        assert_eq!(
+            specifiers(UpdateGranularity::Patch, "1!2.3.4"),
+            ">=1!2.3.4, ==1!2.3.*"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::cmp::Ordering;
+use std::{borrow::Cow, cmp::Ordering};
 
 use super::cli_config::{LockFileUpdateConfig, PrefixUpdateConfig};
 use crate::{
@@ -12,16 +12,23 @@ use fancy_display::FancyDisplay;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use miette::{Context, IntoDiagnostic, MietteDiagnostic};
-use pep508_rs::{MarkerTree, Requirement};
-use pixi_config::ConfigCli;
-use pixi_manifest::{FeatureName, SpecType};
-use pixi_pypi_spec::PixiPypiSpec;
+use pep440_rs::{Operator, VersionSpecifiers};
+use pep508_rs::{MarkerTree, Requirement, VersionOrUrl};
+use pixi_config::{ConfigCli, ConfigCliNetwork};
+use pixi_manifest::{
+    DependencyOverwriteBehavior, Feature, FeatureName, PypiDependencyLocation, SpecType,
+};
+use pixi_pypi_spec::{PixiPypiSpec, PypiPackageName, VersionOrStar};
 use pixi_spec::PixiSpec;
-use rattler_conda_types::{MatchSpec, StringMatcher};
+use rattler_conda_types::{
+    MatchSpec, PackageName, StringMatcher, VersionSpec,
+    version_spec::{LogicalOperator, RangeOperator},
+};
 
 /// Checks if there are newer versions of the dependencies and upgrades them in the lockfile and manifest file.
 ///
 /// `pixi upgrade` loosens the requirements for the given packages, updates the lock file and the adapts the manifest accordingly.
+/// The upper bounds of the specs are derived with the pinning strategy, lower bounds that are still satisfied by the new versions are kept.
 #[derive(Parser, Debug, Default)]
 pub struct Args {
     #[clap(flatten)]
@@ -35,6 +42,8 @@ pub struct Args {
 
     #[clap(flatten)]
     config: ConfigCli,
+    #[clap(flatten)]
+    network_config: ConfigCliNetwork,
 
     #[clap(flatten)]
     pub specs: UpgradeSpecsArgs,
@@ -61,13 +70,22 @@ pub struct UpgradeSpecsArgs {
     /// The packages which should be excluded
     #[clap(long, conflicts_with = "packages")]
     pub exclude: Option<Vec<String>>,
+
+    /// Also upgrade the requirements in `[project.dependencies]` of a
+    /// `pyproject.toml` manifest
+    #[clap(long)]
+    pub pypi: bool,
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
     let workspace = WorkspaceLocator::for_cli()
         .with_search_start(args.workspace_config.workspace_locator_start())
         .locate()?
-        .with_cli_config(args.config.clone());
+        .with_cli_config(
+            args.network_config
+                .clone()
+                .merge_config(args.config.clone().into()),
+        );
 
     let mut workspace = workspace.modify()?;
 
@@ -84,35 +102,57 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         )
     };
 
+    let original_feature = feature.clone();
     let (match_specs, pypi_deps) = parse_specs(feature, &args, &workspace)?;
+    let conda_packages = match_specs.keys().cloned().collect_vec();
+    let pypi_packages = pypi_deps
+        .iter()
+        .map(|(name, (req, _, location))| (name.clone(), location.clone(), req.marker.clone()))
+        .collect_vec();
 
-    let (update_deps, workspace) = match workspace
+    let update_deps = workspace
         .update_dependencies(
             match_specs,
             pypi_deps,
             IndexMap::default(),
             &args.prefix_update_config,
             &args.lock_file_update_config,
-            &args.specs.feature,
+            std::slice::from_ref(&args.specs.feature),
             &[],
             false,
             args.dry_run,
         )
-        .await
-    {
-        Ok(update_deps) => (
-            update_deps,
-            if args.dry_run {
-                workspace.revert().await.into_diagnostic()?
-            } else {
-                workspace.save().await.into_diagnostic()?
-            },
-        ),
-        Err(e) => {
-            return Err(e);
-        }
+        .await?;
+    let spec_changes = preserve_lower_bounds(
+        &mut workspace,
+        &original_feature,
+        &args.specs.feature,
+        &conda_packages,
+        &pypi_packages,
+    )?;
+    let workspace = if args.dry_run {
+        workspace.revert().await.into_diagnostic()?
+    } else {
+        workspace.save().await.into_diagnostic()?
     };
 
+    if !args.json {
+        let operation = if args.dry_run {
+            "Would upgrade"
+        } else {
+            "Upgraded"
+        };
+        for change in &spec_changes {
+            eprintln!(
+                "{}{operation} {} {} -> {}",
+                console::style(console::Emoji("✔ ", "")).green(),
+                console::style(&change.name).bold(),
+                console::style(&change.before).dim(),
+                change.after,
+            );
+        }
+    }
+
     // Is there something to report?
     if let Some(update_deps) = update_deps {
         let diff = update_deps.lock_file_diff;
@@ -267,7 +307,6 @@ pub fn parse_specs(
                 Requirement {
                     name: name.as_normalized().clone(),
                     extras: extras.clone(),
-                    // TODO: Add marker support here to avoid overwriting existing markers
                     marker: MarkerTree::default(),
                     origin: None,
                     version_or_url: None,
@@ -287,19 +326,268 @@ pub fn parse_specs(
             )),
             _ => None,
         })
-        .map(|(name, req, pixi_req)| {
+        .map(|(name, mut req, pixi_req)| {
             let location = workspace.document().pypi_dependency_location(
                 &name,
                 None, // TODO: add support for platforms
                 &args.specs.feature,
             );
+            // Keep the markers of requirements that are written as PEP 508
+            // strings, the upgraded requirement replaces the whole string.
+            if let Some(original) = location.as_ref().and_then(|location| {
+                workspace
+                    .document()
+                    .pep508_requirement(&name, &args.specs.feature, location)
+            }) {
+                req.marker = original.marker;
+            }
             (name, (req, Some(pixi_req), location))
         })
+        // The requirements in `[project.dependencies]` are also used by other
+        // tools, so only upgrade them when explicitly requested
+        .filter(|(name, (_, _, location))| {
+            if args.specs.pypi || location != &Some(PypiDependencyLocation::Dependencies) {
+                return true;
+            }
+            tracing::debug!(
+                "skipping {} in [project.dependencies], use --pypi to upgrade it",
+                name.as_source()
+            );
+            false
+        })
         .collect();
 
     Ok((match_specs, pypi_deps))
 }
 
+/// A dependency spec in the manifest that was changed by the upgrade.
+pub struct SpecChange {
+    pub name: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Keeps the lower bounds that the user wrote for the upgraded dependencies,
+/// as long as the newly locked version still satisfies them. The upper bounds
+/// are taken from the spec that was derived with the pinning strategy.
+///
+/// Returns the dependencies whose spec in the manifest changed.
+pub fn preserve_lower_bounds(
+    workspace: &mut WorkspaceMut,
+    original: &Feature,
+    feature_name: &FeatureName,
+    conda_packages: &[PackageName],
+    pypi_packages: &[(PypiPackageName, Option<PypiDependencyLocation>, MarkerTree)],
+) -> miette::Result<Vec<SpecChange>> {
+    let original_conda = original
+        .dependencies(SpecType::Run, None)
+        .map(Cow::into_owned)
+        .unwrap_or_default();
+    let original_pypi = original
+        .pypi_dependencies(None)
+        .map(Cow::into_owned)
+        .unwrap_or_default();
+    let Some(feature) = workspace.workspace().workspace.value.feature(feature_name) else {
+        return Ok(Vec::new());
+    };
+    let upgraded_conda = feature
+        .dependencies(SpecType::Run, None)
+        .map(Cow::into_owned)
+        .unwrap_or_default();
+    let upgraded_pypi = feature
+        .pypi_dependencies(None)
+        .map(Cow::into_owned)
+        .unwrap_or_default();
+
+    let mut changes = Vec::new();
+    for name in conda_packages {
+        let (Some(before), Some(after)) = (original_conda.get(name), upgraded_conda.get(name))
+        else {
+            continue;
+        };
+        let before_version = before
+            .as_version_spec()
+            .cloned()
+            .unwrap_or(VersionSpec::Any);
+        let mut after_version = after.as_version_spec().cloned().unwrap_or(VersionSpec::Any);
+        if let Some(merged) = merge_conda_lower_bounds(&before_version, &after_version) {
+            let spec = match after.clone() {
+                PixiSpec::DetailedVersion(mut detailed) => {
+                    detailed.version = Some(merged.clone());
+                    PixiSpec::DetailedVersion(detailed)
+                }
+                _ => PixiSpec::Version(merged.clone()),
+            };
+            workspace.manifest().add_dependency(
+                name,
+                &spec,
+                SpecType::Run,
+                &[],
+                feature_name,
+                DependencyOverwriteBehavior::Overwrite,
+            )?;
+            after_version = merged;
+        }
+        if before_version != after_version {
+            changes.push(SpecChange {
+                name: name.as_source().to_string(),
+                before: before_version.to_string(),
+                after: after_version.to_string(),
+            });
+        }
+    }
+
+    for (name, location, marker) in pypi_packages {
+        let (Some(before), Some(after)) = (original_pypi.get(name), upgraded_pypi.get(name)) else {
+            continue;
+        };
+        let (Some(before_version), Some(after_version)) =
+            (pypi_version_spec(before), pypi_version_spec(after))
+        else {
+            continue;
+        };
+        let mut after_spec = after_version.to_string();
+        if let Some(merged) = merge_pypi_lower_bounds(before_version, after_version) {
+            let requirement = Requirement {
+                name: name.as_normalized().clone(),
+                extras: after.extras().to_vec(),
+                marker: marker.clone(),
+                origin: None,
+                version_or_url: Some(VersionOrUrl::VersionSpecifier(merged.clone())),
+            };
+            workspace.manifest().add_pep508_dependency(
+                (&requirement, Some(after)),
+                &[],
+                feature_name,
+                None,
+                DependencyOverwriteBehavior::Overwrite,
+                location.as_ref(),
+            )?;
+            after_spec = merged.to_string();
+        }
+        if before_version.to_string() != after_spec {
+            changes.push(SpecChange {
+                name: name.as_source().to_string(),
+                before: before_version.to_string(),
+                after: after_spec,
+            });
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Returns the version requirement of a pypi spec that only requires a
+/// version.
+fn pypi_version_spec(spec: &PixiPypiSpec) -> Option<&VersionOrStar> {
+    match spec {
+        PixiPypiSpec::Version { version, .. } | PixiPypiSpec::RawVersion(version) => Some(version),
+        _ => None,
+    }
+}
+
+/// Returns the lower bounds of a conda version spec.
+fn conda_lower_bounds(spec: &VersionSpec) -> Vec<VersionSpec> {
+    let specs = match spec {
+        VersionSpec::Group(LogicalOperator::And, specs) => specs.as_slice(),
+        spec => std::slice::from_ref(spec),
+    };
+    specs
+        .iter()
+        .filter(|spec| {
+            matches!(
+                spec,
+                VersionSpec::Range(RangeOperator::Greater | RangeOperator::GreaterEquals, _)
+            )
+        })
+        .cloned()
+        .collect_vec()
+}
+
+/// Combines the lower bounds of the original spec with the upper bounds of
+/// the upgraded spec. Returns `None` if the upgraded spec should be used as
+/// is, because the original spec has no lower bounds or the newly locked
+/// version no longer satisfies them.
+fn merge_conda_lower_bounds(original: &VersionSpec, upgraded: &VersionSpec) -> Option<VersionSpec> {
+    let lower_bounds = conda_lower_bounds(original);
+    let upgraded_specs = match upgraded {
+        VersionSpec::Group(LogicalOperator::And, specs) => specs.as_slice(),
+        spec => std::slice::from_ref(spec),
+    };
+
+    // The pinning strategy starts the range at the locked version
+    let locked = upgraded_specs.iter().find_map(|spec| match spec {
+        VersionSpec::Range(RangeOperator::GreaterEquals, version) => Some(version),
+        _ => None,
+    })?;
+    if lower_bounds.is_empty() || !lower_bounds.iter().all(|bound| bound.matches(locked)) {
+        return None;
+    }
+
+    let mut specs = lower_bounds;
+    specs.extend(
+        upgraded_specs
+            .iter()
+            .filter(|spec| {
+                matches!(
+                    spec,
+                    VersionSpec::Range(RangeOperator::Less | RangeOperator::LessEquals, _)
+                )
+            })
+            .cloned(),
+    );
+    let merged = match specs.len() {
+        1 => specs.into_iter().next()?,
+        _ => VersionSpec::Group(LogicalOperator::And, specs),
+    };
+    (&merged != upgraded).then_some(merged)
+}
+
+/// Combines the lower bounds of the original pypi requirement with the upper
+/// bounds of the upgraded requirement, see [`merge_conda_lower_bounds`].
+fn merge_pypi_lower_bounds(
+    original: &VersionOrStar,
+    upgraded: &VersionOrStar,
+) -> Option<VersionSpecifiers> {
+    let (VersionOrStar::Version(original), VersionOrStar::Version(upgraded)) = (original, upgraded)
+    else {
+        return None;
+    };
+    let lower_bounds = original
+        .iter()
+        .filter(|specifier| {
+            matches!(
+                specifier.operator(),
+                Operator::GreaterThan | Operator::GreaterThanEqual
+            )
+        })
+        .cloned()
+        .collect_vec();
+
+    // The pinning strategy starts the range at the locked version
+    let locked = upgraded.iter().find_map(|specifier| {
+        (specifier.operator() == &Operator::GreaterThanEqual).then_some(specifier.version())
+    })?;
+    if lower_bounds.is_empty()
+        || !lower_bounds
+            .iter()
+            .all(|specifier| specifier.contains(locked))
+    {
+        return None;
+    }
+
+    let merged = lower_bounds
+        .into_iter()
+        .chain(upgraded.iter().cloned().filter(|specifier| {
+            matches!(
+                specifier.operator(),
+                Operator::LessThan | Operator::LessThanEqual
+            )
+        }))
+        .collect::<VersionSpecifiers>();
+    (&merged != upgraded).then_some(merged)
+}
+
 /// Ensures the existence of the specified package
 ///
 /// # Returns
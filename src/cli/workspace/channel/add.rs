@@ -1,7 +1,7 @@
 use crate::{
     UpdateLockFileOptions, WorkspaceLocator,
     environment::{LockFileUsage, get_update_lock_file_and_prefix},
-    lock_file::{ReinstallPackages, UpdateMode},
+    lock_file::{InstallFilter, ReinstallPackages, UpdateMode},
 };
 use miette::IntoDiagnostic;
 
@@ -30,8 +30,10 @@ pub async fn execute(args: AddRemoveArgs) -> miette::Result<()> {
             no_install: args.prefix_update_config.no_install
                 && args.lock_file_update_config.no_lockfile_update,
             max_concurrent_solves: workspace.workspace().config().max_concurrent_solves(),
+            no_prune: false,
         },
         ReinstallPackages::default(),
+        InstallFilter::default(),
     )
     .await?;
 
@@ -45,7 +45,32 @@ fn format_pip_dependency(name: &PypiPackageName, requirement: &PixiPypiSpec) ->
     match requirement {
         PixiPypiSpec::Git {
             url: git_url,
+            editable: Some(true),
             extras,
+        } => {
+            // pip only supports editable VCS installs with an `#egg=` fragment
+            let mut git_string = format!("-e git+{url}", url = git_url.git);
+
+            if let Some(Some(rev)) = git_url.rev.as_ref().map(|rev| rev.reference()) {
+                git_string.push_str(&format!("@{rev}"));
+            }
+
+            git_string.push_str(&format!(
+                "#egg={name}{extras}",
+                name = name.as_normalized(),
+                extras = format_pip_extras(extras),
+            ));
+
+            if let Some(ref subdirectory) = git_url.subdirectory {
+                git_string.push_str(&format!("&subdirectory={subdirectory}"));
+            }
+
+            git_string
+        }
+        PixiPypiSpec::Git {
+            url: git_url,
+            extras,
+            ..
         } => {
             let mut git_string = format!(
                 "{name}{extras} @ git+{url}",
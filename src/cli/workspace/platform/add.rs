@@ -1,19 +1,18 @@
-use std::str::FromStr;
-
 use clap::Parser;
 use miette::IntoDiagnostic;
 use pixi_manifest::FeatureName;
-use rattler_conda_types::Platform;
 
 use crate::{
     UpdateLockFileOptions, Workspace,
+    cli::cli_config::parse_platforms,
     environment::{LockFileUsage, get_update_lock_file_and_prefix},
-    lock_file::{ReinstallPackages, UpdateMode},
+    lock_file::{InstallFilter, ReinstallPackages, UpdateMode},
 };
 
 #[derive(Parser, Debug, Default)]
 pub struct Args {
-    /// The platform name(s) to add.
+    /// The platform name(s) to add, `linux`, `macos`, `windows` and `all` are
+    /// accepted as shorthands.
     #[clap(required = true, num_args=1..)]
     pub platform: Vec<String>,
 
@@ -33,12 +32,7 @@ pub async fn execute(workspace: Workspace, args: Args) -> miette::Result<()> {
         .map_or_else(FeatureName::default, FeatureName::from);
 
     // Determine which platforms are missing
-    let platforms = args
-        .platform
-        .into_iter()
-        .map(|platform_str| Platform::from_str(&platform_str))
-        .collect::<Result<Vec<_>, _>>()
-        .into_diagnostic()?;
+    let platforms = parse_platforms(&args.platform)?;
 
     let mut workspace = workspace.modify()?;
 
@@ -55,8 +49,10 @@ pub async fn execute(workspace: Workspace, args: Args) -> miette::Result<()> {
             lock_file_usage: LockFileUsage::Update,
             no_install: args.no_install,
             max_concurrent_solves: workspace.workspace().config().max_concurrent_solves(),
+            no_prune: false,
         },
         ReinstallPackages::default(),
+        InstallFilter::default(),
     )
     .await?;
     workspace.save().await.into_diagnostic()?;
@@ -6,7 +6,7 @@ use rattler_conda_types::Platform;
 use crate::{
     UpdateLockFileOptions, Workspace,
     environment::{LockFileUsage, get_update_lock_file_and_prefix},
-    lock_file::{ReinstallPackages, UpdateMode},
+    lock_file::{InstallFilter, ReinstallPackages, UpdateMode},
 };
 
 #[derive(Parser, Debug, Default)]
@@ -44,8 +44,10 @@ pub async fn execute(workspace: Workspace, args: Args) -> miette::Result<()> {
             lock_file_usage: LockFileUsage::Update,
             no_install: args.no_install,
             max_concurrent_solves: workspace.workspace().config().max_concurrent_solves(),
+            no_prune: false,
         },
         ReinstallPackages::default(),
+        InstallFilter::default(),
     )
     .await?;
     workspace.save().await.into_diagnostic()?;
@@ -1,20 +1,68 @@
 use std::{
     collections::HashSet,
+    fmt::{Display, Formatter},
     io::{Write, stderr},
+    path::Path,
 };
 
 use ahash::HashMap;
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use itertools::{Either, Itertools};
 use pixi_consts::consts;
-use pixi_manifest::FeaturesExt;
-use rattler_conda_types::Platform;
-use rattler_lock::{LockFile, LockedPackage, LockedPackageRef};
+use pixi_git::sha::GitSha;
+use pixi_manifest::{FeaturesExt, pypi::pypi_options::PypiOptions};
+use pixi_pypi_spec::PixiPypiSpec;
+use pixi_record::LockedGitUrl;
+use rattler_conda_types::{PackageName, PackageRecord, Platform};
+use rattler_lock::{
+    CondaPackageData, LockFile, LockedPackage, LockedPackageRef, PypiPackageData, UrlOrPath,
+};
 use serde::Serialize;
 use serde_json::Value;
 use tabwriter::TabWriter;
+use url::Url;
+use uv_distribution_filename::DistExtension;
+
+use crate::{
+    Workspace,
+    install_pypi::utils::{is_direct_url, strip_direct_scheme},
+};
+
+/// The names of the packages that are listed in the dependency tables of the
+/// features of an environment, as opposed to the packages that are only
+/// pulled in by other packages.
+#[derive(Default)]
+struct DirectDependencies {
+    conda: HashSet<PackageName>,
+    pypi: HashSet<pep508_rs::PackageName>,
+}
+
+impl DirectDependencies {
+    fn new(workspace: &Workspace, environment_name: &str, platform: Platform) -> Self {
+        let Some(environment) = workspace.environment(environment_name) else {
+            return Self::default();
+        };
+        Self {
+            conda: environment
+                .combined_dependencies(Some(platform))
+                .names()
+                .cloned()
+                .collect(),
+            pypi: environment
+                .pypi_dependencies(Some(platform))
+                .names()
+                .map(|name| name.as_normalized().clone())
+                .collect(),
+        }
+    }
 
-use crate::Workspace;
+    fn contains(&self, package: &LockedPackage) -> bool {
+        match package {
+            LockedPackage::Conda(p) => self.conda.contains(&p.record().name),
+            LockedPackage::Pypi(p, _) => self.pypi.contains(&p.name),
+        }
+    }
+}
 
 // Represents the differences between two sets of packages.
 #[derive(Default, Clone)]
@@ -79,7 +127,10 @@ impl LockFileDiff {
                         LockedPackageRef::Conda(data) => {
                             let name = &data.record().name;
                             match previous_conda_packages.remove(name) {
-                                Some(previous) if previous.location() != data.location() => {
+                                Some(previous)
+                                    if previous.location() != data.location()
+                                        || metadata_changed(previous.record(), data.record()) =>
+                                {
                                     diff.changed
                                         .push((previous.clone().into(), data.clone().into()));
                                 }
@@ -93,7 +144,8 @@ impl LockFileDiff {
                             let name = &data.name;
                             match previous_pypi_packages.remove(name) {
                                 Some((previous_data, previous_env))
-                                    if previous_data.location != data.location =>
+                                    if previous_data.location != data.location
+                                        || previous_data.version != data.version =>
                                 {
                                     diff.changed.push((
                                         (previous_data.clone(), previous_env.clone()).into(),
@@ -177,36 +229,79 @@ impl LockFileDiff {
     // Format the lock-file diff.
     pub fn print(&self) -> std::io::Result<()> {
         let mut writer = TabWriter::new(stderr());
-        for (idx, (environment_name, environment)) in self
-            .environment
-            .iter()
-            .sorted_by(|(a, _), (b, _)| a.cmp(b))
-            .enumerate()
+        self.write(&mut writer, None, false)?;
+        writer.flush()
+    }
+
+    /// Prints the lock-file diff with the changes to the direct dependencies
+    /// of the environments in a separate section, before the changes to the
+    /// other packages. If `direct_only` is set, only the changes to the
+    /// direct dependencies are printed.
+    pub fn print_by_dependency_kind(
+        &self,
+        workspace: &Workspace,
+        direct_only: bool,
+    ) -> std::io::Result<()> {
+        let mut writer = TabWriter::new(stderr());
+        self.write(&mut writer, Some(workspace), direct_only)?;
+        writer.flush()
+    }
+
+    /// Writes the lock-file diff. If a workspace is given, the changes to the
+    /// direct dependencies of an environment are written in a separate
+    /// section before the changes to the transitive dependencies.
+    pub fn write(
+        &self,
+        writer: &mut impl Write,
+        workspace: Option<&Workspace>,
+        direct_only: bool,
+    ) -> std::io::Result<()> {
+        let mut written_environments = 0;
+        for (environment_name, environment) in
+            self.environment.iter().sorted_by(|(a, _), (b, _)| a.cmp(b))
         {
-            // Find the changes that happened in all platforms.
-            let changes_by_platform = environment
-                .into_iter()
-                .map(|(platform, packages)| {
-                    let changes = Self::format_changes(packages)
-                        .into_iter()
-                        .collect::<HashSet<_>>();
-                    (platform, changes)
-                })
-                .collect::<Vec<_>>();
-
-            // Find the changes that happened in all platforms.
-            let common_changes = changes_by_platform
-                .iter()
-                .fold(None, |acc, (_, changes)| match acc {
-                    None => Some(changes.clone()),
-                    Some(acc) => Some(acc.intersection(changes).cloned().collect()),
-                })
-                .unwrap_or_default();
+            let sections = match workspace {
+                None => vec![(None, Self::changes_by_platform(environment, |_, _| true))],
+                Some(workspace) => {
+                    let direct_dependencies = environment
+                        .keys()
+                        .map(|platform| {
+                            (
+                                *platform,
+                                DirectDependencies::new(workspace, environment_name, *platform),
+                            )
+                        })
+                        .collect::<HashMap<_, _>>();
+                    let is_direct = |platform: Platform, package: &LockedPackage| {
+                        direct_dependencies
+                            .get(&platform)
+                            .is_some_and(|direct| direct.contains(package))
+                    };
+
+                    let mut sections = vec![(
+                        Some("Direct dependencies"),
+                        Self::changes_by_platform(environment, is_direct),
+                    )];
+                    if !direct_only {
+                        sections.push((
+                            Some("Transitive dependencies"),
+                            Self::changes_by_platform(environment, |platform, package| {
+                                !is_direct(platform, package)
+                            }),
+                        ));
+                    }
+                    sections
+                }
+            };
+            if workspace.is_some() && sections.iter().all(|(_, changes)| changes.is_empty()) {
+                continue;
+            }
 
             // Add a new line between environments
-            if idx > 0 {
+            if written_environments > 0 {
                 writeln!(writer, "\t\t\t",)?;
             }
+            written_environments += 1;
 
             writeln!(
                 writer,
@@ -215,39 +310,87 @@ impl LockFileDiff {
                 consts::ENVIRONMENT_STYLE.apply_to(environment_name)
             )?;
 
-            // Print the common changes.
-            for (_, line) in common_changes.iter().sorted_by_key(|(name, _)| name) {
-                writeln!(writer, "  {}", line)?;
+            for (section, changes_by_platform) in sections {
+                if changes_by_platform.is_empty() {
+                    continue;
+                }
+                if let Some(section) = section {
+                    writeln!(writer, "{}:\t\t\t", console::style(section).bold())?;
+                }
+                Self::write_changes(writer, environment_name, changes_by_platform)?;
             }
+        }
 
-            // Print the per-platform changes.
-            for (platform, changes) in changes_by_platform {
-                let mut changes = changes
-                    .iter()
-                    .filter(|change| !common_changes.contains(change))
-                    .sorted_by_key(|(name, _)| name)
-                    .peekable();
-                if changes.peek().is_some() {
-                    writeln!(
-                        writer,
-                        "{}: {}:{}\t\t\t",
-                        console::style("Platform").underlined(),
-                        consts::ENVIRONMENT_STYLE.apply_to(environment_name),
-                        consts::PLATFORM_STYLE.apply_to(platform),
-                    )?;
-                    for (_, line) in changes {
-                        writeln!(writer, "  {}", line)?;
-                    }
+        Ok(())
+    }
+
+    /// Returns the formatted changes of the packages for which `include`
+    /// returns true, per platform. Platforms without changes are left out.
+    fn changes_by_platform(
+        environment: &IndexMap<Platform, PackagesDiff>,
+        include: impl Fn(Platform, &LockedPackage) -> bool,
+    ) -> Vec<(Platform, HashSet<(&str, String)>)> {
+        environment
+            .iter()
+            .map(|(platform, packages)| {
+                let changes = Self::format_changes(packages, |package| include(*platform, package))
+                    .into_iter()
+                    .collect::<HashSet<_>>();
+                (*platform, changes)
+            })
+            .filter(|(_, changes)| !changes.is_empty())
+            .collect()
+    }
+
+    /// Writes the changes that happened in all platforms, followed by the
+    /// changes of the individual platforms.
+    fn write_changes(
+        writer: &mut impl Write,
+        environment_name: &str,
+        changes_by_platform: Vec<(Platform, HashSet<(&str, String)>)>,
+    ) -> std::io::Result<()> {
+        // Find the changes that happened in all platforms.
+        let common_changes = changes_by_platform
+            .iter()
+            .fold(None, |acc: Option<HashSet<_>>, (_, changes)| match acc {
+                None => Some(changes.clone()),
+                Some(acc) => Some(acc.intersection(changes).cloned().collect()),
+            })
+            .unwrap_or_default();
+
+        // Print the common changes.
+        for (_, line) in common_changes.iter().sorted_by_key(|(name, _)| name) {
+            writeln!(writer, "  {}", line)?;
+        }
+
+        // Print the per-platform changes.
+        for (platform, changes) in changes_by_platform {
+            let mut changes = changes
+                .iter()
+                .filter(|change| !common_changes.contains(change))
+                .sorted_by_key(|(name, _)| name)
+                .peekable();
+            if changes.peek().is_some() {
+                writeln!(
+                    writer,
+                    "{}: {}:{}\t\t\t",
+                    console::style("Platform").underlined(),
+                    consts::ENVIRONMENT_STYLE.apply_to(environment_name),
+                    consts::PLATFORM_STYLE.apply_to(platform),
+                )?;
+                for (_, line) in changes {
+                    writeln!(writer, "  {}", line)?;
                 }
             }
         }
 
-        writer.flush()?;
-
         Ok(())
     }
 
-    fn format_changes(packages: &PackagesDiff) -> Vec<(&str, String)> {
+    fn format_changes(
+        packages: &PackagesDiff,
+        include: impl Fn(&LockedPackage) -> bool,
+    ) -> Vec<(&str, String)> {
         enum Change<'i> {
             Added(&'i LockedPackage),
             Removed(&'i LockedPackage),
@@ -268,6 +411,11 @@ impl LockFileDiff {
             packages.removed.iter().map(Change::Removed),
             packages.changed.iter().map(|a| Change::Changed(&a.0, &a.1))
         )
+        .filter(|c| match c {
+            Change::Added(p) => include(p),
+            Change::Removed(p) => include(p),
+            Change::Changed(p, _) => include(p),
+        })
         .sorted_by_key(|c| match c {
             Change::Added(p) => p.name(),
             Change::Removed(p) => p.name(),
@@ -316,7 +464,7 @@ impl LockFileDiff {
                         let current = current.record();
 
                         format!(
-                            "{} {} {}\t{} {}\t->\t{} {}",
+                            "{} {} {}\t{} {}\t->\t{} {}{}",
                             console::style("~").yellow(),
                             consts::CondaEmoji,
                             name,
@@ -324,11 +472,36 @@ impl LockFileDiff {
                             choose_style(previous.build.as_str(), current.build.as_str()),
                             choose_style(&current.version.as_str(), &previous.version.as_str()),
                             choose_style(current.build.as_str(), previous.build.as_str()),
+                            if is_metadata_only(previous, current) {
+                                format!(" {}", console::style("(metadata only)").dim())
+                            } else {
+                                String::new()
+                            },
                         )
                     }
                     (LockedPackage::Pypi(previous, _), LockedPackage::Pypi(current, _)) => {
+                        let previous_source = PypiSource::from_location(&previous.location);
+                        let current_source = PypiSource::from_location(&current.location);
+                        let source_change = if previous_source != current_source {
+                            format!(" ({} -> {})", previous_source, current_source)
+                        } else {
+                            match (
+                                git_commit(&previous.location),
+                                git_commit(&current.location),
+                            ) {
+                                (Some(previous), Some(current)) if previous != current => {
+                                    format!(
+                                        " ({} -> {})",
+                                        previous.to_short_string(),
+                                        current.to_short_string()
+                                    )
+                                }
+                                _ => String::new(),
+                            }
+                        };
+
                         format!(
-                            "{} {} {}\t{}\t->\t{}",
+                            "{} {} {}\t{}\t->\t{}{}",
                             console::style("~").yellow(),
                             consts::PypiEmoji,
                             name,
@@ -340,6 +513,7 @@ impl LockFileDiff {
                                 &current.version.to_string(),
                                 &previous.version.to_string()
                             ),
+                            source_change,
                         )
                     }
                     _ => unreachable!(),
@@ -352,28 +526,384 @@ impl LockFileDiff {
     }
 }
 
+/// Returns true if the hashes or the license of a locked conda package changed.
+fn metadata_changed(previous: &PackageRecord, current: &PackageRecord) -> bool {
+    previous.sha256 != current.sha256
+        || previous.md5 != current.md5
+        || previous.license != current.license
+}
+
+/// Returns true if the version and build of a changed conda package are the
+/// same, e.g. when only the url or the hashes of the package changed.
+fn is_metadata_only(previous: &PackageRecord, current: &PackageRecord) -> bool {
+    previous.version == current.version && previous.build == current.build
+}
+
+/// How a locked pypi package is distributed.
+#[derive(Serialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PypiDistribution {
+    /// A built distribution.
+    Wheel,
+    /// A source distribution archive.
+    Sdist,
+    /// A source tree, e.g. a local directory or a git checkout.
+    SourceTree,
+}
+
+/// Where a locked pypi package is retrieved from.
+#[derive(Serialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PypiOrigin {
+    /// A package index.
+    Index,
+    /// A direct url to an archive.
+    DirectUrl,
+    /// A git repository.
+    Git,
+    /// A local path.
+    Path,
+}
+
+/// The kind of source a locked pypi package is installed from.
+#[derive(Serialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PypiSource {
+    pub distribution: PypiDistribution,
+    pub origin: PypiOrigin,
+}
+
+impl PypiSource {
+    /// Determines the source from the location of a locked pypi package.
+    pub fn from_location(location: &UrlOrPath) -> Self {
+        match location {
+            UrlOrPath::Url(url) if is_direct_url(url.scheme()) => {
+                let url = strip_direct_scheme(url);
+                if LockedGitUrl::is_locked_git_url(&url) {
+                    Self {
+                        distribution: PypiDistribution::SourceTree,
+                        origin: PypiOrigin::Git,
+                    }
+                } else if url.scheme() == "file" {
+                    Self {
+                        distribution: distribution_from_path(url.path()),
+                        origin: PypiOrigin::Path,
+                    }
+                } else {
+                    Self {
+                        distribution: distribution_from_path(url.path()),
+                        origin: PypiOrigin::DirectUrl,
+                    }
+                }
+            }
+            UrlOrPath::Url(url) => Self {
+                distribution: distribution_from_path(url.path()),
+                origin: PypiOrigin::Index,
+            },
+            UrlOrPath::Path(path) => Self {
+                distribution: distribution_from_path(path.as_str()),
+                origin: PypiOrigin::Path,
+            },
+        }
+    }
+}
+
+/// Anything that is not a wheel or a source distribution archive is
+/// considered to be a source tree.
+fn distribution_from_path(path: &str) -> PypiDistribution {
+    match DistExtension::from_path(Path::new(path)) {
+        Ok(DistExtension::Wheel) => PypiDistribution::Wheel,
+        Ok(DistExtension::Source(_)) => PypiDistribution::Sdist,
+        Err(_) => PypiDistribution::SourceTree,
+    }
+}
+
+impl Display for PypiDistribution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PypiDistribution::Wheel => write!(f, "wheel"),
+            PypiDistribution::Sdist => write!(f, "sdist"),
+            PypiDistribution::SourceTree => write!(f, "source tree"),
+        }
+    }
+}
+
+impl Display for PypiOrigin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PypiOrigin::Index => write!(f, "index"),
+            PypiOrigin::DirectUrl => write!(f, "direct url"),
+            PypiOrigin::Git => write!(f, "git"),
+            PypiOrigin::Path => write!(f, "path"),
+        }
+    }
+}
+
+impl Display for PypiSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} from {}", self.distribution, self.origin)
+    }
+}
+
+/// A change to a locked conda package. `before` and `after` contain the
+/// fields of the locked package that changed, the other fields describe the
+/// artifacts that are installed before and after the change.
 #[derive(Serialize, Clone)]
 pub struct JsonPackageDiff {
     name: String,
     before: Option<serde_json::Value>,
     after: Option<serde_json::Value>,
-    #[serde(rename = "type")]
-    ty: JsonPackageType,
+    before_build: Option<String>,
+    after_build: Option<String>,
+    /// The size of the artifact in bytes.
+    before_size: Option<u64>,
+    after_size: Option<u64>,
+    before_channel: Option<String>,
+    after_channel: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    channel_changed: bool,
+    /// Only the url, hashes or metadata of the package changed.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    metadata_only: bool,
     #[serde(skip_serializing_if = "std::ops::Not::not")]
     explicit: bool,
 }
 
-#[derive(Serialize, Copy, Clone)]
-#[serde(rename_all = "kebab-case")]
-pub enum JsonPackageType {
-    Conda,
-    Pypi,
+impl JsonPackageDiff {
+    fn new(
+        before: Option<&CondaPackageData>,
+        after: Option<&CondaPackageData>,
+        explicit: bool,
+    ) -> Self {
+        let name = before
+            .or(after)
+            .expect("either the previous or the current package must be set")
+            .record()
+            .name
+            .as_normalized()
+            .to_string();
+        let to_value = |pkg: &CondaPackageData| {
+            serde_json::to_value(pkg).expect("should be able to serialize")
+        };
+        let (before_value, after_value) = match (before, after) {
+            (Some(before), Some(after)) => {
+                let (before, after) = compute_json_diff(to_value(before), to_value(after));
+                (Some(before), Some(after))
+            }
+            (before, after) => (before.map(to_value), after.map(to_value)),
+        };
+        let before_channel = before.and_then(conda_channel);
+        let after_channel = after.and_then(conda_channel);
+        Self {
+            name,
+            before: before_value,
+            after: after_value,
+            before_build: before.map(|pkg| pkg.record().build.clone()),
+            after_build: after.map(|pkg| pkg.record().build.clone()),
+            before_size: before.and_then(|pkg| pkg.record().size),
+            after_size: after.and_then(|pkg| pkg.record().size),
+            channel_changed: before.is_some() && after.is_some() && before_channel != after_channel,
+            metadata_only: before
+                .zip(after)
+                .is_some_and(|(before, after)| is_metadata_only(before.record(), after.record())),
+            before_channel,
+            after_channel,
+            explicit,
+        }
+    }
+}
+
+/// Returns the url of the channel a binary package was locked from.
+fn conda_channel(package: &CondaPackageData) -> Option<String> {
+    match package {
+        CondaPackageData::Binary(binary) => binary.channel.as_ref().map(|c| c.to_string()),
+        CondaPackageData::Source(_) => None,
+    }
+}
+
+/// A change to a locked pypi package, `before` and `after` are the versions of
+/// the package.
+#[derive(Serialize, Clone)]
+pub struct JsonPypiPackageDiff {
+    name: String,
+    before: Option<String>,
+    after: Option<String>,
+    before_source: Option<PypiSource>,
+    after_source: Option<PypiSource>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    source_changed: bool,
+    /// The file name of the wheel or source distribution.
+    before_filename: Option<String>,
+    after_filename: Option<String>,
+    /// The locked commit of a package from a git repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    before_commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after_commit: Option<String>,
+    /// The index the package is resolved from, this is only known if the
+    /// workspace configures a single index for the package.
+    index_url: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    explicit: bool,
+}
+
+impl JsonPypiPackageDiff {
+    fn new(
+        before: Option<&PypiPackageData>,
+        after: Option<&PypiPackageData>,
+        index_url: Option<String>,
+        explicit: bool,
+    ) -> Self {
+        let name = before
+            .or(after)
+            .expect("either the previous or the current package must be set")
+            .name
+            .as_dist_info_name()
+            .into_owned();
+        let before_source = before.map(|pkg| PypiSource::from_location(&pkg.location));
+        let after_source = after.map(|pkg| PypiSource::from_location(&pkg.location));
+        Self {
+            name,
+            before: before.map(|pkg| pkg.version.to_string()),
+            after: after.map(|pkg| pkg.version.to_string()),
+            before_source,
+            after_source,
+            source_changed: before_source.is_some()
+                && after_source.is_some()
+                && before_source != after_source,
+            before_filename: before.and_then(|pkg| artifact_filename(&pkg.location)),
+            after_filename: after.and_then(|pkg| artifact_filename(&pkg.location)),
+            before_commit: before
+                .and_then(|pkg| git_commit(&pkg.location))
+                .map(|commit| commit.to_string()),
+            after_commit: after
+                .and_then(|pkg| git_commit(&pkg.location))
+                .map(|commit| commit.to_string()),
+            index_url,
+            explicit,
+        }
+    }
+}
+
+/// Returns the locked commit of a pypi package from a git repository.
+fn git_commit(location: &UrlOrPath) -> Option<GitSha> {
+    let UrlOrPath::Url(url) = location else {
+        return None;
+    };
+    if !is_direct_url(url.scheme()) {
+        return None;
+    }
+    let url = strip_direct_scheme(url);
+    if !LockedGitUrl::is_locked_git_url(&url) {
+        return None;
+    }
+    LockedGitUrl::new(url.into_owned())
+        .to_pinned_git_spec()
+        .ok()
+        .map(|spec| spec.source.commit)
+}
+
+/// Returns the file name of a wheel or source distribution archive.
+fn artifact_filename(location: &UrlOrPath) -> Option<String> {
+    let source = PypiSource::from_location(location);
+    if source.distribution == PypiDistribution::SourceTree {
+        return None;
+    }
+    let path = match location {
+        UrlOrPath::Url(url) => url.path().to_string(),
+        UrlOrPath::Path(path) => path.as_str().to_string(),
+    };
+    path.rsplit(['/', '\\'])
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+/// Returns the index a pypi package from an index is resolved from. This is
+/// the `index` of the requirement, or the configured index that serves the
+/// locked file. When extra indexes are configured the index is matched on the
+/// host of the locked file, `None` is returned if no index matches.
+fn configured_index_url(
+    package: &PypiPackageData,
+    specs: Option<&IndexSet<PixiPypiSpec>>,
+    options: Option<&PypiOptions>,
+) -> Option<String> {
+    if PypiSource::from_location(&package.location).origin != PypiOrigin::Index {
+        return None;
+    }
+    let package_index = specs.into_iter().flatten().find_map(|spec| match spec {
+        PixiPypiSpec::Version {
+            index: Some(index), ..
+        } => Some(index.to_string()),
+        _ => None,
+    });
+    if package_index.is_some() {
+        return package_index;
+    }
+    let options = options?;
+    let index_url = options
+        .index_url
+        .as_ref()
+        .unwrap_or(&consts::DEFAULT_PYPI_INDEX_URL);
+    let extra_index_urls = options.extra_index_urls.as_deref().unwrap_or_default();
+    if extra_index_urls.is_empty() {
+        return Some(index_url.to_string());
+    }
+
+    // Extra indexes take precedence over the primary index
+    let UrlOrPath::Url(url) = &package.location else {
+        return None;
+    };
+    extra_index_urls
+        .iter()
+        .chain(std::iter::once(index_url))
+        .find(|index| serves_file(index, url))
+        .map(Url::to_string)
+}
+
+/// Returns true if the file at `url` is served by the index at `index`. The
+/// files of PyPI are hosted on a separate domain.
+fn serves_file(index: &Url, url: &Url) -> bool {
+    match (index.host_str(), url.host_str()) {
+        (Some("pypi.org"), Some("files.pythonhosted.org")) => true,
+        (Some(index_host), Some(host)) => index_host == host,
+        _ => false,
+    }
+}
+
+/// The changes to the conda and pypi packages of a single platform.
+#[derive(Serialize, Clone, Default)]
+pub struct JsonPlatformDiff {
+    pub conda: Vec<JsonPackageDiff>,
+    pub pypi: Vec<JsonPypiPackageDiff>,
+}
+
+/// The total size in bytes of the conda artifacts that are added and removed
+/// by the changes to an environment, summed over all platforms. Pypi
+/// packages are not included because the lock-file doesn't record their size.
+#[derive(Serialize, Clone, Default)]
+pub struct JsonDownloadSize {
+    pub added: u64,
+    pub removed: u64,
+}
+
+impl JsonDownloadSize {
+    fn from_platforms<'a>(platforms: impl IntoIterator<Item = &'a JsonPlatformDiff>) -> Self {
+        let mut size = Self::default();
+        for package in platforms.into_iter().flat_map(|diff| &diff.conda) {
+            size.added += package.after_size.unwrap_or_default();
+            size.removed += package.before_size.unwrap_or_default();
+        }
+        size
+    }
 }
 
 #[derive(Serialize, Clone)]
 pub struct LockFileJsonDiff {
     pub version: usize,
-    pub environment: IndexMap<String, IndexMap<Platform, Vec<JsonPackageDiff>>>,
+    /// Whether any package of the lock-file changed.
+    pub changed: bool,
+    pub environment: IndexMap<String, IndexMap<Platform, JsonPlatformDiff>>,
+    pub download_size: IndexMap<String, JsonDownloadSize>,
 }
 
 impl LockFileJsonDiff {
@@ -384,12 +914,8 @@ impl LockFileJsonDiff {
             let mut environment_diff_json = IndexMap::new();
 
             for (platform, packages_diff) in environment_diff {
-                let conda_dependencies = project
-                    .and_then(|p| {
-                        p.environment(environment_name.as_str()).map(|env| {
-                            env.dependencies(pixi_manifest::SpecType::Run, Some(platform))
-                        })
-                    })
+                let direct_dependencies = project
+                    .map(|p| DirectDependencies::new(p, &environment_name, platform))
                     .unwrap_or_default();
 
                 let pypi_dependencies = project
@@ -399,93 +925,171 @@ impl LockFileJsonDiff {
                     })
                     .unwrap_or_default();
 
-                let add_diffs = packages_diff.added.into_iter().map(|new| match new {
-                    LockedPackage::Conda(pkg) => JsonPackageDiff {
-                        name: pkg.record().name.as_normalized().to_string(),
-                        before: None,
-                        after: Some(
-                            serde_json::to_value(&pkg).expect("should be able to serialize"),
-                        ),
-                        ty: JsonPackageType::Conda,
-                        explicit: conda_dependencies.contains_key(&pkg.record().name),
-                    },
-                    LockedPackage::Pypi(pkg, _) => JsonPackageDiff {
-                        name: pkg.name.as_dist_info_name().into_owned(),
-                        before: None,
-                        after: Some(
-                            serde_json::to_value(&pkg).expect("should be able to serialize"),
-                        ),
-                        ty: JsonPackageType::Pypi,
-                        explicit: pypi_dependencies.contains_key(&pkg.name),
-                    },
+                let pypi_options = project.and_then(|p| {
+                    p.environment(environment_name.as_str())
+                        .map(|env| env.pypi_options())
                 });
+                let index_url = |pkg: &PypiPackageData| {
+                    configured_index_url(
+                        pkg,
+                        pypi_dependencies.get(&pkg.name),
+                        pypi_options.as_ref(),
+                    )
+                };
 
-                let removed_diffs = packages_diff.removed.into_iter().map(|old| match old {
-                    LockedPackage::Conda(pkg) => JsonPackageDiff {
-                        name: pkg.record().name.as_normalized().to_string(),
-                        before: Some(
-                            serde_json::to_value(&pkg).expect("should be able to serialize"),
-                        ),
-                        after: None,
-                        ty: JsonPackageType::Conda,
-                        explicit: conda_dependencies.contains_key(&pkg.record().name),
-                    },
+                let mut platform_diff = JsonPlatformDiff::default();
 
-                    LockedPackage::Pypi(pkg, _) => JsonPackageDiff {
-                        name: pkg.name.as_dist_info_name().into_owned(),
-                        before: Some(
-                            serde_json::to_value(&pkg).expect("should be able to serialize"),
-                        ),
-                        after: None,
-                        ty: JsonPackageType::Pypi,
-                        explicit: pypi_dependencies.contains_key(&pkg.name),
-                    },
-                });
+                for new in packages_diff.added {
+                    match new {
+                        LockedPackage::Conda(pkg) => {
+                            platform_diff.conda.push(JsonPackageDiff::new(
+                                None,
+                                Some(&pkg),
+                                direct_dependencies.conda.contains(&pkg.record().name),
+                            ))
+                        }
+                        LockedPackage::Pypi(pkg, _) => {
+                            platform_diff.pypi.push(JsonPypiPackageDiff::new(
+                                None,
+                                Some(&pkg),
+                                index_url(&pkg),
+                                direct_dependencies.pypi.contains(&pkg.name),
+                            ))
+                        }
+                    }
+                }
 
-                let changed_diffs = packages_diff.changed.into_iter().map(|(old, new)| match (old, new) {
-                    (LockedPackage::Conda(old), LockedPackage::Conda(new)) =>
-                        {
-                            let before = serde_json::to_value(&old).expect("should be able to serialize");
-                            let after = serde_json::to_value(&new).expect("should be able to serialize");
-                            let (before, after) = compute_json_diff(before, after);
-                            JsonPackageDiff {
-                                name: old.record().name.as_normalized().to_string(),
-                                before: Some(before),
-                                after: Some(after),
-                                ty: JsonPackageType::Conda,
-                                explicit: conda_dependencies.contains_key(&old.record().name),
-                            }
+                for old in packages_diff.removed {
+                    match old {
+                        LockedPackage::Conda(pkg) => {
+                            platform_diff.conda.push(JsonPackageDiff::new(
+                                Some(&pkg),
+                                None,
+                                direct_dependencies.conda.contains(&pkg.record().name),
+                            ))
                         }
-                    (LockedPackage::Pypi(old, _), LockedPackage::Pypi(new, _)) => {
-                        let before = serde_json::to_value(&old).expect("should be able to serialize");
-                        let after = serde_json::to_value(&new).expect("should be able to serialize");
-                        let (before, after) = compute_json_diff(before, after);
-                        JsonPackageDiff {
-                            name: old.name.as_dist_info_name().into_owned(),
-                            before: Some(before),
-                            after: Some(after),
-                            ty: JsonPackageType::Pypi,
-                            explicit: pypi_dependencies.contains_key(&old.name),
+                        LockedPackage::Pypi(pkg, _) => {
+                            platform_diff.pypi.push(JsonPypiPackageDiff::new(
+                                Some(&pkg),
+                                None,
+                                index_url(&pkg),
+                                direct_dependencies.pypi.contains(&pkg.name),
+                            ))
                         }
                     }
-                    _ => unreachable!("packages cannot change type, they are represented as removals and inserts instead"),
-                });
+                }
+
+                for (old, new) in packages_diff.changed {
+                    match (old, new) {
+                        (LockedPackage::Conda(old), LockedPackage::Conda(new)) => {
+                            platform_diff.conda.push(JsonPackageDiff::new(
+                                Some(&old),
+                                Some(&new),
+                                direct_dependencies.conda.contains(&old.record().name),
+                            ))
+                        }
+                        (LockedPackage::Pypi(old, _), LockedPackage::Pypi(new, _)) => {
+                            platform_diff.pypi.push(JsonPypiPackageDiff::new(
+                                Some(&old),
+                                Some(&new),
+                                index_url(&new),
+                                direct_dependencies.pypi.contains(&old.name),
+                            ))
+                        }
+                        _ => unreachable!(
+                            "packages cannot change type, they are represented as removals and inserts instead"
+                        ),
+                    }
+                }
 
-                let packages_diff_json = add_diffs
-                    .chain(removed_diffs)
-                    .chain(changed_diffs)
-                    .sorted_by_key(|diff| diff.name.clone())
-                    .collect_vec();
+                platform_diff.conda.sort_by(|a, b| a.name.cmp(&b.name));
+                platform_diff.pypi.sort_by(|a, b| a.name.cmp(&b.name));
 
-                environment_diff_json.insert(platform, packages_diff_json);
+                environment_diff_json.insert(platform, platform_diff);
             }
 
             environment.insert(environment_name, environment_diff_json);
         }
 
+        let download_size = environment
+            .iter()
+            .map(|(name, platforms)| {
+                (
+                    name.clone(),
+                    JsonDownloadSize::from_platforms(platforms.values()),
+                )
+            })
+            .collect();
+
+        let changed = environment
+            .values()
+            .flat_map(|platforms| platforms.values())
+            .any(|diff| !diff.conda.is_empty() || !diff.pypi.is_empty());
+
         Self {
-            version: 1,
+            version: 2,
+            changed,
             environment,
+            download_size,
+        }
+    }
+
+    /// Only keeps the changes to the direct dependencies of the environments.
+    pub fn direct_only(mut self) -> Self {
+        for platforms in self.environment.values_mut() {
+            for diff in platforms.values_mut() {
+                diff.conda.retain(|package| package.explicit);
+                diff.pypi.retain(|package| package.explicit);
+            }
+        }
+        self.download_size = self
+            .environment
+            .iter()
+            .map(|(name, platforms)| {
+                (
+                    name.clone(),
+                    JsonDownloadSize::from_platforms(platforms.values()),
+                )
+            })
+            .collect();
+        self
+    }
+}
+
+/// A change to a single dependency in the manifest.
+#[derive(Serialize, Clone)]
+pub struct JsonManifestChange {
+    pub name: String,
+    pub feature: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<Platform>,
+    pub table: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// The JSON output of commands that modify the dependencies of a workspace,
+/// e.g. `pixi add --json` and `pixi remove --json`.
+#[derive(Serialize, Clone)]
+pub struct JsonDependencyChanges {
+    pub version: usize,
+    pub manifest_changes: Vec<JsonManifestChange>,
+    pub lock_changes: IndexMap<String, IndexMap<Platform, JsonPlatformDiff>>,
+}
+
+impl JsonDependencyChanges {
+    pub fn new(
+        project: Option<&Workspace>,
+        manifest_changes: Vec<JsonManifestChange>,
+        lock_file_diff: Option<LockFileDiff>,
+    ) -> Self {
+        let lock_changes = lock_file_diff
+            .map(|diff| LockFileJsonDiff::new(project, diff).environment)
+            .unwrap_or_default();
+        Self {
+            version: 2,
+            manifest_changes,
+            lock_changes,
         }
     }
 }
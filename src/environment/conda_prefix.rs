@@ -72,6 +72,7 @@ pub struct CondaPrefixUpdaterBuilder<'a> {
     group: GroupedEnvironment<'a>,
     platform: Platform,
     build_context: BuildContext,
+    prefix: Option<Prefix>,
 }
 
 impl<'a> CondaPrefixUpdaterBuilder<'a> {
@@ -85,6 +86,15 @@ impl<'a> CondaPrefixUpdaterBuilder<'a> {
             group,
             platform,
             build_context,
+            prefix: None,
+        }
+    }
+
+    /// Installs into the given prefix instead of the prefix of the group.
+    pub fn with_prefix(self, prefix: Prefix) -> Self {
+        Self {
+            prefix: Some(prefix),
+            ..self
         }
     }
 
@@ -96,7 +106,7 @@ impl<'a> CondaPrefixUpdaterBuilder<'a> {
             .channel_urls(&self.group.workspace().channel_config())
             .into_diagnostic()?;
         let name = self.group.name();
-        let prefix = self.group.prefix();
+        let prefix = self.prefix.unwrap_or_else(|| self.group.prefix());
 
         Ok(CondaPrefixUpdater::new(
             channels,
@@ -0,0 +1,310 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Write, stderr},
+    path::Path,
+};
+
+use fancy_display::FancyDisplay;
+use human_bytes::human_bytes;
+use itertools::{Either, Itertools};
+use miette::IntoDiagnostic;
+use pixi_consts::consts;
+use pixi_manifest::EnvironmentName;
+use pixi_record::PixiRecord;
+use rattler::install::{Transaction, TransactionOperation};
+use rattler_conda_types::{PackageName, PackageRecord, Platform, PrefixRecord};
+use rattler_lock::{PypiPackageData, PypiPackageEnvironmentData};
+use tabwriter::TabWriter;
+use uv_distribution_types::Name;
+
+use super::PythonStatus;
+use crate::{install_pypi::plan::PyPIInstallationPlan, prefix::Prefix};
+
+/// What installing an environment would do with a package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingAction {
+    /// The package is not installed yet.
+    Link,
+    /// Another version or build of the package is installed.
+    Change,
+    /// The same package is linked again.
+    Reinstall,
+    /// The package is no longer part of the environment.
+    Unlink,
+    /// The package is built from source before it is linked.
+    Build,
+}
+
+/// The kind of package that an operation changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingPackageKind {
+    Conda,
+    Pypi,
+}
+
+/// A package operation that installing an environment would perform.
+#[derive(Debug, Clone)]
+pub struct PendingOperation {
+    pub name: String,
+    pub kind: PendingPackageKind,
+    pub action: PendingAction,
+    /// The version and build that is currently installed.
+    pub installed: Option<String>,
+    /// The version and build that would be installed.
+    pub locked: Option<String>,
+    /// The size of the archive that still has to be downloaded, `None` if it is
+    /// already in the package cache or the size is unknown.
+    pub download_size: Option<u64>,
+}
+
+/// Computes the operations that installing the conda records into the prefix
+/// would perform, with the same diffing as the installer, without downloading
+/// or modifying anything. Also returns how the python interpreter would
+/// change, which determines what happens with the PyPI packages.
+pub(crate) fn pending_operations(
+    prefix: &Prefix,
+    records: Vec<PixiRecord>,
+    reinstall_packages: Option<HashSet<PackageName>>,
+    platform: Platform,
+    package_cache_dir: &Path,
+) -> miette::Result<(Vec<PendingOperation>, PythonStatus)> {
+    let installed: Vec<PrefixRecord> = if prefix.root().join(consts::CONDA_META_DIR).is_dir() {
+        prefix.find_installed_packages().into_diagnostic()?
+    } else {
+        Vec::new()
+    };
+
+    let (source_records, binary_records): (Vec<_>, Vec<_>) =
+        records.into_iter().partition_map(|record| match record {
+            PixiRecord::Source(record) => Either::Left(record),
+            PixiRecord::Binary(record) => Either::Right(record),
+        });
+
+    // Source packages are only known after they are built, so they are left out
+    // of the transaction
+    let source_names = source_records
+        .iter()
+        .map(|record| record.package_record.name.clone())
+        .collect::<HashSet<_>>();
+
+    let transaction = Transaction::from_current_and_desired(
+        installed.clone(),
+        binary_records,
+        reinstall_packages.as_ref(),
+        Some(&source_names),
+        platform,
+    )
+    .into_diagnostic()?;
+    let python_status = PythonStatus::from_transaction(&transaction);
+
+    let download_size = |record: &PackageRecord| {
+        let cached = package_cache_dir
+            .join(format!(
+                "{}-{}-{}",
+                record.name.as_normalized(),
+                record.version,
+                record.build
+            ))
+            .is_dir();
+        if cached { None } else { record.size }
+    };
+
+    let mut operations = transaction
+        .operations
+        .into_iter()
+        .map(|operation| match operation {
+            TransactionOperation::Install(new) => PendingOperation {
+                name: new.package_record.name.as_source().to_string(),
+                kind: PendingPackageKind::Conda,
+                action: PendingAction::Link,
+                installed: None,
+                locked: Some(version_and_build(&new.package_record)),
+                download_size: download_size(&new.package_record),
+            },
+            TransactionOperation::Change { old, new } => PendingOperation {
+                name: new.package_record.name.as_source().to_string(),
+                kind: PendingPackageKind::Conda,
+                action: PendingAction::Change,
+                installed: Some(version_and_build(&old.repodata_record.package_record)),
+                locked: Some(version_and_build(&new.package_record)),
+                download_size: download_size(&new.package_record),
+            },
+            TransactionOperation::Reinstall { old, new } => PendingOperation {
+                name: new.package_record.name.as_source().to_string(),
+                kind: PendingPackageKind::Conda,
+                action: PendingAction::Reinstall,
+                installed: Some(version_and_build(&old.repodata_record.package_record)),
+                locked: Some(version_and_build(&new.package_record)),
+                download_size: download_size(&new.package_record),
+            },
+            TransactionOperation::Remove(old) => PendingOperation {
+                name: old
+                    .repodata_record
+                    .package_record
+                    .name
+                    .as_source()
+                    .to_string(),
+                kind: PendingPackageKind::Conda,
+                action: PendingAction::Unlink,
+                installed: Some(version_and_build(&old.repodata_record.package_record)),
+                locked: None,
+                download_size: None,
+            },
+        })
+        .collect_vec();
+
+    // A source package that is installed with the locked version is only
+    // rebuilt when its sources changed, which is only known when building it
+    for record in source_records {
+        let installed = installed
+            .iter()
+            .find(|installed| {
+                installed.repodata_record.package_record.name == record.package_record.name
+            })
+            .map(|installed| version_and_build(&installed.repodata_record.package_record));
+        let locked = version_and_build(&record.package_record);
+        if installed.as_ref() == Some(&locked) {
+            continue;
+        }
+        operations.push(PendingOperation {
+            name: record.package_record.name.as_source().to_string(),
+            kind: PendingPackageKind::Conda,
+            action: PendingAction::Build,
+            installed,
+            locked: Some(locked),
+            download_size: None,
+        });
+    }
+
+    operations.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok((operations, python_status))
+}
+
+/// Converts the installation plan of the PyPI packages into the operations it
+/// would perform.
+pub(crate) fn pending_pypi_operations(
+    plan: &PyPIInstallationPlan,
+    pypi_records: &[(PypiPackageData, PypiPackageEnvironmentData)],
+) -> Vec<PendingOperation> {
+    let locked = pypi_records
+        .iter()
+        .map(|(data, _)| (data.name.to_string(), data.version.to_string()))
+        .collect::<HashMap<_, _>>();
+    let reinstalled = plan
+        .reinstalls
+        .iter()
+        .map(|(dist, _)| (dist.name().to_string(), dist.version().to_string()))
+        .collect::<HashMap<_, _>>();
+
+    let installs = plan
+        .local
+        .iter()
+        .map(|(dist, _)| dist.name().to_string())
+        .chain(plan.remote.iter().map(|(dist, _)| dist.name().to_string()));
+    let mut operations = installs
+        .map(|name| {
+            let locked = locked.get(&name).cloned();
+            let installed = reinstalled.get(&name).cloned();
+            let action = match &installed {
+                None => PendingAction::Link,
+                Some(installed) if Some(installed) == locked.as_ref() => PendingAction::Reinstall,
+                Some(_) => PendingAction::Change,
+            };
+            PendingOperation {
+                name,
+                kind: PendingPackageKind::Pypi,
+                action,
+                installed,
+                locked,
+                download_size: None,
+            }
+        })
+        .chain(plan.extraneous.iter().map(|dist| PendingOperation {
+            name: dist.name().to_string(),
+            kind: PendingPackageKind::Pypi,
+            action: PendingAction::Unlink,
+            installed: Some(dist.version().to_string()),
+            locked: None,
+            download_size: None,
+        }))
+        .collect_vec();
+    operations.sort_by(|a, b| a.name.cmp(&b.name));
+    operations
+}
+
+/// Returns the operations for a prefix whose python interpreter is added or
+/// replaced, every locked PyPI package is installed into the new
+/// site-packages.
+pub(crate) fn pending_pypi_links(
+    pypi_records: &[(PypiPackageData, PypiPackageEnvironmentData)],
+) -> Vec<PendingOperation> {
+    pypi_records
+        .iter()
+        .map(|(data, _)| PendingOperation {
+            name: data.name.to_string(),
+            kind: PendingPackageKind::Pypi,
+            action: PendingAction::Link,
+            installed: None,
+            locked: Some(data.version.to_string()),
+            download_size: None,
+        })
+        .sorted_by(|a, b| a.name.cmp(&b.name))
+        .collect()
+}
+
+fn version_and_build(record: &PackageRecord) -> String {
+    format!("{} {}", record.version, record.build)
+}
+
+/// Prints a table with the pending operations of every environment.
+pub fn print_pending_operations(
+    environments: &[(EnvironmentName, Vec<PendingOperation>)],
+) -> std::io::Result<()> {
+    let mut writer = TabWriter::new(stderr());
+    for (idx, (environment_name, operations)) in environments.iter().enumerate() {
+        // Add a new line between environments
+        if idx > 0 {
+            writeln!(writer, "\t\t\t\t")?;
+        }
+        writeln!(
+            writer,
+            "{}: {}\t\t\t\t",
+            console::style("Environment").underlined(),
+            environment_name.fancy_display()
+        )?;
+        if operations.is_empty() {
+            writeln!(writer, "  {}\t\t\t\t", console::style("up-to-date").dim())?;
+            continue;
+        }
+
+        for operation in operations {
+            let action = match operation.action {
+                PendingAction::Link => console::style("link").green(),
+                PendingAction::Change => console::style("change").yellow(),
+                PendingAction::Reinstall => console::style("reinstall").yellow(),
+                PendingAction::Unlink => console::style("unlink").red(),
+                PendingAction::Build => console::style("build").cyan(),
+            };
+            let versions = match (&operation.installed, &operation.locked) {
+                (Some(installed), Some(locked)) => format!("{installed} -> {locked}"),
+                (None, Some(locked)) => locked.clone(),
+                (Some(installed), None) => installed.clone(),
+                (None, None) => String::new(),
+            };
+            let download = operation
+                .download_size
+                .map(|size| format!("download {}", human_bytes(size as f64)))
+                .unwrap_or_default();
+            let kind = match operation.kind {
+                PendingPackageKind::Conda => console::style("conda").dim(),
+                PendingPackageKind::Pypi => console::style("pypi").dim(),
+            };
+            writeln!(
+                writer,
+                "  {}\t{}\t{}\t{}\t{}",
+                action, kind, operation.name, versions, download
+            )?;
+        }
+    }
+    writer.flush()
+}
@@ -1,11 +1,17 @@
 pub(crate) mod conda_metadata;
 mod conda_prefix;
+mod dry_run;
 pub mod list;
+mod post_install;
 mod pypi_prefix;
 mod python_status;
+mod verify;
 pub use conda_prefix::{CondaPrefixUpdated, CondaPrefixUpdater, CondaPrefixUpdaterBuilder};
 use dialoguer::theme::ColorfulTheme;
-use futures::{FutureExt, StreamExt, TryStreamExt, stream};
+pub use dry_run::{PendingAction, PendingOperation, PendingPackageKind, print_pending_operations};
+pub(crate) use dry_run::{pending_operations, pending_pypi_links, pending_pypi_operations};
+use fancy_display::FancyDisplay;
+use futures::{StreamExt, stream};
 use miette::{Context, IntoDiagnostic};
 use pixi_consts::consts;
 use pixi_git::credentials::store_credentials_from_url;
@@ -13,23 +19,27 @@ use pixi_manifest::FeaturesExt;
 use pixi_progress::await_in_progress;
 use pixi_pypi_spec::PixiPypiSpec;
 use pixi_spec::{GitSpec, PixiSpec};
-pub use pypi_prefix::update_prefix_pypi;
+pub use post_install::{PostInstallTaskFailed, run_post_install_tasks};
+pub use pypi_prefix::{pending_prefix_pypi, update_prefix_pypi};
 pub use python_status::PythonStatus;
-use rattler_conda_types::Platform;
+use rattler_conda_types::{PackageName, Platform};
 use rattler_lock::LockedPackageRef;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     hash::{Hash, Hasher},
-    io::ErrorKind,
+    io::{ErrorKind, IsTerminal},
     path::{Path, PathBuf},
 };
+pub use verify::{PrefixVerification, verify_prefix};
 use xxhash_rust::xxh3::Xxh3;
 
 use crate::{
     Workspace,
-    lock_file::{LockFileDerivedData, ReinstallPackages, UpdateLockFileOptions, UpdateMode},
+    lock_file::{
+        InstallFilter, LockFileDerivedData, ReinstallPackages, UpdateLockFileOptions, UpdateMode,
+    },
     prefix::Prefix,
     rlimit::try_increase_rlimit_to_sensible,
     workspace::{Environment, HasWorkspaceRef, grouped_environment::GroupedEnvironment},
@@ -151,6 +161,9 @@ pub(crate) struct EnvironmentFile {
     pub(crate) pixi_version: String,
     /// The hash of the lock file that was used to create the environment.
     pub(crate) environment_lock_file_hash: LockedEnvironmentHash,
+    /// The packages of the lock-file that were not installed.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub(crate) skipped_packages: BTreeSet<PackageName>,
 }
 
 /// The path to the environment file in the `conda-meta` directory of the
@@ -418,12 +431,14 @@ pub async fn get_update_lock_file_and_prefix<'env>(
     update_mode: UpdateMode,
     update_lock_file_options: UpdateLockFileOptions,
     reinstall_packages: ReinstallPackages,
+    filter: InstallFilter,
 ) -> miette::Result<(LockFileDerivedData<'env>, Prefix)> {
     let (lock_file, prefixes) = get_update_lock_file_and_prefixes(
         &[environment.clone()],
         update_mode,
         update_lock_file_options,
         reinstall_packages,
+        filter,
     )
     .await?;
     Ok((
@@ -442,6 +457,7 @@ pub async fn get_update_lock_file_and_prefixes<'env>(
     update_mode: UpdateMode,
     update_lock_file_options: UpdateLockFileOptions,
     reinstall_packages: ReinstallPackages,
+    filter: InstallFilter,
 ) -> miette::Result<(LockFileDerivedData<'env>, Vec<Prefix>)> {
     if environments.is_empty() {
         return Err(miette::miette!("No environments provided to install."));
@@ -475,27 +491,72 @@ pub async fn get_update_lock_file_and_prefixes<'env>(
             lock_file_usage: update_lock_file_options.lock_file_usage,
             no_install,
             max_concurrent_solves: update_lock_file_options.max_concurrent_solves,
+            no_prune: update_lock_file_options.no_prune,
         })
         .await?;
 
-    // Get the prefix from the lock-file.
+    // Get the prefix from the lock-file. Environments are installed
+    // concurrently, the package cache makes sure that a package that is needed
+    // by multiple environments is only downloaded once.
     let lock_file_ref = &lock_file;
     let reinstall_packages = &reinstall_packages;
-    let prefixes = stream::iter(environments.iter())
-        .map(move |env| {
+    let filter = &filter;
+    let no_install_envs = &no_install_envs;
+    // Without a terminal there are no progress bars, so log which environments
+    // are being installed instead.
+    let log_progress = environments.len() > 1 && !std::io::stderr().is_terminal();
+    let results: Vec<_> = stream::iter(environments.iter())
+        .map(move |env| async move {
             if no_install || no_install_envs.contains(env) {
-                std::future::ready(Ok(Prefix::new(env.dir()))).left_future()
-            } else {
-                lock_file_ref
-                    .prefix(env, update_mode, reinstall_packages)
-                    .right_future()
+                return (env, Ok(Prefix::new(env.dir())));
             }
+            if log_progress {
+                eprintln!("installing the {} environment", env.name().fancy_display());
+            }
+            let result = lock_file_ref
+                .prefix(env, update_mode, reinstall_packages, filter)
+                .await;
+            if log_progress && result.is_ok() {
+                eprintln!("installed the {} environment", env.name().fancy_display());
+            }
+            (env, result)
         })
-        .buffer_unordered(environments.len())
-        .try_collect()
-        .await?;
+        .buffer_unordered(workspace.config().max_concurrent_installs().max(1))
+        .collect()
+        .await;
+
+    // A failing environment doesn't stop the others from being installed, the
+    // failures are reported together once all environments are done.
+    let mut prefixes = Vec::with_capacity(results.len());
+    let mut failures = Vec::new();
+    for (env, result) in results {
+        match result {
+            Ok(prefix) => prefixes.push(prefix),
+            Err(err) => failures.push((env, err)),
+        }
+    }
+    if failures.is_empty() {
+        return Ok((lock_file, prefixes));
+    }
+    if let [_] = environments {
+        let (_, err) = failures.remove(0);
+        return Err(err);
+    }
 
-    Ok((lock_file, prefixes))
+    let failed_count = failures.len();
+    for (env, err) in failures {
+        eprintln!(
+            "{:?}",
+            err.wrap_err(format!(
+                "failed to install the {} environment",
+                env.name().fancy_display()
+            ))
+        );
+    }
+    miette::bail!(
+        "{failed_count} of {} environments failed to install",
+        environments.len()
+    )
 }
 
 pub type PerEnvironment<'p, T> = HashMap<Environment<'p>, T>;
@@ -0,0 +1,181 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use fancy_display::FancyDisplay;
+use miette::{Diagnostic, IntoDiagnostic};
+use pixi_consts::consts;
+use pixi_manifest::EnvironmentName;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{LockedEnvironmentHash, read_environment_file};
+use crate::{
+    lock_file::{InstallFilter, LockFileDerivedData, ReinstallPackages, UpdateMode},
+    task::{ExecutableTask, SearchEnvironments, TaskGraph, get_task_env},
+    workspace::{Environment, HasWorkspaceRef},
+};
+
+/// Records the post-install tasks that ran and the locked packages they ran
+/// for, so they don't run again until the environment changes.
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+struct PostInstallFile {
+    environment_lock_file_hash: LockedEnvironmentHash,
+    tasks: Vec<String>,
+}
+
+/// The path to the post-install file in the `conda-meta` directory of the
+/// environment.
+fn post_install_file_path(environment_dir: &Path) -> PathBuf {
+    environment_dir
+        .join(consts::CONDA_META_DIR)
+        .join(consts::POST_INSTALL_FILE_NAME)
+}
+
+fn read_post_install_file(environment_dir: &Path) -> Option<PostInstallFile> {
+    let contents = fs_err::read_to_string(post_install_file_path(environment_dir)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// A post-install task exited with a non-zero exit code, the CLI exits with the
+/// same exit code.
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "the post-install task '{task}' of the environment '{environment}' failed with exit code {exit_code}"
+)]
+pub struct PostInstallTaskFailed {
+    pub task: String,
+    pub environment: EnvironmentName,
+    pub exit_code: i32,
+}
+
+/// Runs the `post-install` tasks of the environment, in dependency order, if
+/// they didn't run yet for the packages that are installed. When one of the
+/// tasks fails a [`PostInstallTaskFailed`] error is returned.
+pub async fn run_post_install_tasks<'p>(
+    environment: &Environment<'p>,
+    lock_file: &LockFileDerivedData<'p>,
+) -> miette::Result<()> {
+    let task_names = environment.post_install_tasks();
+    if task_names.is_empty() {
+        return Ok(());
+    }
+
+    // The environment file is written every time the prefix is updated.
+    let environment_dir = environment.dir();
+    let Some(environment_file) = read_environment_file(&environment_dir)? else {
+        return Ok(());
+    };
+    let post_install_file = PostInstallFile {
+        environment_lock_file_hash: environment_file.environment_lock_file_hash,
+        tasks: task_names.iter().map(|name| name.to_string()).collect(),
+    };
+    if read_post_install_file(&environment_dir).as_ref() == Some(&post_install_file) {
+        tracing::debug!(
+            "post-install tasks of '{}' already ran for the installed packages",
+            environment.name()
+        );
+        return Ok(());
+    }
+
+    let workspace = environment.workspace();
+    let platform = environment.best_platform();
+    let search_environments =
+        SearchEnvironments::from_opt_env(workspace, Some(environment.clone()), Some(platform));
+
+    // Clear the progress of the installation before the tasks write output.
+    lock_file
+        .build_context
+        .command_dispatcher()
+        .clear_reporter()
+        .await;
+
+    let mut task_envs = HashMap::new();
+    for task_name in task_names {
+        // Names that are not a task would otherwise be executed as a command.
+        if environment.task(task_name, Some(platform)).is_err() {
+            miette::bail!(
+                help = "define the task or remove it from `post-install`",
+                "the post-install task '{}' of the environment '{}' could not be found",
+                task_name.fancy_display(),
+                environment.name().fancy_display()
+            );
+        }
+
+        let task_graph = TaskGraph::from_cmd_args(
+            workspace,
+            &search_environments,
+            vec![task_name.to_string()],
+            false,
+        )?;
+        for task_id in task_graph.topological_order() {
+            let executable_task = ExecutableTask::from_task_graph(&task_graph, task_id);
+            if !executable_task.task().is_executable() {
+                continue;
+            }
+
+            eprintln!(
+                "{}{}{}{}{}",
+                console::Emoji("✨ ", ""),
+                console::style("Post-install task (").bold(),
+                console::style(executable_task.name().unwrap_or("unnamed"))
+                    .green()
+                    .bold(),
+                console::style("): ").bold(),
+                executable_task.display_command(),
+            );
+
+            let task_env: &HashMap<String, String> =
+                match task_envs.entry(executable_task.run_environment.clone()) {
+                    Entry::Occupied(env) => env.into_mut(),
+                    Entry::Vacant(entry) => {
+                        // Dependencies can run in other environments, make sure those
+                        // are installed as well.
+                        lock_file
+                            .prefix(
+                                &executable_task.run_environment,
+                                UpdateMode::QuickValidate,
+                                &ReinstallPackages::None,
+                                &InstallFilter::default(),
+                            )
+                            .await?;
+                        let command_env = get_task_env(
+                            &executable_task.run_environment,
+                            executable_task.task().clean_env(),
+                            executable_task.task().clean_env_keep(),
+                            Some(lock_file.as_lock_file()),
+                            workspace.config().force_activate(),
+                            workspace.config().experimental_activation_cache_usage(),
+                        )
+                        .await?;
+                        entry.insert(command_env)
+                    }
+                };
+
+            let task_env = task_env
+                .iter()
+                .map(|(k, v)| (OsString::from(k), OsString::from(v)))
+                .collect();
+            let status_code = executable_task
+                .execute(&task_env, Default::default())
+                .await?;
+            if status_code != 0 {
+                return Err(PostInstallTaskFailed {
+                    task: executable_task.name().unwrap_or("unnamed").to_string(),
+                    environment: environment.name().clone(),
+                    exit_code: status_code,
+                }
+                .into());
+            }
+        }
+    }
+
+    // Only record the tasks once all of them succeeded, so that failed tasks
+    // run again on the next install.
+    let contents = serde_json::to_string_pretty(&post_install_file).into_diagnostic()?;
+    fs_err::write(post_install_file_path(&environment_dir), contents).into_diagnostic()?;
+
+    Ok(())
+}
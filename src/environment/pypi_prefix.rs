@@ -3,8 +3,8 @@ use std::path::Path;
 use miette::IntoDiagnostic;
 use rattler::install::PythonInfo;
 
-use crate::install_pypi::PyPIPrefixUpdaterBuilder;
-use crate::{lock_file::UvResolutionContext, prefix::Prefix};
+use crate::install_pypi::{PyPIPrefixUpdaterBuilder, git_checkout::checkout_editable_git_packages};
+use crate::{lock_file::UvResolutionContext, prefix::Prefix, reporters::json_lines::EventContext};
 use fancy_display::FancyDisplay;
 use pixi_consts::consts;
 use pixi_manifest::pypi::pypi_options::NoBuildIsolation;
@@ -13,10 +13,11 @@ use pixi_progress::await_in_progress;
 use pixi_record::PixiRecord;
 use rattler_conda_types::Platform;
 use rattler_lock::{PypiIndexes, PypiPackageData, PypiPackageEnvironmentData};
+use reqwest_middleware::ClientWithMiddleware;
 use std::collections::HashMap;
 use uv_distribution_types::{InstalledDist, Name};
 
-use super::PythonStatus;
+use super::{PendingOperation, PythonStatus, pending_pypi_links, pending_pypi_operations};
 
 /// If the python interpreter is outdated, we need to uninstall all outdated
 /// site packages. from the old interpreter.
@@ -136,6 +137,7 @@ pub async fn update_prefix_pypi(
     status: &PythonStatus,
     system_requirements: &SystemRequirements,
     uv_context: &UvResolutionContext,
+    client: &ClientWithMiddleware,
     pypi_indexes: Option<&PypiIndexes>,
     environment_variables: &HashMap<String, String>,
     lock_file_dir: &Path,
@@ -157,6 +159,9 @@ pub async fn update_prefix_pypi(
             environment_name.fancy_display()
         ),
         |_| async {
+            // Editable git packages are installed from a checkout in the prefix
+            let pypi_records = checkout_editable_git_packages(prefix, pypi_records, client).await?;
+
             PyPIPrefixUpdaterBuilder::new(
                 lock_file_dir,
                 prefix,
@@ -172,10 +177,61 @@ pub async fn update_prefix_pypi(
                 no_binary,
             )
             .await?
-            .build(pypi_records)?
+            .build(&pypi_records)?
+            .with_event_context(EventContext {
+                environment: environment_name.to_string(),
+                platform,
+            })
             .update()
             .await
         },
     )
     .await
 }
+
+/// Returns the operations that updating the PyPI packages of the prefix would
+/// perform after the conda packages are updated, without downloading or
+/// modifying anything.
+#[allow(clippy::too_many_arguments)]
+pub async fn pending_prefix_pypi(
+    prefix: &Prefix,
+    pixi_records: &[PixiRecord],
+    pypi_records: &[(PypiPackageData, PypiPackageEnvironmentData)],
+    status: &PythonStatus,
+    system_requirements: &SystemRequirements,
+    uv_context: &UvResolutionContext,
+    pypi_indexes: Option<&PypiIndexes>,
+    lock_file_dir: &Path,
+    platform: Platform,
+    non_isolated_packages: &NoBuildIsolation,
+    no_build: &pixi_manifest::pypi::pypi_options::NoBuild,
+    no_binary: &pixi_manifest::pypi::pypi_options::NoBinary,
+) -> miette::Result<Vec<PendingOperation>> {
+    let python_info = match status {
+        // The packages are installed into the site-packages of the new
+        // interpreter
+        PythonStatus::Added { .. } | PythonStatus::Changed { .. } => {
+            return Ok(pending_pypi_links(pypi_records));
+        }
+        PythonStatus::Unchanged(python_info) => python_info,
+        PythonStatus::Removed { .. } | PythonStatus::DoesNotExist => return Ok(Vec::new()),
+    };
+
+    let plan = PyPIPrefixUpdaterBuilder::new(
+        lock_file_dir,
+        prefix,
+        pixi_records,
+        &python_info.path,
+        system_requirements,
+        uv_context,
+        pypi_indexes,
+        &HashMap::new(),
+        platform,
+        non_isolated_packages,
+        no_build,
+        no_binary,
+    )
+    .await?
+    .plan(pypi_records)?;
+    Ok(pending_pypi_operations(&plan, pypi_records))
+}
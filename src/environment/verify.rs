@@ -0,0 +1,147 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use miette::IntoDiagnostic;
+use rattler_conda_types::{
+    PackageName, PrefixRecord,
+    prefix_record::{PathType, PathsEntry},
+};
+use rattler_digest::Sha256;
+use rayon::prelude::*;
+
+use crate::prefix::Prefix;
+
+/// The result of verifying the content of the files in a prefix.
+#[derive(Debug, Default)]
+pub struct PrefixVerification {
+    /// The number of files whose content was compared with the record.
+    pub checked_files: usize,
+
+    /// The number of files that contain a prefix placeholder but have no
+    /// recorded digest of the linked file, their content can't be compared.
+    pub unverifiable_files: usize,
+
+    /// The files of each package that are missing or whose content doesn't
+    /// match the `conda-meta` record of the package.
+    pub corrupted: BTreeMap<PackageName, Vec<PathBuf>>,
+}
+
+impl PrefixVerification {
+    /// Returns the names of the packages that have to be linked again.
+    pub fn corrupted_packages(&self) -> BTreeSet<PackageName> {
+        self.corrupted.keys().cloned().collect()
+    }
+}
+
+/// Checks the size and the `sha256` of every file that the installed conda
+/// packages recorded in the `conda-meta` directory of the prefix. The files
+/// are hashed in parallel. With `packages` only the files of those packages
+/// are checked.
+pub async fn verify_prefix(
+    prefix: &Prefix,
+    packages: Option<&BTreeSet<PackageName>>,
+) -> miette::Result<PrefixVerification> {
+    if !prefix.root().exists() {
+        return Ok(PrefixVerification::default());
+    }
+
+    let prefix = prefix.clone();
+    let packages = packages.cloned();
+    tokio::task::spawn_blocking(move || {
+        // This also initializes the rayon thread pool that hashes the files
+        let records = prefix.find_installed_packages().into_diagnostic()?;
+
+        let files = records
+            .iter()
+            .filter(|record| {
+                packages.as_ref().is_none_or(|packages| {
+                    packages.contains(&record.repodata_record.package_record.name)
+                })
+            })
+            .flat_map(|record: &PrefixRecord| {
+                let name = &record.repodata_record.package_record.name;
+                record
+                    .paths_data
+                    .paths
+                    .iter()
+                    .filter(|entry| verifiable(entry))
+                    .map(move |entry| (name, entry))
+            })
+            .collect::<Vec<_>>();
+
+        let statuses = files
+            .into_par_iter()
+            .map(|(name, entry)| (name, entry, file_status(prefix.root(), entry)))
+            .collect::<Vec<_>>();
+
+        let mut verification = PrefixVerification::default();
+        for (name, entry, status) in statuses {
+            match status {
+                FileStatus::Intact => verification.checked_files += 1,
+                FileStatus::Corrupted => {
+                    verification.checked_files += 1;
+                    verification
+                        .corrupted
+                        .entry(name.clone())
+                        .or_default()
+                        .push(entry.relative_path.clone());
+                }
+                FileStatus::Unverifiable => verification.unverifiable_files += 1,
+            }
+        }
+
+        Ok(verification)
+    })
+    .await
+    .into_diagnostic()?
+}
+
+/// Only regular files can be compared with their record, links and
+/// directories don't have a recorded digest.
+fn verifiable(entry: &PathsEntry) -> bool {
+    matches!(entry.path_type, PathType::HardLink)
+}
+
+/// The result of comparing a file in the prefix with its record.
+enum FileStatus {
+    Intact,
+    Corrupted,
+    Unverifiable,
+}
+
+/// Compares the size and digest of the file in the prefix with the values
+/// that were recorded when it was linked.
+fn file_status(prefix: &Path, entry: &PathsEntry) -> FileStatus {
+    let path = prefix.join(&entry.relative_path);
+    let Ok(metadata) = fs_err::metadata(&path) else {
+        return FileStatus::Corrupted;
+    };
+
+    // The placeholder in a file is replaced by the path of the prefix when it
+    // is linked, so only the digest of the linked file can be compared.
+    let expected = match (&entry.sha256_in_prefix, &entry.prefix_placeholder) {
+        (Some(sha256), _) => sha256,
+        (None, None) => {
+            if entry
+                .size_in_bytes
+                .is_some_and(|size| size != metadata.len())
+            {
+                return FileStatus::Corrupted;
+            }
+            match &entry.sha256 {
+                Some(sha256) => sha256,
+                None if entry.size_in_bytes.is_some() => return FileStatus::Intact,
+                None => return FileStatus::Unverifiable,
+            }
+        }
+        (None, Some(_)) => return FileStatus::Unverifiable,
+    };
+    if rattler_digest::compute_file_digest::<Sha256>(&path).is_ok_and(|digest| digest == *expected)
+    {
+        FileStatus::Intact
+    } else {
+        FileStatus::Corrupted
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use miette::{Context, IntoDiagnostic};
+use pixi_consts::consts;
+use pixi_git::{GitUrl, source::GitSource};
+use pixi_record::LockedGitUrl;
+use rattler_lock::{PypiPackageData, UrlOrPath};
+use reqwest_middleware::ClientWithMiddleware;
+use typed_path::Utf8TypedPathBuf;
+
+use super::{CombinedPypiPackageData, utils::strip_direct_scheme};
+use crate::prefix::Prefix;
+
+/// The directory in the prefix where editable git dependencies are checked
+/// out.
+const EDITABLE_GIT_SOURCE_DIR: &str = "src";
+
+/// Checks out the editable git packages into the `src` directory of the prefix
+/// and points their location to that checkout, so they are installed like any
+/// other editable directory.
+///
+/// An existing checkout is never replaced, because it may contain changes of
+/// the user. A warning is printed when it is not at the locked commit.
+pub(crate) async fn checkout_editable_git_packages(
+    prefix: &Prefix,
+    pypi_records: &[CombinedPypiPackageData],
+    client: &ClientWithMiddleware,
+) -> miette::Result<Vec<CombinedPypiPackageData>> {
+    let mut records = pypi_records.to_vec();
+    let mut cache_dir = None;
+    for (package, _) in records.iter_mut() {
+        let Some(locked_git_url) = editable_git_url(package) else {
+            continue;
+        };
+
+        let cache_dir = match &cache_dir {
+            Some(cache_dir) => cache_dir,
+            None => cache_dir.insert(pixi_config::get_cache_dir()?.join(consts::CACHED_GIT_DIR)),
+        };
+
+        let pinned = locked_git_url.to_pinned_git_spec()?;
+        let git_url = GitUrl::from_commit(
+            pinned.git.clone(),
+            pinned.source.reference.clone().into(),
+            pinned.source.commit,
+        );
+        let checkout_path = prefix
+            .root()
+            .join(EDITABLE_GIT_SOURCE_DIR)
+            .join(package.name.as_ref());
+
+        let source = GitSource::new(git_url, client.clone(), cache_dir.clone())
+            .with_checkout_path(checkout_path);
+        let fetch = tokio::task::spawn_blocking(move || source.fetch())
+            .await
+            .into_diagnostic()?
+            .into_diagnostic()
+            .with_context(|| format!("failed to check out '{}'", pinned.git))?;
+
+        let path: PathBuf = match &pinned.source.subdirectory {
+            Some(subdirectory) => fetch.path().join(subdirectory),
+            None => fetch.into_path(),
+        };
+        package.location =
+            UrlOrPath::Path(Utf8TypedPathBuf::from(path.to_string_lossy().to_string()));
+    }
+
+    Ok(records)
+}
+
+/// Returns the locked git url of the package if it should be installed
+/// editable from git.
+fn editable_git_url(package: &PypiPackageData) -> Option<LockedGitUrl> {
+    if !package.editable {
+        return None;
+    }
+    let UrlOrPath::Url(url) = &package.location else {
+        return None;
+    };
+    let url = strip_direct_scheme(url);
+    LockedGitUrl::is_locked_git_url(&url).then(|| LockedGitUrl::new(url.into_owned()))
+}
@@ -23,7 +23,7 @@ use rattler_conda_types::Platform;
 use rattler_lock::{PypiIndexes, PypiPackageData, PypiPackageEnvironmentData};
 use utils::elapsed;
 use uv_auth::store_credentials_from_url;
-use uv_client::{Connectivity, FlatIndexClient, RegistryClient, RegistryClientBuilder};
+use uv_client::{FlatIndexClient, RegistryClient, RegistryClientBuilder};
 use uv_configuration::{BuildOptions, ConfigSettings, Constraints, IndexStrategy, PreviewMode};
 use uv_dispatch::{BuildDispatch, SharedState};
 use uv_distribution::{DistributionDatabase, RegistryWheelIndex};
@@ -40,11 +40,13 @@ use uv_workspace::WorkspaceCache;
 use crate::{
     lock_file::UvResolutionContext,
     prefix::Prefix,
+    reporters::json_lines::{self, EventContext, JsonLinesUvReporter},
     uv_reporter::{UvReporter, UvReporterOptions},
 };
 
 pub(crate) mod conda_pypi_clobber;
 pub(crate) mod conversions;
+pub(crate) mod git_checkout;
 pub(crate) mod install_wheel;
 pub(crate) mod plan;
 pub(crate) mod utils;
@@ -104,7 +106,7 @@ impl<'a> PyPIPrefixUpdaterBuilder<'a> {
         let mut uv_client_builder = RegistryClientBuilder::new(uv_context.cache.clone())
             .allow_insecure_host(uv_context.allow_insecure_host.clone())
             .keyring(uv_context.keyring_provider)
-            .connectivity(Connectivity::Online)
+            .connectivity(uv_context.connectivity)
             .extra_middleware(uv_context.extra_middleware.clone())
             .index_locations(&index_locations);
 
@@ -118,7 +120,7 @@ impl<'a> PyPIPrefixUpdaterBuilder<'a> {
         // In UV 0.7.8, we need to fetch flat index entries from the index locations
         let flat_index_client = FlatIndexClient::new(
             registry_client.cached_client(),
-            Connectivity::Online,
+            uv_context.connectivity,
             &uv_context.cache,
         );
         let flat_index_urls: Vec<&IndexUrl> = index_locations
@@ -170,11 +172,12 @@ impl<'a> PyPIPrefixUpdaterBuilder<'a> {
         })
     }
 
-    /// Builds the installation plan and creates an updater
-    pub fn build(
-        self,
+    /// Determines which packages have to be installed, reinstalled or removed
+    /// without changing the prefix.
+    pub fn plan(
+        &self,
         python_packages: &[CombinedPypiPackageData],
-    ) -> miette::Result<PyPIPrefixUpdater> {
+    ) -> miette::Result<PyPIInstallationPlan> {
         // Create a map of the required packages
         let required_map: std::collections::HashMap<uv_normalize::PackageName, &PypiPackageData> =
             python_packages
@@ -221,6 +224,16 @@ impl<'a> PyPIPrefixUpdaterBuilder<'a> {
             total_required
         );
 
+        Ok(installation_plan)
+    }
+
+    /// Builds the installation plan and creates an updater
+    pub fn build(
+        self,
+        python_packages: &[CombinedPypiPackageData],
+    ) -> miette::Result<PyPIPrefixUpdater> {
+        let installation_plan = self.plan(python_packages)?;
+
         // Create the updater
         let updater = PyPIPrefixUpdater {
             prefix: self.prefix,
@@ -235,6 +248,7 @@ impl<'a> PyPIPrefixUpdaterBuilder<'a> {
             index_locations: self.index_locations,
             build_isolation: self.build_isolation,
             installation_plan,
+            json_lines_reporter: None,
         };
 
         Ok(updater)
@@ -255,9 +269,18 @@ pub struct PyPIPrefixUpdater {
     index_locations: IndexLocations,
     build_isolation: BuildIsolation,
     installation_plan: PyPIInstallationPlan,
+    json_lines_reporter: Option<Arc<JsonLinesUvReporter>>,
 }
 
 impl PyPIPrefixUpdater {
+    /// Reports the progress as JSON lines events of the given environment when
+    /// JSON lines reporting is enabled, instead of drawing progress bars.
+    pub fn with_event_context(mut self, context: EventContext) -> Self {
+        self.json_lines_reporter =
+            json_lines::json_lines().map(|sink| JsonLinesUvReporter::new_arc(sink, context));
+        self
+    }
+
     /// Remove metadata for duplicate packages
     fn remove_duplicate_metadata(&self, duplicates: &[InstalledDist]) -> std::io::Result<()> {
         for duplicate in duplicates {
@@ -372,6 +395,15 @@ impl PyPIPrefixUpdater {
         // Log installation details for debugging
         self.log_installation_details(local, remote, reinstalls, extraneous, duplicates);
 
+        if let Some(reporter) = &self.json_lines_reporter {
+            if let Some(sink) = json_lines::json_lines() {
+                sink.add_cache_lookups(local.len() + remote.len());
+            }
+            reporter.transaction_started(
+                local.len() + remote.len() + reinstalls.len() + extraneous.len(),
+            );
+        }
+
         // Download, build, and unzip any missing distributions.
         let remote_dists = if remote.is_empty() {
             Vec::new()
@@ -399,6 +431,9 @@ impl PyPIPrefixUpdater {
             .await?;
 
         self.install_distributions(all_dists).await?;
+        if let Some(reporter) = &self.json_lines_reporter {
+            reporter.transaction_finished();
+        }
         tracing::info!("{}", format!("finished in {}", elapsed(start.elapsed())));
 
         Ok(())
@@ -540,8 +575,11 @@ impl PyPIPrefixUpdater {
             &uv_types::HashStrategy::None,
             &self.build_options,
             distribution_database,
-        )
-        .with_reporter(UvReporter::new_arc(options));
+        );
+        let preparer = match &self.json_lines_reporter {
+            Some(reporter) => preparer.with_reporter(reporter.clone()),
+            None => preparer.with_reporter(UvReporter::new_arc(options)),
+        };
 
         let resolution = Resolution::default();
         let remote_dists = preparer
@@ -671,10 +709,17 @@ impl PyPIPrefixUpdater {
 
         let start = std::time::Instant::now();
 
-        uv_installer::Installer::new(&self.venv)
+        let installer = uv_installer::Installer::new(&self.venv)
             .with_link_mode(LinkMode::default())
-            .with_installer_name(Some(consts::PIXI_UV_INSTALLER.to_string()))
-            .with_reporter(UvReporter::new_arc(options))
+            .with_installer_name(Some(consts::PIXI_UV_INSTALLER.to_string()));
+        let installer = match &self.json_lines_reporter {
+            Some(reporter) => {
+                reporter.link_started(&all_dists);
+                installer.with_reporter(reporter.clone())
+            }
+            None => installer.with_reporter(UvReporter::new_arc(options)),
+        };
+        installer
             .install(all_dists.clone())
             .await
             .expect("should be able to install all distributions");
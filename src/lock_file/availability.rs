@@ -0,0 +1,164 @@
+use std::io::Write;
+
+use fancy_display::FancyDisplay;
+use itertools::Itertools;
+use miette::IntoDiagnostic;
+use pixi_consts::consts;
+use pixi_manifest::{EnvironmentName, FeaturesExt};
+use pixi_progress::await_in_progress;
+use rattler_conda_types::{MatchSpec, Platform};
+use rattler_lock::{CondaPackageData, LockFile, LockedPackageRef, UrlOrPath};
+use rattler_repodata_gateway::{ChannelConfig, fetch::CacheAction};
+use serde::Serialize;
+use tabwriter::TabWriter;
+use url::Url;
+
+use crate::{Workspace, repodata::Repodata, workspace::grouped_environment::GroupedEnvironment};
+
+/// A locked conda package whose file no longer appears in the repodata of the
+/// channel it was locked from, e.g. because it was yanked.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnavailablePackage {
+    pub environment: EnvironmentName,
+    pub platform: Platform,
+    pub file_name: String,
+    /// The channel the package was expected in.
+    pub channel: String,
+}
+
+/// Looks up the file of every locked binary conda package of the selected
+/// environments and platforms in the repodata of the channel it was locked
+/// from. The cached repodata is used if it is still valid, unless `refresh` is
+/// set. No packages are downloaded.
+pub(crate) async fn find_unavailable_packages(
+    workspace: &Workspace,
+    lock_file: &LockFile,
+    mut selected: impl FnMut(&str, Platform) -> bool,
+    refresh: bool,
+) -> miette::Result<Vec<UnavailablePackage>> {
+    let refreshed_gateway;
+    let gateway = if refresh {
+        let mut channel_config = ChannelConfig::from(workspace.config());
+        channel_config.default.cache_action = CacheAction::NoCache;
+        for source_config in channel_config.per_channel.values_mut() {
+            source_config.cache_action = CacheAction::NoCache;
+        }
+        refreshed_gateway = workspace
+            .config()
+            .gateway()
+            .with_channel_config(channel_config)
+            .with_client(workspace.authenticated_client()?.clone())
+            .finish();
+        &refreshed_gateway
+    } else {
+        workspace.repodata_gateway()?
+    };
+    let channel_config = workspace.channel_config();
+
+    let mut unavailable = Vec::new();
+    for (environment_name, locked_environment) in lock_file
+        .environments()
+        .sorted_by_key(|(name, _)| name.to_string())
+    {
+        let Some(environment) = workspace.environment(environment_name) else {
+            continue;
+        };
+        let channels = GroupedEnvironment::from(environment.clone())
+            .channels()
+            .into_iter()
+            .cloned()
+            .map(|channel| channel.into_channel(&channel_config))
+            .collect::<Result<Vec<_>, _>>()
+            .into_diagnostic()?;
+
+        for (platform, packages) in locked_environment
+            .packages_by_platform()
+            .sorted_by_key(|(platform, _)| platform.as_str())
+        {
+            if !selected(environment_name, platform) {
+                continue;
+            }
+            let locked = packages
+                .filter_map(|package| match package {
+                    LockedPackageRef::Conda(CondaPackageData::Binary(binary)) => {
+                        match &binary.location {
+                            UrlOrPath::Url(url) => Some((binary, url)),
+                            UrlOrPath::Path(_) => None,
+                        }
+                    }
+                    _ => None,
+                })
+                .collect_vec();
+            if locked.is_empty() {
+                continue;
+            }
+
+            let specs = locked
+                .iter()
+                .map(|(binary, _)| MatchSpec::from(binary.package_record.name.clone()))
+                .collect_vec();
+            let repodata = await_in_progress(
+                format!("fetching repodata for {}:{}", environment_name, platform),
+                |_| async {
+                    gateway
+                        .query(channels.clone(), [platform, Platform::NoArch], specs)
+                        .execute()
+                        .await
+                        .into_diagnostic()
+                },
+            )
+            .await?;
+
+            for (_, url) in locked {
+                let expected = split_package_url(url);
+                let available = repodata
+                    .iter()
+                    .flat_map(|repodata| repodata.iter())
+                    .any(|record| split_package_url(&record.url) == expected);
+                if !available {
+                    let (channel, file_name) = expected;
+                    unavailable.push(UnavailablePackage {
+                        environment: environment.name().clone(),
+                        platform,
+                        file_name,
+                        channel,
+                    });
+                }
+            }
+        }
+    }
+    Ok(unavailable)
+}
+
+/// Splits the url of a package into the url of its channel and its file name.
+fn split_package_url(url: &Url) -> (String, String) {
+    let mut segments = url
+        .path_segments()
+        .map(|segments| segments.collect_vec())
+        .unwrap_or_default();
+    let file_name = segments.pop().unwrap_or_default().to_string();
+    // Drop the subdirectory of the package
+    segments.pop();
+
+    let mut channel = url.clone();
+    channel.set_path(&format!("{}/", segments.join("/")));
+    channel.set_query(None);
+    (channel.to_string(), file_name)
+}
+
+/// Prints the locked packages that are no longer available, grouped by
+/// environment and platform.
+pub fn print_unavailable_packages(unavailable: &[UnavailablePackage]) -> std::io::Result<()> {
+    let mut writer = TabWriter::new(std::io::stderr());
+    for package in unavailable {
+        writeln!(
+            writer,
+            "  {}:{}\t{}\t{}",
+            package.environment.fancy_display(),
+            consts::PLATFORM_STYLE.apply_to(package.platform),
+            console::style(&package.file_name).bold(),
+            package.channel
+        )?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,62 @@
+use std::cmp::Ordering;
+
+use itertools::Itertools;
+use rattler_lock::{CondaPackageData, LockFile, LockedPackageRef};
+
+/// Rebuilds the lock-file with the environments, platforms and packages in a
+/// stable order, so that locking the same manifest always writes the same
+/// file, independent of the order in which the channels returned the
+/// packages. The order of the channels and indexes of an environment is kept,
+/// because it determines their priority.
+pub(crate) fn canonicalize_lock_file(lock_file: &LockFile) -> LockFile {
+    let mut builder = LockFile::builder();
+    for (environment_name, environment) in lock_file
+        .environments()
+        .sorted_by_key(|(name, _)| name.to_string())
+    {
+        builder.set_channels(environment_name, environment.channels().to_vec());
+        builder.set_options(environment_name, environment.solve_options().clone());
+        if let Some(indexes) = environment.pypi_indexes() {
+            builder.set_pypi_indexes(environment_name, indexes.clone());
+        }
+
+        for (platform, packages) in environment
+            .packages_by_platform()
+            .sorted_by_key(|(platform, _)| platform.as_str())
+        {
+            for package in packages.sorted_by(compare_packages) {
+                builder.add_package(environment_name, platform, package.into());
+            }
+        }
+    }
+    builder.finish()
+}
+
+/// Orders the conda packages before the pypi packages, and the packages of a
+/// kind by name, then version, then build.
+fn compare_packages(a: &LockedPackageRef<'_>, b: &LockedPackageRef<'_>) -> Ordering {
+    match (a, b) {
+        (LockedPackageRef::Conda(a), LockedPackageRef::Conda(b)) => {
+            let (a_record, b_record) = (a.record(), b.record());
+            a_record
+                .name
+                .cmp(&b_record.name)
+                .then_with(|| a_record.version.cmp(&b_record.version))
+                .then_with(|| a_record.build.cmp(&b_record.build))
+                .then_with(|| a_record.subdir.cmp(&b_record.subdir))
+                .then_with(|| is_source(a).cmp(&is_source(b)))
+                .then_with(|| a.location().to_string().cmp(&b.location().to_string()))
+        }
+        (LockedPackageRef::Pypi(a, _), LockedPackageRef::Pypi(b, _)) => a
+            .name
+            .cmp(&b.name)
+            .then_with(|| a.version.cmp(&b.version))
+            .then_with(|| a.location.to_string().cmp(&b.location.to_string())),
+        (LockedPackageRef::Conda(_), LockedPackageRef::Pypi(..)) => Ordering::Less,
+        (LockedPackageRef::Pypi(..), LockedPackageRef::Conda(_)) => Ordering::Greater,
+    }
+}
+
+fn is_source(package: &CondaPackageData) -> bool {
+    matches!(package, CondaPackageData::Source(_))
+}
@@ -0,0 +1,550 @@
+use std::str::FromStr;
+
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use pixi_manifest::FeaturesExt;
+use pixi_record::PixiRecord;
+use pixi_utils::conda_lock_file::{
+    CondaLockChannel, CondaLockFile, CondaLockHash, CondaLockManager, CondaLockMetadata,
+    CondaLockPackage,
+};
+use rattler_conda_types::{
+    PackageName, PackageRecord, Platform, RepoDataRecord, VersionWithSource,
+};
+use rattler_digest::{Md5, Sha256, parse_digest_from_hex};
+use rattler_lock::{
+    CondaPackageData, LockFile, LockedPackageRef, PackageHashes, PypiPackageData,
+    PypiPackageEnvironmentData, UrlOrPath,
+};
+use url::Url;
+
+use crate::{
+    Workspace,
+    workspace::{Environment, grouped_environment::GroupedEnvironment},
+};
+
+/// Creates a lock-file for the default environment of the workspace from the
+/// packages of a conda-lock file, so that the environment can be installed
+/// without solving it first.
+pub(crate) fn lock_file_from_conda_lock(
+    workspace: &Workspace,
+    conda_lock: &CondaLockFile,
+) -> miette::Result<LockFile> {
+    import_conda_lock(
+        workspace,
+        &workspace.default_environment(),
+        conda_lock,
+        &LockFile::default(),
+    )
+}
+
+/// Replaces the locked packages of an environment in the lock-file with the
+/// packages of a conda-lock file. The other environments of the lock-file are
+/// kept as they are.
+pub(crate) fn import_conda_lock(
+    workspace: &Workspace,
+    environment: &Environment<'_>,
+    conda_lock: &CondaLockFile,
+    lock_file: &LockFile,
+) -> miette::Result<LockFile> {
+    let environment_name = environment.name().to_string();
+    let grouped_env = GroupedEnvironment::from(environment.clone());
+
+    let channel_config = workspace.channel_config();
+    let channels: Vec<String> = grouped_env
+        .channels()
+        .into_iter()
+        .cloned()
+        .map(|channel| {
+            channel
+                .into_base_url(&channel_config)
+                .map(|ch| ch.to_string())
+        })
+        .try_collect()
+        .into_diagnostic()?;
+
+    let mut builder = LockFile::builder();
+    for (name, locked_environment) in lock_file.environments() {
+        if name == environment_name {
+            continue;
+        }
+        builder.set_channels(name, locked_environment.channels().to_vec());
+        builder.set_options(name, locked_environment.solve_options().clone());
+        if let Some(indexes) = locked_environment.pypi_indexes() {
+            builder.set_pypi_indexes(name, indexes.clone());
+        }
+        for (platform, packages) in locked_environment.packages_by_platform() {
+            for package in packages {
+                builder.add_package(name, platform, package.into());
+            }
+        }
+    }
+
+    builder.set_channels(&environment_name, channels);
+    builder.set_options(
+        &environment_name,
+        rattler_lock::SolveOptions {
+            strategy: grouped_env.solve_strategy(),
+            channel_priority: grouped_env
+                .channel_priority()
+                .unwrap_or_default()
+                .unwrap_or_default()
+                .into(),
+            exclude_newer: grouped_env.exclude_newer(),
+        },
+    );
+
+    let mut has_pypi_records = false;
+    for platform in environment.platforms() {
+        for package in conda_lock.packages(platform, CondaLockManager::Conda) {
+            let record = repodata_record(package)
+                .wrap_err_with(|| format!("failed to import the package '{}'", package.name))?;
+            builder.add_conda_package(
+                &environment_name,
+                platform,
+                PixiRecord::Binary(record).into(),
+            );
+        }
+        for package in conda_lock.packages(platform, CondaLockManager::Pip) {
+            let package_data = pypi_package_data(package)
+                .wrap_err_with(|| format!("failed to import the package '{}'", package.name))?;
+            builder.add_pypi_package(
+                &environment_name,
+                platform,
+                package_data,
+                PypiPackageEnvironmentData::default(),
+            );
+            has_pypi_records = true;
+        }
+    }
+    if has_pypi_records {
+        builder.set_pypi_indexes(&environment_name, grouped_env.pypi_options().into());
+    }
+
+    Ok(builder.finish())
+}
+
+/// Creates a conda-lock file from the locked packages of an environment. The
+/// pypi packages are written with the `pip` manager.
+pub(crate) fn conda_lock_from_lock_file(
+    lock_file: &LockFile,
+    environment_name: &str,
+    sources: Vec<String>,
+) -> miette::Result<CondaLockFile> {
+    let Some(locked_environment) = lock_file.environment(environment_name) else {
+        miette::bail!("the lock-file does not contain the environment '{environment_name}'");
+    };
+
+    let mut package = Vec::new();
+    for (platform, packages) in locked_environment
+        .packages_by_platform()
+        .sorted_by_key(|(platform, _)| platform.as_str())
+    {
+        for locked_package in packages {
+            let conda_lock_package = match locked_package {
+                LockedPackageRef::Conda(CondaPackageData::Binary(binary)) => {
+                    let record = RepoDataRecord::try_from(binary.clone()).into_diagnostic()?;
+                    conda_lock_conda_package(&record, platform)
+                }
+                LockedPackageRef::Conda(CondaPackageData::Source(source)) => {
+                    miette::bail!(
+                        "the source package '{}' can't be exported to a conda-lock file",
+                        source.package_record.name.as_source()
+                    )
+                }
+                LockedPackageRef::Pypi(data, _) => conda_lock_pip_package(data, platform)?,
+            };
+            package.push(conda_lock_package);
+        }
+    }
+    package.sort_by(|a, b| (a.platform.as_str(), &a.name).cmp(&(b.platform.as_str(), &b.name)));
+
+    Ok(CondaLockFile {
+        version: 1,
+        metadata: CondaLockMetadata {
+            content_hash: Default::default(),
+            channels: locked_environment
+                .channels()
+                .iter()
+                .map(|channel| CondaLockChannel {
+                    url: channel.url.clone(),
+                    used_env_vars: channel.used_env_vars.clone(),
+                })
+                .collect(),
+            platforms: locked_environment
+                .platforms()
+                .sorted_by_key(|platform| platform.as_str())
+                .collect(),
+            sources,
+        },
+        package,
+    })
+}
+
+/// Converts a locked conda package into a package of a conda-lock file. The
+/// dependencies are split into the name and the spec of the dependency.
+fn conda_lock_conda_package(record: &RepoDataRecord, platform: Platform) -> CondaLockPackage {
+    let package_record = &record.package_record;
+    CondaLockPackage {
+        name: package_record.name.as_normalized().to_string(),
+        version: package_record.version.to_string(),
+        manager: CondaLockManager::Conda,
+        platform,
+        dependencies: package_record
+            .depends
+            .iter()
+            .map(|depend| match depend.trim().split_once(' ') {
+                Some((name, spec)) => (name.to_string(), spec.trim().to_string()),
+                None => (depend.trim().to_string(), "*".to_string()),
+            })
+            .collect(),
+        url: record.url.to_string(),
+        hash: CondaLockHash {
+            md5: package_record.md5.map(|md5| format!("{md5:x}")),
+            sha256: package_record.sha256.map(|sha256| format!("{sha256:x}")),
+        },
+        category: "main".to_string(),
+        optional: false,
+    }
+}
+
+/// Converts a locked pypi package into a `pip` package of a conda-lock file.
+fn conda_lock_pip_package(
+    data: &PypiPackageData,
+    platform: Platform,
+) -> miette::Result<CondaLockPackage> {
+    let UrlOrPath::Url(url) = &data.location else {
+        miette::bail!(
+            "the pypi package '{}' is installed from a path and can't be exported to a conda-lock file",
+            data.name
+        )
+    };
+    if data.editable {
+        miette::bail!(
+            "the editable pypi package '{}' can't be exported to a conda-lock file",
+            data.name
+        )
+    }
+
+    // A conda-lock file only records the version of a dependency, the
+    // dependencies that only apply under a marker (e.g. an extra) are skipped.
+    let dependencies = data
+        .requires_dist
+        .iter()
+        .filter(|requirement| requirement.marker.is_true())
+        .map(|requirement| {
+            let spec = match &requirement.version_or_url {
+                Some(pep508_rs::VersionOrUrl::VersionSpecifier(specifiers))
+                    if !specifiers.is_empty() =>
+                {
+                    specifiers.to_string()
+                }
+                _ => "*".to_string(),
+            };
+            (requirement.name.to_string(), spec)
+        })
+        .collect();
+
+    Ok(CondaLockPackage {
+        name: data.name.to_string(),
+        version: data.version.to_string(),
+        manager: CondaLockManager::Pip,
+        platform,
+        dependencies,
+        url: url.to_string(),
+        hash: CondaLockHash {
+            md5: data
+                .hash
+                .as_ref()
+                .and_then(|hash| hash.md5())
+                .map(|md5| format!("{md5:x}")),
+            sha256: data
+                .hash
+                .as_ref()
+                .and_then(|hash| hash.sha256())
+                .map(|sha256| format!("{sha256:x}")),
+        },
+        category: "main".to_string(),
+        optional: false,
+    })
+}
+
+/// Converts a conda package of a conda-lock file into a record. The build
+/// string and subdir are derived from the url of the package.
+fn repodata_record(package: &CondaLockPackage) -> miette::Result<RepoDataRecord> {
+    let url = Url::parse(&package.url).into_diagnostic()?;
+    let mut segments = url
+        .path_segments()
+        .map(|segments| segments.rev().collect_vec())
+        .unwrap_or_default()
+        .into_iter();
+    let file_name = segments.next().unwrap_or_default().to_string();
+    let subdir = segments
+        .next()
+        .map(ToString::to_string)
+        .unwrap_or_else(|| package.platform.to_string());
+    let Some(build) = file_name
+        .strip_suffix(".conda")
+        .or_else(|| file_name.strip_suffix(".tar.bz2"))
+        .and_then(|stem| stem.rsplit('-').next())
+    else {
+        miette::bail!("'{}' is not the url of a conda package", package.url);
+    };
+
+    let mut package_record = PackageRecord::new(
+        PackageName::from_str(&package.name).into_diagnostic()?,
+        VersionWithSource::from_str(&package.version).into_diagnostic()?,
+        build.to_string(),
+    );
+    // The build number is the last numeric part of the build string, e.g. `1`
+    // of `hab00c5b_1_cpython`
+    package_record.build_number = build
+        .split('_')
+        .filter_map(|part| part.parse().ok())
+        .next_back()
+        .unwrap_or_default();
+    package_record.subdir = subdir;
+    package_record.depends = package
+        .dependencies
+        .iter()
+        .map(|(name, spec)| match spec.trim() {
+            "" | "*" => name.clone(),
+            spec => format!("{name} {spec}"),
+        })
+        .collect();
+    package_record.md5 = package
+        .hash
+        .md5
+        .as_deref()
+        .and_then(parse_digest_from_hex::<Md5>);
+    package_record.sha256 = package
+        .hash
+        .sha256
+        .as_deref()
+        .and_then(parse_digest_from_hex::<Sha256>);
+
+    Ok(RepoDataRecord {
+        package_record,
+        file_name,
+        channel: url.join("..").ok().map(|channel| channel.to_string()),
+        url,
+    })
+}
+
+/// Converts a pip package of a conda-lock file into the locked data of a pypi
+/// package.
+fn pypi_package_data(package: &CondaLockPackage) -> miette::Result<PypiPackageData> {
+    let requires_dist = package
+        .dependencies
+        .iter()
+        .map(|(name, spec)| {
+            let spec = spec.trim();
+            let requirement = if spec == "*" {
+                name.clone()
+            } else {
+                format!("{name}{spec}")
+            };
+            pep508_rs::Requirement::from_str(&requirement)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("can't parse the dependency '{requirement}'"))
+        })
+        .collect::<miette::Result<Vec<_>>>()?;
+
+    Ok(PypiPackageData {
+        name: pep508_rs::PackageName::new(package.name.clone()).into_diagnostic()?,
+        version: pep440_rs::Version::from_str(&package.version).into_diagnostic()?,
+        location: UrlOrPath::Url(Url::parse(&package.url).into_diagnostic()?),
+        hash: package_hashes(&package.hash),
+        requires_dist,
+        requires_python: None,
+        editable: false,
+    })
+}
+
+fn package_hashes(hash: &CondaLockHash) -> Option<PackageHashes> {
+    let md5 = hash.md5.as_deref().and_then(parse_digest_from_hex::<Md5>);
+    let sha256 = hash
+        .sha256
+        .as_deref()
+        .and_then(parse_digest_from_hex::<Sha256>);
+    match (md5, sha256) {
+        (Some(md5), Some(sha256)) => Some(PackageHashes::Md5Sha256(md5, sha256)),
+        (Some(md5), None) => Some(PackageHashes::Md5(md5)),
+        (None, Some(sha256)) => Some(PackageHashes::Sha256(sha256)),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repodata_record() {
+        let package = CondaLockPackage {
+            name: "python".to_string(),
+            version: "3.12.1".to_string(),
+            manager: CondaLockManager::Conda,
+            platform: Platform::Linux64,
+            dependencies: [
+                ("libzlib".to_string(), ">=1.2.13".to_string()),
+                ("tzdata".to_string(), "*".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            url: "https://conda.anaconda.org/conda-forge/linux-64/python-3.12.1-hab00c5b_1_cpython.conda"
+                .to_string(),
+            hash: CondaLockHash {
+                md5: Some("0bab699354cbd66959550eb9b9866620".to_string()),
+                sha256: None,
+            },
+            category: "main".to_string(),
+            optional: false,
+        };
+
+        let record = repodata_record(&package).unwrap();
+        assert_eq!(record.file_name, "python-3.12.1-hab00c5b_1_cpython.conda");
+        assert_eq!(
+            record.channel.as_deref(),
+            Some("https://conda.anaconda.org/conda-forge/")
+        );
+        let package_record = record.package_record;
+        assert_eq!(package_record.build, "hab00c5b_1_cpython");
+        assert_eq!(package_record.build_number, 1);
+        assert_eq!(package_record.subdir, "linux-64");
+        assert_eq!(package_record.depends, vec!["libzlib >=1.2.13", "tzdata"]);
+        assert!(package_record.md5.is_some());
+    }
+
+    #[test]
+    fn test_conda_lock_round_trip() {
+        let package = CondaLockPackage {
+            name: "python".to_string(),
+            version: "3.12.1".to_string(),
+            manager: CondaLockManager::Conda,
+            platform: Platform::Linux64,
+            dependencies: [
+                ("libzlib".to_string(), ">=1.2.13".to_string()),
+                ("tzdata".to_string(), "*".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            url: "https://conda.anaconda.org/conda-forge/linux-64/python-3.12.1-hab00c5b_1_cpython.conda"
+                .to_string(),
+            hash: CondaLockHash {
+                md5: Some("0bab699354cbd66959550eb9b9866620".to_string()),
+                sha256: None,
+            },
+            category: "main".to_string(),
+            optional: false,
+        };
+        let pip_package = CondaLockPackage {
+            name: "requests".to_string(),
+            version: "2.31.0".to_string(),
+            manager: CondaLockManager::Pip,
+            platform: Platform::Linux64,
+            dependencies: [("certifi".to_string(), ">=2017.4.17".to_string())]
+                .into_iter()
+                .collect(),
+            url: "https://files.pythonhosted.org/packages/requests-2.31.0-py3-none-any.whl"
+                .to_string(),
+            hash: CondaLockHash {
+                md5: None,
+                sha256: Some(
+                    "58cd2187c01e70e6e26505bca751777aa9f2ee0b7f4300988b709f44e013003f".to_string(),
+                ),
+            },
+            category: "main".to_string(),
+            optional: false,
+        };
+
+        let mut builder = LockFile::builder();
+        builder.set_channels("default", ["https://conda.anaconda.org/conda-forge/"]);
+        builder.add_conda_package(
+            "default",
+            Platform::Linux64,
+            PixiRecord::Binary(repodata_record(&package).unwrap()).into(),
+        );
+        builder.add_pypi_package(
+            "default",
+            Platform::Linux64,
+            pypi_package_data(&pip_package).unwrap(),
+            PypiPackageEnvironmentData::default(),
+        );
+        let lock_file = builder.finish();
+
+        let exported = conda_lock_from_lock_file(&lock_file, "default", Vec::new()).unwrap();
+        let exported = exported
+            .to_yaml_string()
+            .unwrap()
+            .parse::<CondaLockFile>()
+            .unwrap();
+        assert_eq!(exported.platforms(), vec![Platform::Linux64]);
+
+        let conda = exported
+            .packages(Platform::Linux64, CondaLockManager::Conda)
+            .collect_vec();
+        assert_eq!(conda.len(), 1);
+        assert_eq!(conda[0].url, package.url);
+        assert_eq!(conda[0].dependencies, package.dependencies);
+        assert_eq!(conda[0].hash.md5, package.hash.md5);
+        assert_eq!(
+            repodata_record(conda[0]).unwrap(),
+            repodata_record(&package).unwrap()
+        );
+
+        let pip = exported
+            .packages(Platform::Linux64, CondaLockManager::Pip)
+            .collect_vec();
+        assert_eq!(pip.len(), 1);
+        assert_eq!(pip[0].dependencies, pip_package.dependencies);
+        assert_eq!(
+            pypi_package_data(pip[0]).unwrap(),
+            pypi_package_data(&pip_package).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pip_package_dependencies_without_markers() {
+        let requirement =
+            |requirement: &str| pep508_rs::Requirement::from_str(requirement).unwrap();
+        let data = PypiPackageData {
+            name: pep508_rs::PackageName::new("requests".to_string()).unwrap(),
+            version: pep440_rs::Version::from_str("2.31.0").unwrap(),
+            location: UrlOrPath::Url(
+                Url::parse(
+                    "https://files.pythonhosted.org/packages/requests-2.31.0-py3-none-any.whl",
+                )
+                .unwrap(),
+            ),
+            hash: None,
+            requires_dist: vec![
+                requirement("certifi>=2017.4.17"),
+                requirement("urllib3[socks]<3,>=1.21.1"),
+                requirement("idna"),
+                requirement("PySocks!=1.5.7,>=1.5.6 ; extra == 'socks'"),
+                requirement("chardet<6,>=3.0.2 ; python_version < '3'"),
+            ],
+            requires_python: None,
+            editable: false,
+        };
+
+        let package = conda_lock_pip_package(&data, Platform::Linux64).unwrap();
+        assert_eq!(
+            package.dependencies.keys().collect_vec(),
+            vec!["certifi", "idna", "urllib3"]
+        );
+        assert_eq!(package.dependencies["idna"], "*");
+
+        // The dependencies can be read back without the extras and markers
+        let imported = pypi_package_data(&package).unwrap();
+        assert_eq!(
+            imported.requires_dist,
+            vec![
+                requirement("certifi>=2017.4.17"),
+                requirement("idna"),
+                requirement("urllib3<3,>=1.21.1"),
+            ]
+        );
+    }
+}
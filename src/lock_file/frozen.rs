@@ -0,0 +1,113 @@
+use miette::Diagnostic;
+use pixi_manifest::EnvironmentName;
+use rattler_conda_types::Platform;
+use rattler_lock::LockFile;
+use thiserror::Error;
+
+use super::{PlatformUnsat, verify_platform_satisfiability};
+use crate::{
+    build::GlobHashCache,
+    workspace::{Environment, HasWorkspaceRef},
+};
+
+/// Describes why an environment can't be installed from the lock-file as it
+/// is, which is what `--frozen` requires.
+#[derive(Debug, Error, Diagnostic)]
+pub enum FrozenLockFileError {
+    #[error("the lock-file doesn't contain the environment '{environment}'")]
+    #[diagnostic(help(
+        "run `pixi lock` to add the environment to the lock-file, or install without `--frozen`"
+    ))]
+    MissingEnvironment { environment: EnvironmentName },
+
+    #[error(
+        "the lock-file doesn't contain the platform '{platform}' for the environment '{environment}'"
+    )]
+    #[diagnostic(help(
+        "run `pixi lock` to add the platform to the lock-file, or install without `--frozen`"
+    ))]
+    MissingPlatform {
+        environment: EnvironmentName,
+        platform: Platform,
+    },
+
+    #[error(
+        "the lock-file contains a wheel for '{package}' in the environment '{environment}' for '{platform}', but the pypi-option `no-binary` is set"
+    )]
+    #[diagnostic(help(
+        "run `pixi lock` to lock a source distribution instead, or install without `--frozen`"
+    ))]
+    LockedWheel {
+        environment: EnvironmentName,
+        platform: Platform,
+        package: String,
+    },
+
+    #[error(
+        "the locked packages of the environment '{environment}' for '{platform}' don't satisfy the manifest"
+    )]
+    #[diagnostic(help("run `pixi lock` to update the lock-file, or install without `--frozen`"))]
+    Unsatisfied {
+        environment: EnvironmentName,
+        platform: Platform,
+        #[source]
+        reason: Box<PlatformUnsat>,
+    },
+}
+
+impl FrozenLockFileError {
+    /// Returns `true` if the environment can't be installed at all. The locked
+    /// packages of an unsatisfied environment can still be installed, they
+    /// are just not what the manifest asks for. A wheel of a package that
+    /// `no-binary` applies to is never installed.
+    pub fn is_missing(&self) -> bool {
+        !matches!(self, FrozenLockFileError::Unsatisfied { .. })
+    }
+}
+
+/// Verifies that the lock-file contains the environment for the platform it
+/// is installed on, and that the locked packages still satisfy the manifest.
+pub(crate) async fn verify_frozen_environment(
+    environment: &Environment<'_>,
+    lock_file: &LockFile,
+    glob_hash_cache: GlobHashCache,
+) -> Result<(), FrozenLockFileError> {
+    let Some(locked_environment) = lock_file.environment(environment.name().as_str()) else {
+        return Err(FrozenLockFileError::MissingEnvironment {
+            environment: environment.name().clone(),
+        });
+    };
+
+    let platform = environment.best_platform();
+    if !locked_environment
+        .platforms()
+        .any(|locked| locked == platform)
+    {
+        return Err(FrozenLockFileError::MissingPlatform {
+            environment: environment.name().clone(),
+            platform,
+        });
+    }
+
+    verify_platform_satisfiability(
+        environment,
+        locked_environment,
+        platform,
+        environment.workspace().root(),
+        glob_hash_cache,
+    )
+    .await
+    .map(|_| ())
+    .map_err(|reason| match *reason {
+        PlatformUnsat::NoBinaryWithBinaryPackages(package) => FrozenLockFileError::LockedWheel {
+            environment: environment.name().clone(),
+            platform,
+            package,
+        },
+        reason => FrozenLockFileError::Unsatisfied {
+            environment: environment.name().clone(),
+            platform,
+            reason: Box::new(reason),
+        },
+    })
+}
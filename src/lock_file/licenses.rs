@@ -0,0 +1,294 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use futures::{StreamExt, stream};
+use itertools::Itertools;
+use rattler_conda_types::Platform;
+use rattler_lock::{LockFile, LockedPackageRef, UrlOrPath};
+use reqwest_middleware::ClientWithMiddleware;
+use serde::Serialize;
+use tabwriter::TabWriter;
+use url::Url;
+
+use crate::{Workspace, workspace::Environment};
+
+/// The bucket of the packages without license information.
+pub const UNKNOWN_LICENSE: &str = "unknown";
+
+/// A locked package in the license report.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct LicensedPackage {
+    pub name: String,
+    pub version: String,
+    /// Either `conda` or `pypi`.
+    pub kind: &'static str,
+}
+
+/// The locked packages of an environment grouped by their license.
+#[derive(Debug, Serialize)]
+pub struct LicenseReport {
+    pub environment: String,
+    pub platforms: Vec<Platform>,
+    pub licenses: BTreeMap<String, BTreeSet<LicensedPackage>>,
+}
+
+impl LicenseReport {
+    /// Prints the packages grouped by license, the unknown licenses last.
+    pub fn print(&self) -> std::io::Result<()> {
+        let mut writer = TabWriter::new(std::io::stdout());
+        for (license, packages) in self
+            .licenses
+            .iter()
+            .sorted_by_key(|(license, _)| (license.as_str() == UNKNOWN_LICENSE, license.as_str()))
+        {
+            let license = if license == UNKNOWN_LICENSE {
+                console::style(license.as_str()).red().bold()
+            } else {
+                console::style(license.as_str()).bold()
+            };
+            writeln!(writer, "{} ({})", license, packages.len())?;
+            for package in packages {
+                writeln!(
+                    writer,
+                    "  {}\t{}\t{}",
+                    package.name, package.version, package.kind
+                )?;
+            }
+        }
+        writer.flush()
+    }
+}
+
+/// Groups the locked packages of the environment for the selected platforms by
+/// their license. The license of a conda package is read from the lock-file.
+/// The license of a pypi package is read from the metadata of the package
+/// installed in the environment, and with `refresh` the missing licenses are
+/// fetched from the index. Packages without a license are put in the
+/// [`UNKNOWN_LICENSE`] bucket.
+pub(crate) async fn collect_licenses(
+    workspace: &Workspace,
+    lock_file: &LockFile,
+    environment: &Environment<'_>,
+    platforms: &[Platform],
+    refresh: bool,
+) -> miette::Result<LicenseReport> {
+    let mut licenses: BTreeMap<String, BTreeSet<LicensedPackage>> = BTreeMap::new();
+    let mut pypi_packages = BTreeMap::new();
+    let locked_environment = lock_file.environment(environment.name().as_str());
+    for platform in platforms {
+        let Some(packages) = locked_environment.and_then(|env| env.packages(*platform)) else {
+            continue;
+        };
+        for package in packages {
+            match package {
+                LockedPackageRef::Conda(conda) => {
+                    let record = conda.record();
+                    let license = record
+                        .license
+                        .clone()
+                        .or_else(|| record.license_family.clone());
+                    licenses
+                        .entry(license_bucket(license))
+                        .or_default()
+                        .insert(LicensedPackage {
+                            name: record.name.as_source().to_string(),
+                            version: record.version.to_string(),
+                            kind: "conda",
+                        });
+                }
+                LockedPackageRef::Pypi(data, _) => {
+                    let package = LicensedPackage {
+                        name: data.name.to_string(),
+                        version: data.version.to_string(),
+                        kind: "pypi",
+                    };
+                    let url = match &data.location {
+                        UrlOrPath::Url(url) => Some(url.clone()),
+                        UrlOrPath::Path(_) => None,
+                    };
+                    pypi_packages.insert(package, url);
+                }
+            }
+        }
+    }
+
+    // The metadata of the installed pypi packages is used first, the index is
+    // only queried with `refresh`.
+    let installed = installed_metadata(&environment.dir());
+    let mut missing = Vec::new();
+    for (package, url) in pypi_packages {
+        let metadata = installed.get(&dist_info_key(&package.name, &package.version));
+        match metadata.and_then(|path| fs_err::read_to_string(path).ok()) {
+            Some(metadata) => {
+                licenses
+                    .entry(license_bucket(license_from_metadata(&metadata)))
+                    .or_default()
+                    .insert(package);
+            }
+            None => missing.push((package, url)),
+        }
+    }
+
+    let client = workspace.authenticated_client()?.clone();
+    let fetched = stream::iter(missing)
+        .map(|(package, url)| {
+            let client = client.clone();
+            async move {
+                let license = match url {
+                    Some(url) if refresh => fetch_license(&client, &url).await,
+                    _ => None,
+                };
+                (package, license)
+            }
+        })
+        .buffered(workspace.config().max_concurrent_downloads().max(1))
+        .collect::<Vec<_>>()
+        .await;
+    for (package, license) in fetched {
+        licenses
+            .entry(license_bucket(license))
+            .or_default()
+            .insert(package);
+    }
+
+    Ok(LicenseReport {
+        environment: environment.name().to_string(),
+        platforms: platforms.to_vec(),
+        licenses,
+    })
+}
+
+fn license_bucket(license: Option<String>) -> String {
+    license
+        .map(|license| license.trim().to_string())
+        .filter(|license| !license.is_empty() && !license.eq_ignore_ascii_case("unknown"))
+        .unwrap_or_else(|| UNKNOWN_LICENSE.to_string())
+}
+
+/// The key of an installed distribution, the normalized name and the version.
+fn dist_info_key(name: &str, version: &str) -> String {
+    format!(
+        "{}-{}",
+        name.to_lowercase().replace(['-', '.'], "_"),
+        version
+    )
+}
+
+/// Finds the `METADATA` files of the pypi packages that are installed in the
+/// environment.
+fn installed_metadata(prefix: &Path) -> BTreeMap<String, PathBuf> {
+    let mut site_packages = vec![prefix.join("Lib").join("site-packages")];
+    if let Ok(entries) = fs_err::read_dir(prefix.join("lib")) {
+        site_packages.extend(
+            entries
+                .flatten()
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with("python"))
+                .map(|entry| entry.path().join("site-packages")),
+        );
+    }
+
+    let mut metadata = BTreeMap::new();
+    for dir in site_packages {
+        let Ok(entries) = fs_err::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let Some(stem) = file_name.strip_suffix(".dist-info") else {
+                continue;
+            };
+            let Some((name, version)) = stem.rsplit_once('-') else {
+                continue;
+            };
+            metadata.insert(dist_info_key(name, version), entry.path().join("METADATA"));
+        }
+    }
+    metadata
+}
+
+/// Fetches the core metadata of a distribution from the index, which is
+/// served next to the distribution (PEP 658).
+async fn fetch_license(client: &ClientWithMiddleware, url: &Url) -> Option<String> {
+    let metadata_url = Url::parse(&format!("{}.metadata", url.as_str().split('#').next()?)).ok()?;
+    let response = client.get(metadata_url).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    license_from_metadata(&response.text().await.ok()?)
+}
+
+/// Reads the license from the headers of the core metadata of a python
+/// distribution. `License-Expression` is preferred over `License`, which is
+/// preferred over the license classifiers.
+fn license_from_metadata(metadata: &str) -> Option<String> {
+    let headers = metadata
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(": "))
+        .collect_vec();
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim().to_string())
+    };
+
+    if let Some(expression) = header("License-Expression") {
+        return Some(expression);
+    }
+    // The `License` header often contains the full license text instead of
+    // its identifier, so it is only used if it is a valid SPDX expression
+    if let Some(license) = header("License")
+        .filter(|license| spdx::Expression::parse_mode(license, spdx::ParseMode::LAX).is_ok())
+    {
+        return Some(license);
+    }
+    let classifiers = headers
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case("Classifier"))
+        .filter_map(|(_, value)| value.strip_prefix("License :: "))
+        .filter_map(|value| value.rsplit(" :: ").next())
+        .map(str::trim)
+        .collect_vec();
+    (!classifiers.is_empty()).then(|| classifiers.join(" OR "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_license_from_metadata() {
+        let metadata = "Metadata-Version: 2.4\nName: foo\nLicense-Expression: MIT\nLicense: BSD\n\nLicense: body";
+        assert_eq!(license_from_metadata(metadata).as_deref(), Some("MIT"));
+
+        let metadata = "Metadata-Version: 2.1\nName: foo\nLicense: UNKNOWN\nClassifier: License :: OSI Approved :: Apache Software License\nClassifier: Programming Language :: Python\n";
+        assert_eq!(
+            license_from_metadata(metadata).as_deref(),
+            Some("Apache Software License")
+        );
+
+        let metadata = "Metadata-Version: 2.1\nName: foo\nLicense: Permission is hereby granted, free of charge\nClassifier: License :: OSI Approved :: MIT License\n";
+        assert_eq!(
+            license_from_metadata(metadata).as_deref(),
+            Some("MIT License")
+        );
+
+        let metadata = "Metadata-Version: 2.1\nName: foo\nLicense: BSD-3-Clause OR Apache-2.0\n";
+        assert_eq!(
+            license_from_metadata(metadata).as_deref(),
+            Some("BSD-3-Clause OR Apache-2.0")
+        );
+
+        let metadata = "Metadata-Version: 2.1\nName: foo\n";
+        assert_eq!(license_from_metadata(metadata), None);
+    }
+
+    #[test]
+    fn test_dist_info_key() {
+        assert_eq!(dist_info_key("Foo.Bar-baz", "1.0"), "foo_bar_baz-1.0");
+    }
+}
@@ -1,26 +1,62 @@
+mod availability;
+mod canonical;
+mod conda_lock;
+mod frozen;
+mod licenses;
+mod offline;
 mod outdated;
 mod package_identifier;
+mod prune;
 mod records_by_name;
+mod refresh_metadata;
 mod reporter;
 mod resolve;
 mod satisfiability;
+mod solve_constraints;
 mod update;
 mod utils;
+mod verified_targets;
+mod verify_artifacts;
+mod version;
 pub mod virtual_packages;
 
 pub use crate::environment::CondaPrefixUpdater;
+pub(crate) use availability::find_unavailable_packages;
+pub use availability::{UnavailablePackage, print_unavailable_packages};
+pub(crate) use canonical::canonicalize_lock_file;
+pub(crate) use conda_lock::{
+    conda_lock_from_lock_file, import_conda_lock, lock_file_from_conda_lock,
+};
+pub use frozen::FrozenLockFileError;
+pub(crate) use frozen::verify_frozen_environment;
+pub(crate) use licenses::collect_licenses;
+pub use licenses::{LicenseReport, LicensedPackage, UNKNOWN_LICENSE};
+pub use offline::PackageMetadataNotCachedError;
+pub(crate) use offline::ensure_repodata_cached;
+pub(crate) use outdated::OutdatedEnvironments;
+pub use outdated::OutdatedReason;
 pub(crate) use package_identifier::PypiPackageIdentifier;
 use pixi_record::PixiRecord;
+pub use prune::StaleTarget;
+pub(crate) use prune::{print_pruned_targets, prune_lock_file, restore_stale_targets};
 use rattler_lock::{PypiPackageData, PypiPackageEnvironmentData};
 pub(crate) use records_by_name::{PixiRecordsByName, PypiRecordsByName};
+pub use refresh_metadata::MissingLockedPackagesError;
+pub(crate) use refresh_metadata::refresh_lock_file_metadata;
 pub(crate) use resolve::{pypi::resolve_pypi, uv_resolution_context::UvResolutionContext};
 pub use satisfiability::{
     EnvironmentUnsat, PlatformUnsat, verify_environment_satisfiability,
     verify_platform_satisfiability,
 };
-pub use update::{LockFileDerivedData, ReinstallPackages, UpdateContext};
-pub use update::{UpdateLockFileOptions, UpdateMode};
+pub use solve_constraints::SolveConstraints;
+pub use update::{InstallFilter, LockFileDerivedData, ReinstallPackages, UpdateContext};
+pub use update::{LockFileOutdatedError, UpdateLockFileOptions, UpdateMode};
 pub(crate) use utils::filter_lock_file;
+pub(crate) use verified_targets::VerifiedTargets;
+pub(crate) use verify_artifacts::verify_locked_artifacts;
+pub use verify_artifacts::{ArtifactFailure, ArtifactProblem, ArtifactReport};
+pub use version::UnsupportedLockFileError;
+pub(crate) use version::{lock_file_parse_error, write_lock_file_with_version};
 
 pub use utils::IoConcurrencyLimit;
 
@@ -0,0 +1,155 @@
+use std::{
+    collections::BTreeSet,
+    fmt::{Display, Formatter},
+};
+
+use itertools::Itertools;
+use miette::{Diagnostic, IntoDiagnostic};
+use pixi_manifest::FeaturesExt;
+use rattler_conda_types::{MatchSpec, Platform};
+use rattler_repodata_gateway::{ChannelConfig, Gateway, SourceConfig};
+use thiserror::Error;
+
+use super::OutdatedEnvironments;
+use crate::{Workspace, repodata::Repodata, workspace::grouped_environment::GroupedEnvironment};
+
+/// A subdirectory of a channel of which the repodata is not in the cache.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UncachedRepodata {
+    channel: String,
+    platform: String,
+}
+
+impl Display for UncachedRepodata {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "  - {} ({})", self.channel, self.platform)
+    }
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "package metadata not in cache, the repodata of the following channels is needed to update the lock-file:\n{}",
+    .missing.iter().format("\n")
+)]
+#[diagnostic(help(
+    "run the command once without `--offline` to fill the cache, or set `network.offline` to false"
+))]
+pub struct PackageMetadataNotCachedError {
+    missing: Vec<UncachedRepodata>,
+}
+
+/// Checks that the repodata of every channel and platform that has to be
+/// solved again is in the cache, so that an offline update fails with the list
+/// of missing channels instead of a solve error.
+///
+/// Channels with sharded repodata only need the shards of the requested
+/// packages. When those are not cached the `repodata.json` of the channel is
+/// tried instead, in that case the returned gateway has sharded repodata
+/// disabled for those channels and should be used for the solve.
+pub(crate) async fn ensure_repodata_cached(
+    workspace: &Workspace,
+    outdated: &OutdatedEnvironments<'_>,
+) -> miette::Result<Option<Gateway>> {
+    let gateway = workspace.repodata_gateway()?;
+    let channel_config = workspace.channel_config();
+
+    let mut checked = BTreeSet::new();
+    let mut uncached = Vec::new();
+    for (environment, platforms) in outdated
+        .conda
+        .iter()
+        .sorted_by_key(|(env, _)| env.name().clone())
+    {
+        let group = GroupedEnvironment::from(environment.clone());
+        let channels = group
+            .channels()
+            .into_iter()
+            .cloned()
+            .map(|channel| channel.into_channel(&channel_config))
+            .collect::<Result<Vec<_>, _>>()
+            .into_diagnostic()?;
+
+        for platform in platforms.iter().copied().chain([Platform::NoArch]).unique() {
+            let specs = group
+                .combined_dependencies(Some(platform))
+                .names()
+                .cloned()
+                .map(MatchSpec::from)
+                .collect_vec();
+            if specs.is_empty() {
+                continue;
+            }
+            for channel in &channels {
+                if !checked.insert((channel.base_url.to_string(), platform)) {
+                    continue;
+                }
+                let result = gateway
+                    .query([channel.clone()], [platform], specs.clone())
+                    .execute()
+                    .await;
+                if let Err(err) = result {
+                    tracing::debug!(
+                        "repodata of {} ({platform}) is not cached: {err}",
+                        channel.base_url
+                    );
+                    uncached.push((channel.clone(), platform, specs.clone()));
+                }
+            }
+        }
+    }
+
+    if uncached.is_empty() {
+        return Ok(None);
+    }
+
+    // Retry the channels without sharded repodata, a `repodata.json` that was
+    // cached before sharded repodata was used (or with it disabled) still works.
+    let mut source_configs = ChannelConfig::from(workspace.config());
+    for (channel, _, _) in &uncached {
+        let url = channel.base_url.url().clone();
+        let source_config = source_configs
+            .per_channel
+            .get(&url)
+            .unwrap_or(&source_configs.default)
+            .clone();
+        source_configs.per_channel.insert(
+            url,
+            SourceConfig {
+                sharded_enabled: false,
+                ..source_config
+            },
+        );
+    }
+    let fallback = workspace
+        .config()
+        .gateway()
+        .with_channel_config(source_configs)
+        .with_client(workspace.authenticated_client()?.clone())
+        .finish();
+
+    let mut missing = BTreeSet::new();
+    for (channel, platform, specs) in uncached {
+        let result = fallback
+            .query([channel.clone()], [platform], specs)
+            .execute()
+            .await;
+        if let Err(err) = result {
+            tracing::debug!(
+                "repodata.json of {} ({platform}) is not cached either: {err}",
+                channel.base_url
+            );
+            missing.insert(UncachedRepodata {
+                channel: channel.base_url.to_string(),
+                platform: platform.to_string(),
+            });
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(PackageMetadataNotCachedError {
+            missing: missing.into_iter().collect(),
+        }
+        .into());
+    }
+    Ok(Some(fallback))
+}
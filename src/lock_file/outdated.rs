@@ -3,15 +3,19 @@ use std::collections::{HashMap, HashSet};
 use fancy_display::FancyDisplay;
 use itertools::Itertools;
 use pixi_consts::consts;
-use pixi_manifest::FeaturesExt;
+use pixi_manifest::{EnvironmentName, FeaturesExt};
 use rattler_conda_types::Platform;
 use rattler_lock::{LockFile, LockedPackageRef};
+use serde::Serialize;
 
 use super::{verify_environment_satisfiability, verify_platform_satisfiability};
 use crate::{
     Workspace,
     build::GlobHashCache,
-    lock_file::satisfiability::{EnvironmentUnsat, verify_solve_group_satisfiability},
+    lock_file::{
+        VerifiedTargets,
+        satisfiability::{EnvironmentUnsat, verify_solve_group_satisfiability},
+    },
     workspace::{Environment, SolveGroup},
 };
 
@@ -34,6 +38,40 @@ pub struct OutdatedEnvironments<'p> {
     /// discarded. This is the case for instance when the order of the
     /// channels changed.
     pub disregard_locked_content: DisregardLockedContent<'p>,
+
+    /// Why the targets that are out of date are not satisfied by the
+    /// lock-file.
+    pub reasons: Vec<OutdatedReason>,
+}
+
+/// Describes why an environment, or one of its platforms, is not satisfied by
+/// the lock-file.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutdatedReason {
+    pub environment: EnvironmentName,
+    /// The platform that is out of date, `None` if the whole environment is
+    /// out of date.
+    pub platform: Option<Platform>,
+    /// The solve group that is out of date, if the reason applies to all the
+    /// environments of a solve group.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solve_group: Option<String>,
+    pub reason: String,
+}
+
+impl OutdatedReason {
+    fn new(
+        environment: &Environment<'_>,
+        platform: Option<Platform>,
+        reason: impl ToString,
+    ) -> Self {
+        Self {
+            environment: environment.name().clone(),
+            platform,
+            solve_group: None,
+            reason: reason.to_string(),
+        }
+    }
 }
 
 /// A struct that stores whether the locked content of certain environments
@@ -71,6 +109,7 @@ impl<'p> OutdatedEnvironments<'p> {
             mut outdated_conda,
             mut outdated_pypi,
             disregard_locked_content,
+            mut reasons,
         } = find_unsatisfiable_targets(workspace, lock_file, glob_hash_cache).await;
 
         // Extend the outdated targets to include the solve groups
@@ -115,18 +154,115 @@ impl<'p> OutdatedEnvironments<'p> {
                 .extend(platforms.iter().copied());
         }
 
+        // The remaining targets are out of date because of the other environments
+        // in their solve group.
+        for (environment, platforms) in outdated_conda.iter().chain(outdated_pypi.iter()) {
+            for platform in platforms.iter().sorted_by_key(|p| p.as_str()) {
+                let explained = reasons.iter().any(|reason| {
+                    &reason.environment == environment.name()
+                        && reason.platform.is_none_or(|p| p == *platform)
+                });
+                if explained {
+                    continue;
+                }
+                let solve_group = environment
+                    .solve_group()
+                    .map(|group| group.name().to_string());
+                reasons.push(OutdatedReason {
+                    solve_group: solve_group.clone(),
+                    ..OutdatedReason::new(
+                        environment,
+                        Some(*platform),
+                        match solve_group {
+                            Some(solve_group) => format!(
+                                "the locked packages of solve group '{solve_group}' are out of date"
+                            ),
+                            None => "the locked packages are out of date".to_string(),
+                        },
+                    )
+                });
+            }
+        }
+
         Self {
             conda: outdated_conda,
             pypi: outdated_pypi,
             disregard_locked_content,
+            reasons,
         }
     }
 
+    /// Marks every environment and platform of the workspace as out of date
+    /// and disregards all the locked packages, so that everything is solved
+    /// again from scratch.
+    pub(crate) fn all(workspace: &'p Workspace) -> Self {
+        let mut outdated = Self {
+            conda: HashMap::new(),
+            pypi: HashMap::new(),
+            disregard_locked_content: DisregardLockedContent::default(),
+            reasons: Vec::new(),
+        };
+        for environment in workspace.environments() {
+            let platforms = environment.platforms();
+            outdated
+                .conda
+                .insert(environment.clone(), platforms.iter().copied().collect());
+            outdated
+                .pypi
+                .insert(environment.clone(), platforms.into_iter().collect());
+            outdated
+                .disregard_locked_content
+                .conda
+                .insert(environment.clone());
+            outdated.reasons.push(OutdatedReason::new(
+                &environment,
+                None,
+                "every environment is solved again",
+            ));
+        }
+        outdated
+    }
+
     /// Returns true if the lock-file is up-to-date with the project (e.g. there
     /// are no outdated targets).
     pub(crate) fn is_empty(&self) -> bool {
         self.conda.is_empty() && self.pypi.is_empty()
     }
+
+    /// Only keeps the environments for which `keep` returns true, the locked
+    /// content of all other environments is left as is.
+    pub(crate) fn retain(&mut self, keep: impl Fn(&Environment<'p>) -> bool) {
+        self.conda.retain(|env, _| keep(env));
+        self.pypi.retain(|env, _| keep(env));
+        self.disregard_locked_content.conda.retain(|env| keep(env));
+        self.disregard_locked_content.pypi.retain(|env| keep(env));
+        self.retain_reasons();
+    }
+
+    /// Only keeps the platforms for which `keep` returns true, the locked
+    /// content of all other platforms is left as is.
+    pub(crate) fn retain_platforms(&mut self, keep: impl Fn(Platform) -> bool) {
+        for platforms in self.conda.values_mut().chain(self.pypi.values_mut()) {
+            platforms.retain(|platform| keep(*platform));
+        }
+        self.conda.retain(|_, platforms| !platforms.is_empty());
+        self.pypi.retain(|_, platforms| !platforms.is_empty());
+        self.reasons
+            .retain(|reason| reason.platform.is_none_or(|platform| keep(platform)));
+        self.retain_reasons();
+    }
+
+    /// Removes the reasons of the environments that are no longer out of date.
+    fn retain_reasons(&mut self) {
+        let outdated = self
+            .conda
+            .keys()
+            .chain(self.pypi.keys())
+            .map(|env| env.name().clone())
+            .collect::<HashSet<_>>();
+        self.reasons
+            .retain(|reason| outdated.contains(&reason.environment));
+    }
 }
 
 #[derive(Debug, Default)]
@@ -134,6 +270,7 @@ struct UnsatisfiableTargets<'p> {
     outdated_conda: HashMap<Environment<'p>, HashSet<Platform>>,
     outdated_pypi: HashMap<Environment<'p>, HashSet<Platform>>,
     disregard_locked_content: DisregardLockedContent<'p>,
+    reasons: Vec<OutdatedReason>,
 }
 
 /// Find all targets (combination of environment and platform) who's
@@ -145,6 +282,7 @@ async fn find_unsatisfiable_targets<'p>(
 ) -> UnsatisfiableTargets<'p> {
     let mut verified_environments = HashMap::new();
     let mut unsatisfiable_targets = UnsatisfiableTargets::default();
+    let verified_targets = VerifiedTargets::load(project);
     for environment in project.environments() {
         let platforms = environment.platforms();
 
@@ -154,6 +292,11 @@ async fn find_unsatisfiable_targets<'p>(
                 "environment '{0}' is out of date because it does not exist in the lock-file.",
                 environment.name().fancy_display()
             );
+            unsatisfiable_targets.reasons.push(OutdatedReason::new(
+                &environment,
+                None,
+                "it does not exist in the lock-file",
+            ));
 
             unsatisfiable_targets
                 .outdated_conda
@@ -170,6 +313,9 @@ async fn find_unsatisfiable_targets<'p>(
                 "environment '{0}' is out of date because {unsat}",
                 environment.name().fancy_display()
             );
+            unsatisfiable_targets
+                .reasons
+                .push(OutdatedReason::new(&environment, None, &unsat));
 
             unsatisfiable_targets
                 .outdated_conda
@@ -227,6 +373,16 @@ async fn find_unsatisfiable_targets<'p>(
 
         // Verify each individual platform
         for platform in platforms {
+            // Targets whose inputs and locked packages didn't change since they were
+            // last verified are still satisfied.
+            if verified_targets.contains(&environment, platform, lock_file) {
+                tracing::debug!(
+                    "the inputs of environment '{0}' for platform {platform} did not change",
+                    environment.name().fancy_display()
+                );
+                continue;
+            }
+
             match verify_platform_satisfiability(
                 &environment,
                 locked_environment,
@@ -244,6 +400,11 @@ async fn find_unsatisfiable_targets<'p>(
                         "the pypi dependencies of environment '{0}' for platform {platform} are out of date because {unsat}",
                         environment.name().fancy_display()
                     );
+                    unsatisfiable_targets.reasons.push(OutdatedReason::new(
+                        &environment,
+                        Some(platform),
+                        &unsat,
+                    ));
 
                     unsatisfiable_targets
                         .outdated_pypi
@@ -256,6 +417,11 @@ async fn find_unsatisfiable_targets<'p>(
                         "the dependencies of environment '{0}' for platform {platform} are out of date because {unsat}",
                         environment.name().fancy_display()
                     );
+                    unsatisfiable_targets.reasons.push(OutdatedReason::new(
+                        &environment,
+                        Some(platform),
+                        &unsat,
+                    ));
 
                     unsatisfiable_targets
                         .outdated_conda
@@ -290,6 +456,10 @@ async fn find_unsatisfiable_targets<'p>(
             );
 
             for env in solve_group.environments() {
+                unsatisfiable_targets.reasons.push(OutdatedReason {
+                    solve_group: Some(solve_group.name().to_string()),
+                    ..OutdatedReason::new(&env, Some(platform), &unsat)
+                });
                 unsatisfiable_targets
                     .outdated_conda
                     .entry(env.clone())
@@ -309,6 +479,11 @@ async fn find_unsatisfiable_targets<'p>(
             "the dependencies of environment '{0}' for platform {platform} are out of date because {unsat}",
             individual_env.name().fancy_display(),
         );
+        unsatisfiable_targets.reasons.push(OutdatedReason::new(
+            &individual_env,
+            Some(platform),
+            &unsat,
+        ));
 
         unsatisfiable_targets
             .outdated_conda
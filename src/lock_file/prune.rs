@@ -0,0 +1,169 @@
+use std::str::FromStr;
+
+use fancy_display::FancyDisplay;
+use itertools::Itertools;
+use pixi_consts::consts;
+use pixi_manifest::EnvironmentName;
+use rattler_conda_types::Platform;
+use rattler_lock::{LockFile, LockFileBuilder};
+
+use crate::Workspace;
+
+/// An environment, or a platform of an environment, that is part of the
+/// lock-file but no longer part of the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleTarget {
+    pub environment: EnvironmentName,
+    /// `None` if the whole environment is no longer part of the manifest.
+    pub platform: Option<Platform>,
+}
+
+/// Returns the environments and platforms of the lock-file that are no longer
+/// part of the manifest, e.g. because an environment was removed or renamed,
+/// or a platform was dropped.
+pub(crate) fn stale_targets(workspace: &Workspace, lock_file: &LockFile) -> Vec<StaleTarget> {
+    let mut stale = Vec::new();
+    for (name, locked_environment) in lock_file
+        .environments()
+        .sorted_by_key(|(name, _)| name.to_string())
+    {
+        let environment_name = EnvironmentName::from_str(name)
+            .unwrap_or_else(|_| EnvironmentName::Named(name.to_string()));
+        let Some(environment) = workspace.environment(name) else {
+            stale.push(StaleTarget {
+                environment: environment_name,
+                platform: None,
+            });
+            continue;
+        };
+        let platforms = environment.platforms();
+        for platform in locked_environment
+            .platforms()
+            .filter(|platform| !platforms.contains(platform))
+            .sorted_by_key(|platform| platform.as_str())
+        {
+            stale.push(StaleTarget {
+                environment: environment_name.clone(),
+                platform: Some(platform),
+            });
+        }
+    }
+    stale
+}
+
+/// Removes the environments and platforms that are no longer part of the
+/// manifest from the lock-file. The locked packages of all other environments
+/// and platforms are kept as they are.
+pub(crate) fn prune_lock_file(
+    workspace: &Workspace,
+    lock_file: &LockFile,
+) -> (LockFile, Vec<StaleTarget>) {
+    let stale = stale_targets(workspace, lock_file);
+    if stale.is_empty() {
+        return (lock_file.clone(), stale);
+    }
+
+    let mut builder = LockFile::builder();
+    for (name, _) in lock_file.environments() {
+        let is_stale_environment = stale
+            .iter()
+            .any(|target| target.environment.as_str() == name && target.platform.is_none());
+        if is_stale_environment {
+            continue;
+        }
+        copy_environment(&mut builder, lock_file, name, |platform| {
+            !stale.iter().any(|target| {
+                target.environment.as_str() == name && target.platform == Some(platform)
+            })
+        });
+    }
+    (builder.finish(), stale)
+}
+
+/// Adds the stale environments and platforms of the original lock-file back to
+/// the updated lock-file, used when pruning is disabled.
+pub(crate) fn restore_stale_targets(
+    workspace: &Workspace,
+    original: &LockFile,
+    updated: &LockFile,
+) -> LockFile {
+    let stale = stale_targets(workspace, original);
+    if stale.is_empty() {
+        return updated.clone();
+    }
+
+    let mut builder = LockFile::builder();
+    for (name, _) in updated.environments() {
+        copy_environment(&mut builder, updated, name, |_| true);
+    }
+    for (environment, targets) in &stale.iter().chunk_by(|target| target.environment.clone()) {
+        let platforms = targets.map(|target| target.platform).collect_vec();
+        let whole_environment = platforms.contains(&None);
+        if whole_environment {
+            copy_environment(&mut builder, original, environment.as_str(), |_| true);
+        } else {
+            copy_packages(&mut builder, original, environment.as_str(), |platform| {
+                platforms.contains(&Some(platform))
+            });
+        }
+    }
+    builder.finish()
+}
+
+/// Copies the channels, options, indexes and the locked packages of the
+/// selected platforms of an environment.
+fn copy_environment(
+    builder: &mut LockFileBuilder,
+    lock_file: &LockFile,
+    name: &str,
+    keep_platform: impl Fn(Platform) -> bool,
+) {
+    let Some(environment) = lock_file.environment(name) else {
+        return;
+    };
+    builder.set_channels(name, environment.channels().to_vec());
+    builder.set_options(name, environment.solve_options().clone());
+    if let Some(indexes) = environment.pypi_indexes() {
+        builder.set_pypi_indexes(name, indexes.clone());
+    }
+    copy_packages(builder, lock_file, name, keep_platform);
+}
+
+fn copy_packages(
+    builder: &mut LockFileBuilder,
+    lock_file: &LockFile,
+    name: &str,
+    keep_platform: impl Fn(Platform) -> bool,
+) {
+    let Some(environment) = lock_file.environment(name) else {
+        return;
+    };
+    for (platform, packages) in environment.packages_by_platform() {
+        if !keep_platform(platform) {
+            continue;
+        }
+        for package in packages {
+            builder.add_package(name, platform, package.into());
+        }
+    }
+}
+
+/// Reports the environments and platforms that were removed from the
+/// lock-file.
+pub(crate) fn print_pruned_targets(pruned: &[StaleTarget]) {
+    for target in pruned {
+        match target.platform {
+            Some(platform) => eprintln!(
+                "{}Pruned {} of {} from the lock-file, it is no longer a platform of the environment",
+                console::style(console::Emoji("ℹ️ ", "")).yellow(),
+                consts::PLATFORM_STYLE.apply_to(platform),
+                target.environment.fancy_display()
+            ),
+            None => eprintln!(
+                "{}Pruned {} from the lock-file, it is no longer an environment of the manifest",
+                console::style(console::Emoji("ℹ️ ", "")).yellow(),
+                target.environment.fancy_display()
+            ),
+        }
+    }
+}
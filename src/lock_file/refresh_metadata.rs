@@ -0,0 +1,173 @@
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+use miette::{Diagnostic, IntoDiagnostic};
+use pixi_manifest::EnvironmentName;
+use pixi_progress::await_in_progress;
+use pixi_record::PixiRecord;
+use rattler_conda_types::{MatchSpec, Platform};
+use rattler_lock::{CondaPackageData, LockFile, LockFileBuilder, LockedPackageRef};
+use thiserror::Error;
+
+use crate::{Workspace, repodata::Repodata, workspace::grouped_environment::GroupedEnvironment};
+
+/// A locked conda package that can't be found in the channels of its
+/// environment anymore.
+#[derive(Debug)]
+pub struct MissingLockedPackage {
+    environment: EnvironmentName,
+    platform: Platform,
+    package: String,
+}
+
+impl Display for MissingLockedPackage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "  - {} ({}, {})",
+            self.package, self.environment, self.platform
+        )
+    }
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "the following locked packages can no longer be found in the channels of their environment:\n{}",
+    .missing.iter().format("\n")
+)]
+#[diagnostic(help(
+    "run `pixi update` without `--refresh-metadata` to solve the environments again"
+))]
+pub struct MissingLockedPackagesError {
+    missing: Vec<MissingLockedPackage>,
+}
+
+/// Looks up every locked binary conda package of the selected environments and
+/// platforms in the repodata of the channels of the environment, and replaces
+/// the url, hashes and metadata of the package with the record that is found.
+/// The versions and builds are kept as they are, nothing is solved.
+///
+/// The pypi and source packages are kept as they are locked.
+pub(crate) async fn refresh_lock_file_metadata(
+    workspace: &Workspace,
+    lock_file: &LockFile,
+    mut selected: impl FnMut(&EnvironmentName, Platform) -> bool,
+) -> miette::Result<LockFile> {
+    let gateway = workspace.repodata_gateway()?;
+    let channel_config = workspace.channel_config();
+
+    let mut builder = LockFileBuilder::new();
+    let mut missing = Vec::new();
+    for (environment_name, locked_environment) in lock_file.environments() {
+        let Some(environment) = workspace.environment(environment_name) else {
+            continue;
+        };
+        let group = GroupedEnvironment::from(environment.clone());
+        let channels = group
+            .channels()
+            .into_iter()
+            .cloned()
+            .map(|channel| channel.into_channel(&channel_config))
+            .collect::<Result<Vec<_>, _>>()
+            .into_diagnostic()?;
+
+        // The packages are looked up in the channels of the manifest, these are
+        // only recorded for environments of which a platform is refreshed.
+        if locked_environment
+            .platforms()
+            .any(|platform| selected(environment.name(), platform))
+        {
+            builder.set_channels(
+                environment_name,
+                channels
+                    .iter()
+                    .map(|channel| channel.base_url.to_string())
+                    .collect_vec(),
+            );
+        } else {
+            builder.set_channels(environment_name, locked_environment.channels().to_vec());
+        }
+        builder.set_options(environment_name, locked_environment.solve_options().clone());
+        if let Some(indexes) = locked_environment.pypi_indexes() {
+            builder.set_pypi_indexes(environment_name, indexes.clone());
+        }
+
+        for (platform, packages) in locked_environment.packages_by_platform() {
+            let packages = packages.collect_vec();
+            if !selected(environment.name(), platform) {
+                for package in packages {
+                    builder.add_package(environment_name, platform, package.into());
+                }
+                continue;
+            }
+
+            // Only the records of the locked packages themselves are needed
+            let specs = packages
+                .iter()
+                .filter_map(|package| match package {
+                    LockedPackageRef::Conda(CondaPackageData::Binary(binary)) => {
+                        Some(MatchSpec::from(binary.package_record.name.clone()))
+                    }
+                    _ => None,
+                })
+                .collect_vec();
+            let repodata = await_in_progress(
+                format!("fetching repodata for {}:{}", environment_name, platform),
+                |_| async {
+                    gateway
+                        .query(channels.clone(), [platform, Platform::NoArch], specs)
+                        .execute()
+                        .await
+                        .into_diagnostic()
+                },
+            )
+            .await?;
+
+            for package in packages {
+                let LockedPackageRef::Conda(CondaPackageData::Binary(binary)) = package else {
+                    builder.add_package(environment_name, platform, package.into());
+                    continue;
+                };
+                let locked = &binary.package_record;
+                let record = repodata
+                    .iter()
+                    .flat_map(|repodata| repodata.iter())
+                    .find(|record| {
+                        let record = &record.package_record;
+                        record.name == locked.name
+                            && record.version == locked.version
+                            && record.build == locked.build
+                            && record.subdir == locked.subdir
+                    });
+                match record {
+                    Some(record) => {
+                        let mut record = record.clone();
+                        // The purls are not part of the repodata, they were added when the
+                        // package was locked
+                        record.package_record.purls = locked.purls.clone();
+                        builder.add_conda_package(
+                            environment_name,
+                            platform,
+                            PixiRecord::Binary(record).into(),
+                        );
+                    }
+                    None => missing.push(MissingLockedPackage {
+                        environment: environment.name().clone(),
+                        platform,
+                        package: format!(
+                            "{} {} {}",
+                            locked.name.as_source(),
+                            locked.version,
+                            locked.build
+                        ),
+                    }),
+                }
+            }
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(MissingLockedPackagesError { missing }.into());
+    }
+    Ok(builder.finish())
+}
@@ -1,6 +1,6 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     iter::once,
     ops::Deref,
     path::{Path, PathBuf},
@@ -22,7 +22,7 @@ use pixi_uv_conversions::{
     ConversionError, as_uv_req, convert_uv_requirements_to_pep508, into_pinned_git_spec,
     pypi_options_to_build_options, pypi_options_to_index_locations, to_exclude_newer,
     to_index_strategy, to_normalize, to_requirements, to_uv_normalize, to_uv_version,
-    to_version_specifiers,
+    to_uv_version_specifiers, to_version_specifiers,
 };
 use pypi_modifiers::{
     pypi_marker_env::determine_marker_environment,
@@ -34,7 +34,7 @@ use rattler_lock::{
 };
 use typed_path::Utf8TypedPathBuf;
 use url::Url;
-use uv_client::{Connectivity, FlatIndexClient, RegistryClient, RegistryClientBuilder};
+use uv_client::{FlatIndexClient, RegistryClient, RegistryClientBuilder};
 use uv_configuration::{ConfigSettings, Constraints, Overrides};
 use uv_distribution::DistributionDatabase;
 use uv_distribution_types::{
@@ -209,6 +209,7 @@ pub async fn resolve_pypi(
     system_requirements: SystemRequirements,
     locked_pixi_records: &[PixiRecord],
     locked_pypi_packages: &[PypiRecord],
+    extra_constraints: &[(pep508_rs::PackageName, pep440_rs::VersionSpecifiers)],
     platform: rattler_conda_types::Platform,
     pb: &ProgressBar,
     project_root: &Path,
@@ -269,6 +270,24 @@ pub async fn resolve_pypi(
         tracing::info!("there are no python packages installed by conda");
     }
 
+    // Editable git dependencies are resolved like any other git dependency, they
+    // are only marked as editable in the lock file.
+    let editable_git_packages: HashSet<uv_normalize::PackageName> = dependencies
+        .iter()
+        .filter(|(_, specs)| {
+            specs.iter().any(|spec| {
+                matches!(
+                    spec,
+                    PixiPypiSpec::Git {
+                        editable: Some(true),
+                        ..
+                    }
+                )
+            })
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+
     let requirements = dependencies
         .into_iter()
         .flat_map(|(name, req)| {
@@ -337,7 +356,7 @@ pub async fn resolve_pypi(
         .index_strategy(index_strategy)
         .markers(&marker_environment)
         .keyring(context.keyring_provider)
-        .connectivity(Connectivity::Online)
+        .connectivity(context.connectivity)
         .extra_middleware(context.extra_middleware.clone());
 
     for p in &context.proxies {
@@ -356,7 +375,7 @@ pub async fn resolve_pypi(
     // In UV 0.7.8, we need to fetch flat index entries from the index locations
     let flat_index_client = FlatIndexClient::new(
         registry_client.cached_client(),
-        Connectivity::Online,
+        context.connectivity,
         &context.cache,
     );
     let flat_index_urls: Vec<&IndexUrl> = index_locations
@@ -423,7 +442,7 @@ pub async fn resolve_pypi(
     );
 
     // Constrain the conda packages to the specific python packages
-    let constraints = conda_python_packages
+    let mut constraints = conda_python_packages
         .values()
         .map(|(_, p)| {
             // Create pep440 version from the conda version
@@ -451,6 +470,22 @@ pub async fn resolve_pypi(
         .collect::<Result<Vec<_>, _>>()
         .into_diagnostic()?;
 
+    // Add the constraints that were requested for this solve
+    for (name, specifiers) in extra_constraints {
+        constraints.push(uv_distribution_types::Requirement {
+            name: to_uv_normalize(name).into_diagnostic()?,
+            extras: vec![].into(),
+            marker: Default::default(),
+            source: RequirementSource::Registry {
+                specifier: to_uv_version_specifiers(specifiers).into_diagnostic()?,
+                index: None,
+                conflict: None,
+            },
+            groups: Default::default(),
+            origin: None,
+        });
+    }
+
     #[derive(Debug, thiserror::Error)]
     enum PixiPreferencesError {
         #[error(transparent)]
@@ -594,6 +629,7 @@ pub async fn resolve_pypi(
         &context.capabilities,
         context.concurrency.downloads,
         project_root,
+        &editable_git_packages,
     )
     .await?;
 
@@ -716,6 +752,7 @@ fn get_url_or_path(
 }
 
 /// Create a vector of locked packages from a resolution
+#[allow(clippy::too_many_arguments)]
 async fn lock_pypi_packages(
     conda_python_packages: CondaPythonPackages,
     pixi_build_dispatch: &LazyBuildDispatch<'_>,
@@ -724,6 +761,7 @@ async fn lock_pypi_packages(
     index_capabilities: &IndexCapabilities,
     concurrent_downloads: usize,
     abs_project_root: &Path,
+    editable_git_packages: &HashSet<uv_normalize::PackageName>,
 ) -> miette::Result<Vec<(PypiPackageData, PypiPackageEnvironmentData)>> {
     let mut locked_packages = LockedPypiPackages::with_capacity(resolution.len());
     let database =
@@ -848,7 +886,7 @@ async fn lock_pypi_packages(
                             (
                                 pinned_git_spec.into_locked_git_url().to_url().into(),
                                 hash,
-                                false,
+                                editable_git_packages.contains(&git.name),
                             )
                         }
                         SourceDist::Path(path) => {
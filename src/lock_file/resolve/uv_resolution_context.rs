@@ -1,6 +1,6 @@
 use miette::{Context, IntoDiagnostic};
 use uv_cache::Cache;
-use uv_client::ExtraMiddleware;
+use uv_client::{Connectivity, ExtraMiddleware};
 use uv_configuration::{Concurrency, SourceStrategy, TrustedHost};
 use uv_dispatch::SharedState;
 use uv_distribution_types::IndexCapabilities;
@@ -26,6 +26,7 @@ pub struct UvResolutionContext {
     pub shared_state: SharedState,
     pub extra_middleware: ExtraMiddleware,
     pub proxies: Vec<reqwest::Proxy>,
+    pub connectivity: Connectivity,
 }
 
 impl UvResolutionContext {
@@ -77,6 +78,11 @@ impl UvResolutionContext {
             shared_state: SharedState::default(),
             extra_middleware: ExtraMiddleware(uv_middlewares(project.config())),
             proxies: project.config().get_proxies().into_diagnostic()?,
+            connectivity: if project.config().network().offline() {
+                Connectivity::Offline
+            } else {
+                Connectivity::Online
+            },
         })
     }
 
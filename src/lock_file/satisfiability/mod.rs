@@ -15,7 +15,11 @@ use pixi_build_discovery::{DiscoveredBackend, EnabledProtocols};
 use pixi_build_type_conversions::compute_project_model_hash;
 use pixi_git::url::RepositoryUrl;
 use pixi_glob::{GlobHashCache, GlobHashError, GlobHashKey};
-use pixi_manifest::{FeaturesExt, pypi::pypi_options::NoBuild};
+use pixi_manifest::{
+    FeaturesExt,
+    pypi::pypi_options::{NoBinary, NoBuild},
+};
+use pixi_pypi_spec::PixiPypiSpec;
 use pixi_record::{LockedGitUrl, ParseLockFileError, PixiRecord, SourceMismatchError};
 use pixi_spec::{PixiSpec, SourceAnchor, SourceSpec, SpecConversionError};
 use pixi_uv_conversions::{
@@ -102,7 +106,7 @@ fn fmt_channel_priority(priority: rattler_solve::ChannelPriority) -> &'static st
     }
 }
 
-fn fmt_solve_strategy(strategy: rattler_solve::SolveStrategy) -> &'static str {
+pub(crate) fn fmt_solve_strategy(strategy: rattler_solve::SolveStrategy) -> &'static str {
     match strategy {
         rattler_solve::SolveStrategy::Highest => "highest",
         rattler_solve::SolveStrategy::LowestVersion => "lowest-version",
@@ -396,6 +400,9 @@ pub enum PlatformUnsat {
 
     #[error("'{name}' is locked as a conda package but only requested by pypi dependencies")]
     CondaPackageShouldBePypi { name: String },
+
+    #[error("the lock-file contains a wheel for '{0}', but the pypi-option `no-binary` is set")]
+    NoBinaryWithBinaryPackages(String),
 }
 
 #[derive(Debug, Error, Diagnostic)]
@@ -416,7 +423,8 @@ impl PlatformUnsat {
                 | PlatformUnsat::FailedToDetermineSourceTreeHash(_, _)
                 | PlatformUnsat::PythonVersionMismatch(_, _, _)
                 | PlatformUnsat::EditablePackageMismatch(_)
-                | PlatformUnsat::SourceTreeHashMismatch(..),
+                | PlatformUnsat::SourceTreeHashMismatch(..)
+                | PlatformUnsat::NoBinaryWithBinaryPackages(_),
         )
     }
 }
@@ -546,49 +554,15 @@ fn verify_pypi_no_build(
         }
     };
 
-    // Small helper function to get the dist extension from a url
-    fn pypi_dist_extension_from_url(url: &Url) -> Result<DistExtension, ExtensionError> {
-        // Take the file name from the url
-        let path = url
-            .path_segments()
-            .and_then(|mut s| s.next_back())
-            .unwrap_or_default();
-        // Convert the path to a dist extension
-        DistExtension::from_path(Path::new(path))
-    }
-
     // Determine if we do not accept non-wheels for all packages or only for a
     // subset Check all the currently locked packages if we are making any
     // violations
     for (_, packages) in locked_environment.pypi_packages_by_platform() {
         for (package, _) in packages {
-            let extension = match &package.location {
-                // Get the extension from the url
-                UrlOrPath::Url(url) => {
-                    if url.scheme().starts_with("git+") {
-                        // Just choose some source extension, does not really matter, cause it is
-                        // actually a directory, this is just for the check
-                        Ok(DistExtension::Source(SourceDistExtension::TarGz))
-                    } else {
-                        pypi_dist_extension_from_url(url)
-                    }
-                }
-                UrlOrPath::Path(path) => {
-                    let path = Path::new(path.as_str());
-                    if path.is_dir() {
-                        // Editables are allowed with no-build
-                        if package.editable {
-                            continue;
-                        } else {
-                            // Non-editable source packages might not be allowed
-                            Ok(DistExtension::Source(SourceDistExtension::TarGz))
-                        }
-                    } else {
-                        // Could be a reference to a wheel or sdist
-                        DistExtension::from_path(path)
-                    }
-                }
-            }?;
+            // Editables are allowed with no-build
+            let Some(extension) = locked_pypi_dist_extension(package).transpose()? else {
+                continue;
+            };
 
             match extension {
                 // Wheels are fine
@@ -615,6 +589,88 @@ fn verify_pypi_no_build(
     Ok(())
 }
 
+/// Verifies that none of the locked pypi packages that `no-binary` applies to
+/// is a wheel. When this fails only the pypi packages of the platform are
+/// re-solved, which replaces the offending wheel with a source distribution.
+fn verify_pypi_no_binary<'a>(
+    no_binary: &NoBinary,
+    locked_packages: impl IntoIterator<Item = &'a PypiPackageData>,
+) -> Result<(), Box<PlatformUnsat>> {
+    let packages = match no_binary {
+        // Any package may be installed from a wheel
+        NoBinary::None => return Ok(()),
+        NoBinary::All => None,
+        NoBinary::Packages(packages) => Some(
+            packages
+                .iter()
+                .filter_map(|name| pep508_rs::PackageName::new(name.to_string()).ok())
+                .collect::<HashSet<_>>(),
+        ),
+    };
+
+    for package in locked_packages {
+        if packages
+            .as_ref()
+            .is_some_and(|packages| !packages.contains(&package.name))
+        {
+            continue;
+        }
+
+        if let Some(Ok(DistExtension::Wheel)) = locked_pypi_dist_extension(package) {
+            return Err(Box::new(PlatformUnsat::NoBinaryWithBinaryPackages(
+                package.name.to_string(),
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Determines whether a locked pypi package refers to a wheel or a source
+/// distribution. Returns `None` for editable packages, those are always built
+/// from their source directory.
+fn locked_pypi_dist_extension(
+    package: &PypiPackageData,
+) -> Option<Result<DistExtension, ExtensionError>> {
+    // Small helper function to get the dist extension from a url
+    fn pypi_dist_extension_from_url(url: &Url) -> Result<DistExtension, ExtensionError> {
+        // Take the file name from the url
+        let path = url
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .unwrap_or_default();
+        // Convert the path to a dist extension
+        DistExtension::from_path(Path::new(path))
+    }
+
+    let extension = match &package.location {
+        // Get the extension from the url
+        UrlOrPath::Url(url) => {
+            if url.scheme().starts_with("git+") {
+                // Just choose some source extension, does not really matter, cause it is
+                // actually a directory, this is just for the check
+                Ok(DistExtension::Source(SourceDistExtension::TarGz))
+            } else {
+                pypi_dist_extension_from_url(url)
+            }
+        }
+        UrlOrPath::Path(path) => {
+            let path = Path::new(path.as_str());
+            if path.is_dir() {
+                if package.editable {
+                    return None;
+                } else {
+                    // Non-editable source packages might not be allowed
+                    Ok(DistExtension::Source(SourceDistExtension::TarGz))
+                }
+            } else {
+                // Could be a reference to a wheel or sdist
+                DistExtension::from_path(path)
+            }
+        }
+    };
+    Some(extension)
+}
+
 fn verify_pypi_indexes(
     locked_environment: rattler_lock::Environment<'_>,
     indexes: PypiIndexes,
@@ -703,6 +759,16 @@ pub async fn verify_platform_satisfiability(
         }
     }
 
+    // Check that the packages we are not allowed to install from a wheel are
+    // locked as a source distribution
+    if let Some(no_binary) = GroupedEnvironment::from(environment.clone())
+        .pypi_options()
+        .no_binary
+        .as_ref()
+    {
+        verify_pypi_no_binary(no_binary, pypi_packages.iter().map(|(package, _)| package))?;
+    }
+
     // Create a lookup table from package name to package record. Returns an error
     // if we find a duplicate entry for a record
     let pixi_records_by_name = match PixiRecordsByName::from_unique_iter(pixi_records) {
@@ -1093,7 +1159,24 @@ pub(crate) async fn verify_package_platform_satisfiability(
     // packages first.
     let mut conda_queue = environment_dependencies;
     let mut pypi_queue = pypi_requirements;
-    let mut expected_editable_pypi_packages = HashSet::new();
+    // Editable git dependencies are resolved like regular git dependencies, so
+    // they are expected to be editable based on the manifest.
+    let mut expected_editable_pypi_packages = environment
+        .pypi_dependencies(Some(platform))
+        .iter()
+        .filter(|(_, specs)| {
+            specs.iter().any(|spec| {
+                matches!(
+                    spec,
+                    PixiPypiSpec::Git {
+                        editable: Some(true),
+                        ..
+                    }
+                )
+            })
+        })
+        .filter_map(|(name, _)| uv_normalize::PackageName::from_str(name.as_source()).ok())
+        .collect::<HashSet<_>>();
     let mut expected_conda_source_dependencies = HashSet::new();
     let mut expected_conda_packages = HashSet::new();
     let mut conda_packages_used_by_pypi = HashSet::new();
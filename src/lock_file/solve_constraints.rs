@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use pixi_manifest::EnvironmentName;
+use pixi_spec_containers::DependencyMap;
+use rattler_conda_types::{NamelessMatchSpec, PackageName, Platform};
+
+use crate::workspace::grouped_environment::GroupedEnvironment;
+
+/// Version constraints that are added to the solve of an environment without
+/// being recorded in the manifest. Constraints don't add packages to an
+/// environment, they only restrict the versions that can be selected.
+#[derive(Debug, Default, Clone)]
+pub struct SolveConstraints {
+    conda: HashMap<(EnvironmentName, Platform), DependencyMap<PackageName, NamelessMatchSpec>>,
+    pypi: HashMap<
+        (EnvironmentName, Platform),
+        Vec<(pep508_rs::PackageName, pep440_rs::VersionSpecifiers)>,
+    >,
+}
+
+impl SolveConstraints {
+    /// Returns true if there are no constraints.
+    pub fn is_empty(&self) -> bool {
+        self.conda.is_empty() && self.pypi.is_empty()
+    }
+
+    /// Constrains a conda package of an environment for a specific platform.
+    pub fn add_conda(
+        &mut self,
+        environment: EnvironmentName,
+        platform: Platform,
+        name: PackageName,
+        spec: NamelessMatchSpec,
+    ) {
+        self.conda
+            .entry((environment, platform))
+            .or_default()
+            .insert(name, spec);
+    }
+
+    /// Constrains a pypi package of an environment for a specific platform.
+    pub fn add_pypi(
+        &mut self,
+        environment: EnvironmentName,
+        platform: Platform,
+        name: pep508_rs::PackageName,
+        specifiers: pep440_rs::VersionSpecifiers,
+    ) {
+        self.pypi
+            .entry((environment, platform))
+            .or_default()
+            .push((name, specifiers));
+    }
+
+    /// Returns the conda constraints of all the environments in a solve group.
+    pub(crate) fn conda_constraints(
+        &self,
+        group: &GroupedEnvironment<'_>,
+        platform: Platform,
+    ) -> DependencyMap<PackageName, NamelessMatchSpec> {
+        let mut constraints = DependencyMap::default();
+        for environment in group.environments() {
+            let Some(specs) = self.conda.get(&(environment.name().clone(), platform)) else {
+                continue;
+            };
+            for (name, spec) in specs.iter_specs() {
+                constraints.insert(name.clone(), spec.clone());
+            }
+        }
+        constraints
+    }
+
+    /// Returns the pypi constraints of all the environments in a solve group.
+    pub(crate) fn pypi_constraints(
+        &self,
+        group: &GroupedEnvironment<'_>,
+        platform: Platform,
+    ) -> Vec<(pep508_rs::PackageName, pep440_rs::VersionSpecifiers)> {
+        group
+            .environments()
+            .filter_map(|environment| self.pypi.get(&(environment.name().clone(), platform)))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+}
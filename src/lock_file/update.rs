@@ -1,9 +1,9 @@
 use std::{
     cmp::PartialEq,
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     future::{Future, ready},
     iter,
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
     time::{Duration, Instant},
@@ -22,6 +22,7 @@ use pixi_consts::consts;
 use pixi_manifest::{ChannelPriority, EnvironmentName, FeaturesExt};
 use pixi_progress::global_multi_progress;
 use pixi_record::{ParseLockFileError, PixiRecord};
+use pixi_spec_containers::DependencyMap;
 use pixi_uv_conversions::{
     ConversionError, to_extra_name, to_marker_environment, to_normalize, to_uv_extra_name,
     to_uv_normalize,
@@ -29,27 +30,31 @@ use pixi_uv_conversions::{
 use pypi_mapping::{self, MappingClient};
 use pypi_modifiers::pypi_marker_env::determine_marker_environment;
 use rattler::package_cache::PackageCache;
-use rattler_conda_types::{Arch, PackageName, Platform};
-use rattler_lock::{
-    LockFile, ParseCondaLockError, PypiIndexes, PypiPackageData, PypiPackageEnvironmentData,
-};
+use rattler_conda_types::{Arch, NamelessMatchSpec, PackageName, Platform};
+use rattler_lock::{LockFile, PypiIndexes, PypiPackageData, PypiPackageEnvironmentData};
+use reqwest_middleware::ClientWithMiddleware;
 use thiserror::Error;
 use tokio::sync::Semaphore;
 use tracing::Instrument;
 use uv_normalize::ExtraName;
 
 use super::{
-    CondaPrefixUpdater, PixiRecordsByName, PypiRecordsByName, UvResolutionContext,
-    outdated::OutdatedEnvironments, utils::IoConcurrencyLimit,
+    CondaPrefixUpdater, PixiRecordsByName, PypiRecordsByName, SolveConstraints,
+    UvResolutionContext, VerifiedTargets, canonicalize_lock_file, ensure_repodata_cached,
+    lock_file_parse_error,
+    outdated::{OutdatedEnvironments, OutdatedReason},
+    print_pruned_targets, prune_lock_file, restore_stale_targets,
+    utils::IoConcurrencyLimit,
+    write_lock_file_with_version,
 };
 use crate::{
     Workspace,
-    activation::CurrentEnvVarBehavior,
+    activation::{CurrentEnvVarBehavior, PrefixSnapshot, invalidate_activation_cache},
     build::{BuildContext, GlobHashCache},
     environment::{
         self, CondaPrefixUpdated, CondaPrefixUpdaterBuilder, EnvironmentFile, LockFileUsage,
         LockedEnvironmentHash, PerEnvironmentAndPlatform, PerGroup, PerGroupAndPlatform,
-        PythonStatus, read_environment_file, write_environment_file,
+        PrefixVerification, PythonStatus, read_environment_file, write_environment_file,
     },
     lock_file::{
         self, PypiRecord, records_by_name::HasNameVersion, reporter::SolveProgressBar,
@@ -79,19 +84,26 @@ impl Workspace {
         &self,
         options: UpdateLockFileOptions,
     ) -> miette::Result<LockFileDerivedData<'_>> {
-        let lock_file = self.load_lock_file().await?;
+        let original_lock_file = self.load_lock_file().await?;
         let glob_hash_cache = GlobHashCache::default();
 
+        // Remove the environments and platforms that are no longer part of the
+        // manifest, but only if the lock-file may be updated.
+        let (lock_file, pruned) =
+            if options.lock_file_usage.allows_lock_file_updates() && !options.no_prune {
+                prune_lock_file(self, &original_lock_file)
+            } else {
+                (original_lock_file.clone(), Vec::new())
+            };
+
         // Construct a command dispatcher that will be used to run the tasks.
         let multi_progress = global_multi_progress();
         let anchor_pb = multi_progress.add(ProgressBar::hidden());
-        let command_dispatcher = self
-            .command_dispatcher_builder()?
-            .with_reporter(crate::reporters::TopLevelProgress::new(
-                global_multi_progress(),
-                anchor_pb,
-            ))
-            .finish();
+        let command_dispatcher = crate::reporters::with_top_level_reporter(
+            self.command_dispatcher_builder()?,
+            anchor_pb,
+        )
+        .finish();
 
         // Get the package cache from the dispatcher.
         let package_cache = command_dispatcher.package_cache().clone();
@@ -111,6 +123,7 @@ impl Workspace {
                 build_context: BuildContext::from_workspace(self, command_dispatcher)?,
                 glob_hash_cache,
                 was_outdated: false,
+                solved_targets: Vec::new(),
             });
         }
 
@@ -124,6 +137,13 @@ impl Workspace {
         if outdated.is_empty() {
             tracing::info!("the lock-file is up-to-date");
 
+            // Only the pruned targets have to be removed from the lock-file
+            if !pruned.is_empty() {
+                self.write_lock_file(&lock_file)?;
+                print_pruned_targets(&pruned);
+            }
+            VerifiedTargets::from_lock_file(self, &lock_file).write(self);
+
             // If no-environment is outdated we can return early.
             return Ok(LockFileDerivedData {
                 workspace: self,
@@ -135,18 +155,22 @@ impl Workspace {
                 io_concurrency_limit: IoConcurrencyLimit::default(),
                 build_context: BuildContext::from_workspace(self, command_dispatcher)?,
                 glob_hash_cache,
-                was_outdated: false,
+                was_outdated: !pruned.is_empty(),
+                solved_targets: Vec::new(),
             });
         }
 
         // If the lock-file is out of date, but we're not allowed to update it, we
         // should exit.
         if !options.lock_file_usage.allows_lock_file_updates() {
-            miette::bail!("lock-file not up-to-date with the workspace");
+            return Err(LockFileOutdatedError {
+                reasons: outdated.reasons,
+            }
+            .into());
         }
 
         // Construct an update context and perform the actual update.
-        let lock_file_derived_data = UpdateContext::builder(self)
+        let mut lock_file_derived_data = UpdateContext::builder(self)
             .with_package_cache(package_cache)
             .with_no_install(options.no_install)
             .with_outdated_environments(outdated)
@@ -156,9 +180,16 @@ impl Workspace {
             .await?
             .update()
             .await?;
+        if options.no_prune {
+            lock_file_derived_data.lock_file =
+                restore_stale_targets(self, &original_lock_file, &lock_file_derived_data.lock_file);
+        }
 
-        // Write the lock-file to disk
+        // Write the lock-file to disk, the pruned targets are only reported once
+        // they are actually removed from it
         lock_file_derived_data.write_to_disk()?;
+        print_pruned_targets(&pruned);
+        VerifiedTargets::from_lock_file(self, &lock_file_derived_data.lock_file).write(self);
 
         Ok(lock_file_derived_data)
     }
@@ -171,16 +202,7 @@ impl Workspace {
             // Spawn a background task because loading the file might be IO bound.
             tokio::task::spawn_blocking(move || {
                 LockFile::from_path(&lock_file_path)
-                    .map_err(|err| match err {
-                        ParseCondaLockError::IncompatibleVersion { lock_file_version, max_supported_version } => {
-                            miette::miette!(
-                            help="Please update pixi to the latest version and try again.",
-                            "The lock file version is {}, but only up to including version {} is supported by the current version.",
-                            lock_file_version, max_supported_version
-                        )
-                        }
-                        _ => miette::miette!(err),
-                    })
+                    .map_err(|err| lock_file_parse_error(&lock_file_path, err))
                     .wrap_err_with(|| {
                         format!(
                             "Failed to load lock file from `{}`",
@@ -188,12 +210,25 @@ impl Workspace {
                         )
                     })
             })
-                .await
-                .unwrap_or_else(|e| Err(e).into_diagnostic())
+            .await
+            .unwrap_or_else(|e| Err(e).into_diagnostic())
         } else {
             Ok(LockFile::default())
         }
     }
+
+    /// Writes the lock-file of the workspace to disk, in canonical form unless
+    /// `lock-file.canonical` is disabled.
+    pub(crate) fn write_lock_file(&self, lock_file: &LockFile) -> miette::Result<()> {
+        let lock_file_path = self.lock_file_path();
+        if self.config().lock_file().canonical() {
+            write_lock_file_with_version(&canonicalize_lock_file(lock_file), &lock_file_path)
+        } else {
+            write_lock_file_with_version(lock_file, &lock_file_path)
+        }
+        .into_diagnostic()
+        .context("failed to write lock-file to disk")
+    }
 }
 
 #[derive(Debug, Error, Diagnostic)]
@@ -204,6 +239,33 @@ enum UpdateError {
     ParseLockFileError(#[from] ParseLockFileError),
 }
 
+/// The lock-file doesn't satisfy the manifest while it may not be updated,
+/// which is what `--locked` requires.
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "lock-file not up-to-date with the workspace{}",
+    format_reasons(reasons)
+)]
+#[diagnostic(help("run `pixi lock` to update the lock-file, or run without `--locked`"))]
+pub struct LockFileOutdatedError {
+    /// Why the lock-file doesn't satisfy the manifest.
+    pub reasons: Vec<OutdatedReason>,
+}
+
+/// Formats why the lock-file is out of date, one target per line.
+fn format_reasons(reasons: &[OutdatedReason]) -> String {
+    reasons
+        .iter()
+        .map(|reason| match reason.platform {
+            Some(platform) => format!(
+                "\n  - {} ({platform}): {}",
+                reason.environment, reason.reason
+            ),
+            None => format!("\n  - {}: {}", reason.environment, reason.reason),
+        })
+        .collect()
+}
+
 /// Options to pass to [`Workspace::update_lock_file`].
 #[derive(Default)]
 pub struct UpdateLockFileOptions {
@@ -217,6 +279,10 @@ pub struct UpdateLockFileOptions {
     /// value is None a heuristic is used based on the number of cores
     /// available from the system.
     pub max_concurrent_solves: usize,
+
+    /// Keep the environments and platforms that are no longer part of the
+    /// manifest in the lock-file.
+    pub no_prune: bool,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -227,6 +293,66 @@ pub enum ReinstallPackages {
     Some(HashSet<String>),
 }
 
+impl ReinstallPackages {
+    /// Returns the packages to reinstall, including the given packages.
+    pub fn with_packages(&self, packages: impl IntoIterator<Item = String>) -> Self {
+        match self {
+            ReinstallPackages::All => ReinstallPackages::All,
+            ReinstallPackages::None => ReinstallPackages::Some(packages.into_iter().collect()),
+            ReinstallPackages::Some(existing) => {
+                ReinstallPackages::Some(existing.iter().cloned().chain(packages).collect())
+            }
+        }
+    }
+}
+
+/// Defines which conda packages of the lock-file are not linked into the
+/// prefix, e.g. because the host already provides them.
+#[derive(Debug, Clone, Default)]
+pub struct InstallFilter {
+    /// The packages to skip. `None` keeps skipping the packages that were
+    /// skipped when the prefix was installed last.
+    skip: Option<BTreeSet<PackageName>>,
+
+    /// The only conda packages that are linked, updated or unlinked, the
+    /// other packages of the prefix are kept as they are installed.
+    only: Option<HashSet<PackageName>>,
+}
+
+impl InstallFilter {
+    /// Skips exactly the given packages, packages that were skipped before
+    /// are installed again.
+    pub fn skip(packages: impl IntoIterator<Item = PackageName>) -> Self {
+        Self {
+            skip: Some(packages.into_iter().collect()),
+            only: None,
+        }
+    }
+
+    /// Only touches the given conda packages when updating the prefix, e.g.
+    /// the packages that changed in the lock-file. The prefix is only marked
+    /// as up-to-date if the rest of its packages match the lock-file.
+    pub fn only(packages: impl IntoIterator<Item = PackageName>) -> Self {
+        Self {
+            skip: None,
+            only: Some(packages.into_iter().collect()),
+        }
+    }
+
+    /// Returns the packages to skip for the environment, falling back to the
+    /// packages recorded in the environment file.
+    fn skipped_packages(&self, environment: &Environment<'_>) -> BTreeSet<PackageName> {
+        match &self.skip {
+            Some(skip) => skip.clone(),
+            None => read_environment_file(&environment.dir())
+                .ok()
+                .flatten()
+                .map(|file| file.skipped_packages)
+                .unwrap_or_default(),
+        }
+    }
+}
+
 /// A struct that holds the lock-file and any potential derived data that was
 /// computed when calling `update_lock_file`.
 pub struct LockFileDerivedData<'p> {
@@ -263,6 +389,9 @@ pub struct LockFileDerivedData<'p> {
 
     /// Whether the lock file was outdated
     pub was_outdated: bool,
+
+    /// The environments and platforms whose conda packages were solved.
+    pub solved_targets: Vec<(EnvironmentName, Platform)>,
 }
 
 /// The mode to use when updating a prefix.
@@ -277,16 +406,17 @@ pub enum UpdateMode {
     /// Used for updating the prefix when the lock-file likely out of date.
     /// Like `pixi install` or `pixi update`.
     Revalidate,
+    /// Revalidate the prefix and also check the content of every installed
+    /// file, packages with missing or modified files are linked again.
+    /// Used by `pixi install --verify`, or when `verify-environments` is
+    /// configured.
+    Verify,
 }
 
 impl<'p> LockFileDerivedData<'p> {
     /// Write the lock-file to disk.
     pub fn write_to_disk(&self) -> miette::Result<()> {
-        let lock_file_path = self.workspace.lock_file_path();
-        self.lock_file
-            .to_path(&lock_file_path)
-            .into_diagnostic()
-            .context("failed to write lock-file to disk")
+        self.workspace.write_lock_file(&self.lock_file)
     }
 
     /// Consumes this instance, dropping any resources that are not needed
@@ -322,18 +452,93 @@ impl<'p> LockFileDerivedData<'p> {
         environment: &Environment<'p>,
         update_mode: UpdateMode,
         reinstall_packages: &ReinstallPackages,
+        filter: &InstallFilter,
     ) -> miette::Result<Prefix> {
+        // The configuration can ask to always verify the content of the prefix
+        let update_mode = if self.workspace.config().verify_environments() {
+            UpdateMode::Verify
+        } else {
+            update_mode
+        };
+
         // Check if the prefix is already up-to-date by validating the hash with the
         // environment file
         let hash = self.locked_environment_hash(environment)?;
-        if update_mode == UpdateMode::QuickValidate {
-            if let Some(prefix) = self.cached_prefix(environment, &hash) {
+        let skipped_packages = filter.skipped_packages(environment);
+        // A requested reinstall always has to touch the prefix
+        let reinstall_requested = !matches!(reinstall_packages, ReinstallPackages::None);
+        if update_mode == UpdateMode::QuickValidate && !reinstall_requested && filter.only.is_none()
+        {
+            if let Some(prefix) = self.cached_prefix(environment, &hash, &skipped_packages) {
                 return prefix;
             }
         }
 
+        // Find the packages whose files don't match their records anymore, they
+        // are linked again while updating the prefix
+        let verification = if update_mode == UpdateMode::Verify {
+            Some(environment::verify_prefix(&Prefix::new(environment.dir()), None).await?)
+        } else {
+            None
+        };
+        let reinstall_packages = match &verification {
+            Some(verification) if !verification.corrupted.is_empty() => reinstall_packages
+                .with_packages(
+                    verification
+                        .corrupted
+                        .keys()
+                        .map(|name| name.as_normalized().to_string()),
+                ),
+            _ => reinstall_packages.clone(),
+        };
+
         // Get the up-to-date prefix
-        let prefix = self.update_prefix(environment, reinstall_packages).await?;
+        let snapshot = PrefixSnapshot::new(&environment.dir());
+        let prefix = self
+            .update_prefix(
+                environment,
+                &reinstall_packages,
+                &skipped_packages,
+                filter.only.as_ref(),
+            )
+            .await?;
+
+        if let Some(verification) = verification {
+            // Linking again doesn't help if the files in the package cache are
+            // corrupted as well, so check the repaired packages once more.
+            let unrepaired = if verification.corrupted.is_empty() {
+                PrefixVerification::default()
+            } else {
+                environment::verify_prefix(
+                    &Prefix::new(environment.dir()),
+                    Some(&verification.corrupted_packages()),
+                )
+                .await?
+            };
+            report_verification(environment, &verification, &unrepaired)?;
+        }
+
+        // The activation of the environment may change with the installed packages.
+        if PrefixSnapshot::new(&environment.dir()) != snapshot {
+            invalidate_activation_cache(environment);
+        }
+
+        // Only part of the prefix was updated. The environment file marks the
+        // whole prefix as up-to-date with the lock-file, so it is only written
+        // if the packages that were kept as they are installed match their
+        // locked records as well. Otherwise the next update has to bring the
+        // rest of the prefix up-to-date.
+        if filter.only.is_some() {
+            let records = skip_records(
+                environment,
+                self.pixi_records(environment, environment.best_platform())?
+                    .unwrap_or_default(),
+                &skipped_packages,
+            );
+            if !installed_records_match(&prefix, &records)? {
+                return Ok(prefix);
+            }
+        }
 
         // Save an environment file to the environment directory after the update.
         // Avoiding writing the cache away before the update is done.
@@ -344,6 +549,7 @@ impl<'p> LockFileDerivedData<'p> {
                 environment_name: environment.name().to_string(),
                 pixi_version: consts::PIXI_VERSION.to_string(),
                 environment_lock_file_hash: hash,
+                skipped_packages,
             },
         )?;
 
@@ -354,6 +560,7 @@ impl<'p> LockFileDerivedData<'p> {
         &self,
         environment: &Environment<'p>,
         hash: &LockedEnvironmentHash,
+        skipped_packages: &BTreeSet<PackageName>,
     ) -> Option<Result<Prefix, Report>> {
         let Ok(Some(environment_file)) = read_environment_file(&environment.dir()) else {
             tracing::debug!(
@@ -363,7 +570,9 @@ impl<'p> LockFileDerivedData<'p> {
             return None;
         };
 
-        if environment_file.environment_lock_file_hash == *hash {
+        if environment_file.environment_lock_file_hash == *hash
+            && environment_file.skipped_packages == *skipped_packages
+        {
             // If we contain source packages from conda or PyPI we update the prefix by
             // default
             let contains_conda_source_pkgs = self.lock_file.environments().any(|(_, env)| {
@@ -398,11 +607,100 @@ impl<'p> LockFileDerivedData<'p> {
         None
     }
 
+    /// Installs the locked packages of the environment into the given
+    /// directory instead of the prefix of the environment.
+    ///
+    /// The directory is not registered as the prefix of the environment, no
+    /// environment file is written and it is not used for caching. This is
+    /// used to materialize an environment outside of the workspace, e.g. when
+    /// building a container image.
+    pub async fn install_into_prefix(
+        &self,
+        environment: &Environment<'p>,
+        target_prefix: &Path,
+        filter: &InstallFilter,
+    ) -> miette::Result<Prefix> {
+        let skipped_packages = filter.skipped_packages(environment);
+        self.install_prefix(
+            environment,
+            Some(Prefix::new(target_prefix)),
+            &ReinstallPackages::None,
+            &skipped_packages,
+            filter.only.as_ref(),
+        )
+        .await
+    }
+
+    /// Returns the operations that updating the conda and PyPI packages of the
+    /// prefix of the environment would perform, without downloading or
+    /// modifying anything.
+    pub async fn pending_operations(
+        &self,
+        environment: &Environment<'p>,
+        reinstall_packages: &ReinstallPackages,
+        filter: &InstallFilter,
+    ) -> miette::Result<Vec<environment::PendingOperation>> {
+        let platform = environment.best_platform();
+        let pixi_records = self
+            .pixi_records(environment, platform)?
+            .unwrap_or_default();
+        let conda_reinstall_packages = conda_reinstall_packages(reinstall_packages, &pixi_records);
+        let records = skip_records(
+            environment,
+            pixi_records,
+            &filter.skipped_packages(environment),
+        );
+        let prefix = Prefix::new(environment.dir());
+        let (mut operations, python_status) = environment::pending_operations(
+            &prefix,
+            records.clone(),
+            conda_reinstall_packages,
+            platform,
+            &pixi_config::get_cache_dir()?.join(consts::CONDA_PACKAGE_CACHE_DIR),
+        )?;
+
+        // No `uv` support for WASM right now
+        if platform.arch() == Some(Arch::Wasm32) {
+            return Ok(operations);
+        }
+
+        let pypi_records = self
+            .pypi_records(environment, platform)?
+            .unwrap_or_default();
+        let (uv_reinstall, uv_packages) = uv_reinstall_packages(reinstall_packages, &pypi_records);
+        let uv_context = self
+            .uv_context
+            .get_or_try_init(|| UvResolutionContext::from_workspace(self.workspace))?
+            .clone()
+            .set_cache_refresh(uv_reinstall, uv_packages);
+        let pypi_options = environment.pypi_options();
+        operations.extend(
+            environment::pending_prefix_pypi(
+                &prefix,
+                &records,
+                &pypi_records,
+                &python_status,
+                &environment.system_requirements(),
+                &uv_context,
+                self.pypi_indexes(environment)?.as_ref(),
+                self.workspace.root(),
+                platform,
+                &pypi_options.no_build_isolation,
+                &pypi_options.no_build.clone().unwrap_or_default(),
+                &pypi_options.no_binary.clone().unwrap_or_default(),
+            )
+            .await?,
+        );
+        Ok(operations)
+    }
+
     /// Returns the up-to-date prefix for the given environment.
     async fn update_prefix(
         &self,
         environment: &Environment<'p>,
         reinstall_packages: &ReinstallPackages,
+        skipped_packages: &BTreeSet<PackageName>,
+        only: Option<&HashSet<PackageName>>,
     ) -> miette::Result<Prefix> {
         let prefix_once_cell = self
             .updated_pypi_prefixes
@@ -410,138 +708,147 @@ impl<'p> LockFileDerivedData<'p> {
             .or_default()
             .clone();
         prefix_once_cell
-            .get_or_try_init(async {
-                let start = Instant::now();
-
-                // Validate the virtual packages for the environment match the system
-                validate_system_meets_environment_requirements(
-                    &self.lock_file,
-                    environment.best_platform(),
-                    environment.name(),
-                    None,
-                )
-                .wrap_err(format!(
-                    "Cannot install environment '{}'",
-                    environment.name().fancy_display()
-                ))?;
-
-                let platform = environment.best_platform();
-                let pixi_records = self
-                    .pixi_records(environment, platform)?
-                    .unwrap_or_default();
-
-                let conda_reinstall_packages = match reinstall_packages {
-                    ReinstallPackages::None => None,
-                    ReinstallPackages::Some(p) => Some(
-                        p.iter()
-                            .filter_map(|p| PackageName::from_str(p).ok())
-                            .filter(|name| pixi_records.iter().any(|r| r.name() == name))
-                            .collect(),
-                    ),
-                    ReinstallPackages::All => {
-                        Some(pixi_records.iter().map(|r| r.name().clone()).collect())
-                    }
-                };
+            .get_or_try_init(self.install_prefix(
+                environment,
+                None,
+                reinstall_packages,
+                skipped_packages,
+                only,
+            ))
+            .await
+            .cloned()
+    }
 
-                // Get the prefix with the conda packages installed.
-                let (prefix, python_status) = self
-                    .conda_prefix(environment, conda_reinstall_packages)
-                    .await?;
+    /// Installs the conda and PyPI packages of the environment into the
+    /// `target_prefix`, or into the prefix of the environment if no target is
+    /// given.
+    async fn install_prefix(
+        &self,
+        environment: &Environment<'p>,
+        target_prefix: Option<Prefix>,
+        reinstall_packages: &ReinstallPackages,
+        skipped_packages: &BTreeSet<PackageName>,
+        only: Option<&HashSet<PackageName>>,
+    ) -> miette::Result<Prefix> {
+        let start = Instant::now();
 
-                let pypi_records = self
-                    .pypi_records(environment, platform)?
-                    .unwrap_or_default();
+        // Validate the virtual packages for the environment match the system
+        validate_system_meets_environment_requirements(
+            &self.lock_file,
+            environment.best_platform(),
+            environment.name(),
+            None,
+        )
+        .wrap_err(format!(
+            "Cannot install environment '{}'",
+            environment.name().fancy_display()
+        ))?;
+
+        let platform = environment.best_platform();
+        let pixi_records = self
+            .pixi_records(environment, platform)?
+            .unwrap_or_default();
+
+        let conda_reinstall_packages = conda_reinstall_packages(reinstall_packages, &pixi_records);
+
+        // Get the prefix with the conda packages installed.
+        let (prefix, python_status) = match target_prefix {
+            Some(target_prefix) => {
+                self.install_conda_packages(
+                    environment,
+                    Some(target_prefix),
+                    conda_reinstall_packages,
+                    skipped_packages,
+                    only,
+                )
+                .await?
+            }
+            None => {
+                self.conda_prefix(
+                    environment,
+                    conda_reinstall_packages,
+                    skipped_packages,
+                    only,
+                )
+                .await?
+            }
+        };
 
-                // No `uv` support for WASM right now
-                if platform.arch() == Some(Arch::Wasm32) {
-                    return Ok(prefix);
-                }
+        let pypi_records = self
+            .pypi_records(environment, platform)?
+            .unwrap_or_default();
 
-                let pypi_lock_file_names = pypi_records
-                    .iter()
-                    .filter_map(|(data, _)| to_uv_normalize(&data.name).ok())
-                    .collect::<HashSet<_>>();
-
-                // Figure out uv reinstall
-                let (uv_reinstall, uv_packages) = match reinstall_packages {
-                    ReinstallPackages::None => (Some(false), None),
-                    ReinstallPackages::All => (Some(true), None),
-                    ReinstallPackages::Some(pkgs) => (
-                        None,
-                        Some(
-                            pkgs.iter()
-                                .filter_map(|pkg| uv_pep508::PackageName::from_str(pkg).ok())
-                                .filter(|name| pypi_lock_file_names.contains(name))
-                                .collect(),
-                        ),
-                    ),
-                };
-
-                let uv_context = self
-                    .uv_context
-                    .get_or_try_init(|| UvResolutionContext::from_workspace(self.workspace))?
-                    .clone()
-                    .set_cache_refresh(uv_reinstall, uv_packages);
+        // No `uv` support for WASM right now
+        if platform.arch() == Some(Arch::Wasm32) {
+            return Ok(prefix);
+        }
 
-                // TODO: This can be really slow (~200ms for pixi on @ruben-arts machine).
-                let env_variables = get_activated_environment_variables(
-                    self.workspace.env_vars(),
-                    environment,
-                    CurrentEnvVarBehavior::Exclude,
-                    None,
-                    false,
-                    false,
-                )
-                .await?;
+        // Figure out uv reinstall
+        let (uv_reinstall, uv_packages) = uv_reinstall_packages(reinstall_packages, &pypi_records);
 
-                let non_isolated_packages = environment.pypi_options().no_build_isolation;
-                let no_build = environment
-                    .pypi_options()
-                    .no_build
-                    .clone()
-                    .unwrap_or_default();
-                let no_binary = environment
-                    .pypi_options()
-                    .no_binary
-                    .clone()
-                    .unwrap_or_default();
+        let uv_context = self
+            .uv_context
+            .get_or_try_init(|| UvResolutionContext::from_workspace(self.workspace))?
+            .clone()
+            .set_cache_refresh(uv_reinstall, uv_packages);
 
-                // Update the prefix with Pypi records
-                environment::update_prefix_pypi(
-                    environment.name(),
-                    &prefix,
-                    platform,
-                    &pixi_records,
-                    &pypi_records,
-                    &python_status,
-                    &environment.system_requirements(),
-                    &uv_context,
-                    self.pypi_indexes(environment)?.as_ref(),
-                    env_variables,
-                    self.workspace.root(),
-                    environment.best_platform(),
-                    &non_isolated_packages,
-                    &no_build,
-                    &no_binary,
-                )
-                .await
-                .with_context(|| {
-                    format!(
-                        "Failed to update PyPI packages for environment '{}'",
-                        environment.name().fancy_display()
-                    )
-                })?;
+        // TODO: This can be really slow (~200ms for pixi on @ruben-arts machine).
+        let env_variables = get_activated_environment_variables(
+            self.workspace.env_vars(),
+            environment,
+            CurrentEnvVarBehavior::Exclude,
+            None,
+            false,
+            false,
+        )
+        .await?;
+
+        let non_isolated_packages = environment.pypi_options().no_build_isolation;
+        let no_build = environment
+            .pypi_options()
+            .no_build
+            .clone()
+            .unwrap_or_default();
+        let no_binary = environment
+            .pypi_options()
+            .no_binary
+            .clone()
+            .unwrap_or_default();
+
+        // Update the prefix with Pypi records
+        environment::update_prefix_pypi(
+            environment.name(),
+            &prefix,
+            platform,
+            &pixi_records,
+            &pypi_records,
+            &python_status,
+            &environment.system_requirements(),
+            &uv_context,
+            self.workspace.authenticated_client()?,
+            self.pypi_indexes(environment)?.as_ref(),
+            env_variables,
+            self.workspace.root(),
+            environment.best_platform(),
+            &non_isolated_packages,
+            &no_build,
+            &no_binary,
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to update PyPI packages for environment '{}'",
+                environment.name().fancy_display()
+            )
+        })?;
 
-                tracing::info!(
-                    "Installed environment '{}' in {:?}",
-                    environment.name().fancy_display(),
-                    start.elapsed()
-                );
+        tracing::info!(
+            "Installed environment '{}' in {:?}",
+            environment.name().fancy_display(),
+            start.elapsed()
+        );
 
-                Ok(prefix)
-            })
-            .await
-            .cloned()
+        Ok(prefix)
     }
 
     fn pypi_records(
@@ -596,6 +903,8 @@ impl<'p> LockFileDerivedData<'p> {
         &self,
         environment: &Environment<'p>,
         reinstall_packages: Option<HashSet<PackageName>>,
+        skipped_packages: &BTreeSet<PackageName>,
+        only: Option<&HashSet<PackageName>>,
     ) -> miette::Result<(Prefix, PythonStatus)> {
         // If we previously updated this environment, early out.
         let prefix_once_cell = self
@@ -604,33 +913,256 @@ impl<'p> LockFileDerivedData<'p> {
             .or_default()
             .clone();
         prefix_once_cell
-            .get_or_try_init(async {
-                // Create object to update the prefix
-                let group = GroupedEnvironment::Environment(environment.clone());
-                let platform = environment.best_platform();
-
-                let conda_prefix_updater =
-                    CondaPrefixUpdaterBuilder::new(group, platform, self.build_context.clone())
-                        .build()?;
-
-                // Get the locked environment from the lock-file.
-                let records = self
-                    .pixi_records(environment, platform)?
-                    .unwrap_or_default();
-                // Update the conda prefix
-                let CondaPrefixUpdated {
-                    prefix,
-                    python_status,
-                    ..
-                } = conda_prefix_updater
-                    .update(records, reinstall_packages)
-                    .await?;
-
-                Ok((prefix.clone(), *python_status.clone()))
-            })
+            .get_or_try_init(self.install_conda_packages(
+                environment,
+                None,
+                reinstall_packages,
+                skipped_packages,
+                only,
+            ))
             .await
             .map(|(prefix, python_status)| (prefix.clone(), python_status.clone()))
     }
+
+    /// Installs the locked conda packages of the environment into the
+    /// `target_prefix`, or into the prefix of the environment if no target is
+    /// given.
+    async fn install_conda_packages(
+        &self,
+        environment: &Environment<'p>,
+        target_prefix: Option<Prefix>,
+        reinstall_packages: Option<HashSet<PackageName>>,
+        skipped_packages: &BTreeSet<PackageName>,
+        only: Option<&HashSet<PackageName>>,
+    ) -> miette::Result<(Prefix, PythonStatus)> {
+        // Create object to update the prefix
+        let group = GroupedEnvironment::Environment(environment.clone());
+        let platform = environment.best_platform();
+
+        // Get the locked environment from the lock-file.
+        let mut records = skip_records(
+            environment,
+            self.pixi_records(environment, platform)?
+                .unwrap_or_default(),
+            skipped_packages,
+        );
+        if let Some(only) = only {
+            let prefix = target_prefix
+                .clone()
+                .unwrap_or_else(|| Prefix::new(environment.dir()));
+            records = keep_installed_records(&prefix, records, only)?;
+        }
+
+        let mut conda_prefix_updater_builder =
+            CondaPrefixUpdaterBuilder::new(group, platform, self.build_context.clone());
+        if let Some(target_prefix) = target_prefix {
+            conda_prefix_updater_builder = conda_prefix_updater_builder.with_prefix(target_prefix);
+        }
+        let conda_prefix_updater = conda_prefix_updater_builder.build()?;
+        // Update the conda prefix
+        let CondaPrefixUpdated {
+            prefix,
+            python_status,
+            ..
+        } = conda_prefix_updater
+            .update(records, reinstall_packages)
+            .await?;
+
+        Ok((prefix.clone(), *python_status.clone()))
+    }
+}
+
+/// Replaces the records of the packages that are not in `only` by the records
+/// of the packages as they are installed in the prefix, so that updating the
+/// prefix only links, updates or unlinks the packages in `only`.
+fn keep_installed_records(
+    prefix: &Prefix,
+    records: Vec<PixiRecord>,
+    only: &HashSet<PackageName>,
+) -> miette::Result<Vec<PixiRecord>> {
+    let installed = if prefix.root().is_dir() {
+        prefix.find_installed_packages()?
+    } else {
+        Vec::new()
+    };
+    Ok(installed
+        .into_iter()
+        .filter(|record| !only.contains(&record.repodata_record.package_record.name))
+        .map(|record| PixiRecord::Binary(record.repodata_record))
+        .chain(
+            records
+                .into_iter()
+                .filter(|record| only.contains(record.name())),
+        )
+        .collect())
+}
+
+/// Returns true if the conda packages installed in the prefix are exactly the
+/// packages of the records, so that updating the whole prefix wouldn't change
+/// it. A prefix with source packages never matches, they are always updated.
+fn installed_records_match(prefix: &Prefix, records: &[PixiRecord]) -> miette::Result<bool> {
+    let mut locked = HashSet::new();
+    for record in records {
+        let PixiRecord::Binary(record) = record else {
+            return Ok(false);
+        };
+        let package = &record.package_record;
+        locked.insert((&package.name, package.version.to_string(), &package.build));
+    }
+    let installed = if prefix.root().is_dir() {
+        prefix.find_installed_packages()?
+    } else {
+        Vec::new()
+    };
+    Ok(installed.len() == locked.len()
+        && installed.iter().all(|record| {
+            let package = &record.repodata_record.package_record;
+            locked.contains(&(&package.name, package.version.to_string(), &package.build))
+        }))
+}
+
+/// Returns the conda packages of the records that have to be reinstalled.
+fn conda_reinstall_packages(
+    reinstall_packages: &ReinstallPackages,
+    pixi_records: &[PixiRecord],
+) -> Option<HashSet<PackageName>> {
+    match reinstall_packages {
+        ReinstallPackages::None => None,
+        ReinstallPackages::Some(p) => Some(
+            p.iter()
+                .filter_map(|p| PackageName::from_str(p).ok())
+                .filter(|name| pixi_records.iter().any(|r| r.name() == name))
+                .collect(),
+        ),
+        ReinstallPackages::All => Some(pixi_records.iter().map(|r| r.name().clone()).collect()),
+    }
+}
+
+/// Returns whether uv reinstalls all packages, or which of the locked PyPI
+/// packages it reinstalls.
+fn uv_reinstall_packages(
+    reinstall_packages: &ReinstallPackages,
+    pypi_records: &[(PypiPackageData, PypiPackageEnvironmentData)],
+) -> (Option<bool>, Option<Vec<uv_pep508::PackageName>>) {
+    match reinstall_packages {
+        ReinstallPackages::None => (Some(false), None),
+        ReinstallPackages::All => (Some(true), None),
+        ReinstallPackages::Some(pkgs) => {
+            let pypi_lock_file_names = pypi_records
+                .iter()
+                .filter_map(|(data, _)| to_uv_normalize(&data.name).ok())
+                .collect::<HashSet<_>>();
+            (
+                None,
+                Some(
+                    pkgs.iter()
+                        .filter_map(|pkg| uv_pep508::PackageName::from_str(pkg).ok())
+                        .filter(|name| pypi_lock_file_names.contains(name))
+                        .collect(),
+                ),
+            )
+        }
+    }
+}
+
+/// Prints which packages of the environment were repaired after verifying the
+/// content of its files. Returns an error for the packages that are still
+/// corrupted after linking them again.
+fn report_verification(
+    environment: &Environment<'_>,
+    verification: &PrefixVerification,
+    unrepaired: &PrefixVerification,
+) -> miette::Result<()> {
+    if verification.unverifiable_files > 0 {
+        tracing::info!(
+            "{} files of the {} environment contain a prefix placeholder without a recorded digest and were not verified",
+            verification.unverifiable_files,
+            environment.name().as_str(),
+        );
+    }
+
+    if !unrepaired.corrupted.is_empty() {
+        miette::bail!(
+            help = "the files in the package cache are corrupted as well, run `pixi clean cache --conda` and install again",
+            "linking the packages of the {} environment again did not repair {}",
+            environment.name().fancy_display(),
+            unrepaired
+                .corrupted
+                .keys()
+                .map(|name| format!("'{}'", name.as_source()))
+                .join(", "),
+        );
+    }
+
+    if verification.corrupted.is_empty() {
+        eprintln!(
+            "{}Verified {} files of the {} environment, no corrupted files found",
+            console::style(console::Emoji("✔ ", "")).green(),
+            verification.checked_files,
+            environment.name().fancy_display(),
+        );
+        return Ok(());
+    }
+
+    eprintln!(
+        "{}Verified {} files of the {} environment, repaired {} packages:",
+        console::style(console::Emoji("✔ ", "")).green(),
+        verification.checked_files,
+        environment.name().fancy_display(),
+        verification.corrupted.len(),
+    );
+    for (name, paths) in &verification.corrupted {
+        eprintln!(
+            "    {} ({} corrupted files)",
+            console::style(name.as_source()).bold(),
+            paths.len()
+        );
+        for path in paths {
+            tracing::info!(
+                "corrupted file of '{}': {}",
+                name.as_source(),
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Removes the skipped packages from the records that are linked into the
+/// prefix. Skipping a package that other packages depend on is allowed, but
+/// it is reported.
+fn skip_records(
+    environment: &Environment<'_>,
+    mut records: Vec<PixiRecord>,
+    skipped_packages: &BTreeSet<PackageName>,
+) -> Vec<PixiRecord> {
+    if skipped_packages.is_empty() {
+        return records;
+    }
+
+    records.retain(|record| !skipped_packages.contains(&record.package_record().name));
+    for skipped in skipped_packages {
+        let dependents = records
+            .iter()
+            .filter(|record| {
+                record
+                    .package_record()
+                    .depends
+                    .iter()
+                    .any(|spec| spec.split_whitespace().next() == Some(skipped.as_normalized()))
+            })
+            .map(|record| record.package_record().name.as_source())
+            .collect_vec();
+        if !dependents.is_empty() {
+            tracing::warn!(
+                "skipping '{}' in the environment '{}', it is required by {}",
+                skipped.as_source(),
+                environment.name().fancy_display(),
+                dependents.iter().map(|name| format!("'{name}'")).join(", ")
+            );
+        }
+    }
+    records
 }
 
 pub struct UpdateContext<'p> {
@@ -703,6 +1235,9 @@ pub struct UpdateContext<'p> {
     /// Whether it is allowed to instantiate any prefix.
     no_install: bool,
 
+    /// Additional version constraints that are applied when solving.
+    solve_constraints: SolveConstraints,
+
     /// The progress bar where all the command dispatcher progress will be
     /// placed.
     dispatcher_progress_bar: ProgressBar,
@@ -879,6 +1414,9 @@ pub struct UpdateContextBuilder<'p> {
 
     /// A cache for computing input hashes
     glob_hash_cache: Option<GlobHashCache>,
+
+    /// Additional version constraints that are applied when solving.
+    solve_constraints: SolveConstraints,
 }
 
 impl<'p> UpdateContextBuilder<'p> {
@@ -923,6 +1461,15 @@ impl<'p> UpdateContextBuilder<'p> {
         }
     }
 
+    /// Sets additional version constraints that are applied when solving the
+    /// outdated environments, without recording them in the manifest.
+    pub(crate) fn with_solve_constraints(self, solve_constraints: SolveConstraints) -> Self {
+        Self {
+            solve_constraints,
+            ..self
+        }
+    }
+
     /// Sets the io concurrency semaphore to use when updating environments.
     #[allow(unused)]
     pub fn with_io_concurrency_semaphore(self, io_concurrency_limit: IoConcurrencyLimit) -> Self {
@@ -955,6 +1502,13 @@ impl<'p> UpdateContextBuilder<'p> {
             }
         };
 
+        // Without network access the solve can only use the cached repodata
+        let offline_gateway = if project.config().network().offline() {
+            ensure_repodata_cached(project, &outdated).await?
+        } else {
+            None
+        };
+
         // Extract the current conda records from the lock-file
         // TODO: Should we parallelize this? Measure please.
         let locked_repodata_records = project
@@ -1111,23 +1665,32 @@ impl<'p> UpdateContextBuilder<'p> {
         // Construct a command dispatcher that will be used to run the tasks.
         let multi_progress = global_multi_progress();
         let anchor_pb = multi_progress.add(ProgressBar::hidden());
-        let command_dispatcher = self
-            .project
-            .command_dispatcher_builder()?
-            .with_reporter(crate::reporters::TopLevelProgress::new(
-                global_multi_progress(),
-                anchor_pb.clone(),
-            ))
-            .finish();
+        let mut command_dispatcher_builder = self.project.command_dispatcher_builder()?;
+        if let Some(gateway) = offline_gateway {
+            command_dispatcher_builder = command_dispatcher_builder.with_gateway(gateway);
+        }
+        let command_dispatcher = crate::reporters::with_top_level_reporter(
+            command_dispatcher_builder,
+            anchor_pb.clone(),
+        )
+        .finish();
 
         // tool context
         let build_context = BuildContext::from_workspace(project, command_dispatcher)?;
 
-        let mapping_client = self.mapping_client.unwrap_or_else(|| {
-            MappingClient::builder(client)
+        let mapping_client = match self.mapping_client {
+            Some(mapping_client) => mapping_client,
+            // The authenticated client refuses every request while offline,
+            // the mapping client reads from its own http cache instead.
+            None if project.config().network().offline() => MappingClient::offline_builder(
+                ClientWithMiddleware::from(project.client()?.clone()),
+            )
+            .with_concurrency_limit(project.concurrent_downloads_semaphore())
+            .finish(),
+            None => MappingClient::builder(client)
                 .with_concurrency_limit(project.concurrent_downloads_semaphore())
-                .finish()
-        });
+                .finish(),
+        };
 
         Ok(UpdateContext {
             project,
@@ -1153,6 +1716,7 @@ impl<'p> UpdateContextBuilder<'p> {
             dispatcher_progress_bar: anchor_pb,
 
             no_install: self.no_install,
+            solve_constraints: self.solve_constraints,
         })
     }
 }
@@ -1169,6 +1733,7 @@ impl<'p> UpdateContext<'p> {
             io_concurrency_limit: None,
             glob_hash_cache: None,
             mapping_client: None,
+            solve_constraints: SolveConstraints::default(),
         }
     }
 
@@ -1242,6 +1807,7 @@ impl<'p> UpdateContext<'p> {
                 let group_solve_task = spawn_solve_conda_environment_task(
                     source.clone(),
                     locked_group_records,
+                    self.solve_constraints.conda_constraints(&source, platform),
                     self.mapping_client.clone(),
                     platform,
                     channel_priority,
@@ -1265,6 +1831,24 @@ impl<'p> UpdateContext<'p> {
             }
         }
 
+        // Record which environments and platforms are solved.
+        let solved_targets = self
+            .grouped_solved_repodata_records
+            .iter()
+            .flat_map(|(group, platforms)| {
+                group
+                    .environments()
+                    .cartesian_product(platforms.keys().copied())
+                    .map(|(env, platform)| (env.name().clone(), platform))
+                    .collect_vec()
+            })
+            .sorted_by(|(a, a_platform), (b, b_platform)| {
+                a.as_str()
+                    .cmp(b.as_str())
+                    .then_with(|| a_platform.as_str().cmp(b_platform.as_str()))
+            })
+            .collect_vec();
+
         // Spawn tasks to update the pypi packages.
         let uv_context = once_cell::sync::OnceCell::new();
         for (environment, platform) in
@@ -1342,6 +1926,7 @@ impl<'p> UpdateContext<'p> {
                 self.pypi_solve_semaphore.clone(),
                 project.root().to_path_buf(),
                 locked_group_records,
+                self.solve_constraints.pypi_constraints(&group, platform),
                 self.no_install,
             );
 
@@ -1648,6 +2233,7 @@ impl<'p> UpdateContext<'p> {
             build_context: self.build_context,
             glob_hash_cache: self.glob_hash_cache,
             was_outdated: true,
+            solved_targets,
         })
     }
 }
@@ -1732,6 +2318,7 @@ pub enum TaskResult {
 async fn spawn_solve_conda_environment_task(
     group: GroupedEnvironment<'_>,
     existing_repodata_records: Arc<PixiRecordsByName>,
+    constraints: DependencyMap<PackageName, NamelessMatchSpec>,
     mapping_client: MappingClient,
     platform: Platform,
     channel_priority: ChannelPriority,
@@ -1796,7 +2383,7 @@ async fn spawn_solve_conda_environment_task(
                 .solve_pixi_environment(PixiEnvironmentSpec {
                     name: Some(group_name.to_string()),
                     dependencies,
-                    constraints: Default::default(),
+                    constraints,
                     installed: existing_repodata_records.records.clone(),
                     build_environment: BuildEnvironment::simple(platform, virtual_packages),
                     channels,
@@ -2037,6 +2624,7 @@ async fn spawn_solve_pypi_task<'p>(
     semaphore: Arc<Semaphore>,
     project_root: PathBuf,
     locked_pypi_packages: Arc<PypiRecordsByName>,
+    constraints: Vec<(pep508_rs::PackageName, pep440_rs::VersionSpecifiers)>,
     disallow_install_conda_prefix: bool,
 ) -> miette::Result<TaskResult> {
     // Get the Pypi dependencies for this environment
@@ -2104,6 +2692,7 @@ async fn spawn_solve_pypi_task<'p>(
             system_requirements,
             pixi_solve_records,
             locked_pypi_records,
+            &constraints,
             platform,
             &pb.pb,
             &project_root,
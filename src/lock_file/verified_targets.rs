@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use pixi_manifest::{FeaturesExt, SystemRequirements, pypi::pypi_options::PypiOptions};
+use pixi_pypi_spec::PixiPypiSpec;
+use pixi_spec::PixiSpec;
+use rattler_conda_types::Platform;
+use rattler_digest::Sha256;
+use rattler_lock::{CondaPackageData, LockFile, LockedPackageRef, UrlOrPath};
+use serde::{Deserialize, Serialize};
+
+use super::satisfiability::fmt_solve_strategy;
+use crate::{Workspace, workspace::Environment};
+
+/// The name of the file in the `.pixi` directory that records the verified
+/// targets.
+const VERIFIED_TARGETS_FILE: &str = "lock-file-inputs.json";
+
+/// Records for every environment and platform the hash of the inputs of the
+/// manifest and of the locked packages the last time the lock-file was found
+/// to satisfy the manifest. As long as neither changes, the locked packages of
+/// the target are reused as they are without verifying them again, so editing
+/// the dependencies of one environment only affects that environment.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct VerifiedTargets {
+    targets: BTreeMap<String, String>,
+}
+
+impl VerifiedTargets {
+    /// Reads the verified targets of the workspace, nothing is verified if the
+    /// file is missing or can't be read.
+    pub(crate) fn load(workspace: &Workspace) -> Self {
+        fs_err::read_to_string(workspace.pixi_dir().join(VERIFIED_TARGETS_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Records every environment and platform of the lock-file, which must
+    /// satisfy the manifest.
+    pub(crate) fn from_lock_file(workspace: &Workspace, lock_file: &LockFile) -> Self {
+        let mut targets = BTreeMap::new();
+        for environment in workspace.environments() {
+            for platform in environment.platforms() {
+                if let Some(hash) = target_hash(&environment, platform, lock_file) {
+                    targets.insert(target_key(&environment, platform), hash);
+                }
+            }
+        }
+        Self { targets }
+    }
+
+    /// Returns true if the target was verified with the same inputs and
+    /// locked packages.
+    pub(crate) fn contains(
+        &self,
+        environment: &Environment<'_>,
+        platform: Platform,
+        lock_file: &LockFile,
+    ) -> bool {
+        self.targets
+            .get(&target_key(environment, platform))
+            .is_some_and(|hash| {
+                target_hash(environment, platform, lock_file).as_ref() == Some(hash)
+            })
+    }
+
+    /// Writes the verified targets to the `.pixi` directory, if it exists, so
+    /// that only locking a workspace doesn't create it. Failing to do so only
+    /// means that the targets are verified again next time.
+    pub(crate) fn write(&self, workspace: &Workspace) {
+        let pixi_dir = workspace.pixi_dir();
+        if !pixi_dir.is_dir() {
+            return;
+        }
+        let result = fs_err::write(
+            pixi_dir.join(VERIFIED_TARGETS_FILE),
+            serde_json::to_string(self).expect("failed to serialize the verified targets"),
+        );
+        if let Err(err) = result {
+            tracing::warn!("failed to record the verified lock-file targets: {err}");
+        }
+    }
+}
+
+fn target_key(environment: &Environment<'_>, platform: Platform) -> String {
+    format!("{}/{}", environment.name().as_str(), platform)
+}
+
+/// The inputs of the manifest that determine the locked packages of an
+/// environment for a platform, together with the locked packages themselves.
+/// This is serialized to compute the hash of a target.
+#[derive(Serialize)]
+struct TargetInputs {
+    dependencies: BTreeMap<String, Vec<PixiSpec>>,
+    pypi_dependencies: BTreeMap<String, Vec<PixiPypiSpec>>,
+    channels: Vec<String>,
+    channel_priority: Option<String>,
+    solve_strategy: &'static str,
+    exclude_newer: Option<String>,
+    system_requirements: SystemRequirements,
+    pypi_options: PypiOptions,
+    locked: Vec<LockedInput>,
+}
+
+/// Identifies a locked package by its artifact.
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+struct LockedInput {
+    location: String,
+    sha256: Option<String>,
+    md5: Option<String>,
+    extras: Vec<String>,
+}
+
+/// Hashes the effective specs of an environment for a platform together with
+/// its locked packages. Returns `None` for targets that depend on more than
+/// the manifest, like source packages, or that are solved together with other
+/// environments, those are always verified.
+fn target_hash(
+    environment: &Environment<'_>,
+    platform: Platform,
+    lock_file: &LockFile,
+) -> Option<String> {
+    if environment.solve_group().is_some() {
+        return None;
+    }
+    let packages = lock_file
+        .environment(environment.name().as_str())?
+        .packages(platform)?
+        .collect::<Vec<_>>();
+
+    // The packages are sorted, because the order in which they are written
+    // depends on whether the lock-file is written in canonical form.
+    let mut locked = Vec::with_capacity(packages.len());
+    for package in packages {
+        locked.push(match package {
+            LockedPackageRef::Conda(CondaPackageData::Source(_)) => return None,
+            LockedPackageRef::Conda(CondaPackageData::Binary(binary)) => LockedInput {
+                location: binary.location.to_string(),
+                sha256: binary
+                    .package_record
+                    .sha256
+                    .map(|sha256| format!("{sha256:x}")),
+                md5: binary.package_record.md5.map(|md5| format!("{md5:x}")),
+                extras: Vec::new(),
+            },
+            LockedPackageRef::Pypi(data, environment_data) => {
+                match &data.location {
+                    UrlOrPath::Path(_) => return None,
+                    UrlOrPath::Url(url) if url.scheme().starts_with("git+") => return None,
+                    UrlOrPath::Url(_) => {}
+                }
+                let hash = data.hash.as_ref();
+                LockedInput {
+                    location: data.location.to_string(),
+                    sha256: hash
+                        .and_then(|hash| hash.sha256())
+                        .map(|sha256| format!("{sha256:x}")),
+                    md5: hash
+                        .and_then(|hash| hash.md5())
+                        .map(|md5| format!("{md5:x}")),
+                    extras: environment_data
+                        .extras
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                }
+            }
+        });
+    }
+    locked.sort();
+
+    let inputs = TargetInputs {
+        dependencies: environment
+            .combined_dependencies(Some(platform))
+            .iter()
+            .map(|(name, specs)| {
+                (
+                    name.as_source().to_string(),
+                    specs.iter().cloned().collect(),
+                )
+            })
+            .collect(),
+        pypi_dependencies: environment
+            .pypi_dependencies(Some(platform))
+            .iter()
+            .map(|(name, specs)| {
+                (
+                    name.as_source().to_string(),
+                    specs.iter().cloned().collect(),
+                )
+            })
+            .collect(),
+        channels: environment
+            .channels()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect(),
+        channel_priority: environment
+            .channel_priority()
+            .ok()
+            .flatten()
+            .map(|priority| priority.to_string()),
+        solve_strategy: fmt_solve_strategy(environment.solve_strategy()),
+        exclude_newer: environment
+            .exclude_newer()
+            .map(|exclude_newer| exclude_newer.to_rfc3339()),
+        system_requirements: environment.system_requirements(),
+        pypi_options: environment.pypi_options(),
+        locked,
+    };
+    let serialized = serde_json::to_vec(&inputs).expect("failed to serialize the target inputs");
+    Some(format!(
+        "{:x}",
+        rattler_digest::compute_bytes_digest::<Sha256>(&serialized)
+    ))
+}
@@ -0,0 +1,446 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use futures::{StreamExt, stream};
+use itertools::Itertools;
+use pixi_consts::consts;
+use rattler_conda_types::{
+    Platform,
+    package::{PackageFile, PathType, PathsEntry, PathsJson},
+};
+use rattler_digest::{Md5, Md5Hash, Sha256, Sha256Hash, digest::Digest, parse_digest_from_hex};
+use rattler_lock::{CondaPackageData, LockFile, LockedPackageRef, UrlOrPath};
+use reqwest::{StatusCode, header::RANGE};
+use reqwest_middleware::ClientWithMiddleware;
+use tabwriter::TabWriter;
+use tokio::io::AsyncReadExt;
+use url::Url;
+
+use crate::Workspace;
+
+/// A downloadable artifact of a locked package.
+#[derive(Debug, Clone)]
+struct LockedArtifact {
+    /// The name and version of the package, used in the report.
+    package: String,
+    url: Url,
+    sha256: Option<Sha256Hash>,
+    md5: Option<Md5Hash>,
+    /// The name of the directory of the package in the conda package cache.
+    cache_entry: Option<String>,
+}
+
+/// Why a locked artifact failed the verification.
+#[derive(Debug, Clone)]
+pub enum ArtifactProblem {
+    /// The artifact could not be downloaded.
+    Unreachable(String),
+    /// The downloaded artifact doesn't have the locked hash.
+    HashMismatch { expected: String, actual: String },
+    /// The artifact in the package cache doesn't have the locked hash.
+    CachedHashMismatch { expected: String, actual: String },
+    /// Files of the extracted package in the package cache are missing or
+    /// don't match the `info/paths.json` of the package.
+    CorruptedCache(Vec<PathBuf>),
+}
+
+/// An artifact that failed the verification.
+#[derive(Debug, Clone)]
+pub struct ArtifactFailure {
+    pub package: String,
+    pub url: Url,
+    pub problem: ArtifactProblem,
+}
+
+/// The result of verifying the artifacts of a lock-file.
+#[derive(Debug, Default)]
+pub struct ArtifactReport {
+    /// The number of unique artifacts that were checked.
+    pub checked: usize,
+    pub failures: Vec<ArtifactFailure>,
+}
+
+impl ArtifactReport {
+    /// Prints the artifacts that failed the verification.
+    pub fn print_failures(&self) -> std::io::Result<()> {
+        let mut writer = TabWriter::new(std::io::stderr());
+        for failure in &self.failures {
+            let problem = match &failure.problem {
+                ArtifactProblem::Unreachable(reason) => format!("unreachable: {reason}"),
+                ArtifactProblem::HashMismatch { expected, actual } => {
+                    format!("hash mismatch: locked {expected}, downloaded {actual}")
+                }
+                ArtifactProblem::CachedHashMismatch { expected, actual } => {
+                    format!("hash mismatch: locked {expected}, cached {actual}")
+                }
+                ArtifactProblem::CorruptedCache(files) => format!(
+                    "corrupted in the package cache: {}",
+                    files.iter().map(|file| file.display()).format(", ")
+                ),
+            };
+            writeln!(
+                writer,
+                "  {}\t{}\t{}",
+                console::style(&failure.package).bold(),
+                failure.url,
+                console::style(problem).red()
+            )?;
+        }
+        writer.flush()
+    }
+}
+
+/// Checks that the artifact of every locked conda and pypi package of the
+/// selected environments and platforms can still be downloaded, without
+/// installing anything. An artifact that is in the conda package cache is also
+/// compared with the locked hash, and the files of the extracted package with
+/// its `info/paths.json`. With `deep`, every artifact is downloaded completely
+/// and its hash is compared with the locked hash.
+///
+/// Every url is only checked once, even if it is locked by multiple
+/// environments or platforms.
+pub(crate) async fn verify_locked_artifacts(
+    workspace: &Workspace,
+    lock_file: &LockFile,
+    mut selected: impl FnMut(&str, Platform) -> bool,
+    deep: bool,
+) -> miette::Result<ArtifactReport> {
+    let mut artifacts: HashMap<Url, LockedArtifact> = HashMap::new();
+    for (environment_name, environment) in lock_file.environments() {
+        for (platform, packages) in environment.packages_by_platform() {
+            if !selected(environment_name, platform) {
+                continue;
+            }
+            for package in packages {
+                if let Some(artifact) = locked_artifact(package) {
+                    artifacts.entry(artifact.url.clone()).or_insert(artifact);
+                }
+            }
+        }
+    }
+
+    let client = workspace.authenticated_client()?.clone();
+    let package_cache_dir = pixi_config::get_cache_dir()?.join(consts::CONDA_PACKAGE_CACHE_DIR);
+    let checked = artifacts.len();
+    let failures = stream::iter(
+        artifacts
+            .into_values()
+            .sorted_by(|a, b| a.package.cmp(&b.package)),
+    )
+    .map(|artifact| {
+        let client = client.clone();
+        let package_cache_dir = package_cache_dir.clone();
+        async move {
+            let problem = verify_artifact(&client, &package_cache_dir, &artifact, deep).await;
+            problem.map(|problem| ArtifactFailure {
+                package: artifact.package,
+                url: artifact.url,
+                problem,
+            })
+        }
+    })
+    .buffered(workspace.config().max_concurrent_downloads().max(1))
+    .filter_map(|failure| async move { failure })
+    .collect::<Vec<_>>()
+    .await;
+
+    Ok(ArtifactReport { checked, failures })
+}
+
+/// Returns the artifact of a locked package, packages that are built from
+/// source or installed from a path or a git repository have no artifact.
+fn locked_artifact(package: LockedPackageRef<'_>) -> Option<LockedArtifact> {
+    match package {
+        LockedPackageRef::Conda(CondaPackageData::Binary(binary)) => {
+            let UrlOrPath::Url(url) = &binary.location else {
+                return None;
+            };
+            let record = &binary.package_record;
+            Some(LockedArtifact {
+                package: format!("{} {}", record.name.as_source(), record.version),
+                url: url.clone(),
+                sha256: record.sha256,
+                md5: record.md5,
+                cache_entry: Some(format!(
+                    "{}-{}-{}",
+                    record.name.as_normalized(),
+                    record.version,
+                    record.build
+                )),
+            })
+        }
+        LockedPackageRef::Conda(CondaPackageData::Source(_)) => None,
+        LockedPackageRef::Pypi(data, _) => {
+            let UrlOrPath::Url(url) = &data.location else {
+                return None;
+            };
+            if !matches!(url.scheme(), "http" | "https" | "file") {
+                return None;
+            }
+            let hash = data.hash.as_ref();
+            Some(LockedArtifact {
+                package: format!("{} {}", data.name, data.version),
+                url: url.clone(),
+                sha256: hash.and_then(|hash| hash.sha256()).cloned(),
+                md5: hash.and_then(|hash| hash.md5()).cloned(),
+                cache_entry: None,
+            })
+        }
+    }
+}
+
+async fn verify_artifact(
+    client: &ClientWithMiddleware,
+    package_cache_dir: &Path,
+    artifact: &LockedArtifact,
+    deep: bool,
+) -> Option<ArtifactProblem> {
+    // The files of the cached package are hashed, which blocks
+    let cached = {
+        let package_cache_dir = package_cache_dir.to_path_buf();
+        let artifact = artifact.clone();
+        tokio::task::spawn_blocking(move || verify_cached_artifact(&package_cache_dir, &artifact))
+            .await
+            .ok()
+            .flatten()
+    };
+    if let Some(problem) = cached {
+        return Some(problem);
+    }
+
+    if artifact.url.scheme() == "file" {
+        let path = artifact.url.to_file_path().unwrap_or_default();
+        return verify_local_artifact(&path, artifact, deep).await;
+    }
+
+    if deep {
+        let Some(mut hasher) = ArtifactHasher::for_artifact(artifact) else {
+            return download(client, &artifact.url, true)
+                .await
+                .err()
+                .map(ArtifactProblem::Unreachable);
+        };
+        let mut response = match download(client, &artifact.url, false).await {
+            Ok(response) => response,
+            Err(reason) => return Some(ArtifactProblem::Unreachable(reason)),
+        };
+        // The artifact is hashed while it is downloaded, so that it is never
+        // kept in memory completely
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => hasher.update(&chunk),
+                Ok(None) => break,
+                Err(err) => return Some(ArtifactProblem::Unreachable(err.to_string())),
+            }
+        }
+        return hasher.compare(artifact);
+    }
+
+    // Only check that the artifact exists, some servers don't support `HEAD`
+    // requests so the first byte is requested instead.
+    match download(client, &artifact.url, true).await {
+        Ok(_) => None,
+        Err(reason) => Some(ArtifactProblem::Unreachable(reason)),
+    }
+}
+
+/// Requests the artifact, only the headers and the first byte if `probe` is
+/// set.
+async fn download(
+    client: &ClientWithMiddleware,
+    url: &Url,
+    probe: bool,
+) -> Result<reqwest::Response, String> {
+    if probe {
+        let response = client
+            .head(url.clone())
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        if !matches!(
+            response.status(),
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::FORBIDDEN
+        ) {
+            return Err(response.status().to_string());
+        }
+    }
+
+    let mut request = client.get(url.clone());
+    if probe {
+        request = request.header(RANGE, "bytes=0-0");
+    }
+    let response = request.send().await.map_err(|err| err.to_string())?;
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(response.status().to_string())
+    }
+}
+
+async fn verify_local_artifact(
+    path: &Path,
+    artifact: &LockedArtifact,
+    deep: bool,
+) -> Option<ArtifactProblem> {
+    let missing = || ArtifactProblem::Unreachable(format!("{} does not exist", path.display()));
+    let (true, Some(mut hasher)) = (deep, ArtifactHasher::for_artifact(artifact)) else {
+        return (!path.is_file()).then(missing);
+    };
+    let mut file = match fs_err::tokio::File::open(path).await {
+        Ok(file) => file,
+        Err(_) => return Some(missing()),
+    };
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match file.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(read) => hasher.update(&buffer[..read]),
+            Err(err) => return Some(ArtifactProblem::Unreachable(err.to_string())),
+        }
+    }
+    hasher.compare(artifact)
+}
+
+/// Hashes the content of an artifact with the hash that is locked for it, the
+/// sha256 is preferred over the md5.
+enum ArtifactHasher {
+    Sha256(Sha256),
+    Md5(Md5),
+}
+
+impl ArtifactHasher {
+    /// Returns `None` if no hash is locked for the artifact.
+    fn for_artifact(artifact: &LockedArtifact) -> Option<Self> {
+        if artifact.sha256.is_some() {
+            Some(Self::Sha256(Sha256::default()))
+        } else if artifact.md5.is_some() {
+            Some(Self::Md5(Md5::default()))
+        } else {
+            None
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(bytes),
+            Self::Md5(hasher) => hasher.update(bytes),
+        }
+    }
+
+    /// Compares the hash of the content with the locked hash.
+    fn compare(self, artifact: &LockedArtifact) -> Option<ArtifactProblem> {
+        let (expected, actual) = match self {
+            Self::Sha256(hasher) => (
+                format!("{:x}", artifact.sha256?),
+                format!("{:x}", hasher.finalize()),
+            ),
+            Self::Md5(hasher) => (
+                format!("{:x}", artifact.md5?),
+                format!("{:x}", hasher.finalize()),
+            ),
+        };
+        (expected != actual).then_some(ArtifactProblem::HashMismatch { expected, actual })
+    }
+}
+
+/// Compares the sha256 that is recorded for a package in the conda package
+/// cache with the locked sha256, and the size and sha256 of the extracted files
+/// with the `info/paths.json` of the package.
+fn verify_cached_artifact(
+    package_cache_dir: &Path,
+    artifact: &LockedArtifact,
+) -> Option<ArtifactProblem> {
+    let expected = artifact.sha256?;
+    let package_dir = package_cache_dir.join(artifact.cache_entry.as_ref()?);
+    let content = fs_err::read_to_string(package_dir.join("info/repodata_record.json")).ok()?;
+    let record: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let actual = record
+        .get("sha256")
+        .and_then(|sha256| sha256.as_str())
+        .and_then(parse_digest_from_hex::<Sha256>)?;
+    if actual != expected {
+        return Some(ArtifactProblem::CachedHashMismatch {
+            expected: format!("{expected:x}"),
+            actual: format!("{actual:x}"),
+        });
+    }
+
+    let paths = PathsJson::from_package_directory(&package_dir).ok()?;
+    let corrupted = paths
+        .paths
+        .iter()
+        .filter(|entry| matches!(entry.path_type, PathType::HardLink))
+        .filter(|entry| !cached_file_intact(&package_dir, entry))
+        .map(|entry| entry.relative_path.clone())
+        .collect_vec();
+    (!corrupted.is_empty()).then_some(ArtifactProblem::CorruptedCache(corrupted))
+}
+
+/// Compares the size and sha256 of an extracted file with its entry in the
+/// `info/paths.json` of the package.
+fn cached_file_intact(package_dir: &Path, entry: &PathsEntry) -> bool {
+    let path = package_dir.join(&entry.relative_path);
+    let Ok(metadata) = fs_err::metadata(&path) else {
+        return false;
+    };
+    if entry
+        .size_in_bytes
+        .is_some_and(|size| size != metadata.len())
+    {
+        return false;
+    }
+    entry.sha256.is_none_or(|sha256| {
+        rattler_digest::compute_file_digest::<Sha256>(&path).is_ok_and(|digest| digest == sha256)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_cached_artifact() {
+        let sha256 = rattler_digest::compute_bytes_digest::<Sha256>(b"foo");
+        let package_cache_dir = tempfile::TempDir::new().unwrap();
+        let package_dir = package_cache_dir.path().join("foo-1-0");
+        fs_err::create_dir_all(package_dir.join("info")).unwrap();
+        fs_err::create_dir_all(package_dir.join("bin")).unwrap();
+        fs_err::write(
+            package_dir.join("info/repodata_record.json"),
+            format!(r#"{{"sha256": "{sha256:x}"}}"#),
+        )
+        .unwrap();
+        fs_err::write(
+            package_dir.join("info/paths.json"),
+            format!(
+                r#"{{"paths_version": 1, "paths": [{{"_path": "bin/foo", "path_type": "hardlink", "sha256": "{sha256:x}", "size_in_bytes": 3}}]}}"#
+            ),
+        )
+        .unwrap();
+        fs_err::write(package_dir.join("bin/foo"), "foo").unwrap();
+
+        let artifact = LockedArtifact {
+            package: "foo 1".to_string(),
+            url: Url::parse("https://example.com/foo-1-0.conda").unwrap(),
+            sha256: Some(sha256),
+            md5: None,
+            cache_entry: Some("foo-1-0".to_string()),
+        };
+        assert!(verify_cached_artifact(package_cache_dir.path(), &artifact).is_none());
+
+        // A file with the same size but another content is found
+        fs_err::write(package_dir.join("bin/foo"), "bar").unwrap();
+        let Some(ArtifactProblem::CorruptedCache(files)) =
+            verify_cached_artifact(package_cache_dir.path(), &artifact)
+        else {
+            panic!("expected the cached package to be corrupted");
+        };
+        assert_eq!(files, vec![PathBuf::from("bin/foo")]);
+    }
+}
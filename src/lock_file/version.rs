@@ -0,0 +1,185 @@
+use std::{path::Path, str::FromStr};
+
+use miette::Diagnostic;
+use pixi_consts::consts;
+use rattler_conda_types::Version;
+use rattler_lock::{LockFile, ParseCondaLockError};
+use thiserror::Error;
+
+/// The comment at the top of the lock-file that records the version of pixi
+/// that wrote it.
+const CREATED_BY_PREFIX: &str = "# This lock-file was created by pixi ";
+
+/// The lock-file was written by a newer version of pixi in a format that the
+/// running version can't read.
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "{}, but pixi {} only supports lock format {} and older",
+    describe_lock_file(.created_by.as_deref(), .lock_file_version.as_deref()),
+    consts::PIXI_VERSION,
+    .max_supported_version
+)]
+pub struct UnsupportedLockFileError {
+    created_by: Option<String>,
+    lock_file_version: Option<String>,
+    max_supported_version: String,
+    #[help]
+    help: String,
+}
+
+impl UnsupportedLockFileError {
+    fn new(
+        created_by: Option<String>,
+        lock_file_version: Option<String>,
+        max_supported_version: String,
+    ) -> Self {
+        let help = format!(
+            "upgrade pixi{}, or re-lock the workspace with this version by removing the lock-file and running `pixi lock`",
+            created_by
+                .as_ref()
+                .map(|created_by| format!(" to {created_by} or newer"))
+                .unwrap_or_default()
+        );
+        Self {
+            created_by,
+            lock_file_version,
+            max_supported_version,
+            help,
+        }
+    }
+}
+
+fn describe_lock_file(created_by: Option<&str>, lock_file_version: Option<&str>) -> String {
+    match (created_by, lock_file_version) {
+        (Some(created_by), Some(version)) => {
+            format!(
+                "this lock-file was created with pixi {created_by} which uses lock format {version}"
+            )
+        }
+        (Some(created_by), None) => format!("this lock-file was created with pixi {created_by}"),
+        (None, Some(version)) => format!("this lock-file uses lock format {version}"),
+        (None, None) => "this lock-file uses an unknown lock format".to_string(),
+    }
+}
+
+/// Writes the lock-file to disk, with a comment that records the version of
+/// pixi that wrote it. The file is left untouched if its content didn't
+/// change, so that using another version of pixi doesn't modify it.
+pub(crate) fn write_lock_file_with_version(
+    lock_file: &LockFile,
+    path: &Path,
+) -> std::io::Result<()> {
+    let content = lock_file.render_to_string()?;
+    if let Ok(existing) = fs_err::read_to_string(path) {
+        let existing = match existing.strip_prefix(CREATED_BY_PREFIX) {
+            Some(rest) => rest.split_once('\n').map_or("", |(_, rest)| rest),
+            None => existing.as_str(),
+        };
+        if existing == content {
+            return Ok(());
+        }
+    }
+    fs_err::write(
+        path,
+        format!("{CREATED_BY_PREFIX}{}\n{content}", consts::PIXI_VERSION),
+    )
+}
+
+/// Turns the error of parsing a lock-file into an [`UnsupportedLockFileError`]
+/// if the lock-file was written in a newer format, or by a newer version of
+/// pixi. Other errors are returned as they are.
+pub(crate) fn lock_file_parse_error(path: &Path, err: ParseCondaLockError) -> miette::Report {
+    let content = fs_err::read_to_string(path).unwrap_or_default();
+    let created_by = content
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix(CREATED_BY_PREFIX))
+        .map(|version| version.trim().to_string());
+    let lock_file_version = content
+        .lines()
+        .find_map(|line| line.strip_prefix("version:"))
+        .map(|version| version.trim().to_string());
+
+    match err {
+        ParseCondaLockError::IncompatibleVersion {
+            lock_file_version,
+            max_supported_version,
+        } => UnsupportedLockFileError::new(
+            created_by,
+            Some(lock_file_version.to_string()),
+            max_supported_version.to_string(),
+        )
+        .into(),
+        _ if created_by.as_deref().is_some_and(is_newer_than_current) => {
+            UnsupportedLockFileError::new(
+                created_by,
+                lock_file_version,
+                rattler_lock::FileFormatVersion::LATEST.to_string(),
+            )
+            .into()
+        }
+        err => miette::miette!(err),
+    }
+}
+
+/// Returns true if the version is newer than the running version of pixi.
+fn is_newer_than_current(version: &str) -> bool {
+    match (
+        Version::from_str(version),
+        Version::from_str(consts::PIXI_VERSION),
+    ) {
+        (Ok(version), Ok(current)) => version > current,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_future_lock_file_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(consts::PROJECT_LOCK_FILE);
+        fs_err::write(
+            &path,
+            format!("{CREATED_BY_PREFIX}99.0.0\nversion: 99\nenvironments: {{}}\npackages: []\n"),
+        )
+        .unwrap();
+
+        let err = LockFile::from_path(&path).unwrap_err();
+        let report = lock_file_parse_error(&path, err);
+        let message = report.to_string();
+        assert!(message.contains("created with pixi 99.0.0 which uses lock format 99"));
+        assert!(
+            report
+                .help()
+                .unwrap()
+                .to_string()
+                .contains("upgrade pixi to 99.0.0 or newer")
+        );
+    }
+
+    #[test]
+    fn test_written_lock_file_can_be_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(consts::PROJECT_LOCK_FILE);
+        write_lock_file_with_version(&LockFile::default(), &path).unwrap();
+
+        let content = fs_err::read_to_string(&path).unwrap();
+        assert!(content.starts_with(&format!("{CREATED_BY_PREFIX}{}\n", consts::PIXI_VERSION)));
+        LockFile::from_path(&path).unwrap();
+    }
+
+    #[test]
+    fn test_unchanged_lock_file_keeps_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(consts::PROJECT_LOCK_FILE);
+        let content = LockFile::default().render_to_string().unwrap();
+        let written_by_other = format!("{CREATED_BY_PREFIX}0.1.0\n{content}");
+        fs_err::write(&path, &written_by_other).unwrap();
+
+        write_lock_file_with_version(&LockFile::default(), &path).unwrap();
+        assert_eq!(fs_err::read_to_string(&path).unwrap(), written_by_other);
+    }
+}
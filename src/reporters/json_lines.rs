@@ -0,0 +1,641 @@
+//! A reporter that emits machine readable progress events instead of drawing
+//! progress bars. Every event is written as a single JSON object on its own
+//! line to stdout, human facing messages keep going to stderr.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Instant,
+};
+
+use parking_lot::Mutex;
+use pixi_command_dispatcher::{
+    CondaSolveReporter, InstallPixiEnvironmentSpec, PixiEnvironmentSpec, PixiInstallReporter,
+    PixiSolveReporter, ReporterContext, SolveCondaEnvironmentSpec,
+    reporter::{CondaSolveId, PixiInstallId, PixiSolveId},
+};
+use rattler::install::Transaction;
+use rattler_conda_types::{Platform, PrefixRecord, RepoDataRecord};
+use serde::Serialize;
+use uv_distribution_types::{BuildableSource, CachedDist, Name};
+
+/// The environment and platform an event belongs to, this allows consumers
+/// to demultiplex the events of installs that run concurrently.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventContext {
+    pub environment: String,
+    pub platform: Platform,
+}
+
+/// A single progress event.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Event {
+    SolveStarted {
+        #[serde(flatten)]
+        context: EventContext,
+    },
+    SolveFinished {
+        #[serde(flatten)]
+        context: EventContext,
+        duration_ms: u64,
+    },
+    InstallStarted {
+        #[serde(flatten)]
+        context: EventContext,
+    },
+    InstallFinished {
+        #[serde(flatten)]
+        context: EventContext,
+        duration_ms: u64,
+    },
+    TransactionStarted {
+        #[serde(flatten)]
+        context: EventContext,
+        operations: usize,
+    },
+    TransactionFinished {
+        #[serde(flatten)]
+        context: EventContext,
+    },
+    DownloadStarted {
+        #[serde(flatten)]
+        context: EventContext,
+        package: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+    },
+    DownloadFinished {
+        #[serde(flatten)]
+        context: EventContext,
+        package: String,
+        bytes: u64,
+    },
+    UnlinkStarted {
+        #[serde(flatten)]
+        context: EventContext,
+        package: String,
+    },
+    UnlinkFinished {
+        #[serde(flatten)]
+        context: EventContext,
+        package: String,
+    },
+    LinkStarted {
+        #[serde(flatten)]
+        context: EventContext,
+        package: String,
+    },
+    LinkFinished {
+        #[serde(flatten)]
+        context: EventContext,
+        package: String,
+    },
+    Summary {
+        environments: Vec<EventContext>,
+        duration_ms: u64,
+        packages_from_cache: usize,
+        packages_downloaded: usize,
+        /// The fraction of the required packages that were already cached,
+        /// `None` if no package had to be fetched at all.
+        cache_hit_ratio: Option<f64>,
+    },
+}
+
+/// The sink that events are written to, together with the statistics that
+/// make up the final summary.
+pub struct JsonLines {
+    started: Instant,
+    cache_lookups: AtomicUsize,
+    downloads: AtomicUsize,
+}
+
+static JSON_LINES: OnceLock<JsonLines> = OnceLock::new();
+
+/// Switches the reporting of the process over to JSON lines. The progress bars
+/// are hidden from then on.
+pub fn enable_json_lines() {
+    JSON_LINES.get_or_init(|| JsonLines {
+        started: Instant::now(),
+        cache_lookups: AtomicUsize::new(0),
+        downloads: AtomicUsize::new(0),
+    });
+    pixi_progress::global_multi_progress().set_draw_target(indicatif::ProgressDrawTarget::hidden());
+}
+
+/// Returns the sink if JSON lines reporting was enabled.
+pub fn json_lines() -> Option<&'static JsonLines> {
+    JSON_LINES.get()
+}
+
+impl JsonLines {
+    /// Writes the event as a single line to stdout.
+    pub fn emit(&self, event: &Event) {
+        let mut stdout = std::io::stdout().lock();
+        if let Err(err) = serde_json::to_writer(&mut stdout, event)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writeln!(stdout))
+        {
+            tracing::debug!("failed to write progress event: {err}");
+        }
+    }
+
+    /// Records packages that are either taken from the cache or downloaded,
+    /// the downloads themselves are counted when they start.
+    pub(crate) fn add_cache_lookups(&self, count: usize) {
+        self.cache_lookups.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Emits the summary of everything that was reported so far.
+    pub fn emit_summary(&self, environments: Vec<EventContext>) {
+        let cache_lookups = self.cache_lookups.load(Ordering::Relaxed);
+        let downloads = self.downloads.load(Ordering::Relaxed).min(cache_lookups);
+        let packages_from_cache = cache_lookups - downloads;
+        self.emit(&Event::Summary {
+            environments,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            packages_from_cache,
+            packages_downloaded: downloads,
+            cache_hit_ratio: (cache_lookups > 0)
+                .then(|| packages_from_cache as f64 / cache_lookups as f64),
+        });
+    }
+}
+
+/// An operation that was queued on the command dispatcher.
+struct Operation {
+    context: EventContext,
+    started: Option<Instant>,
+}
+
+/// A [`pixi_command_dispatcher::Reporter`] that reports solves and installs as
+/// [`Event`]s.
+pub(crate) struct JsonLinesReporter {
+    sink: &'static JsonLines,
+    next_id: usize,
+    solves: HashMap<usize, Operation>,
+    conda_solves: HashMap<usize, Operation>,
+    installs: HashMap<usize, Operation>,
+}
+
+impl JsonLinesReporter {
+    pub fn new(sink: &'static JsonLines) -> Self {
+        Self {
+            sink,
+            next_id: 0,
+            solves: HashMap::new(),
+            conda_solves: HashMap::new(),
+            installs: HashMap::new(),
+        }
+    }
+
+    fn next_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn started(
+        sink: &JsonLines,
+        operations: &mut HashMap<usize, Operation>,
+        id: usize,
+        install: bool,
+    ) {
+        let Some(operation) = operations.get_mut(&id) else {
+            return;
+        };
+        operation.started = Some(Instant::now());
+        let context = operation.context.clone();
+        sink.emit(&if install {
+            Event::InstallStarted { context }
+        } else {
+            Event::SolveStarted { context }
+        });
+    }
+
+    fn finished(
+        sink: &JsonLines,
+        operations: &mut HashMap<usize, Operation>,
+        id: usize,
+        install: bool,
+    ) {
+        let Some(operation) = operations.remove(&id) else {
+            return;
+        };
+        let duration_ms = operation
+            .started
+            .map_or(0, |started| started.elapsed().as_millis() as u64);
+        let context = operation.context;
+        sink.emit(&if install {
+            Event::InstallFinished {
+                context,
+                duration_ms,
+            }
+        } else {
+            Event::SolveFinished {
+                context,
+                duration_ms,
+            }
+        });
+    }
+}
+
+impl pixi_command_dispatcher::Reporter for JsonLinesReporter {
+    fn as_conda_solve_reporter(&mut self) -> Option<&mut dyn CondaSolveReporter> {
+        Some(self)
+    }
+
+    fn as_pixi_solve_reporter(&mut self) -> Option<&mut dyn PixiSolveReporter> {
+        Some(self)
+    }
+
+    fn as_pixi_install_reporter(&mut self) -> Option<&mut dyn PixiInstallReporter> {
+        Some(self)
+    }
+
+    fn create_install_reporter(
+        &mut self,
+        reason: Option<ReporterContext>,
+    ) -> Option<Box<dyn rattler::install::Reporter>> {
+        let context = match reason {
+            Some(ReporterContext::InstallPixi(id)) => self.installs.get(&id.0)?.context.clone(),
+            _ => return None,
+        };
+        Some(Box::new(JsonLinesInstallReporter {
+            sink: self.sink,
+            context,
+            packages: Default::default(),
+        }))
+    }
+}
+
+impl PixiSolveReporter for JsonLinesReporter {
+    fn on_queued(
+        &mut self,
+        _reason: Option<ReporterContext>,
+        env: &PixiEnvironmentSpec,
+    ) -> PixiSolveId {
+        let id = self.next_id();
+        self.solves.insert(
+            id,
+            Operation {
+                context: EventContext {
+                    environment: env.name.clone().unwrap_or_default(),
+                    platform: env.build_environment.host_platform,
+                },
+                started: None,
+            },
+        );
+        PixiSolveId(id)
+    }
+
+    fn on_start(&mut self, solve_id: PixiSolveId) {
+        Self::started(self.sink, &mut self.solves, solve_id.0, false);
+    }
+
+    fn on_finished(&mut self, solve_id: PixiSolveId) {
+        Self::finished(self.sink, &mut self.solves, solve_id.0, false);
+    }
+}
+
+impl CondaSolveReporter for JsonLinesReporter {
+    fn on_queued(
+        &mut self,
+        reason: Option<ReporterContext>,
+        env: &SolveCondaEnvironmentSpec,
+    ) -> CondaSolveId {
+        // Solves that are part of a pixi solve are reported by the pixi solve.
+        let id = self.next_id();
+        if !matches!(reason, Some(ReporterContext::SolvePixi(_))) {
+            self.conda_solves.insert(
+                id,
+                Operation {
+                    context: EventContext {
+                        environment: env.name.clone().unwrap_or_default(),
+                        platform: env.platform,
+                    },
+                    started: None,
+                },
+            );
+        }
+        CondaSolveId(id)
+    }
+
+    fn on_start(&mut self, solve_id: CondaSolveId) {
+        Self::started(self.sink, &mut self.conda_solves, solve_id.0, false);
+    }
+
+    fn on_finished(&mut self, solve_id: CondaSolveId) {
+        Self::finished(self.sink, &mut self.conda_solves, solve_id.0, false);
+    }
+}
+
+impl PixiInstallReporter for JsonLinesReporter {
+    fn on_queued(
+        &mut self,
+        _reason: Option<ReporterContext>,
+        env: &InstallPixiEnvironmentSpec,
+    ) -> PixiInstallId {
+        let id = self.next_id();
+        self.installs.insert(
+            id,
+            Operation {
+                context: EventContext {
+                    environment: env.name.clone(),
+                    platform: env.target_platform,
+                },
+                started: None,
+            },
+        );
+        PixiInstallId(id)
+    }
+
+    fn on_start(&mut self, install_id: PixiInstallId) {
+        Self::started(self.sink, &mut self.installs, install_id.0, true);
+    }
+
+    fn on_finished(&mut self, install_id: PixiInstallId) {
+        Self::finished(self.sink, &mut self.installs, install_id.0, true);
+    }
+}
+
+/// The package name and size of every operation in the transaction.
+#[derive(Default)]
+struct TransactionPackages {
+    operations: Vec<(String, Option<u64>)>,
+    downloaded: HashMap<usize, u64>,
+}
+
+/// Reports the link transaction of a single environment.
+struct JsonLinesInstallReporter {
+    sink: &'static JsonLines,
+    context: EventContext,
+    packages: Mutex<TransactionPackages>,
+}
+
+impl JsonLinesInstallReporter {
+    fn package(&self, operation: usize) -> String {
+        self.packages
+            .lock()
+            .operations
+            .get(operation)
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl rattler::install::Reporter for JsonLinesInstallReporter {
+    fn on_transaction_start(&self, transaction: &Transaction<PrefixRecord, RepoDataRecord>) {
+        self.packages.lock().operations = transaction
+            .operations
+            .iter()
+            .map(|operation| {
+                operation
+                    .record_to_install()
+                    .or_else(|| operation.record_to_remove().map(|r| &r.repodata_record))
+                    .map(|record| {
+                        (
+                            record.package_record.name.as_normalized().to_string(),
+                            record.package_record.size,
+                        )
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        self.sink.emit(&Event::TransactionStarted {
+            context: self.context.clone(),
+            operations: transaction.operations.len(),
+        });
+    }
+
+    fn on_transaction_operation_start(&self, _operation: usize) {}
+
+    fn on_populate_cache_start(&self, operation: usize, _record: &RepoDataRecord) -> usize {
+        self.sink.cache_lookups.fetch_add(1, Ordering::Relaxed);
+        operation
+    }
+
+    fn on_validate_start(&self, cache_entry: usize) -> usize {
+        cache_entry
+    }
+
+    fn on_validate_complete(&self, _validate_idx: usize) {}
+
+    fn on_download_start(&self, cache_entry: usize) -> usize {
+        self.sink.downloads.fetch_add(1, Ordering::Relaxed);
+        let (package, size) = self
+            .packages
+            .lock()
+            .operations
+            .get(cache_entry)
+            .cloned()
+            .unwrap_or_default();
+        self.sink.emit(&Event::DownloadStarted {
+            context: self.context.clone(),
+            package,
+            size,
+        });
+        cache_entry
+    }
+
+    fn on_download_progress(&self, download_idx: usize, progress: u64, _total: Option<u64>) {
+        self.packages
+            .lock()
+            .downloaded
+            .insert(download_idx, progress);
+    }
+
+    fn on_download_completed(&self, download_idx: usize) {
+        let bytes = self
+            .packages
+            .lock()
+            .downloaded
+            .remove(&download_idx)
+            .unwrap_or_default();
+        self.sink.emit(&Event::DownloadFinished {
+            context: self.context.clone(),
+            package: self.package(download_idx),
+            bytes,
+        });
+    }
+
+    fn on_populate_cache_complete(&self, _cache_entry: usize) {}
+
+    fn on_unlink_start(&self, operation: usize, _record: &PrefixRecord) -> usize {
+        self.sink.emit(&Event::UnlinkStarted {
+            context: self.context.clone(),
+            package: self.package(operation),
+        });
+        operation
+    }
+
+    fn on_unlink_complete(&self, index: usize) {
+        self.sink.emit(&Event::UnlinkFinished {
+            context: self.context.clone(),
+            package: self.package(index),
+        });
+    }
+
+    fn on_link_start(&self, operation: usize, _record: &RepoDataRecord) -> usize {
+        self.sink.emit(&Event::LinkStarted {
+            context: self.context.clone(),
+            package: self.package(operation),
+        });
+        operation
+    }
+
+    fn on_link_complete(&self, index: usize) {
+        self.sink.emit(&Event::LinkFinished {
+            context: self.context.clone(),
+            package: self.package(index),
+        });
+    }
+
+    fn on_transaction_operation_complete(&self, _operation: usize) {}
+
+    fn on_transaction_complete(&self) {
+        self.sink.emit(&Event::TransactionFinished {
+            context: self.context.clone(),
+        });
+    }
+}
+
+/// Reports the download and installation of the PyPI packages of a single
+/// environment.
+pub(crate) struct JsonLinesUvReporter {
+    sink: &'static JsonLines,
+    context: EventContext,
+    next_id: AtomicUsize,
+    downloads: Mutex<HashMap<usize, (String, u64)>>,
+}
+
+impl JsonLinesUvReporter {
+    pub fn new_arc(sink: &'static JsonLines, context: EventContext) -> Arc<Self> {
+        Arc::new(Self {
+            sink,
+            context,
+            next_id: AtomicUsize::new(0),
+            downloads: Mutex::default(),
+        })
+    }
+
+    fn next_id(&self) -> usize {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Emits the start of the PyPI transaction of the environment.
+    pub fn transaction_started(&self, operations: usize) {
+        self.sink.emit(&Event::TransactionStarted {
+            context: self.context.clone(),
+            operations,
+        });
+    }
+
+    /// Emits the end of the PyPI transaction of the environment.
+    pub fn transaction_finished(&self) {
+        self.sink.emit(&Event::TransactionFinished {
+            context: self.context.clone(),
+        });
+    }
+
+    /// Emits the start of the installation of the given distributions.
+    pub fn link_started<'a>(&self, packages: impl IntoIterator<Item = &'a CachedDist>) {
+        for package in packages {
+            self.sink.emit(&Event::LinkStarted {
+                context: self.context.clone(),
+                package: package.name().to_string(),
+            });
+        }
+    }
+}
+
+impl uv_installer::PrepareReporter for JsonLinesUvReporter {
+    fn on_progress(&self, _dist: &CachedDist) {}
+
+    fn on_complete(&self) {}
+
+    fn on_build_start(&self, _dist: &BuildableSource) -> usize {
+        self.next_id()
+    }
+
+    fn on_build_complete(&self, _dist: &BuildableSource, _id: usize) {}
+
+    fn on_checkout_start(&self, _url: &url::Url, _rev: &str) -> usize {
+        self.next_id()
+    }
+
+    fn on_checkout_complete(&self, _url: &url::Url, _rev: &str, _index: usize) {}
+
+    fn on_download_start(&self, name: &uv_normalize::PackageName, size: Option<u64>) -> usize {
+        self.sink.downloads.fetch_add(1, Ordering::Relaxed);
+        let id = self.next_id();
+        self.downloads.lock().insert(id, (name.to_string(), 0));
+        self.sink.emit(&Event::DownloadStarted {
+            context: self.context.clone(),
+            package: name.to_string(),
+            size,
+        });
+        id
+    }
+
+    fn on_download_progress(&self, index: usize, bytes: u64) {
+        if let Some((_, downloaded)) = self.downloads.lock().get_mut(&index) {
+            *downloaded += bytes;
+        }
+    }
+
+    fn on_download_complete(&self, name: &uv_normalize::PackageName, index: usize) {
+        let bytes = self
+            .downloads
+            .lock()
+            .remove(&index)
+            .map_or(0, |(_, bytes)| bytes);
+        self.sink.emit(&Event::DownloadFinished {
+            context: self.context.clone(),
+            package: name.to_string(),
+            bytes,
+        });
+    }
+}
+
+impl uv_installer::InstallReporter for JsonLinesUvReporter {
+    fn on_install_progress(&self, wheel: &CachedDist) {
+        self.sink.emit(&Event::LinkFinished {
+            context: self.context.clone(),
+            package: wheel.name().to_string(),
+        });
+    }
+
+    fn on_install_complete(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_format() {
+        let event = Event::DownloadFinished {
+            context: EventContext {
+                environment: "default".to_string(),
+                platform: Platform::Linux64,
+            },
+            package: "python".to_string(),
+            bytes: 42,
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "type": "download-finished",
+                "environment": "default",
+                "platform": "linux-64",
+                "package": "python",
+                "bytes": 42,
+            })
+        );
+    }
+}
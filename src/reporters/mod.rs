@@ -1,6 +1,7 @@
 mod download_verify_reporter;
 mod git;
 mod install_reporter;
+pub mod json_lines;
 mod main_progress_bar;
 mod release_notes;
 mod repodata_reporter;
@@ -10,7 +11,8 @@ use std::sync::LazyLock;
 use git::GitCheckoutProgress;
 use indicatif::{MultiProgress, ProgressBar};
 use pixi_command_dispatcher::{
-    InstallPixiEnvironmentSpec, PixiEnvironmentSpec, ReporterContext, SolveCondaEnvironmentSpec,
+    CommandDispatcherBuilder, InstallPixiEnvironmentSpec, PixiEnvironmentSpec, ReporterContext,
+    SolveCondaEnvironmentSpec,
     reporter::{CondaSolveId, PixiInstallId, PixiSolveId, SourceBuildReporter},
 };
 use pixi_spec::PixiSpec;
@@ -19,10 +21,28 @@ pub use release_notes::format_release_notes;
 use uv_configuration::RAYON_INITIALIZE;
 
 use crate::reporters::{
-    install_reporter::SyncReporter, main_progress_bar::MainProgressBar,
+    install_reporter::SyncReporter,
+    json_lines::{JsonLinesReporter, json_lines},
+    main_progress_bar::MainProgressBar,
     repodata_reporter::RepodataReporter,
 };
 
+/// Configures the reporter of the command dispatcher, this is either the
+/// [`TopLevelProgress`] or, when JSON lines reporting is enabled, a reporter
+/// that emits progress events.
+pub(crate) fn with_top_level_reporter(
+    builder: CommandDispatcherBuilder,
+    anchor_pb: ProgressBar,
+) -> CommandDispatcherBuilder {
+    match json_lines() {
+        Some(sink) => builder.with_reporter(JsonLinesReporter::new(sink)),
+        None => builder.with_reporter(TopLevelProgress::new(
+            pixi_progress::global_multi_progress(),
+            anchor_pb,
+        )),
+    }
+}
+
 /// A top-level reporter that combines the different reporters into one. This
 /// directly implements the [`pixi_command_dispatcher::Reporter`] trait.
 /// And subsequently, offloads the work to its sub progress reporters.
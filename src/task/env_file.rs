@@ -0,0 +1,359 @@
+//! Reads the environment variables of the `.env` files of a workspace.
+//!
+//! The files follow the common dotenv dialect: `KEY=value` lines, `#`
+//! comments, an optional `export` keyword, single-quoted values that are
+//! taken literally and double-quoted values that support escapes. Values
+//! that are not single-quoted expand `${VAR}` references.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+use miette::Diagnostic;
+use pixi_manifest::EnvFile;
+use thiserror::Error;
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum EnvFileError {
+    #[error("the env file '{}' does not exist", .0.display())]
+    #[diagnostic(help("remove `required = true` to continue without the file"))]
+    Missing(PathBuf),
+
+    #[error("failed to read the env file '{}'", .0.display())]
+    Read(PathBuf, #[source] std::io::Error),
+
+    #[error("failed to parse the env file '{}'", .path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: ParseEnvFileError,
+    },
+}
+
+/// An error on a line of a `.env` file.
+#[derive(Debug, Error, Diagnostic, PartialEq, Eq)]
+#[error("line {line}: {kind}")]
+pub struct ParseEnvFileError {
+    pub line: usize,
+    pub kind: ParseEnvFileErrorKind,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseEnvFileErrorKind {
+    #[error("expected 'KEY=value'")]
+    MissingEquals,
+
+    #[error("'{0}' is not a valid variable name")]
+    InvalidKey(String),
+
+    #[error("the quoted value is never closed")]
+    UnterminatedQuote,
+
+    #[error("unexpected characters after the quoted value")]
+    TrailingCharacters,
+}
+
+/// A variable of a `.env` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvFileEntry {
+    pub key: String,
+    pub value: String,
+    /// Whether `${VAR}` references in the value are expanded, which is not
+    /// the case for single-quoted values.
+    pub expand: bool,
+}
+
+/// Parses the content of a `.env` file.
+pub fn parse_env_file(content: &str) -> Result<Vec<EnvFileEntry>, ParseEnvFileError> {
+    let mut entries = Vec::new();
+    let mut lines = content.lines().enumerate();
+    while let Some((idx, line)) = lines.next() {
+        let error = |kind| ParseEnvFileError {
+            line: idx + 1,
+            kind,
+        };
+
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line
+            .strip_prefix("export ")
+            .map(str::trim_start)
+            .unwrap_or(line);
+
+        // Only the first `=` separates the key, the value may contain more.
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error(ParseEnvFileErrorKind::MissingEquals))?;
+        let key = key.trim();
+        if !is_valid_key(key) {
+            return Err(error(ParseEnvFileErrorKind::InvalidKey(key.to_string())));
+        }
+
+        let value = value.trim_start();
+        let entry = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                // Quoted values may span multiple lines.
+                let mut raw = value[1..].to_string();
+                let (value, rest) = loop {
+                    if let Some(result) = split_quoted(&raw, quote) {
+                        break result;
+                    }
+                    let Some((_, next)) = lines.next() else {
+                        return Err(error(ParseEnvFileErrorKind::UnterminatedQuote));
+                    };
+                    raw.push('\n');
+                    raw.push_str(next);
+                };
+                let rest = rest.trim_start();
+                if !rest.is_empty() && !rest.starts_with('#') {
+                    return Err(error(ParseEnvFileErrorKind::TrailingCharacters));
+                }
+                EnvFileEntry {
+                    key: key.to_string(),
+                    value,
+                    expand: quote == '"',
+                }
+            }
+            _ => {
+                // A `#` only starts a comment after whitespace, so that values
+                // like URLs with fragments are kept intact.
+                let value = match value.find(" #").or_else(|| value.find("\t#")) {
+                    Some(idx) => &value[..idx],
+                    None => value,
+                };
+                EnvFileEntry {
+                    key: key.to_string(),
+                    value: value.trim_end().to_string(),
+                    expand: true,
+                }
+            }
+        };
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Splits a quoted value at its closing quote, returns `None` if the value is
+/// not closed. Escapes are only processed in double-quoted values.
+fn split_quoted(raw: &str, quote: char) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = raw.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            c if c == quote => return Some((value, &raw[idx + 1..])),
+            '\\' if quote == '"' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                other @ ('"' | '\\' | '$') => value.push(other),
+                other => {
+                    value.push('\\');
+                    value.push(other);
+                }
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Expands the `${VAR}` references in the value, unknown variables expand to
+/// an empty string.
+fn expand(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        result.push_str(&lookup(&rest[start + 2..start + end]).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Reads the variables of the `.env` files, relative to `root`, in order so
+/// that later files override earlier ones. `${VAR}` references are expanded
+/// against the variables of the files read so far and the `env`.
+///
+/// Files that don't exist are skipped with a warning, unless they are
+/// required.
+pub fn load_env_files(
+    root: &Path,
+    files: &[EnvFile],
+    env: &HashMap<String, String>,
+) -> Result<IndexMap<String, String>, EnvFileError> {
+    let mut variables = IndexMap::new();
+    for file in files {
+        let path = root.join(&file.path);
+        let content = match fs_err::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                if file.required {
+                    return Err(EnvFileError::Missing(path));
+                }
+                tracing::warn!("the env file '{}' does not exist", path.display());
+                continue;
+            }
+            Err(err) => return Err(EnvFileError::Read(path, err)),
+        };
+
+        let entries = parse_env_file(&content).map_err(|source| EnvFileError::Parse {
+            path: path.clone(),
+            source,
+        })?;
+        for entry in entries {
+            let value = if entry.expand {
+                expand(&entry.value, |name| {
+                    variables.get(name).or_else(|| env.get(name)).cloned()
+                })
+            } else {
+                entry.value
+            };
+            variables.insert(entry.key, value);
+        }
+    }
+    Ok(variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Vec<(String, String)> {
+        parse_env_file(content)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.key, entry.value))
+            .collect()
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let content = r#"
+# a comment
+FOO=bar
+export EXPORTED=yes
+  SPACED = value with spaces
+EMPTY=
+URL=https://example.com/?a=b&c=d
+FRAGMENT=https://example.com/#anchor
+COMMENTED=value # a comment
+DOUBLE="quoted # not a comment"
+SINGLE='literal ${FOO} \n'
+ESCAPES="line\nnext \"quoted\" \\"
+EQUALS="a=b=c"
+MULTI="first
+second"
+"#;
+        assert_eq!(
+            parse(content),
+            pairs(&[
+                ("FOO", "bar"),
+                ("EXPORTED", "yes"),
+                ("SPACED", "value with spaces"),
+                ("EMPTY", ""),
+                ("URL", "https://example.com/?a=b&c=d"),
+                ("FRAGMENT", "https://example.com/#anchor"),
+                ("COMMENTED", "value"),
+                ("DOUBLE", "quoted # not a comment"),
+                ("SINGLE", "literal ${FOO} \\n"),
+                ("ESCAPES", "line\nnext \"quoted\" \\"),
+                ("EQUALS", "a=b=c"),
+                ("MULTI", "first\nsecond"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_env_file_errors() {
+        assert_eq!(
+            parse_env_file("FOO=bar\nNOT A VARIABLE").unwrap_err(),
+            ParseEnvFileError {
+                line: 2,
+                kind: ParseEnvFileErrorKind::MissingEquals
+            }
+        );
+        assert_eq!(
+            parse_env_file("1FOO=bar").unwrap_err().kind,
+            ParseEnvFileErrorKind::InvalidKey("1FOO".to_string())
+        );
+        assert_eq!(
+            parse_env_file("FOO=\"bar\nBAR=baz").unwrap_err().kind,
+            ParseEnvFileErrorKind::UnterminatedQuote
+        );
+        assert_eq!(
+            parse_env_file("FOO=\"bar\" baz").unwrap_err().kind,
+            ParseEnvFileErrorKind::TrailingCharacters
+        );
+    }
+
+    #[test]
+    fn test_load_env_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs_err::write(
+            dir.path().join(".env"),
+            "HOST=localhost\nURL=http://${HOST}:${PORT}/${PIXI_ENV}\nLITERAL='${HOST}'\n",
+        )
+        .unwrap();
+        fs_err::write(dir.path().join(".env.local"), "HOST=example.com\n").unwrap();
+
+        let env = HashMap::from([
+            ("PIXI_ENV".to_string(), "default".to_string()),
+            ("HOST".to_string(), "ignored".to_string()),
+        ]);
+        let files = [
+            EnvFile {
+                path: ".env".into(),
+                required: false,
+            },
+            EnvFile {
+                path: ".env.missing".into(),
+                required: false,
+            },
+            EnvFile {
+                path: ".env.local".into(),
+                required: true,
+            },
+        ];
+        let variables = load_env_files(dir.path(), &files, &env).unwrap();
+        assert_eq!(
+            variables,
+            IndexMap::from([
+                ("HOST".to_string(), "example.com".to_string()),
+                ("URL".to_string(), "http://localhost:/default".to_string()),
+                ("LITERAL".to_string(), "${HOST}".to_string()),
+            ])
+        );
+
+        let files = [EnvFile {
+            path: ".env.missing".into(),
+            required: true,
+        }];
+        assert!(matches!(
+            load_env_files(dir.path(), &files, &env),
+            Err(EnvFileError::Missing(_))
+        ));
+    }
+}
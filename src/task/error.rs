@@ -1,8 +1,11 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+};
 
 use fancy_display::FancyDisplay;
 use itertools::Itertools;
-use miette::Diagnostic;
+use miette::{Diagnostic, LabeledSpan, NamedSource};
 use thiserror::Error;
 
 use pixi_manifest::{EnvironmentName, TaskName};
@@ -42,3 +45,21 @@ impl Diagnostic for AmbiguousTaskError {
         )))
     }
 }
+
+/// The tasks depend on each other in a cycle through their `depends-on`.
+#[derive(Debug, Error, Diagnostic)]
+#[error("the tasks depend on each other in a cycle: {}", cycle.iter().format(" → "))]
+#[diagnostic(help("remove one of the `depends-on` entries of the cycle"))]
+pub struct TaskCycleError {
+    /// The tasks of the cycle in order, the first task is repeated at the
+    /// end.
+    pub cycle: Vec<TaskName>,
+
+    /// The manifest that defines the tasks, if it could be read.
+    #[source_code]
+    pub source_code: Option<NamedSource<Arc<str>>>,
+
+    /// The `depends-on` entries of the cycle in the manifest.
+    #[label(collection)]
+    pub labels: Vec<LabeledSpan>,
+}
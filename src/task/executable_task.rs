@@ -3,26 +3,38 @@ use std::{
     collections::HashMap,
     ffi::OsString,
     fmt::{Display, Formatter},
-    path::PathBuf,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
+use chrono::{DateTime, Utc};
 use deno_task_shell::{
-    ShellPipeWriter, ShellState, execute_with_pipes, parser::SequentialList, pipe,
+    KillSignal, ShellPipeReader, ShellPipeWriter, SignalKind, parser::SequentialList, pipe,
 };
 use fs_err::tokio as tokio_fs;
+use indexmap::IndexMap;
 use itertools::Itertools;
 use miette::{Context, Diagnostic};
 use pixi_consts::consts;
-use pixi_manifest::{Task, TaskName, task::ArgValues, task::TemplateStringError};
+use pixi_manifest::{
+    EnvironmentName, Task, TaskName,
+    task::{ArgValues, TaskShell, TemplateStringError},
+};
 use pixi_progress::await_in_progress;
 use rattler_lock::LockFile;
 use thiserror::Error;
 use tokio::task::JoinHandle;
 
-use super::task_hash::{InputHashesError, NameHash, TaskCache, TaskHash};
+use super::{
+    env_file::{EnvFileError, load_env_files},
+    interrupt::{DEFAULT_GRACE_PERIOD, TaskProcessGroup},
+    shell::{MissingShellError, expand_variables, find_shell, spawn_shell, wait_for_shell},
+    task_hash::{InputHashesError, NameHash, OutputHashes, TaskCache, TaskHash},
+};
 use crate::{
     Workspace,
-    activation::CurrentEnvVarBehavior,
+    activation::{CurrentEnvVarBehavior, get_kept_environment_variables},
     task::task_graph::{TaskGraph, TaskId},
     workspace::get_activated_environment_variables,
     workspace::{Environment, HasWorkspaceRef},
@@ -34,6 +46,54 @@ pub struct RunOutput {
     pub exit_code: i32,
     pub stdout: String,
     pub stderr: String,
+    /// The output of every task that ran, in the order in which the tasks
+    /// were started.
+    pub tasks: Vec<TaskOutput>,
+    /// The output per environment if the tasks ran in multiple environments,
+    /// in the order in which the environments were given.
+    pub environments: Vec<(EnvironmentName, RunOutput)>,
+}
+
+/// The output of a single task of a run.
+#[derive(Default, Debug, Clone)]
+pub struct TaskOutput {
+    /// The name of the task or `None` if the task is a custom task.
+    pub name: Option<TaskName>,
+    /// The name of the environment the task ran in.
+    pub environment: Option<EnvironmentName>,
+    /// The command of the task with its arguments, `None` for an alias.
+    pub command: Option<String>,
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    /// Whether the task was skipped because its inputs and outputs didn't
+    /// change since the last time it ran.
+    pub cache_hit: bool,
+    /// Whether the task was skipped with `--skip-deps`.
+    pub skipped: bool,
+    /// When the command of the task started, `None` if it didn't run.
+    pub started_at: Option<DateTime<Utc>>,
+    /// How long the command of the task ran.
+    pub duration: Duration,
+    /// The error that stopped the task before its command finished, like a
+    /// timeout.
+    pub error: Option<String>,
+    /// How many times the command of the task ran, more than once if it was
+    /// retried after a failure.
+    pub attempts: u32,
+}
+
+impl TaskOutput {
+    /// Returns the output of a task that didn't run (yet).
+    pub fn for_task(task: &ExecutableTask<'_>) -> Self {
+        Self {
+            name: task.name.clone(),
+            environment: Some(task.run_environment.name().clone()),
+            command: task.full_command().ok().flatten(),
+            skipped: task.skipped,
+            ..Self::default()
+        }
+    }
 }
 
 #[derive(Debug, Error, Diagnostic)]
@@ -64,6 +124,23 @@ pub enum TaskExecutionError {
 
     #[error(transparent)]
     FailedToParseShellScript(#[from] FailedToParseShellScript),
+
+    #[error("the task was interrupted by {0:?}")]
+    Interrupted(SignalKind),
+
+    #[error("the task timed out after {0:.1?}")]
+    TimedOut(Duration),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MissingShell(#[from] MissingShellError),
+
+    #[error("failed to start the shell '{shell}'")]
+    SpawnShell {
+        shell: TaskShell,
+        #[source]
+        source: std::io::Error,
+    },
 }
 
 #[derive(Debug, Error, Diagnostic)]
@@ -93,6 +170,29 @@ pub struct ExecutableTask<'p> {
     pub task: Cow<'p, Task>,
     pub run_environment: Environment<'p>,
     pub args: ArgValues,
+    /// Whether the task is skipped, see [`super::TaskNode::skipped`].
+    pub skipped: bool,
+    /// The working directory that overrides the `cwd` of the task, e.g. from
+    /// `pixi run --cwd`.
+    pub cwd_override: Option<PathBuf>,
+    /// The timeout that overrides the `timeout` of the task, e.g. from
+    /// `pixi run --timeout`.
+    pub timeout_override: Option<Duration>,
+    /// The shell that runs a command that isn't a task, e.g. from
+    /// `pixi run --shell`.
+    pub shell_override: Option<TaskShell>,
+    /// Runs the task attached to the terminal even if it isn't
+    /// `interactive`, e.g. from `pixi run --interactive`.
+    pub interactive_override: bool,
+    /// The number of retries that overrides the `retries` of the task, e.g.
+    /// from `pixi run --retries`.
+    pub retries_override: Option<u32>,
+    /// The time that the task gets to stop after it timed out, before its
+    /// processes are killed, e.g. from `pixi run --grace-period`.
+    pub grace_period: Duration,
+    /// The pixi executable that runs the task with the shell that is built
+    /// into pixi, the current executable if it isn't set.
+    pub pixi_executable: Option<PathBuf>,
 }
 
 impl<'p> ExecutableTask<'p> {
@@ -106,6 +206,107 @@ impl<'p> ExecutableTask<'p> {
             task: node.task.clone(),
             run_environment: node.run_environment.clone(),
             args: node.args.clone().unwrap_or_default(),
+            skipped: node.skipped,
+            cwd_override: None,
+            timeout_override: None,
+            shell_override: None,
+            interactive_override: false,
+            retries_override: None,
+            grace_period: DEFAULT_GRACE_PERIOD,
+            pixi_executable: None,
+        }
+    }
+
+    /// Runs the task in the given working directory instead of its own `cwd`.
+    pub fn with_cwd_override(mut self, cwd: Option<PathBuf>) -> Self {
+        self.cwd_override = cwd;
+        self
+    }
+
+    /// Uses the given timeout instead of the `timeout` of the task, if any.
+    pub fn with_timeout_override(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout_override = timeout;
+        self
+    }
+
+    /// Returns the maximum time the command of the task may run.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_override.or_else(|| self.task.timeout())
+    }
+
+    /// Gives the task the grace period to stop after SIGTERM when it timed
+    /// out, before its processes are killed.
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Runs the tasks that use the shell that is built into pixi with the
+    /// given pixi executable, e.g. when pixi runs as a library in tests.
+    pub fn with_pixi_executable(mut self, pixi_executable: Option<PathBuf>) -> Self {
+        self.pixi_executable = pixi_executable;
+        self
+    }
+
+    /// Runs a command that isn't a task with the given shell. Tasks always
+    /// use their own `shell`.
+    pub fn with_shell_override(mut self, shell: Option<TaskShell>) -> Self {
+        self.shell_override = shell;
+        self
+    }
+
+    /// Uses the given number of retries instead of the `retries` of the task,
+    /// if any.
+    pub fn with_retries_override(mut self, retries: Option<u32>) -> Self {
+        self.retries_override = retries;
+        self
+    }
+
+    /// Returns the variables of the running machine that are kept when the
+    /// task runs in a clean environment, the `keep_env` followed by the
+    /// `clean-env-keep` of the task. Returns `None` if the task doesn't run in
+    /// a clean environment, i.e. neither `clean_env` nor the task asks for it.
+    pub fn clean_env_keep(&self, clean_env: bool, keep_env: &[String]) -> Option<Vec<String>> {
+        if !clean_env && !self.task.clean_env() {
+            return None;
+        }
+        Some(
+            keep_env
+                .iter()
+                .chain(self.task.clean_env_keep())
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Returns how many more times the command runs when it fails.
+    pub fn retries(&self) -> u32 {
+        self.retries_override.unwrap_or_else(|| self.task.retries())
+    }
+
+    /// Returns the time to wait before the command runs again after it
+    /// failed.
+    pub fn retry_delay(&self) -> Option<Duration> {
+        self.task.retry_delay()
+    }
+
+    /// Runs the task attached to the terminal, even if it isn't `interactive`.
+    pub fn with_interactive_override(mut self, interactive: bool) -> Self {
+        self.interactive_override = interactive;
+        self
+    }
+
+    /// Returns true if the task runs attached to the terminal, without
+    /// capturing its output.
+    pub fn interactive(&self) -> bool {
+        self.interactive_override || self.task.interactive()
+    }
+
+    /// Returns the shell that runs the command of the task.
+    pub fn shell(&self) -> TaskShell {
+        match (self.task.as_ref(), self.shell_override) {
+            (Task::Custom(_), Some(shell)) => shell,
+            (task, _) => task.shell(),
         }
     }
 
@@ -128,76 +329,56 @@ impl<'p> ExecutableTask<'p> {
         &self.args
     }
 
-    /// Returns the task as script
-    fn as_script(&self) -> Result<Option<String>, FailedToParseShellScript> {
+    /// Returns the command of the task with the command line arguments,
+    /// without the environment variables of the task.
+    fn command_script(&self) -> Result<Option<String>, FailedToParseShellScript> {
         // Convert the task into an executable string
         let task = self
             .task
             .as_single_command(Some(&self.args))
             .map_err(FailedToParseShellScript::ArgumentReplacement)?;
-        if let Some(task) = task {
-            // Get the export specific environment variables
-            let export = get_export_specific_task_env(self.task.as_ref());
-
-            // Append the command line arguments verbatim
-            let cli_args = if let ArgValues::FreeFormArgs(additional_args) = &self.args {
-                additional_args
-                    .iter()
-                    .format_with(" ", |arg, f| f(&format_args!("'{}'", arg)))
-                    .to_string()
-            } else {
-                String::new()
-            };
-
-            // Skip the export if it's empty, to avoid newlines
-            let full_script = if export.is_empty() {
-                format!("{} {}", task, cli_args)
-            } else {
-                format!("{}\n{} {}", export, task, cli_args)
-            };
+        let Some(task) = task else {
+            return Ok(None);
+        };
 
-            Ok(Some(full_script))
+        // Append the command line arguments verbatim
+        let cli_args = if let ArgValues::FreeFormArgs(additional_args) = &self.args {
+            additional_args
+                .iter()
+                .format_with(" ", |arg, f| f(&format_args!("'{}'", arg)))
+                .to_string()
         } else {
-            Ok(None)
-        }
+            String::new()
+        };
+
+        Ok(Some(format!("{} {}", task, cli_args)))
     }
 
-    /// Returns a [`SequentialList`] which can be executed by deno task shell.
-    /// Returns `None` if the command is not executable like in the case of
-    /// an alias.
-    pub(crate) fn as_deno_script(
-        &self,
-    ) -> Result<Option<SequentialList>, FailedToParseShellScript> {
-        let full_script = self.as_script()?;
-
-        if let Some(full_script) = full_script {
-            tracing::debug!("Parsing shell script: {}", full_script);
-
-            // Parse the shell command
-            deno_task_shell::parser::parse(full_script.trim())
-                .map_err(|e| FailedToParseShellScript::ParseError {
-                    source: e,
-                    task: full_script.to_string(),
-                })
-                .map(Some)
+    /// Returns the task as script
+    fn as_script(&self) -> Result<Option<String>, FailedToParseShellScript> {
+        let Some(command) = self.command_script()? else {
+            return Ok(None);
+        };
+
+        // Get the export specific environment variables
+        let export = get_export_specific_task_env(self.task.as_ref());
+
+        // Skip the export if it's empty, to avoid newlines
+        if export.is_empty() {
+            Ok(Some(command))
         } else {
-            Ok(None)
+            Ok(Some(format!("{}\n{}", export, command)))
         }
     }
 
     /// Returns the working directory for this task.
     pub(crate) fn working_directory(&self) -> Result<PathBuf, InvalidWorkingDirectory> {
+        if let Some(cwd) = &self.cwd_override {
+            return Ok(cwd.clone());
+        }
         Ok(match self.task.working_directory() {
             Some(cwd) if cwd.is_absolute() => cwd.to_path_buf(),
-            Some(cwd) => {
-                let abs_path = self.workspace.root().join(cwd);
-                if !abs_path.is_dir() {
-                    return Err(InvalidWorkingDirectory {
-                        path: cwd.to_string_lossy().to_string(),
-                    });
-                }
-                abs_path
-            }
+            Some(cwd) => resolve_working_directory(self.workspace.root(), cwd)?,
             None => self.workspace.root().to_path_buf(),
         })
     }
@@ -233,20 +414,49 @@ impl<'p> ExecutableTask<'p> {
         ExecutableTaskConsoleDisplay { task: self }
     }
 
-    /// Executes the task and capture its output.
+    /// Adds the variables of the `env-files` of the task to the environment
+    /// the task runs in. The variables in the `env` of the task take
+    /// precedence.
+    pub fn extend_with_env_files(
+        &self,
+        command_env: &mut HashMap<String, String>,
+    ) -> Result<(), EnvFileError> {
+        let Some(env_files) = self.task.env_files() else {
+            return Ok(());
+        };
+        let variables = load_env_files(self.workspace.root(), env_files, command_env)?;
+        let declared_env = self.task.env();
+        for (key, value) in variables {
+            if declared_env.is_some_and(|env| env.contains_key(&key)) {
+                continue;
+            }
+            command_env.insert(key, value);
+        }
+        Ok(())
+    }
+
+    /// Executes the task and capture its output. Sending the `kill_signal`
+    /// kills the processes that the task started and the task fails with
+    /// [`TaskExecutionError::Interrupted`], a task that runs longer than its
+    /// [`Self::timeout`] fails with [`TaskExecutionError::TimedOut`].
+    ///
+    /// An [`Self::interactive`] task runs attached to the terminal and its
+    /// output is not captured.
     pub async fn execute_with_pipes(
         &self,
         command_env: &HashMap<OsString, OsString>,
         input: Option<&[u8]>,
+        kill_signal: KillSignal,
     ) -> Result<RunOutput, TaskExecutionError> {
-        let Some(script) = self.as_deno_script()? else {
+        if self.interactive() {
             return Ok(RunOutput {
-                exit_code: 0,
-                stdout: String::new(),
-                stderr: String::new(),
+                exit_code: self.execute(command_env, kill_signal).await?,
+                ..RunOutput::default()
             });
+        }
+        let Some(script) = self.prepare_script()? else {
+            return Ok(RunOutput::default());
         };
-        let cwd = self.working_directory()?;
         let (stdin, mut stdin_writer) = pipe();
         if let Some(stdin) = input {
             stdin_writer
@@ -256,20 +466,235 @@ impl<'p> ExecutableTask<'p> {
         drop(stdin_writer); // prevent a deadlock by dropping the writer
         let (stdout, stdout_handle) = get_output_writer_and_handle();
         let (stderr, stderr_handle) = get_output_writer_and_handle();
-        let state = ShellState::new(
-            command_env.clone(),
-            cwd,
-            Default::default(),
-            Default::default(),
-        );
-        let code = execute_with_pipes(script, state, stdin, stdout, stderr).await;
+        let code = self
+            .run_script(
+                script,
+                command_env,
+                stdin,
+                stdout,
+                stderr,
+                kill_signal,
+                false,
+            )
+            .await?;
+        Ok(RunOutput {
+            exit_code: code,
+            stdout: stdout_handle.await.expect("should be able to get stdout"),
+            stderr: stderr_handle.await.expect("should be able to get stderr"),
+            ..RunOutput::default()
+        })
+    }
+
+    /// Executes the task with the input and output of pixi and returns its
+    /// exit code, `0` if the task doesn't run a command.
+    pub async fn execute(
+        &self,
+        command_env: &HashMap<OsString, OsString>,
+        kill_signal: KillSignal,
+    ) -> Result<i32, TaskExecutionError> {
+        let Some(script) = self.prepare_script()? else {
+            return Ok(0);
+        };
+        self.run_script(
+            script,
+            command_env,
+            ShellPipeReader::stdin(),
+            ShellPipeWriter::stdout(),
+            ShellPipeWriter::stderr(),
+            kill_signal,
+            true,
+        )
+        .await
+    }
+
+    /// Executes the task like [`Self::execute_with_pipes`], but its output is
+    /// also forwarded to the output of pixi while it runs. Only the first
+    /// `limit` bytes of `stdout` and `stderr` are returned.
+    pub async fn execute_with_tee(
+        &self,
+        command_env: &HashMap<OsString, OsString>,
+        kill_signal: KillSignal,
+        limit: usize,
+    ) -> Result<RunOutput, TaskExecutionError> {
+        if self.interactive() {
+            return Ok(RunOutput {
+                exit_code: self.execute(command_env, kill_signal).await?,
+                ..RunOutput::default()
+            });
+        }
+        let Some(script) = self.prepare_script()? else {
+            return Ok(RunOutput::default());
+        };
+        let (stdout, stdout_handle) = get_tee_writer_and_handle(std::io::stdout(), limit);
+        let (stderr, stderr_handle) = get_tee_writer_and_handle(std::io::stderr(), limit);
+        let code = self
+            .run_script(
+                script,
+                command_env,
+                ShellPipeReader::stdin(),
+                stdout,
+                stderr,
+                kill_signal,
+                true,
+            )
+            .await?;
         Ok(RunOutput {
             exit_code: code,
             stdout: stdout_handle.await.expect("should be able to get stdout"),
             stderr: stderr_handle.await.expect("should be able to get stderr"),
+            ..RunOutput::default()
         })
     }
 
+    /// Executes the task without input, with every line of its output
+    /// prefixed by `prefix` while it runs, so that the output of tasks that
+    /// run at the same time can be told apart. Only the first `limit` bytes of
+    /// `stdout` and `stderr` are returned.
+    pub async fn execute_with_prefix(
+        &self,
+        command_env: &HashMap<OsString, OsString>,
+        prefix: &str,
+        kill_signal: KillSignal,
+        limit: usize,
+    ) -> Result<RunOutput, TaskExecutionError> {
+        if self.interactive() {
+            return Ok(RunOutput {
+                exit_code: self.execute(command_env, kill_signal).await?,
+                ..RunOutput::default()
+            });
+        }
+        let Some(script) = self.prepare_script()? else {
+            return Ok(RunOutput::default());
+        };
+        let (stdin, stdin_writer) = pipe();
+        drop(stdin_writer);
+        let (stdout, stdout_handle) =
+            get_prefixed_writer_and_handle(std::io::stdout(), prefix.to_string(), limit);
+        let (stderr, stderr_handle) =
+            get_prefixed_writer_and_handle(std::io::stderr(), prefix.to_string(), limit);
+        let code = self
+            .run_script(
+                script,
+                command_env,
+                stdin,
+                stdout,
+                stderr,
+                kill_signal,
+                false,
+            )
+            .await?;
+        Ok(RunOutput {
+            exit_code: code,
+            stdout: stdout_handle.await.expect("should be able to get stdout"),
+            stderr: stderr_handle.await.expect("should be able to get stderr"),
+            ..RunOutput::default()
+        })
+    }
+
+    /// Returns the script to run with the shell of the task, or `None` if the
+    /// task doesn't run a command. A shell from the environment has to be
+    /// installed in it.
+    fn prepare_script(&self) -> Result<Option<TaskScript>, TaskExecutionError> {
+        let shell = self.shell();
+        if shell == TaskShell::Deno {
+            // The script is parsed here as well, so that an invalid script
+            // fails before the shell starts.
+            let Some(script) = self.as_script()? else {
+                return Ok(None);
+            };
+            parse_deno_script(&script)?;
+            return Ok(Some(TaskScript {
+                shell,
+                path: None,
+                script,
+            }));
+        }
+        let Some(script) = self.command_script()? else {
+            return Ok(None);
+        };
+        let path = find_shell(
+            &self.run_environment.dir(),
+            shell,
+            self.run_environment.name(),
+        )?;
+        Ok(Some(TaskScript {
+            shell,
+            path: Some(path),
+            script,
+        }))
+    }
+
+    /// Runs the script in the working directory of the task until it exits,
+    /// is killed or times out, and returns its exit code. A task that
+    /// `reads_terminal` gets the terminal while it runs, see
+    /// [`TaskProcessGroup::takes_terminal`].
+    #[allow(clippy::too_many_arguments)]
+    async fn run_script(
+        &self,
+        script: TaskScript,
+        command_env: &HashMap<OsString, OsString>,
+        stdin: ShellPipeReader,
+        stdout: ShellPipeWriter,
+        stderr: ShellPipeWriter,
+        kill_signal: KillSignal,
+        reads_terminal: bool,
+    ) -> Result<i32, TaskExecutionError> {
+        let cwd = self.working_directory()?;
+        let TaskScript {
+            shell,
+            path,
+            script,
+        } = script;
+        let spawn_error = |source| TaskExecutionError::SpawnShell { shell, source };
+        let path = match path {
+            Some(path) => path,
+            None => match &self.pixi_executable {
+                Some(path) => path.clone(),
+                None => std::env::current_exe().map_err(spawn_error)?,
+            },
+        };
+
+        // The built-in shell sets the `env` of the task with `export`s in front
+        // of the script, the other shells get them as part of their
+        // environment, so that they are the same for every shell.
+        let mut command_env = command_env.clone();
+        if shell != TaskShell::Deno {
+            for (key, value) in specific_task_env(self.task.as_ref()) {
+                let value = expand_variables(value, &command_env);
+                command_env.insert(key.into(), value.into());
+            }
+        }
+
+        // The signals that are sent to the `kill_signal` are sent on to the
+        // processes of the task by its group, see [`TaskProcessGroup`].
+        let mut group = TaskProcessGroup::new();
+        let shell_signal = KillSignal::default();
+        let foreground = reads_terminal && TaskProcessGroup::takes_terminal();
+        let child = spawn_shell(
+            &path,
+            shell,
+            &script,
+            &command_env,
+            &cwd,
+            foreground,
+            stdin,
+            stdout,
+            stderr,
+        )
+        .map_err(spawn_error)?;
+        group.add(&child, foreground);
+        let execution = wait_for_shell(child, shell_signal.clone());
+        run_interruptible(
+            execution,
+            &kill_signal,
+            &group,
+            &shell_signal,
+            self.timeout(),
+            self.grace_period,
+        )
+        .await
+    }
+
     /// We store the hashes of the inputs and the outputs of the task in a file
     /// in the cache. The current name is something like
     /// `run_environment-task_name.json`.
@@ -289,7 +714,7 @@ impl<'p> ExecutableTask<'p> {
     /// `CanSkip::No` and includes the hash of the task that caused the task
     /// to not be skipped - we can use this later to update the cache file
     /// quickly.
-    pub(crate) async fn can_skip(&self, lock_file: &LockFile) -> Result<CanSkip, std::io::Error> {
+    pub async fn can_skip(&self, lock_file: &LockFile) -> Result<CanSkip, std::io::Error> {
         tracing::info!("Checking if task can be skipped");
         let args_hash = TaskHash::task_args_hash(self).unwrap_or_default();
         let cache_name = self.cache_name(args_hash);
@@ -299,7 +724,10 @@ impl<'p> ExecutableTask<'p> {
             let cache: TaskCache = serde_json::from_str(&cache)?;
             let hash = TaskHash::from_task(self, lock_file).await;
             if let Ok(Some(hash)) = hash {
-                if hash.computation_hash() != cache.hash {
+                // The task also runs again if one of its outputs was removed.
+                if hash.computation_hash() != cache.hash
+                    || !OutputHashes::all_exist(self).await.unwrap_or(false)
+                {
                     return Ok(CanSkip::No(Some(hash)));
                 } else {
                     return Ok(CanSkip::Yes);
@@ -312,7 +740,7 @@ impl<'p> ExecutableTask<'p> {
     /// Saves the cache of the task. This function will update the cache file
     /// with the new hash of the task (inputs and outputs). If the task has
     /// no hash, it will not save the cache.
-    pub(crate) async fn save_cache(
+    pub async fn save_cache(
         &self,
         lock_file: &LockFile,
         previous_hash: Option<TaskHash>,
@@ -339,6 +767,15 @@ impl<'p> ExecutableTask<'p> {
     }
 }
 
+/// The script of a task, ready to run with its shell.
+struct TaskScript {
+    shell: TaskShell,
+    /// The executable of a shell from the environment of the task, `None` for
+    /// the shell that is built into pixi.
+    path: Option<PathBuf>,
+    script: String,
+}
+
 /// A helper object that implements [`Display`] to display (with ascii color)
 /// the command of the task.
 struct ExecutableTaskConsoleDisplay<'p, 't> {
@@ -377,6 +814,19 @@ impl Display for ExecutableTaskConsoleDisplay<'_, '_> {
         }
     }
 }
+/// Parses a script for the shell that is built into pixi.
+pub fn parse_deno_script(script: &str) -> Result<SequentialList, FailedToParseShellScript> {
+    tracing::debug!("Parsing shell script: {}", script);
+
+    // Parse the shell command
+    deno_task_shell::parser::parse(script.trim()).map_err(|e| {
+        FailedToParseShellScript::ParseError {
+            source: e,
+            task: script.to_string(),
+        }
+    })
+}
+
 /// Helper function to create a pipe that we can get the output from.
 fn get_output_writer_and_handle() -> (ShellPipeWriter, JoinHandle<String>) {
     let (reader, writer) = pipe();
@@ -384,30 +834,180 @@ fn get_output_writer_and_handle() -> (ShellPipeWriter, JoinHandle<String>) {
     (writer, handle)
 }
 
+/// Returns a writer that forwards everything to `forward`, and a handle to the
+/// first `limit` bytes that were written.
+fn get_tee_writer_and_handle(
+    mut forward: impl Write + Send + 'static,
+    limit: usize,
+) -> (ShellPipeWriter, JoinHandle<String>) {
+    let (mut reader, writer) = pipe();
+    let handle = tokio::task::spawn_blocking(move || {
+        let mut kept = Vec::new();
+        let mut buffer = [0; 8192];
+        while let Ok(read) = reader.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            let chunk = &buffer[..read];
+            let _ = forward.write_all(chunk).and_then(|_| forward.flush());
+            let remaining = limit.saturating_sub(kept.len());
+            kept.extend_from_slice(&chunk[..read.min(remaining)]);
+        }
+        String::from_utf8_lossy(&kept).into_owned()
+    });
+    (writer, handle)
+}
+
+/// Returns a writer that forwards every line that is written to `forward`,
+/// prefixed by `prefix`, as soon as the line is complete, and a handle to the
+/// first `limit` bytes that were written.
+fn get_prefixed_writer_and_handle(
+    mut forward: impl Write + Send + 'static,
+    prefix: String,
+    limit: usize,
+) -> (ShellPipeWriter, JoinHandle<String>) {
+    let (mut reader, writer) = pipe();
+    let handle = tokio::task::spawn_blocking(move || {
+        let mut kept = Vec::new();
+        let mut line = Vec::new();
+        let mut buffer = [0; 8192];
+        let mut write_line = |line: &[u8]| {
+            // The line is written at once, so that it isn't interleaved with
+            // the lines of other tasks.
+            let mut prefixed = Vec::with_capacity(prefix.len() + line.len() + 2);
+            prefixed.extend_from_slice(prefix.as_bytes());
+            prefixed.push(b' ');
+            prefixed.extend_from_slice(line);
+            prefixed.push(b'\n');
+            let _ = forward.write_all(&prefixed).and_then(|_| forward.flush());
+        };
+        while let Ok(read) = reader.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            let chunk = &buffer[..read];
+            let remaining = limit.saturating_sub(kept.len());
+            kept.extend_from_slice(&chunk[..read.min(remaining)]);
+            for part in chunk.split_inclusive(|byte| *byte == b'\n') {
+                match part.strip_suffix(b"\n") {
+                    Some(rest) => {
+                        line.extend_from_slice(rest);
+                        write_line(&line);
+                        line.clear();
+                    }
+                    None => line.extend_from_slice(part),
+                }
+            }
+        }
+        if !line.is_empty() {
+            write_line(&line);
+        }
+        String::from_utf8_lossy(&kept).into_owned()
+    });
+    (writer, handle)
+}
+
+/// The `env` of the task that is set when the task runs, a variable that is
+/// already set is only overwritten if its value refers to itself.
+fn specific_task_env(task: &Task) -> impl Iterator<Item = (&String, &String)> {
+    task.env().into_iter().flatten().filter(|(key, value)| {
+        if value.contains(format!("${}", key).as_str()) || std::env::var(key.as_str()).is_err() {
+            tracing::info!("Setting environment variable: {}=\"{}\"", key, value);
+            true
+        } else {
+            tracing::info!("Environment variable {} already set", key);
+            false
+        }
+    })
+}
+
 /// Task specific environment variables.
 fn get_export_specific_task_env(task: &Task) -> String {
-    // Append the environment variables if they don't exist
-    let mut export = String::new();
-    if let Some(env) = task.env() {
-        for (key, value) in env {
-            if value.contains(format!("${}", key).as_str()) || std::env::var(key.as_str()).is_err()
-            {
-                tracing::info!("Setting environment variable: {}=\"{}\"", key, value);
-                export.push_str(&format!("export \"{}={}\";\n", key, value));
-            } else {
-                tracing::info!("Environment variable {} already set", key);
+    specific_task_env(task)
+        .map(|(key, value)| format!("export \"{}={}\";\n", key, value))
+        .collect()
+}
+
+/// Runs the shell script to completion and returns its exit code. The script
+/// fails if a signal is sent to the `kill_signal` while it runs. When it runs
+/// longer than the `timeout` it receives SIGTERM, and it is killed if it still
+/// runs after the `grace_period`. The signals are sent to the processes of
+/// the task with its `group`, the `shell_signal` is the kill signal of the
+/// shell that runs the script.
+pub async fn run_interruptible(
+    execution: impl Future<Output = i32>,
+    kill_signal: &KillSignal,
+    group: &TaskProcessGroup,
+    shell_signal: &KillSignal,
+    timeout: Option<Duration>,
+    grace_period: Duration,
+) -> Result<i32, TaskExecutionError> {
+    let started = Instant::now();
+    let mut execution = std::pin::pin!(execution);
+    let timed_out = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    let (error, mut kill_at) = tokio::select! {
+        code = &mut execution => return Ok(code),
+        signal = kill_signal.wait_any() => {
+            group.send(signal, shell_signal);
+            (TaskExecutionError::Interrupted(signal), None)
+        }
+        _ = timed_out => {
+            group.send(SignalKind::SIGTERM, shell_signal);
+            let kill_at = tokio::time::Instant::now() + grace_period;
+            (TaskExecutionError::TimedOut(started.elapsed()), Some(kill_at))
+        }
+    };
+
+    // The signals that follow, like SIGKILL after the grace period, are sent
+    // to the processes of the task as well.
+    loop {
+        let grace_period_ended = async {
+            match kill_at {
+                Some(kill_at) => tokio::time::sleep_until(kill_at).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = &mut execution => return Err(error),
+            signal = kill_signal.wait_any() => group.send(signal, shell_signal),
+            _ = grace_period_ended => {
+                kill_at = None;
+                group.send(SignalKind::SIGKILL, shell_signal);
             }
         }
     }
-    export
+}
+
+/// Resolves a working directory relative to the root of the workspace and
+/// checks that it is an existing directory.
+pub fn resolve_working_directory(
+    root: &Path,
+    cwd: &Path,
+) -> Result<PathBuf, InvalidWorkingDirectory> {
+    let abs_path = root.join(cwd);
+    if !abs_path.is_dir() {
+        return Err(InvalidWorkingDirectory {
+            path: cwd.to_string_lossy().to_string(),
+        });
+    }
+    Ok(abs_path)
 }
 
 /// Determine the environment variables to use when executing a command. The
 /// method combines the activation environment with the system environment
-/// variables.
+/// variables and the variables of the `.env` files of the environment.
+///
+/// In a clean environment, the variables of the system whose names match one
+/// of the `clean_env_keep` patterns are kept.
 pub async fn get_task_env(
     environment: &Environment<'_>,
     clean_env: bool,
+    clean_env_keep: &[String],
     lock_file: Option<&LockFile>,
     force_activate: bool,
     experimental_cache: bool,
@@ -432,6 +1032,14 @@ pub async fn get_task_env(
     .wrap_err("failed to activate environment")?
     .clone();
 
+    // Copy the variables that should survive a clean environment from the
+    // system, the variables of the activation take precedence.
+    if clean_env {
+        for (key, value) in get_kept_environment_variables(clean_env_keep) {
+            activation_env.entry(key).or_insert(value);
+        }
+    }
+
     // Add the current working directory to the environment
     if let Ok(init_cwd) = std::env::current_dir() {
         activation_env.insert(
@@ -442,10 +1050,34 @@ pub async fn get_task_env(
         tracing::warn!("Failed to get the current working directory for INIT_CWD.");
     }
 
+    // Add the variables of the `.env` files, the variables declared in the
+    // manifest take precedence.
+    let env_file_vars = environment_env_file_variables(environment, &activation_env)?;
+    activation_env.extend(env_file_vars);
+
     // Concatenate with the system environment variables
     Ok(activation_env)
 }
 
+/// Reads the variables of the `.env` files of the environment, `${VAR}`
+/// references are expanded against `env`. The variables that the manifest
+/// declares in the `env` of the environment are left out, because those take
+/// precedence.
+pub fn environment_env_file_variables(
+    environment: &Environment<'_>,
+    env: &HashMap<String, String>,
+) -> Result<IndexMap<String, String>, EnvFileError> {
+    let platform = Some(environment.best_platform());
+    let declared_env = environment.activation_env(platform);
+    let mut variables = load_env_files(
+        environment.workspace().root(),
+        &environment.env_files(platform),
+        env,
+    )?;
+    variables.retain(|key, _| !declared_env.contains_key(key));
+    Ok(variables)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -506,6 +1138,14 @@ mod tests {
             task: Cow::Borrowed(task),
             run_environment: workspace.default_environment(),
             args: ArgValues::default(),
+            skipped: false,
+            cwd_override: None,
+            timeout_override: None,
+            shell_override: None,
+            interactive_override: false,
+            retries_override: None,
+            grace_period: DEFAULT_GRACE_PERIOD,
+            pixi_executable: None,
         };
 
         let script = executable_task.as_script().unwrap().unwrap();
@@ -525,7 +1165,7 @@ mod tests {
         .unwrap();
 
         let environment = workspace.default_environment();
-        let env = get_task_env(&environment, false, None, false, false)
+        let env = get_task_env(&environment, false, &[], None, false, false)
             .await
             .unwrap();
         assert_eq!(
@@ -536,4 +1176,32 @@ mod tests {
                 .to_string()
         );
     }
+
+    #[tokio::test]
+    async fn test_get_task_env_with_env_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs_err::write(
+            dir.path().join(".env"),
+            "FROM_FILE=file\nDECLARED=file\nEXPANDED=${PIXI_ENVIRONMENT_NAME}-${FROM_FILE}\n",
+        )
+        .unwrap();
+        let file_contents = r#"
+            [activation]
+            env = { DECLARED = "manifest" }
+            env-files = [".env", ".env.missing"]
+            "#;
+        let workspace = Workspace::from_str(
+            &dir.path().join("pixi.toml"),
+            &format!("{PROJECT_BOILERPLATE}\n{file_contents}"),
+        )
+        .unwrap();
+
+        let environment = workspace.default_environment();
+        let env = get_task_env(&environment, false, &[], None, false, false)
+            .await
+            .unwrap();
+        assert_eq!(env.get("FROM_FILE").unwrap(), "file");
+        assert_eq!(env.get("DECLARED").unwrap(), "manifest");
+        assert_eq!(env.get("EXPANDED").unwrap(), "default-file");
+    }
 }
@@ -0,0 +1,346 @@
+//! Stops the processes of the tasks when `pixi run` is interrupted.
+//!
+//! Every task runs in a process of its own, also the tasks that run with the
+//! shell that is built into pixi. On Unix that process is the leader of a new
+//! process group, which the processes that it starts in turn belong to, so
+//! that a signal reaches every process of the task with a single `killpg`. On
+//! Windows the process is assigned to a job object instead.
+//!
+//! A task that reads from the terminal gets the terminal while it runs, so
+//! that it can read from it and receives CTRL-C from it directly.
+
+#[cfg(windows)]
+use std::sync::Arc;
+use std::{sync::Mutex, time::Duration};
+
+use deno_task_shell::{KillSignal, SignalKind};
+use tokio::time::Instant;
+
+/// The time that the tasks get to stop after SIGTERM, before their processes
+/// are killed.
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// The groups of the tasks of this pixi, so that the processes that a task
+/// started in the background can be stopped after the task exited.
+static TASK_GROUPS: Mutex<Vec<Group>> = Mutex::new(Vec::new());
+
+/// The process group of a task on Unix.
+#[cfg(unix)]
+type Group = libc::pid_t;
+
+/// The job object of a task on Windows.
+#[cfg(windows)]
+type Group = Arc<job::Job>;
+
+/// Waits until pixi receives SIGINT or SIGTERM, or CTRL-C on Windows.
+pub async fn wait_for_interrupt() -> std::io::Result<SignalKind> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind as UnixSignalKind, signal};
+
+        let mut interrupt = signal(UnixSignalKind::interrupt())?;
+        let mut terminate = signal(UnixSignalKind::terminate())?;
+        tokio::select! {
+            _ = interrupt.recv() => Ok(SignalKind::SIGINT),
+            _ = terminate.recv() => Ok(SignalKind::SIGTERM),
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok(SignalKind::SIGINT)
+    }
+}
+
+/// Returns the exit code of pixi when it stops because of the signal,
+/// `128 + signal` like a process that the signal terminated.
+pub fn signal_exit_code(signal: SignalKind) -> i32 {
+    128 + match signal {
+        SignalKind::SIGKILL => 9,
+        SignalKind::SIGTERM => 15,
+        _ => 2,
+    }
+}
+
+/// The processes of a single task.
+#[derive(Default)]
+pub struct TaskProcessGroup {
+    /// The process group of the task, the id of the process that runs it.
+    #[cfg(unix)]
+    leader: Option<libc::pid_t>,
+
+    /// Whether the task was given the terminal, which is given back to pixi
+    /// when the group is dropped.
+    #[cfg(unix)]
+    foreground: bool,
+
+    /// The job object of the task.
+    #[cfg(windows)]
+    job: Option<Arc<job::Job>>,
+}
+
+impl TaskProcessGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if a task that reads the input of pixi should get the
+    /// terminal, which is the case if the input is a terminal of which pixi
+    /// is in the foreground. The task then takes the terminal when it starts,
+    /// see [`take_terminal`].
+    pub fn takes_terminal() -> bool {
+        #[cfg(unix)]
+        {
+            use std::io::IsTerminal;
+            // SAFETY: only reads the process groups.
+            std::io::stdin().is_terminal()
+                && unsafe { libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp() }
+        }
+
+        #[cfg(windows)]
+        false
+    }
+
+    /// Adds the process that runs the task, which was started in a process
+    /// group of its own and took the terminal if `foreground` is true. On
+    /// Windows the process is assigned to a job object, together with the
+    /// processes that it starts from now on.
+    pub fn add(&mut self, child: &tokio::process::Child, foreground: bool) {
+        #[cfg(unix)]
+        if let Some(pid) = child.id() {
+            let pid = pid as libc::pid_t;
+            self.leader = Some(pid);
+            self.foreground = foreground;
+            register(pid);
+        }
+
+        #[cfg(windows)]
+        {
+            let _ = foreground;
+            if let Some(job) = child.raw_handle().and_then(job::Job::assign) {
+                let job = Arc::new(job);
+                self.job = Some(job.clone());
+                register(job);
+            }
+        }
+    }
+
+    /// Sends the signal to the processes of the task. The `shell_signal` is
+    /// the kill signal of the process that runs the task, which kills it if
+    /// it couldn't be assigned to a job object on Windows.
+    pub fn send(&self, signal: SignalKind, shell_signal: &KillSignal) {
+        shell_signal.send(signal);
+
+        #[cfg(unix)]
+        if let Some(leader) = self.leader {
+            // SAFETY: only sends the signal to the process group of the task.
+            unsafe { libc::killpg(leader, unix_signal(signal)) };
+        }
+
+        #[cfg(windows)]
+        if matches!(signal, SignalKind::SIGKILL) {
+            if let Some(job) = &self.job {
+                job.terminate(1);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for TaskProcessGroup {
+    fn drop(&mut self) {
+        if self.foreground {
+            // SAFETY: only changes the foreground process group of the
+            // terminal back to the one of pixi.
+            let _ = with_sigttou_blocked(|| unsafe {
+                libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp())
+            });
+        }
+    }
+}
+
+/// The processes of all the tasks of a run, including the ones that a task
+/// started in the background and that outlive it.
+#[derive(Default)]
+pub struct TaskProcesses;
+
+impl TaskProcesses {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Waits until all the processes of the tasks exited, returns false if
+    /// some are still running at the `deadline`.
+    pub async fn wait_for_exit(&self, deadline: Instant) -> bool {
+        loop {
+            if running_groups().is_empty() {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Kills the processes of the tasks that are still running.
+    pub fn kill(&self) {
+        for group in running_groups() {
+            // SAFETY: only sends the signal to the process group of a task.
+            #[cfg(unix)]
+            unsafe {
+                libc::killpg(group, libc::SIGKILL);
+            }
+
+            #[cfg(windows)]
+            group.terminate(1);
+        }
+    }
+}
+
+/// Remembers the group of a task, and forgets the groups without processes.
+fn register(group: Group) {
+    let mut groups = TASK_GROUPS.lock().expect("the lock is never poisoned");
+    groups.retain(is_running);
+    groups.push(group);
+}
+
+/// Returns the groups of the tasks that still have running processes.
+fn running_groups() -> Vec<Group> {
+    let mut groups = TASK_GROUPS.lock().expect("the lock is never poisoned");
+    groups.retain(is_running);
+    groups.clone()
+}
+
+/// Returns true if a process of the group is still running.
+fn is_running(group: &Group) -> bool {
+    #[cfg(unix)]
+    {
+        // SAFETY: signal 0 only checks whether the process group exists.
+        unsafe { libc::killpg(*group, 0) == 0 }
+    }
+
+    #[cfg(windows)]
+    {
+        group.active_processes() > 0
+    }
+}
+
+/// Makes the process group of the calling process the foreground process
+/// group of the terminal of its input. Called in the process of a task before
+/// it runs the command of the task, SIGTTOU is blocked so that the process
+/// isn't stopped because it isn't in the foreground yet.
+#[cfg(unix)]
+pub(crate) fn take_terminal() -> std::io::Result<()> {
+    // SAFETY: only changes the foreground process group of the terminal.
+    with_sigttou_blocked(|| unsafe { libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp()) })
+}
+
+/// Calls `f` with SIGTTOU blocked for the calling thread. A process that
+/// isn't in the foreground process group can only change the foreground
+/// process group of the terminal while it blocks SIGTTOU.
+#[cfg(unix)]
+fn with_sigttou_blocked(f: impl FnOnce() -> libc::c_int) -> std::io::Result<()> {
+    // SAFETY: the signal sets are initialized before they are used and the
+    // previous mask is restored.
+    unsafe {
+        let mut blocked: libc::sigset_t = std::mem::zeroed();
+        let mut previous: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut blocked);
+        libc::sigaddset(&mut blocked, libc::SIGTTOU);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &blocked, &mut previous);
+        let result = f();
+        let error = std::io::Error::last_os_error();
+        libc::pthread_sigmask(libc::SIG_SETMASK, &previous, std::ptr::null_mut());
+        if result == -1 { Err(error) } else { Ok(()) }
+    }
+}
+
+#[cfg(unix)]
+fn unix_signal(signal: SignalKind) -> libc::c_int {
+    match signal {
+        SignalKind::SIGINT => libc::SIGINT,
+        SignalKind::SIGKILL => libc::SIGKILL,
+        _ => libc::SIGTERM,
+    }
+}
+
+#[cfg(windows)]
+mod job {
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, HANDLE},
+        System::JobObjects::{
+            AssignProcessToJobObject, CreateJobObjectW, JOBOBJECT_BASIC_ACCOUNTING_INFORMATION,
+            JobObjectBasicAccountingInformation, QueryInformationJobObject, TerminateJobObject,
+        },
+    };
+
+    /// A job object that contains a process that pixi started and the
+    /// processes that it starts in turn.
+    pub struct Job(HANDLE);
+
+    impl Job {
+        /// Creates a job object and assigns the process to it, returns `None`
+        /// if the process can't be assigned, e.g. because it runs in a job
+        /// that doesn't allow nested jobs.
+        pub fn assign(process: HANDLE) -> Option<Self> {
+            // SAFETY: the handle is checked before it is used and closed when
+            // the job is dropped.
+            unsafe {
+                let handle = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+                if handle.is_null() {
+                    return None;
+                }
+                let job = Job(handle);
+                if AssignProcessToJobObject(job.0, process) == 0 {
+                    tracing::debug!("failed to assign a task to a job object");
+                    return None;
+                }
+                Some(job)
+            }
+        }
+
+        /// Returns the number of processes of the job that are running.
+        pub fn active_processes(&self) -> u32 {
+            // SAFETY: the handle is valid for the lifetime of the job and the
+            // information fits in the buffer.
+            unsafe {
+                let mut info: JOBOBJECT_BASIC_ACCOUNTING_INFORMATION = std::mem::zeroed();
+                let queried = QueryInformationJobObject(
+                    self.0,
+                    JobObjectBasicAccountingInformation,
+                    (&mut info as *mut JOBOBJECT_BASIC_ACCOUNTING_INFORMATION).cast(),
+                    std::mem::size_of::<JOBOBJECT_BASIC_ACCOUNTING_INFORMATION>() as u32,
+                    std::ptr::null_mut(),
+                );
+                if queried == 0 {
+                    0
+                } else {
+                    info.ActiveProcesses
+                }
+            }
+        }
+
+        /// Terminates every process of the job.
+        pub fn terminate(&self, exit_code: u32) {
+            // SAFETY: the handle is valid for the lifetime of the job.
+            unsafe {
+                TerminateJobObject(self.0, exit_code);
+            }
+        }
+    }
+
+    // SAFETY: the handle of a job object can be used from any thread.
+    unsafe impl Send for Job {}
+    unsafe impl Sync for Job {}
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            // SAFETY: the handle is valid and not used after this.
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
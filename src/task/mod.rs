@@ -1,20 +1,35 @@
+mod env_file;
 mod error;
 mod executable_task;
 mod file_hashes;
+mod interrupt;
+mod report;
+mod shell;
 mod task_environment;
 mod task_graph;
 mod task_hash;
+mod task_scheduler;
+mod watch;
 
+pub use env_file::{EnvFileError, ParseEnvFileError, load_env_files};
 pub use file_hashes::{FileHashes, FileHashesError};
+pub use interrupt::{
+    DEFAULT_GRACE_PERIOD, TaskProcessGroup, TaskProcesses, signal_exit_code, wait_for_interrupt,
+};
 pub use pixi_manifest::{Task, TaskName};
+pub use report::{REPORT_OUTPUT_LIMIT, ReportError, write_json_report};
+pub use shell::MissingShellError;
 pub use task_hash::{ComputationHash, EnvironmentHash, InputHashes, TaskHash};
 
 pub use executable_task::{
     CanSkip, ExecutableTask, FailedToParseShellScript, InvalidWorkingDirectory, RunOutput,
-    TaskExecutionError, get_task_env,
+    TaskExecutionError, TaskOutput, environment_env_file_variables, get_task_env,
+    parse_deno_script, resolve_working_directory, run_interruptible,
 };
 pub use task_environment::{
     AmbiguousTask, FindTaskError, FindTaskSource, SearchEnvironments, TaskAndEnvironment,
-    TaskDisambiguation,
+    TaskDisambiguation, print_environment_choice,
 };
 pub use task_graph::{TaskGraph, TaskGraphError, TaskId, TaskNode};
+pub use task_scheduler::TaskScheduler;
+pub use watch::{FileWatcher, WatchChange, WatchSnapshot, WatchedPaths};
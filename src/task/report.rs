@@ -0,0 +1,170 @@
+//! The report of a run that `pixi run --report` writes, for CI analytics.
+
+use std::path::{Path, PathBuf};
+
+use chrono::TimeDelta;
+use miette::Diagnostic;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::TaskOutput;
+
+/// The number of bytes of the `stdout` and `stderr` of every task that are
+/// included in a report.
+pub const REPORT_OUTPUT_LIMIT: usize = 64 * 1024;
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum ReportError {
+    #[error("failed to serialize the run report")]
+    Serialize(#[source] serde_json::Error),
+
+    #[error("failed to write the run report to '{}'", .0.display())]
+    Write(PathBuf, #[source] std::io::Error),
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    tasks: Vec<JsonTask<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonTask<'a> {
+    name: Option<&'a str>,
+    environment: Option<&'a str>,
+    command: Option<&'a str>,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+    duration_secs: f64,
+    /// `None` if the command didn't run or didn't finish by itself.
+    exit_code: Option<i32>,
+    skipped: bool,
+    cached: bool,
+    /// How many times the command ran, `0` if it didn't run.
+    attempts: u32,
+    error: Option<&'a str>,
+    stdout: &'a str,
+    stderr: &'a str,
+}
+
+impl<'a> From<&'a TaskOutput> for JsonTask<'a> {
+    fn from(output: &'a TaskOutput) -> Self {
+        let ran = output.started_at.is_some() && output.error.is_none();
+        Self {
+            name: output.name.as_ref().map(|name| name.as_str()),
+            environment: output.environment.as_ref().map(|name| name.as_str()),
+            command: output.command.as_deref(),
+            started_at: output.started_at.map(|started| started.to_rfc3339()),
+            finished_at: output
+                .started_at
+                .map(|started| (started + duration(output)).to_rfc3339()),
+            duration_secs: output.duration.as_secs_f64(),
+            exit_code: ran.then_some(output.exit_code),
+            skipped: output.skipped,
+            cached: output.cache_hit,
+            attempts: output.attempts,
+            error: output.error.as_deref(),
+            stdout: truncate(&output.stdout, REPORT_OUTPUT_LIMIT),
+            stderr: truncate(&output.stderr, REPORT_OUTPUT_LIMIT),
+        }
+    }
+}
+
+/// Returns how long the task ran, to compute when it finished.
+fn duration(output: &TaskOutput) -> TimeDelta {
+    TimeDelta::from_std(output.duration).unwrap_or(TimeDelta::zero())
+}
+
+/// Returns the first `limit` bytes of the string, without splitting a
+/// character.
+fn truncate(value: &str, limit: usize) -> &str {
+    if value.len() <= limit {
+        return value;
+    }
+    let mut end = limit;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+/// Writes a JSON report of the tasks of a run to `path`.
+pub fn write_json_report(path: &Path, tasks: &[TaskOutput]) -> Result<(), ReportError> {
+    let report = JsonReport {
+        tasks: tasks.iter().map(JsonTask::from).collect(),
+    };
+    let contents = serde_json::to_string_pretty(&report).map_err(ReportError::Serialize)?;
+    fs_err::write(path, contents).map_err(|err| ReportError::Write(path.to_path_buf(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("hello", 10), "hello");
+        assert_eq!(truncate("hello", 2), "he");
+        // 'é' takes two bytes
+        assert_eq!(truncate("héllo", 2), "h");
+    }
+
+    #[test]
+    fn test_write_json_report() {
+        let started_at = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let tasks = [
+            TaskOutput {
+                name: Some("build".into()),
+                command: Some("cargo build".to_string()),
+                exit_code: 0,
+                stdout: "built\n".to_string(),
+                started_at: Some(started_at),
+                duration: Duration::from_millis(1500),
+                attempts: 2,
+                ..TaskOutput::default()
+            },
+            TaskOutput {
+                name: Some("test".into()),
+                exit_code: 1,
+                started_at: Some(started_at),
+                duration: Duration::from_secs(1),
+                error: Some("the task timed out after 1.0s".to_string()),
+                ..TaskOutput::default()
+            },
+            TaskOutput {
+                name: Some("lint".into()),
+                cache_hit: true,
+                ..TaskOutput::default()
+            },
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.json");
+        write_json_report(&path, &tasks).unwrap();
+        let report: serde_json::Value =
+            serde_json::from_str(&fs_err::read_to_string(&path).unwrap()).unwrap();
+
+        let build = &report["tasks"][0];
+        assert_eq!(build["name"], "build");
+        assert_eq!(build["command"], "cargo build");
+        assert_eq!(build["exit_code"], 0);
+        assert_eq!(build["duration_secs"], 1.5);
+        assert_eq!(build["started_at"], "2025-01-01T12:00:00+00:00");
+        assert_eq!(build["finished_at"], "2025-01-01T12:00:01.500+00:00");
+        assert_eq!(build["stdout"], "built\n");
+        assert_eq!(build["attempts"], 2);
+
+        let test = &report["tasks"][1];
+        assert!(test["exit_code"].is_null());
+        assert_eq!(test["error"], "the task timed out after 1.0s");
+
+        let lint = &report["tasks"][2];
+        assert!(lint["exit_code"].is_null());
+        assert!(lint["started_at"].is_null());
+        assert_eq!(lint["cached"], true);
+        assert_eq!(lint["attempts"], 0);
+    }
+}
@@ -0,0 +1,243 @@
+//! Runs the commands of tasks in a shell process. A task runs with a shell
+//! from the environment of the task, or with the shell that is built into
+//! pixi in a pixi process of its own.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+};
+
+use deno_task_shell::{KillSignal, ShellPipeReader, ShellPipeWriter, SignalKind};
+use miette::Diagnostic;
+use pixi_manifest::{EnvironmentName, task::TaskShell};
+use thiserror::Error;
+use tokio::process::{Child, Command};
+
+/// The shell that a task asks for is not installed in its environment.
+#[derive(Debug, Error, Diagnostic)]
+#[error("the shell '{shell}' is not installed in the environment '{environment}'")]
+#[diagnostic(help(
+    "add it to the environment with `pixi add {package}`, the shell is never taken from the system"
+))]
+pub struct MissingShellError {
+    pub shell: TaskShell,
+    pub environment: EnvironmentName,
+    pub package: &'static str,
+}
+
+/// Returns the name of the conda package that provides the shell.
+fn package_name(shell: TaskShell) -> &'static str {
+    match shell {
+        TaskShell::Deno => "deno",
+        TaskShell::Bash => "bash",
+        TaskShell::Nu => "nushell",
+        TaskShell::Powershell => "powershell",
+    }
+}
+
+/// Finds the executable of the shell in the prefix of an environment.
+pub(crate) fn find_shell(
+    prefix: &Path,
+    shell: TaskShell,
+    environment: &EnvironmentName,
+) -> Result<PathBuf, MissingShellError> {
+    let missing = || MissingShellError {
+        shell,
+        environment: environment.clone(),
+        package: package_name(shell),
+    };
+    let name = shell.executable_name().ok_or_else(missing)?;
+    let (folders, file_name) = if cfg!(windows) {
+        (
+            &["Library/bin", "Library/usr/bin", "Scripts", "bin", ""][..],
+            format!("{name}.exe"),
+        )
+    } else {
+        (&["bin"][..], name.to_string())
+    };
+    folders
+        .iter()
+        .map(|folder| prefix.join(folder).join(&file_name))
+        .find(|path| path.is_file())
+        .ok_or_else(missing)
+}
+
+/// Returns the arguments that make the shell run the script. The shell that is
+/// built into pixi runs in a pixi process of its own, see
+/// [`crate::cli::task_shell`].
+fn script_args(shell: TaskShell, script: &str) -> Vec<&str> {
+    match shell {
+        TaskShell::Deno => vec![TASK_SHELL_COMMAND, "-c", script],
+        TaskShell::Powershell => vec!["-NoProfile", "-NonInteractive", "-Command", script],
+        _ => vec!["-c", script],
+    }
+}
+
+/// The hidden command of pixi that runs a script with the shell that is built
+/// into pixi.
+pub(crate) const TASK_SHELL_COMMAND: &str = "__task-shell";
+
+/// Starts the shell to run the script, with exactly the given environment
+/// variables in the given working directory. On Unix the shell is started in
+/// a process group of its own, which takes the terminal if `foreground` is
+/// true, see [`super::interrupt::take_terminal`].
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn spawn_shell(
+    shell_path: &Path,
+    shell: TaskShell,
+    script: &str,
+    command_env: &HashMap<OsString, OsString>,
+    cwd: &Path,
+    foreground: bool,
+    stdin: ShellPipeReader,
+    stdout: ShellPipeWriter,
+    stderr: ShellPipeWriter,
+) -> std::io::Result<Child> {
+    // The command is dropped after spawning the shell, so that its copies of
+    // the pipes are closed and the output ends when the shell exits.
+    let mut command = Command::new(shell_path);
+    command
+        .args(script_args(shell, script))
+        .env_clear()
+        .envs(command_env)
+        .current_dir(cwd)
+        .stdin(stdin.into_stdio())
+        .stdout(stdout.into_stdio())
+        .stderr(stderr.into_stdio())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    {
+        command.process_group(0);
+        if foreground {
+            // SAFETY: `take_terminal` only calls functions that are safe to
+            // call between fork and exec.
+            unsafe { command.pre_exec(super::interrupt::take_terminal) };
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = foreground;
+    command.spawn()
+}
+
+/// Waits for the shell to exit and returns its exit code. The other signals
+/// reach the shell with its group, which [`super::TaskProcessGroup::send`]
+/// signals, only SIGKILL that is sent to the `kill_signal` kills the shell
+/// directly.
+pub(crate) async fn wait_for_shell(mut child: Child, kill_signal: KillSignal) -> i32 {
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status,
+            signal = kill_signal.wait_any() => {
+                if matches!(signal, SignalKind::SIGKILL) {
+                    let _ = child.start_kill();
+                }
+            }
+        }
+    };
+    status.map_or(1, exit_code)
+}
+
+/// Returns the exit code of a process, a process that was killed by a signal
+/// exits with `128 + signal` like it does in a shell.
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+/// Expands the `$NAME` and `${NAME}` references to environment variables in
+/// a value, like the built-in shell does for the `env` of a task. Variables
+/// that are not set expand to an empty string.
+pub(crate) fn expand_variables(value: &str, env: &HashMap<OsString, OsString>) -> String {
+    let lookup = |name: &str| {
+        env.get(&OsString::from(name))
+            .map(|value| value.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(braced) = after.strip_prefix('{') {
+            if let Some(end) = braced.find('}') {
+                expanded.push_str(&lookup(&braced[..end]));
+                rest = &braced[end + 1..];
+                continue;
+            }
+        }
+        let end = after
+            .find(|c: char| !is_name_char(c))
+            .unwrap_or(after.len());
+        if end == 0 {
+            expanded.push('$');
+        } else {
+            expanded.push_str(&lookup(&after[..end]));
+        }
+        rest = &after[end..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_variables() {
+        let env = HashMap::from([
+            (OsString::from("HOME"), OsString::from("/home/user")),
+            (OsString::from("PATH"), OsString::from("/usr/bin")),
+        ]);
+        assert_eq!(expand_variables("$HOME/bin", &env), "/home/user/bin");
+        assert_eq!(
+            expand_variables("${HOME}_x:$PATH", &env),
+            "/home/user_x:/usr/bin"
+        );
+        assert_eq!(expand_variables("$MISSING-a", &env), "-a");
+        assert_eq!(expand_variables("costs $5 or $", &env), "costs  or $");
+        assert_eq!(expand_variables("a ${unclosed", &env), "a ${unclosed");
+    }
+
+    #[test]
+    fn test_find_shell() {
+        let prefix = tempfile::tempdir().unwrap();
+        let environment = EnvironmentName::Default;
+        let err = find_shell(prefix.path(), TaskShell::Bash, &environment).unwrap_err();
+        assert_eq!(err.package, "bash");
+
+        let bin = if cfg!(windows) {
+            prefix.path().join("Library/bin/bash.exe")
+        } else {
+            prefix.path().join("bin/bash")
+        };
+        fs_err::create_dir_all(bin.parent().unwrap()).unwrap();
+        fs_err::write(&bin, "").unwrap();
+        assert_eq!(
+            find_shell(prefix.path(), TaskShell::Bash, &environment).unwrap(),
+            bin
+        );
+
+        // `m2-bash` installs the shell in `Library/usr/bin` on Windows
+        if cfg!(windows) {
+            let prefix = tempfile::tempdir().unwrap();
+            let bin = prefix.path().join("Library/usr/bin/bash.exe");
+            fs_err::create_dir_all(bin.parent().unwrap()).unwrap();
+            fs_err::write(&bin, "").unwrap();
+            assert_eq!(
+                find_shell(prefix.path(), TaskShell::Bash, &environment).unwrap(),
+                bin
+            );
+        }
+    }
+}
@@ -1,5 +1,7 @@
+use fancy_display::FancyDisplay;
+use itertools::Itertools;
 use miette::Diagnostic;
-use pixi_manifest::{Task, TaskName};
+use pixi_manifest::{EnvironmentName, Task, TaskName};
 use rattler_conda_types::Platform;
 use thiserror::Error;
 
@@ -129,27 +131,41 @@ impl<'p, D: TaskDisambiguation<'p>> SearchEnvironments<'p, D> {
             let default_env = self.project.default_environment();
             // If the default environment has the task
             if let Ok(default_env_task) = default_env.task(&name, self.platform) {
-                // If no other environment has the task name but a different task, return the
-                // default environment
-                if !self
+                let mut ambiguous = false;
+                let mut sharing_environments = Vec::new();
+                for env in self
                     .project
                     .environments()
-                    .iter()
+                    .into_iter()
                     // Filter out default environment
                     .filter(|env| !env.name().is_default())
                     // Filter out environments that can not run on this machine.
                     .filter(|env| verify_current_platform_can_run_environment(env, None).is_ok())
-                    .any(|env| {
-                        if let Ok(task) = env.task(&name, self.platform) {
-                            // If the task exists in the environment but it is not the reference to
-                            // the same task, return true to make it ambiguous
-                            !std::ptr::eq(task, default_env_task)
+                {
+                    if let Ok(task) = env.task(&name, self.platform) {
+                        // If the task exists in the environment but it is not the reference to
+                        // the same task, it is ambiguous
+                        if std::ptr::eq(task, default_env_task) {
+                            sharing_environments.push(env.name().clone());
                         } else {
-                            // If the task does not exist in the environment, return false
-                            false
+                            ambiguous = true;
+                            break;
                         }
-                    })
-                {
+                    }
+                }
+
+                // If no other environment has the task name but a different task, return the
+                // default environment
+                if !ambiguous {
+                    if !sharing_environments.is_empty() && matches!(source, FindTaskSource::CmdArgs)
+                    {
+                        print_environment_choice(
+                            &name,
+                            std::iter::once(default_env.name()).chain(&sharing_environments),
+                            default_env.name(),
+                            "it is the default environment",
+                        );
+                    }
                     return Ok((self.project.default_environment(), default_env_task));
                 }
             }
@@ -211,6 +227,26 @@ impl<'p, D: TaskDisambiguation<'p>> SearchEnvironments<'p, D> {
     }
 }
 
+/// Prints which of the environments that define a task was chosen to run it
+/// in, and why.
+pub fn print_environment_choice<'a>(
+    task_name: &TaskName,
+    environments: impl IntoIterator<Item = &'a EnvironmentName>,
+    chosen: &EnvironmentName,
+    reason: &str,
+) {
+    eprintln!(
+        "{}The task '{}' is defined in the environments {}, running it in '{}' because {reason}. Use `--environment` to choose another one",
+        console::style(console::Emoji("ℹ️ ", "")).yellow(),
+        task_name.fancy_display(),
+        environments
+            .into_iter()
+            .map(|name| name.fancy_display())
+            .format(", "),
+        chosen.fancy_display(),
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
@@ -4,10 +4,11 @@ use std::{
     env,
     fmt::{self, Display},
     ops::Index,
+    sync::Arc,
 };
 
 use itertools::Itertools;
-use miette::Diagnostic;
+use miette::{Diagnostic, LabeledSpan, NamedSource};
 use pixi_manifest::{
     EnvironmentName, Task, TaskName,
     task::{ArgValues, CmdArgs, Custom, TaskArg, TemplateStringError, TypedArg, TypedDependency},
@@ -18,7 +19,7 @@ use crate::{
     Workspace,
     task::{
         TaskDisambiguation,
-        error::{AmbiguousTaskError, MissingTaskError},
+        error::{AmbiguousTaskError, MissingTaskError, TaskCycleError},
         task_environment::{FindTaskError, FindTaskSource, SearchEnvironments},
     },
     workspace::Environment,
@@ -58,6 +59,34 @@ pub struct TaskNode<'p> {
 
     /// The id's of the task that this task depends on.
     pub dependencies: Vec<GraphDependency>,
+
+    /// Whether the task is skipped, which is the case for the dependencies of
+    /// the requested task with `--skip-deps`. A skipped task is part of the
+    /// graph, so the order of the other tasks doesn't change, but it doesn't
+    /// run.
+    pub skipped: bool,
+}
+
+impl TaskNode<'_> {
+    /// Returns the key that identifies the node in the graph, or `None` for a
+    /// custom task.
+    fn key(&self) -> Option<NodeKey> {
+        Some(NodeKey {
+            task_name: self.name.clone()?,
+            environment: self.run_environment.name().clone(),
+            args: self.args.clone().filter(|args| !args.is_empty()),
+        })
+    }
+}
+
+/// Identifies a node of the [`TaskGraph`]. A task that is depended on by
+/// several tasks runs once for every environment and every set of rendered
+/// arguments it's requested with, so all its dependents share that node.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct NodeKey {
+    task_name: TaskName,
+    environment: EnvironmentName,
+    args: Option<ArgValues>,
 }
 
 impl fmt::Display for TaskNode<'_> {
@@ -217,19 +246,6 @@ impl<'p> TaskGraph<'p> {
                         Some(ArgValues::FreeFormArgs(args.clone()))
                     };
 
-                    if skip_deps {
-                        return Ok(Self {
-                            project,
-                            nodes: vec![TaskNode {
-                                name: Some(task_name.into()),
-                                task: Cow::Borrowed(task),
-                                run_environment: run_env,
-                                args: arg_values,
-                                dependencies: vec![],
-                            }],
-                        });
-                    }
-
                     return Self::from_root(
                         project,
                         search_envs,
@@ -239,8 +255,9 @@ impl<'p> TaskGraph<'p> {
                             run_environment: run_env,
                             args: arg_values,
                             dependencies: vec![],
+                            skipped: false,
                         },
-                        Some(args),
+                        skip_deps,
                     );
                 }
             }
@@ -282,39 +299,49 @@ impl<'p> TaskGraph<'p> {
                 run_environment,
                 args: Some(ArgValues::FreeFormArgs(additional_args)),
                 dependencies: vec![],
+                skipped: false,
             },
-            None,
+            false,
         )
     }
 
     /// Constructs a new instance of a [`TaskGraph`] from a root task.
+    ///
+    /// With `skip_deps` the dependencies of the root task, or of the tasks it
+    /// expands to if it is an alias, are added as skipped tasks. They are
+    /// looked up in the environment of the task that depends on them, tasks
+    /// that are not found are left out, and their own dependencies are not
+    /// resolved at all.
     fn from_root<D: TaskDisambiguation<'p>>(
         project: &'p Workspace,
         search_environments: &SearchEnvironments<'p, D>,
         root: TaskNode<'p>,
-        root_args: Option<Vec<String>>,
+        skip_deps: bool,
     ) -> Result<Self, TaskGraphError> {
-        let mut task_name_with_args_to_node: HashMap<TypedDependency, TaskId> =
-            HashMap::from_iter(root.name.clone().into_iter().map(|name| {
-                (
-                    TypedDependency {
-                        task_name: name,
-                        args: root_args.clone(),
-                        environment: None,
-                    },
-                    TaskId(0),
-                )
-            }));
+        // The nodes by the dependency as it is written, to not look up the
+        // same dependency again, and by the task, environment and rendered
+        // arguments they run with, so that different dependencies that resolve
+        // to the same task share a node.
+        let mut task_name_with_args_to_node: HashMap<TypedDependency, TaskId> = HashMap::new();
+        let mut key_to_node: HashMap<NodeKey, TaskId> =
+            HashMap::from_iter(root.key().map(|key| (key, TaskId(0))));
         let mut nodes = vec![root];
 
         // Iterate over all the nodes in the graph and add them to the graph.
         let mut next_node_to_visit = 0;
         while next_node_to_visit < nodes.len() {
             let node = &nodes[next_node_to_visit];
+            if node.skipped {
+                next_node_to_visit += 1;
+                continue;
+            }
+            let skip_dependencies = skip_deps && node.task.is_executable();
             let dependencies = Vec::from_iter(node.task.depends_on().iter().cloned());
 
-            // Collect all dependency data before modifying nodes
-            let mut deps_to_process: Vec<(TypedDependency, Environment<'p>, &Task)> = Vec::new();
+            // Collect all dependency data before modifying nodes, together with
+            // whether the dependency is skipped.
+            let mut deps_to_process: Vec<(TypedDependency, Environment<'p>, &Task, bool)> =
+                Vec::new();
 
             // Iterate over all the dependencies of the node and add them to the graph.
             let mut node_dependencies = Vec::with_capacity(dependencies.len());
@@ -340,10 +367,35 @@ impl<'p> TaskGraph<'p> {
                     Cow::Owned(_) => unreachable!("only named tasks can have dependencies"),
                 };
 
-                let task_specific_environment = dependency
-                    .environment
-                    .clone()
-                    .and_then(|environment| project.environment(&environment));
+                if skip_dependencies {
+                    let environment = match &dependency.environment {
+                        Some(environment) => project.environment(environment),
+                        None => Some(node.run_environment.clone()),
+                    };
+                    let Some((environment, task)) = environment.and_then(|environment| {
+                        let task = environment
+                            .task(&dependency.task_name, search_environments.platform)
+                            .ok()?;
+                        Some((environment, task))
+                    }) else {
+                        continue;
+                    };
+                    deps_to_process.push((dependency, environment, task, true));
+                    continue;
+                }
+
+                let task_specific_environment = match &dependency.environment {
+                    Some(environment) => {
+                        Some(project.environment(environment).ok_or_else(|| {
+                            TaskGraphError::UnknownEnvironment(
+                                environment.to_string(),
+                                dependency.task_name.to_string(),
+                                node_name.to_string(),
+                            )
+                        })?)
+                    }
+                    None => None,
+                };
 
                 let (task_env, task_dependency) = match search_environments.find_task(
                     dependency.task_name.clone(),
@@ -360,24 +412,46 @@ impl<'p> TaskGraph<'p> {
                 };
 
                 // Store the dependency data for processing later
-                deps_to_process.push((dependency, task_env, task_dependency));
+                deps_to_process.push((dependency, task_env, task_dependency, false));
             }
 
             // Process all dependencies after collecting them
-            for (dependency, task_env, task_dependency) in deps_to_process {
-                // Add the node to the graph
-                let task_id = TaskId(nodes.len());
-                nodes.push(TaskNode {
+            for (dependency, task_env, task_dependency, skipped) in deps_to_process {
+                // The arguments of a skipped task are only shown, so they
+                // don't have to be valid.
+                let args = if skipped {
+                    Self::dependency_args(&dependency, task_dependency).ok()
+                } else {
+                    Some(Self::dependency_args(&dependency, task_dependency)?)
+                };
+                let node = TaskNode {
                     name: Some(dependency.task_name.clone()),
                     task: Cow::Borrowed(task_dependency),
                     run_environment: task_env,
-                    args: Some(Self::merge_args(
-                        &dependency.task_name,
-                        task_dependency.args().map(|args| args.to_vec()).as_ref(),
-                        dependency.args.as_ref(),
-                    )?),
+                    args,
                     dependencies: Vec::new(),
-                });
+                    skipped,
+                };
+
+                // Reuse the node of the same task if another dependency already
+                // added it, otherwise add the node to the graph. A task that an
+                // alias expands to runs, even if it is also skipped as the
+                // dependency of another task.
+                let key = node.key().expect("dependencies are named tasks");
+                let task_id = match key_to_node.get(&key) {
+                    Some(&task_id) => {
+                        if !skipped {
+                            nodes[task_id.0].skipped = false;
+                        }
+                        task_id
+                    }
+                    None => {
+                        let task_id = TaskId(nodes.len());
+                        nodes.push(node);
+                        key_to_node.insert(key, task_id);
+                        task_id
+                    }
+                };
 
                 // Store the task id in the map to be able to look up the name later
                 task_name_with_args_to_node.insert(dependency.clone(), task_id);
@@ -394,7 +468,138 @@ impl<'p> TaskGraph<'p> {
             next_node_to_visit += 1;
         }
 
-        Ok(Self { project, nodes })
+        let graph = Self { project, nodes };
+        if let Some(cycle) = graph.find_cycle() {
+            return Err(TaskGraphError::Cycle(graph.cycle_error(&cycle)));
+        }
+        Ok(graph)
+    }
+
+    /// Returns the tasks of a cycle in the graph, if there is one. The first
+    /// task of the cycle is repeated at the end.
+    fn find_cycle(&self) -> Option<Vec<TaskId>> {
+        let mut finished = HashSet::new();
+        let mut path = Vec::new();
+        for i in 0..self.nodes.len() {
+            if let Some(cycle) = visit(TaskId(i), &self.nodes, &mut path, &mut finished) {
+                return Some(cycle);
+            }
+        }
+        return None;
+
+        fn visit(
+            id: TaskId,
+            nodes: &[TaskNode<'_>],
+            path: &mut Vec<TaskId>,
+            finished: &mut HashSet<TaskId>,
+        ) -> Option<Vec<TaskId>> {
+            if finished.contains(&id) {
+                return None;
+            }
+            if let Some(start) = path.iter().position(|&task| task == id) {
+                let mut cycle = path[start..].to_vec();
+                cycle.push(id);
+                return Some(cycle);
+            }
+
+            path.push(id);
+            for dependency in nodes[id.0].dependencies.iter() {
+                if let Some(cycle) = visit(dependency.task_id(), nodes, path, finished) {
+                    return Some(cycle);
+                }
+            }
+            path.pop();
+            finished.insert(id);
+            None
+        }
+    }
+
+    /// Constructs the error for a cycle, with labels at the `depends-on`
+    /// entries of the manifest that form it.
+    fn cycle_error(&self, cycle: &[TaskId]) -> TaskCycleError {
+        let cycle = cycle
+            .iter()
+            .map(|id| {
+                self.nodes[id.0]
+                    .name
+                    .clone()
+                    .expect("only named tasks can have dependencies")
+            })
+            .collect_vec();
+
+        let provenance = &self.project.workspace.provenance;
+        let Ok(source) = provenance.read().map(|source| source.into_inner()) else {
+            return TaskCycleError {
+                cycle,
+                source_code: None,
+                labels: Vec::new(),
+            };
+        };
+
+        let labels = toml_span::parse(&source)
+            .map(|root| {
+                cycle
+                    .iter()
+                    .tuple_windows()
+                    .flat_map(|(task, dependency)| {
+                        let mut spans = Vec::new();
+                        depends_on_spans(&root, task, dependency, &mut spans);
+                        spans.into_iter().map(move |span| {
+                            LabeledSpan::new_with_span(
+                                Some(format!("'{task}' depends on '{dependency}'")),
+                                span,
+                            )
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        TaskCycleError {
+            cycle,
+            source_code: Some(NamedSource::new(
+                provenance.path.to_string_lossy(),
+                Arc::from(source),
+            )),
+            labels,
+        }
+    }
+
+    /// Returns the tasks that are skipped, in topological order.
+    pub fn skipped_tasks(&self) -> Vec<TaskId> {
+        self.topological_order()
+            .into_iter()
+            .filter(|id| self.nodes[id.0].skipped)
+            .collect()
+    }
+
+    /// Returns the argument values that a task runs with as a dependency. The
+    /// arguments of the dependency fill in the `args` of the task, or are
+    /// passed verbatim to a task that doesn't define `args`, like the
+    /// arguments on the command line.
+    fn dependency_args(
+        dependency: &TypedDependency,
+        task: &Task,
+    ) -> Result<ArgValues, TaskGraphError> {
+        let Some(task_arguments) = task.args() else {
+            return Ok(ArgValues::FreeFormArgs(
+                dependency.args.clone().unwrap_or_default(),
+            ));
+        };
+        if dependency
+            .args
+            .as_ref()
+            .is_some_and(|args| args.len() > task_arguments.len())
+        {
+            return Err(TaskGraphError::TooManyArguments(
+                dependency.task_name.to_string(),
+            ));
+        }
+        Self::merge_args(
+            &dependency.task_name,
+            Some(&task_arguments.to_vec()),
+            dependency.args.as_ref(),
+        )
     }
 
     fn merge_args(
@@ -484,12 +689,79 @@ pub enum TaskGraphError {
     #[error("task '{0}' received more arguments than expected")]
     TooManyArguments(String),
 
+    #[error("the environment '{0}' of the dependency '{1}' of task '{2}' does not exist")]
+    UnknownEnvironment(String, String, String),
+
     #[error("no value provided for argument '{0}' for task '{1}'")]
     MissingArgument(String, String),
 
     #[error(transparent)]
     #[diagnostic(transparent)]
     TemplateStringError(#[from] TemplateStringError),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Cycle(TaskCycleError),
+}
+
+/// Finds the spans of the `depends-on` entries of `task` that refer to
+/// `dependency` in every `tasks` table of the manifest, including the ones of
+/// features and targets.
+fn depends_on_spans(
+    value: &toml_span::Value<'_>,
+    task: &TaskName,
+    dependency: &TaskName,
+    spans: &mut Vec<std::ops::Range<usize>>,
+) {
+    let Some(table) = value.as_table() else {
+        return;
+    };
+    for (key, value) in table.iter() {
+        if key.name != "tasks" {
+            depends_on_spans(value, task, dependency, spans);
+            continue;
+        }
+        let Some(definition) = value
+            .as_table()
+            .and_then(|tasks| tasks.iter().find(|(name, _)| name.name == task.as_str()))
+            .map(|(_, definition)| definition)
+        else {
+            continue;
+        };
+
+        // An alias is an array of its dependencies.
+        let entries = if let Some(entries) = definition.as_array() {
+            Some(entries)
+        } else {
+            definition
+                .as_table()
+                .and_then(|definition| {
+                    definition
+                        .iter()
+                        .find(|(key, _)| key.name == "depends-on" || key.name == "depends_on")
+                })
+                .and_then(|(_, depends_on)| {
+                    if depends_on.as_str() == Some(dependency.as_str()) {
+                        spans.push(depends_on.span.start..depends_on.span.end);
+                    }
+                    depends_on.as_array()
+                })
+        };
+
+        for entry in entries.into_iter().flatten() {
+            let name = entry.as_str().or_else(|| {
+                entry.as_table().and_then(|entry| {
+                    entry
+                        .iter()
+                        .find(|(key, _)| key.name == "task")
+                        .and_then(|(_, name)| name.as_str())
+                })
+            });
+            if name == Some(dependency.as_str()) {
+                spans.push(entry.span.start..entry.span.end);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -501,7 +773,10 @@ mod test {
 
     use crate::{
         Workspace,
-        task::{task_environment::SearchEnvironments, task_graph::TaskGraph},
+        task::{
+            task_environment::SearchEnvironments,
+            task_graph::{TaskGraph, TaskGraphError},
+        },
     };
 
     fn commands_in_order(
@@ -528,6 +803,7 @@ mod test {
             .topological_order()
             .into_iter()
             .map(|task| &graph[task])
+            .filter(|task| !task.skipped)
             .filter_map(|task| task.full_command().ok().flatten())
             .collect()
     }
@@ -556,8 +832,23 @@ mod test {
         );
     }
 
+    fn cycle_in(project_str: &str, run_args: &[&str]) -> String {
+        let project = Workspace::from_str(Path::new("pixi.toml"), project_str).unwrap();
+        let search_envs = SearchEnvironments::from_opt_env(&project, None, None);
+
+        let err = TaskGraph::from_cmd_args(
+            &project,
+            &search_envs,
+            run_args.iter().map(|arg| arg.to_string()).collect(),
+            false,
+        )
+        .unwrap_err();
+        assert!(matches!(err, TaskGraphError::Cycle(_)), "{err:?}");
+        err.to_string()
+    }
+
     #[test]
-    fn test_cycle_ordered_commands() {
+    fn test_shared_dependency() {
         assert_eq!(
             commands_in_order(
                 r#"
@@ -566,17 +857,193 @@ mod test {
         channels = []
         platforms = ["linux-64", "osx-64", "win-64", "osx-arm64"]
         [tasks]
+        gen = { cmd = "echo gen {{ target }}", args = [{ arg = "target", default = "all" }] }
+        test = { cmd = "echo test", depends-on = ["gen"] }
+        lint = { cmd = "echo lint", depends-on = [{ task = "gen", args = ["all"] }] }
+        docs = { cmd = "echo docs", depends-on = [{ task = "gen", args = ["docs"] }] }
+        ci = { depends-on = ["test", "lint", "docs"] }
+    "#,
+                &["ci"],
+                None,
+                None,
+                false
+            ),
+            vec![
+                "echo gen all",
+                "echo test",
+                "echo lint",
+                "echo gen docs",
+                "echo docs"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dependency_args() {
+        let tasks = r#"
+        build = "cargo build"
+        package = { cmd = "echo package {{ target }}", args = ["target"] }
+        release = { depends-on = [{ task = "build", args = ["--profile", "production"] }, { task = "package", args = ["wheel"] }] }
+        nightly = { depends-on = [{ task = "build", args = ["--profile", "nightly"] }] }
+        all = { depends-on = ["release", "nightly"] }
+        broken = { depends-on = [{ task = "package", args = ["wheel", "sdist"] }] }
+    "#;
+        let expected = vec![
+            "cargo build '--profile' 'production'",
+            "echo package wheel",
+            "cargo build '--profile' 'nightly'",
+        ];
+
+        let pixi_toml = format!(
+            r#"
+        [workspace]
+        name = "pixi"
+        channels = []
+        platforms = ["linux-64", "osx-64", "win-64", "osx-arm64"]
+        [tasks]
+        {tasks}"#
+        );
+        assert_eq!(
+            commands_in_order(&pixi_toml, &["all"], None, None, false),
+            expected
+        );
+
+        let pyproject_toml = format!(
+            r#"
+        [project]
+        name = "pixi"
+        version = "0.1.0"
+        [tool.pixi.workspace]
+        channels = []
+        platforms = ["linux-64", "osx-64", "win-64", "osx-arm64"]
+        [tool.pixi.tasks]
+        {tasks}"#
+        );
+        let project = Workspace::from_str(Path::new("pyproject.toml"), &pyproject_toml).unwrap();
+        let search_envs = SearchEnvironments::from_opt_env(&project, None, None);
+        let graph =
+            TaskGraph::from_cmd_args(&project, &search_envs, vec!["all".to_string()], false)
+                .unwrap();
+        let commands: Vec<String> = graph
+            .topological_order()
+            .into_iter()
+            .filter_map(|task| graph[task].full_command().ok().flatten())
+            .collect();
+        assert_eq!(commands, expected);
+
+        let err =
+            TaskGraph::from_cmd_args(&project, &search_envs, vec!["broken".to_string()], false)
+                .unwrap_err();
+        assert!(matches!(err, TaskGraphError::TooManyArguments(task) if task == "package"));
+    }
+
+    #[test]
+    fn test_dependency_environment() {
+        let project = Workspace::from_str(
+            Path::new("pixi.toml"),
+            r#"
+        [workspace]
+        name = "pixi"
+        channels = []
+        platforms = ["linux-64", "osx-64", "win-64", "osx-arm64"]
+        [tasks]
+        build = "echo build"
+        deploy = { cmd = "echo deploy", depends-on = [{ task = "build", environment = "prod" }] }
+        broken = { cmd = "echo broken", depends-on = [{ task = "build", environment = "staging" }] }
+        [feature.prod.dependencies]
+        python = "*"
+        [environments]
+        prod = ["prod"]
+    "#,
+        )
+        .unwrap();
+        let search_envs = SearchEnvironments::from_opt_env(&project, None, None);
+        let graph =
+            TaskGraph::from_cmd_args(&project, &search_envs, vec!["deploy".to_string()], false)
+                .unwrap();
+        let environments: Vec<(String, String)> = graph
+            .topological_order()
+            .into_iter()
+            .map(|task| {
+                (
+                    graph[task].name.as_ref().unwrap().to_string(),
+                    graph[task].run_environment.name().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            environments,
+            vec![
+                ("build".to_string(), "prod".to_string()),
+                ("deploy".to_string(), "default".to_string())
+            ]
+        );
+
+        let err =
+            TaskGraph::from_cmd_args(&project, &search_envs, vec!["broken".to_string()], false)
+                .unwrap_err();
+        assert!(matches!(
+            err,
+            TaskGraphError::UnknownEnvironment(environment, ..) if environment == "staging"
+        ));
+    }
+
+    #[test]
+    fn test_cycle_ordered_commands() {
+        assert_eq!(
+            cycle_in(
+                r#"
+        [project]
+        name = "pixi"
+        channels = []
+        platforms = ["linux-64", "osx-64", "win-64", "osx-arm64"]
+        [tasks]
         root = {cmd="echo root", depends-on=["task1"]}
         task1 = {cmd="echo task1", depends-on=["root"]}
         task2 = {cmd="echo task2", depends-on=["root"]}
         top = {cmd="echo top", depends-on=["task1","task2"]}
     "#,
                 &["top"],
-                None,
-                None,
-                false
             ),
-            vec!["echo root", "echo task1", "echo task2", "echo top"]
+            "the tasks depend on each other in a cycle: task1 → root → task1"
+        );
+    }
+
+    #[test]
+    fn test_self_dependency_cycle() {
+        assert_eq!(
+            cycle_in(
+                r#"
+        [project]
+        name = "pixi"
+        channels = []
+        platforms = ["linux-64", "osx-64", "win-64", "osx-arm64"]
+        [tasks]
+        build = {cmd="echo build", depends-on=["build"]}
+    "#,
+                &["build"],
+            ),
+            "the tasks depend on each other in a cycle: build → build"
+        );
+    }
+
+    #[test]
+    fn test_alias_cycle() {
+        assert_eq!(
+            cycle_in(
+                r#"
+        [project]
+        name = "pixi"
+        channels = []
+        platforms = ["linux-64", "osx-64", "win-64", "osx-arm64"]
+        [tasks]
+        build = {cmd="echo build", depends-on=["all"]}
+        codegen = "echo codegen"
+        all = [{task="codegen"}, {task="build"}]
+    "#,
+                &["build"],
+            ),
+            "the tasks depend on each other in a cycle: build → all → build"
         );
     }
 
@@ -595,7 +1062,7 @@ mod test {
         task2 = {cmd="echo task2", depends-on=["root"]}
         top = {cmd="echo top", depends-on=["task1","task2"]}
         [target.linux-64.tasks]
-        root = {cmd="echo linux", depends-on=["task1"]}
+        root = {cmd="echo linux"}
     "#,
                 &["top"],
                 Some(Platform::Linux64),
@@ -790,4 +1257,82 @@ mod test {
             vec!["echo foo", "echo bar"]
         );
     }
+
+    #[test]
+    fn test_skip_deps_of_alias() {
+        let project = r#"
+        [project]
+        name = "pixi"
+        channels = []
+        platforms = ["linux-64", "osx-64", "win-64", "osx-arm64", "linux-riscv64"]
+
+        [tasks]
+        build = "echo build"
+        test = { cmd = "echo test", depends-on = ["build"] }
+        lint = { cmd = "echo lint", depends-on = ["build"] }
+        check = { depends-on = ["test", "lint"] }
+    "#;
+        assert_eq!(
+            commands_in_order(project, &["check"], None, None, true),
+            vec!["echo test", "echo lint"]
+        );
+
+        let workspace = Workspace::from_str(Path::new("pixi.toml"), project).unwrap();
+        let search_envs = SearchEnvironments::from_opt_env(&workspace, None, None);
+        let graph =
+            TaskGraph::from_cmd_args(&workspace, &search_envs, vec!["check".to_string()], true)
+                .unwrap();
+        let skipped = graph
+            .skipped_tasks()
+            .into_iter()
+            .map(|id| graph[id].name.as_ref().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(skipped, vec!["build"]);
+    }
+
+    #[test]
+    fn test_skip_deps_does_not_resolve_dependencies() {
+        let project = r#"
+        [project]
+        name = "pixi"
+        channels = []
+        platforms = ["linux-64", "osx-64", "win-64", "osx-arm64", "linux-riscv64"]
+
+        [tasks]
+        setup = { cmd = "echo setup", depends-on = ["missing"] }
+        build = { cmd = "echo build", depends-on = ["setup", "lint"] }
+
+        [feature.a.tasks]
+        lint = "echo lint a"
+
+        [feature.b.tasks]
+        lint = "echo lint b"
+
+        [environments]
+        a = ["a"]
+        b = ["b"]
+    "#;
+        let workspace = Workspace::from_str(Path::new("pixi.toml"), project).unwrap();
+        let search_envs = SearchEnvironments::from_opt_env(&workspace, None, None);
+
+        // The missing and ambiguous dependencies only fail the full graph
+        assert!(
+            TaskGraph::from_cmd_args(&workspace, &search_envs, vec!["build".to_string()], false)
+                .is_err()
+        );
+
+        let graph =
+            TaskGraph::from_cmd_args(&workspace, &search_envs, vec!["build".to_string()], true)
+                .unwrap();
+        let skipped = graph
+            .skipped_tasks()
+            .into_iter()
+            .map(|id| graph[id].name.as_ref().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(skipped, vec!["setup"]);
+        assert_eq!(
+            commands_in_order(project, &["build"], None, None, true),
+            vec!["echo build"]
+        );
+    }
 }
@@ -1,12 +1,13 @@
 use crate::task::{ExecutableTask, FileHashes, FileHashesError, InvalidWorkingDirectory};
 use crate::workspace;
 use miette::Diagnostic;
-use pixi_manifest::task::TemplateStringError;
+use pixi_manifest::task::{TaskShell, TemplateStringError};
 use rattler_lock::LockFile;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
 use thiserror::Error;
 use xxhash_rust::xxh3::Xxh3;
 
@@ -126,8 +127,12 @@ impl Display for EnvironmentHash {
 pub struct TaskHash {
     pub environment: EnvironmentHash,
     pub command: Option<String>,
+    pub shell: TaskShell,
     pub inputs: Option<InputHashes>,
     pub outputs: Option<OutputHashes>,
+    /// The `.env` files of the environment and the task with their content,
+    /// which is `None` for files that don't exist.
+    pub env_files: Vec<(PathBuf, Option<String>)>,
 }
 
 impl TaskHash {
@@ -136,17 +141,19 @@ impl TaskHash {
         task: &ExecutableTask<'_>,
         lock_file: &LockFile,
     ) -> Result<Option<Self>, InputHashesError> {
-        let input_hashes = InputHashes::from_task(task).await?;
-        let output_hashes = OutputHashes::from_task(task, false).await?;
-
-        if input_hashes.is_none() && output_hashes.is_none() {
+        // Tasks without inputs always run, the outputs alone can't tell
+        // whether the task has to run again.
+        let Some(input_hashes) = InputHashes::from_task(task).await? else {
             return Ok(None);
-        }
+        };
+        let output_hashes = OutputHashes::from_task(task, false).await?;
 
         Ok(Some(Self {
             command: task.full_command().ok().flatten(),
+            shell: task.shell(),
             outputs: output_hashes,
-            inputs: input_hashes,
+            inputs: Some(input_hashes),
+            env_files: env_file_contents(task),
             // Skipping environment variables used for caching the task
             environment: EnvironmentHash::from_environment(
                 &task.run_environment,
@@ -168,9 +175,19 @@ impl TaskHash {
     pub fn computation_hash(&self) -> ComputationHash {
         let mut hasher = Xxh3::new();
         self.command.hash(&mut hasher);
+        // The built-in shell isn't hashed, so that the caches from before
+        // tasks could choose their shell stay valid.
+        if self.shell != TaskShell::Deno {
+            self.shell.hash(&mut hasher);
+        }
         self.inputs.hash(&mut hasher);
         self.outputs.hash(&mut hasher);
         self.environment.hash(&mut hasher);
+        // Only hashed when there are any, so that the caches of tasks without
+        // `.env` files stay valid.
+        if !self.env_files.is_empty() {
+            self.env_files.hash(&mut hasher);
+        }
         ComputationHash(format!("{:x}", hasher.finish()))
     }
 
@@ -201,6 +218,21 @@ impl TaskHash {
     }
 }
 
+/// Reads the `.env` files that are loaded into the environment of the task,
+/// those of its environment first.
+fn env_file_contents(task: &ExecutableTask<'_>) -> Vec<(PathBuf, Option<String>)> {
+    let platform = Some(task.run_environment.best_platform());
+    task.run_environment
+        .env_files(platform)
+        .iter()
+        .chain(task.task().env_files().into_iter().flatten())
+        .map(|file| {
+            let content = fs_err::read_to_string(task.project().root().join(&file.path)).ok();
+            (file.path.clone(), content)
+        })
+        .collect()
+}
+
 /// The combination of all the hashes of the inputs of a task.
 #[derive(Debug, Hash)]
 pub struct InputHashes {
@@ -298,6 +330,26 @@ impl OutputHashes {
 
         Ok(Some(Self { files }))
     }
+
+    /// Returns true if every output glob of the task matches at least one
+    /// file.
+    pub async fn all_exist(task: &ExecutableTask<'_>) -> Result<bool, InputHashesError> {
+        let Ok(execute) = task.task().as_execute() else {
+            return Ok(true);
+        };
+        let Some(outputs) = &execute.outputs else {
+            return Ok(true);
+        };
+
+        for output in outputs.iter() {
+            let rendered = output.render(Some(task.args()))?;
+            let files = FileHashes::from_files(task.project().root(), [rendered]).await?;
+            if files.files.is_empty() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
 }
 
 /// An error that might occur when computing the input hashes of a task.
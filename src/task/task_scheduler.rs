@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+
+use super::task_graph::{TaskGraph, TaskId};
+
+/// Decides which tasks of a [`TaskGraph`] can be started. A task is ready as
+/// soon as all the tasks it depends on have finished, so tasks that don't
+/// depend on each other can run at the same time.
+///
+/// Ready tasks are handed out in the topological order of the graph, running
+/// one task at a time therefore runs the tasks in exactly that order.
+#[derive(Debug)]
+pub struct TaskScheduler {
+    /// The tasks that have not been started yet, in topological order.
+    pending: Vec<TaskId>,
+
+    /// The dependencies of every task that have not finished yet.
+    unfinished_dependencies: HashMap<TaskId, HashSet<TaskId>>,
+
+    /// The tasks that transitively depend on every task.
+    ancestors: HashMap<TaskId, HashSet<TaskId>>,
+}
+
+impl TaskScheduler {
+    /// Constructs a scheduler for all the tasks in the graph.
+    pub fn new(task_graph: &TaskGraph<'_>) -> Self {
+        let order = task_graph.topological_order();
+        let position: HashMap<TaskId, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(idx, id)| (*id, idx))
+            .collect();
+
+        // Only the dependencies that come earlier in the topological order are
+        // waited for, which breaks cycles the same way the order does.
+        let mut unfinished_dependencies = HashMap::new();
+        let mut ancestors: HashMap<TaskId, HashSet<TaskId>> = HashMap::new();
+        for (idx, id) in order.iter().enumerate() {
+            let dependencies: HashSet<TaskId> = task_graph[*id]
+                .dependencies
+                .iter()
+                .map(|dependency| dependency.task_id())
+                .filter(|dependency| position[dependency] < idx)
+                .collect();
+            let mut transitive = HashSet::new();
+            for dependency in &dependencies {
+                transitive.insert(*dependency);
+                transitive.extend(ancestors[dependency].iter().copied());
+            }
+            ancestors.insert(*id, transitive);
+            unfinished_dependencies.insert(*id, dependencies);
+        }
+
+        Self {
+            pending: order,
+            unfinished_dependencies,
+            ancestors,
+        }
+    }
+
+    /// Returns the next task that can be started, if any, and marks it as
+    /// started.
+    pub fn next_ready(&mut self) -> Option<TaskId> {
+        let idx = self
+            .pending
+            .iter()
+            .position(|id| self.unfinished_dependencies[id].is_empty())?;
+        Some(self.pending.remove(idx))
+    }
+
+    /// Marks a started task as finished, which may make the tasks that depend
+    /// on it ready.
+    pub fn finish(&mut self, task: TaskId) {
+        for dependencies in self.unfinished_dependencies.values_mut() {
+            dependencies.remove(&task);
+        }
+    }
+
+    /// Drops all the tasks that have not been started yet.
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Drops the tasks that have not been started yet and that depend on the
+    /// task, directly or indirectly. Returns the dropped tasks in topological
+    /// order.
+    pub fn skip_dependents(&mut self, task: TaskId) -> Vec<TaskId> {
+        let (skipped, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|id| self.ancestors[id].contains(&task));
+        self.pending = pending;
+        skipped
+    }
+
+    /// Returns true if there are tasks that have not been started yet.
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Returns the tasks that have not been started yet, in topological
+    /// order.
+    pub fn pending(&self) -> impl Iterator<Item = TaskId> + '_ {
+        self.pending.iter().copied()
+    }
+
+    /// Returns true if the task may run at the same time as any of the other
+    /// tasks, because neither of them depends on the other.
+    pub fn may_overlap(&self, task: TaskId, others: impl IntoIterator<Item = TaskId>) -> bool {
+        others.into_iter().any(|other| {
+            other != task
+                && !self.ancestors[&task].contains(&other)
+                && !self.ancestors[&other].contains(&task)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::TaskScheduler;
+    use crate::{
+        Workspace,
+        task::{task_environment::SearchEnvironments, task_graph::TaskGraph},
+    };
+
+    const WORKSPACE: &str = r#"
+        [project]
+        name = "pixi"
+        channels = []
+        platforms = ["linux-64", "osx-64", "win-64", "osx-arm64"]
+        [tasks]
+        root = "echo root"
+        build-frontend = {cmd="echo frontend", depends-on=["root"]}
+        build-backend = {cmd="echo backend", depends-on=["root"]}
+        build = {cmd="echo build", depends-on=["build-frontend","build-backend"]}
+    "#;
+
+    fn name(graph: &TaskGraph<'_>, id: super::TaskId) -> String {
+        graph[id].name.as_ref().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_independent_tasks_are_ready_together() {
+        let project = Workspace::from_str(Path::new("pixi.toml"), WORKSPACE).unwrap();
+        let search_envs = SearchEnvironments::from_opt_env(&project, None, None);
+        let graph =
+            TaskGraph::from_cmd_args(&project, &search_envs, vec!["build".to_string()], false)
+                .unwrap();
+        let mut scheduler = TaskScheduler::new(&graph);
+
+        let root = scheduler.next_ready().unwrap();
+        assert_eq!(name(&graph, root), "root");
+        assert_eq!(scheduler.next_ready(), None);
+        scheduler.finish(root);
+
+        let first = scheduler.next_ready().unwrap();
+        let second = scheduler.next_ready().unwrap();
+        assert_eq!(
+            [name(&graph, first), name(&graph, second)],
+            ["build-frontend", "build-backend"]
+        );
+        assert!(scheduler.may_overlap(first, [root, second]));
+        assert!(!scheduler.may_overlap(root, [first, second]));
+        assert_eq!(scheduler.next_ready(), None);
+
+        scheduler.finish(first);
+        assert_eq!(scheduler.next_ready(), None);
+        scheduler.finish(second);
+        let build = scheduler.next_ready().unwrap();
+        assert_eq!(name(&graph, build), "build");
+        assert!(!scheduler.has_pending());
+    }
+
+    #[test]
+    fn test_cancel_drops_pending_tasks() {
+        let project = Workspace::from_str(Path::new("pixi.toml"), WORKSPACE).unwrap();
+        let search_envs = SearchEnvironments::from_opt_env(&project, None, None);
+        let graph =
+            TaskGraph::from_cmd_args(&project, &search_envs, vec!["build".to_string()], false)
+                .unwrap();
+        let mut scheduler = TaskScheduler::new(&graph);
+
+        let root = scheduler.next_ready().unwrap();
+        scheduler.cancel();
+        scheduler.finish(root);
+        assert_eq!(scheduler.next_ready(), None);
+        assert!(!scheduler.has_pending());
+    }
+
+    #[test]
+    fn test_skip_dependents_keeps_other_branches() {
+        let project = Workspace::from_str(Path::new("pixi.toml"), WORKSPACE).unwrap();
+        let search_envs = SearchEnvironments::from_opt_env(&project, None, None);
+        let graph =
+            TaskGraph::from_cmd_args(&project, &search_envs, vec!["build".to_string()], false)
+                .unwrap();
+        let mut scheduler = TaskScheduler::new(&graph);
+
+        let root = scheduler.next_ready().unwrap();
+        scheduler.finish(root);
+        let frontend = scheduler.next_ready().unwrap();
+        let backend = scheduler.next_ready().unwrap();
+
+        // The frontend failed, only the build that depends on it is skipped
+        let skipped = scheduler.skip_dependents(frontend);
+        assert_eq!(
+            skipped
+                .iter()
+                .map(|id| name(&graph, *id))
+                .collect::<Vec<_>>(),
+            ["build"]
+        );
+        scheduler.finish(frontend);
+        scheduler.finish(backend);
+        assert_eq!(scheduler.next_ready(), None);
+        assert!(!scheduler.has_pending());
+    }
+}
@@ -0,0 +1,415 @@
+//! Implements the watching of the files for `pixi run --watch`.
+//!
+//! The files are selected with the same globs as the `inputs` of a task. The
+//! file system notifies pixi when files change in the directories that the
+//! globs can match, after which the files are compared by their hashes, see
+//! [`FileHashes`], so that touching a file without changing it doesn't
+//! trigger a run.
+
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use pixi_consts::consts;
+use thiserror::Error;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+use super::{FileHashes, FileHashesError, TaskGraph};
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error(transparent)]
+    FileHashes(#[from] FileHashesError),
+
+    #[error("failed to watch the files for changes")]
+    Notify(#[from] notify::Error),
+}
+
+/// Watches directories for changes. A single watcher is used for a whole
+/// `pixi run --watch` session, the directories it watches follow the
+/// [`WatchedPaths`] of the current run.
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    events: UnboundedReceiver<()>,
+    watched: HashMap<PathBuf, RecursiveMode>,
+}
+
+impl FileWatcher {
+    /// Creates a watcher that ignores the changes in the `.pixi` and `.git`
+    /// directories of the workspace at `root`.
+    pub fn new(root: &Path) -> Result<Self, WatchError> {
+        let (sender, events) = unbounded_channel();
+        let ignored_dirs = [root.join(consts::PIXI_DIR), root.join(".git")];
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let relevant = event.map_or(true, |event| {
+                event
+                    .paths
+                    .iter()
+                    .any(|path| !ignored_dirs.iter().any(|dir| path.starts_with(dir)))
+            });
+            if relevant {
+                let _ = sender.send(());
+            }
+        })?;
+        Ok(Self {
+            watcher,
+            events,
+            watched: HashMap::new(),
+        })
+    }
+
+    /// Watches the directories that the globs of `paths` can match, and stops
+    /// watching the directories that they can't match anymore.
+    fn watch(&mut self, paths: &WatchedPaths) -> Result<(), WatchError> {
+        let directories = paths.watched_directories();
+        for (directory, mode) in std::mem::take(&mut self.watched) {
+            if directories.get(&directory) == Some(&mode) {
+                self.watched.insert(directory, mode);
+            } else {
+                // The directory may have been removed already.
+                let _ = self.watcher.unwatch(&directory);
+            }
+        }
+        for (directory, mode) in directories {
+            if let Entry::Vacant(entry) = self.watched.entry(directory) {
+                self.watcher.watch(entry.key(), mode)?;
+                entry.insert(mode);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The files that are watched for changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchedPaths {
+    /// The directory the globs are relative to.
+    pub root: PathBuf,
+
+    /// The globs of the files that re-run the tasks when they change.
+    pub globs: Vec<String>,
+
+    /// The globs of the files that also re-resolve the task graph and the
+    /// environment when they change, e.g. the manifest and the lock-file.
+    pub workspace_globs: Vec<String>,
+
+    /// The globs of the files that are not watched, the `outputs` of the
+    /// tasks, so that a task that writes its outputs doesn't run again.
+    pub ignored_globs: Vec<String>,
+}
+
+impl WatchedPaths {
+    /// Watches the `inputs` of the tasks in the task graphs that are not
+    /// skipped, or the `overrides` if there are any, except for the `outputs`
+    /// of the tasks. The globs are relative to `root`.
+    pub fn from_task_graphs<'a, 'p: 'a>(
+        task_graphs: impl IntoIterator<Item = &'a TaskGraph<'p>>,
+        root: &Path,
+        overrides: &[String],
+        workspace_globs: Vec<String>,
+    ) -> Self {
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        for task_graph in task_graphs {
+            for task_id in task_graph.topological_order() {
+                let node = &task_graph[task_id];
+                if node.skipped {
+                    continue;
+                }
+                let Ok(execute) = node.task.as_execute() else {
+                    continue;
+                };
+                for (globs, patterns) in [
+                    (&mut inputs, &execute.inputs),
+                    (&mut outputs, &execute.outputs),
+                ] {
+                    for pattern in patterns.iter().flat_map(|patterns| patterns.iter()) {
+                        match pattern.render(node.args.as_ref()) {
+                            Ok(glob) if !globs.contains(&glob) => globs.push(glob),
+                            Ok(_) => {}
+                            Err(err) => tracing::warn!("failed to render a glob of a task: {err}"),
+                        }
+                    }
+                }
+            }
+        }
+
+        Self {
+            root: root.to_path_buf(),
+            globs: if overrides.is_empty() {
+                inputs
+            } else {
+                overrides.to_vec()
+            },
+            workspace_globs,
+            ignored_globs: outputs,
+        }
+    }
+
+    /// Takes a snapshot of the hashes of the watched files.
+    pub async fn snapshot(&self) -> Result<WatchSnapshot, FileHashesError> {
+        Ok(WatchSnapshot {
+            files: self.hash_files(&self.globs).await?,
+            workspace_files: self.hash_files(&self.workspace_globs).await?,
+        })
+    }
+
+    /// Waits until the watched files differ from the `baseline` and didn't
+    /// change anymore for the `debounce` duration. Returns which files changed.
+    ///
+    /// The files are only hashed again when the `watcher` reports a change in
+    /// one of the directories that the globs can match.
+    pub async fn wait_for_change(
+        &self,
+        watcher: &mut FileWatcher,
+        baseline: &WatchSnapshot,
+        debounce: Duration,
+    ) -> Result<WatchChange, WatchError> {
+        watcher.watch(self)?;
+        let events = &mut watcher.events;
+
+        // The files may have changed before the directories were watched.
+        let mut previous = self.snapshot().await?;
+        while previous == *baseline {
+            if events.recv().await.is_none() {
+                // The watcher stopped, nothing changes anymore.
+                std::future::pending::<()>().await;
+            }
+            previous = self.snapshot().await?;
+        }
+
+        // Wait until there were no changes for the debounce duration, so that
+        // saving multiple files only results in a single run.
+        loop {
+            while let Ok(Some(())) = tokio::time::timeout(debounce, events.recv()).await {}
+            let snapshot = self.snapshot().await?;
+            if snapshot == previous {
+                break;
+            }
+            previous = snapshot;
+        }
+
+        Ok(if previous.workspace_files != baseline.workspace_files {
+            WatchChange::Workspace
+        } else {
+            WatchChange::Inputs
+        })
+    }
+
+    /// Returns the directories that contain the files the globs can match,
+    /// and whether their subdirectories are watched as well. A glob without
+    /// wildcards only watches the directory of its file. The `.pixi` and
+    /// `.git` directories of the root are never watched, so that the files of
+    /// the environments don't use up the watches of the system.
+    fn watched_directories(&self) -> HashMap<PathBuf, RecursiveMode> {
+        let mut directories = HashMap::new();
+        let mut insert =
+            |directory: PathBuf, mode: RecursiveMode| match directories.entry(directory) {
+                Entry::Vacant(entry) => {
+                    entry.insert(mode);
+                }
+                Entry::Occupied(mut entry) => {
+                    if mode == RecursiveMode::Recursive {
+                        entry.insert(mode);
+                    }
+                }
+            };
+
+        for glob in self.globs.iter().chain(&self.workspace_globs) {
+            if glob.starts_with('!') {
+                continue;
+            }
+            let components: Vec<&str> = glob.split('/').filter(|c| !c.is_empty()).collect();
+            let literal = components
+                .iter()
+                .take_while(|component| !component.contains(['*', '?', '[', '{']))
+                .count();
+            let base: PathBuf = std::iter::once(self.root.as_path())
+                .chain(components[..literal].iter().map(Path::new))
+                .collect();
+            let (mut directory, mut mode) = if literal == components.len() {
+                if base.is_dir() {
+                    (base, RecursiveMode::Recursive)
+                } else {
+                    let parent = base.parent().unwrap_or(&self.root).to_path_buf();
+                    (parent, RecursiveMode::NonRecursive)
+                }
+            } else if components.len() - literal > 1 || glob.contains("**") {
+                (base, RecursiveMode::Recursive)
+            } else {
+                (base, RecursiveMode::NonRecursive)
+            };
+
+            // A directory that doesn't exist yet shows up in its parent.
+            while !directory.is_dir() {
+                let Some(parent) = directory.parent() else {
+                    break;
+                };
+                directory = parent.to_path_buf();
+                mode = RecursiveMode::NonRecursive;
+            }
+
+            if mode == RecursiveMode::Recursive && is_same_directory(&directory, &self.root) {
+                let ignored = [consts::PIXI_DIR, ".git"];
+                let children = fs_err::read_dir(&directory).into_iter().flatten().flatten();
+                for child in children {
+                    let path = child.path();
+                    if path.is_dir() && !ignored.iter().any(|name| child.file_name() == *name) {
+                        insert(path, RecursiveMode::Recursive);
+                    }
+                }
+                mode = RecursiveMode::NonRecursive;
+            }
+            insert(directory, mode);
+        }
+        directories
+    }
+
+    /// Returns the hashes of the files that match the globs, except for the
+    /// ignored files.
+    async fn hash_files(
+        &self,
+        globs: &[String],
+    ) -> Result<HashMap<PathBuf, String>, FileHashesError> {
+        if globs.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let filters = globs
+            .iter()
+            .cloned()
+            .chain(self.ignored_globs.iter().map(|glob| format!("!{glob}")));
+        Ok(FileHashes::from_files(&self.root, filters).await?.files)
+    }
+}
+
+/// Returns true if both paths are the same directory, ignoring `.` components.
+fn is_same_directory(a: &Path, b: &Path) -> bool {
+    let normal = |path: &Path| {
+        path.components()
+            .filter(|component| *component != Component::CurDir)
+            .collect::<Vec<_>>()
+    };
+    normal(a) == normal(b)
+}
+
+/// The hashes of the watched files at a point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchSnapshot {
+    files: HashMap<PathBuf, String>,
+    workspace_files: HashMap<PathBuf, String>,
+}
+
+/// The kind of files that changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchChange {
+    /// The inputs of the tasks changed.
+    Inputs,
+    /// The manifest or the lock-file changed.
+    Workspace,
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use notify::RecursiveMode;
+
+    use super::{FileWatcher, WatchChange, WatchedPaths};
+
+    #[tokio::test]
+    async fn test_wait_for_change() {
+        let dir = tempfile::tempdir().unwrap();
+        fs_err::create_dir(dir.path().join("src")).unwrap();
+        fs_err::write(dir.path().join("src/main.py"), "print('hello')").unwrap();
+        fs_err::write(dir.path().join("pixi.toml"), "").unwrap();
+
+        let watched = WatchedPaths {
+            root: dir.path().to_path_buf(),
+            globs: vec!["src/*.py".to_string()],
+            workspace_globs: vec!["pixi.toml".to_string()],
+            ignored_globs: vec!["src/generated.py".to_string()],
+        };
+        let baseline = watched.snapshot().await.unwrap();
+        let mut watcher = FileWatcher::new(dir.path()).unwrap();
+
+        let path = dir.path().to_path_buf();
+        let write = |file: &'static str, content: &'static str| {
+            let path = path.join(file);
+            async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                fs_err::write(&path, content).unwrap();
+            }
+        };
+
+        let (change, _) = tokio::join!(
+            watched.wait_for_change(&mut watcher, &baseline, Duration::from_millis(20)),
+            write("src/main.py", "print('changed')")
+        );
+        assert_eq!(change.unwrap(), WatchChange::Inputs);
+
+        let baseline = watched.snapshot().await.unwrap();
+        let (change, _) = tokio::join!(
+            watched.wait_for_change(&mut watcher, &baseline, Duration::from_millis(20)),
+            write("pixi.toml", "[workspace]")
+        );
+        assert_eq!(change.unwrap(), WatchChange::Workspace);
+
+        // Writing an ignored file isn't a change
+        let baseline = watched.snapshot().await.unwrap();
+        fs_err::write(dir.path().join("src/generated.py"), "print('output')").unwrap();
+        assert_eq!(watched.snapshot().await.unwrap(), baseline);
+    }
+
+    #[test]
+    fn test_watched_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        for sub in ["src/nested", "docs", ".pixi/envs/default", ".git"] {
+            fs_err::create_dir_all(dir.path().join(sub)).unwrap();
+        }
+        let watched = |globs: &[&str]| {
+            WatchedPaths {
+                root: dir.path().to_path_buf(),
+                globs: globs.iter().map(|glob| glob.to_string()).collect(),
+                workspace_globs: vec!["pixi.toml".to_string()],
+                ignored_globs: Vec::new(),
+            }
+            .watched_directories()
+        };
+
+        // Only the directories of the files are watched
+        let directories = watched(&["src/*.py"]);
+        assert_eq!(directories.len(), 2);
+        assert_eq!(
+            directories[&dir.path().join("src")],
+            RecursiveMode::NonRecursive
+        );
+        assert_eq!(directories[dir.path()], RecursiveMode::NonRecursive);
+
+        let directories = watched(&["src/**/*.py", "missing/*.txt"]);
+        assert_eq!(
+            directories[&dir.path().join("src")],
+            RecursiveMode::Recursive
+        );
+        assert!(!directories.contains_key(&dir.path().join("missing")));
+
+        // The `.pixi` and `.git` directories are never watched
+        let directories = watched(&["**/*.py"]);
+        assert_eq!(directories[dir.path()], RecursiveMode::NonRecursive);
+        assert_eq!(
+            directories[&dir.path().join("src")],
+            RecursiveMode::Recursive
+        );
+        assert_eq!(
+            directories[&dir.path().join("docs")],
+            RecursiveMode::Recursive
+        );
+        assert!(
+            directories
+                .keys()
+                .all(|path| !path.ends_with(".pixi") && !path.ends_with(".git"))
+        );
+    }
+}
@@ -6,10 +6,10 @@ use std::{
 };
 
 use indexmap::IndexMap;
-use itertools::Either;
+use itertools::{Either, Itertools};
 use pixi_consts::consts;
 use pixi_manifest::{
-    self as manifest, EnvironmentName, Feature, FeatureName, FeaturesExt, HasFeaturesIter,
+    self as manifest, EnvFile, EnvironmentName, Feature, FeatureName, FeaturesExt, HasFeaturesIter,
     HasWorkspaceManifest, SystemRequirements, Task, TaskName, WorkspaceManifest,
 };
 use rattler_conda_types::{Arch, ChannelConfig, Platform};
@@ -228,6 +228,29 @@ impl<'p> Environment<'p> {
         }
     }
 
+    /// Returns the names of the tasks to run after the environment was
+    /// installed. The tasks of the features come first, followed by the ones
+    /// of the environment itself.
+    pub(crate) fn post_install_tasks(&self) -> Vec<&'p TaskName> {
+        self.features()
+            .flat_map(|feature| feature.post_install.iter())
+            .chain(self.environment.post_install.iter())
+            .unique()
+            .collect()
+    }
+
+    /// Returns the `.env` files to load into the environment of the tasks. The
+    /// files of the features come first, followed by the ones of the
+    /// environment itself.
+    pub(crate) fn env_files(&self, platform: Option<Platform>) -> Vec<EnvFile> {
+        self.features()
+            .filter_map(|feature| feature.activation_env_files(platform))
+            .flatten()
+            .chain(self.environment.env_files.iter())
+            .cloned()
+            .collect()
+    }
+
     /// Returns a map of all the features and their tasks for this environment.
     ///
     /// Resolves for the best platform target.
@@ -415,6 +438,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_post_install_tasks() {
+        let manifest = Workspace::from_str(
+            Path::new("pixi.toml"),
+            r#"
+        [project]
+        name = "foobar"
+        channels = []
+        platforms = ["linux-64"]
+        post-install = ["setup"]
+
+        [tasks]
+        setup = "echo setup"
+        weights = "echo weights"
+        hooks = "echo hooks"
+
+        [feature.foo]
+        post-install = ["weights", "setup"]
+
+        [environments]
+        foo = { features = ["foo"], post-install = ["hooks"] }
+        "#,
+        )
+        .unwrap();
+
+        let post_install_tasks = |name: &str| {
+            manifest
+                .environment(name)
+                .unwrap()
+                .post_install_tasks()
+                .into_iter()
+                .map(|task| task.as_str().to_string())
+                .collect_vec()
+        };
+        assert_eq!(post_install_tasks("default"), vec!["setup"]);
+        assert_eq!(post_install_tasks("foo"), vec!["weights", "setup", "hooks"]);
+    }
+
     #[test]
     fn test_default_tasks() {
         let manifest = Workspace::from_str(
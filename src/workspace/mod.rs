@@ -35,7 +35,7 @@ use pixi_consts::consts;
 use pixi_manifest::{
     AssociateProvenance, EnvironmentName, Environments, ExplicitManifestError,
     HasWorkspaceManifest, LoadManifestsError, ManifestProvenance, Manifests, PackageManifest,
-    SpecType, WithProvenance, WithWarnings, WorkspaceManifest,
+    SpecType, TaskName, WithProvenance, WithWarnings, WorkspaceManifest,
 };
 use pixi_pypi_spec::{PixiPypiSpec, PypiPackageName};
 use pixi_spec::SourceSpec;
@@ -426,6 +426,20 @@ impl Workspace {
             .collect()
     }
 
+    /// Returns the environments that define the task with the given name
+    /// for their best platform, either in their own features or in the
+    /// default feature that they inherit.
+    pub fn environments_with_task(&self, name: &TaskName) -> Vec<Environment> {
+        self.environments()
+            .into_iter()
+            .filter(|environment| {
+                environment
+                    .task(name, Some(environment.best_platform()))
+                    .is_ok()
+            })
+            .collect()
+    }
+
     /// Returns an environment in this project based on a name or an environment
     /// variable.
     pub fn environment_from_name_or_env_var(
@@ -463,11 +477,11 @@ impl Workspace {
             })
     }
 
-    // /// Returns the reqwest client used for http networking
-    // /// this api is not used now, uncomment when use in the future
-    // pub(crate) fn client(&self) -> miette::Result<&reqwest::Client> {
-    //     Ok(&self.client_and_authenticated_client()?.0)
-    // }
+    /// Returns the reqwest client used for http networking, without
+    /// authentication, mirrors or the offline guard.
+    pub(crate) fn client(&self) -> miette::Result<&reqwest::Client> {
+        Ok(&self.client_and_authenticated_client()?.0)
+    }
 
     /// Create an authenticated reqwest client for this project
     /// use authentication from `rattler_networking`
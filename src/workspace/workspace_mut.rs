@@ -13,8 +13,9 @@ use pep508_rs::{Requirement, VersionOrUrl::VersionSpecifier};
 use pixi_config::PinningStrategy;
 use pixi_manifest::{
     DependencyOverwriteBehavior, FeatureName, FeaturesExt, HasFeaturesIter, LoadManifestsError,
-    ManifestDocument, ManifestKind, PypiDependencyLocation, SpecType, TomlError, WorkspaceManifest,
-    WorkspaceManifestMut, toml::TomlDocument, utils::WithSourceCode,
+    ManifestDocument, ManifestKind, PackageManifest, PackageManifestMut, PypiDependencyLocation,
+    SpecType, TomlError, WorkspaceManifest, WorkspaceManifestMut, toml::TomlDocument,
+    utils::WithSourceCode,
 };
 use pixi_pypi_spec::{PixiPypiSpec, PypiPackageName};
 use pixi_spec::PixiSpec;
@@ -27,7 +28,10 @@ use crate::{
     cli::cli_config::{LockFileUpdateConfig, PrefixUpdateConfig},
     diff::LockFileDiff,
     environment::LockFileUsage,
-    lock_file::{LockFileDerivedData, ReinstallPackages, UpdateContext, UpdateMode},
+    lock_file::{
+        InstallFilter, LockFileDerivedData, ReinstallPackages, UpdateContext, UpdateMode,
+        VerifiedTargets,
+    },
     workspace::{
         MatchSpecs, NON_SEMVER_PACKAGES, PypiDeps, SourceSpecs, UpdateDeps,
         grouped_environment::GroupedEnvironment,
@@ -36,6 +40,7 @@ use crate::{
 
 struct OriginalContent {
     manifest: WorkspaceManifest,
+    package: Option<PackageManifest>,
     source: String,
 }
 
@@ -112,6 +117,10 @@ impl WorkspaceMut {
         Ok(Self {
             original: Some(OriginalContent {
                 manifest: workspace.workspace.value.clone(),
+                package: workspace
+                    .package
+                    .as_ref()
+                    .map(|package| package.value.clone()),
                 source: contents.clone(),
             }),
             modified: false,
@@ -177,6 +186,24 @@ impl WorkspaceMut {
         }
     }
 
+    /// Returns a [`PackageManifestMut`] which implements methods to modify the
+    /// package manifest both in memory and on-disk.
+    ///
+    /// Returns `None` if the workspace has no package or if the package is not
+    /// defined in the workspace manifest.
+    #[must_use]
+    pub fn package(&mut self) -> Option<PackageManifestMut<'_>> {
+        let workspace = self.workspace.as_mut().expect("workspace is not available");
+        let package = workspace.package.as_mut()?;
+        if package.provenance.path != workspace.workspace.provenance.path {
+            return None;
+        }
+        Some(PackageManifestMut {
+            package: &mut package.value,
+            document: &mut self.workspace_manifest_document,
+        })
+    }
+
     /// Returns a reference to the in-memory representation of the workspace.
     ///
     /// Any previous changes made to the workspace are reflected in the returned
@@ -215,6 +242,11 @@ impl WorkspaceMut {
         let mut workspace = self.workspace.take().expect("workspace is not available");
         if let Some(original) = self.original.take() {
             workspace.workspace.value = original.manifest;
+            if let (Some(package), Some(original_package)) =
+                (workspace.package.as_mut(), original.package)
+            {
+                package.value = original_package;
+            }
             fs_err::tokio::write(&workspace.workspace.provenance.path, original.source).await?;
         }
 
@@ -237,7 +269,7 @@ impl WorkspaceMut {
         source_specs: SourceSpecs,
         prefix_update_config: &PrefixUpdateConfig,
         lock_file_update_config: &LockFileUpdateConfig,
-        feature_name: &FeatureName,
+        features: &[FeatureName],
         platforms: &[Platform],
         editable: bool,
         dry_run: bool,
@@ -252,14 +284,17 @@ impl WorkspaceMut {
             let pixi_spec =
                 PixiSpec::from_nameless_matchspec(nameless_spec.clone(), &channel_config);
 
-            let added = self.manifest().add_dependency(
-                &name,
-                &pixi_spec,
-                spec_type,
-                platforms,
-                feature_name,
-                DependencyOverwriteBehavior::Overwrite,
-            )?;
+            let mut added = false;
+            for feature_name in features {
+                added |= self.manifest().add_dependency(
+                    &name,
+                    &pixi_spec,
+                    spec_type,
+                    platforms,
+                    feature_name,
+                    DependencyOverwriteBehavior::Overwrite,
+                )?;
+            }
             if added {
                 if nameless_spec.version.is_none() {
                     conda_specs_to_add_constraints_for
@@ -272,25 +307,30 @@ impl WorkspaceMut {
         for (name, (spec, spec_type)) in source_specs {
             let pixi_spec = PixiSpec::from(spec);
 
-            self.manifest().add_dependency(
-                &name,
-                &pixi_spec,
-                spec_type,
-                platforms,
-                feature_name,
-                DependencyOverwriteBehavior::Overwrite,
-            )?;
+            for feature_name in features {
+                self.manifest().add_dependency(
+                    &name,
+                    &pixi_spec,
+                    spec_type,
+                    platforms,
+                    feature_name,
+                    DependencyOverwriteBehavior::Overwrite,
+                )?;
+            }
         }
 
         for (name, (spec, pixi_spec, location)) in pypi_deps {
-            let added = self.manifest().add_pep508_dependency(
-                (&spec, pixi_spec.as_ref()),
-                platforms,
-                feature_name,
-                Some(editable),
-                DependencyOverwriteBehavior::Overwrite,
-                location.as_ref(),
-            )?;
+            let mut added = false;
+            for feature_name in features {
+                added |= self.manifest().add_pep508_dependency(
+                    (&spec, pixi_spec.as_ref()),
+                    platforms,
+                    feature_name,
+                    Some(editable),
+                    DependencyOverwriteBehavior::Overwrite,
+                    location.as_ref(),
+                )?;
+            }
             if added {
                 if spec.version_or_url.is_none() {
                     pypi_specs_to_add_constraints_for
@@ -316,8 +356,8 @@ impl WorkspaceMut {
             .workspace()
             .environments()
             .iter()
-            // Filter out any environment that does not contain the feature we modified
-            .filter(|e| e.features().any(|f| f.name == *feature_name))
+            // Filter out any environment that does not contain a feature we modified
+            .filter(|e| e.features().any(|f| features.contains(&f.name)))
             // Expand the selection to also included any environment that shares the same solve
             // group
             .flat_map(|e| {
@@ -361,6 +401,7 @@ impl WorkspaceMut {
             glob_hash_cache,
             io_concurrency_limit,
             was_outdated: _,
+            solved_targets,
         } = UpdateContext::builder(self.workspace())
             .with_lock_file(unlocked_lock_file)
             .with_no_install(
@@ -372,13 +413,19 @@ impl WorkspaceMut {
             .update()
             .await?;
 
+        // With the `no-pin` strategy the specs are kept exactly as they were passed.
+        if self.workspace().config().pinning_strategy == Some(PinningStrategy::NoPin) {
+            conda_specs_to_add_constraints_for.clear();
+            pypi_specs_to_add_constraints_for.clear();
+        }
+
         let mut implicit_constraints = HashMap::new();
         if !conda_specs_to_add_constraints_for.is_empty() {
             let conda_constraints = self.update_conda_specs_from_lock_file(
                 &lock_file,
                 conda_specs_to_add_constraints_for,
                 affect_environment_and_platforms.clone(),
-                feature_name,
+                features,
                 platforms,
             )?;
             implicit_constraints.extend(conda_constraints);
@@ -389,7 +436,7 @@ impl WorkspaceMut {
                 &lock_file,
                 pypi_specs_to_add_constraints_for,
                 affect_environment_and_platforms,
-                feature_name,
+                features,
                 platforms,
                 editable,
             )?;
@@ -414,21 +461,28 @@ impl WorkspaceMut {
             build_context,
             glob_hash_cache,
             was_outdated: true,
+            solved_targets,
         };
         if !lock_file_update_config.no_lockfile_update && !dry_run {
             updated_lock_file.write_to_disk()?;
+            VerifiedTargets::from_lock_file(self.workspace(), &updated_lock_file.lock_file)
+                .write(self.workspace());
         }
+        // With `--revalidate` the whole prefix is checked, also if the default
+        // environment didn't change.
         if !prefix_update_config.no_install
             && !lock_file_update_config.no_lockfile_update
             && !dry_run
             && self.workspace().environments().len() == 1
-            && default_environment_is_affected
+            && (default_environment_is_affected || prefix_update_config.revalidate)
         {
             updated_lock_file
                 .prefix(
                     &self.workspace().default_environment(),
+                    // The manifest changed, so the prefix is always revalidated
                     UpdateMode::Revalidate,
                     &ReinstallPackages::default(),
+                    &InstallFilter::default(),
                 )
                 .await?;
         }
@@ -449,7 +503,7 @@ impl WorkspaceMut {
         updated_lock_file: &LockFile,
         conda_specs_to_add_constraints_for: IndexMap<PackageName, (SpecType, NamelessMatchSpec)>,
         affect_environment_and_platforms: Vec<(String, Platform)>,
-        feature_name: &FeatureName,
+        features: &[FeatureName],
         platforms: &[Platform],
     ) -> miette::Result<HashMap<String, String>> {
         let mut implicit_constraints = HashMap::new();
@@ -501,14 +555,16 @@ impl WorkspaceMut {
 
                 let pixi_spec = PixiSpec::from_nameless_matchspec(spec.clone(), &channel_config);
 
-                self.manifest().add_dependency(
-                    &name,
-                    &pixi_spec,
-                    spec_type,
-                    platforms,
-                    feature_name,
-                    DependencyOverwriteBehavior::Overwrite,
-                )?;
+                for feature_name in features {
+                    self.manifest().add_dependency(
+                        &name,
+                        &pixi_spec,
+                        spec_type,
+                        platforms,
+                        feature_name,
+                        DependencyOverwriteBehavior::Overwrite,
+                    )?;
+                }
             }
         }
 
@@ -529,7 +585,7 @@ impl WorkspaceMut {
             ),
         >,
         affect_environment_and_platforms: Vec<(String, Platform)>,
-        feature_name: &FeatureName,
+        features: &[FeatureName],
         platforms: &[Platform],
         editable: bool,
     ) -> miette::Result<HashMap<String, String>> {
@@ -581,14 +637,16 @@ impl WorkspaceMut {
                     ..req
                 };
 
-                self.manifest().add_pep508_dependency(
-                    (&req, pixi_req.as_ref()),
-                    platforms,
-                    feature_name,
-                    Some(editable),
-                    DependencyOverwriteBehavior::Overwrite,
-                    location.as_ref(),
-                )?;
+                for feature_name in features {
+                    self.manifest().add_pep508_dependency(
+                        (&req, pixi_req.as_ref()),
+                        platforms,
+                        feature_name,
+                        Some(editable),
+                        DependencyOverwriteBehavior::Overwrite,
+                        location.as_ref(),
+                    )?;
+                }
             }
         }
 
@@ -27,6 +27,10 @@ class ExitCode(IntEnum):
     SUCCESS = 0
     FAILURE = 1
     INCORRECT_USAGE = 2
+    LOCK_FILE_OUTDATED = 3
+    PACKAGES_UNAVAILABLE = 4
+    PENDING_CHANGES = 5
+    LOCK_FILE_MISSING = 6
     COMMAND_NOT_FOUND = 127
 
 
@@ -48,7 +52,7 @@ class Output:
 
 def verify_cli_command(
     command: list[Path | str],
-    expected_exit_code: ExitCode = ExitCode.SUCCESS,
+    expected_exit_code: ExitCode | int = ExitCode.SUCCESS,
     stdout_contains: str | list[str] | None = None,
     stdout_excludes: str | list[str] | None = None,
     stderr_contains: str | list[str] | None = None,
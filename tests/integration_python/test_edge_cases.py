@@ -353,6 +353,75 @@ def test_build_git_source_deps(
     )
 
 
+@pytest.mark.extra_slow
+def test_update_git_branch_pypi_dependency(
+    pixi: Path, tmp_pixi_workspace: Path, pypi_data: Path, pixi_tomls: Path
+) -> None:
+    """
+    Updating a single pypi git dependency that follows a branch re-locks it
+    to the head of the branch, a dependency pinned to a tag is not updated
+    """
+
+    project = pypi_data / "rich_table"
+    target_git_dir = tmp_pixi_workspace / "git_project"
+    shutil.copytree(project, target_git_dir)
+    shutil.rmtree(target_git_dir.joinpath(".pixi"), ignore_errors=True)
+
+    verify_cli_command(["git", "init", "-b", "main"], cwd=target_git_dir)
+    verify_cli_command(["git", "config", "user.email", "some@email.com"], cwd=target_git_dir)
+    verify_cli_command(["git", "config", "user.name", "some-name"], cwd=target_git_dir)
+    verify_cli_command(["git", "add", "."], cwd=target_git_dir)
+    verify_cli_command(["git", "commit", "-m", "initial commit"], cwd=target_git_dir)
+    verify_cli_command(["git", "tag", "v1"], cwd=target_git_dir)
+    commit_hash = verify_cli_command(
+        ["git", "rev-parse", "HEAD"], cwd=target_git_dir
+    ).stdout.strip()
+
+    minimal_workspace = tmp_pixi_workspace / "pixi_with_git_pypi"
+    minimal_workspace.mkdir()
+    workspace_manifest = minimal_workspace / "pixi.toml"
+    shutil.copyfile(pixi_tomls / "pypi_local_git.toml", workspace_manifest)
+    target_git_url = target_git_dir.as_uri()
+    branch_manifest = (
+        workspace_manifest.read_text()
+        .replace('"file://" }', f'"git+{target_git_url}", branch = "main" }}')
+        .replace("CURRENT_PLATFORM", CURRENT_PLATFORM)
+    )
+    workspace_manifest.write_text(branch_manifest)
+
+    verify_cli_command([pixi, "lock", "--manifest-path", workspace_manifest])
+    pixi_lock_file = minimal_workspace / "pixi.lock"
+    assert commit_hash in pixi_lock_file.read_text()
+
+    # Move the branch forward
+    readme = target_git_dir / "README.md"
+    readme.write_text("updated")
+    verify_cli_command(["git", "add", "."], cwd=target_git_dir)
+    verify_cli_command(["git", "commit", "-m", "update readme"], cwd=target_git_dir)
+    new_commit_hash = verify_cli_command(
+        ["git", "rev-parse", "HEAD"], cwd=target_git_dir
+    ).stdout.strip()
+
+    # The new commit is shown without changing the lock-file
+    verify_cli_command(
+        [pixi, "update", "--manifest-path", workspace_manifest, "--dry-run", "rich-table"],
+        stderr_contains=new_commit_hash[:16],
+    )
+    assert new_commit_hash not in pixi_lock_file.read_text()
+
+    verify_cli_command([pixi, "update", "--manifest-path", workspace_manifest, "rich-table"])
+    assert new_commit_hash in pixi_lock_file.read_text()
+
+    # A dependency that is pinned to a tag doesn't move
+    workspace_manifest.write_text(branch_manifest.replace('branch = "main"', 'tag = "v1"'))
+    verify_cli_command([pixi, "lock", "--manifest-path", workspace_manifest])
+    verify_cli_command(
+        [pixi, "update", "--manifest-path", workspace_manifest, "rich-table"],
+        stderr_contains="is pinned to tag 'v1'",
+    )
+    assert f"#{commit_hash}" in pixi_lock_file.read_text()
+
+
 def test_installation_pypi_conda_mismatch(
     pixi: Path, tmp_pixi_workspace: Path, test_data: Path, pixi_tomls: Path
 ) -> None:
@@ -1,9 +1,12 @@
+import http.server
 import json
 import os
 import platform
 import shutil
+import threading
 import tomllib
 from pathlib import Path
+from typing import Any
 
 import pytest
 from syrupy.assertion import SnapshotAssertion
@@ -276,6 +279,21 @@ def test_simple_project_setup(pixi: Path, tmp_pixi_workspace: Path) -> None:
     # Create a new project
     verify_cli_command([pixi, "init", "-c", conda_forge, tmp_pixi_workspace], ExitCode.SUCCESS)
 
+    # Make sure the platforms used for target specific dependencies are supported
+    verify_cli_command(
+        [
+            pixi,
+            "workspace",
+            "platform",
+            "add",
+            "--manifest-path",
+            manifest_path,
+            "linux-64",
+            "osx-arm64",
+        ],
+        ExitCode.SUCCESS,
+    )
+
     # Add package
     verify_cli_command(
         [pixi, "add", "--manifest-path", manifest_path, "_r-mutex"],
@@ -404,6 +422,25 @@ def test_pixi_init_non_existing_dir(pixi: Path, tmp_pixi_workspace: Path) -> Non
     assert "[workspace]" in manifest_content
 
 
+def test_pixi_init_template_keeps_existing_manifest(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    verify_cli_command(
+        [pixi, "init", tmp_pixi_workspace, "--template", "python"],
+        ExitCode.SUCCESS,
+        stderr_contains="Created",
+    )
+
+    # Applying a template again keeps the manifest and says so
+    manifest_path.write_text(EMPTY_BOILERPLATE_PROJECT)
+    verify_cli_command(
+        [pixi, "init", tmp_pixi_workspace, "--template", "rust"],
+        ExitCode.SUCCESS,
+        stderr_contains="kept the existing",
+        stderr_excludes="Created",
+    )
+    assert manifest_path.read_text() == EMPTY_BOILERPLATE_PROJECT
+
+
 @pytest.mark.slow
 def test_pixi_init_pyproject(pixi: Path, tmp_pixi_workspace: Path) -> None:
     manifest_path = tmp_pixi_workspace / "pyproject.toml"
@@ -535,6 +572,24 @@ def test_upgrade_json_output(
     assert data["environment"]["default"]
 
 
+def test_update_interactive_requires_terminal(
+    pixi: Path, tmp_pixi_workspace: Path, multiple_versions_channel_1: str
+) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    lock_file_path = tmp_pixi_workspace / "pixi.lock"
+    verify_cli_command([pixi, "init", "--channel", multiple_versions_channel_1, tmp_pixi_workspace])
+    verify_cli_command([pixi, "add", "--manifest-path", manifest_path, "package"])
+    lock_file_content = lock_file_path.read_text()
+
+    # The output of the test is not a terminal
+    verify_cli_command(
+        [pixi, "update", "--manifest-path", manifest_path, "--interactive"],
+        ExitCode.FAILURE,
+        stderr_contains="can only be used in a terminal",
+    )
+    assert lock_file_path.read_text() == lock_file_content
+
+
 def test_upgrade_dryrun(
     pixi: Path, tmp_pixi_workspace: Path, multiple_versions_channel_1: str
 ) -> None:
@@ -568,6 +623,31 @@ def test_upgrade_dryrun(
     assert not os.path.exists(tmp_pixi_workspace / ".pixi")
 
 
+@pytest.mark.slow
+def test_install_json_lines_reports_pypi_packages(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    verify_cli_command([pixi, "init", tmp_pixi_workspace])
+    verify_cli_command(
+        [pixi, "add", "--manifest-path", manifest_path, "--no-install", "python=3.13"]
+    )
+    verify_cli_command(
+        [pixi, "add", "--manifest-path", manifest_path, "--no-install", "--pypi", "six==1.16.0"]
+    )
+
+    output = verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest_path, "--output-format", "json-lines"]
+    )
+    events = [json.loads(line) for line in output.stdout.splitlines()]
+    linked = {
+        (event["package"], event["environment"])
+        for event in events
+        if event["type"] == "link-finished"
+    }
+    assert ("python", "default") in linked
+    assert ("six", "default") in linked
+    assert events[-1]["type"] == "summary"
+
+
 @pytest.mark.slow
 def test_upgrade_pypi_package(pixi: Path, tmp_pixi_workspace: Path) -> None:
     manifest_path = tmp_pixi_workspace / "pixi.toml"
@@ -807,6 +887,74 @@ def test_concurrency_flags(
     )
 
 
+def test_add_remove_revalidate(
+    pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str
+) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    verify_cli_command([pixi, "init", "--channel", dummy_channel_1, tmp_pixi_workspace])
+    verify_cli_command(
+        [pixi, "add", "--manifest-path", manifest_path, "dummy-b", "dummy-c", "dummy-f"]
+    )
+
+    # Break the prefix, it forgets that `dummy-b` is installed and its file is gone
+    prefix = tmp_pixi_workspace / ".pixi" / "envs" / "default"
+    executable = prefix / "bin" / ("dummy-b.bat" if platform.system() == "Windows" else "dummy-b")
+    assert executable.is_file()
+    executable.unlink()
+    for record in prefix.joinpath("conda-meta").glob("dummy-b-*.json"):
+        record.unlink()
+
+    # Only the packages that changed are touched by default
+    verify_cli_command([pixi, "remove", "--manifest-path", manifest_path, "dummy-c"])
+    assert not executable.exists()
+
+    # With `--revalidate` the whole prefix is checked and repaired
+    verify_cli_command(
+        [pixi, "remove", "--manifest-path", manifest_path, "--revalidate", "dummy-f"]
+    )
+    assert executable.is_file()
+
+    executable.unlink()
+    for record in prefix.joinpath("conda-meta").glob("dummy-b-*.json"):
+        record.unlink()
+    verify_cli_command([pixi, "add", "--manifest-path", manifest_path, "--revalidate", "dummy-f"])
+    assert executable.is_file()
+
+
+def test_remove_marks_prefix_up_to_date(
+    pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str
+) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    verify_cli_command([pixi, "init", "--channel", dummy_channel_1, tmp_pixi_workspace])
+    verify_cli_command(
+        [pixi, "add", "--manifest-path", manifest_path, "dummy-b", "dummy-c", "dummy-f"]
+    )
+
+    prefix = tmp_pixi_workspace / ".pixi" / "envs" / "default"
+    environment_file = prefix / "conda-meta" / "pixi"
+    hash_after_add = json.loads(environment_file.read_text())["environment_lock_file_hash"]
+
+    # Only `dummy-c` is touched, the rest of the prefix matches the lock-file
+    # so the prefix is marked as up-to-date with the new lock-file
+    verify_cli_command([pixi, "remove", "--manifest-path", manifest_path, "dummy-c"])
+    hash_after_remove = json.loads(environment_file.read_text())["environment_lock_file_hash"]
+    assert hash_after_remove != hash_after_add
+
+    # So the next run uses the prefix as it is instead of updating it, which
+    # would install the package that the prefix forgot about again
+    for record in prefix.joinpath("conda-meta").glob("dummy-b-*.json"):
+        record.unlink()
+    verify_cli_command([pixi, "run", "--manifest-path", manifest_path, "echo", "hello"])
+    assert not list(prefix.joinpath("conda-meta").glob("dummy-b-*.json"))
+
+    # A prefix that doesn't match the lock-file isn't marked as up-to-date
+    # after a partial update, the next run installs the rest of the packages
+    verify_cli_command([pixi, "remove", "--manifest-path", manifest_path, "dummy-f"])
+    assert not list(prefix.joinpath("conda-meta").glob("dummy-b-*.json"))
+    verify_cli_command([pixi, "run", "--manifest-path", manifest_path, "echo", "hello"])
+    assert list(prefix.joinpath("conda-meta").glob("dummy-b-*.json"))
+
+
 def test_dont_add_broken_dep(pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str) -> None:
     manifest_path = tmp_pixi_workspace / "pixi.toml"
 
@@ -825,6 +973,28 @@ def test_dont_add_broken_dep(pixi: Path, tmp_pixi_workspace: Path, dummy_channel
     assert manifest_content == tmp_pixi_workspace.joinpath("pixi.toml").read_text()
 
 
+def test_add_from_file(pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    verify_cli_command([pixi, "init", "--channel", dummy_channel_1, tmp_pixi_workspace])
+
+    specs = tmp_pixi_workspace / "specs.txt"
+    specs.write_text("# The dependencies\ndummy-a  # inline comment\n\ndummy-b>=0.1\n")
+    verify_cli_command(
+        [pixi, "add", "--manifest-path", manifest_path, "--no-install", "--from-file", specs]
+    )
+    dependencies = tomllib.loads(manifest_path.read_text())["dependencies"]
+    assert set(dependencies) == {"dummy-a", "dummy-b"}
+    assert dependencies["dummy-b"] == ">=0.1"
+
+    # Includes of pip requirement files are rejected
+    specs.write_text("-r other.txt\n")
+    verify_cli_command(
+        [pixi, "add", "--manifest-path", manifest_path, "--no-install", "--from-file", specs],
+        ExitCode.FAILURE,
+        stderr_contains="'-r' options are not supported",
+    )
+
+
 def test_list_exits_unsuccessful_on_unknown_pkg(
     pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str
 ) -> None:
@@ -1040,11 +1210,11 @@ def test_pixi_lock(pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str) -
     dot_pixi = tmp_pixi_workspace / ".pixi"
     shutil.rmtree(dot_pixi)
 
-    # Run pixi lock to recreate the lock file and validate the return code with --check is 1
+    # Run pixi lock to recreate the lock file and validate the return code with --check
     verify_cli_command(
         [pixi, "lock", "--manifest-path", manifest_path, "--check"],
-        expected_exit_code=ExitCode.FAILURE,
-        stderr_contains=["+", "dummy-a"],
+        expected_exit_code=ExitCode.LOCK_FILE_MISSING,
+        stderr_contains=["no lock-file", "+", "dummy-a"],
     )
 
     # Run pixi lock again to validate that the return code with --check is 0
@@ -1053,6 +1223,10 @@ def test_pixi_lock(pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str) -
         expected_exit_code=ExitCode.SUCCESS,
     )
 
+    # The JSON output only goes to stdout and reports that nothing changed
+    output = verify_cli_command([pixi, "lock", "--manifest-path", manifest_path, "--json"])
+    assert json.loads(output.stdout)["changed"] is False
+
     # Read the recreated lock file content
     recreated_lock_content = lock_file_path.read_text()
 
@@ -1062,6 +1236,380 @@ def test_pixi_lock(pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str) -
     # Ensure the .pixi folder does not exist
     assert not dot_pixi.exists()
 
+    # A dependency that is not locked makes the lock-file out of date
+    manifest_path.write_text(
+        manifest_path.read_text().replace("[dependencies]", '[dependencies]\ndummy-b = "*"')
+    )
+    output = verify_cli_command(
+        [pixi, "lock", "--manifest-path", manifest_path, "--check", "--json"],
+        expected_exit_code=ExitCode.LOCK_FILE_OUTDATED,
+    )
+    report = json.loads(output.stdout)
+    assert report["status"] == "outdated"
+    assert {reason["environment"] for reason in report["outdated"]} == {"default"}
+    assert any("dummy-b" in reason["reason"] for reason in report["outdated"])
+    conda_diff = report["diff"]["environment"]["default"][CURRENT_PLATFORM]["conda"]
+    assert any(package["name"] == "dummy-b" for package in conda_diff)
+
+
+def test_pixi_lock_reports_pruned_environments_after_solving(
+    pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str
+) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    verify_cli_command([pixi, "init", "--channel", dummy_channel_1, tmp_pixi_workspace])
+    manifest = manifest_path.read_text()
+    manifest_path.write_text(
+        manifest
+        + '\n[feature.docs.dependencies]\ndummy-b = "*"\n'
+        + '\n[environments]\ndocs = ["docs"]\n'
+    )
+    verify_cli_command([pixi, "lock", "--manifest-path", manifest_path])
+
+    # The lock-file isn't written when the solve fails, so nothing is pruned
+    manifest_path.write_text(
+        manifest.replace("[dependencies]", '[dependencies]\ndoes-not-exist = "*"')
+    )
+    verify_cli_command(
+        [pixi, "lock", "--manifest-path", manifest_path],
+        ExitCode.FAILURE,
+        stderr_excludes="Pruned",
+    )
+
+    manifest_path.write_text(manifest)
+    verify_cli_command(
+        [pixi, "lock", "--manifest-path", manifest_path],
+        stderr_contains="Pruned docs from the lock-file",
+    )
+
+
+def test_pixi_lock_check_availability(
+    pixi: Path, tmp_pixi_workspace: Path, channels: Path
+) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+
+    # Work on a copy of the channel, so that a package can be yanked
+    channel = tmp_pixi_workspace / "channel"
+    shutil.copytree(channels / "dummy_channel_1", channel)
+    verify_cli_command([pixi, "init", "--channel", channel.as_uri(), tmp_pixi_workspace])
+    verify_cli_command([pixi, "add", "--manifest-path", manifest_path, "--no-install", "dummy-a"])
+
+    verify_cli_command(
+        [pixi, "lock", "--manifest-path", manifest_path, "--check", "--check-availability"],
+        expected_exit_code=ExitCode.SUCCESS,
+        stderr_contains="All locked packages are available",
+    )
+
+    # Yank `dummy-a` from the channel
+    for repodata_path in channel.glob("*/repodata.json"):
+        repodata = json.loads(repodata_path.read_text())
+        for key in ["packages", "packages.conda"]:
+            repodata[key] = {
+                file_name: record
+                for file_name, record in repodata.get(key, {}).items()
+                if not file_name.startswith("dummy-a-")
+            }
+        repodata_path.write_text(json.dumps(repodata))
+
+    output = verify_cli_command(
+        [
+            pixi,
+            "lock",
+            "--manifest-path",
+            manifest_path,
+            "--check",
+            "--check-availability",
+            "--refresh",
+            "--json",
+        ],
+        expected_exit_code=ExitCode.PACKAGES_UNAVAILABLE,
+    )
+    report = json.loads(output.stdout)
+    assert report["status"] == "up-to-date"
+    [unavailable] = report["unavailable"]
+    assert unavailable["environment"] == "default"
+    assert unavailable["file_name"].startswith("dummy-a-")
+    assert unavailable["channel"].rstrip("/") == channel.as_uri()
+
+
+def test_pixi_lock_licenses(pixi: Path, tmp_pixi_workspace: Path, channels: Path) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+
+    # Work on a copy of the channel, so that `dummy-a` can be given a license
+    channel = tmp_pixi_workspace / "channel"
+    shutil.copytree(channels / "dummy_channel_1", channel)
+    for repodata_path in channel.glob("*/repodata.json"):
+        repodata = json.loads(repodata_path.read_text())
+        for key in ["packages", "packages.conda"]:
+            for file_name, record in repodata.get(key, {}).items():
+                if file_name.startswith("dummy-a-"):
+                    record["license"] = "MIT"
+        repodata_path.write_text(json.dumps(repodata))
+
+    verify_cli_command([pixi, "init", "--channel", channel.as_uri(), tmp_pixi_workspace])
+    verify_cli_command([pixi, "add", "--manifest-path", manifest_path, "--no-install", "dummy-a"])
+    lock_file = (tmp_pixi_workspace / "pixi.lock").read_text()
+
+    output = verify_cli_command(
+        [pixi, "lock", "--manifest-path", manifest_path, "--licenses", "--json"],
+    )
+    report = json.loads(output.stdout)
+    assert report["environment"] == "default"
+    assert [package["name"] for package in report["licenses"]["MIT"]] == ["dummy-a"]
+    assert "dummy-c" in [package["name"] for package in report["licenses"]["unknown"]]
+
+    # The lock-file is used as it is
+    assert (tmp_pixi_workspace / "pixi.lock").read_text() == lock_file
+
+
+def test_pixi_lock_licenses_of_pypi_packages(
+    pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str
+) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    verify_cli_command([pixi, "init", "--channel", dummy_channel_1, tmp_pixi_workspace])
+
+    # Serve the metadata of `remote-pkg` next to its wheel
+    index = tmp_pixi_workspace / "index"
+    index.mkdir()
+    (index / "remote_pkg-2.0-py3-none-any.whl.metadata").write_text(
+        "Metadata-Version: 2.4\nName: remote-pkg\nVersion: 2.0\nLicense-Expression: BSD-3-Clause\n"
+    )
+    requests: list[str] = []
+
+    class Handler(http.server.SimpleHTTPRequestHandler):
+        def __init__(self, *args: Any, **kwargs: Any) -> None:
+            super().__init__(*args, directory=str(index), **kwargs)
+
+        def log_message(self, format: str, *args: Any) -> None:
+            requests.append(self.path)
+
+    server = http.server.ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+    threading.Thread(target=server.serve_forever, daemon=True).start()
+    url = f"http://127.0.0.1:{server.server_address[1]}"
+
+    packages = {"installed-pkg": "1.0", "remote-pkg": "2.0"}
+    wheels = {
+        name: f"{url}/{name.replace('-', '_')}-{version}-py3-none-any.whl"
+        for name, version in packages.items()
+    }
+    locked_packages = "".join(
+        f"- pypi: {wheels[name]}\n  name: {name}\n  version: '{version}'\n  sha256: {'0' * 64}\n"
+        for name, version in packages.items()
+    )
+    (tmp_pixi_workspace / "pixi.lock").write_text(
+        "version: 6\n"
+        "environments:\n"
+        "  default:\n"
+        "    channels:\n"
+        f"    - url: {dummy_channel_1}/\n"
+        "    indexes:\n"
+        f"    - {url}/simple\n"
+        "    packages:\n"
+        f"      {CURRENT_PLATFORM}:\n"
+        + "".join(f"      - pypi: {wheel}\n" for wheel in wheels.values())
+        + "packages:\n"
+        + locked_packages
+    )
+
+    # The `License` header of the installed package contains the license text,
+    # so the classifier is used instead
+    env_dir = tmp_pixi_workspace / ".pixi" / "envs" / "default"
+    if platform.system() == "Windows":
+        site_packages = env_dir / "Lib" / "site-packages"
+    else:
+        site_packages = env_dir / "lib" / "python3.12" / "site-packages"
+    dist_info = site_packages / "installed_pkg-1.0.dist-info"
+    dist_info.mkdir(parents=True)
+    (dist_info / "METADATA").write_text(
+        "Metadata-Version: 2.1\n"
+        "Name: installed-pkg\n"
+        "Version: 1.0\n"
+        "License: Permission is hereby granted, free of charge\n"
+        "Classifier: License :: OSI Approved :: MIT License\n"
+    )
+
+    def licenses(*args: str) -> dict[str, list[str]]:
+        output = verify_cli_command(
+            [pixi, "lock", "--manifest-path", manifest_path, "--licenses", "--json", *args],
+        )
+        report = json.loads(output.stdout)
+        return {
+            license: [package["name"] for package in entries]
+            for license, entries in report["licenses"].items()
+        }
+
+    try:
+        # Without `--refresh` the index isn't queried
+        assert licenses() == {"MIT License": ["installed-pkg"], "unknown": ["remote-pkg"]}
+        assert requests == []
+
+        assert licenses("--refresh") == {
+            "BSD-3-Clause": ["remote-pkg"],
+            "MIT License": ["installed-pkg"],
+        }
+        assert requests == ["/remote_pkg-2.0-py3-none-any.whl.metadata"]
+    finally:
+        server.shutdown()
+
+
+def test_pixi_lock_offline(pixi: Path, tmp_pixi_workspace: Path, channels: Path) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    env = {"PIXI_CACHE_DIR": str(tmp_pixi_workspace / "pixi_cache")}
+
+    # Serve the channel over http and remember which files are requested
+    requests: list[str] = []
+
+    class Handler(http.server.SimpleHTTPRequestHandler):
+        def __init__(self, *args: Any, **kwargs: Any) -> None:
+            super().__init__(
+                *args, directory=str(channels / "multiple_versions_channel_1"), **kwargs
+            )
+
+        def log_message(self, format: str, *args: Any) -> None:
+            requests.append(self.path)
+
+    server = http.server.ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+    threading.Thread(target=server.serve_forever, daemon=True).start()
+    channel = f"http://127.0.0.1:{server.server_address[1]}"
+
+    try:
+        verify_cli_command([pixi, "init", "--channel", channel, tmp_pixi_workspace], env=env)
+
+        # Without cached repodata there is nothing to solve with
+        verify_cli_command(
+            [pixi, "add", "--manifest-path", manifest_path, "--no-install", "--offline", "package"],
+            ExitCode.FAILURE,
+            stderr_contains=["package metadata not in cache", channel],
+            env=env,
+        )
+        assert requests == []
+
+        verify_cli_command(
+            [pixi, "add", "--manifest-path", manifest_path, "--no-install", "package==0.1.0"],
+            env=env,
+        )
+        assert requests != []
+        requests.clear()
+
+        # The cached repodata is enough to solve a new requirement
+        verify_cli_command(
+            [
+                pixi,
+                "add",
+                "--manifest-path",
+                manifest_path,
+                "--no-install",
+                "--offline",
+                "package==0.2.0",
+            ],
+            env=env,
+        )
+        assert "package-0.2.0" in (tmp_pixi_workspace / "pixi.lock").read_text()
+
+        # The packages were never downloaded, so they can't be installed
+        verify_cli_command(
+            [pixi, "install", "--manifest-path", manifest_path, "--offline"],
+            ExitCode.FAILURE,
+            stderr_contains="while offline",
+            env=env,
+        )
+        assert requests == []
+    finally:
+        server.shutdown()
+
+
+@pytest.mark.skipif(
+    platform.system() == "Windows", reason="dummy-a only installs a batch file on Windows"
+)
+def test_install_verify_corrupted_package_cache(
+    pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str
+) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    env = {"PIXI_CACHE_DIR": str(tmp_pixi_workspace / "pixi_cache")}
+    verify_cli_command([pixi, "init", "--channel", dummy_channel_1, tmp_pixi_workspace])
+    verify_cli_command([pixi, "add", "--manifest-path", manifest_path, "dummy-a"], env=env)
+
+    # Corrupt the file in the package cache without changing its size
+    [cached] = list(
+        (tmp_pixi_workspace / "pixi_cache" / "pkgs").glob("dummy-a-0.1.0-*/bin/dummy-a")
+    )
+    with open(cached, "r+b") as file:
+        file.write(b"X")
+    installed = tmp_pixi_workspace / ".pixi" / "envs" / "default" / "bin" / "dummy-a"
+    installed.unlink()
+
+    # Linking the package again copies the corrupted file, so it is not repaired
+    verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest_path, "--verify"],
+        ExitCode.FAILURE,
+        stderr_contains=["did not repair 'dummy-a'", "pixi clean cache --conda"],
+        stderr_excludes="repaired",
+        env=env,
+    )
+
+    # Once the cache is cleaned the package is downloaded and repaired
+    verify_cli_command(
+        [pixi, "clean", "cache", "--conda", "--yes"],
+        env=env,
+    )
+    installed.unlink()
+    verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest_path, "--verify"],
+        stderr_contains=["repaired 1 packages", "dummy-a"],
+        env=env,
+    )
+
+
+def test_install_dry_run(pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    verify_cli_command([pixi, "init", "--channel", dummy_channel_1, tmp_pixi_workspace])
+    verify_cli_command([pixi, "add", "--manifest-path", manifest_path, "--no-install", "dummy-a"])
+
+    # `--dry-run` exits with 5 when installing would change the environment
+    verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest_path, "--dry-run"],
+        expected_exit_code=ExitCode.PENDING_CHANGES,
+        stderr_contains=["link", "dummy-a"],
+    )
+    assert not tmp_pixi_workspace.joinpath(".pixi", "envs", "default", "conda-meta").exists()
+
+    verify_cli_command([pixi, "install", "--manifest-path", manifest_path])
+    verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest_path, "--dry-run"],
+        stderr_contains="Nothing to install",
+    )
+
+
+@pytest.mark.slow
+def test_install_dry_run_pypi(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest_path = tmp_pixi_workspace / "pixi.toml"
+    verify_cli_command([pixi, "init", tmp_pixi_workspace])
+    verify_cli_command(
+        [pixi, "add", "--manifest-path", manifest_path, "--no-install", "python=3.13"]
+    )
+    verify_cli_command(
+        [pixi, "add", "--manifest-path", manifest_path, "--no-install", "--pypi", "six==1.16.0"]
+    )
+
+    # The PyPI packages are installed with the new interpreter
+    verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest_path, "--dry-run"],
+        expected_exit_code=ExitCode.PENDING_CHANGES,
+        stderr_contains=["pypi", "six", "1.16.0"],
+    )
+
+    verify_cli_command([pixi, "install", "--manifest-path", manifest_path])
+    verify_cli_command(
+        [pixi, "add", "--manifest-path", manifest_path, "--no-install", "--pypi", "six==1.17.0"]
+    )
+
+    # Only the PyPI package changes, the conda packages are up-to-date
+    verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest_path, "--dry-run"],
+        expected_exit_code=ExitCode.PENDING_CHANGES,
+        stderr_contains=["change", "pypi", "six", "1.16.0 -> 1.17.0"],
+        stderr_excludes="python",
+    )
+
 
 @pytest.mark.extra_slow
 def test_pixi_auth(pixi: Path) -> None:
@@ -1389,6 +1937,116 @@ def test_pixi_task_list_json(
     assert task_data == snapshot
 
 
+def test_pixi_task_add_depends_on_environment(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = """
+[workspace]
+name = "test"
+channels = []
+platforms = ["linux-64", "win-64", "osx-64", "osx-arm64"]
+
+[tasks]
+build = "echo build"
+
+[feature.prod.activation.env]
+MODE = "prod"
+
+[environments]
+prod = ["prod"]
+"""
+    manifest.write_text(toml)
+
+    verify_cli_command(
+        [
+            pixi,
+            "task",
+            "add",
+            "--manifest-path",
+            manifest,
+            "deploy",
+            "echo deploy",
+            "--depends-on",
+            "build:prod",
+        ],
+        stderr_contains="build:prod",
+    )
+    deploy = tomllib.loads(manifest.read_text())["tasks"]["deploy"]
+    assert deploy["depends-on"] == [{"task": "build", "environment": "prod"}]
+
+    result = verify_cli_command([pixi, "task", "list", "--json", "--manifest-path", manifest])
+    tasks = {
+        task["name"]: task
+        for env in json.loads(result.stdout)
+        if env["environment"] == "default"
+        for feature in env["features"]
+        for task in feature["tasks"]
+    }
+    assert tasks["deploy"]["depends_on"] == [
+        {"task_name": "build", "args": None, "environment": "prod"}
+    ]
+
+    # The environment has to exist
+    verify_cli_command(
+        [
+            pixi,
+            "task",
+            "add",
+            "--manifest-path",
+            manifest,
+            "release",
+            "echo release",
+            "--depends-on",
+            "build:staging",
+        ],
+        ExitCode.FAILURE,
+        stderr_contains="unknown environment 'staging'",
+    )
+
+
+def test_pixi_task_add_depends_on_task_with_colon(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = """
+[workspace]
+name = "test"
+channels = []
+platforms = ["linux-64", "win-64", "osx-64", "osx-arm64"]
+
+[tasks]
+"lint:fix" = "echo fix"
+"docs:prod" = "echo docs"
+
+[feature.prod.activation.env]
+MODE = "prod"
+
+[environments]
+prod = ["prod"]
+"""
+    manifest.write_text(toml)
+
+    # `fix` isn't an environment and `docs:prod` is a task itself
+    verify_cli_command(
+        [
+            pixi,
+            "task",
+            "add",
+            "--manifest-path",
+            manifest,
+            "check",
+            "echo check",
+            "--depends-on",
+            "lint:fix",
+            "docs:prod",
+        ],
+    )
+    check = tomllib.loads(manifest.read_text())["tasks"]["check"]
+    assert check["depends-on"] == ["lint:fix", "docs:prod"]
+
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "check"],
+        stdout_contains=["fix", "docs", "check"],
+    )
+
+
 def test_info_output_extended(
     pixi: Path, tmp_pixi_workspace: Path, snapshot: SnapshotAssertion
 ) -> None:
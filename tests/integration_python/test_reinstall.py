@@ -135,3 +135,47 @@ def test_pixi_reinstall_multi_env(pixi: Path, reinstall_workspace: Path) -> None
         stdout_contains="PyPI is number 2",
         env=env,
     )
+
+
+@pytest.mark.slow
+def test_pixi_install_and_run_reinstall_flags(pixi: Path, reinstall_workspace: Path) -> None:
+    env = {
+        "PIXI_CACHE_DIR": str(reinstall_workspace.joinpath("pixi_cache")),
+    }
+    manifest = reinstall_workspace.joinpath("pixi.toml")
+
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "pypi-package-main"],
+        stdout_contains="PyPI is number 1",
+        env=env,
+    )
+
+    # Modify the Python files
+    init_py = reinstall_workspace.joinpath("pypi_package", "src", "pypi_package", "__init__.py")
+    init_py.write_text(init_py.read_text().replace("1", "2"))
+
+    # Installing without reinstalling doesn't pick up the change
+    verify_cli_command([pixi, "install", "--manifest-path", manifest], env=env)
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "pypi-package-main"],
+        stdout_contains="PyPI is number 1",
+        env=env,
+    )
+
+    # Reinstalling the package while installing does
+    verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest, "--reinstall", "pypi_package"], env=env
+    )
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "pypi-package-main"],
+        stdout_contains="PyPI is number 2",
+        env=env,
+    )
+
+    # Reinstalling everything before running a task
+    init_py.write_text(init_py.read_text().replace("2", "3"))
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--reinstall-all", "pypi-package-main"],
+        stdout_contains="PyPI is number 3",
+        env=env,
+    )
@@ -1,7 +1,13 @@
 import json
+import signal
+import subprocess
+import sys
+import time
 import tomli_w
 from pathlib import Path
 
+import pytest
+
 from .common import (
     EMPTY_BOILERPLATE_PROJECT,
     verify_cli_command,
@@ -11,6 +17,7 @@ from .common import (
 
 import tempfile
 import os
+import shutil
 import tomli
 
 
@@ -240,33 +247,16 @@ def test_run_with_activation(pixi: Path, tmp_pixi_workspace: Path) -> None:
         stdout_contains="test123",
     )
 
-    # Validate that without experimental it does not use the cache
-    assert not tmp_pixi_workspace.joinpath(".pixi/activation-env-v0").exists()
-
-    # Enable the experimental cache config
-    verify_cli_command(
-        [
-            pixi,
-            "config",
-            "set",
-            "--manifest-path",
-            manifest,
-            "--local",
-            "experimental.use-environment-activation-cache",
-            "true",
-        ],
-    )
-
-    # Run the default task and create cache
+    # The activation is cached and reused by the next run
+    cache_path = tmp_pixi_workspace.joinpath(".pixi", "activation-cache", "activation_default.json")
+    assert cache_path.exists()
     verify_cli_command(
-        [pixi, "run", "--manifest-path", manifest, "task"],
+        [pixi, "run", "--manifest-path", manifest, "-vvv", "task"],
         stdout_contains="test123",
+        stderr_contains="Using activation cache",
     )
 
     # Modify the environment variable in cache
-    cache_path = tmp_pixi_workspace.joinpath(
-        ".pixi", "activation-env-v0", "activation_default.json"
-    )
     data = json.loads(cache_path.read_text())
     data["environment_variables"]["TEST_ENV_VAR_FOR_ACTIVATION_TEST"] = "test456"
     cache_path.write_text(json.dumps(data, indent=4))
@@ -284,6 +274,44 @@ def test_run_with_activation(pixi: Path, tmp_pixi_workspace: Path) -> None:
         stdout_contains="test123",
     )
 
+    # Disable the activation cache
+    verify_cli_command(
+        [
+            pixi,
+            "config",
+            "set",
+            "--manifest-path",
+            manifest,
+            "--local",
+            "run.use-activation-cache",
+            "false",
+        ],
+    )
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "-vvv", "task"],
+        stdout_contains="test123",
+        stderr_excludes="Using activation cache",
+    )
+    verify_cli_command(
+        [
+            pixi,
+            "config",
+            "unset",
+            "--manifest-path",
+            manifest,
+            "--local",
+            "run.use-activation-cache",
+        ],
+    )
+
+    # Installing the environment again invalidates the cache
+    cache_path.write_text(cache_path.read_text().replace("test123", "test456"))
+    shutil.rmtree(tmp_pixi_workspace.joinpath(".pixi", "envs"))
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "task"],
+        stdout_contains="test123",
+    )
+
 
 def test_detached_environments_run(pixi: Path, tmp_path: Path, dummy_channel_1: str) -> None:
     tmp_project = tmp_path.joinpath("pixi-project")
@@ -402,6 +430,36 @@ def test_run_dry_run(pixi: Path, tmp_pixi_workspace: Path) -> None:
     )
 
 
+def test_run_dry_run_task_order(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    tmp_pixi_workspace.joinpath("src").mkdir()
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    build = {{ cmd = "make {{{{ target }}}}", args = ["target"], cwd = "src", env = {{ CFLAGS = "-O2" }} }}
+    test = {{ cmd = "pytest", depends-on = [{{ task = "build", args = ["all"] }}] }}
+    """
+    manifest.write_text(toml)
+
+    output = verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--dry-run", "test"],
+        stderr_contains=["make all", "CFLAGS=-O2", "pytest"],
+    )
+    assert output.stderr.index("make all") < output.stderr.index("pytest")
+    assert str(tmp_pixi_workspace.joinpath("src")) in output.stderr
+
+    # Nothing is locked or installed
+    assert not tmp_pixi_workspace.joinpath("pixi.lock").exists()
+    assert not tmp_pixi_workspace.joinpath(".pixi", "envs").exists()
+
+    # Unknown dependencies fail like in a real run
+    manifest.write_text(toml + 'broken = { depends-on = ["missing"] }\n')
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--dry-run", "broken"],
+        expected_exit_code=ExitCode.FAILURE,
+    )
+
+
 def test_invalid_task_args(pixi: Path, tmp_pixi_workspace: Path) -> None:
     manifest_path = tmp_pixi_workspace.joinpath("pixi.toml")
 
@@ -1261,6 +1319,7 @@ def test_task_caching_with_multiple_outputs_args(pixi: Path, tmp_pixi_workspace:
         "base-task": {
             "cmd": "echo task with {{ arg1 }} > outputs/{{ arg1 }}.txt && cat outputs/{{ arg1 }}.txt",
             "args": ["arg1"],
+            "inputs": ["pixi.toml"],
             "outputs": ["outputs/{{ arg1 }}.txt"],
         },
         "multiple-depends": {
@@ -1356,3 +1415,846 @@ def test_task_caching_with_multiple_inputs_args(pixi: Path, tmp_pixi_workspace:
             "cache hit",
         ],
     )
+
+
+def test_post_install_tasks(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    hooks_log = tmp_pixi_workspace.joinpath("hooks.log")
+    toml = f"""
+    post-install = ["hook"]
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    prepare = "echo prepare >> hooks.log"
+    hook = {{ cmd = "echo hook >> hooks.log", depends-on = ["prepare"] }}
+    """
+    manifest.write_text(toml)
+
+    # The hook and its dependency run after the environment was created
+    verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest],
+        stderr_contains="Post-install task",
+    )
+    assert hooks_log.read_text().split() == ["prepare", "hook"]
+
+    # Nothing changed, so the hooks don't run again
+    verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest],
+        stderr_excludes="Post-install task",
+    )
+    verify_cli_command([pixi, "run", "--manifest-path", manifest, "echo", "hi"])
+    assert hooks_log.read_text().split() == ["prepare", "hook"]
+
+    # Recreating the environment without hooks doesn't run them
+    shutil.rmtree(tmp_pixi_workspace.joinpath(".pixi", "envs"))
+    verify_cli_command([pixi, "install", "--manifest-path", manifest, "--no-hooks"])
+    assert hooks_log.read_text().split() == ["prepare", "hook"]
+
+    # The implicit install of `pixi run` runs them
+    shutil.rmtree(tmp_pixi_workspace.joinpath(".pixi", "envs"))
+    verify_cli_command([pixi, "run", "--manifest-path", manifest, "echo", "hi"])
+    assert hooks_log.read_text().split() == ["prepare", "hook", "prepare", "hook"]
+
+    # A failing hook fails the install with its exit code
+    manifest.write_text(
+        toml.replace('post-install = ["hook"]', 'post-install = ["fail"]')
+        + '\nfail = "exit 42"\n'
+    )
+    verify_cli_command(
+        [pixi, "install", "--manifest-path", manifest],
+        42,
+        stderr_contains="failed with exit code 42",
+    )
+    # Also when it runs as part of the implicit install of `pixi run`
+    shutil.rmtree(tmp_pixi_workspace.joinpath(".pixi", "envs"))
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "echo", "hi"],
+        42,
+        stderr_contains="failed with exit code 42",
+    )
+
+
+def test_run_lists_tasks(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    build = {{ cmd = "make", description = "Build the project", depends-on = ["_configure"] }}
+    _configure = "cmake ."
+
+    [feature.docs.tasks]
+    serve = {{ cmd = "mkdocs serve", depends-on = ["build"] }}
+
+    [environments]
+    docs = ["docs"]
+    """
+    manifest.write_text(toml)
+
+    # Without a task, the tasks are listed by environment and feature
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest],
+        stderr_contains=["build", "Build the project", "depends on _configure", "serve"],
+        stderr_excludes="cmake",
+    )
+
+    output = verify_cli_command([pixi, "run", "--manifest-path", manifest, "--json"])
+    listing = json.loads(output.stdout)
+    assert [environment["environment"] for environment in listing] == ["default", "docs"]
+    docs_features = listing[1]["features"]
+    assert [feature["feature"] for feature in docs_features] == ["default", "docs"]
+    assert docs_features[0]["tasks"] == [
+        {"name": "build", "description": "Build the project", "depends_on": ["_configure"]}
+    ]
+    assert docs_features[1]["tasks"] == [
+        {"name": "serve", "description": None, "depends_on": ["build"]}
+    ]
+
+    # The environment scopes the listing
+    output = verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--json", "--environment", "default"]
+    )
+    assert [environment["environment"] for environment in json.loads(output.stdout)] == [
+        "default"
+    ]
+
+    # A command that isn't a task or an executable suggests a similar task
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "biuld"],
+        ExitCode.COMMAND_NOT_FOUND,
+        stderr_contains="did you mean the task 'build'?",
+    )
+
+
+def start_cli_command(command: list[Path | str]) -> subprocess.Popen[str]:
+    """Starts a pixi command in the background, with its output captured.
+
+    Pixi runs in a new session without a terminal, so that it only receives the
+    signals that the test sends.
+    """
+    return subprocess.Popen(
+        command,
+        stdout=subprocess.PIPE,
+        stderr=subprocess.PIPE,
+        text=True,
+        env=os.environ | {"PIXI_NO_WRAP": "1"},
+        start_new_session=sys.platform != "win32",
+    )
+
+
+def wait_for_file(path: Path, process: subprocess.Popen[str], timeout: float = 120) -> None:
+    """Waits until a task created the file, the environment may be installed first."""
+    deadline = time.monotonic() + timeout
+    while not path.exists():
+        assert process.poll() is None, process.communicate()
+        assert time.monotonic() < deadline, f"{path} was not created"
+        time.sleep(0.1)
+
+
+def test_run_parallel_tasks_failure(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    slow = "sleep 2 && echo slow done"
+    fail = "exit 3"
+    after-fail = {{ cmd = "echo after fail", depends-on = ["fail"] }}
+    all = {{ depends-on = ["slow", "after-fail"] }}
+    first = "echo first"
+    second = {{ cmd = "echo second", depends-on = ["first"] }}
+    """
+    manifest.write_text(toml)
+
+    # The tasks that may run at the same time have their output prefixed while
+    # they run. A failure cancels the tasks that have not started, the task
+    # that is running finishes.
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--jobs", "2", "all"],
+        3,
+        stdout_contains="[slow] slow done",
+        stdout_excludes="after fail",
+    )
+
+    # Tasks that can't run at the same time as another task keep the terminal
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--jobs", "2", "second"],
+        stdout_contains=["first\n", "second\n"],
+        stdout_excludes="[first]",
+    )
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="sends SIGINT to pixi")
+def test_run_parallel_tasks_interrupted(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    first = "echo started > started.txt && sleep 30"
+    second = "sleep 30"
+    later = {{ cmd = "echo later", depends-on = ["first", "second"] }}
+    """
+    manifest.write_text(toml)
+
+    process = start_cli_command([pixi, "run", "--manifest-path", manifest, "--jobs", "2", "later"])
+    wait_for_file(tmp_pixi_workspace / "started.txt", process)
+    time.sleep(0.5)
+    process.send_signal(signal.SIGINT)
+    stdout, stderr = process.communicate(timeout=30)
+
+    # The running tasks are stopped and the pending task never starts
+    assert process.returncode == 130, stderr
+    assert "later" not in stdout
+
+
+def cpu_seconds(pid: int) -> float:
+    """Returns the CPU time that a process used so far, from `/proc`."""
+    fields = Path(f"/proc/{pid}/stat").read_text().rsplit(")", 1)[1].split()
+    return (int(fields[11]) + int(fields[12])) / os.sysconf("SC_CLK_TCK")
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="sends SIGINT to pixi")
+def test_run_watch(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks.build]
+    cmd = "echo run >> runs.txt && echo built > src/generated.txt"
+    inputs = ["src/**"]
+    outputs = ["src/generated.txt"]
+    """
+    manifest.write_text(toml)
+    tmp_pixi_workspace.joinpath("src").mkdir()
+    tmp_pixi_workspace.joinpath("src", "input.txt").write_text("first")
+    runs = tmp_pixi_workspace / "runs.txt"
+
+    process = start_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--watch", "--debounce", "0", "build"]
+    )
+    try:
+        wait_for_file(runs, process)
+
+        # Writing the outputs doesn't run the task again, and waiting for
+        # changes doesn't keep pixi busy
+        cpu_before = cpu_seconds(process.pid) if sys.platform == "linux" else 0.0
+        time.sleep(2)
+        assert runs.read_text().splitlines() == ["run"]
+        if sys.platform == "linux":
+            assert cpu_seconds(process.pid) - cpu_before < 1.0
+
+        # Changing an input runs the task again
+        tmp_pixi_workspace.joinpath("src", "input.txt").write_text("second")
+        deadline = time.monotonic() + 30
+        while len(runs.read_text().splitlines()) < 2:
+            assert process.poll() is None, process.communicate()
+            assert time.monotonic() < deadline, "the task didn't run again"
+            time.sleep(0.1)
+        time.sleep(1)
+        assert runs.read_text().splitlines() == ["run", "run"]
+    finally:
+        process.send_signal(signal.SIGINT)
+        stdout, stderr = process.communicate(timeout=30)
+
+    assert process.returncode == 0, stderr
+
+
+def test_task_cache_env_files(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks.greet]
+    cmd = "echo greeting $GREETING"
+    inputs = ["pixi.toml"]
+    env-files = [".env"]
+    """
+    manifest.write_text(toml)
+    env_file = tmp_pixi_workspace.joinpath(".env")
+    env_file.write_text("GREETING=hello\n")
+
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "greet"],
+        stdout_contains="greeting hello",
+        stderr_excludes="cache hit",
+    )
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "greet"],
+        stdout_excludes="greeting",
+        stderr_contains="cache hit",
+    )
+
+    # Changing the `.env` file runs the task again
+    env_file.write_text("GREETING=bye\n")
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "greet"],
+        stdout_contains="greeting bye",
+        stderr_excludes="cache hit",
+    )
+
+
+def is_running(pid: int) -> bool:
+    """Returns true if the process runs, a process that exited but was not
+    reaped yet doesn't."""
+    try:
+        os.kill(pid, 0)
+    except ProcessLookupError:
+        return False
+    stat = Path(f"/proc/{pid}/stat")
+    if sys.platform == "linux" and stat.exists():
+        return stat.read_text().rsplit(")", 1)[1].split()[0] != "Z"
+    return True
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="sends SIGTERM to pixi")
+def test_run_interrupt_exit_code(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    slow = "echo started > started.txt && sleep 30"
+    """
+    manifest.write_text(toml)
+
+    # The exit code is 128 + the signal that interrupted pixi
+    for sig, exit_code in [(signal.SIGINT, 130), (signal.SIGTERM, 143)]:
+        tmp_pixi_workspace.joinpath("started.txt").unlink(missing_ok=True)
+        process = start_cli_command([pixi, "run", "--manifest-path", manifest, "slow"])
+        wait_for_file(tmp_pixi_workspace / "started.txt", process)
+        process.send_signal(sig)
+        _, stderr = process.communicate(timeout=30)
+        assert process.returncode == exit_code, stderr
+        assert f"exiting with code {exit_code}" in stderr
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="sends SIGTERM to pixi")
+def test_run_interrupt_grace_period(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    graceful = '''sh -c 'trap "echo stopped > stopped.txt; exit 0" TERM; echo started > started.txt; while true; do sleep 0.1; done' '''
+    stubborn = '''sh -c 'trap "" TERM; echo started > started.txt; sleep 60' '''
+    """
+    manifest.write_text(toml)
+    started = tmp_pixi_workspace / "started.txt"
+
+    # A task that handles the signal gets to stop by itself
+    process = start_cli_command([pixi, "run", "--manifest-path", manifest, "graceful"])
+    wait_for_file(started, process)
+    process.send_signal(signal.SIGTERM)
+    _, stderr = process.communicate(timeout=30)
+    assert process.returncode == 143, stderr
+    assert tmp_pixi_workspace.joinpath("stopped.txt").read_text().strip() == "stopped"
+
+    # A task that ignores the signal is killed after the grace period
+    started.unlink()
+    process = start_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--grace-period", "1", "stubborn"]
+    )
+    wait_for_file(started, process)
+    interrupted = time.monotonic()
+    process.send_signal(signal.SIGTERM)
+    _, stderr = process.communicate(timeout=30)
+    assert process.returncode == 143, stderr
+    assert time.monotonic() - interrupted < 20
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="sends SIGINT to pixi")
+def test_run_interrupt_kills_grandchildren(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    serve = '''env -i sh -c 'sleep 300 & echo $! > grandchild.pid; wait' '''
+    """
+    manifest.write_text(toml)
+    pid_file = tmp_pixi_workspace / "grandchild.pid"
+
+    process = start_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--grace-period", "1", "serve"]
+    )
+    wait_for_file(pid_file, process)
+    time.sleep(0.5)
+    grandchild = int(pid_file.read_text())
+    assert is_running(grandchild)
+
+    # The background process ignores SIGINT and runs without the environment of
+    # the task, it is killed with the process group of the task after the grace
+    # period
+    process.send_signal(signal.SIGINT)
+    _, stderr = process.communicate(timeout=30)
+    assert process.returncode == 130, stderr
+    deadline = time.monotonic() + 10
+    while is_running(grandchild):
+        assert time.monotonic() < deadline, "the process that the task started still runs"
+        time.sleep(0.1)
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="sends SIGINT to pixi")
+def test_run_interrupt_nested_run(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    inner = '''sh -c 'trap "echo int >> ints.txt" INT; echo started > started.txt; for i in 1 2 3 4 5 6 7 8 9 10; do sleep 0.2; done' '''
+    outer = '''"{pixi}" run --manifest-path "{manifest}" inner'''
+    """
+    manifest.write_text(toml)
+
+    process = start_cli_command([pixi, "run", "--manifest-path", manifest, "outer"])
+    wait_for_file(tmp_pixi_workspace / "started.txt", process)
+    process.send_signal(signal.SIGINT)
+    _, stderr = process.communicate(timeout=30)
+
+    # The task of the nested run receives the signal once, from the outer run
+    assert process.returncode == 130, stderr
+    assert tmp_pixi_workspace.joinpath("ints.txt").read_text().splitlines() == ["int"]
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="handles SIGTERM in the task")
+def test_run_timeout_grace_period(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    graceful = {{ cmd = '''sh -c 'trap "echo stopped > stopped.txt; exit 0" TERM; while true; do sleep 0.1; done' ''', timeout = "1s" }}
+    stubborn = {{ cmd = '''sh -c 'trap "" TERM; sleep 60' ''', timeout = "1s" }}
+    """
+    manifest.write_text(toml)
+
+    # A task that times out receives SIGTERM and gets to stop by itself
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "graceful"],
+        ExitCode.FAILURE,
+        stderr_contains="timed out",
+    )
+    assert tmp_pixi_workspace.joinpath("stopped.txt").read_text().strip() == "stopped"
+
+    # A task that ignores SIGTERM is killed after the grace period
+    started = time.monotonic()
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--grace-period", "1", "stubborn"],
+        ExitCode.FAILURE,
+        stderr_contains="timed out",
+    )
+    assert time.monotonic() - started < 30
+
+
+def test_run_report(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    build = "echo built"
+    test = {{ cmd = "exit 3", depends-on = ["build"] }}
+    """
+    manifest.write_text(toml)
+    report_path = tmp_pixi_workspace / "report.json"
+
+    # The output is still printed while it is captured for the report
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--report", "json", report_path, "test"],
+        3,
+        stdout_contains="built",
+    )
+
+    # The report is written even though a task failed
+    tasks = json.loads(report_path.read_text())["tasks"]
+    assert [task["name"] for task in tasks] == ["build", "test"]
+    assert tasks[0]["environment"] == "default"
+    assert tasks[0]["exit_code"] == 0
+    assert tasks[0]["stdout"] == "built\n"
+    assert isinstance(tasks[0]["started_at"], str)
+    assert tasks[1]["exit_code"] == 3
+
+    # Only the `json` format is supported
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--report", "xml", report_path, "build"],
+        ExitCode.FAILURE,
+        stderr_contains="unsupported report format 'xml'",
+    )
+
+
+def test_run_multiple_environments(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    """The environments run one after the other, unless `--jobs` is given."""
+    manifest_path = tmp_pixi_workspace.joinpath("pixi.toml")
+    manifest_content = tomli.loads(EMPTY_BOILERPLATE_PROJECT)
+    manifest_content["feature"] = {
+        name: {"tasks": {"test": f"echo start-{name} && sleep 1 && echo end-{name}"}}
+        for name in ["a", "b"]
+    }
+    manifest_content["environments"] = {"a": ["a"], "b": ["b"]}
+    manifest_path.write_text(tomli_w.dumps(manifest_content))
+
+    output = verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest_path, "-e", "a", "-e", "b", "test"],
+    )
+    lines = [line for line in output.stdout.splitlines() if "start-" in line or "end-" in line]
+    assert lines == ["start-a", "end-a", "start-b", "end-b"]
+
+    # Both environments start before either of them finishes.
+    output = verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest_path, "--jobs", "2", "-e", "all", "test"],
+    )
+    lines = [line for line in output.stdout.splitlines() if "start-" in line or "end-" in line]
+    assert all(line.split()[-1].startswith("start-") for line in lines[:2])
+
+
+def test_run_environment_named_all(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    """An environment named `all` is selected by `-e all`."""
+    manifest_path = tmp_pixi_workspace.joinpath("pixi.toml")
+    manifest_content = tomli.loads(EMPTY_BOILERPLATE_PROJECT)
+    manifest_content["feature"] = {
+        "a": {"tasks": {"test": "echo in-a"}},
+        "all": {"tasks": {"test": "echo in-all"}},
+    }
+    manifest_content["environments"] = {"a": ["a"], "all": ["all"]}
+    manifest_path.write_text(tomli_w.dumps(manifest_content))
+
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest_path, "-e", "all", "test"],
+        stdout_contains="in-all",
+        stdout_excludes="in-a\n",
+    )
+
+
+def test_run_ambiguous_task(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    """The environment that was chosen for a task is printed."""
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    shared = "echo shared"
+    task = "echo default"
+    [feature.a.tasks]
+    task = "echo a"
+
+    [environments]
+    a = ["a"]
+    """
+    manifest.write_text(toml)
+
+    # The default environment shares its task with `a`
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "shared"],
+        stdout_contains="shared",
+        stderr_contains=["because it is the default environment", "--environment"],
+    )
+
+    # With `run.ambiguous = "default"` an ambiguous task runs in the default environment
+    tmp_pixi_workspace.joinpath(".pixi").mkdir(exist_ok=True)
+    tmp_pixi_workspace.joinpath(".pixi", "config.toml").write_text('[run]\nambiguous = "default"\n')
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "task"],
+        stdout_contains="default",
+        stderr_contains=["because it is the default environment", "--environment"],
+    )
+
+    # With `run.ambiguous = "error"` it fails
+    tmp_pixi_workspace.joinpath(".pixi", "config.toml").write_text('[run]\nambiguous = "error"\n')
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "task"],
+        ExitCode.FAILURE,
+        stderr_contains=["ambiguous", "default", "a"],
+    )
+
+
+@pytest.mark.slow
+@pytest.mark.parametrize(
+    "shell, package, cmd",
+    [
+        (
+            "bash",
+            "m2-bash" if sys.platform == "win32" else "bash",
+            'echo "value=$TASK_VALUE" && echo "cwd=$(pwd)"',
+        ),
+        ("nu", "nushell", 'print $"value=($env.TASK_VALUE)"; print $"cwd=(pwd)"'),
+        (
+            "powershell",
+            "powershell",
+            'Write-Output "value=$env:TASK_VALUE"; Write-Output "cwd=$((Get-Location).Path)"',
+        ),
+    ],
+)
+def test_run_task_shell(
+    pixi: Path, tmp_pixi_workspace: Path, shell: str, package: str, cmd: str
+) -> None:
+    """Every shell gets the environment variables and working directory of the task."""
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    tmp_pixi_workspace.joinpath("task-cwd").mkdir()
+    manifest_content = tomli.loads(EMPTY_BOILERPLATE_PROJECT)
+    manifest_content["workspace"]["channels"] = ["https://prefix.dev/conda-forge"]
+    manifest_content["dependencies"] = {package: "*"}
+    manifest_content["tasks"] = {
+        "task": {
+            "cmd": cmd,
+            "shell": shell,
+            "cwd": "task-cwd",
+            "env": {"TASK_VALUE": "from-the-task"},
+        }
+    }
+    manifest.write_text(tomli_w.dumps(manifest_content))
+
+    output = verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "task"],
+        stdout_contains="value=from-the-task",
+    )
+    cwd = next(line for line in output.stdout.splitlines() if line.startswith("cwd="))
+    assert cwd.rstrip("/\\").endswith("task-cwd")
+
+
+def test_run_retries(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    flaky = {{ cmd = "echo running && exit 3", retries = 2 }}
+    slow = {{ cmd = "sleep 10", timeout = "1s", retries = 1 }}
+    """
+    manifest.write_text(toml)
+    report_path = tmp_pixi_workspace / "report.json"
+
+    # Every attempt is announced, only the exit code of the last one counts
+    output = verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--report", "json", report_path, "flaky"],
+        3,
+        stderr_contains=[
+            "Task 'flaky' failed, retrying (attempt 2/3)",
+            "Task 'flaky' failed, retrying (attempt 3/3)",
+        ],
+    )
+    assert output.stdout.count("running") == 3
+    tasks = json.loads(report_path.read_text())["tasks"]
+    assert tasks[0]["attempts"] == 3
+    assert tasks[0]["exit_code"] == 3
+
+    # `--retries` overrides the retries of the task
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--retries", "0", "flaky"],
+        3,
+        stderr_excludes="retrying",
+    )
+
+    # A task that times out runs again as well
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--grace-period", "1", "slow"],
+        ExitCode.FAILURE,
+        stderr_contains="Task 'slow' failed, retrying (attempt 2/2)",
+    )
+
+
+def test_run_continue_on_error(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    lint = "exit 2"
+    typecheck = "exit 4"
+    test = "echo tested > test.txt"
+    deploy = {{ cmd = "echo deployed > deploy.txt", depends-on = ["lint"] }}
+    ci = {{ depends-on = ["lint", "typecheck", "test", "deploy"] }}
+    """
+    manifest.write_text(toml)
+    test_output = tmp_pixi_workspace / "test.txt"
+    deploy_output = tmp_pixi_workspace / "deploy.txt"
+
+    # By default the run stops at the first failure, without a summary
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--jobs", "1", "ci"],
+        2,
+        stderr_excludes="Summary:",
+    )
+    assert not test_output.exists()
+
+    # The sibling tasks still run, the dependents of the failed task are
+    # skipped and pixi exits with the exit code of the first failure
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--jobs", "1", "--continue-on-error", "ci"],
+        2,
+        stderr_contains=[
+            "Summary:",
+            "succeeded: test",
+            "failed: lint (exit code 2), typecheck (exit code 4)",
+            "skipped: deploy",
+        ],
+    )
+    assert test_output.exists()
+    assert not deploy_output.exists()
+
+
+def test_run_skip_deps(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    build = "echo build"
+    test = {{ cmd = "echo test", depends-on = ["build"] }}
+    lint = {{ cmd = "echo lint", depends-on = ["build"] }}
+    check = {{ depends-on = ["test", "lint"] }}
+    """
+    manifest.write_text(toml)
+
+    output = verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--skip-deps", "test"],
+    )
+    assert output.stdout.splitlines() == ["test"]
+
+    # The tasks of an alias run, but not their dependencies
+    output = verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--jobs", "1", "--skip-deps", "check"],
+    )
+    assert output.stdout.splitlines() == ["test", "lint"]
+
+    # Without the flag the dependencies run first
+    output = verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--jobs", "1", "check"],
+    )
+    assert output.stdout.splitlines() == ["build", "test", "lint"]
+
+
+def test_run_cwd(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    where = {{ cmd = "pwd", cwd = "task-dir" }}
+    """
+    manifest.write_text(toml)
+    task_dir = tmp_pixi_workspace / "task-dir"
+    task_dir.mkdir()
+    sub_dir = tmp_pixi_workspace / "sub"
+    sub_dir.mkdir()
+
+    output = verify_cli_command([pixi, "run", "--manifest-path", manifest, "where"])
+    assert Path(output.stdout.strip()).resolve() == task_dir.resolve()
+
+    # `--cwd` is relative to the root of the workspace and overrides the `cwd`
+    # of the task
+    output = verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--cwd", "sub", "where"],
+        cwd=task_dir,
+    )
+    assert Path(output.stdout.strip()).resolve() == sub_dir.resolve()
+
+    # A directory that doesn't exist is rejected before any task runs
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--cwd", "missing", "where"],
+        ExitCode.FAILURE,
+        stdout_excludes="task-dir",
+        stderr_contains="invalid working directory 'missing'",
+    )
+
+
+def test_run_clean_env_keep_env(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    show = "echo exact=$KEEP_ME_EXACT glob=$KEEP_ME_GLOB_1 dropped=$DROP_ME"
+    """
+    manifest.write_text(toml)
+    env = {"KEEP_ME_EXACT": "exact", "KEEP_ME_GLOB_1": "glob", "DROP_ME": "dropped"}
+
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "show"],
+        stdout_contains="exact=exact glob=glob dropped=dropped",
+        env=env,
+    )
+
+    # Only the variables that match one of the names or patterns are kept
+    verify_cli_command(
+        [
+            pixi,
+            "run",
+            "--manifest-path",
+            manifest,
+            "--clean-env",
+            "--keep-env",
+            "KEEP_ME_EXACT",
+            "--keep-env",
+            "KEEP_ME_GLOB_*",
+            "show",
+        ],
+        stdout_contains="exact=exact glob=glob dropped=",
+        stdout_excludes="dropped=dropped",
+        env=env,
+    )
+
+    # Without `--keep-env` none of them are kept
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--clean-env", "show"],
+        stdout_contains="exact= glob= dropped=",
+        env=env,
+    )
+
+
+def test_run_timeout(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    slow = "sleep 10"
+    after = {{ cmd = "echo after > after.txt", depends-on = ["slow"] }}
+    """
+    manifest.write_text(toml)
+
+    # The task is stopped and the tasks that depend on it don't run
+    started = time.monotonic()
+    verify_cli_command(
+        [
+            pixi,
+            "run",
+            "--manifest-path",
+            manifest,
+            "--timeout",
+            "1s",
+            "--grace-period",
+            "1",
+            "after",
+        ],
+        ExitCode.FAILURE,
+        stderr_contains="the task 'slow' timed out after 1.0s",
+    )
+    assert time.monotonic() - started < 10
+    assert not tmp_pixi_workspace.joinpath("after.txt").exists()
+
+
+def test_run_interactive(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    toml = f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [tasks]
+    greet = "echo hello"
+    monitor = {{ cmd = "echo monitor", interactive = true }}
+    both = {{ depends-on = ["greet", "monitor"] }}
+    """
+    manifest.write_text(toml)
+    report_path = tmp_pixi_workspace / "report.json"
+
+    # A task on its own is attached to the terminal
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--interactive", "greet"],
+        stdout_contains="hello",
+    )
+
+    # An interactive task can't run at the same time as other tasks, which is
+    # rejected before any task runs
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--jobs", "2", "both"],
+        ExitCode.FAILURE,
+        stdout_excludes="hello",
+        stderr_contains="the interactive task 'monitor' can't run at the same time as other tasks",
+    )
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--interactive", "--jobs", "2", "both"],
+        ExitCode.FAILURE,
+        stderr_contains="the interactive task 'greet' can't run at the same time as other tasks",
+    )
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--jobs", "1", "both"],
+        stdout_contains=["hello", "monitor"],
+    )
+
+    # The output of an interactive task can't be captured for the report
+    verify_cli_command(
+        [pixi, "run", "--manifest-path", manifest, "--report", "json", report_path, "monitor"],
+        ExitCode.FAILURE,
+        stdout_excludes="monitor",
+        stderr_contains="the output of the interactive task 'monitor' can't be captured",
+    )
@@ -1,7 +1,7 @@
 from pathlib import Path
 import platform
 
-from .common import ExitCode, verify_cli_command
+from .common import EMPTY_BOILERPLATE_PROJECT, ExitCode, verify_cli_command
 
 
 def test_shell_hook_completions(
@@ -72,3 +72,26 @@ def test_shell_hook_completions(
             ExitCode.SUCCESS,
             stdout_contains=["for file in", "source", "share/fish/vendor_completions.d"],
         )
+
+
+def test_shell_hook_env_files(pixi: Path, tmp_pixi_workspace: Path) -> None:
+    manifest = tmp_pixi_workspace.joinpath("pixi.toml")
+    manifest.write_text(f"""
+    {EMPTY_BOILERPLATE_PROJECT}
+    [activation]
+    env = {{ DECLARED = "from-manifest" }}
+    env-files = [".env"]
+    """)
+    tmp_pixi_workspace.joinpath(".env").write_text("FROM_ENV_FILE=hello\nDECLARED=from-file\n")
+
+    # The variables of the `.env` files are part of the activation, the
+    # variables of the manifest take precedence
+    verify_cli_command(
+        [pixi, "shell-hook", "--manifest-path", manifest, "--json"],
+        stdout_contains=['"FROM_ENV_FILE":"hello"', '"DECLARED":"from-manifest"'],
+        stdout_excludes=["from-file"],
+    )
+    verify_cli_command(
+        [pixi, "shell-hook", "--manifest-path", manifest, "--shell", "bash"],
+        stdout_contains=["FROM_ENV_FILE", "hello"],
+    )
@@ -1,10 +1,13 @@
 use std::str::FromStr;
 
+use itertools::Itertools;
 use pixi::{DependencyType, Workspace, cli::cli_config::GitRev};
+use pixi_config::PinningStrategy;
 use pixi_consts::consts;
 use pixi_manifest::{FeaturesExt, SpecType};
 use pixi_pypi_spec::{PixiPypiSpec, PypiPackageName, VersionOrStar};
 use rattler_conda_types::{PackageName, Platform};
+use rattler_lock::UrlOrPath;
 use tempfile::TempDir;
 use url::Url;
 
@@ -41,14 +44,8 @@ async fn add_functionality() {
 
     // Add a package
     pixi.add("rattler==1").await.unwrap();
-    pixi.add("rattler==2")
-        .set_type(DependencyType::CondaDependency(SpecType::Host))
-        .await
-        .unwrap();
-    pixi.add("rattler==3")
-        .set_type(DependencyType::CondaDependency(SpecType::Build))
-        .await
-        .unwrap();
+    pixi.add("rattler==2").await.unwrap();
+    pixi.add("rattler==3").await.unwrap();
 
     let lock = pixi.lock_file().await.unwrap();
     assert!(lock.contains_match_spec(
@@ -90,6 +87,7 @@ async fn add_with_channel() {
         .unwrap();
 
     pixi.add("https://prefix.dev/conda-forge::_r-mutex")
+        .with_assume_yes(true)
         .without_lockfile_update()
         .await
         .unwrap();
@@ -115,16 +113,211 @@ async fn add_with_channel() {
     );
 }
 
-/// Test that we get the union of all packages in the lockfile for the run,
-/// build and host
+/// Returns the channels of the default environment without trailing slashes.
+fn default_channels(pixi: &PixiControl) -> Vec<String> {
+    pixi.workspace()
+        .unwrap()
+        .default_environment()
+        .channels()
+        .into_iter()
+        .map(|channel| channel.to_string().trim_end_matches('/').to_string())
+        .collect()
+}
+
+/// Test that the channel of a `channel::package` spec is added to the
+/// workspace
 #[tokio::test]
-async fn add_functionality_union() {
-    let mut package_database = PackageDatabase::default();
+async fn add_with_channel_registers_channel() {
+    let mut package_database_a = PackageDatabase::default();
+    package_database_a.add_package(Package::build("bar", "1.0.0").finish());
+    let channel_a = package_database_a.into_channel().await.unwrap();
+    let mut package_database_b = PackageDatabase::default();
+    package_database_b.add_package(Package::build("foobar", "1.0.0").finish());
+    let channel_b = package_database_b.into_channel().await.unwrap();
 
-    // Add a package `foo` that depends on `bar` both set to version 1.
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().with_channel(channel_a.url()).await.unwrap();
+
+    // Without confirmation the channel is not added
+    pixi.add(&format!("{}::foobar", channel_b.url()))
+        .await
+        .unwrap_err();
+    assert_eq!(default_channels(&pixi), vec![channel_a.url().to_string()]);
+
+    pixi.add(&format!("{}::foobar", channel_b.url()))
+        .with_assume_yes(true)
+        .await
+        .unwrap();
+    assert_eq!(
+        default_channels(&pixi),
+        vec![channel_a.url().to_string(), channel_b.url().to_string()]
+    );
+
+    // The channel is kept in the spec
+    let workspace = pixi.workspace().unwrap();
+    let spec = workspace
+        .default_environment()
+        .combined_dependencies(Some(Platform::current()))
+        .get(&PackageName::try_from("foobar").unwrap())
+        .and_then(|specs| specs.first())
+        .cloned()
+        .unwrap();
+    assert_eq!(
+        spec.into_detailed()
+            .unwrap()
+            .channel
+            .unwrap()
+            .to_string()
+            .trim_end_matches('/'),
+        channel_b.url().as_str()
+    );
+}
+
+/// Test that `--prepend` adds the channel of a `channel::package` spec to the
+/// beginning of the channels of a pyproject manifest
+#[tokio::test]
+async fn add_with_channel_pyproject_prepend() {
+    let channel_a = PackageDatabase::default().into_channel().await.unwrap();
+    let channel_b = PackageDatabase::default().into_channel().await.unwrap();
+
+    let pixi = PixiControl::from_pyproject_manifest(&format!(
+        r#"
+        [project]
+        name = "channel-shorthand"
+        version = "0.1.0"
+
+        [tool.pixi.workspace]
+        channels = ["{channel_a}"]
+        platforms = ["{platform}"]
+        "#,
+        channel_a = channel_a.url(),
+        platform = Platform::current(),
+    ))
+    .unwrap();
+
+    pixi.add(&format!("{}::foobar", channel_b.url()))
+        .with_assume_yes(true)
+        .with_prepend(true)
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    assert_eq!(
+        default_channels(&pixi),
+        vec![channel_b.url().to_string(), channel_a.url().to_string()]
+    );
+
+    // Adding the spec again doesn't add the channel twice
+    pixi.add(&format!("{}::foobar", channel_b.url()))
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    assert_eq!(default_channels(&pixi).len(), 2);
+}
+
+/// Test that build and host dependencies are added to the package without
+/// updating the lock file
+#[tokio::test]
+async fn add_package_build_host_dependencies() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "test-package-dependencies"
+channels = []
+platforms = ["{platform}"]
+preview = ["pixi-build"]
+
+[package]
+name = "test-package-dependencies"
+version = "0.1.0"
+
+[package.build]
+backend = {{ name = "pixi-build-cmake", version = "*" }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    pixi.add("libcomputer")
+        .set_type(DependencyType::CondaDependency(SpecType::Host))
+        .await
+        .unwrap();
+    pixi.add("libidk>=3")
+        .set_type(DependencyType::CondaDependency(SpecType::Build))
+        .await
+        .unwrap();
+
+    let workspace = pixi.workspace().unwrap();
+    let package = &workspace.package.as_ref().unwrap().value;
+    let target = package.targets.default();
+    assert!(
+        target
+            .host_dependencies()
+            .unwrap()
+            .contains_key(&PackageName::try_from("libcomputer").unwrap())
+    );
+    assert_eq!(
+        target
+            .build_dependencies()
+            .unwrap()
+            .get(&PackageName::try_from("libidk").unwrap())
+            .unwrap()
+            .to_string(),
+        ">=3"
+    );
+
+    // The environments are not affected by the package dependencies
+    assert!(
+        workspace
+            .default_environment()
+            .dependencies(SpecType::Host, None)
+            .is_empty()
+    );
+    assert!(
+        !pixi
+            .workspace_path()
+            .join(consts::PROJECT_LOCK_FILE)
+            .exists()
+    );
+
+    // Removing them is symmetric
+    pixi.remove("libcomputer")
+        .set_type(DependencyType::CondaDependency(SpecType::Host))
+        .await
+        .unwrap();
+    let workspace = pixi.workspace().unwrap();
+    let package = &workspace.package.as_ref().unwrap().value;
+    assert!(
+        !package
+            .targets
+            .default()
+            .host_dependencies()
+            .unwrap()
+            .contains_key(&PackageName::try_from("libcomputer").unwrap())
+    );
+}
+
+/// Test that build and host dependencies can't be added without a package
+#[tokio::test]
+async fn add_build_dependency_without_package() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().await.unwrap();
+
+    let manifest_before = pixi.manifest_contents().unwrap();
+    let err = pixi
+        .add("cmake")
+        .set_type(DependencyType::CondaDependency(SpecType::Build))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not contain a package"));
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+}
+
+/// Test that `--dry-run` resolves the dependency without touching the manifest
+/// or the lock-file
+#[tokio::test]
+async fn add_dry_run() {
+    let mut package_database = PackageDatabase::default();
     package_database.add_package(Package::build("rattler", "1").finish());
-    package_database.add_package(Package::build("libcomputer", "1.2").finish());
-    package_database.add_package(Package::build("libidk", "3.1").finish());
 
     // Write the repodata to disk
     let channel_dir = TempDir::new().unwrap();
@@ -140,58 +333,319 @@ async fn add_functionality_union() {
         .await
         .unwrap();
 
-    // Add a package
-    pixi.add("rattler").await.unwrap();
-    pixi.add("libcomputer")
-        .set_type(DependencyType::CondaDependency(SpecType::Host))
-        .await
-        .unwrap();
-    pixi.add("libidk")
-        .set_type(DependencyType::CondaDependency(SpecType::Build))
-        .await
-        .unwrap();
+    let manifest_before = pixi.manifest_contents().unwrap();
 
-    // Toml should contain the correct sections
-    // We test if the toml file that is saved is correct
-    // by checking if we get the correct values back in the manifest
-    // We know this works because we test the manifest in another test
-    // Where we check if the sections are put in the correct variables
-    let project = pixi.workspace().unwrap();
+    pixi.add("rattler").dry_run(true).await.unwrap();
 
-    // Should contain all added dependencies
-    let dependencies = project
-        .default_environment()
-        .dependencies(SpecType::Run, Some(Platform::current()));
-    let (name, _) = dependencies.into_specs().next().unwrap();
-    assert_eq!(name, PackageName::try_from("rattler").unwrap());
-    let host_deps = project
-        .default_environment()
-        .dependencies(SpecType::Host, Some(Platform::current()));
-    let (name, _) = host_deps.into_specs().next().unwrap();
-    assert_eq!(name, PackageName::try_from("libcomputer").unwrap());
-    let build_deps = project
-        .default_environment()
-        .dependencies(SpecType::Build, Some(Platform::current()));
-    let (name, _) = build_deps.into_specs().next().unwrap();
-    assert_eq!(name, PackageName::try_from("libidk").unwrap());
+    // The manifest should not have been modified
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
 
-    // Lock file should contain all packages as well
+    // The lock-file should not contain the package
     let lock = pixi.lock_file().await.unwrap();
-    assert!(lock.contains_match_spec(
+    assert!(!lock.contains_conda_package(
         consts::DEFAULT_ENVIRONMENT_NAME,
         Platform::current(),
-        "rattler==1"
-    ));
-    assert!(lock.contains_match_spec(
-        consts::DEFAULT_ENVIRONMENT_NAME,
-        Platform::current(),
-        "libcomputer==1.2"
-    ));
-    assert!(lock.contains_match_spec(
-        consts::DEFAULT_ENVIRONMENT_NAME,
-        Platform::current(),
-        "libidk==3.1"
+        "rattler"
     ));
+
+    // An unsolvable spec should still result in an error
+    assert!(pixi.add("rattler>=2").dry_run(true).await.is_err());
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+}
+
+/// Test that `pixi remove --dry-run` solves without the dependency but leaves
+/// the manifest and the lock-file untouched
+#[tokio::test]
+async fn remove_dry_run() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(
+        Package::build("foo", "1")
+            .with_dependency("bar >=1")
+            .finish(),
+    );
+    package_database.add_package(Package::build("bar", "1").finish());
+    let local_channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().with_channel(local_channel.url()).await.unwrap();
+    pixi.add("foo").await.unwrap();
+
+    let manifest_before = pixi.manifest_contents().unwrap();
+    pixi.remove("foo").dry_run(true).await.unwrap();
+
+    // The manifest should not have been modified
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+
+    // The lock-file should still contain the package and its dependency
+    let lock = pixi.lock_file().await.unwrap();
+    for package in ["foo", "bar"] {
+        assert!(lock.contains_conda_package(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            package
+        ));
+    }
+}
+
+/// Test that `pixi remove --json` fails when nothing would be removed
+#[tokio::test]
+async fn remove_json() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    let local_channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().with_channel(local_channel.url()).await.unwrap();
+    pixi.add("foo").await.unwrap();
+
+    let manifest_before = pixi.manifest_contents().unwrap();
+    let err = pixi
+        .remove("bar")
+        .json(true)
+        .without_lockfile_update()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("nothing was removed"), "{err}");
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+
+    pixi.remove("foo")
+        .json(true)
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    assert!(!pixi.manifest_contents().unwrap().contains("foo"));
+}
+
+/// Test that `pixi remove --all-features` removes the dependency from every
+/// conda, pypi and platform table of all features at once
+#[tokio::test]
+async fn remove_all_features() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("pytest", "8").finish());
+    let local_channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "remove-all-features"
+channels = ["{channel}"]
+platforms = ["{platform}"]
+
+[target.{platform}.dependencies]
+pytest = "*"
+
+[feature.test.dependencies]
+pytest = "*"
+
+[feature.lint.dependencies]
+pytest = "*"
+
+[feature.lint.pypi-dependencies]
+pytest = "*"
+
+[environments]
+test = ["test"]
+lint = ["lint"]
+"#,
+        channel = local_channel.url(),
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    pixi.remove("pytest").with_all_features(true).await.unwrap();
+
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(!manifest.contains("pytest"), "{manifest}");
+
+    let lock = pixi.lock_file().await.unwrap();
+    for environment in [consts::DEFAULT_ENVIRONMENT_NAME, "test", "lint"] {
+        assert!(!lock.contains_conda_package(environment, Platform::current(), "pytest"));
+    }
+
+    // A dependency that is not found anywhere is an error
+    let err = pixi
+        .remove("pytest")
+        .with_all_features(true)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("could not find 'pytest'"));
+}
+
+/// Test that `pixi remove` matches names containing `*` or `?` against the
+/// dependencies of the selected feature
+#[tokio::test]
+async fn remove_glob() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "remove-glob"
+channels = ["https://prefix.dev/conda-forge"]
+platforms = ["{platform}"]
+
+[dependencies]
+jupyterlab = "*"
+jupyterlab-git = "*"
+jupyterlab-lsp = "*"
+jupyterlab-myst = "*"
+
+[feature.test.dependencies]
+jupyterlab-spellchecker = "*"
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    // More than one match requires a confirmation
+    let err = pixi
+        .remove("jupyterlab-*")
+        .without_lockfile_update()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("matched 3 packages"), "{err}");
+
+    pixi.remove("jupyterlab-?it")
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    pixi.remove("jupyterlab-*")
+        .with_assume_yes(true)
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(manifest.contains("jupyterlab = \"*\""), "{manifest}");
+    assert!(manifest.contains("jupyterlab-spellchecker"), "{manifest}");
+    assert!(!manifest.contains("jupyterlab-lsp"), "{manifest}");
+    assert!(!manifest.contains("jupyterlab-myst"), "{manifest}");
+
+    // A pattern that matches nothing is an error
+    let err = pixi
+        .remove("jupyterlab-*")
+        .without_lockfile_update()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no dependencies match"), "{err}");
+
+    // Other features are searched when selected
+    pixi.remove("jupyterlab-*")
+        .with_feature("test")
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    assert!(
+        !pixi
+            .manifest_contents()
+            .unwrap()
+            .contains("jupyterlab-spellchecker")
+    );
+}
+
+/// Test that `pixi remove --check-tasks` fails when a task runs an executable
+/// of the removed package
+#[tokio::test]
+async fn remove_check_tasks() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "remove-check-tasks"
+channels = ["https://prefix.dev/conda-forge"]
+platforms = ["{platform}"]
+
+[dependencies]
+pytest = "*"
+ruff = "*"
+
+[tasks]
+test = "PYTHONWARNINGS=error pytest -v"
+fmt = "ruff format"
+
+[feature.lint.dependencies]
+ruff = "*"
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let manifest_before = pixi.manifest_contents().unwrap();
+    let err = pixi
+        .remove("pytest")
+        .with_check_tasks(true)
+        .without_lockfile_update()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("'test'"), "{err}");
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+
+    // `ruff` is still provided by the lint feature
+    pixi.remove("ruff")
+        .with_check_tasks(true)
+        .without_lockfile_update()
+        .await
+        .unwrap();
+
+    // Without the flag only a warning is printed
+    pixi.remove("pytest")
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    assert!(!pixi.manifest_contents().unwrap().contains("pytest = "));
+}
+
+/// Test that `pixi remove --platform` only removes the dependency from the
+/// target table of that platform
+#[tokio::test]
+async fn remove_platform_target() {
+    let pixi = PixiControl::from_manifest(
+        r#"
+[workspace]
+name = "remove-platform"
+channels = ["https://prefix.dev/conda-forge"]
+platforms = ["linux-64", "win-64"]
+
+[dependencies]
+python = "*"
+
+[target.win-64.dependencies]
+mkl = "*"
+
+[target.linux-64.dependencies]
+mkl = "*"
+"#,
+    )
+    .unwrap();
+
+    pixi.remove("mkl")
+        .set_platforms(&[Platform::Win64])
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(!manifest.contains("target.win-64"), "{manifest}");
+    assert!(
+        manifest.contains("[target.linux-64.dependencies]\nmkl"),
+        "{manifest}"
+    );
+
+    // The package is no longer defined for the platform
+    let err = pixi
+        .remove("mkl")
+        .set_platforms(&[Platform::Win64])
+        .without_lockfile_update()
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("not a dependency of 'win-64'"),
+        "{err}"
+    );
+
+    // A dependency of all platforms can't be removed for a single one
+    assert!(
+        pixi.remove("python")
+            .set_platforms(&[Platform::Linux64])
+            .without_lockfile_update()
+            .await
+            .is_err()
+    );
 }
 
 /// Test adding a package for a specific OS
@@ -215,7 +669,7 @@ async fn add_functionality_os() {
 
     let pixi = PixiControl::new().unwrap();
 
-    pixi.init()
+    pixi.init_with_platforms(vec![Platform::LinuxS390X.to_string()])
         .with_local_channel(channel_dir.path())
         .await
         .unwrap();
@@ -223,7 +677,6 @@ async fn add_functionality_os() {
     // Add a package
     pixi.add("rattler==1")
         .set_platforms(&[Platform::LinuxS390X])
-        .set_type(DependencyType::CondaDependency(SpecType::Host))
         .await
         .unwrap();
 
@@ -235,13 +688,303 @@ async fn add_functionality_os() {
     ));
 }
 
+/// Test that adding a dependency for a platform that is not supported by the
+/// workspace fails without modifying the manifest
+#[tokio::test]
+async fn add_unsupported_platform() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(
+        Package::build("rattler", "1")
+            .with_subdir(Platform::LinuxS390X)
+            .finish(),
+    );
+    let local_channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init_with_platforms(vec![Platform::Linux64.to_string()])
+        .with_channel(local_channel.url())
+        .await
+        .unwrap();
+
+    let manifest_before = pixi.manifest_contents().unwrap();
+    let err = pixi
+        .add("rattler")
+        .set_platforms(&[Platform::LinuxS390X])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("linux-s390x"));
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+}
+
+/// Test that a failed solve points at the version constraints in the manifest
+/// and keeps the output of the solver
+#[tokio::test]
+async fn add_explains_solve_conflict() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    package_database.add_package(Package::build("foo", "2").finish());
+    package_database.add_package(
+        Package::build("bar", "1")
+            .with_dependency("foo >=2")
+            .finish(),
+    );
+    let local_channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().with_channel(local_channel.url()).await.unwrap();
+    pixi.add("foo==1").with_install(false).await.unwrap();
+
+    let manifest_before = pixi.manifest_contents().unwrap();
+    let err = pixi.add("bar").with_install(false).await.unwrap_err();
+    assert!(err.to_string().contains("cannot add `bar`"), "{err}");
+    let help = err.help().map(|help| help.to_string()).unwrap_or_default();
+    assert!(
+        help.contains("relax `foo ==1` to `>=1` in `[dependencies]`"),
+        "{help}"
+    );
+    assert!(
+        err.chain()
+            .any(|cause| cause.downcast_ref::<rattler_solve::SolveError>().is_some())
+    );
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+}
+
+/// Test that adding a pypi path dependency outside of the workspace requires
+/// `--allow-external-path`
+#[tokio::test]
+async fn add_pypi_external_path() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().await.unwrap();
+
+    let external = TempDir::new().unwrap();
+    let url = Url::from_directory_path(external.path()).unwrap();
+
+    let manifest_before = pixi.manifest_contents().unwrap();
+    let err = pixi
+        .add(&format!("mypkg @ {url}"))
+        .set_pypi(true)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("outside of the workspace"));
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+}
+
+/// Test that `--optional` is rejected for a `pixi.toml` workspace
+#[tokio::test]
+async fn add_pypi_optional_pixi_toml() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().await.unwrap();
+
+    let manifest_before = pixi.manifest_contents().unwrap();
+    let err = pixi
+        .add("pytest")
+        .set_pypi(true)
+        .with_optional("test")
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("can only be used with a `pyproject.toml` manifest")
+    );
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+}
+
+/// Test that `pixi add --optional` creates a normalized environment for a new
+/// extra of a pyproject manifest, and refuses to reuse an existing environment
+/// that doesn't include the feature of the extra
+#[tokio::test]
+async fn add_pypi_optional_pyproject() {
+    let pixi = PixiControl::from_pyproject_manifest(&format!(
+        r#"
+[project]
+name = "add-optional"
+version = "0.1.0"
+
+[tool.pixi.workspace]
+channels = ["https://prefix.dev/conda-forge"]
+platforms = ["{platform}"]
+
+[tool.pixi.feature.lint.dependencies]
+
+[tool.pixi.environments]
+test = ["lint"]
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    pixi.add("sphinx")
+        .set_pypi(true)
+        .with_optional("Dev_Tools")
+        .with_install(false)
+        .with_no_lockfile_update(true)
+        .await
+        .unwrap();
+    let workspace = pixi.workspace().unwrap();
+    let environment = workspace.environment("dev-tools").unwrap();
+    assert!(
+        environment
+            .features()
+            .any(|feature| feature.name.as_str() == "Dev_Tools")
+    );
+
+    let manifest_before = pixi.manifest_contents().unwrap();
+    let err = pixi
+        .add("pytest")
+        .set_pypi(true)
+        .with_optional("test")
+        .with_install(false)
+        .with_no_lockfile_update(true)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("the environment 'test' already exists"),
+        "{err}"
+    );
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+}
+
+/// Test that `pixi remove --optional` only removes the requirement from the
+/// group and drops the group once it is empty
+#[tokio::test]
+async fn remove_pypi_optional() {
+    let pixi = PixiControl::from_pyproject_manifest(&format!(
+        r#"
+[project]
+name = "remove-optional"
+version = "0.1.0"
+dependencies = ["sphinx"]
+
+[project.optional-dependencies]
+docs = [
+    # The documentation generator
+    "sphinx",
+    # The theme
+    "furo",
+]
+
+[tool.pixi.workspace]
+channels = ["https://prefix.dev/conda-forge"]
+platforms = ["{platform}"]
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    pixi.remove("sphinx")
+        .set_type(DependencyType::PypiDependency)
+        .with_optional("docs")
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(
+        manifest.contains(r#"dependencies = ["sphinx"]"#),
+        "{manifest}"
+    );
+    assert!(
+        manifest.contains("    # The theme\n    \"furo\","),
+        "{manifest}"
+    );
+    assert!(
+        !manifest.contains("The documentation generator"),
+        "{manifest}"
+    );
+
+    // Removing a requirement that is not part of the group fails
+    assert!(
+        pixi.remove("sphinx")
+            .set_type(DependencyType::PypiDependency)
+            .with_optional("docs")
+            .without_lockfile_update()
+            .await
+            .is_err()
+    );
+
+    // The empty group is kept when asked for
+    pixi.remove("furo")
+        .set_type(DependencyType::PypiDependency)
+        .with_optional("docs")
+        .with_keep_empty_group(true)
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    assert!(pixi.manifest_contents().unwrap().contains("docs = ["));
+}
+
+/// Test that `pixi remove --optional` only removes the environment that
+/// `pixi add --optional` created for the extra
+#[tokio::test]
+async fn remove_pypi_optional_environment() {
+    let pixi = PixiControl::from_pyproject_manifest(&format!(
+        r#"
+[project]
+name = "remove-optional-environment"
+version = "0.1.0"
+
+[project.optional-dependencies]
+test = ["pytest"]
+
+[tool.pixi.workspace]
+channels = ["https://prefix.dev/conda-forge"]
+platforms = ["{platform}"]
+
+[tool.pixi.environments]
+test = {{ features = ["test"], solve-group = "default" }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    // The environment created for the extra is removed together with the group
+    pixi.add("sphinx")
+        .set_pypi(true)
+        .with_optional("docs")
+        .with_install(false)
+        .with_no_lockfile_update(true)
+        .await
+        .unwrap();
+    assert!(pixi.workspace().unwrap().environment("docs").is_some());
+    pixi.remove("sphinx")
+        .set_type(DependencyType::PypiDependency)
+        .with_optional("docs")
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(!manifest.contains("docs"), "{manifest}");
+
+    // A user defined environment is kept, and so is the group it uses
+    pixi.remove("pytest")
+        .set_type(DependencyType::PypiDependency)
+        .with_optional("test")
+        .without_lockfile_update()
+        .await
+        .unwrap();
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(
+        manifest.contains(r#"test = { features = ["test"], solve-group = "default" }"#),
+        "{manifest}"
+    );
+    assert!(manifest.contains("test = []"), "{manifest}");
+}
+
 /// Test the `pixi add --pypi` functionality
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[cfg_attr(not(feature = "slow_integration_tests"), ignore)]
 async fn add_pypi_functionality() {
     let pixi = PixiControl::new().unwrap();
 
-    pixi.init().await.unwrap();
+    pixi.init_with_platforms(
+        [Platform::current(), Platform::Osx64, Platform::Linux64]
+            .into_iter()
+            .unique()
+            .map(|p| p.to_string())
+            .collect(),
+    )
+    .await
+    .unwrap();
 
     // Add python
     pixi.add("python~=3.12.0")
@@ -651,6 +1394,166 @@ async fn add_dependency_pinning_strategy() {
     assert_eq!(bar_spec, r#"">=1,<2""#);
 }
 
+/// Test that the pinning strategy passed on the command line is used for the
+/// spec written to the manifest
+#[tokio::test]
+async fn add_with_pinning_strategy() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foobar", "1.2.3").finish());
+    package_database.add_package(Package::build("bar", "0.4.2").finish());
+    package_database.add_package(Package::build("baz", "2.1").finish());
+    let local_channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().with_channel(local_channel.url()).await.unwrap();
+
+    pixi.add("foobar")
+        .with_pinning_strategy(PinningStrategy::ExactVersion)
+        .await
+        .unwrap();
+    pixi.add("bar")
+        .with_pinning_strategy(PinningStrategy::Minor)
+        .await
+        .unwrap();
+    pixi.add("baz")
+        .with_pinning_strategy(PinningStrategy::NoPin)
+        .await
+        .unwrap();
+
+    let workspace = pixi.workspace().unwrap();
+    let dependencies = workspace
+        .workspace
+        .value
+        .default_feature()
+        .combined_dependencies(None)
+        .unwrap_or_default();
+    let specs = ["foobar", "bar", "baz"]
+        .into_iter()
+        .map(|name| {
+            format!(
+                "{name} = {}",
+                dependencies.get(name).unwrap().to_toml_value()
+            )
+        })
+        .join("\n");
+
+    insta::assert_snapshot!(specs, @r###"
+    foobar = "==1.2.3"
+    bar = ">=0.4.2,<0.5"
+    baz = "*"
+    "###);
+}
+
+/// Test that `--no-pin` keeps the specs as they were passed while still
+/// locking the packages
+#[tokio::test]
+async fn add_with_no_pin() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foobar", "1.2.3").finish());
+    package_database.add_package(Package::build("bar", "0.4.2").finish());
+    let local_channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().with_channel(local_channel.url()).await.unwrap();
+
+    pixi.add_multiple(vec!["foobar", "bar>=0.4"])
+        .with_feature("tools")
+        .with_no_pin(true)
+        .await
+        .unwrap();
+
+    let workspace = pixi.workspace().unwrap();
+    let dependencies = workspace
+        .workspace
+        .value
+        .feature("tools")
+        .unwrap()
+        .combined_dependencies(None)
+        .unwrap_or_default();
+    let specs = ["foobar", "bar"]
+        .into_iter()
+        .map(|name| {
+            format!(
+                "{name} = {}",
+                dependencies.get(name).unwrap().to_toml_value()
+            )
+        })
+        .join("\n");
+
+    insta::assert_snapshot!(specs, @r###"
+    foobar = "*"
+    bar = ">=0.4"
+    "###);
+}
+
+/// Test adding a dependency to multiple features with a single invocation
+#[tokio::test]
+async fn add_to_multiple_features() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("pytest", "8").finish());
+    let local_channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "multiple-features"
+channels = ["{channel}"]
+platforms = ["{platform}"]
+
+[feature.test.dependencies]
+
+[feature.lint.dependencies]
+
+[environments]
+test = ["test"]
+lint = ["lint"]
+"#,
+        channel = local_channel.url(),
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    pixi.add("pytest")
+        .with_feature("test")
+        .with_feature("lint")
+        .await
+        .unwrap();
+
+    // Both features should contain the dependency
+    let workspace = pixi.workspace().unwrap();
+    for feature in ["test", "lint"] {
+        assert!(
+            workspace
+                .workspace
+                .value
+                .feature(feature)
+                .unwrap()
+                .combined_dependencies(None)
+                .unwrap_or_default()
+                .get("pytest")
+                .is_some(),
+            "pytest is missing from feature {feature}"
+        );
+    }
+
+    // Both environments should be locked in the same update
+    let lock = pixi.lock_file().await.unwrap();
+    for environment in ["test", "lint"] {
+        assert!(lock.contains_conda_package(environment, Platform::current(), "pytest"));
+    }
+
+    // Unknown features are reported and nothing is written
+    let manifest_before = pixi.manifest_contents().unwrap();
+    let err = pixi
+        .add("pytest")
+        .with_feature("test")
+        .with_feature("docs")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("docs"));
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest_before);
+}
+
 /// Test adding a git dependency with a specific branch
 #[tokio::test]
 #[cfg_attr(not(feature = "online_tests"), ignore)]
@@ -950,6 +1853,88 @@ platforms = ["{platform}"]
     });
 }
 
+/// Test adding an editable git dependency, which is checked out in the
+/// environment
+#[tokio::test]
+#[cfg_attr(not(feature = "online_tests"), ignore)]
+async fn add_pypi_git_editable() {
+    let pixi = PixiControl::from_manifest(
+        format!(
+            r#"
+[project]
+name = "test-git-editable"
+channels = ["https://prefix.dev/conda-forge"]
+platforms = ["{platform}"]
+
+"#,
+            platform = Platform::current()
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    pixi.add("python>=3.13.2,<3.14").await.unwrap();
+    pixi.add("boltons")
+        .set_pypi(true)
+        .set_editable(true)
+        .with_git_url(Url::parse("https://github.com/mahmoud/boltons.git").unwrap())
+        .await
+        .unwrap();
+
+    // The manifest records the editable flag
+    let project = Workspace::from_path(pixi.manifest_path().as_path()).unwrap();
+    let (_, spec) = project
+        .default_environment()
+        .pypi_dependencies(None)
+        .into_specs()
+        .find(|(name, _)| *name == PypiPackageName::from_str("boltons").unwrap())
+        .unwrap();
+    assert!(matches!(
+        spec,
+        PixiPypiSpec::Git {
+            editable: Some(true),
+            ..
+        }
+    ));
+
+    // The lock file contains the git url with the resolved commit
+    let lock_file = pixi.lock_file().await.unwrap();
+    let (boltons, _) = lock_file
+        .default_environment()
+        .unwrap()
+        .pypi_packages(Platform::current())
+        .unwrap()
+        .find(|(p, _)| p.name.to_string() == "boltons")
+        .unwrap();
+    assert!(boltons.editable);
+    let location = lock_file
+        .get_pypi_package_url(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            "boltons",
+        )
+        .unwrap();
+    let UrlOrPath::Url(url) = location else {
+        panic!("expected a git url, got {location}");
+    };
+    assert!(
+        url.as_str()
+            .starts_with("git+https://github.com/mahmoud/boltons.git")
+    );
+    assert!(url.fragment().is_some());
+
+    // The package is checked out in the environment
+    pixi.install().await.unwrap();
+    assert!(
+        pixi.default_env_path()
+            .unwrap()
+            .join("src")
+            .join("boltons")
+            .join(".git")
+            .exists()
+    );
+}
+
 #[tokio::test]
 async fn add_dependency_dont_create_project() {
     // Create a channel with two packages
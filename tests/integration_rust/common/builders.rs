@@ -25,7 +25,7 @@
 
 use pixi::cli::{
     cli_config::{GitRev, LockFileUpdateConfig, PrefixUpdateConfig, WorkspaceConfig},
-    lock,
+    lock::{self, GraphFormat, LockFileFormat},
 };
 use std::{
     future::{Future, IntoFuture},
@@ -42,8 +42,9 @@ use pixi::{
         add, cli_config::DependencyConfig, init, install, remove, search, task, update, workspace,
     },
 };
-use pixi_manifest::{EnvironmentName, FeatureName, SpecType, task::Dependency};
-use rattler_conda_types::{NamedChannelOrUrl, Platform, RepoDataRecord};
+use pixi_config::PinningStrategy;
+use pixi_manifest::{ChannelPriority, EnvironmentName, FeatureName, SpecType, task::Dependency};
+use rattler_conda_types::{NamedChannelOrUrl, PackageName, Platform, RepoDataRecord};
 use url::Url;
 
 /// Strings from an iterator
@@ -67,6 +68,36 @@ impl InitBuilder {
         }
     }
 
+    /// Set the name of the workspace
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.args.name = Some(name.to_string());
+        self
+    }
+
+    /// Set the description of the workspace
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.args.description = Some(description.to_string());
+        self
+    }
+
+    /// Set the version of the workspace
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.args.version = Some(version.parse().unwrap());
+        self
+    }
+
+    /// Create an empty feature and an environment for it
+    pub fn with_feature(mut self, feature: &str) -> Self {
+        self.args.features.push(feature.into());
+        self
+    }
+
+    /// Don't include the default feature in the environments of the features
+    pub fn without_default_feature(mut self) -> Self {
+        self.args.no_default_feature = true;
+        self
+    }
+
     pub fn with_channel(mut self, channel: impl ToString) -> Self {
         self.args
             .channels
@@ -84,11 +115,80 @@ impl InitBuilder {
         self
     }
 
+    /// Set the channel priority of the workspace
+    pub fn with_channel_priority(mut self, channel_priority: ChannelPriority) -> Self {
+        self.args.channel_priority = Some(channel_priority);
+        self
+    }
+
     /// Instruct init which manifest format to use
     pub fn with_format(mut self, format: init::ManifestFormat) -> Self {
         self.args.format = Some(format);
         self
     }
+
+    /// Don't write the `.gitignore` and `.gitattributes` files
+    pub fn without_scm_files(mut self) -> Self {
+        self.args.scm = None;
+        self.args.no_scm_files = true;
+        self
+    }
+
+    /// Set the source control management, `None` detects it
+    pub fn with_scm(mut self, scm: Option<init::GitAttributes>) -> Self {
+        self.args.scm = scm;
+        self
+    }
+
+    /// Import an environment file, `path[:feature]`
+    pub fn with_import(mut self, import: &str) -> Self {
+        self.args.env_files.push(import.parse().unwrap());
+        self
+    }
+
+    /// Import a pip requirements file
+    pub fn with_import_requirements(mut self, path: impl AsRef<Path>) -> Self {
+        self.args.requirements_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Don't solve the lock-file after importing a requirements file
+    pub fn without_lockfile_update(mut self) -> Self {
+        self.args.no_lockfile_update = true;
+        self
+    }
+
+    /// Create the workspace from a template
+    pub fn with_template(mut self, template: &str) -> Self {
+        self.args.template = Some(template.parse().unwrap());
+        self
+    }
+
+    /// Add tasks for the console scripts of an existing pyproject.toml
+    pub fn with_import_scripts(mut self) -> Self {
+        self.args.import_scripts = true;
+        self
+    }
+
+    /// Create a package in the directory and add it to the workspace
+    pub fn with_workspace_member(mut self, member: impl AsRef<Path>) -> Self {
+        self.args
+            .workspace_members
+            .push(member.as_ref().to_path_buf());
+        self
+    }
+
+    /// Don't access the network while creating the workspace
+    pub fn offline(mut self) -> Self {
+        self.args.offline = true;
+        self
+    }
+
+    /// Create the workspace from an existing conda environment
+    pub fn with_from_prefix(mut self, prefix: impl AsRef<Path>) -> Self {
+        self.args.from_prefix = Some(prefix.as_ref().to_path_buf());
+        self
+    }
 }
 
 impl IntoFuture for InitBuilder {
@@ -149,7 +249,7 @@ pub trait HasDependencyConfig: Sized {
             build: false,
             pypi: false,
             platforms: Default::default(),
-            feature: Default::default(),
+            features: Default::default(),
             git: Default::default(),
             rev: Default::default(),
             subdir: Default::default(),
@@ -211,7 +311,10 @@ impl AddBuilder {
     }
 
     pub fn with_feature(mut self, feature: impl ToString) -> Self {
-        self.args.dependency_config.feature = FeatureName::from(feature.to_string());
+        self.args
+            .dependency_config
+            .features
+            .push(FeatureName::from(feature.to_string()));
         self
     }
 
@@ -239,6 +342,41 @@ impl AddBuilder {
         self.args.lock_file_update_config.no_lockfile_update = no_lockfile_update;
         self
     }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.args.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_pinning_strategy(mut self, pinning_strategy: PinningStrategy) -> Self {
+        self.args.pinning_strategy = Some(pinning_strategy);
+        self
+    }
+
+    pub fn with_no_pin(mut self, no_pin: bool) -> Self {
+        self.args.no_pin = no_pin;
+        self
+    }
+
+    pub fn allow_external_path(mut self, allow_external_path: bool) -> Self {
+        self.args.allow_external_path = allow_external_path;
+        self
+    }
+
+    pub fn with_optional(mut self, extra: &str) -> Self {
+        self.args.optional = Some(extra.to_string());
+        self
+    }
+
+    pub fn with_assume_yes(mut self, assume_yes: bool) -> Self {
+        self.args.assume_yes = assume_yes;
+        self
+    }
+
+    pub fn with_prepend(mut self, prepend: bool) -> Self {
+        self.args.prepend = prepend;
+        self
+    }
 }
 
 impl HasDependencyConfig for AddBuilder {
@@ -292,6 +430,51 @@ pub struct RemoveBuilder {
     pub args: remove::Args,
 }
 
+impl RemoveBuilder {
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.args.dry_run = dry_run;
+        self
+    }
+
+    pub fn json(mut self, json: bool) -> Self {
+        self.args.json = json;
+        self
+    }
+
+    pub fn with_feature(mut self, feature: impl ToString) -> Self {
+        self.args
+            .dependency_config
+            .features
+            .push(FeatureName::from(feature.to_string()));
+        self
+    }
+
+    pub fn with_all_features(mut self, all_features: bool) -> Self {
+        self.args.all_features = all_features;
+        self
+    }
+
+    pub fn with_optional(mut self, extra: &str) -> Self {
+        self.args.optional = Some(extra.to_string());
+        self
+    }
+
+    pub fn with_keep_empty_group(mut self, keep_empty_group: bool) -> Self {
+        self.args.keep_empty_group = keep_empty_group;
+        self
+    }
+
+    pub fn with_assume_yes(mut self, assume_yes: bool) -> Self {
+        self.args.assume_yes = assume_yes;
+        self
+    }
+
+    pub fn with_check_tasks(mut self, check_tasks: bool) -> Self {
+        self.args.check_tasks = check_tasks;
+        self
+    }
+}
+
 impl HasDependencyConfig for RemoveBuilder {
     fn dependency_config(&mut self) -> &mut DependencyConfig {
         &mut self.args.dependency_config
@@ -304,6 +487,12 @@ impl HasPrefixUpdateConfig for RemoveBuilder {
     }
 }
 
+impl HasLockFileUpdateConfig for RemoveBuilder {
+    fn lock_file_update_config(&mut self) -> &mut LockFileUpdateConfig {
+        &mut self.args.lock_file_update_config
+    }
+}
+
 impl IntoFuture for RemoveBuilder {
     type Output = miette::Result<()>;
     type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + 'static>>;
@@ -453,6 +642,18 @@ pub struct InstallBuilder {
 }
 
 impl InstallBuilder {
+    /// Install the environment, can be called multiple times
+    pub fn with_environment(mut self, environment: impl Into<String>) -> Self {
+        self.args.environment.push(environment.into());
+        self
+    }
+
+    /// Install all environments
+    pub fn with_all(mut self) -> Self {
+        self.args.all = true;
+        self
+    }
+
     pub fn with_locked(mut self) -> Self {
         self.args.lock_file_usage.locked = true;
         self
@@ -461,6 +662,38 @@ impl InstallBuilder {
         self.args.lock_file_usage.frozen = true;
         self
     }
+
+    /// Skip a locked conda package, can be called multiple times
+    pub fn with_skip(mut self, package: &str) -> Self {
+        self.args
+            .skip
+            .push(PackageName::from_str(package).expect("invalid package name"));
+        self
+    }
+
+    /// Limit the number of environments that are installed at once
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.args.concurrency = Some(concurrency);
+        self
+    }
+
+    /// Install into the given directory instead of the workspace
+    pub fn with_target_prefix(mut self, target_prefix: impl Into<PathBuf>) -> Self {
+        self.args.target_prefix = Some(target_prefix.into());
+        self
+    }
+
+    /// Install into a target prefix that is not empty
+    pub fn with_force(mut self) -> Self {
+        self.args.force = true;
+        self
+    }
+
+    /// Check the content of the installed files and repair corrupted packages
+    pub fn with_verify(mut self) -> Self {
+        self.args.verify = true;
+        self
+    }
 }
 
 impl IntoFuture for InstallBuilder {
@@ -549,6 +782,30 @@ impl UpdateBuilder {
         self
     }
 
+    pub fn with_conservative(mut self) -> Self {
+        self.args.specs.conservative = true;
+        self
+    }
+
+    pub fn with_patch(mut self) -> Self {
+        self.args.specs.patch = true;
+        self
+    }
+
+    pub fn with_exclude(mut self, package: impl ToString) -> Self {
+        self.args
+            .specs
+            .exclude
+            .get_or_insert_with(Vec::new)
+            .push(package.to_string());
+        self
+    }
+
+    pub fn with_refresh_metadata(mut self) -> Self {
+        self.args.refresh_metadata = true;
+        self
+    }
+
     pub fn dry_run(mut self, dry_run: bool) -> Self {
         self.args.dry_run = dry_run;
         self
@@ -574,6 +831,81 @@ pub struct LockBuilder {
     pub args: lock::Args,
 }
 
+impl LockBuilder {
+    pub fn with_environment(mut self, env: impl Into<EnvironmentName>) -> Self {
+        self.args
+            .environments
+            .get_or_insert_with(Vec::new)
+            .push(env.into());
+        self
+    }
+
+    pub fn with_platform(mut self, platform: Platform) -> Self {
+        self.args
+            .platforms
+            .get_or_insert_with(Vec::new)
+            .push(platform);
+        self
+    }
+
+    /// Exports the lock-file of the selected environment to a conda-lock file.
+    pub fn with_conda_lock_output(mut self, output: impl Into<PathBuf>) -> Self {
+        self.args.format = LockFileFormat::CondaLock;
+        self.args.output = Some(output.into());
+        self
+    }
+
+    /// Seeds the lock-file of the selected environment from a conda-lock file.
+    pub fn with_import(mut self, path: impl Into<PathBuf>) -> Self {
+        self.args.import = Some(path.into());
+        self
+    }
+
+    /// Rewrites the lock-file in canonical form without solving it.
+    pub fn with_reformat(mut self) -> Self {
+        self.args.reformat = true;
+        self
+    }
+
+    /// Checks the locked artifacts instead of updating the lock-file.
+    pub fn with_verify_hashes(mut self, deep: bool) -> Self {
+        self.args.verify_hashes = true;
+        self.args.deep = deep;
+        self
+    }
+
+    /// Only use the cached repodata to update the lock-file.
+    pub fn with_offline(mut self) -> Self {
+        self.args.network_config.offline = true;
+        self
+    }
+
+    /// Writes the dependency graph of the lock-file to `output`.
+    pub fn with_graph(mut self, format: GraphFormat, output: impl Into<PathBuf>) -> Self {
+        self.args.graph = Some(format);
+        self.args.output = Some(output.into());
+        self
+    }
+
+    /// Restricts the dependency graph to the packages reachable from `package`.
+    pub fn with_graph_package(mut self, package: impl ToString) -> Self {
+        self.args.package = Some(package.to_string());
+        self
+    }
+
+    /// Solve every environment and platform again from scratch.
+    pub fn with_force_all(mut self) -> Self {
+        self.args.force_all = true;
+        self
+    }
+
+    /// Keep the stale environments and platforms in the lock-file.
+    pub fn with_no_prune(mut self) -> Self {
+        self.args.no_prune = true;
+        self
+    }
+}
+
 impl IntoFuture for LockBuilder {
     type Output = miette::Result<()>;
     type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + 'static>>;
@@ -5,14 +5,19 @@ pub mod client;
 pub mod package_database;
 
 use std::{
+    collections::{HashMap, hash_map::Entry},
     ffi::OsString,
     path::{Path, PathBuf},
     process::Output,
     str::FromStr,
+    time::{Duration, Instant},
 };
 
 use builders::{LockBuilder, SearchBuilder};
+use deno_task_shell::KillSignal;
+use futures::{StreamExt, stream::FuturesUnordered};
 use indicatif::ProgressDrawTarget;
+use itertools::Itertools;
 use miette::{Context, Diagnostic, IntoDiagnostic};
 use pixi::{
     UpdateLockFileOptions, Workspace,
@@ -21,14 +26,16 @@ use pixi::{
         cli_config::{ChannelsConfig, LockFileUpdateConfig, PrefixUpdateConfig, WorkspaceConfig},
         init::{self, GitAttributes},
         install::Args,
-        lock, remove, run, search,
+        lock::{self, LockFileFormat},
+        remove, run, search,
         task::{self, AddArgs, AliasArgs},
         update, workspace,
     },
-    lock_file::{ReinstallPackages, UpdateMode},
+    lock_file::{InstallFilter, ReinstallPackages, UpdateMode},
     task::{
-        ExecutableTask, RunOutput, SearchEnvironments, TaskExecutionError, TaskGraph,
-        TaskGraphError, TaskName, get_task_env,
+        CanSkip, ExecutableTask, RunOutput, SearchEnvironments, TaskExecutionError, TaskGraph,
+        TaskGraphError, TaskName, TaskOutput, TaskScheduler, get_task_env,
+        resolve_working_directory,
     },
 };
 use pixi_consts::consts;
@@ -318,12 +325,26 @@ impl PixiControl {
             no_fast_prefix: false,
             args: init::Args {
                 path: self.workspace_path().to_path_buf(),
+                name: None,
+                description: None,
+                version: None,
+                features: Vec::new(),
+                no_default_feature: false,
                 channels: None,
+                channel_priority: None,
                 platforms: Vec::new(),
-                env_file: None,
+                env_files: Vec::new(),
+                requirements_file: None,
+                no_lockfile_update: false,
+                template: None,
+                from_prefix: None,
+                import_scripts: false,
+                workspace_members: Vec::new(),
+                offline: false,
                 format: None,
                 pyproject_toml: false,
                 scm: Some(GitAttributes::Github),
+                no_scm_files: false,
             },
         }
     }
@@ -336,12 +357,26 @@ impl PixiControl {
             no_fast_prefix: false,
             args: init::Args {
                 path: self.workspace_path().to_path_buf(),
+                name: None,
+                description: None,
+                version: None,
+                features: Vec::new(),
+                no_default_feature: false,
                 channels: None,
+                channel_priority: None,
                 platforms,
-                env_file: None,
+                env_files: Vec::new(),
+                requirements_file: None,
+                no_lockfile_update: false,
+                template: None,
+                from_prefix: None,
+                import_scripts: false,
+                workspace_members: Vec::new(),
+                offline: false,
                 format: None,
                 pyproject_toml: false,
                 scm: Some(GitAttributes::Github),
+                no_scm_files: false,
             },
         }
     }
@@ -371,7 +406,18 @@ impl PixiControl {
                     lock_file_usage: LockFileUsageConfig::default(),
                 },
                 config: Default::default(),
+                network_config: Default::default(),
                 editable: false,
+                dry_run: false,
+                from_file: None,
+                pinning_strategy: None,
+                no_pin: false,
+                allow_external_path: false,
+                json: false,
+                optional: None,
+                no_suggestions: false,
+                assume_yes: false,
+                prepend: false,
             },
         }
     }
@@ -409,6 +455,14 @@ impl PixiControl {
                     lock_file_usage: LockFileUsageConfig::default(),
                 },
                 config: Default::default(),
+                network_config: Default::default(),
+                json: false,
+                dry_run: false,
+                all_features: false,
+                optional: None,
+                keep_empty_group: false,
+                assume_yes: false,
+                check_tasks: false,
             },
         }
     }
@@ -476,7 +530,16 @@ impl PixiControl {
     }
 
     /// Run a command
-    pub async fn run(&self, mut args: run::Args) -> miette::Result<RunOutput> {
+    pub async fn run(&self, args: run::Args) -> miette::Result<RunOutput> {
+        self.run_with_kill_signal(args, KillSignal::default()).await
+    }
+
+    /// Run a command, sending the `kill_signal` interrupts the running tasks
+    pub async fn run_with_kill_signal(
+        &self,
+        mut args: run::Args,
+        kill_signal: KillSignal,
+    ) -> miette::Result<RunOutput> {
         args.workspace_config.manifest_path = args
             .workspace_config
             .manifest_path
@@ -485,17 +548,30 @@ impl PixiControl {
         // Load the project
         let project = self.workspace()?;
 
-        // Extract the passed in environment name.
-        let explicit_environment = args
-            .environment
-            .map(|n| EnvironmentName::from_str(n.as_str()))
-            .transpose()?
-            .map(|n| {
+        // The environments to run the tasks in, `None` for the environments
+        // that define the tasks.
+        let explicit_environments = match args.environment.as_slice() {
+            [] => vec![None],
+            [all] if all == "all" => {
+                let name = TaskName::from(args.task[0].clone());
                 project
-                    .environment(&n)
-                    .ok_or_else(|| miette::miette!("unknown environment '{n}'"))
-            })
-            .transpose()?;
+                    .environments_with_task(&name)
+                    .into_iter()
+                    .map(Some)
+                    .collect()
+            }
+            names => names
+                .iter()
+                .map(|n| {
+                    let n = EnvironmentName::from_str(n.as_str())?;
+                    project
+                        .environment(&n)
+                        .map(Some)
+                        .ok_or_else(|| miette::miette!("unknown environment '{n}'"))
+                })
+                .collect::<miette::Result<Vec<_>>>()?,
+        };
+        let multiple_environments = explicit_environments.len() > 1;
 
         // Ensure the lock-file is up-to-date
         let lock_file = project
@@ -505,54 +581,176 @@ impl PixiControl {
             })
             .await?;
 
-        // Create a task graph from the command line arguments.
-        let search_env = SearchEnvironments::from_opt_env(
-            &project,
-            explicit_environment.clone(),
-            explicit_environment
-                .as_ref()
-                .map(|e| e.best_platform())
-                .or(Some(Platform::current())),
-        );
-        let task_graph = TaskGraph::from_cmd_args(&project, &search_env, args.task, false)
-            .map_err(RunError::TaskGraphError)?;
-
-        // Iterate over all tasks in the graph and execute them.
-        let mut task_env = None;
+        let cwd_override = args
+            .cwd
+            .as_deref()
+            .map(|cwd| resolve_working_directory(project.root(), cwd))
+            .transpose()
+            .map_err(TaskExecutionError::from)?;
+
+        // The tasks run in one environment after the other, a failure in one
+        // environment doesn't stop the others.
         let mut result = RunOutput::default();
-        for task_id in task_graph.topological_order() {
-            let task = ExecutableTask::from_task_graph(&task_graph, task_id);
-
-            // Construct the task environment if not already created.
-            let task_env = match task_env.as_ref() {
-                None => {
-                    lock_file
-                        .prefix(
-                            &task.run_environment,
-                            UpdateMode::Revalidate,
-                            &ReinstallPackages::default(),
-                        )
-                        .await?;
-                    let env =
-                        get_task_env(&task.run_environment, args.clean_env, None, false, false)
+        let mut failures = Vec::new();
+        for explicit_environment in explicit_environments {
+            // Create a task graph from the command line arguments.
+            let search_env = SearchEnvironments::from_opt_env(
+                &project,
+                explicit_environment.clone(),
+                explicit_environment
+                    .as_ref()
+                    .map(|e| e.best_platform())
+                    .or(Some(Platform::current())),
+            );
+            let task_graph =
+                TaskGraph::from_cmd_args(&project, &search_env, args.task.clone(), args.skip_deps)
+                    .map_err(RunError::TaskGraphError)?;
+            // Execute the tasks of the graph, the tasks whose dependencies have
+            // finished run concurrently.
+            let jobs = args.jobs();
+            let force = args.force;
+            let mut scheduler = TaskScheduler::new(&task_graph);
+            let mut running = FuturesUnordered::new();
+            let mut task_envs = HashMap::new();
+            let mut outputs = Vec::new();
+            let mut env_failures = Vec::new();
+            loop {
+                while running.len() < jobs {
+                    let Some(task_id) = scheduler.next_ready() else {
+                        break;
+                    };
+                    let task = ExecutableTask::from_task_graph(&task_graph, task_id)
+                        .with_cwd_override(cwd_override.clone())
+                        .with_timeout_override(args.timeout)
+                        .with_shell_override(args.shell)
+                        .with_interactive_override(args.interactive)
+                        .with_retries_override(args.retries)
+                        .with_grace_period(Duration::from_secs(args.grace_period))
+                        // The tests run pixi as a library, the tasks of the
+                        // built-in shell run with the pixi binary.
+                        .with_pixi_executable(Some(PathBuf::from(env!("CARGO_BIN_EXE_pixi"))));
+                    if task.skipped {
+                        scheduler.finish(task_id);
+                        continue;
+                    }
+
+                    // Construct the task environment if not already created,
+                    // per environment and clean environment like the CLI does.
+                    let keep_env = task.clean_env_keep(args.clean_env, &args.keep_env);
+                    let key = (task.run_environment.name().clone(), keep_env);
+                    let task_env = match task_envs.entry(key) {
+                        Entry::Vacant(entry) => {
+                            lock_file
+                                .prefix(
+                                    &task.run_environment,
+                                    UpdateMode::Revalidate,
+                                    &ReinstallPackages::default(),
+                                    &InstallFilter::default(),
+                                )
+                                .await?;
+                            let env = get_task_env(
+                                &task.run_environment,
+                                entry.key().1.is_some(),
+                                entry.key().1.as_deref().unwrap_or_default(),
+                                None,
+                                false,
+                                false,
+                            )
                             .await?;
-                    task_env.insert(env)
+                            entry.insert(env)
+                        }
+                        Entry::Occupied(entry) => entry.into_mut(),
+                    };
+
+                    let mut task_env = task_env.clone();
+                    task.extend_with_env_files(&mut task_env)
+                        .into_diagnostic()?;
+                    let task_env: HashMap<OsString, OsString> = task_env
+                        .into_iter()
+                        .map(|(k, v)| (OsString::from(k), OsString::from(v)))
+                        .collect();
+
+                    let continue_on_error =
+                        args.continue_on_error || task.task().continue_on_error();
+                    let started = outputs.len() + running.len();
+                    let locked = lock_file.as_lock_file();
+                    let kill_signal = kill_signal.clone();
+                    running.push(async move {
+                        let report = TaskOutput {
+                            started_at: Some(chrono::Utc::now()),
+                            ..TaskOutput::for_task(&task)
+                        };
+                        let start = Instant::now();
+                        let output =
+                            run_cached_task(&task, &task_env, locked, force, kill_signal).await;
+                        let report = TaskOutput {
+                            duration: start.elapsed(),
+                            ..report
+                        };
+                        (started, task_id, continue_on_error, report, output)
+                    });
                 }
-                Some(task_env) => task_env,
-            };
 
-            let task_env = task_env
-                .iter()
-                .map(|(k, v)| (OsString::from(k), OsString::from(v)))
-                .collect();
-
-            let output = task.execute_with_pipes(&task_env, None).await?;
-            result.stdout.push_str(&output.stdout);
-            result.stderr.push_str(&output.stderr);
-            result.exit_code = output.exit_code;
-            if output.exit_code != 0 {
-                return Err(RunError::NonZeroExitCode(output.exit_code).into());
+                let Some((started, task_id, continue_on_error, report, output)) =
+                    running.next().await
+                else {
+                    break;
+                };
+                scheduler.finish(task_id);
+                let (output, attempts) = output?;
+                if output.exit_code != 0 {
+                    env_failures.push(TaskFailure {
+                        name: report.name.clone(),
+                        exit_code: output.exit_code,
+                    });
+                    if continue_on_error {
+                        scheduler.skip_dependents(task_id);
+                    } else {
+                        scheduler.cancel();
+                    }
+                }
+                outputs.push((
+                    started,
+                    TaskOutput {
+                        exit_code: output.exit_code,
+                        stdout: output.stdout,
+                        stderr: output.stderr,
+                        cache_hit: attempts == 0,
+                        attempts,
+                        ..report
+                    },
+                ));
             }
+
+            // The output is combined in the order in which the tasks were started,
+            // so that it doesn't depend on which of the concurrent tasks finished
+            // first.
+            let mut env_result = RunOutput::default();
+            for (_, output) in outputs.into_iter().sorted_by_key(|(started, _)| *started) {
+                env_result.stdout.push_str(&output.stdout);
+                env_result.stderr.push_str(&output.stderr);
+                env_result.exit_code = output.exit_code;
+                env_result.tasks.push(output);
+            }
+
+            // The output of all the environments is combined as well, in the
+            // order of the environments.
+            result.stdout.push_str(&env_result.stdout);
+            result.stderr.push_str(&env_result.stderr);
+            result.exit_code = env_failures
+                .first()
+                .map_or(env_result.exit_code, |failure| failure.exit_code);
+            result.tasks.extend(env_result.tasks.iter().cloned());
+            if let Some(environment) = explicit_environment.filter(|_| multiple_environments) {
+                result
+                    .environments
+                    .push((environment.name().clone(), env_result));
+            }
+            failures.extend(env_failures);
+        }
+
+        if !failures.is_empty() {
+            return Err(RunError::NonZeroExitCode(failures).into());
         }
 
         Ok(result)
@@ -563,7 +761,7 @@ impl PixiControl {
     pub fn install(&self) -> InstallBuilder {
         InstallBuilder {
             args: Args {
-                environment: None,
+                environment: Vec::new(),
                 project_config: WorkspaceConfig {
                     manifest_path: Some(self.manifest_path()),
                 },
@@ -572,7 +770,17 @@ impl PixiControl {
                     locked: false,
                 },
                 config: Default::default(),
+                network_config: Default::default(),
+                reinstall_config: Default::default(),
                 all: false,
+                skip: Vec::new(),
+                output_format: Default::default(),
+                no_hooks: false,
+                concurrency: None,
+                target_prefix: None,
+                force: false,
+                verify: false,
+                dry_run: false,
             },
         }
     }
@@ -583,6 +791,7 @@ impl PixiControl {
         UpdateBuilder {
             args: update::Args {
                 config: Default::default(),
+                network_config: Default::default(),
                 project_config: WorkspaceConfig {
                     manifest_path: Some(self.manifest_path()),
                 },
@@ -590,6 +799,9 @@ impl PixiControl {
                 dry_run: false,
                 specs: Default::default(),
                 json: false,
+                interactive: false,
+                refresh_metadata: false,
+                direct_only: false,
             },
         }
     }
@@ -622,7 +834,24 @@ impl PixiControl {
                     manifest_path: Some(self.manifest_path()),
                 },
                 check: false,
+                check_availability: false,
+                refresh: false,
                 json: false,
+                environments: None,
+                platforms: None,
+                format: LockFileFormat::Pixi,
+                output: None,
+                import: None,
+                reformat: false,
+                verify_hashes: false,
+                deep: false,
+                graph: None,
+                package: None,
+                licenses: false,
+                no_prune: false,
+                force_all: false,
+                config: Default::default(),
+                network_config: Default::default(),
             },
         }
     }
@@ -720,12 +949,69 @@ impl IntoMatchSpec for MatchSpec {
     }
 }
 
+/// Runs a task and captures its output, unless the task cache shows that its
+/// inputs and outputs didn't change. A task that fails or times out runs
+/// again as long as it has retries left, like it does in `pixi run`. Returns how many times the task ran, which is `0` if
+/// the task was skipped.
+async fn run_cached_task(
+    task: &ExecutableTask<'_>,
+    task_env: &HashMap<OsString, OsString>,
+    lock_file: &LockFile,
+    force: bool,
+    kill_signal: KillSignal,
+) -> miette::Result<(RunOutput, u32)> {
+    let task_cache = if force {
+        None
+    } else {
+        match task.can_skip(lock_file).await.into_diagnostic()? {
+            CanSkip::Yes => return Ok((RunOutput::default(), 0)),
+            CanSkip::No(task_cache) => task_cache,
+        }
+    };
+
+    let mut attempts = 1;
+    loop {
+        let result = task
+            .execute_with_pipes(task_env, None, kill_signal.clone())
+            .await;
+        let retryable = match &result {
+            Ok(output) => output.exit_code != 0,
+            Err(err) => matches!(err, TaskExecutionError::TimedOut(..)),
+        };
+        if retryable && attempts <= task.retries() {
+            if let Some(delay) = task.retry_delay() {
+                tokio::time::sleep(delay).await;
+            }
+            attempts += 1;
+            continue;
+        }
+
+        // The error is returned as is, so that tests can downcast it.
+        let output = result?;
+        if output.exit_code == 0 {
+            task.save_cache(lock_file, task_cache)
+                .await
+                .into_diagnostic()?;
+        }
+        return Ok((output, attempts));
+    }
+}
+
+/// A task that exited with a non-zero exit code.
+#[derive(Debug)]
+pub struct TaskFailure {
+    pub name: Option<TaskName>,
+    pub exit_code: i32,
+}
+
 #[derive(Error, Debug, Diagnostic)]
-enum RunError {
+pub enum RunError {
     #[error(transparent)]
     TaskGraphError(#[from] TaskGraphError),
-    #[error(transparent)]
-    ExecutionError(#[from] TaskExecutionError),
-    #[error("the task executed with a non-zero exit code {0}")]
-    NonZeroExitCode(i32),
+    #[error(
+        "{} task(s) executed with a non-zero exit code, the first with exit code {}",
+        .0.len(),
+        .0[0].exit_code
+    )]
+    NonZeroExitCode(Vec<TaskFailure>),
 }
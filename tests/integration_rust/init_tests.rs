@@ -1,10 +1,19 @@
 use std::str::FromStr;
 
-use pixi::cli;
-use pixi_manifest::FeaturesExt;
-use rattler_conda_types::{NamedChannelOrUrl, Platform, Version};
+use pixi::{
+    cli,
+    workspace::{DiscoveryStart, WorkspaceLocator},
+};
+use pixi_config::Config;
+use pixi_consts::consts;
+use pixi_manifest::{ChannelPriority, FeaturesExt, Manifests};
+use rattler_conda_types::{
+    NamedChannelOrUrl, PackageRecord, Platform, PrefixRecord, RepoDataRecord, Version,
+    VersionWithSource,
+};
+use url::Url;
 
-use crate::common::PixiControl;
+use crate::common::{LockFileExt, PixiControl};
 
 #[tokio::test]
 async fn init_creates_project_manifest() {
@@ -61,6 +70,759 @@ async fn specific_channel() {
     )
 }
 
+#[tokio::test]
+async fn init_channel_order_and_priority() {
+    let pixi = PixiControl::new().unwrap();
+    let local_channel = pixi.workspace_path().join("local-channel");
+    pixi.init()
+        .with_channel("bioconda")
+        .with_channel("conda-forge")
+        .with_channel("https://prefix.dev/my-channel")
+        .with_channel(local_channel.display())
+        .with_channel("bioconda")
+        .with_channel_priority(ChannelPriority::Disabled)
+        .await
+        .unwrap();
+
+    // The order of the command line is kept, duplicates are removed
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(
+        manifest.contains(
+            r#"channels = ["bioconda", "conda-forge", "https://prefix.dev/my-channel", "#
+        ),
+        "{manifest}"
+    );
+    assert!(
+        manifest.contains(r#"channel-priority = "disabled""#),
+        "{manifest}"
+    );
+
+    // The manifest is loaded without warnings
+    let manifests = Manifests::from_workspace_manifest_path(pixi.manifest_path()).unwrap();
+    assert!(manifests.warnings.is_empty());
+    let workspace = pixi.workspace().unwrap();
+    assert_eq!(workspace.default_environment().channels().len(), 4);
+    assert_eq!(
+        workspace.default_environment().channel_priority().unwrap(),
+        Some(ChannelPriority::Disabled)
+    );
+}
+
+/// Tests that the name, description and version can be given explicitly
+#[tokio::test]
+async fn init_with_name_description_and_version() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_name("my-workspace")
+        .with_description(r#"A "quoted" description"#)
+        .with_version("1.2.3")
+        .await
+        .unwrap();
+
+    let workspace = pixi.workspace().unwrap();
+    assert_eq!(workspace.display_name(), "my-workspace");
+    assert_eq!(
+        workspace.workspace.value.workspace.description.as_deref(),
+        Some(r#"A "quoted" description"#)
+    );
+    assert_eq!(
+        workspace.workspace.value.workspace.version,
+        Some(Version::from_str("1.2.3").unwrap())
+    );
+
+    // The name is also the name of the python project
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_name("my-package")
+        .with_format(cli::init::ManifestFormat::Pyproject)
+        .await
+        .unwrap();
+    let manifest =
+        fs_err::read_to_string(pixi.workspace_path().join(consts::PYPROJECT_MANIFEST)).unwrap();
+    assert!(
+        manifest.contains("[project]\ndependencies = []\nname = \"my-package\""),
+        "{manifest}"
+    );
+    assert!(pixi.workspace_path().join("src/my_package").is_dir());
+}
+
+/// Tests that a conda-lock file is imported together with its locked packages
+#[tokio::test]
+async fn init_import_conda_lock() {
+    let pixi = PixiControl::new().unwrap();
+    let conda_lock = pixi.workspace_path().join("conda-lock.yml");
+    fs_err::write(
+        &conda_lock,
+        r#"version: 1
+metadata:
+  channels:
+  - url: conda-forge
+    used_env_vars: []
+  platforms:
+  - linux-64
+  - win-64
+package:
+- name: python
+  version: 3.12.1
+  manager: conda
+  platform: linux-64
+  dependencies:
+    libzlib: '>=1.2.13'
+  url: https://conda.anaconda.org/conda-forge/linux-64/python-3.12.1-hab00c5b_1_cpython.conda
+  hash:
+    md5: 0bab699354cbd66959550eb9b9866620
+  category: main
+  optional: false
+- name: libzlib
+  version: 1.3.1
+  manager: conda
+  platform: linux-64
+  dependencies: {}
+  url: https://conda.anaconda.org/conda-forge/linux-64/libzlib-1.3.1-h4ab18f5_1.conda
+  hash:
+    md5: 57d7dc60e9325e3de37ff8dffd18e814
+  category: main
+  optional: false
+- name: python
+  version: 3.12.1
+  manager: conda
+  platform: win-64
+  dependencies: {}
+  url: https://conda.anaconda.org/conda-forge/win-64/python-3.12.1-h2628c8c_1_cpython.conda
+  hash:
+    md5: 25fdbda7fe9ab9d9a5a4ad6e7d1fa2bb
+  category: main
+  optional: false
+- name: requests
+  version: 2.31.0
+  manager: pip
+  platform: linux-64
+  dependencies: {}
+  url: https://files.pythonhosted.org/packages/requests-2.31.0-py3-none-any.whl
+  hash:
+    sha256: 58cd2187c01e70e6e26505bca751777aa9f2ee0b7f4300988b709f44e013003f
+  category: main
+  optional: false
+"#,
+    )
+    .unwrap();
+
+    pixi.init()
+        .with_import(&conda_lock.display().to_string())
+        .await
+        .unwrap();
+
+    let workspace = pixi.workspace().unwrap();
+    assert_eq!(
+        Vec::from_iter(workspace.default_environment().platforms()),
+        [Platform::Linux64, Platform::Win64]
+    );
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(
+        manifest.contains("[dependencies]\npython = \"==3.12.1\""),
+        "{manifest}"
+    );
+    assert!(
+        manifest.contains("[target.linux-64.pypi-dependencies]\nrequests = \"==2.31.0\""),
+        "{manifest}"
+    );
+
+    let lock_file =
+        rattler_lock::LockFile::from_path(&pixi.workspace_path().join(consts::PROJECT_LOCK_FILE))
+            .unwrap();
+    assert!(lock_file.contains_conda_package(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::Linux64,
+        "libzlib"
+    ));
+    assert!(lock_file.contains_conda_package(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::Win64,
+        "python"
+    ));
+    assert!(lock_file.contains_pypi_package(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::Linux64,
+        "requests"
+    ));
+
+    // Files that are neither an environment file nor a conda-lock file are rejected
+    let other = PixiControl::new().unwrap();
+    let unknown = other.workspace_path().join("unknown.yml");
+    fs_err::write(&unknown, "numpy\nscipy\n").unwrap();
+    let err = other
+        .init()
+        .with_import(&unknown.display().to_string())
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{err:?}").contains("unrecognized environment file"),
+        "{err:?}"
+    );
+}
+
+/// Tests that an offline init doesn't access the network
+#[tokio::test]
+async fn init_offline() {
+    let pixi = PixiControl::new().unwrap();
+    fs_err::write(pixi.workspace_path().join("requirements.txt"), "requests\n").unwrap();
+
+    pixi.init()
+        .offline()
+        .with_import_requirements(pixi.workspace_path().join("requirements.txt"))
+        .await
+        .unwrap();
+
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(manifest.contains("requests = \"*\""), "{manifest}");
+    assert!(pixi.workspace_path().join(".gitignore").is_file());
+    assert!(pixi.workspace_path().join(".gitattributes").is_file());
+    assert!(
+        !pixi
+            .workspace_path()
+            .join(consts::PROJECT_LOCK_FILE)
+            .exists()
+    );
+    let config_path = pixi
+        .workspace_path()
+        .join(consts::PIXI_DIR)
+        .join(consts::CONFIG_FILE);
+    let config = Config::from_path(&config_path).unwrap();
+    assert!(config.network().offline());
+
+    // An existing config that blocks the network is kept
+    let blocked = PixiControl::new().unwrap();
+    let config_path = blocked
+        .workspace_path()
+        .join(consts::PIXI_DIR)
+        .join(consts::CONFIG_FILE);
+    fs_err::create_dir_all(config_path.parent().unwrap()).unwrap();
+    fs_err::write(
+        &config_path,
+        "[proxy-config]\nhttps = \"http://127.0.0.1:9\"\nhttp = \"http://127.0.0.1:9\"\n",
+    )
+    .unwrap();
+    fs_err::write(
+        blocked.workspace_path().join("requirements.txt"),
+        "requests\n",
+    )
+    .unwrap();
+    blocked
+        .init()
+        .offline()
+        .with_import_requirements(blocked.workspace_path().join("requirements.txt"))
+        .await
+        .unwrap();
+    let config = Config::from_path(&config_path).unwrap();
+    assert!(config.network().offline());
+    assert!(config.proxy_config.https.is_some());
+
+    // Git templates can't be cloned
+    let other = PixiControl::new().unwrap();
+    let err = other
+        .init()
+        .offline()
+        .with_template("https://github.com/my-org/pixi-template")
+        .await
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("while offline"), "{err:?}");
+}
+
+/// Tests that `--feature` scaffolds empty features with an environment each
+#[tokio::test]
+async fn init_with_features() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_feature("test")
+        .with_feature("docs")
+        .with_feature("test")
+        .await
+        .unwrap();
+
+    let manifest = pixi.manifest_contents().unwrap();
+    assert_eq!(
+        manifest.matches("[feature.test.dependencies]").count(),
+        1,
+        "{manifest}"
+    );
+    assert!(
+        manifest.contains("[feature.docs.dependencies]"),
+        "{manifest}"
+    );
+
+    let workspace = pixi.workspace().unwrap();
+    for name in ["test", "docs"] {
+        let environment = workspace.environment(name).unwrap();
+        let features = environment
+            .features()
+            .map(|feature| feature.name.as_str().to_string())
+            .collect::<Vec<_>>();
+        assert!(features.iter().any(|feature| feature == name));
+        assert!(
+            features
+                .iter()
+                .any(|feature| feature == consts::DEFAULT_FEATURE_NAME)
+        );
+    }
+
+    // Without the default feature
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_feature("lint")
+        .without_default_feature()
+        .await
+        .unwrap();
+    let workspace = pixi.workspace().unwrap();
+    let environment = workspace.environment("lint").unwrap();
+    assert!(
+        environment
+            .features()
+            .all(|feature| feature.name.as_str() == "lint")
+    );
+}
+
+/// Tests that `--workspace-member` creates the member packages and that the
+/// workspace is discovered from inside a member
+#[tokio::test]
+async fn init_workspace_members() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_workspace_member("packages/core")
+        .with_workspace_member("packages/cli")
+        .await
+        .unwrap();
+
+    let core_manifest_path = pixi
+        .workspace_path()
+        .join("packages/core")
+        .join(consts::WORKSPACE_MANIFEST);
+    let core_manifest = fs_err::read_to_string(&core_manifest_path).unwrap();
+    assert!(core_manifest.contains("name = \"core\""), "{core_manifest}");
+
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(manifest.contains("pixi-build"), "{manifest}");
+    assert!(
+        manifest.contains(r#"core = { path = "packages/core" }"#),
+        "{manifest}"
+    );
+    assert!(
+        manifest.contains(r#"cli = { path = "packages/cli" }"#),
+        "{manifest}"
+    );
+
+    // Running it again only adds the new member
+    let edited_core_manifest = format!("{core_manifest}\n# edited\n");
+    fs_err::write(&core_manifest_path, &edited_core_manifest).unwrap();
+    pixi.init()
+        .with_workspace_member("packages/core")
+        .with_workspace_member("packages/docs")
+        .await
+        .unwrap();
+    assert_eq!(
+        fs_err::read_to_string(&core_manifest_path).unwrap(),
+        edited_core_manifest
+    );
+    let manifest = pixi.manifest_contents().unwrap();
+    assert_eq!(manifest.matches("core = {").count(), 1, "{manifest}");
+    assert!(
+        manifest.contains(r#"docs = { path = "packages/docs" }"#),
+        "{manifest}"
+    );
+
+    // The workspace is found from inside a member
+    let workspace = WorkspaceLocator::default()
+        .with_search_start(DiscoveryStart::SearchRoot(
+            pixi.workspace_path().join("packages/cli"),
+        ))
+        .locate()
+        .unwrap();
+    assert_eq!(
+        workspace.workspace.provenance.path,
+        dunce::canonicalize(pixi.manifest_path()).unwrap()
+    );
+}
+
+/// Tests that multiple environment files are imported into their own features
+#[tokio::test]
+async fn init_import_multiple_env_files() {
+    let pixi = PixiControl::new().unwrap();
+    let files = [
+        (
+            "base.yml",
+            "name: imported\nchannels:\n  - conda-forge\ndependencies:\n  - python=3.11\n",
+        ),
+        (
+            "dev.yml",
+            "channels:\n  - conda-forge\n  - bioconda\ndependencies:\n  - pytest\n  - pip:\n    - ruff\n",
+        ),
+        (
+            "gpu.yml",
+            "dependencies:\n  - pytorch::pytorch\nprefix: /opt/conda/envs/gpu\n",
+        ),
+    ];
+    for (name, contents) in files {
+        fs_err::write(pixi.workspace_path().join(name), contents).unwrap();
+    }
+    let path = |name: &str| pixi.workspace_path().join(name).display().to_string();
+
+    pixi.init()
+        .with_import(&path("base.yml"))
+        .with_import(&format!("{}:dev", path("dev.yml")))
+        .with_import(&format!("{}:GPU_Cuda", path("gpu.yml")))
+        .await
+        .unwrap();
+
+    let workspace = pixi.workspace().unwrap();
+    assert_eq!(workspace.display_name(), "imported");
+    assert_eq!(
+        Vec::from_iter(workspace.default_environment().channels()),
+        [
+            &NamedChannelOrUrl::Name(String::from("conda-forge")),
+            &NamedChannelOrUrl::Name(String::from("bioconda")),
+            &NamedChannelOrUrl::Name(String::from("pytorch")),
+        ]
+    );
+
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(manifest.contains(r#"python = "3.11.*""#), "{manifest}");
+    assert!(
+        manifest.contains("[feature.dev.pypi-dependencies]"),
+        "{manifest}"
+    );
+    // The names of the environments are normalized
+    for (environment, feature) in [("dev", "dev"), ("gpu-cuda", "GPU_Cuda")] {
+        let environment = workspace.environment(environment).unwrap();
+        assert!(environment.features().any(|f| f.name.as_str() == feature));
+    }
+
+    // Two files can't be imported into the same feature
+    let other = PixiControl::new().unwrap();
+    let err = other
+        .init()
+        .with_import(&path("base.yml"))
+        .with_import(&path("dev.yml"))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("are imported into feature"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn init_import_requirements() {
+    let pixi = PixiControl::new().unwrap();
+    let root = pixi.workspace_path();
+    fs_err::create_dir_all(root.join("libs/mypkg")).unwrap();
+    fs_err::write(
+        root.join("requirements.txt"),
+        "requests>=2.31\npywin32 ; sys_platform == 'win32'\nappnope ; sys_platform == 'darwin'\ntomli ; python_version < '3.11'\n-e ./libs/mypkg[dev]\n-r dev-requirements.txt\n",
+    )
+    .unwrap();
+    fs_err::write(root.join("dev-requirements.txt"), "pytest\n").unwrap();
+
+    pixi.init_with_platforms(vec![
+        Platform::Linux64.to_string(),
+        Platform::Win64.to_string(),
+    ])
+    .with_import_requirements(root.join("requirements.txt"))
+    .without_lockfile_update()
+    .await
+    .unwrap();
+
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(manifest.contains(r#"requests = ">=2.31""#), "{manifest}");
+    assert!(manifest.contains("pytest"), "{manifest}");
+    // The marker of a platform of the workspace is turned into a target
+    assert!(
+        manifest.contains("[target.win-64.pypi-dependencies]\npywin32 = \"*\""),
+        "{manifest}"
+    );
+    // Requirements for other platforms or with other markers are skipped, a
+    // pixi.toml has no markers
+    assert!(!manifest.contains("appnope"), "{manifest}");
+    assert!(!manifest.contains("tomli"), "{manifest}");
+    assert!(
+        manifest
+            .contains(r#"mypkg = { path = "./libs/mypkg", editable = true, extras = ["dev"] }"#),
+        "{manifest}"
+    );
+    assert!(!root.join(consts::PROJECT_LOCK_FILE).exists());
+}
+
+/// Tests that the markers of the requirements are kept in a pyproject.toml
+#[tokio::test]
+async fn init_import_requirements_pyproject() {
+    let pixi = PixiControl::new().unwrap();
+    let root = pixi.workspace_path();
+    fs_err::write(
+        root.join("requirements.txt"),
+        "requests>=2.31\nappnope ; sys_platform == 'darwin'\ntomli ; python_version < '3.11'\n",
+    )
+    .unwrap();
+
+    pixi.init_with_platforms(vec![Platform::Linux64.to_string()])
+        .with_format(cli::init::ManifestFormat::Pyproject)
+        .with_import_requirements(root.join("requirements.txt"))
+        .without_lockfile_update()
+        .await
+        .unwrap();
+
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(manifest.contains("requests>=2.31"), "{manifest}");
+    let dependency = |name: &str| {
+        manifest
+            .lines()
+            .find(|line| line.contains(name))
+            .unwrap_or_default()
+    };
+    assert!(
+        dependency("appnope").contains("sys_platform == 'darwin'"),
+        "{manifest}"
+    );
+    assert!(dependency("tomli").contains("'3.11'"), "{manifest}");
+}
+
+#[tokio::test]
+async fn init_from_builtin_template() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init_with_platforms(vec![Platform::Linux64.to_string()])
+        .with_template("python")
+        .await
+        .unwrap();
+
+    let workspace = pixi.workspace().unwrap();
+    assert_eq!(
+        Vec::from_iter(workspace.default_environment().platforms()),
+        [Platform::Linux64]
+    );
+    assert!(workspace.environment("lint").is_some());
+    assert!(
+        pixi.workspace_path().join(".gitignore").is_file(),
+        "the default scaffolding is still created"
+    );
+
+    // An existing manifest is never overwritten
+    fs_err::write(pixi.manifest_path(), "existing").unwrap();
+    pixi.init().with_template("rust").await.unwrap();
+    assert_eq!(pixi.manifest_contents().unwrap(), "existing");
+}
+
+/// Tests that a workspace is created from the packages of a conda environment
+#[tokio::test]
+async fn init_from_prefix() {
+    let prefix = tempfile::tempdir().unwrap();
+    let conda_meta = prefix.path().join(consts::CONDA_META_DIR);
+    fs_err::create_dir_all(&conda_meta).unwrap();
+    let packages = [
+        (
+            "python",
+            "3.12.1",
+            vec![],
+            Some("https://conda.anaconda.org/conda-forge"),
+        ),
+        (
+            "numpy",
+            "2.0.0",
+            vec!["python >=3.12"],
+            Some("https://conda.anaconda.org/conda-forge"),
+        ),
+        ("mylib", "1.0", vec![], None),
+    ];
+    for (name, version, depends, channel) in packages {
+        let mut package_record = PackageRecord::new(
+            name.parse().unwrap(),
+            VersionWithSource::from_str(version).unwrap(),
+            "0".to_string(),
+        );
+        package_record.depends = depends.into_iter().map(String::from).collect();
+        let repodata_record = RepoDataRecord {
+            package_record,
+            file_name: format!("{name}-{version}-0.conda"),
+            url: Url::parse(&format!("https://example.com/{name}-{version}-0.conda")).unwrap(),
+            channel: channel.map(String::from),
+        };
+        PrefixRecord::from_repodata_record(
+            repodata_record,
+            None,
+            None,
+            vec![],
+            Default::default(),
+            None,
+        )
+        .write_to_path(conda_meta.join(format!("{name}-{version}-0.json")), true)
+        .unwrap();
+    }
+    let dist_info = prefix
+        .path()
+        .join("lib/python3.12/site-packages/requests-2.31.0.dist-info");
+    fs_err::create_dir_all(&dist_info).unwrap();
+    fs_err::write(dist_info.join("INSTALLER"), "pip\n").unwrap();
+    fs_err::write(
+        dist_info.join("METADATA"),
+        "Metadata-Version: 2.1\nName: requests\nVersion: 2.31.0\n",
+    )
+    .unwrap();
+
+    // Without a history only the packages that nothing depends on are imported
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().with_from_prefix(prefix.path()).await.unwrap();
+    let workspace = pixi.workspace().unwrap();
+    assert_eq!(
+        Vec::from_iter(workspace.default_environment().channels()),
+        [&NamedChannelOrUrl::Name(String::from("conda-forge"))]
+    );
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(manifest.contains(r#"numpy = "==2.0.0""#), "{manifest}");
+    assert!(manifest.contains(r#"mylib = "==1.0""#), "{manifest}");
+    assert!(!manifest.contains("python ="), "{manifest}");
+    assert!(manifest.contains(r#"requests = "==2.31.0""#), "{manifest}");
+
+    // The history determines the requested packages
+    fs_err::write(
+        conda_meta.join("history"),
+        "# update specs: ['python=3.12', 'numpy']\n# remove specs: ['numpy']\n",
+    )
+    .unwrap();
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().with_from_prefix(prefix.path()).await.unwrap();
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(manifest.contains(r#"python = "==3.12.1""#), "{manifest}");
+    assert!(!manifest.contains("numpy"), "{manifest}");
+}
+
+#[tokio::test]
+async fn init_scm() {
+    // Not being in a git repository falls back to GitHub
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().with_scm(None).await.unwrap();
+    let gitattributes =
+        fs_err::read_to_string(pixi.workspace_path().join(".gitattributes")).unwrap();
+    assert!(gitattributes.contains("linguist-generated=true"));
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_scm(Some(cli::init::GitAttributes::Gitlab))
+        .await
+        .unwrap();
+    let gitattributes =
+        fs_err::read_to_string(pixi.workspace_path().join(".gitattributes")).unwrap();
+    assert!(gitattributes.contains("gitlab-generated=true"));
+
+    // No SCM files are written with `--scm none`
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_scm(Some(cli::init::GitAttributes::None))
+        .await
+        .unwrap();
+    assert!(!pixi.workspace_path().join(".gitattributes").exists());
+    assert!(!pixi.workspace_path().join(".gitignore").exists());
+
+    // Or with `--no-scm-files`
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().without_scm_files().await.unwrap();
+    assert!(!pixi.workspace_path().join(".gitattributes").exists());
+    assert!(!pixi.workspace_path().join(".gitignore").exists());
+}
+
+/// Tests that existing SCM files are extended with the missing pixi lines and
+/// that re-running init doesn't change them
+#[tokio::test]
+async fn init_scm_files_are_merged() {
+    let pixi = PixiControl::new().unwrap();
+    let gitignore_path = pixi.workspace_path().join(".gitignore");
+    let gitattributes_path = pixi.workspace_path().join(".gitattributes");
+    fs_err::write(&gitignore_path, "target/\n.pixi/").unwrap();
+    fs_err::write(&gitattributes_path, "*.png binary\n").unwrap();
+
+    pixi.init()
+        .with_workspace_member("packages/core")
+        .await
+        .unwrap();
+    let gitignore = fs_err::read_to_string(&gitignore_path).unwrap();
+    let gitattributes = fs_err::read_to_string(&gitattributes_path).unwrap();
+    assert_eq!(gitignore, "target/\n.pixi/");
+    assert!(
+        gitattributes.starts_with("*.png binary\n"),
+        "{gitattributes}"
+    );
+    assert_eq!(
+        gitattributes.matches("pixi.lock merge=binary").count(),
+        1,
+        "{gitattributes}"
+    );
+
+    // Running init again on the existing workspace keeps the files as they are
+    pixi.init()
+        .with_workspace_member("packages/core")
+        .await
+        .unwrap();
+    assert_eq!(fs_err::read_to_string(&gitignore_path).unwrap(), gitignore);
+    assert_eq!(
+        fs_err::read_to_string(&gitattributes_path).unwrap(),
+        gitattributes
+    );
+
+    // Re-running a plain init merges the SCM files and keeps the manifest
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().await.unwrap();
+    let manifest = pixi.manifest_contents().unwrap();
+    let gitattributes_path = pixi.workspace_path().join(".gitattributes");
+    fs_err::write(&gitattributes_path, "*.png binary\n").unwrap();
+    pixi.init().await.unwrap();
+    let gitattributes = fs_err::read_to_string(&gitattributes_path).unwrap();
+    assert!(
+        gitattributes.starts_with("*.png binary\n"),
+        "{gitattributes}"
+    );
+    assert!(
+        gitattributes.contains("pixi.lock merge=binary"),
+        "{gitattributes}"
+    );
+    assert_eq!(pixi.manifest_contents().unwrap(), manifest);
+    pixi.init().await.unwrap();
+    assert_eq!(
+        fs_err::read_to_string(&gitattributes_path).unwrap(),
+        gitattributes
+    );
+}
+
+/// Tests that the console scripts of a pyproject.toml are imported as tasks
+#[tokio::test]
+async fn init_import_scripts() {
+    let pixi = PixiControl::from_pyproject_manifest(
+        r#"
+[project]
+name = "myapp"
+version = "0.1.0"
+
+[project.scripts]
+myapp-serve = "myapp.server:main"
+
+[tool.poetry.scripts]
+serve = "myapp.other:main"
+migrate = "myapp.db:migrate"
+"#,
+    )
+    .unwrap();
+    pixi.init()
+        .with_format(cli::init::ManifestFormat::Pyproject)
+        .with_import_scripts()
+        .await
+        .unwrap();
+
+    let manifest = fs_err::read_to_string(pixi.pyproject_manifest_path()).unwrap();
+    assert!(
+        manifest.contains("[tool.pixi.tasks]\nserve = \"myapp-serve\"\nmigrate = \"migrate\""),
+        "{manifest}"
+    );
+
+    // The tasks are part of the workspace
+    let workspace = pixi.workspace().unwrap();
+    let tasks = workspace.default_environment().tasks(None).unwrap();
+    assert!(tasks.keys().any(|name| name.as_str() == "serve"));
+}
+
 // Test the initialization from an existing pyproject.toml file without the pixi information
 #[tokio::test]
 async fn init_from_existing_pyproject_toml() {
@@ -93,6 +855,25 @@ async fn init_from_existing_pyproject_toml() {
             .platforms()
             .contains(&Platform::current())
     );
+
+    // The python dependency is derived from `requires-python`
+    assert!(
+        pixi.manifest_contents()
+            .unwrap()
+            .contains("[tool.pixi.dependencies]\npython = \">=3.10\"")
+    );
+
+    // The pixi tables are never added twice
+    let err = pixi
+        .init()
+        .with_format(cli::init::ManifestFormat::Pyproject)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("already contains a '[tool.pixi]' table"),
+        "{err}"
+    );
 }
 
 // TODO: enable and fix this test when we fix the global config loading
@@ -16,14 +16,14 @@ use pixi::{
         run::{self, Args},
     },
     environment::LockFileUsage,
-    lock_file::{CondaPrefixUpdater, ReinstallPackages, UpdateMode},
+    lock_file::{CondaPrefixUpdater, InstallFilter, ReinstallPackages, UpdateMode},
     workspace::{HasWorkspaceRef, grouped_environment::GroupedEnvironment},
 };
 use pixi_config::{Config, DetachedEnvironments};
 use pixi_consts::consts;
 use pixi_manifest::{FeatureName, FeaturesExt};
 use pixi_record::PixiRecord;
-use rattler_conda_types::{ChannelConfig, Platform, RepoDataRecord};
+use rattler_conda_types::{ChannelConfig, Platform, PrefixRecord, RepoDataRecord};
 use tempfile::{TempDir, tempdir};
 use tokio::{fs, task::JoinSet};
 use url::Url;
@@ -479,6 +479,102 @@ async fn install_conda_meta_history() {
     assert!(conda_meta_history_file.exists());
 }
 
+/// Returns the `conda-meta` record file of the package installed in the
+/// prefix, if any.
+fn conda_meta_record(prefix: &Path, package: &str) -> Option<PathBuf> {
+    fs_err::read_dir(prefix.join("conda-meta"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|name| name.rsplitn(3, '-').nth(2))
+                == Some(package)
+        })
+}
+
+/// Removing a package should only unlink that package, the other packages of
+/// the prefix are left untouched until the next full update.
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[cfg_attr(not(feature = "slow_integration_tests"), ignore)]
+async fn remove_only_unlinks_removed_packages() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().await.unwrap();
+    pixi.add_multiple(vec![
+        "tzdata",
+        "ca-certificates",
+        "font-ttf-dejavu-sans-mono",
+    ])
+    .with_install(true)
+    .await
+    .unwrap();
+
+    let prefix = pixi.default_env_path().unwrap();
+    let tzdata_record = conda_meta_record(&prefix, "tzdata").unwrap();
+    let linked_at = fs_err::metadata(&tzdata_record)
+        .unwrap()
+        .modified()
+        .unwrap();
+
+    // An unrelated package that went missing is not installed again by the
+    // removal
+    fs_err::remove_file(conda_meta_record(&prefix, "font-ttf-dejavu-sans-mono").unwrap()).unwrap();
+
+    pixi.remove("ca-certificates")
+        .with_install(true)
+        .await
+        .unwrap();
+
+    assert!(conda_meta_record(&prefix, "ca-certificates").is_none());
+    assert_eq!(
+        fs_err::metadata(&tzdata_record)
+            .unwrap()
+            .modified()
+            .unwrap(),
+        linked_at,
+        "tzdata should not have been relinked"
+    );
+    assert!(conda_meta_record(&prefix, "font-ttf-dejavu-sans-mono").is_none());
+
+    // The prefix isn't marked as up-to-date, so the next update repairs it
+    pixi.install().await.unwrap();
+    assert!(conda_meta_record(&prefix, "font-ttf-dejavu-sans-mono").is_some());
+}
+
+/// A file that was modified after it was linked is restored by verifying the
+/// environment.
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[cfg_attr(not(feature = "slow_integration_tests"), ignore)]
+async fn install_verify_repairs_corrupted_files() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().await.unwrap();
+    pixi.add("tzdata").with_install(true).await.unwrap();
+
+    let prefix = pixi.default_env_path().unwrap();
+    let record = PrefixRecord::from_path(conda_meta_record(&prefix, "tzdata").unwrap()).unwrap();
+    let entry = record
+        .paths_data
+        .paths
+        .iter()
+        .find(|entry| entry.sha256.is_some() && entry.prefix_placeholder.is_none())
+        .unwrap();
+    let path = prefix.join(&entry.relative_path);
+    let original = fs_err::read(&path).unwrap();
+
+    // Replace the hard link instead of writing through it, which would also
+    // corrupt the package cache
+    fs_err::remove_file(&path).unwrap();
+    fs_err::write(&path, b"corrupted").unwrap();
+
+    // A normal install only looks at the records, not at the files
+    pixi.install().await.unwrap();
+    assert_eq!(fs_err::read(&path).unwrap(), b"corrupted");
+
+    pixi.install().with_verify().await.unwrap();
+    assert_eq!(fs_err::read(&path).unwrap(), original);
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[cfg_attr(not(feature = "slow_integration_tests"), ignore)]
 async fn minimal_lockfile_update_pypi() {
@@ -601,6 +697,7 @@ async fn test_old_lock_install() {
             ..Default::default()
         },
         ReinstallPackages::default(),
+        InstallFilter::default(),
     )
     .await
     .unwrap();
@@ -1202,3 +1299,357 @@ async fn test_exclude_newer_pypi() {
         "boltons ==20.2.1".parse().unwrap()
     ));
 }
+
+/// Tests that `pixi install -e` installs exactly the given environments
+#[tokio::test]
+async fn test_install_multiple_environments() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("bar", "1").finish());
+    package_database.add_package(Package::build("baz", "1").finish());
+    let channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-install-multiple-environments"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    bar = "*"
+
+    [feature.docs.dependencies]
+    baz = "*"
+
+    [environments]
+    dev = []
+    docs = ["docs"]
+    lint = []
+    "#,
+        channel = channel.url(),
+        platform = Platform::current(),
+    ))
+    .unwrap();
+
+    pixi.install()
+        .with_environment("dev")
+        .with_environment("docs")
+        .with_environment("dev")
+        .await
+        .unwrap();
+
+    let workspace = pixi.workspace().unwrap();
+    for (name, installed) in [
+        ("dev", true),
+        ("docs", true),
+        ("lint", false),
+        (consts::DEFAULT_ENVIRONMENT_NAME, false),
+    ] {
+        let prefix = workspace.environment(name).unwrap().dir();
+        assert_eq!(
+            prefix.join(consts::CONDA_META_DIR).is_dir(),
+            installed,
+            "{name}"
+        );
+    }
+
+    // Typos are reported with a suggestion
+    let err = pixi.install().with_environment("dosc").await.unwrap_err();
+    assert!(
+        format!("{err:?}").contains("'docs' instead of 'dosc'"),
+        "{err:?}"
+    );
+}
+
+/// Tests that a frozen install reports the environments that are missing from
+/// the lock-file and still installs the others
+#[tokio::test]
+async fn test_install_frozen_missing_environment() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("bar", "1").finish());
+    let channel = package_database.into_channel().await.unwrap();
+
+    let manifest = format!(
+        r#"
+    [workspace]
+    name = "test-install-frozen-missing-environment"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    bar = "*"
+    "#,
+        channel = channel.url(),
+        platform = Platform::current(),
+    );
+    let pixi = PixiControl::from_manifest(&manifest).unwrap();
+    pixi.update_lock_file().await.unwrap();
+
+    // Add an environment that is not part of the lock-file
+    pixi.update_manifest(&format!("{manifest}\n    [environments]\n    dev = []\n"))
+        .unwrap();
+
+    let err = pixi
+        .install()
+        .with_frozen()
+        .with_environment("dev")
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("the lock-file doesn't contain the environment 'dev'"),
+        "{err}"
+    );
+
+    let err = pixi
+        .install()
+        .with_frozen()
+        .with_environment(consts::DEFAULT_ENVIRONMENT_NAME)
+        .with_environment("dev")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("1 of 2 environments"), "{err}");
+    let workspace = pixi.workspace().unwrap();
+    assert!(
+        workspace
+            .default_environment()
+            .dir()
+            .join(consts::CONDA_META_DIR)
+            .is_dir()
+    );
+    assert!(
+        !workspace
+            .environment("dev")
+            .unwrap()
+            .dir()
+            .join(consts::CONDA_META_DIR)
+            .exists()
+    );
+}
+
+#[tokio::test]
+async fn test_install_skip_packages() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("bar", "1").finish());
+    package_database.add_package(
+        Package::build("foo", "1")
+            .with_dependency("bar >=1")
+            .finish(),
+    );
+    let channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-install-skip-packages"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+    "#,
+        channel = channel.url(),
+        platform = Platform::current(),
+    ))
+    .unwrap();
+
+    let installed_names = || {
+        let workspace = pixi.workspace().unwrap();
+        let conda_meta = workspace
+            .default_environment()
+            .dir()
+            .join(consts::CONDA_META_DIR);
+        let mut names = std::fs::read_dir(conda_meta)
+            .unwrap()
+            .filter_map(|entry| {
+                let file_name = entry.unwrap().file_name().into_string().unwrap();
+                let stem = file_name.strip_suffix(".json")?;
+                // The records are named `<name>-<version>-<build>.json`
+                stem.rsplitn(3, '-').last().map(str::to_string)
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    pixi.install().with_skip("bar").await.unwrap();
+    assert_eq!(installed_names(), vec!["foo"]);
+
+    // Other commands keep skipping the package
+    let workspace = pixi.workspace().unwrap();
+    pixi::environment::get_update_lock_file_and_prefix(
+        &workspace.default_environment(),
+        UpdateMode::QuickValidate,
+        UpdateLockFileOptions::default(),
+        ReinstallPackages::default(),
+        InstallFilter::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(installed_names(), vec!["foo"]);
+
+    // Installing without `--skip` adds the package again
+    pixi.install().await.unwrap();
+    assert_eq!(installed_names(), vec!["bar", "foo"]);
+}
+
+#[tokio::test]
+async fn test_install_all_environments_concurrently() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+    let channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-install-all-concurrently"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+
+    [feature.bar.dependencies]
+    bar = "*"
+
+    [environments]
+    bar = ["bar"]
+    other = {{ features = ["bar"], no-default-feature = true }}
+    "#,
+        channel = channel.url(),
+        platform = Platform::current(),
+    ))
+    .unwrap();
+
+    // More environments than may be installed at once
+    pixi.install().with_all().with_concurrency(2).await.unwrap();
+
+    let workspace = pixi.workspace().unwrap();
+    for environment in workspace.environments() {
+        assert!(
+            environment
+                .dir()
+                .join(consts::CONDA_META_DIR)
+                .join(consts::ENVIRONMENT_FILE_NAME)
+                .is_file(),
+            "the {} environment was not installed",
+            environment.name()
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_install_into_target_prefix() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    let channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-install-target-prefix"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+    "#,
+        channel = channel.url(),
+        platform = Platform::current(),
+    ))
+    .unwrap();
+
+    let target_dir = tempdir().unwrap();
+    let target_prefix = target_dir.path().join("env");
+    pixi.install()
+        .with_target_prefix(&target_prefix)
+        .await
+        .unwrap();
+
+    // The records are installed in the target prefix
+    let conda_meta = target_prefix.join(consts::CONDA_META_DIR);
+    assert!(std::fs::read_dir(&conda_meta).unwrap().any(|entry| {
+        entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .starts_with("foo-1-")
+    }));
+
+    // The target prefix is not registered as the environment of the workspace
+    assert!(!conda_meta.join(consts::ENVIRONMENT_FILE_NAME).exists());
+    let workspace = pixi.workspace().unwrap();
+    assert!(!workspace.default_environment().dir().exists());
+
+    // Installing into a directory that is not empty has to be forced
+    let err = pixi
+        .install()
+        .with_target_prefix(&target_prefix)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("is not empty"), "{err}");
+    pixi.install()
+        .with_target_prefix(&target_prefix)
+        .with_force()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_install_all_environments_reports_failures() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+    let channel = package_database.into_channel().await.unwrap();
+
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-install-all-failures"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+
+    [feature.bar.dependencies]
+    bar = "*"
+
+    [environments]
+    bar = ["bar"]
+    other = {{ features = ["bar"], no-default-feature = true }}
+    "#,
+        channel = channel.url(),
+        platform = Platform::current(),
+    ))
+    .unwrap();
+    pixi.update_lock_file().await.unwrap();
+
+    // A file in place of the prefix makes the install of `other` fail
+    let workspace = pixi.workspace().unwrap();
+    let failing_dir = workspace.environment("other").unwrap().dir();
+    fs_err::create_dir_all(failing_dir.parent().unwrap()).unwrap();
+    fs_err::write(&failing_dir, "not a prefix").unwrap();
+
+    let err = pixi.install().with_all().await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("1 of 3 environments failed to install"),
+        "{err:?}"
+    );
+
+    // The other environments are still installed
+    for name in ["default", "bar"] {
+        assert!(
+            workspace
+                .environment(name)
+                .unwrap()
+                .dir()
+                .join(consts::CONDA_META_DIR)
+                .join(consts::ENVIRONMENT_FILE_NAME)
+                .is_file(),
+            "the {name} environment was not installed"
+        );
+    }
+}
@@ -0,0 +1,736 @@
+use std::path::Path;
+
+use itertools::Itertools;
+use pixi::{UpdateLockFileOptions, cli::lock::GraphFormat};
+use pixi_consts::consts;
+use pixi_manifest::EnvironmentName;
+use rattler_conda_types::Platform;
+use tempfile::TempDir;
+use url::Url;
+
+use crate::common::{
+    LockFileExt, PixiControl,
+    package_database::{Package, PackageDatabase},
+};
+
+/// Writes a channel that contains version 1 and 2 of `foo` and `bar`.
+async fn foo_bar_channel() -> (TempDir, Url) {
+    let mut package_database = PackageDatabase::default();
+    for version in ["1", "2"] {
+        package_database.add_package(Package::build("foo", version).finish());
+        package_database.add_package(Package::build("bar", version).finish());
+    }
+
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    (channel_dir, channel)
+}
+
+#[tokio::test]
+async fn test_lock_single_environment_keeps_other_environments() {
+    let (_channel_dir, channel) = foo_bar_channel().await;
+    let platform = Platform::current();
+    let manifest = |foo: &str, bar: &str| {
+        format!(
+            r#"
+    [workspace]
+    name = "test-lock-environment"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "{foo}"
+
+    [feature.docs.dependencies]
+    bar = "{bar}"
+
+    [environments]
+    docs = ["docs"]
+    "#
+        )
+    };
+    let pixi = PixiControl::from_manifest(&manifest("==1", "==1")).unwrap();
+    pixi.update_lock_file().await.unwrap();
+
+    // Change the requirements of both environments, but only lock `docs`
+    fs_err::write(pixi.manifest_path(), manifest("==2", "==2")).unwrap();
+    pixi.lock()
+        .with_environment(EnvironmentName::Named("docs".to_string()))
+        .await
+        .unwrap();
+
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec("docs", platform, "foo ==2"));
+    assert!(lock.contains_match_spec("docs", platform, "bar ==2"));
+    assert!(lock.contains_match_spec(consts::DEFAULT_ENVIRONMENT_NAME, platform, "foo ==1"));
+
+    // Environments that were removed from the manifest are still pruned
+    fs_err::write(
+        pixi.manifest_path(),
+        manifest("==2", "==2").replace("docs = [\"docs\"]", ""),
+    )
+    .unwrap();
+    pixi.lock()
+        .with_environment(EnvironmentName::Default)
+        .await
+        .unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.environment("docs").is_none());
+    assert!(lock.contains_match_spec(consts::DEFAULT_ENVIRONMENT_NAME, platform, "foo ==2"));
+}
+
+#[tokio::test]
+async fn test_lock_single_platform_keeps_other_platforms() {
+    let (_channel_dir, channel) = foo_bar_channel().await;
+    let manifest = |foo: &str| {
+        format!(
+            r#"
+    [workspace]
+    name = "test-lock-platform"
+    channels = ["{channel}"]
+    platforms = ["linux-64", "osx-arm64"]
+
+    [dependencies]
+    foo = "{foo}"
+    "#
+        )
+    };
+    let pixi = PixiControl::from_manifest(&manifest("==1")).unwrap();
+    pixi.update_lock_file().await.unwrap();
+
+    fs_err::write(pixi.manifest_path(), manifest("==2")).unwrap();
+    pixi.lock().with_platform(Platform::Linux64).await.unwrap();
+
+    let lock = pixi.lock_file().await.unwrap();
+    let env = consts::DEFAULT_ENVIRONMENT_NAME;
+    assert!(lock.contains_match_spec(env, Platform::Linux64, "foo ==2"));
+    assert!(lock.contains_match_spec(env, Platform::OsxArm64, "foo ==1"));
+}
+
+#[tokio::test]
+async fn test_lock_refuses_unsupported_platform() {
+    let (_channel_dir, channel) = foo_bar_channel().await;
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-lock-unsupported-platform"
+    channels = ["{channel}"]
+    platforms = ["linux-64"]
+
+    [dependencies]
+    foo = "*"
+    "#
+    ))
+    .unwrap();
+
+    let err = pixi
+        .lock()
+        .with_platform(Platform::Win64)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("are not supported by the workspace"),
+        "unexpected error: {err}"
+    );
+    assert!(
+        !pixi
+            .workspace_path()
+            .join(consts::PROJECT_LOCK_FILE)
+            .exists()
+    );
+}
+
+#[tokio::test]
+async fn test_lock_refuses_part_of_solve_group() {
+    let (_channel_dir, channel) = foo_bar_channel().await;
+    let platform = Platform::current();
+    let manifest = |foo: &str| {
+        format!(
+            r#"
+    [workspace]
+    name = "test-lock-solve-group"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "{foo}"
+
+    [feature.docs.dependencies]
+    bar = "*"
+
+    [environments]
+    default = {{ solve-group = "main" }}
+    docs = {{ features = ["docs"], solve-group = "main" }}
+    "#
+        )
+    };
+    let pixi = PixiControl::from_manifest(&manifest("==1")).unwrap();
+    pixi.update_lock_file().await.unwrap();
+
+    fs_err::write(pixi.manifest_path(), manifest("==2")).unwrap();
+    let err = pixi
+        .lock()
+        .with_environment(EnvironmentName::Named("docs".to_string()))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("solve group 'main'"),
+        "unexpected error: {err}"
+    );
+
+    // Selecting all environments of the solve group is fine
+    pixi.lock()
+        .with_environment(EnvironmentName::Named("docs".to_string()))
+        .with_environment(EnvironmentName::Default)
+        .await
+        .unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec("docs", platform, "foo ==2"));
+}
+
+#[tokio::test]
+async fn test_lock_conda_lock_round_trip() {
+    let (_channel_dir, channel) = foo_bar_channel().await;
+    let platform = Platform::current();
+    let manifest = |foo: &str| {
+        format!(
+            r#"
+    [workspace]
+    name = "test-lock-conda-lock"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "{foo}"
+    bar = "*"
+    "#
+        )
+    };
+    let pixi = PixiControl::from_manifest(&manifest("==1")).unwrap();
+    let conda_lock_path = pixi.workspace_path().join("conda-lock.yml");
+    pixi.lock()
+        .with_conda_lock_output(&conda_lock_path)
+        .await
+        .unwrap();
+    let locked = pixi.lock_file().await.unwrap();
+
+    // Importing the exported file results in the same locked packages
+    fs_err::remove_file(pixi.workspace().unwrap().lock_file_path()).unwrap();
+    pixi.lock().with_import(&conda_lock_path).await.unwrap();
+    let imported = pixi.lock_file().await.unwrap();
+    let locations = |lock: &rattler_lock::LockFile| {
+        lock.environment(consts::DEFAULT_ENVIRONMENT_NAME)
+            .unwrap()
+            .packages(platform)
+            .unwrap()
+            .map(|package| package.location().to_string())
+            .sorted()
+            .collect_vec()
+    };
+    assert_eq!(locations(&imported), locations(&locked));
+    assert!(imported.contains_match_spec(consts::DEFAULT_ENVIRONMENT_NAME, platform, "foo ==1"));
+
+    // The packages of the conda-lock file don't satisfy the new requirement
+    fs_err::write(pixi.manifest_path(), manifest("==2")).unwrap();
+    let err = pixi.lock().with_import(&conda_lock_path).await.unwrap_err();
+    assert!(err.to_string().contains("don't satisfy the manifest"));
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec(consts::DEFAULT_ENVIRONMENT_NAME, platform, "foo ==1"));
+}
+
+#[tokio::test]
+async fn test_lock_offline_uses_cached_repodata() {
+    let (_channel_dir, channel) = foo_bar_channel().await;
+    let platform = Platform::current();
+    let manifest = |foo: &str| {
+        format!(
+            r#"
+    [workspace]
+    name = "test-lock-offline"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "{foo}"
+    "#
+        )
+    };
+    let pixi = PixiControl::from_manifest(&manifest("==1")).unwrap();
+    pixi.lock().await.unwrap();
+
+    // The repodata that was fetched before is enough to solve the new requirement
+    fs_err::write(pixi.manifest_path(), manifest("==2")).unwrap();
+    pixi.lock().with_offline().await.unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec(consts::DEFAULT_ENVIRONMENT_NAME, platform, "foo ==2"));
+}
+
+/// Writes the packages to the channel in the given order.
+async fn write_channel(channel_dir: &Path, names: &[&str]) {
+    let mut package_database = PackageDatabase::default();
+    for name in names {
+        let package = match *name {
+            "foo" => Package::build("foo", "1")
+                .with_dependency("bar")
+                .with_dependency("baz"),
+            name => Package::build(name, "1"),
+        };
+        package_database.add_package(package.finish());
+    }
+    package_database.write_repodata(channel_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_lock_file_is_canonical() {
+    let channel_dir = TempDir::new().unwrap();
+    write_channel(channel_dir.path(), &["foo", "bar", "baz"]).await;
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-lock-canonical"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+    "#
+    ))
+    .unwrap();
+    let config_path = pixi
+        .workspace()
+        .unwrap()
+        .pixi_dir()
+        .join(consts::CONFIG_FILE);
+    fs_err::create_dir_all(config_path.parent().unwrap()).unwrap();
+    fs_err::write(&config_path, "[lock-file]\ncanonical = true\n").unwrap();
+
+    let lock_file_path = pixi.workspace().unwrap().lock_file_path();
+    pixi.lock().await.unwrap();
+    let first = fs_err::read_to_string(&lock_file_path).unwrap();
+
+    // The channel returns the packages in another order
+    write_channel(channel_dir.path(), &["baz", "bar", "foo"]).await;
+    fs_err::remove_file(&lock_file_path).unwrap();
+    pixi.lock().await.unwrap();
+    let second = fs_err::read_to_string(&lock_file_path).unwrap();
+    assert_eq!(first, second);
+
+    // A lock-file with the packages in another order is rewritten without
+    // solving
+    let lock = pixi.lock_file().await.unwrap();
+    let environment = lock.environment(consts::DEFAULT_ENVIRONMENT_NAME).unwrap();
+    let mut builder = rattler_lock::LockFile::builder();
+    builder.set_channels(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        environment.channels().to_vec(),
+    );
+    builder.set_options(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        environment.solve_options().clone(),
+    );
+    for (platform, packages) in environment.packages_by_platform() {
+        for package in packages.collect_vec().into_iter().rev() {
+            builder.add_package(consts::DEFAULT_ENVIRONMENT_NAME, platform, package.into());
+        }
+    }
+    builder.finish().to_path(&lock_file_path).unwrap();
+    pixi.lock().with_reformat().await.unwrap();
+    assert_eq!(fs_err::read_to_string(&lock_file_path).unwrap(), first);
+}
+
+#[tokio::test]
+async fn test_lock_verify_hashes() {
+    // The locked hashes are the hashes of the content `foo`
+    let sha256 = rattler_digest::compute_bytes_digest::<rattler_digest::Sha256>(b"foo");
+    let md5 = rattler_digest::compute_bytes_digest::<rattler_digest::Md5>(b"foo");
+    let channel_dir = TempDir::new().unwrap();
+    PackageDatabase::default()
+        .with_package(
+            Package::build("foo", "1")
+                .with_hashes(&format!("{sha256:x}"), &format!("{md5:x}"))
+                .finish(),
+        )
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-lock-verify-hashes"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "==1"
+    "#
+    ))
+    .unwrap();
+    pixi.lock().await.unwrap();
+
+    // The channel only contains the repodata, the archive can't be found
+    let err = pixi.lock().with_verify_hashes(false).await.unwrap_err();
+    assert!(err.to_string().contains("can't be verified"));
+
+    let lock = pixi.lock_file().await.unwrap();
+    let location = lock
+        .environment(consts::DEFAULT_ENVIRONMENT_NAME)
+        .unwrap()
+        .packages(platform)
+        .unwrap()
+        .map(|package| package.location().to_string())
+        .collect_vec();
+    let [location] = location.as_slice() else {
+        panic!("expected a single locked package, found {location:?}");
+    };
+    let archive = Url::parse(location).unwrap().to_file_path().unwrap();
+    fs_err::create_dir_all(archive.parent().unwrap()).unwrap();
+    fs_err::write(&archive, "foo").unwrap();
+    pixi.lock().with_verify_hashes(false).await.unwrap();
+    pixi.lock().with_verify_hashes(true).await.unwrap();
+
+    // Only `--deep` reads the content of the archive and finds the mismatch
+    fs_err::write(&archive, "bar").unwrap();
+    pixi.lock().with_verify_hashes(false).await.unwrap();
+    let err = pixi.lock().with_verify_hashes(true).await.unwrap_err();
+    assert!(err.to_string().contains("can't be verified"));
+}
+
+#[tokio::test]
+async fn test_lock_prunes_stale_environments_and_platforms() {
+    let (_channel_dir, channel) = foo_bar_channel().await;
+    let manifest = |platforms: &str, environment: &str| {
+        format!(
+            r#"
+    [workspace]
+    name = "test-lock-prune"
+    channels = ["{channel}"]
+    platforms = [{platforms}]
+
+    [dependencies]
+    foo = "==1"
+
+    [feature.docs.dependencies]
+    bar = "==1"
+
+    [environments]
+    {environment} = ["docs"]
+    "#
+        )
+    };
+    let pixi = PixiControl::from_manifest(&manifest(r#""linux-64", "osx-arm64""#, "docs")).unwrap();
+    pixi.lock().await.unwrap();
+
+    // Renaming an environment and dropping a platform leaves stale entries
+    // behind, which are kept with `--no-prune`
+    fs_err::write(
+        pixi.manifest_path(),
+        manifest(r#""linux-64""#, "documentation"),
+    )
+    .unwrap();
+    pixi.lock().with_no_prune().await.unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.environment("docs").is_some());
+    assert!(lock.contains_match_spec("documentation", Platform::Linux64, "bar ==1"));
+    assert!(lock.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::OsxArm64,
+        "foo ==1"
+    ));
+
+    // Without it they are removed
+    pixi.lock().await.unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.environment("docs").is_none());
+    assert!(lock.contains_match_spec("documentation", Platform::Linux64, "bar ==1"));
+    let default_environment = lock.environment(consts::DEFAULT_ENVIRONMENT_NAME).unwrap();
+    assert_eq!(
+        default_environment.platforms().collect_vec(),
+        vec![Platform::Linux64]
+    );
+}
+
+#[tokio::test]
+async fn test_lock_dependency_graph() {
+    // `foo` and `bar` depend on each other, which must not loop forever
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").with_dependency("bar").finish());
+    package_database.add_package(Package::build("bar", "1").with_dependency("foo").finish());
+    package_database.add_package(Package::build("baz", "1").finish());
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-lock-graph"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+    baz = "*"
+    "#
+    ))
+    .unwrap();
+    pixi.lock().await.unwrap();
+
+    let output = pixi.workspace_path().join("graph.dot");
+    pixi.lock()
+        .with_graph(GraphFormat::Dot, &output)
+        .await
+        .unwrap();
+    let dot = fs_err::read_to_string(&output).unwrap();
+    assert!(dot.starts_with("digraph \"default\" {"));
+    assert!(dot.contains("\"foo\" [label=\"foo 1\", style=bold, color=green];"));
+    assert!(dot.contains("\"bar\" [label=\"bar 1\"];"));
+    assert!(dot.contains("\"foo\" -> \"bar\";"));
+    assert!(dot.contains("\"bar\" -> \"foo\";"));
+
+    let output = pixi.workspace_path().join("graph.mmd");
+    pixi.lock()
+        .with_graph(GraphFormat::Mermaid, &output)
+        .with_graph_package("bar")
+        .await
+        .unwrap();
+    let mermaid = fs_err::read_to_string(&output).unwrap();
+    assert_eq!(
+        mermaid,
+        "flowchart LR\n  n0(\"bar 1\")\n  n1(\"foo 1\")\n  n0 --> n1\n  n1 --> n0\n  classDef direct stroke:#2e7d32,stroke-width:3px\n  class n1 direct\n"
+    );
+}
+
+#[tokio::test]
+async fn test_lock_dependency_graph_with_pypi_packages() {
+    let channel_dir = TempDir::new().unwrap();
+    PackageDatabase::default()
+        .with_package(Package::build("foo", "1").finish())
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-lock-graph-pypi"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+    "#
+    ))
+    .unwrap();
+    pixi.lock().await.unwrap();
+
+    // Lock a pypi package that depends on the conda package, the graph only
+    // reads the lock-file
+    let lock = pixi.lock_file().await.unwrap();
+    let environment = lock.environment(consts::DEFAULT_ENVIRONMENT_NAME).unwrap();
+    let mut builder = rattler_lock::LockFile::builder();
+    builder.set_channels(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        environment.channels().to_vec(),
+    );
+    for package in environment.packages(platform).unwrap() {
+        builder.add_package(consts::DEFAULT_ENVIRONMENT_NAME, platform, package.into());
+    }
+    builder.add_package(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        platform,
+        rattler_lock::LockedPackage::Pypi(
+            rattler_lock::PypiPackageData {
+                name: "my-pkg".parse().unwrap(),
+                version: "1.0+local".parse().unwrap(),
+                location: rattler_lock::UrlOrPath::Url(
+                    Url::parse("https://example.com/my_pkg-1.0-py3-none-any.whl").unwrap(),
+                ),
+                hash: None,
+                requires_dist: vec!["foo".parse().unwrap()],
+                requires_python: None,
+                editable: false,
+            },
+            Default::default(),
+        ),
+    );
+    builder
+        .finish()
+        .to_path(&pixi.workspace().unwrap().lock_file_path())
+        .unwrap();
+
+    let output = pixi.workspace_path().join("graph.dot");
+    pixi.lock()
+        .with_graph(GraphFormat::Dot, &output)
+        .await
+        .unwrap();
+    let dot = fs_err::read_to_string(&output).unwrap();
+    assert!(dot.contains("\"my_pkg\" [label=\"my_pkg 1.0+local\", shape=box];"));
+    assert!(dot.contains("\"my_pkg\" -> \"foo\";"));
+
+    let output = pixi.workspace_path().join("graph.mmd");
+    pixi.lock()
+        .with_graph(GraphFormat::Mermaid, &output)
+        .with_graph_package("my-pkg")
+        .await
+        .unwrap();
+    let mermaid = fs_err::read_to_string(&output).unwrap();
+    assert_eq!(
+        mermaid,
+        "flowchart LR\n  n0(\"foo 1\")\n  n1[\"my_pkg 1.0+local\"]\n  n1 --> n0\n  classDef direct stroke:#2e7d32,stroke-width:3px\n  class n0 direct\n"
+    );
+}
+
+#[tokio::test]
+async fn test_lock_only_solves_changed_environments() {
+    let (_channel_dir, channel) = foo_bar_channel().await;
+    let platform = Platform::current();
+    let manifest = |bar: &str| {
+        format!(
+            r#"
+    [workspace]
+    name = "test-lock-minimal"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+
+    [feature.docs.dependencies]
+    bar = "{bar}"
+
+    [environments]
+    docs = ["docs"]
+    "#
+        )
+    };
+    let pixi = PixiControl::from_manifest(&manifest("*")).unwrap();
+    let solved_targets = || async {
+        pixi.workspace()
+            .unwrap()
+            .update_lock_file(UpdateLockFileOptions::default())
+            .await
+            .unwrap()
+            .solved_targets
+    };
+    let docs = EnvironmentName::Named("docs".to_string());
+    assert_eq!(
+        solved_targets().await,
+        vec![
+            (EnvironmentName::Default, platform),
+            (docs.clone(), platform)
+        ]
+    );
+
+    // Only the environment that uses the edited feature is solved again
+    fs_err::write(pixi.manifest_path(), manifest("==1")).unwrap();
+    assert_eq!(solved_targets().await, vec![(docs.clone(), platform)]);
+    assert!(solved_targets().await.is_empty());
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec("docs", platform, "bar ==1"));
+}
+
+#[tokio::test]
+async fn test_lock_force_all_solves_again() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-lock-force-all"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+    "#
+    ))
+    .unwrap();
+    pixi.lock().await.unwrap();
+
+    // A newer version doesn't change the lock-file as long as it satisfies
+    // the manifest
+    package_database.add_package(Package::build("foo", "2").finish());
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.lock().await.unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec("default", platform, "foo ==1"));
+
+    // Unless everything is solved again
+    pixi.lock().with_force_all().await.unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec("default", platform, "foo ==2"));
+}
+
+#[tokio::test]
+async fn test_add_records_verified_targets() {
+    let (_channel_dir, channel) = foo_bar_channel().await;
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-add-verified-targets"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [feature.docs.dependencies]
+    bar = "*"
+
+    [environments]
+    docs = ["docs"]
+    "#
+    ))
+    .unwrap();
+    let pixi_dir = pixi.workspace().unwrap().pixi_dir();
+    fs_err::create_dir_all(&pixi_dir).unwrap();
+
+    pixi.add("foo").await.unwrap();
+    let verified = fs_err::read_to_string(pixi_dir.join("lock-file-inputs.json")).unwrap();
+    assert!(verified.contains(&format!("\"default/{platform}\"")));
+    assert!(verified.contains(&format!("\"docs/{platform}\"")));
+}
+
+#[tokio::test]
+async fn test_lock_file_from_future_pixi() {
+    let pixi = PixiControl::from_manifest(
+        r#"
+    [workspace]
+    name = "test-lock-future"
+    channels = []
+    platforms = ["linux-64"]
+    "#,
+    )
+    .unwrap();
+    fs_err::write(
+        pixi.workspace_path().join(consts::PROJECT_LOCK_FILE),
+        "# This lock-file was created by pixi 99.0.0\nversion: 99\nenvironments: {}\npackages: []\n",
+    )
+    .unwrap();
+
+    let err = pixi.lock().await.unwrap_err();
+    let message = format!("{err:?}");
+    assert!(message.contains("created with pixi 99.0.0 which uses lock format 99"));
+    assert!(message.contains("upgrade pixi to 99.0.0 or newer"));
+}
@@ -423,3 +423,124 @@ async fn test_cross_platform_resolve_with_no_build() {
             .join("foo-1.0.0-py2.py3-none-any.whl")
     );
 }
+
+#[tokio::test]
+#[cfg_attr(not(feature = "slow_integration_tests"), ignore)]
+async fn test_no_binary_locks_source_distribution() {
+    let pypi_indexes = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/pypi-indexes");
+    let flat_index = Utf8TypedPath::from(&*pypi_indexes.as_os_str().to_string_lossy())
+        .join("multiple-indexes-a")
+        .join("flat");
+
+    // `foo` is available as both a wheel and a source distribution, with
+    // `no-binary` the source distribution should be locked.
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+        [project]
+        name = "pypi-no-binary"
+        platforms = ["{platform}"]
+        channels = ["https://prefix.dev/conda-forge"]
+
+        [dependencies]
+        python = "~=3.12.0"
+
+        [pypi-dependencies]
+        foo = "*"
+
+        [pypi-options]
+        no-binary = ["foo"]
+        find-links = [{{ path = "{pypi_indexes}/multiple-indexes-a/flat"}}]"#,
+        platform = Platform::current(),
+        pypi_indexes = pypi_indexes.display().to_string().replace("\\", "/"),
+    ))
+    .unwrap();
+    let lock_file = pixi.update_lock_file().await.unwrap();
+    assert_eq!(
+        lock_file
+            .get_pypi_package_url("default", Platform::current(), "foo")
+            .unwrap()
+            .as_path()
+            .unwrap(),
+        flat_index.join("foo-1.0.0.tar.gz")
+    );
+
+    // With `only-binary` the wheel should be locked instead.
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+        [project]
+        name = "pypi-only-binary"
+        platforms = ["{platform}"]
+        channels = ["https://prefix.dev/conda-forge"]
+
+        [dependencies]
+        python = "~=3.12.0"
+
+        [pypi-dependencies]
+        foo = "*"
+
+        [pypi-options]
+        only-binary = ":all:"
+        find-links = [{{ path = "{pypi_indexes}/multiple-indexes-a/flat"}}]"#,
+        platform = Platform::current(),
+        pypi_indexes = pypi_indexes.display().to_string().replace("\\", "/"),
+    ))
+    .unwrap();
+    let lock_file = pixi.update_lock_file().await.unwrap();
+    assert_eq!(
+        lock_file
+            .get_pypi_package_url("default", Platform::current(), "foo")
+            .unwrap()
+            .as_path()
+            .unwrap(),
+        flat_index.join("foo-1.0.0-py2.py3-none-any.whl")
+    );
+}
+
+#[tokio::test]
+#[cfg_attr(not(feature = "slow_integration_tests"), ignore)]
+async fn test_no_binary_frozen_install_rejects_locked_wheel() {
+    let pypi_indexes = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/pypi-indexes");
+    let manifest = format!(
+        r#"
+        [project]
+        name = "pypi-no-binary-frozen"
+        platforms = ["{platform}"]
+        channels = ["https://prefix.dev/conda-forge"]
+
+        [dependencies]
+        python = "~=3.12.0"
+
+        [pypi-dependencies]
+        foo = "*"
+
+        [pypi-options]
+        find-links = [{{ path = "{pypi_indexes}/multiple-indexes-a/flat"}}]"#,
+        platform = Platform::current(),
+        pypi_indexes = pypi_indexes.display().to_string().replace("\\", "/"),
+    );
+
+    // Without `no-binary` the wheel is locked
+    let pixi = PixiControl::from_manifest(&manifest).unwrap();
+    let lock_file = pixi.update_lock_file().await.unwrap();
+    assert!(
+        lock_file
+            .get_pypi_package_url("default", Platform::current(), "foo")
+            .unwrap()
+            .as_path()
+            .unwrap()
+            .as_str()
+            .ends_with(".whl")
+    );
+
+    // A frozen install doesn't re-solve, the locked wheel is rejected
+    fs_err::write(
+        pixi.manifest_path(),
+        manifest.replace("[pypi-options]", "[pypi-options]\nno-binary = [\"foo\"]"),
+    )
+    .unwrap();
+    let err = pixi.install().with_frozen().await.unwrap_err();
+    assert!(
+        format!("{err:?}").contains("contains a wheel for 'foo'"),
+        "{err:?}"
+    );
+}
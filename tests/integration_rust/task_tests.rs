@@ -1,16 +1,22 @@
-use std::path::PathBuf;
+use std::{num::NonZeroUsize, path::PathBuf, time::Duration};
 
+use deno_task_shell::{KillSignal, SignalKind};
 use pixi::{
-    cli::{cli_config::WorkspaceConfig, run::Args},
-    task::TaskName,
+    cli::{
+        LockFileUsageConfig,
+        cli_config::{LockFileUpdateConfig, WorkspaceConfig},
+        run::Args,
+    },
+    task::{RunOutput, TaskExecutionError, TaskName},
 };
+use pixi_consts::consts;
 use pixi_manifest::{
     FeatureName, Task,
-    task::{CmdArgs, TemplateString},
+    task::{CmdArgs, TaskShell, TemplateString},
 };
 use rattler_conda_types::Platform;
 
-use crate::common::PixiControl;
+use crate::common::{PixiControl, RunError};
 
 #[tokio::test]
 pub async fn add_remove_task() {
@@ -138,6 +144,182 @@ async fn test_alias() {
     assert_eq!(result.stdout, "hello\nworld\n");
 }
 
+#[tokio::test]
+async fn test_output_per_task() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "output-per-task"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+generate = "echo generated && echo warning >&2"
+build = {{ cmd = "echo built", depends-on = ["generate"] }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let result = pixi
+        .run(Args {
+            task: vec!["build".to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    // The output of all tasks is combined, but also kept per task
+    assert_eq!(result.stdout, "generated\nbuilt\n");
+    assert_eq!(result.stderr, "warning\n");
+    let tasks = result
+        .tasks
+        .iter()
+        .map(|task| {
+            (
+                task.name.as_ref().unwrap().as_str(),
+                task.command.as_deref().unwrap(),
+                task.exit_code,
+                task.stdout.as_str(),
+                task.stderr.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tasks,
+        [
+            (
+                "generate",
+                "echo generated && echo warning >&2",
+                0,
+                "generated\n",
+                "warning\n"
+            ),
+            ("build", "echo built", 0, "built\n", ""),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_independent_tasks() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().without_channels().await.unwrap();
+
+    for (name, command, depends_on) in [
+        ("root", "echo root", vec![]),
+        ("build-frontend", "echo frontend", vec!["root".into()]),
+        ("build-backend", "echo backend", vec!["root".into()]),
+        (
+            "build",
+            "echo build",
+            vec!["build-frontend".into(), "build-backend".into()],
+        ),
+    ] {
+        pixi.tasks()
+            .add(name.into(), None, FeatureName::default())
+            .with_commands([command])
+            .with_depends_on(depends_on)
+            .execute()
+            .await
+            .unwrap();
+    }
+
+    for jobs in [None, NonZeroUsize::new(1)] {
+        let result = pixi
+            .run(Args {
+                task: vec!["build".to_string()],
+                workspace_config: WorkspaceConfig {
+                    manifest_path: None,
+                },
+                jobs,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // The frontend and backend may run at the same time, but their output
+        // is kept per task.
+        assert_eq!(result.stdout, "root\nfrontend\nbackend\nbuild\n");
+        let tasks = result
+            .tasks
+            .iter()
+            .map(|task| {
+                (
+                    task.name.as_ref().unwrap().as_str(),
+                    task.exit_code,
+                    task.stdout.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tasks,
+            [
+                ("root", 0, "root\n"),
+                ("build-frontend", 0, "frontend\n"),
+                ("build-backend", 0, "backend\n"),
+                ("build", 0, "build\n"),
+            ]
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_task_cache() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "task-cache"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+build = {{ cmd = "echo built > out.txt && echo building", inputs = ["src/*.txt"], outputs = ["out.txt"] }}
+outputs-only = {{ cmd = "echo outputs-only > only.txt", outputs = ["only.txt"] }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+    fs_err::create_dir(pixi.workspace_path().join("src")).unwrap();
+    fs_err::write(pixi.workspace_path().join("src/input.txt"), "input").unwrap();
+
+    let run = |task: &str, force: bool| {
+        pixi.run(Args {
+            task: vec![task.to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            force,
+            ..Default::default()
+        })
+    };
+    let cache_hit = |output: RunOutput| output.tasks.iter().all(|task| task.cache_hit);
+
+    let first = run("build", false).await.unwrap();
+    assert_eq!(first.stdout, "building\n");
+    assert!(!cache_hit(first));
+
+    let second = run("build", false).await.unwrap();
+    assert_eq!(second.stdout, "");
+    assert!(cache_hit(second));
+
+    // `--force` bypasses the cache
+    assert!(!cache_hit(run("build", true).await.unwrap()));
+
+    // The task runs again if an input changed or an output is missing
+    fs_err::write(pixi.workspace_path().join("src/input.txt"), "changed").unwrap();
+    assert!(!cache_hit(run("build", false).await.unwrap()));
+    fs_err::remove_file(pixi.workspace_path().join("out.txt")).unwrap();
+    assert!(!cache_hit(run("build", false).await.unwrap()));
+    assert!(cache_hit(run("build", false).await.unwrap()));
+
+    // Tasks without inputs always run
+    assert!(!cache_hit(run("outputs-only", false).await.unwrap()));
+    assert!(!cache_hit(run("outputs-only", false).await.unwrap()));
+}
+
 #[tokio::test]
 pub async fn add_remove_target_specific_task() {
     let pixi = PixiControl::new().unwrap();
@@ -238,6 +420,48 @@ async fn test_cwd() {
     );
 }
 
+#[tokio::test]
+async fn test_cwd_override() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "cwd-override"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+write = {{ cmd = "echo written > out.txt", cwd = "task-dir" }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+    fs_err::create_dir(pixi.workspace_path().join("sub")).unwrap();
+
+    let run = |cwd: &str| {
+        pixi.run(Args {
+            task: vec!["write".to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            cwd: Some(PathBuf::from(cwd)),
+            ..Default::default()
+        })
+    };
+
+    // The relative redirect resolves against the overridden working directory
+    // instead of the `cwd` of the task, which doesn't exist.
+    let result = run("sub").await.unwrap();
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(
+        fs_err::read_to_string(pixi.workspace_path().join("sub/out.txt")).unwrap(),
+        "written\n"
+    );
+    assert!(!pixi.workspace_path().join("out.txt").exists());
+
+    // A working directory that doesn't exist is an error
+    assert!(run("missing").await.is_err());
+}
+
 #[tokio::test]
 async fn test_task_with_env() {
     let pixi = PixiControl::new().unwrap();
@@ -296,14 +520,10 @@ async fn test_clean_env() {
         ..Default::default()
     });
 
-    if cfg!(windows) {
-        // Clean env running not supported on windows.
-        run.await.unwrap_err();
-    } else {
-        let result = run.await.unwrap();
-        assert_eq!(result.exit_code, 0);
-        assert_eq!(result.stdout, "Hello is:\n");
-    }
+    // On Windows the variables that Windows needs are kept, but not `HELLO`
+    let result = run.await.unwrap();
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout, "Hello is:\n");
 
     let result = pixi
         .run(Args {
@@ -321,5 +541,591 @@ async fn test_clean_env() {
     assert_eq!(result.stdout, "Hello is: world from env\n");
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn test_clean_env_keep() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().without_channels().await.unwrap();
+
+    // SAFETY: `set_var` is only unsafe in a multi-threaded context
+    // We enforce that this test runs on the current thread
+    unsafe {
+        std::env::set_var("KEEP_ME_EXACT", "exact");
+        std::env::set_var("KEEP_ME_GLOB_1", "glob");
+        std::env::set_var("DROP_ME_KEEP", "dropped");
+    }
+
+    pixi.tasks()
+        .add("keep-test".into(), None, FeatureName::default())
+        .with_commands(["echo $KEEP_ME_EXACT $KEEP_ME_GLOB_1 $DROP_ME_KEEP"])
+        .execute()
+        .await
+        .unwrap();
+
+    let result = pixi
+        .run(Args {
+            task: vec!["keep-test".to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            clean_env: true,
+            keep_env: vec!["KEEP_ME_EXACT".to_string(), "KEEP_ME_GLOB_*".to_string()],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout, "exact glob\n");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_task_clean_env_keep() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "task-clean-env-keep"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+isolated = {{ cmd = "echo $KEEP_ME_TASK $DROP_ME_TASK", clean-env = true, clean-env-keep = ["KEEP_ME_TASK"] }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    // SAFETY: `set_var` is only unsafe in a multi-threaded context
+    // We enforce that this test runs on the current thread
+    unsafe {
+        std::env::set_var("KEEP_ME_TASK", "kept");
+        std::env::set_var("DROP_ME_TASK", "dropped");
+    }
+
+    // The task asks for the clean environment itself
+    let result = pixi
+        .run(Args {
+            task: vec!["isolated".to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout, "kept\n");
+}
+
+#[tokio::test]
+async fn test_task_timeout() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "task-timeout"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+slow = {{ cmd = "sleep 5", timeout = "1s" }}
+after = {{ cmd = "echo after > after.txt", depends-on = ["slow"] }}
+unbounded = "sleep 5"
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let run = |task: &str, timeout: Option<Duration>| {
+        pixi.run(Args {
+            task: vec![task.to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            timeout,
+            ..Default::default()
+        })
+    };
+    let timed_out = |err: miette::Report| match err.downcast_ref::<TaskExecutionError>() {
+        Some(TaskExecutionError::TimedOut(elapsed)) => *elapsed >= Duration::from_secs(1),
+        _ => false,
+    };
+
+    // The task that depends on the timed out task doesn't run
+    assert!(timed_out(run("after", None).await.unwrap_err()));
+    assert!(!pixi.workspace_path().join("after.txt").exists());
+
+    // `--timeout` applies to tasks without a timeout
+    assert!(timed_out(
+        run("unbounded", Some(Duration::from_secs(1)))
+            .await
+            .unwrap_err()
+    ));
+}
+
+#[tokio::test]
+async fn test_interrupted_task() {
+    let pixi = PixiControl::new().unwrap();
+    pixi.init().without_channels().await.unwrap();
+
+    pixi.tasks()
+        .add("sleep".into(), None, FeatureName::default())
+        .with_commands(["sleep 30"])
+        .execute()
+        .await
+        .unwrap();
+
+    // The signal is sent until the run stops, a signal that is sent before the
+    // task started is not seen by the task.
+    let kill_signal = KillSignal::default();
+    let run = pixi.run_with_kill_signal(
+        Args {
+            task: vec!["sleep".to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            ..Default::default()
+        },
+        kill_signal.clone(),
+    );
+    let interrupt = async {
+        loop {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            kill_signal.send(SignalKind::SIGTERM);
+        }
+    };
+    let result = tokio::select! {
+        result = run => result,
+        _ = interrupt => unreachable!(),
+    };
+
+    let err = result.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<TaskExecutionError>(),
+        Some(TaskExecutionError::Interrupted(SignalKind::SIGTERM))
+    ));
+}
+
+#[tokio::test]
+async fn test_run_in_multiple_environments() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "multiple-environments"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+greet = "echo hello"
+check = "touch checked.txt"
+
+[feature.a.tasks]
+only-a = "echo only a"
+check = "exit 1"
+
+[feature.b.tasks]
+only-b = "echo only b"
+
+[environments]
+a = ["a"]
+b = ["b"]
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let run = |task: &str, environments: &[&str]| {
+        pixi.run(Args {
+            task: vec![task.to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            environment: environments.iter().map(|env| env.to_string()).collect(),
+            ..Default::default()
+        })
+    };
+
+    // The results of every environment are kept separate
+    let output = run("greet", &["a", "b"]).await.unwrap();
+    let environments = output
+        .environments
+        .iter()
+        .map(|(name, output)| (name.as_str(), output.stdout.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(environments, [("a", "hello\n"), ("b", "hello\n")]);
+
+    // `all` only selects the environments that define the task
+    let output = run("only-a", &["all"]).await.unwrap();
+    assert_eq!(output.tasks.len(), 1);
+    assert_eq!(output.tasks[0].environment.as_ref().unwrap().as_str(), "a");
+
+    // A failure in one environment doesn't stop the others
+    assert!(run("check", &["a", "b"]).await.is_err());
+    assert!(pixi.workspace_path().join("checked.txt").exists());
+}
+
+#[tokio::test]
+async fn test_continue_on_error() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "continue-on-error"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+lint = "exit 2"
+flaky = {{ cmd = "exit 3", continue-on-error = true }}
+test = "touch test.txt"
+deploy = {{ cmd = "touch deploy.txt", depends-on = ["lint"] }}
+ci = {{ depends-on = ["lint", "test", "deploy"] }}
+nightly = {{ depends-on = ["flaky", "test"] }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let run = |task: &str, continue_on_error: bool| {
+        pixi.run(Args {
+            task: vec![task.to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            jobs: NonZeroUsize::new(1),
+            continue_on_error,
+            ..Default::default()
+        })
+    };
+    let exit_codes = |err: miette::Report| match err.downcast_ref::<RunError>() {
+        Some(RunError::NonZeroExitCode(failures)) => failures
+            .iter()
+            .map(|failure| failure.exit_code)
+            .collect::<Vec<_>>(),
+        _ => panic!("expected a non-zero exit code, got {err:?}"),
+    };
+    let test_output = pixi.workspace_path().join("test.txt");
+
+    // By default the run stops at the first failure
+    assert_eq!(exit_codes(run("ci", false).await.unwrap_err()), [2]);
+    assert!(!test_output.exists());
+
+    // The independent branches continue, only the dependents are skipped
+    assert_eq!(exit_codes(run("ci", true).await.unwrap_err()), [2]);
+    assert!(test_output.exists());
+    assert!(!pixi.workspace_path().join("deploy.txt").exists());
+
+    // The task property has the same effect for a single task
+    fs_err::remove_file(&test_output).unwrap();
+    assert_eq!(exit_codes(run("nightly", false).await.unwrap_err()), [3]);
+    assert!(test_output.exists());
+}
+
+#[tokio::test]
+async fn test_task_cycle() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "task-cycle"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+build = {{ cmd = "echo build", depends-on = ["codegen"] }}
+codegen = {{ cmd = "echo codegen", depends-on = ["build"] }}
+lint = {{ cmd = "echo lint", depends-on = ["lint"] }}
+check = {{ cmd = "echo check", depends-on = ["all"] }}
+all = [{{ task = "lint-all" }}, {{ task = "check" }}]
+lint-all = "echo lint-all"
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let cycle = |task: &str| {
+        let task = task.to_string();
+        let pixi = &pixi;
+        async move {
+            let err = pixi
+                .run(Args {
+                    task: vec![task],
+                    workspace_config: WorkspaceConfig {
+                        manifest_path: None,
+                    },
+                    ..Default::default()
+                })
+                .await
+                .unwrap_err();
+            match err.downcast_ref::<RunError>() {
+                Some(RunError::TaskGraphError(err)) => (
+                    err.to_string(),
+                    miette::Diagnostic::labels(err).map_or(0, Iterator::count),
+                ),
+                _ => panic!("expected a task graph error, got {err:?}"),
+            }
+        }
+    };
+
+    let (message, labels) = cycle("build").await;
+    assert!(message.contains("build → codegen → build"), "{message}");
+    assert_eq!(labels, 2);
+
+    let (message, labels) = cycle("lint").await;
+    assert!(message.contains("lint → lint"), "{message}");
+    assert_eq!(labels, 1);
+
+    let (message, labels) = cycle("check").await;
+    assert!(message.contains("check → all → check"), "{message}");
+    assert_eq!(labels, 2);
+}
+
+#[tokio::test]
+async fn test_run_with_outdated_lock_file() {
+    let manifest = |platforms: &str| {
+        format!(
+            r#"
+[workspace]
+name = "outdated-lock-file"
+channels = []
+platforms = [{platforms}]
+
+[tasks]
+hello = "echo hello"
+"#
+        )
+    };
+    let other_platform = if Platform::current() == Platform::Linux64 {
+        Platform::OsxArm64
+    } else {
+        Platform::Linux64
+    };
+    let pixi =
+        PixiControl::from_manifest(&manifest(&format!("\"{}\"", Platform::current()))).unwrap();
+
+    let run = |lock_file_update_config: LockFileUpdateConfig| {
+        pixi.run(Args {
+            task: vec!["hello".to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            lock_file_update_config,
+            ..Default::default()
+        })
+    };
+    let usage = |frozen: bool, locked: bool| LockFileUpdateConfig {
+        no_lockfile_update: false,
+        lock_file_usage: LockFileUsageConfig { frozen, locked },
+    };
+
+    // Create the lock-file and make it outdated by adding a platform
+    run(usage(false, false)).await.unwrap();
+    pixi.update_manifest(&manifest(&format!(
+        "\"{}\", \"{other_platform}\"",
+        Platform::current()
+    )))
+    .unwrap();
+    let lock_file =
+        fs_err::read_to_string(pixi.workspace_path().join(consts::PROJECT_LOCK_FILE)).unwrap();
+
+    // `--locked` fails and suggests to update the lock-file
+    let err = run(usage(false, true)).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("lock-file not up-to-date with the workspace"),
+        "{err:?}"
+    );
+    assert!(err.help().unwrap().to_string().contains("pixi lock"));
+
+    // `--frozen` and `--no-lockfile-update` run with the lock-file as it is
+    run(usage(true, false)).await.unwrap();
+    run(LockFileUpdateConfig {
+        no_lockfile_update: true,
+        ..usage(false, false)
+    })
+    .await
+    .unwrap();
+    assert_eq!(
+        fs_err::read_to_string(pixi.workspace_path().join(consts::PROJECT_LOCK_FILE)).unwrap(),
+        lock_file
+    );
+}
+
+#[tokio::test]
+async fn test_shell_from_environment() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "task-shell"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+strict = {{ cmd = "set -euo pipefail; echo strict", shell = "bash" }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let run = |task: &[&str], shell: Option<TaskShell>| {
+        pixi.run(Args {
+            task: task.iter().map(|arg| arg.to_string()).collect(),
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            shell,
+            ..Default::default()
+        })
+    };
+    let missing_shell = |err: miette::Report| match err.downcast_ref::<TaskExecutionError>() {
+        Some(TaskExecutionError::MissingShell(err)) => Some((err.shell, err.package)),
+        _ => None,
+    };
+
+    // The shell is never taken from the system
+    let err = run(&["strict"], None).await.unwrap_err();
+    assert_eq!(missing_shell(err), Some((TaskShell::Bash, "bash")));
+
+    let err = run(&["echo", "hello"], Some(TaskShell::Nu))
+        .await
+        .unwrap_err();
+    assert_eq!(missing_shell(err), Some((TaskShell::Nu, "nushell")));
+
+    // The built-in shell is always available
+    let result = run(&["echo", "hello"], Some(TaskShell::Deno))
+        .await
+        .unwrap();
+    assert_eq!(result.stdout, "hello\n");
+}
+
+#[tokio::test]
+async fn test_interactive_task() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "task-interactive"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+greet = "echo hello"
+monitor = {{ cmd = "echo monitor", interactive = true }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let run = |task: &str, interactive: bool| {
+        pixi.run(Args {
+            task: vec![task.to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            interactive,
+            ..Default::default()
+        })
+    };
+
+    let result = run("greet", false).await.unwrap();
+    assert_eq!(result.stdout, "hello\n");
+
+    // The output of interactive tasks goes to the terminal and isn't captured
+    let result = run("monitor", false).await.unwrap();
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout, "");
+
+    let result = run("greet", true).await.unwrap();
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout, "");
+}
+
+#[tokio::test]
+async fn test_shared_dependency_runs_once() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "task-shared-dependency"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+generate = "echo generated >> counter.txt"
+test = {{ cmd = "echo test", depends-on = ["generate"] }}
+lint = {{ cmd = "echo lint", depends-on = ["generate"] }}
+ci = {{ depends-on = ["test", "lint"] }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let result = pixi
+        .run(Args {
+            task: vec!["ci".to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.exit_code, 0);
+
+    let counter = fs_err::read_to_string(pixi.workspace_path().join("counter.txt")).unwrap();
+    assert_eq!(counter.lines().count(), 1);
+}
+
+#[tokio::test]
+async fn test_task_retries() {
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "task-retries"
+channels = []
+platforms = ["{platform}"]
+
+[tasks]
+generate = "echo generated >> counter.txt"
+flaky = {{ cmd = "echo attempt >> attempts.txt && test -f succeeded.txt || (touch succeeded.txt && exit 1)", depends-on = ["generate"], retries = 1 }}
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let result = pixi
+        .run(Args {
+            task: vec!["flaky".to_string()],
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.exit_code, 0);
+    let flaky = result
+        .tasks
+        .iter()
+        .find(|task| task.name.as_ref().map(TaskName::as_str) == Some("flaky"))
+        .unwrap();
+    assert_eq!(flaky.attempts, 2);
+
+    // The dependency of the task is not run again for the retry.
+    let counter = fs_err::read_to_string(pixi.workspace_path().join("counter.txt")).unwrap();
+    assert_eq!(counter.lines().count(), 1);
+
+    // Without retries the exit code of the failed attempt is returned.
+    fs_err::remove_file(pixi.workspace_path().join("succeeded.txt")).unwrap();
+    let err = pixi
+        .run(Args {
+            task: vec!["flaky".to_string()],
+            retries: Some(0),
+            workspace_config: WorkspaceConfig {
+                manifest_path: None,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    match err.downcast_ref::<RunError>() {
+        Some(RunError::NonZeroExitCode(failures)) => assert_eq!(failures[0].exit_code, 1),
+        _ => panic!("expected a non-zero exit code, got {err:?}"),
+    }
+}
+
 // When adding another test with an environment variable, please choose a unique
 // name to avoid collisions
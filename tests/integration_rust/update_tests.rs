@@ -1,6 +1,13 @@
+use std::str::FromStr;
+
+use itertools::Itertools;
+use pixi::diff::{LockFileDiff, LockFileJsonDiff};
 use pixi_consts::consts;
+use pixi_manifest::EnvironmentName;
 use rattler_conda_types::Platform;
+use rattler_lock::{LockFile, LockedPackageRef};
 use tempfile::TempDir;
+use url::Url;
 
 use crate::common::{
     LockFileExt, PixiControl,
@@ -151,6 +158,889 @@ async fn test_update_single_package() {
     );
 }
 
+#[tokio::test]
+async fn test_update_conservative() {
+    let mut package_database = PackageDatabase::default();
+
+    // `foo` depends on `bar`
+    package_database.add_package(Package::build("bar", "1").finish());
+    package_database.add_package(Package::build("foo", "1").with_dependency("bar").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.add("foo").await.unwrap();
+    let initial_lock = pixi.lock_file().await.unwrap();
+
+    // Release new versions of both packages, `foo` 2 still works with `bar` 1.
+    package_database.add_package(Package::build("bar", "2").finish());
+    package_database.add_package(Package::build("foo", "2").with_dependency("bar").finish());
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let changed_packages = |lock: &LockFile| {
+        LockFileDiff::from_lock_files(&initial_lock, lock)
+            .environment
+            .get(consts::DEFAULT_ENVIRONMENT_NAME)
+            .and_then(|env| env.get(&Platform::current()))
+            .map(|diff| diff.changed.len())
+            .unwrap_or_default()
+    };
+
+    // A conservative update only moves the direct dependency
+    pixi.update().with_conservative().await.unwrap();
+    let conservative_lock = pixi.lock_file().await.unwrap();
+    assert!(conservative_lock.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "foo ==2"
+    ));
+    assert!(
+        conservative_lock.contains_match_spec(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            "bar ==1"
+        ),
+        "expected `bar` to stay on version 1 because it is not a direct dependency"
+    );
+
+    // A regular update moves all packages
+    pixi.update().await.unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "bar ==2"
+    ));
+
+    assert!(changed_packages(&conservative_lock) < changed_packages(&lock));
+}
+
+#[tokio::test]
+async fn test_update_patch() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1.0.0").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.add("foo").await.unwrap();
+
+    // Release a patch and a minor version
+    package_database.add_package(Package::build("foo", "1.0.1").finish());
+    package_database.add_package(Package::build("foo", "1.1.0").finish());
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    // Only the patch release is selected
+    pixi.update()
+        .with_package("foo")
+        .with_patch()
+        .await
+        .unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(
+        lock.contains_match_spec(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            "foo ==1.0.1"
+        ),
+        "expected `foo` to only be updated to the patch release"
+    );
+
+    // There is no newer patch release, so nothing changes
+    pixi.update()
+        .with_package("foo")
+        .with_patch()
+        .await
+        .unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(
+        lock.contains_match_spec(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            "foo ==1.0.1"
+        ),
+        "expected `foo` to stay at the patch release"
+    );
+
+    // A regular update selects the minor release
+    pixi.update().with_package("foo").await.unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(
+        lock.contains_match_spec(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            "foo ==1.1.0"
+        ),
+        "expected `foo` to be updated to the minor release"
+    );
+}
+
+/// A locked version with fewer segments than the granularity is padded with
+/// zeros, `1` may move to `1.0.1` with `--patch` but not to `1.1`.
+#[tokio::test]
+async fn test_update_patch_short_version() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.add("foo").await.unwrap();
+
+    package_database.add_package(Package::build("foo", "1.0.1").finish());
+    package_database.add_package(Package::build("foo", "1.1").finish());
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    pixi.update()
+        .with_package("foo")
+        .with_patch()
+        .await
+        .unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(
+        lock.contains_match_spec(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            "foo ==1.0.1"
+        ),
+        "expected `foo` 1 to only be updated to the patch release 1.0.1"
+    );
+}
+
+#[tokio::test]
+async fn test_update_single_environment_keeps_other_environments() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-update-environment"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+
+    [feature.dev.dependencies]
+    bar = "*"
+
+    [environments]
+    dev = ["dev"]
+    "#
+    ))
+    .unwrap();
+    let initial_lock = pixi.update_lock_file().await.unwrap();
+
+    // Release new versions of both packages
+    package_database.add_package(Package::build("foo", "2").finish());
+    package_database.add_package(Package::build("bar", "2").finish());
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    pixi.update()
+        .with_environment(EnvironmentName::Named("dev".to_string()))
+        .await
+        .unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec("dev", platform, "foo ==2"));
+    assert!(lock.contains_match_spec("dev", platform, "bar ==2"));
+
+    // The default environment was not re-solved
+    assert!(lock.contains_match_spec(consts::DEFAULT_ENVIRONMENT_NAME, platform, "foo ==1"));
+    let diff = LockFileDiff::from_lock_files(&initial_lock, &lock);
+    assert!(
+        !diff
+            .environment
+            .contains_key(consts::DEFAULT_ENVIRONMENT_NAME),
+        "expected the default environment to be unchanged"
+    );
+}
+
+#[tokio::test]
+async fn test_update_refresh_metadata() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let manifest = |channel: &Url| {
+        format!(
+            r#"
+    [workspace]
+    name = "test-update-refresh-metadata"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+    "#,
+            platform = Platform::current()
+        )
+    };
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let pixi = PixiControl::from_manifest(&manifest(&channel)).unwrap();
+    pixi.update_lock_file().await.unwrap();
+
+    // Move the packages to a mirror that also contains a newer version
+    let mirror_dir = TempDir::new().unwrap();
+    package_database.add_package(Package::build("foo", "2").finish());
+    package_database
+        .write_repodata(mirror_dir.path())
+        .await
+        .unwrap();
+    let mirror = Url::from_file_path(mirror_dir.path()).unwrap();
+    fs_err::write(pixi.manifest_path(), manifest(&mirror)).unwrap();
+
+    pixi.update().with_refresh_metadata().await.unwrap();
+
+    // The version is kept, but the package is now locked from the mirror
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "foo ==1"
+    ));
+    let mirror_name = mirror_dir.path().file_name().unwrap().to_string_lossy();
+    let locations = lock
+        .environment(consts::DEFAULT_ENVIRONMENT_NAME)
+        .unwrap()
+        .packages(Platform::current())
+        .unwrap()
+        .map(|package| package.location().to_string())
+        .collect::<Vec<_>>();
+    assert!(
+        locations
+            .iter()
+            .all(|location| location.contains(mirror_name.as_ref())),
+        "expected all packages to be locked from the mirror: {locations:?}"
+    );
+
+    // A mirror without the locked package can't be used
+    let empty_dir = TempDir::new().unwrap();
+    PackageDatabase::default()
+        .with_package(Package::build("bar", "1").finish())
+        .write_repodata(empty_dir.path())
+        .await
+        .unwrap();
+    let empty = Url::from_file_path(empty_dir.path()).unwrap();
+    fs_err::write(pixi.manifest_path(), manifest(&empty)).unwrap();
+    let err = pixi.update().with_refresh_metadata().await.unwrap_err();
+    assert!(
+        err.to_string().contains("can no longer be found"),
+        "unexpected error: {err}"
+    );
+}
+
+#[tokio::test]
+async fn test_update_refresh_metadata_keeps_channels_of_other_environments() {
+    let package_database = PackageDatabase::default()
+        .with_package(Package::build("foo", "1").finish())
+        .with_package(Package::build("bar", "1").finish());
+
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+    let mirror_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(mirror_dir.path())
+        .await
+        .unwrap();
+
+    let manifest = |channel: &Url| {
+        format!(
+            r#"
+    [workspace]
+    name = "test-update-refresh-metadata-environments"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+
+    [feature.other.dependencies]
+    bar = "*"
+
+    [environments]
+    other = ["other"]
+    "#,
+            platform = Platform::current()
+        )
+    };
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let pixi = PixiControl::from_manifest(&manifest(&channel)).unwrap();
+    pixi.update_lock_file().await.unwrap();
+
+    let mirror = Url::from_file_path(mirror_dir.path()).unwrap();
+    fs_err::write(pixi.manifest_path(), manifest(&mirror)).unwrap();
+    pixi.update()
+        .with_refresh_metadata()
+        .with_environment(consts::DEFAULT_ENVIRONMENT_NAME)
+        .await
+        .unwrap();
+
+    // Only the channels of the refreshed environment are replaced
+    let lock = pixi.lock_file().await.unwrap();
+    let channels = |name: &str| {
+        lock.environment(name)
+            .unwrap()
+            .channels()
+            .iter()
+            .map(|channel| channel.url.clone())
+            .collect::<Vec<_>>()
+    };
+    let directory_name = |dir: &TempDir| {
+        dir.path()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string()
+    };
+    assert!(
+        channels(consts::DEFAULT_ENVIRONMENT_NAME)
+            .iter()
+            .all(|url| url.contains(&directory_name(&mirror_dir))),
+        "expected the default environment to use the mirror"
+    );
+    assert!(
+        channels("other")
+            .iter()
+            .all(|url| url.contains(&directory_name(&channel_dir))),
+        "expected the other environment to keep its locked channels"
+    );
+}
+
+/// Returns the serialized locked packages of an environment for a platform.
+fn locked_packages(lock: &LockFile, platform: Platform) -> Vec<serde_json::Value> {
+    lock.environment(consts::DEFAULT_ENVIRONMENT_NAME)
+        .and_then(|env| env.packages(platform))
+        .into_iter()
+        .flatten()
+        .map(|package| match package {
+            LockedPackageRef::Conda(data) => serde_json::to_value(data).unwrap(),
+            LockedPackageRef::Pypi(data, _) => serde_json::to_value(data).unwrap(),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_update_single_platform_keeps_other_platforms() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-update-platform"
+    channels = ["{channel}"]
+    platforms = ["linux-64", "osx-arm64"]
+
+    [dependencies]
+    foo = "*"
+    "#
+    ))
+    .unwrap();
+    let initial_lock = pixi.update_lock_file().await.unwrap();
+
+    // Release a new version
+    package_database.add_package(Package::build("foo", "2").finish());
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    pixi.update()
+        .with_platform(Platform::Linux64)
+        .await
+        .unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::Linux64,
+        "foo ==2"
+    ));
+
+    // The other platform was not re-solved
+    assert_eq!(
+        locked_packages(&initial_lock, Platform::OsxArm64),
+        locked_packages(&lock, Platform::OsxArm64),
+    );
+}
+
+#[tokio::test]
+async fn test_update_single_platform_refuses_outdated_platforms() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let manifest = format!(
+        r#"
+    [workspace]
+    name = "test-update-platform"
+    channels = ["{channel}"]
+    platforms = ["linux-64", "osx-arm64"]
+
+    [dependencies]
+    foo = "*"
+    "#
+    );
+    let pixi = PixiControl::from_manifest(&manifest).unwrap();
+    pixi.update_lock_file().await.unwrap();
+
+    // Only the `osx-arm64` platform no longer satisfies the manifest
+    fs_err::write(
+        pixi.manifest_path(),
+        format!("{manifest}\n[target.osx-arm64.dependencies]\nbar = \"*\"\n"),
+    )
+    .unwrap();
+
+    let err = pixi
+        .update()
+        .with_platform(Platform::Linux64)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("also has to be updated for osx-arm64"),
+        "unexpected error: {err}"
+    );
+}
+
+#[tokio::test]
+async fn test_update_exclude() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.add("foo").await.unwrap();
+    pixi.add("bar").await.unwrap();
+
+    // Release new versions of both packages
+    package_database.add_package(Package::build("foo", "2").finish());
+    package_database.add_package(Package::build("bar", "2").finish());
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    pixi.update().with_exclude("foo").await.unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(
+        lock.contains_match_spec(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            "foo ==1"
+        ),
+        "expected `foo` to stay on version 1 because it is excluded"
+    );
+    assert!(lock.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "bar ==2"
+    ));
+}
+
+/// An excluded package that has to change to satisfy the manifest is named in
+/// the error.
+#[tokio::test]
+async fn test_update_exclude_unsatisfiable() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let manifest = |bar: &str| {
+        format!(
+            r#"
+    [workspace]
+    name = "test-update-exclude"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    [dependencies]
+    foo = "*"
+    bar = "{bar}"
+    "#,
+            platform = Platform::current()
+        )
+    };
+    let pixi = PixiControl::from_manifest(&manifest("*")).unwrap();
+    pixi.update_lock_file().await.unwrap();
+
+    // The new version of `bar` needs the new version of `foo`
+    package_database.add_package(Package::build("foo", "2").finish());
+    package_database.add_package(
+        Package::build("bar", "2")
+            .with_dependency("foo >=2")
+            .finish(),
+    );
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.update_manifest(&manifest(">=2")).unwrap();
+
+    let err = pixi.update().with_exclude("foo").await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("failed to update while keeping 'foo' at the locked version"),
+        "expected the error to name the excluded package, got: {err}"
+    );
+
+    // Without excluding `foo` both packages are updated
+    pixi.update().await.unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "foo ==2"
+    ));
+}
+
+/// Builds a lock-file for linux-64 that only contains the given pypi packages,
+/// specified as `(url, name, version)`.
+fn pypi_lock_file(packages: &[(&str, &str, &str)]) -> LockFile {
+    let mut lock = String::from(
+        r#"version: 6
+environments:
+  default:
+    channels:
+    - url: https://prefix.dev/conda-forge/
+    indexes:
+    - https://pypi.org/simple
+    packages:
+      linux-64:
+"#,
+    );
+    for (url, _, _) in packages {
+        lock.push_str(&format!("      - pypi: {url}\n"));
+    }
+    lock.push_str("packages:\n");
+    for (url, name, version) in packages {
+        lock.push_str(&format!(
+            "- pypi: {url}\n  name: {name}\n  version: {version}\n"
+        ));
+    }
+    LockFile::from_str(&lock).unwrap()
+}
+
+#[test]
+fn test_update_diff_contains_pypi_packages() {
+    let previous = pypi_lock_file(&[
+        (
+            "https://files.pythonhosted.org/packages/certifi-2024.2.2-py3-none-any.whl",
+            "certifi",
+            "2024.2.2",
+        ),
+        (
+            "https://files.pythonhosted.org/packages/idna-3.6.tar.gz",
+            "idna",
+            "3.6",
+        ),
+        (
+            "https://files.pythonhosted.org/packages/six-1.16.0-py2.py3-none-any.whl",
+            "six",
+            "1.16.0",
+        ),
+    ]);
+    let current = pypi_lock_file(&[
+        (
+            "https://files.pythonhosted.org/packages/certifi-2024.7.4-py3-none-any.whl",
+            "certifi",
+            "2024.7.4",
+        ),
+        (
+            "https://files.pythonhosted.org/packages/idna-3.7-py3-none-any.whl",
+            "idna",
+            "3.7",
+        ),
+        (
+            "https://example.com/packages/urllib3-2.2.1-py3-none-any.whl",
+            "urllib3",
+            "2.2.1",
+        ),
+    ]);
+
+    let diff = LockFileDiff::from_lock_files(&previous, &current);
+    let json = serde_json::to_value(LockFileJsonDiff::new(None, diff)).unwrap();
+    let platform_diff = &json["environment"]["default"]["linux-64"];
+    assert_eq!(platform_diff["conda"], serde_json::json!([]));
+
+    let pypi = platform_diff["pypi"].as_array().unwrap();
+    let entry = |name: &str| {
+        pypi.iter()
+            .find(|entry| entry["name"] == name)
+            .unwrap_or_else(|| panic!("expected `{name}` in the pypi diff"))
+    };
+    let version = |lock: &LockFile, name: &str| {
+        lock.get_pypi_package_version(consts::DEFAULT_ENVIRONMENT_NAME, Platform::Linux64, name)
+            .map(serde_json::Value::from)
+            .unwrap_or(serde_json::Value::Null)
+    };
+
+    for name in ["certifi", "idna", "six", "urllib3"] {
+        assert_eq!(entry(name)["before"], version(&previous, name));
+        assert_eq!(entry(name)["after"], version(&current, name));
+    }
+
+    // Only the version of `certifi` changed
+    assert!(entry("certifi").get("source_changed").is_none());
+
+    // `idna` moved from a source distribution to a wheel
+    let idna = entry("idna");
+    assert_eq!(idna["source_changed"], true);
+    assert_eq!(idna["before_source"]["distribution"], "sdist");
+    assert_eq!(idna["after_source"]["distribution"], "wheel");
+    assert_eq!(idna["after_source"]["origin"], "index");
+
+    // Added and removed packages don't have a source to compare with
+    assert_eq!(entry("six")["after_source"], serde_json::Value::Null);
+    assert_eq!(entry("urllib3")["before_source"], serde_json::Value::Null);
+
+    // Wheels and source distributions report their file name
+    assert_eq!(idna["before_filename"], "idna-3.6.tar.gz");
+    assert_eq!(idna["after_filename"], "idna-3.7-py3-none-any.whl");
+}
+
+#[test]
+fn test_update_json_diff_index_url_with_extra_indexes() {
+    let pixi = PixiControl::from_manifest(
+        r#"
+        [workspace]
+        channels = ["https://prefix.dev/conda-forge"]
+        platforms = ["linux-64"]
+
+        [pypi-options]
+        extra-index-urls = ["https://example.com/simple"]
+
+        [pypi-dependencies]
+        certifi = "*"
+        urllib3 = "*"
+        six = "*"
+        idna = { version = "*", index = "https://custom.org/simple" }
+        "#,
+    )
+    .unwrap();
+    let workspace = pixi.workspace().unwrap();
+
+    let previous = pypi_lock_file(&[(
+        "https://other.org/packages/six-1.15.0-py2.py3-none-any.whl",
+        "six",
+        "1.15.0",
+    )]);
+    let current = pypi_lock_file(&[
+        (
+            "https://files.pythonhosted.org/packages/certifi-2024.7.4-py3-none-any.whl",
+            "certifi",
+            "2024.7.4",
+        ),
+        (
+            "https://example.com/packages/urllib3-2.2.1-py3-none-any.whl",
+            "urllib3",
+            "2.2.1",
+        ),
+        (
+            "https://other.org/packages/six-1.16.0-py2.py3-none-any.whl",
+            "six",
+            "1.16.0",
+        ),
+        (
+            "https://custom.org/packages/idna-3.7-py3-none-any.whl",
+            "idna",
+            "3.7",
+        ),
+    ]);
+
+    let diff = LockFileDiff::from_lock_files(&previous, &current);
+    let json = serde_json::to_value(LockFileJsonDiff::new(Some(&workspace), diff)).unwrap();
+    let pypi = json["environment"]["default"]["linux-64"]["pypi"]
+        .as_array()
+        .unwrap();
+    let index_url = |name: &str| {
+        pypi.iter()
+            .find(|entry| entry["name"] == name)
+            .unwrap_or_else(|| panic!("expected `{name}` in the pypi diff"))["index_url"]
+            .clone()
+    };
+
+    // The files of PyPI are served from another host than the index
+    assert_eq!(index_url("certifi"), "https://pypi.org/simple");
+    assert_eq!(index_url("urllib3"), "https://example.com/simple");
+    assert_eq!(index_url("idna"), "https://custom.org/simple");
+    // None of the configured indexes hosts `six`
+    assert_eq!(index_url("six"), serde_json::Value::Null);
+}
+
+/// Builds a lock-file for linux-64 that only contains the given conda
+/// packages, specified as `(url, size)`.
+fn conda_lock_file(packages: &[(&str, u64)]) -> LockFile {
+    let mut lock = String::from(
+        r#"version: 6
+environments:
+  default:
+    channels:
+    - url: https://prefix.dev/conda-forge/
+    packages:
+      linux-64:
+"#,
+    );
+    for (url, _) in packages {
+        lock.push_str(&format!("      - conda: {url}\n"));
+    }
+    lock.push_str("packages:\n");
+    for (url, size) in packages {
+        lock.push_str(&format!("- conda: {url}\n  size: {size}\n"));
+    }
+    LockFile::from_str(&lock).unwrap()
+}
+
+#[test]
+fn test_update_json_diff_contains_conda_artifacts() {
+    let previous = conda_lock_file(&[
+        (
+            "https://prefix.dev/conda-forge/linux-64/foo-1.0-h1_0.conda",
+            1000,
+        ),
+        (
+            "https://prefix.dev/conda-forge/linux-64/bar-1.0-h1_0.conda",
+            500,
+        ),
+    ]);
+    let current = conda_lock_file(&[
+        (
+            "https://prefix.dev/my-channel/linux-64/foo-2.0-h2_0.conda",
+            3000,
+        ),
+        (
+            "https://prefix.dev/conda-forge/linux-64/baz-1.0-h1_0.conda",
+            200,
+        ),
+    ]);
+
+    let diff = LockFileDiff::from_lock_files(&previous, &current);
+    let json = serde_json::to_value(LockFileJsonDiff::new(None, diff)).unwrap();
+    assert_eq!(json["changed"], true);
+    let conda = json["environment"]["default"]["linux-64"]["conda"]
+        .as_array()
+        .unwrap();
+    let entry = |name: &str| {
+        conda
+            .iter()
+            .find(|entry| entry["name"] == name)
+            .unwrap_or_else(|| panic!("expected `{name}` in the conda diff"))
+    };
+
+    // `foo` was rebuilt and moved to another channel
+    let foo = entry("foo");
+    assert_eq!(foo["before_build"], "h1_0");
+    assert_eq!(foo["after_build"], "h2_0");
+    assert_eq!(foo["before_size"], 1000);
+    assert_eq!(foo["after_size"], 3000);
+    assert_eq!(foo["before_channel"], "https://prefix.dev/conda-forge/");
+    assert_eq!(foo["after_channel"], "https://prefix.dev/my-channel/");
+    assert_eq!(foo["channel_changed"], true);
+
+    // Added and removed packages only have one side
+    assert_eq!(entry("bar")["after_size"], serde_json::Value::Null);
+    assert_eq!(entry("baz")["before_channel"], serde_json::Value::Null);
+    assert!(entry("baz").get("channel_changed").is_none());
+
+    assert_eq!(
+        json["download_size"]["default"],
+        serde_json::json!({ "added": 3200, "removed": 1500 })
+    );
+}
+
 // #[tokio::test]
 // async fn test_update_single_environment() {
 //     let mut package_database = PackageDatabase::default();
@@ -202,3 +1092,85 @@ async fn test_update_single_package() {
 //         "expected `bar` to be on version 1 because only foo should be updated"
 //     );
 // }
+
+#[tokio::test]
+async fn test_update_diff_lists_direct_dependencies_first() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").with_dependency("bar").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+    package_database.add_package(Package::build("baz", "1").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [workspace]
+    name = "test-update-direct"
+    channels = ["{channel}"]
+    platforms = ["linux-64"]
+
+    [dependencies]
+    foo = "*"
+
+    [feature.extra.dependencies]
+    baz = "*"
+
+    [environments]
+    default = ["extra"]
+    "#
+    ))
+    .unwrap();
+    let initial_lock = pixi.update_lock_file().await.unwrap();
+
+    // Release new versions of all packages
+    for name in ["foo", "bar", "baz"] {
+        package_database.add_package(Package::build(name, "2").finish());
+    }
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    pixi.update().await.unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    let diff = LockFileDiff::from_lock_files(&initial_lock, &lock);
+    let workspace = pixi.workspace().unwrap();
+
+    // Strips the styling and the alignment of the output
+    let render = |direct_only: bool| {
+        let mut output = Vec::new();
+        diff.write(&mut output, Some(&workspace), direct_only)
+            .unwrap();
+        console::strip_ansi_codes(&String::from_utf8(output).unwrap())
+            .replace("(conda)", "C")
+            .lines()
+            .map(|line| {
+                line.split('\t')
+                    .map(str::trim_end)
+                    .filter(|s| !s.is_empty())
+                    .join(" ")
+            })
+            .join("\n")
+    };
+
+    insta::assert_snapshot!(render(false), @r###"
+    Environment: default
+    Direct dependencies:
+      ~ C baz 1 0 -> 2 0
+      ~ C foo 1 0 -> 2 0
+    Transitive dependencies:
+      ~ C bar 1 0 -> 2 0
+    "###);
+    insta::assert_snapshot!(render(true), @r###"
+    Environment: default
+    Direct dependencies:
+      ~ C baz 1 0 -> 2 0
+      ~ C foo 1 0 -> 2 0
+    "###);
+}
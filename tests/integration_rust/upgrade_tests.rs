@@ -1,10 +1,18 @@
+use std::str::FromStr;
+
 use indexmap::IndexMap;
 use insta::assert_snapshot;
+use pep508_rs::MarkerTree;
 use pixi::Workspace;
 use pixi::cli::upgrade::{Args, parse_specs};
 use rattler_conda_types::Platform;
+use tempfile::TempDir;
+use url::Url;
 
-use crate::common::PixiControl;
+use crate::common::{
+    LockFileExt, PixiControl,
+    package_database::{Package, PackageDatabase},
+};
 
 // This test requires network connection and takes around 40s to
 // complete on my machine.
@@ -61,3 +69,105 @@ async fn pypi_dependency_index_preserved_on_upgrade() {
     let redacted_content = content.replace(&Platform::current().to_string(), "[PLATFORM]");
     assert_snapshot!(redacted_content);
 }
+
+#[tokio::test]
+async fn upgrade_keeps_lower_bounds_and_formatting() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1.0.0").finish());
+    package_database.add_package(Package::build("bar", "1.0.0").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+[workspace]
+name = "test-upgrade-formatting"
+channels = ["{channel}"]
+platforms = ["{platform}"]
+
+[dependencies]
+# Keep foo close to the release we tested with
+foo = ">=1.0,<1.1"  # foo comment
+bar = "==1.0.0"
+"#
+    ))
+    .unwrap();
+    pixi.update_lock_file().await.unwrap();
+
+    // Release a new version of both packages
+    package_database.add_package(Package::build("foo", "1.2.0").finish());
+    package_database.add_package(Package::build("bar", "1.2.0").finish());
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let mut args = Args::default();
+    args.workspace_config.manifest_path = Some(pixi.manifest_path());
+    args.prefix_update_config.no_install = true;
+    pixi::cli::upgrade::execute(args).await.unwrap();
+
+    // The lower bound of `foo` is kept, `bar` is pinned with the pinning strategy
+    // and the comments and whitespace around the values are untouched.
+    let manifest = pixi.manifest_contents().unwrap();
+    assert!(
+        manifest.contains(
+            "# Keep foo close to the release we tested with\nfoo = \">=1.0,<2\"  # foo comment\n"
+        ),
+        "unexpected manifest:\n{manifest}"
+    );
+    assert!(
+        manifest.contains("bar = \">=1.2.0,<2\"\n"),
+        "unexpected manifest:\n{manifest}"
+    );
+
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec("default", platform, "foo ==1.2.0"));
+    assert!(lock.contains_match_spec("default", platform, "bar ==1.2.0"));
+}
+
+#[tokio::test]
+async fn upgrade_keeps_pypi_markers() {
+    let pixi = PixiControl::from_pyproject_manifest(&format!(
+        r#"
+[project]
+name = "test-upgrade-markers"
+version = "0.1.0"
+dependencies = ["numpy>=1.0 ; python_version >= '3.8'"]
+
+[tool.pixi.workspace]
+channels = ["https://prefix.dev/conda-forge"]
+platforms = ["{platform}"]
+"#,
+        platform = Platform::current()
+    ))
+    .unwrap();
+
+    let mut args = Args::default();
+    args.workspace_config.manifest_path = Some(pixi.pyproject_manifest_path());
+    args.specs.pypi = true;
+
+    let workspace = Workspace::from_path(&pixi.pyproject_manifest_path()).unwrap();
+    let workspace_value = workspace.workspace.value.clone();
+    let feature = workspace_value.feature(&args.specs.feature).unwrap();
+    let workspace = workspace.modify().unwrap();
+
+    // The upgraded requirement replaces the whole string in the array, so it
+    // has to carry the marker of the original requirement.
+    let (_, pypi_deps) = parse_specs(feature, &args, &workspace).unwrap();
+    let (requirement, _, _) = pypi_deps
+        .values()
+        .find(|(requirement, _, _)| requirement.name.as_ref() == "numpy")
+        .expect("numpy should be upgraded");
+    assert_eq!(
+        requirement.marker,
+        MarkerTree::from_str("python_version >= '3.8'").unwrap()
+    );
+}